@@ -0,0 +1,5 @@
+announcer-match-start = Ready? Go!
+announcer-multi-kill = P{ $player }: { $kills } kills in a row!
+announcer-last-stock = P{ $player } is on their last life!
+announcer-victory = P{ $player } wins!
+announcer-draw = It's a draw!
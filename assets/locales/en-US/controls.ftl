@@ -5,4 +5,7 @@ move-right = Move Right
 jump = Jump
 grab-drop = Grab / Drop
 shoot = Shoot
-slide = Slide
\ No newline at end of file
+slide = Slide
+block = Block
+taunt = Taunt
+emote = Emote Wheel
\ No newline at end of file
@@ -3,7 +3,7 @@ show-kinematic-colliders = Show Kinematic Colliders
 show-damage-regions = Show Damage Regions
 show-world-inspector = Show World Inspector
 show-frame-time-diagnostics = Show Frame Time Diagnostics
-show-network-visualizer = Show Network Visualizer
+show-netplay-diagnostics = Show Netplay Diagnostics
 
 frame-diagnostics = Frame Diagnostics
 frames-per-second = Frames Per Second
@@ -12,4 +12,32 @@ reset-min-max = Reset Min/Max
 
 snapshot = Snapshot
 take-snapshot = Take Snapshot
-restore-snapshot = Restore Snapshot
\ No newline at end of file
+restore-snapshot = Restore Snapshot
+
+frame-step = Frame Step
+pause-session = Pause
+resume-session = Resume
+step-frame = Step Frame
+
+input-recording = Input Recording
+start-recording = Start Recording
+stop-recording = Stop Recording
+replay-recording = Replay Recording
+play-saved-replay = Play Saved Replay
+
+replay-playback = Replay Playback
+replay-frame = Frame
+replay-speed = Speed
+
+determinism-audit = Determinism Audit
+enable-determinism-audit = Compare With Shadow Session
+determinism-audit-diverged = Diverged on frame
+
+netplay-diagnostics = Netplay Diagnostics
+rollback-disabled = Rollback is disabled
+input-delay-frames = Input Delay: { $frames } frames ({ $ms }ms)
+rollback-frames-last-second = Rolled Back: { $frames } frames in the last second
+remote-players = Remote Players
+remote-player-lateness = Player { $player }: { $ms }ms ({ $frames } frames late)
+rollback-depth = Rollback Depth
+rollback-depth-max = Graph height: { $frames } frames
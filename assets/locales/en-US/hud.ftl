@@ -0,0 +1,21 @@
+hud-player = P{ $player }
+hud-ammo = P{ $player }: { $count } / { $max }
+hud-reloading = P{ $player }: Reloading...
+hud-out-of-bounds = P{ $player }: { $arrow } Get back!
+hud-combo = P{ $player }: { $hits } hit combo!
+hud-respawning = P{ $player }: Respawning in { $seconds }...
+hud-kill-cam = Kill Cam
+emote-crab = Crab
+emote-boots = Boots
+emote-crate = Crate
+emote-seaweed = Seaweed
+# Scoreboard
+scoreboard-winner = P{ $player } wins!
+scoreboard-team-winner = Team { $team } wins!
+scoreboard-draw = It's a draw!
+scoreboard-sudden-death = Decided in sudden death
+scoreboard-place = #
+scoreboard-player = Player
+scoreboard-kills = Kills
+scoreboard-deaths = Deaths
+scoreboard-damage = Damage
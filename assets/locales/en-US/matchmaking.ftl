@@ -1,5 +1,5 @@
 configure-match = Configure Match
-player-count = Player Count
+player-count = Player Count:
 search-for-match = Search for Match
 connecting = Connecting...
 connected-and-querying = Connected
@@ -18,4 +18,5 @@ play = Play
 quit = Quit
 export = Export
 reload = Reload
-restart = Restart
\ No newline at end of file
+restart = Restart
+rematch = Rematch
\ No newline at end of file
@@ -9,4 +9,8 @@ player-select-unready = Pres { $button } to unready
 waiting-for-more-players = Waiting for more players...
 press-button-to-join = Press { $button } to Join
 press-button-to-lock-in = Press { $button } to Lock In
-press-button-to-remove = Press { $button } to Remove
\ No newline at end of file
+press-button-to-remove = Press { $button } to Remove
+up-down-to-change-color = Up / Down to Change Color
+press-button-to-change-team = Press { $button } to Change Team
+player-select-team = Team { $team }
+player-select-no-team = No Team
\ No newline at end of file
@@ -11,4 +11,8 @@ action = Action
 
 # Networking settings
 networking = Networking
-matchmaking-server = Matchmaking Server
+matchmaking-server = Matchmaking Server:
+
+# Language settings
+language = Language
+language-name = English
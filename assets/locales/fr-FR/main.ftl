@@ -4,4 +4,8 @@ start-game = Démarrer Jeu
 # Pause Menu
 paused = En Pause
 continue = Continuez
-main-menu = Menu Principal
\ No newline at end of file
+main-menu = Menu Principal
+
+# Language settings
+language = Langue
+language-name = Français
@@ -0,0 +1,274 @@
+//! Gameplay events fired on specific frames of sprite animations.
+//!
+//! Entities with an [`AnimationFrameEvents`] component have an [`AnimationEvent`] pushed onto the
+//! [`AnimationEvents`] list every time their [`AnimationBankSprite`] reaches one of the frames that
+//! an event is tagged on, such as the frame that a player's foot hits the ground.
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<AnimationEvents>();
+    session
+        .stages
+        // Transitions are picked once the player states have chosen their animations for the frame.
+        .add_system_to_stage(CoreStage::PostUpdate, play_animation_transitions)
+        // This must run after the animations have been advanced for the frame.
+        .add_system_to_stage(CoreStage::Last, fire_animation_events);
+}
+
+/// Component containing the event keys tagged on each frame of an entity's animations, by
+/// animation and then by frame.
+#[derive(Clone, Debug, Default, Deref, DerefMut, TypeUlid)]
+#[ulid = "01GWR3N7Y1V6B5XTE8K0C2DQ4M"]
+pub struct AnimationFrameEvents(pub Arc<std::collections::HashMap<Key, Vec<Vec<Key>>>>);
+
+/// Component tracking the animation frame that an entity's events were last fired for.
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GWR3N7Y2JZQ3A1W9HS6PFX8R"]
+pub struct AnimationEventCursor {
+    pub animation: Key,
+    pub index: usize,
+    pub timer: f32,
+}
+
+/// An event tagged on an animation frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationEvent {
+    /// The entity whose animation reached the tagged frame.
+    pub entity: Entity,
+    /// The event that the frame was tagged with.
+    pub key: Key,
+}
+
+/// Resource containing the animation events fired on the last frame.
+///
+/// The list is replaced at the end of every frame, so any number of systems may read it during the
+/// next one.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GWR3N7Y2R0KXGM4CB7EW5T9H"]
+pub struct AnimationEvents {
+    pub events: Vec<AnimationEvent>,
+}
+
+impl AnimationEvents {
+    /// Iterate over the events with the given key.
+    pub fn with_key(&self, key: Key) -> impl Iterator<Item = &AnimationEvent> {
+        self.events.iter().filter(move |event| event.key == key)
+    }
+}
+
+/// Component containing the transitions played between an entity's animations.
+#[derive(Clone, Debug, Default, Deref, DerefMut, TypeUlid)]
+#[ulid = "01GWS0C4JQ5D7N2V8XKB3HRT6E"]
+pub struct AnimationTransitions(pub Arc<Vec<AnimationTransitionMeta>>);
+
+/// Component tracking the transition that is playing between an entity's animations.
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GWS0C4JRA8XW0M5ZF1YQ3PNC"]
+pub struct AnimationTransitionState {
+    /// The animation that the entity wanted to play on the last frame.
+    pub animation: Key,
+    /// The transition animation being played instead, and the number of frames left before it
+    /// finishes.
+    pub playing: Option<(Key, u32)>,
+}
+
+impl AnimationTransitionState {
+    pub fn new(animation: Key) -> Self {
+        Self {
+            animation,
+            playing: None,
+        }
+    }
+
+    /// Update the transition for an entity that wants to play `animation`, returning the
+    /// animation that it should actually play.
+    pub fn update(&mut self, animation: Key, transitions: &[AnimationTransitionMeta]) -> Key {
+        if animation != self.animation {
+            // Switching to another animation cancels the transition that was playing
+            self.playing = transitions
+                .iter()
+                .find(|x| x.from == self.animation && x.to == animation && x.frames > 0)
+                .map(|x| (x.animation, x.frames));
+            self.animation = animation;
+        }
+
+        match &mut self.playing {
+            Some((transition, frames_remaining)) => {
+                let transition = *transition;
+                *frames_remaining -= 1;
+                if *frames_remaining == 0 {
+                    self.playing = None;
+                }
+                transition
+            }
+            None => animation,
+        }
+    }
+}
+
+fn play_animation_transitions(
+    entities: Res<Entities>,
+    transitions: Comp<AnimationTransitions>,
+    killed_players: Comp<PlayerKilled>,
+    mut animation_banks: CompMut<AnimationBankSprite>,
+    mut states: CompMut<AnimationTransitionState>,
+) {
+    for (entity, (transitions, bank)) in entities.iter_with((&transitions, &mut animation_banks)) {
+        let Some(state) = states.get_mut(entity) else {
+            states.insert(entity, AnimationTransitionState::new(bank.current));
+            continue;
+        };
+
+        if killed_players.contains(entity) {
+            // Deaths are never blended, so that the hit lands with a snap
+            *state = AnimationTransitionState::new(bank.current);
+            continue;
+        }
+
+        bank.current = state.update(bank.current, transitions);
+    }
+}
+
+/// Get the frames of an animation that were entered since it was last at `last_index` with
+/// `last_timer` time on its frame.
+///
+/// Animations are only ever moved forward, so every frame up to and including the current one is
+/// passed through exactly once, wrapping around at the end of repeating animations.
+fn entered_frames(sprite: &AnimatedSprite, last_index: usize, last_timer: f32) -> Vec<usize> {
+    let frame_count = sprite.frames.len();
+    if frame_count == 0 {
+        return Vec::new();
+    }
+
+    let steps = if sprite.index != last_index {
+        (sprite.index + frame_count - last_index) % frame_count
+    } else if sprite.repeat && sprite.timer < last_timer {
+        // The animation came all of the way back around to the frame it was on
+        frame_count
+    } else {
+        // Still on the same frame, or stopped on the last frame of an animation that doesn't
+        // repeat
+        0
+    };
+
+    (1..=steps)
+        .map(|step| (last_index + step) % frame_count)
+        .collect()
+}
+
+fn fire_animation_events(
+    entities: Res<Entities>,
+    frame_events: Comp<AnimationFrameEvents>,
+    animation_banks: Comp<AnimationBankSprite>,
+    animated_sprites: Comp<AnimatedSprite>,
+    mut cursors: CompMut<AnimationEventCursor>,
+    mut animation_events: ResMut<AnimationEvents>,
+) {
+    animation_events.events.clear();
+
+    for (entity, (frame_events, bank, sprite)) in
+        entities.iter_with((&frame_events, &animation_banks, &animated_sprites))
+    {
+        let frames = match cursors.get(entity) {
+            // The animation changed, so it starts over from its first frame
+            Some(cursor) if cursor.animation == bank.current => {
+                entered_frames(sprite, cursor.index, cursor.timer)
+            }
+            _ => vec![sprite.index],
+        };
+        cursors.insert(
+            entity,
+            AnimationEventCursor {
+                animation: bank.current,
+                index: sprite.index,
+                timer: sprite.timer,
+            },
+        );
+
+        let Some(animation_frames) = frame_events.get(&bank.current) else {
+            continue;
+        };
+        for frame in frames {
+            for &key in animation_frames.get(frame).into_iter().flatten() {
+                animation_events.events.push(AnimationEvent { entity, key });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transitions_play_between_animations() {
+        let transitions = [AnimationTransitionMeta {
+            from: key!("fall"),
+            to: key!("idle"),
+            animation: key!("crouch"),
+            frames: 3,
+        }];
+        let mut state = AnimationTransitionState::new(key!("fall"));
+
+        let played = [
+            key!("fall"),
+            key!("idle"),
+            key!("idle"),
+            key!("idle"),
+            key!("idle"),
+        ]
+        .map(|animation| state.update(animation, &transitions));
+        assert_eq!(
+            played,
+            [
+                key!("fall"),
+                key!("crouch"),
+                key!("crouch"),
+                key!("crouch"),
+                key!("idle")
+            ]
+        );
+
+        // Other animations don't have a transition, and cut the playing one short
+        let mut state = AnimationTransitionState::new(key!("fall"));
+        assert_eq!(state.update(key!("idle"), &transitions), key!("crouch"));
+        assert_eq!(state.update(key!("walk"), &transitions), key!("walk"));
+        assert_eq!(state.update(key!("idle"), &transitions), key!("idle"));
+    }
+
+    #[test]
+    fn events_fire_once_per_frame_pass() {
+        let mut sprite = AnimatedSprite {
+            index: 0,
+            frames: vec![0, 1, 2, 3].into(),
+            fps: 10.0,
+            timer: 0.0,
+            repeat: true,
+        };
+
+        // Slow playback stays on each frame for a few updates, but only enters it once
+        let mut entered = Vec::new();
+        let (mut index, mut timer) = (sprite.index, sprite.timer);
+        for _ in 0..12 {
+            sprite.timer += 0.04;
+            if sprite.timer >= 0.1 {
+                sprite.timer = 0.0;
+                sprite.index = (sprite.index + 1) % sprite.frames.len();
+            }
+            entered.extend(entered_frames(&sprite, index, timer));
+            (index, timer) = (sprite.index, sprite.timer);
+        }
+        assert_eq!(entered, vec![1, 2, 3, 0]);
+
+        // Frames that are skipped over are still passed through, wrapping around the end
+        sprite.index = 1;
+        assert_eq!(entered_frames(&sprite, 2, 0.0), vec![3, 0, 1]);
+
+        // Animations that don't repeat stop on their last frame
+        sprite.repeat = false;
+        sprite.index = 3;
+        sprite.timer = 0.0;
+        assert!(entered_frames(&sprite, 3, 0.05).is_empty());
+    }
+}
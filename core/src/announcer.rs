@@ -0,0 +1,215 @@
+//! Announcements of the big moments of a match, like multi-kills and the winner, for the game to
+//! call out with voice lines.
+//!
+//! The announcements are worked out from the state of the match at the end of each frame, and
+//! pushed onto the [`Announcer`] queue, much like the sounds in the
+//! [`AudioEvents`][crate::audio::AudioEvents] queue. Which voice line is used for an announcement,
+//! and when it is played, is left up to the game.
+
+use std::collections::VecDeque;
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<Announcer>();
+    session
+        .stages
+        // This must run after the match mode has checked whether the match is over.
+        .add_system_to_stage(CoreStage::Last, announce);
+}
+
+/// How long after a kill, in seconds, the same player's next kill still counts towards a
+/// multi-kill.
+pub const MULTI_KILL_SECONDS: f32 = 3.0;
+
+/// Something worth announcing that happened in the match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Announcement {
+    /// The match has started.
+    MatchStart,
+    /// A player has killed several opponents in quick succession.
+    MultiKill {
+        player_idx: usize,
+        /// The number of kills in the streak, which is at least two.
+        kills: u32,
+    },
+    /// A player is down to the last of their lives.
+    LastStock { player_idx: usize },
+    /// The match is over, and was won by `winner`, or was a draw.
+    Victory { winner: Option<usize> },
+}
+
+impl Announcement {
+    /// How important the announcement is, deciding which are called out first when several are
+    /// made at once.
+    pub fn priority(&self) -> SoundPriority {
+        match self {
+            Announcement::MatchStart | Announcement::Victory { .. } => SoundPriority::High,
+            Announcement::LastStock { .. } => SoundPriority::Normal,
+            Announcement::MultiKill { .. } => SoundPriority::Low,
+        }
+    }
+}
+
+/// Resource containing the announcements made since the queue was last drained, and what the
+/// announcer has seen of the match so far.
+#[derive(Clone, TypeUlid, Debug, Default)]
+#[ulid = "01GVHT451NC4D6CH0QBB5ARDRJ"]
+pub struct Announcer {
+    /// The announcements, with the frame that each was made on.
+    pub queue: VecDeque<(u64, Announcement)>,
+    /// Whether the start of the match has been announced.
+    pub started: bool,
+    /// Whether the result of the match has been announced.
+    pub finished: bool,
+    /// The number of kills that each player had on the last frame.
+    pub kills: [u32; MAX_PLAYERS],
+    /// The number of kills in each player's current streak, and the frame of the latest one.
+    pub streaks: [(u32, u64); MAX_PLAYERS],
+    /// The number of lives that each player had on the last frame.
+    pub stocks: [u32; MAX_PLAYERS],
+}
+
+impl Announcer {
+    /// Take the announcements out of the queue, with the frame that each was made on.
+    pub fn drain(&mut self) -> impl Iterator<Item = (u64, Announcement)> + '_ {
+        self.queue.drain(..)
+    }
+}
+
+/// Push the announcements for what happened in the match this frame.
+fn announce(
+    frame: Res<SimulationFrame>,
+    simulation_rate: Res<SimulationRate>,
+    match_mode: Res<MatchMode>,
+    match_state: Res<MatchState>,
+    stocks: Res<Stocks>,
+    stats: Res<SessionStats>,
+    player_inputs: Res<PlayerInputs>,
+    mut announcer: ResMut<Announcer>,
+) {
+    if !announcer.started {
+        announcer.started = true;
+        announcer.stocks = stocks.remaining;
+        announcer
+            .queue
+            .push_back((**frame, Announcement::MatchStart));
+    }
+
+    let multi_kill_frames = simulation_rate.frames(MULTI_KILL_SECONDS);
+    for player_idx in (0..MAX_PLAYERS).filter(|&i| player_inputs.players[i].active) {
+        let kills = stats.for_player(player_idx).kills;
+        let new_kills = kills.saturating_sub(announcer.kills[player_idx]);
+        announcer.kills[player_idx] = kills;
+        if new_kills > 0 {
+            let (streak, last_kill) = &mut announcer.streaks[player_idx];
+            if **frame - *last_kill > multi_kill_frames {
+                *streak = 0;
+            }
+            *streak += new_kills;
+            *last_kill = **frame;
+            let streak = *streak;
+            if streak > 1 {
+                announcer.queue.push_back((
+                    **frame,
+                    Announcement::MultiKill {
+                        player_idx,
+                        kills: streak,
+                    },
+                ));
+            }
+        }
+
+        let remaining = stocks.remaining[player_idx];
+        let last_stock = std::mem::replace(&mut announcer.stocks[player_idx], remaining);
+        if matches!(*match_mode, MatchMode::Stock { .. }) && remaining == 1 && last_stock > 1 {
+            announcer
+                .queue
+                .push_back((**frame, Announcement::LastStock { player_idx }));
+        }
+    }
+
+    if let Some(result) = &match_state.result {
+        if !announcer.finished {
+            announcer.finished = true;
+            announcer.queue.push_back((
+                **frame,
+                Announcement::Victory {
+                    winner: result.winner,
+                },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn streaks_and_last_stocks_are_announced() {
+        let mut player_inputs = PlayerInputs::default();
+        player_inputs.players[0].active = true;
+        player_inputs.players[1].active = true;
+        let mut test = SystemTest::new(
+            |world| {
+                world.insert_resource(MatchMode::Stock { stocks: 3 });
+                world.insert_resource(Stocks::new(MatchMode::Stock { stocks: 3 }));
+                world.init_resource::<SimulationFrame>();
+                world.init_resource::<SimulationRate>();
+                world.init_resource::<MatchState>();
+                world.init_resource::<SessionStats>();
+                world.init_resource::<Announcer>();
+                world.insert_resource(player_inputs);
+            },
+            |stages| {
+                stages.add_system_to_stage(CoreStage::Last, announce);
+            },
+        );
+
+        // Player 0 takes a life from player 1 every second, then waits too long for the last kill
+        let mut announcements = Vec::new();
+        for frame in 0..(8.0 * crate::FPS) as u64 {
+            test.world
+                .run_initialized_system(
+                    move |mut sim_frame: ResMut<SimulationFrame>,
+                          mut stats: ResMut<SessionStats>,
+                          mut stocks: ResMut<Stocks>,
+                          mut match_state: ResMut<MatchState>| {
+                        **sim_frame = frame;
+                        if [30, 90, 150, 420].contains(&frame) {
+                            stats.for_player_mut(0).kills += 1;
+                            stocks.lose_stock(1, frame);
+                        }
+                        if frame == 420 {
+                            match_state.result = Some(MatchResult { winner: Some(0) });
+                        }
+                        Ok(())
+                    },
+                )
+                .unwrap();
+            test.run();
+            let announcer = test.world.resource::<Announcer>();
+            announcements.extend(announcer.borrow_mut().drain().map(|(_frame, x)| x));
+        }
+
+        assert_eq!(
+            announcements,
+            [
+                Announcement::MatchStart,
+                Announcement::MultiKill {
+                    player_idx: 0,
+                    kills: 2
+                },
+                Announcement::LastStock { player_idx: 1 },
+                Announcement::MultiKill {
+                    player_idx: 0,
+                    kills: 3
+                },
+                Announcement::Victory { winner: Some(0) },
+            ]
+        );
+    }
+}
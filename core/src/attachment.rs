@@ -28,6 +28,9 @@ pub struct Attachment {
     pub offset: Vec3,
     /// Synchronize [`AtlasSprite`] animation with entity animation
     pub sync_animation: bool,
+    /// The rotation relative to the attached entity, in radians. Like the offset, this is mirrored
+    /// when the sprite that it is attached to is flipped.
+    pub rotation: f32,
 }
 
 /// System to update the transforms of entities with the [`Attachment`] component.
@@ -49,6 +52,7 @@ pub fn update_attachments(
         *transform = attached_transform;
 
         let mut offset = attachment.offset;
+        let mut rotation = attachment.rotation;
         if let Some((flip_x, flip_y)) = atlas_sprites
             .get(attachment.entity)
             .map(|x| (x.flip_x, x.flip_y))
@@ -60,6 +64,9 @@ pub fn update_attachments(
             if flip_y {
                 offset.y *= -1.0;
             }
+            if flip_x != flip_y {
+                rotation *= -1.0;
+            }
 
             if let Some((self_flip_x, self_flip_y)) = atlas_sprites
                 .get_mut(ent)
@@ -82,6 +89,7 @@ pub fn update_attachments(
         }
 
         transform.translation += offset;
+        transform.rotation *= Quat::from_rotation_z(rotation);
     }
 }
 
@@ -100,6 +108,8 @@ pub struct PlayerBodyAttachment {
     /// Whether or not to automatically play the same animation bank animation as the sprite that it
     /// is attached to.
     pub sync_animation: bool,
+    /// The rotation relative to the player's body, in radians.
+    pub rotation: f32,
 }
 
 #[derive(Clone, Copy, TypeUlid)]
@@ -138,6 +148,7 @@ fn update_player_body_attachments(
                 entity: player_ent,
                 offset: current_body_offset.extend(0.0) + body_attachment.offset,
                 sync_animation: body_attachment.sync_animation,
+                rotation: body_attachment.rotation,
             },
         );
     }
@@ -157,3 +168,66 @@ fn remove_player_body_attachments(
         had_player_body_attachment_markers.remove(entity);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn attachments_turn_with_the_way_their_sprite_is_flipped() {
+        let mut test = SystemTest::new(
+            |_world| (),
+            |stages| {
+                stages.add_system_to_stage(CoreStage::Last, update_attachments);
+            },
+        );
+
+        // A fin aimed up and forward, attached to a player facing left
+        let aim_angle = PlayerLayers::aim_angle_towards(vec2(-1.0, 1.0));
+        let fin = test
+            .world
+            .run_initialized_system(
+                move |mut entities: ResMut<Entities>,
+                      mut transforms: CompMut<Transform>,
+                      mut atlas_sprites: CompMut<AtlasSprite>,
+                      mut attachments: CompMut<Attachment>| {
+                    let player = entities.create();
+                    transforms.insert(player, default());
+                    atlas_sprites.insert(
+                        player,
+                        AtlasSprite {
+                            flip_x: true,
+                            ..default()
+                        },
+                    );
+
+                    let fin = entities.create();
+                    transforms.insert(fin, default());
+                    atlas_sprites.insert(fin, default());
+                    attachments.insert(
+                        fin,
+                        Attachment {
+                            entity: player,
+                            offset: vec3(4.0, 0.0, 0.0),
+                            sync_animation: false,
+                            rotation: aim_angle,
+                        },
+                    );
+                    Ok(fin)
+                },
+            )
+            .unwrap();
+
+        test.run();
+
+        // The flipped fin turns the other way, so that it still points up and to the left
+        let transforms = test.world.components.get::<Transform>();
+        let transforms = transforms.borrow();
+        let transform = transforms.get(fin).unwrap();
+        assert_eq!(transform.translation.x, -4.0);
+        let pointing = transform.rotation.mul_vec3(Vec3::NEG_X).truncate();
+        assert!(pointing.abs_diff_eq(vec2(-1.0, 1.0).normalize(), 0.001));
+    }
+}
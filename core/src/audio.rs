@@ -0,0 +1,223 @@
+//! Audio events and utilities.
+//!
+//! The game core doesn't play any audio itself. Instead, systems push [`AudioEvent`]s onto the
+//! [`AudioEvents`] queue, and the game runner drains the queue every frame and plays the sounds
+//! with whatever audio backend it uses.
+//!
+//! Sounds with [`SoundVariationsMeta`] are resolved to the variation that is heard when they are
+//! queued, using the session's [`GlobalRng`], so that every client in a networked game, and every
+//! replay, plays the same variations at the same pitch.
+
+use std::collections::{HashMap, VecDeque};
+
+use ::bevy::asset::HandleId;
+
+use crate::{prelude::*, random::GlobalRng};
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<AudioEvents>();
+}
+
+/// Resource containing the audio event queue.
+#[derive(Clone, TypeUlid, Debug, Default)]
+#[ulid = "01GTDD7Y5QZ4W5W0J2R5VXKJ6B"]
+pub struct AudioEvents {
+    /// List of audio events that haven't been handled by the audio system yet.
+    pub queue: VecDeque<AudioEvent>,
+    /// The handle ID that will be given to the next looping sound.
+    pub next_loop_id: u64,
+    /// The variation of each sound with [`SoundVariationsMeta`] that was played last, so that it
+    /// isn't played twice in a row.
+    pub last_variations: HashMap<HandleId, usize>,
+}
+
+impl AudioEvents {
+    /// Play a sound that isn't emitted from any particular place in the world.
+    ///
+    /// This will play at the given volume, regardless of where the camera is.
+    pub fn play(&mut self, sound_source: Handle<AudioSource>, volume: f32) {
+        self.play_with_priority(sound_source, volume, None, SoundPriority::Normal);
+    }
+
+    /// Play a sound emitted from the given world position.
+    ///
+    /// The sound will be attenuated and panned by the audio system based on where it is relative
+    /// to the camera.
+    pub fn play_at(&mut self, sound_source: Handle<AudioSource>, volume: f32, position: Vec2) {
+        self.play_with_priority(sound_source, volume, Some(position), SoundPriority::Normal);
+    }
+
+    /// Play a sound with the given priority, from the given world position if there is one.
+    pub fn play_with_priority(
+        &mut self,
+        sound_source: Handle<AudioSource>,
+        volume: f32,
+        position: Option<Vec2>,
+        priority: SoundPriority,
+    ) {
+        self.queue.push_back(AudioEvent::PlaySound {
+            sound_source,
+            volume,
+            pitch: 1.0,
+            priority,
+            position,
+        });
+    }
+
+    /// Play one of the variations of a sound, from the given world position if there is one.
+    ///
+    /// The sound is picked out of `sound_source` and the [`variations`][SoundVariationsMeta], never
+    /// the same one twice in a row, and played with a random pitch jitter. A sound without any
+    /// variations is played as is, without using up any random numbers.
+    pub fn play_variation(
+        &mut self,
+        rng: &GlobalRng,
+        sound_source: &Handle<AudioSource>,
+        variations: &SoundVariationsMeta,
+        volume: f32,
+        position: Option<Vec2>,
+        priority: SoundPriority,
+    ) {
+        if variations.is_empty() {
+            self.queue.push_back(AudioEvent::PlaySound {
+                sound_source: sound_source.clone(),
+                volume,
+                pitch: 1.0,
+                position,
+            });
+            return;
+        }
+
+        let count = variations.variations.len() + 1;
+        let pool = sound_source.get_bevy_handle_untyped().id;
+        let variation = pick_variation(rng, count, self.last_variations.get(&pool).copied());
+        self.last_variations.insert(pool, variation);
+        let sound_source = match variation {
+            0 => sound_source.clone(),
+            i => variations.variations[i - 1].clone(),
+        };
+        let pitch = 1.0 + rng.f32_normalized() * variations.pitch_jitter;
+
+        self.queue.push_back(AudioEvent::PlaySound {
+            sound_source,
+            volume,
+            pitch,
+            priority,
+            position,
+        });
+    }
+
+    /// Start playing a looping sound, returning the handle ID that can be used to stop it with
+    /// [`stop_loop`][Self::stop_loop].
+    ///
+    /// The loop will keep playing until it is stopped, or until the game session is stopped.
+    pub fn play_loop(&mut self, sound_source: Handle<AudioSource>, volume: f32) -> u64 {
+        let handle_id = self.next_loop_id;
+        self.next_loop_id = self.next_loop_id.wrapping_add(1);
+        self.queue.push_back(AudioEvent::PlayLoop {
+            sound_source,
+            volume,
+            handle_id,
+        });
+        handle_id
+    }
+
+    /// Stop a looping sound started with [`play_loop`][Self::play_loop].
+    pub fn stop_loop(&mut self, handle_id: u64) {
+        self.queue.push_back(AudioEvent::StopLoop { handle_id });
+    }
+}
+
+/// How important a sound is, deciding which sounds are cut off first when too many are playing at
+/// once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SoundPriority {
+    /// Sounds that are played all the time, like footsteps, that nobody misses if a few are cut.
+    Low,
+    #[default]
+    Normal,
+    /// Cues that the players must hear, like explosions, which are never cut off for a less
+    /// important sound.
+    High,
+}
+
+/// An audio event that may be sent to the [`AudioEvents`] resource.
+#[derive(Clone, Debug)]
+pub enum AudioEvent {
+    /// Play some sound from an audio source.
+    PlaySound {
+        /// The sound to play.
+        sound_source: Handle<AudioSource>,
+        /// The volume to play the sound at.
+        volume: f32,
+        /// The pitch to play the sound at, relative to its own.
+        pitch: f32,
+        /// How important the sound is, if too many sounds are playing at once.
+        priority: SoundPriority,
+        /// The world position that the sound is emitted from, if any.
+        ///
+        /// Sounds without a position are played without any spatialization.
+        position: Option<Vec2>,
+    },
+    /// Start playing a sound on loop.
+    PlayLoop {
+        /// The sound to play.
+        sound_source: Handle<AudioSource>,
+        /// The volume to play the sound at.
+        volume: f32,
+        /// The ID used to refer to this loop when stopping it.
+        ///
+        /// Starting a loop with the ID of a loop that is already playing will replace it.
+        handle_id: u64,
+    },
+    /// Stop a looping sound.
+    StopLoop {
+        /// The ID of the loop to stop.
+        handle_id: u64,
+    },
+}
+
+/// Pick one of `count` variations of a sound, that isn't the `last` one that was played, unless
+/// there is only one.
+fn pick_variation(rng: &GlobalRng, count: usize, last: Option<usize>) -> usize {
+    match last {
+        Some(last) if count > 1 => {
+            let variation = rng.usize(..count - 1);
+            if variation >= last {
+                variation + 1
+            } else {
+                variation
+            }
+        }
+        _ => rng.usize(..count.max(1)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn variations_dont_repeat_and_follow_the_seed() {
+        let picks = |seed: u64| {
+            let rng = GlobalRng::with_seed(seed);
+            let mut last = None;
+            (0..50)
+                .map(|_| {
+                    let variation = pick_variation(&rng, 3, last);
+                    last = Some(variation);
+                    variation
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let variations = picks(42);
+        assert!(variations.windows(2).all(|x| x[0] != x[1]));
+        assert!((0..3).all(|i| variations.contains(&i)));
+        assert_eq!(picks(42), variations);
+
+        // There's nothing else to play if there is only one variation
+        let rng = GlobalRng::with_seed(42);
+        assert_eq!(pick_variation(&rng, 1, Some(0)), 0);
+    }
+}
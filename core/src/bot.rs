@@ -0,0 +1,306 @@
+//! AI-controlled players, for practicing alone and filling empty player slots.
+//!
+//! Bots are simulated as part of the game session, writing their [`PlayerControl`] before the
+//! player states read it, just like the controls of a human player. Their decisions only depend on
+//! the state of the session and the [`GlobalRng`], so they play out the same way for every player
+//! in a networked game.
+
+use crate::{prelude::*, random::GlobalRng};
+
+pub fn install(session: &mut GameSession) {
+    session
+        .stages
+        // Bots must press their buttons before the new presses are buffered by the input module.
+        .add_system_to_stage(CoreStage::First, update_bots);
+}
+
+/// How well a bot plays, from `0.0` for the easiest bots to `1.0` for the hardest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BotDifficulty(pub f32);
+
+impl Default for BotDifficulty {
+    fn default() -> Self {
+        Self(0.5)
+    }
+}
+
+impl BotDifficulty {
+    /// Get the difficulty, clamped to the valid range.
+    pub fn get(&self) -> f32 {
+        self.0.clamp(0.0, 1.0)
+    }
+
+    /// Get the number of frames that the bot takes to react to what is happening.
+    pub fn reaction_frames(&self, meta: &BotMeta) -> u32 {
+        let max = meta.max_reaction_frames as f32;
+        let min = meta.min_reaction_frames as f32;
+        (max + (min - max) * self.get()).round() as u32
+    }
+}
+
+/// What a bot is trying to do.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BotAction {
+    /// Stand still, because there is nobody to fight.
+    #[default]
+    Idle,
+    /// Move towards an opponent.
+    Approach { target: Entity },
+    /// Attack an opponent that is in range.
+    Attack { target: Entity },
+    /// Get out of danger, by moving towards the given horizontal position.
+    Dodge { x: f32 },
+}
+
+/// Component added to the players that are controlled by a bot.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GT57VX4A0R43KJS5B42THH0F"]
+pub struct BotController {
+    pub difficulty: BotDifficulty,
+    /// What the bot decided to do the last time it thought about it.
+    pub action: BotAction,
+    /// The number of frames until the bot decides what to do next.
+    pub decision_cooldown: u32,
+    /// The controls that the bot used on the last frame.
+    pub control: PlayerControl,
+}
+
+impl BotController {
+    pub fn new(difficulty: BotDifficulty) -> Self {
+        Self {
+            difficulty,
+            ..default()
+        }
+    }
+}
+
+/// Decide what a bot at `position` should do, given the positions of its opponents, the collision
+/// rectangles of the hazards, and the area where it is safe from falling out of the blast zone.
+///
+/// This is a small behavior tree: staying out of danger comes first, then attacking an opponent in
+/// range, then approaching the nearest opponent.
+pub fn choose_bot_action(
+    meta: &BotMeta,
+    position: Vec2,
+    opponents: &[(Entity, Vec2)],
+    hazards: &[Rect],
+    safe_area: Rect,
+) -> BotAction {
+    // Dodge hazards
+    let distance = Vec2::splat(meta.hazard_distance);
+    for hazard in hazards {
+        let danger = Rect {
+            min: hazard.min - distance,
+            max: hazard.max + distance,
+        };
+        if danger.contains(position) {
+            let away = if position.x < hazard.center().x {
+                danger.min.x
+            } else {
+                danger.max.x
+            };
+            return BotAction::Dodge {
+                x: away.clamp(safe_area.min.x, safe_area.max.x),
+            };
+        }
+    }
+
+    // Head away from the edges of the blast zone
+    if !safe_area.contains(position) {
+        return BotAction::Dodge {
+            x: safe_area.center().x,
+        };
+    }
+
+    let Some(&(target, target_position)) = opponents.iter().min_by(|a, b| {
+        let a = a.1.distance_squared(position);
+        let b = b.1.distance_squared(position);
+        a.total_cmp(&b)
+    }) else {
+        return BotAction::Idle;
+    };
+
+    if target_position.distance(position) <= meta.attack_range {
+        BotAction::Attack { target }
+    } else {
+        BotAction::Approach { target }
+    }
+}
+
+fn update_bots(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
+    rng: Res<GlobalRng>,
+    mut player_inputs: ResMut<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    transforms: Comp<Transform>,
+    bodies: Comp<KinematicBody>,
+    hazards: Comp<Hazard>,
+    inventories: Comp<Inventory>,
+    mut bots: CompMut<BotController>,
+) {
+    let meta = &core_meta.bots;
+
+    // Add and remove the bot controllers of the players that are, or are no longer, bots
+    for (player_ent, player_idx) in entities.iter_with(&player_indexes) {
+        if let Some(difficulty) = player_inputs.players[player_idx.0].bot {
+            if let Some(bot) = bots.get_mut(player_ent) {
+                bot.difficulty = difficulty;
+            } else {
+                bots.insert(player_ent, BotController::new(difficulty));
+            }
+        } else if bots.remove(player_ent).is_some() {
+            // Let go of whatever the bot was holding
+            player_inputs.players[player_idx.0].control = default();
+        }
+    }
+
+    let players = entities
+        .iter_with((&player_indexes, &transforms))
+        .filter(|(ent, _)| !killed_players.contains(*ent))
+        .map(|(ent, (player_idx, transform))| (ent, player_idx.0, transform.translation.truncate()))
+        .collect::<Vec<_>>();
+    let hazard_rects = entities
+        .iter_with((&hazards, &transforms))
+        .map(|(_ent, (hazard, transform))| hazard.collider_rect(transform.translation))
+        .collect::<Vec<_>>();
+    let blast_zone = map.blast_zone_rect();
+    let edge = Vec2::splat(meta.edge_distance);
+    let safe_area = Rect {
+        min: blast_zone.min + edge,
+        max: blast_zone.max - edge,
+    };
+
+    for (bot_ent, (bot, player_idx, body)) in
+        entities.iter_with((&mut bots, &player_indexes, &bodies))
+    {
+        if killed_players.contains(bot_ent) {
+            continue;
+        }
+        let position = transforms.get(bot_ent).unwrap().translation.truncate();
+
+        // Only think about what to do every so often, so that easier bots are slower to react
+        if bot.decision_cooldown == 0 {
+            let opponents = players
+                .iter()
+                .filter(|(ent, idx, _)| {
+                    *ent != bot_ent && !player_inputs.are_teammates(player_idx.0, *idx)
+                })
+                .map(|(ent, _, position)| (*ent, *position))
+                .collect::<Vec<_>>();
+            bot.action = choose_bot_action(meta, position, &opponents, &hazard_rects, safe_area);
+            bot.decision_cooldown = bot.difficulty.reaction_frames(meta) + rng.u32(0..4);
+        } else {
+            bot.decision_cooldown -= 1;
+        }
+
+        let target_position = |target: Entity| {
+            players
+                .iter()
+                .find(|(ent, ..)| *ent == target)
+                .map(|(.., position)| *position)
+        };
+        let direction_to = |x: f32| {
+            if (x - position.x).abs() < 4.0 {
+                0.0
+            } else {
+                (x - position.x).signum()
+            }
+        };
+
+        let mut control = PlayerControl::default();
+        match bot.action {
+            BotAction::Idle => (),
+            BotAction::Approach { target } => {
+                if let Some(target_position) = target_position(target) {
+                    control.move_direction.x = direction_to(target_position.x);
+                    control.jump_pressed = body.is_on_ground
+                        && target_position.y - position.y > meta.jump_height
+                        && !bot.control.jump_pressed;
+                }
+            }
+            BotAction::Attack { target } => {
+                if let Some(target_position) = target_position(target) {
+                    // Turn to face the target, then attack with whatever the bot is holding, or
+                    // try to grab the target if it's empty-handed
+                    control.move_direction.x = direction_to(target_position.x) * 0.1;
+                    let error = meta.max_aim_error.to_radians()
+                        * (1.0 - bot.difficulty.get())
+                        * rng.f32_normalized();
+                    control.aim_direction = Vec2::from_angle(error)
+                        .rotate(target_position - position)
+                        .normalize_or_zero();
+
+                    let holding_item = inventories
+                        .get(bot_ent)
+                        .map_or(false, |inventory| inventory.0.is_some());
+                    if holding_item {
+                        control.shoot_pressed = !bot.control.shoot_pressed;
+                    } else {
+                        control.grab_pressed = !bot.control.grab_pressed;
+                    }
+                }
+            }
+            BotAction::Dodge { x } => {
+                control.move_direction.x = direction_to(x);
+                // Jump back up when falling towards the bottom of the blast zone
+                control.jump_pressed = position.y < safe_area.min.y && !bot.control.jump_pressed;
+            }
+        }
+
+        // Press the buttons the same way that the live input does for human players
+        control.detect_presses(&bot.control);
+
+        bot.control = control.clone();
+        player_inputs.players[player_idx.0].control = control;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_meta() -> BotMeta {
+        BotMeta {
+            max_reaction_frames: 30,
+            min_reaction_frames: 6,
+            attack_range: 40.0,
+            hazard_distance: 48.0,
+            edge_distance: 64.0,
+            jump_height: 24.0,
+            max_aim_error: 25.0,
+        }
+    }
+
+    #[test]
+    fn bots_dodge_before_fighting() {
+        let meta = test_meta();
+        let mut entities = Entities::default();
+        let near = entities.create();
+        let far = entities.create();
+        let opponents = [(far, vec2(300.0, 0.0)), (near, vec2(130.0, 0.0))];
+        let safe_area = Rect::new(0.0, 0.0, 1000.0, 1000.0);
+
+        // Approach the nearest opponent, and attack them once in range
+        let action = choose_bot_action(&meta, vec2(0.0, 0.0), &opponents, &[], safe_area);
+        assert_eq!(action, BotAction::Approach { target: near });
+        let action = choose_bot_action(&meta, vec2(100.0, 0.0), &opponents, &[], safe_area);
+        assert_eq!(action, BotAction::Attack { target: near });
+
+        // Run away from a hazard, even with an opponent in range
+        let hazard = Rect::new(80.0, 0.0, 32.0, 32.0);
+        let action = choose_bot_action(&meta, vec2(100.0, 0.0), &opponents, &[hazard], safe_area);
+        assert_eq!(action, BotAction::Dodge { x: 144.0 });
+
+        // Head back from the edge of the blast zone
+        let action = choose_bot_action(&meta, vec2(0.0, -600.0), &opponents, &[], safe_area);
+        assert_eq!(action, BotAction::Dodge { x: 0.0 });
+
+        // Harder bots react faster
+        assert_eq!(BotDifficulty(0.0).reaction_frames(&meta), 30);
+        assert_eq!(BotDifficulty(1.0).reaction_frames(&meta), 6);
+        assert_eq!(BotDifficulty(2.0).reaction_frames(&meta), 6);
+    }
+}
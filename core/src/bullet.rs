@@ -10,15 +10,41 @@ pub fn install(session: &mut GameSession) {
         .add_system_to_stage(CoreStage::PostUpdate, update);
 }
 
-#[derive(Clone, Debug, TypeUlid, Copy)]
-#[ulid = "01GQX3KM2A4WPV2NKJNG85TJ3P"]
-pub struct Bullet {
-    pub direction: f32,
+/// Component for a projectile that flies in a straight line until it hits something.
+///
+/// Projectiles are destroyed when they hit a player, a solid tile, or another projectile, or when
+/// the [`Lifetime`] from their [`BulletMeta`] runs out.
+#[derive(Clone, Debug, Default, TypeUlid, Copy)]
+#[ulid = "01GV7HRTS2PTW5P5M99XWSF3RF"]
+pub struct Projectile {
+    /// The distance that the projectile moves every frame.
+    pub velocity: Vec2,
+    /// The damage dealt to a player hit by the projectile.
+    pub damage: f32,
+    /// The player that fired the projectile, if any.
+    pub owner: Option<Entity>,
 }
 
-impl Default for Bullet {
-    fn default() -> Self {
-        Self { direction: 1.0 }
+impl Projectile {
+    /// Create a projectile fired by `owner` from the bullet metadata, mirrored if it is fired to
+    /// the left, and turned from straight ahead to the unit `aim` direction.
+    pub fn new(meta: &BulletMeta, flip_x: bool, aim: Vec2, owner: Option<Entity>) -> Self {
+        let (direction, forward) = if flip_x {
+            (-1.0, Vec2::NEG_X)
+        } else {
+            (1.0, Vec2::X)
+        };
+        let velocity = meta.velocity * vec2(direction, 1.0);
+        let velocity = if aim == Vec2::ZERO {
+            velocity
+        } else {
+            Vec2::from_angle(forward.angle_between(aim)).rotate(velocity)
+        };
+        Self {
+            velocity,
+            damage: meta.damage,
+            owner,
+        }
     }
 }
 
@@ -86,16 +112,17 @@ fn update(
     collision_world: CollisionWorld,
     mut player_events: ResMut<PlayerEvents>,
     mut transforms: CompMut<Transform>,
-    mut bullets: CompMut<Bullet>,
+    projectiles: Comp<Projectile>,
+    mut destructibles: CompMut<Destructible>,
     mut audio_events: ResMut<AudioEvents>,
 ) {
-    for (entity, (bullet, bullet_handle)) in entities.iter_with((&mut bullets, &bullet_handles)) {
+    for (entity, (projectile, bullet_handle)) in entities.iter_with((&projectiles, &bullet_handles))
+    {
         let Some(bullet_meta) = bullet_assets.get(&bullet_handle.get_bevy_handle()) else {
             continue;
         };
 
         let BulletMeta {
-            velocity,
             body_diameter,
             explosion_fps,
             explosion_volume,
@@ -109,32 +136,56 @@ fn update(
         // Move bullet
         let position = {
             let position = transforms.get_mut(entity).unwrap();
-            position.translation += bullet.direction * velocity.extend(0.0);
+            position.translation += projectile.velocity.extend(0.0);
             *position
         };
 
         // Check actor collisions
         let mut hit_player = false;
-        collision_world
-            .actor_collisions(entity)
-            .into_iter()
-            .filter(|&x| player_indexes.contains(x))
-            .for_each(|player| {
+        let mut hit_projectile = false;
+        for other in collision_world.actor_collisions(entity) {
+            if player_indexes.contains(other) {
                 hit_player = true;
-                player_events.kill(player, Some(position.translation.xy()));
-            });
+                player_events.damage(
+                    other,
+                    Damage {
+                        amount: projectile.damage,
+                        source: projectile.owner,
+                        direction: projectile.velocity.normalize_or_zero(),
+                        knockback: 1.0,
+                        weapon: Some(key!("bullet")),
+                        kind: HitKind::Projectile,
+                    },
+                );
+            } else if projectiles.contains(other) {
+                // Projectiles that run into each other are both destroyed
+                hit_projectile = true;
+            }
+        }
 
         // check solid tile collisions
-        let hit_solid = collision_world.tile_collision(
-            position,
-            ColliderShape::Circle {
-                diameter: *body_diameter,
-            },
-        ) != TileCollisionKind::EMPTY;
+        let hit_tile = collision_world
+            .tile_collision_entity(
+                position,
+                ColliderShape::Circle {
+                    diameter: *body_diameter,
+                },
+            )
+            .filter(|(_, kind)| *kind != TileCollisionKind::EMPTY);
+        let hit_solid = hit_tile.is_some();
+
+        // Chip away at destructible tiles
+        if let Some(destructible) = hit_tile.and_then(|(tile, _)| destructibles.get_mut(tile)) {
+            destructible.health -= projectile.damage;
+        }
 
         // Bullet hit something
-        if hit_player || hit_solid {
-            audio_events.play(explosion_sound.clone(), *explosion_volume);
+        if hit_player || hit_projectile || hit_solid {
+            audio_events.play_at(
+                explosion_sound.clone(),
+                *explosion_volume,
+                position.translation.truncate(),
+            );
 
             let mut explosion_transform = *transforms.get(entity).unwrap();
             explosion_transform.translation.z += 1.0;
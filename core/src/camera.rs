@@ -1,9 +1,15 @@
 use crate::prelude::*;
 
 pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<CameraMode>();
+    session.world.init_resource::<CameraTrauma>();
+
     session
         .stages
         .add_system_to_stage(CoreStage::Last, camera_controller);
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, split_screen_controller);
     session
         .stages
         .add_system_to_stage(CoreStage::Last, camera_parallax);
@@ -16,6 +22,15 @@ pub struct ParallaxBackgroundSprite {
     pub meta: ParallaxLayerMeta,
 }
 
+/// Marker component for entities that must not be smoothed out between simulation frames when they
+/// are rendered, such as sprites that are locked to the camera.
+///
+/// Cameras are never smoothed out, so anything positioned relative to them should opt out too, or
+/// it would lag behind.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GWR10X75PM75HNZEHV99M9CN"]
+pub struct NoInterpolation;
+
 #[derive(Clone, Debug, TypeUlid, Default)]
 #[ulid = "01GPV6M1KY0GBRQVJ3WG5CSBBS"]
 pub struct CameraState {
@@ -25,6 +40,47 @@ pub struct CameraState {
     pub disable_controller: bool,
 }
 
+/// Resource containing whether the players are sharing one camera, or the screen is split into a
+/// panel for each of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, TypeUlid)]
+#[ulid = "01GV71DTZDSHZCXRDM0NATW9XV"]
+pub enum CameraMode {
+    /// All of the players are shown by the main camera.
+    #[default]
+    Unified,
+    /// Each player is shown by their own [`SplitCamera`].
+    Split,
+}
+
+/// Resource collecting the camera trauma added during the simulation, by things like deaths and
+/// explosions.
+///
+/// The game takes the trauma out of the session every frame and uses it to shake the camera.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GTEHBJTYZWDGA6J9F39D4SJC"]
+pub struct CameraTrauma {
+    pub trauma: f32,
+}
+
+impl CameraTrauma {
+    /// Shake the camera, with `1.0` being the most violent shake.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Take the trauma that has been added since the last time it was taken.
+    pub fn take(&mut self) -> f32 {
+        std::mem::take(&mut self.trauma)
+    }
+}
+
+/// Component for the camera following a single player while the screen is split.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GW8G8X8YAYJ9K8B17DNEXHX4"]
+pub struct SplitCamera {
+    pub player_idx: usize,
+}
+
 fn camera_controller(
     game_meta: Res<CoreMetaArc>,
     entities: Res<Entities>,
@@ -34,6 +90,7 @@ fn camera_controller(
     mut camera_states: CompMut<CameraState>,
     transforms: Comp<Transform>,
     player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
     bodies: Comp<KinematicBody>,
     window: Res<Window>,
 ) {
@@ -101,8 +158,10 @@ fn camera_controller(
     let mut min = Vec2::new(f32::MAX, f32::MAX);
     let mut max = Vec2::new(f32::MIN, f32::MIN);
 
+    // Only frame the living players
     let players: Vec<usize> = entities
         .iter_with(&player_indexes)
+        .filter(|(ent, _)| !killed_players.contains(*ent))
         .map(|x| x.1 .0)
         .collect();
     let player_count = players.len();
@@ -143,17 +202,210 @@ fn camera_controller(
         middle_point.y = size.y / 2.0;
     }
 
+    // Stay within the zoom limits, and don't zoom out further than the whole map
+    let map_fit_height = map_size.y.min(map_size.x / viewport_aspect);
+    let max_height = meta.max_height.min(map_fit_height).max(meta.min_height);
+    camera.height = (scale * default_height).clamp(meta.min_height, max_height);
+
     let delta = camera_pos.truncate() - middle_point;
     let dist = delta * meta.move_lerp_factor;
-    camera.height = scale * default_height;
     *camera_pos -= dist.extend(0.0);
+
+    // Never show anything outside of the map
+    let half_view = vec2(camera.height * viewport_aspect, camera.height) / 2.0;
+    *camera_pos = clamp_to_map(camera_pos.truncate(), half_view, map_size).extend(camera_pos.z);
+}
+
+/// Clamp a camera position so that a view with the given half size stays inside of the map,
+/// centering the view on any axis where the map is smaller than it.
+fn clamp_to_map(pos: Vec2, half_view: Vec2, map_size: Vec2) -> Vec2 {
+    let clamp_axis = |pos: f32, half_view: f32, map_size: f32| {
+        if half_view * 2.0 >= map_size {
+            map_size / 2.0
+        } else {
+            pos.clamp(half_view, map_size - half_view)
+        }
+    };
+
+    vec2(
+        clamp_axis(pos.x, half_view.x, map_size.x),
+        clamp_axis(pos.y, half_view.y, map_size.y),
+    )
+}
+
+/// Split the screen when the players get far apart, and merge it again when they come back
+/// together.
+///
+/// The main camera keeps following all of the players while the screen is split, and the split
+/// cameras move towards it as the players get closer, so that merging the screen is seamless.
+fn split_screen_controller(
+    game_meta: Res<CoreMetaArc>,
+    mut entities: ResMut<Entities>,
+    mut camera_mode: ResMut<CameraMode>,
+    map: Res<LoadedMap>,
+    window: Res<Window>,
+    player_indexes: Comp<PlayerIdx>,
+    camera_states: Comp<CameraState>,
+    mut cameras: CompMut<Camera>,
+    mut camera_shakes: CompMut<CameraShake>,
+    mut split_cameras: CompMut<SplitCamera>,
+    mut transforms: CompMut<Transform>,
+) {
+    let meta = &game_meta.camera;
+
+    let Some((main_ent, camera_state)) = entities.iter_with(&camera_states).next() else {
+        return;
+    };
+    let camera_state = camera_state.clone();
+
+    let mut players = entities
+        .iter_with(&player_indexes)
+        .map(|(_ent, player_idx)| player_idx.0)
+        .collect::<Vec<_>>();
+    players.sort_unstable();
+    players.dedup();
+    let player_centers = players
+        .iter()
+        .map(|idx| camera_state.player_camera_rects[*idx].center())
+        .collect::<Vec<_>>();
+
+    // The largest distance between any two players
+    let spread = player_centers
+        .iter()
+        .flat_map(|a| player_centers.iter().map(move |b| a.distance(*b)))
+        .fold(0.0, f32::max);
+
+    let can_split = meta.split_screen
+        && !camera_state.disable_controller
+        && (2..=MAX_PLAYERS.min(4)).contains(&players.len());
+    *camera_mode = match *camera_mode {
+        _ if !can_split => CameraMode::Unified,
+        CameraMode::Unified if spread > meta.split_distance => CameraMode::Split,
+        CameraMode::Split if spread < meta.merge_distance => CameraMode::Unified,
+        mode => mode,
+    };
+
+    // Remove the split cameras when they no longer match the players
+    let split_players = entities
+        .iter_with(&split_cameras)
+        .map(|(ent, split_camera)| (ent, split_camera.player_idx))
+        .collect::<Vec<_>>();
+    let up_to_date = *camera_mode == CameraMode::Split
+        && split_players.len() == players.len()
+        && split_players
+            .iter()
+            .all(|(_ent, player_idx)| players.contains(player_idx));
+    if !up_to_date {
+        for (ent, _) in &split_players {
+            entities.kill(*ent);
+        }
+    }
+
+    let main_camera = cameras.get_mut(main_ent).unwrap();
+    main_camera.active = *camera_mode == CameraMode::Unified;
+    if *camera_mode == CameraMode::Unified {
+        return;
+    }
+    let main_height = main_camera.height;
+    let main_center = camera_shakes.get(main_ent).unwrap().center;
+
+    // Spawn the split cameras where the main camera is, so they move smoothly to their players
+    if !up_to_date {
+        for (i, player_idx) in players.iter().enumerate() {
+            let ent = entities.create();
+            split_cameras.insert(
+                ent,
+                SplitCamera {
+                    player_idx: *player_idx,
+                },
+            );
+            camera_shakes.insert(
+                ent,
+                CameraShake {
+                    center: main_center,
+                    ..CameraShake::new(6.0, glam::vec2(3.0, 3.0), 1.0)
+                },
+            );
+            cameras.insert(
+                ent,
+                Camera {
+                    height: main_height,
+                    priority: i as i32 + 1,
+                    ..default()
+                },
+            );
+            transforms.insert(ent, default());
+        }
+    }
+
+    // As the players get closer, the split cameras converge on the main camera
+    let merge_progress = ((meta.split_distance - spread)
+        / (meta.split_distance - meta.merge_distance).max(f32::EPSILON))
+    .clamp(0.0, 1.0);
+    let panels = split_screen_layout(players.len(), window.size);
+    for (_ent, (split_camera, camera, camera_shake)) in
+        entities.iter_with((&split_cameras, &mut cameras, &mut camera_shakes))
+    {
+        let Some(i) = players.iter().position(|x| *x == split_camera.player_idx) else {
+            continue;
+        };
+        let panel = panels[i];
+
+        camera.viewport = Some(Viewport {
+            position: panel.min.as_uvec2(),
+            size: panel.size().as_uvec2(),
+            depth_min: 0.0,
+            depth_max: 1.0,
+        });
+        // Keep the same scale as the unsplit screen
+        camera.height = meta.default_height * panel.height() / window.size.y.max(1.0);
+
+        let mut target = player_centers[i].lerp(main_center.truncate(), merge_progress);
+        // Keep camera above the map floor
+        target.y = target.y.max(camera.height / 2.0);
+        target.x = target
+            .x
+            .clamp(0.0, map.grid_size.x as f32 * map.tile_size.x);
+
+        let camera_pos = &mut camera_shake.center;
+        let delta = camera_pos.truncate() - target;
+        *camera_pos -= (delta * meta.move_lerp_factor).extend(0.0);
+    }
+}
+
+/// Get the screen area of each player's panel when the screen is split, in order of player index.
+///
+/// Two players get a side-by-side split, three players share the bottom half of the screen with
+/// the first player on top, and four players get a quarter of the screen each.
+fn split_screen_layout(player_count: usize, screen_size: Vec2) -> Vec<Rect> {
+    let Vec2 { x: w, y: h } = screen_size;
+    let panel = |x: f32, y: f32, width: f32, height: f32| Rect {
+        min: vec2(x, y),
+        max: vec2(x + width, y + height),
+    };
+
+    match player_count {
+        0 | 1 => vec![panel(0.0, 0.0, w, h)],
+        2 => vec![panel(0.0, 0.0, w / 2.0, h), panel(w / 2.0, 0.0, w / 2.0, h)],
+        3 => vec![
+            panel(0.0, 0.0, w, h / 2.0),
+            panel(0.0, h / 2.0, w / 2.0, h / 2.0),
+            panel(w / 2.0, h / 2.0, w / 2.0, h / 2.0),
+        ],
+        _ => vec![
+            panel(0.0, 0.0, w / 2.0, h / 2.0),
+            panel(w / 2.0, 0.0, w / 2.0, h / 2.0),
+            panel(0.0, h / 2.0, w / 2.0, h / 2.0),
+            panel(w / 2.0, h / 2.0, w / 2.0, h / 2.0),
+        ],
+    }
 }
 
 fn camera_parallax(
     entities: Res<Entities>,
     mut transforms: CompMut<Transform>,
     parallax_bg_sprites: Comp<ParallaxBackgroundSprite>,
-    cameras: Comp<Camera>,
+    camera_states: Comp<CameraState>,
     map: Res<LoadedMap>,
 ) {
     // TODO: This constant represents that maximum camera-visible distance, and should be moved
@@ -162,8 +414,9 @@ fn camera_parallax(
 
     let map_size = map.grid_size.as_vec2() * map.tile_size;
 
+    // Follow the main camera, even while the screen is split
     let camera_transform = entities
-        .iter_with((&transforms, &cameras))
+        .iter_with((&transforms, &camera_states))
         .next()
         .map(|x| x.1 .0)
         .copied()
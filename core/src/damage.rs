@@ -5,7 +5,10 @@ use crate::prelude::*;
 pub fn install(session: &mut GameSession) {
     session
         .stages
-        .add_system_to_stage(CoreStage::PostUpdate, kill_players_in_damage_region);
+        .add_system_to_stage(CoreStage::PostUpdate, kill_players_in_damage_region)
+        .add_system_to_stage(CoreStage::PostUpdate, apply_explosions)
+        .add_system_to_stage(CoreStage::PostUpdate, break_destructible_tiles)
+        .add_system_to_stage(CoreStage::PostUpdate, damage_players_in_hazards);
 }
 
 /// A rectangular damage region.
@@ -33,6 +36,269 @@ impl DamageRegion {
 #[ulid = "01GP1X4NM7GMEKKZ4FEZ1RK3T0"]
 pub struct DamageRegionOwner(pub Entity);
 
+/// A component that may be added to a damage region entity to indicate the kind of weapon that
+/// created it, for the killfeed.
+#[derive(Debug, Clone, TypeUlid)]
+#[ulid = "01GTBF1WVKCTBF54AFT74YMBZG"]
+pub struct DamageRegionWeapon(pub Key);
+
+/// Component for a map tile that can be destroyed by explosions and projectiles.
+///
+/// The tile breaks once it's health runs out.
+#[derive(Debug, Clone, Copy, TypeUlid)]
+#[ulid = "01GVACH5ENTP5SXKWF4HBZQQX2"]
+pub struct Destructible {
+    /// The amount of damage the tile can take before it breaks.
+    pub health: f32,
+}
+
+/// Component for a rectangular region of the map, such as spikes or lava, that hurts the players
+/// that touch it.
+///
+/// Hazards don't have an owner, so players that die to them aren't credited to anybody.
+#[derive(Debug, Clone, Default, TypeUlid)]
+#[ulid = "01GVGJ4Y0HNH1S0XQ9XBBG8D4T"]
+pub struct Hazard {
+    /// The size of the hazard in pixels.
+    pub size: Vec2,
+    /// The damage dealt to a player each time they are hit by the hazard.
+    pub damage: f32,
+    /// Whether the hazard kills players outright, whatever health they have left.
+    pub instant_kill: bool,
+    /// The direction that players are knocked in when they are hit.
+    pub direction: Vec2,
+}
+
+impl Hazard {
+    /// Get the collision rectangle of this hazard, given it's transform.
+    pub fn collider_rect(&self, position: Vec3) -> Rect {
+        Rect::new(position.x, position.y, self.size.x, self.size.y)
+    }
+}
+
+/// Component for an explosion, that damages and knocks back every player within its radius.
+///
+/// The explosion goes off on the frame after it is spawned, and the entity is then despawned. Its
+/// damage and knockback fall off linearly from its center, down to nothing at its radius.
+#[derive(Debug, Clone, Default, TypeUlid)]
+#[ulid = "01GVEWT4FRY855DMC06BMT2FAT"]
+pub struct Explosion {
+    /// The distance from the center of the explosion that players are hit within, in pixels.
+    pub radius: f32,
+    /// The damage dealt to a player at the center of the explosion.
+    pub damage: f32,
+    /// The knockback of a player at the center of the explosion, relative to the usual knockback
+    /// speed.
+    pub knockback: f32,
+    /// The player that caused the explosion, if any.
+    pub owner: Option<Entity>,
+    /// The kind of weapon that caused the explosion, for the killfeed.
+    pub weapon: Option<Key>,
+    /// The sound played at the position of the explosion, and its volume.
+    pub sound: Option<(Handle<AudioSource>, f32)>,
+}
+
+impl Explosion {
+    /// Get the damage dealt by the explosion at `center` to a player at `player_pos`, if they are
+    /// within its radius.
+    ///
+    /// The player is knocked directly away from the center, or straight up if they are right on
+    /// top of it.
+    pub fn damage_at(&self, center: Vec2, player_pos: Vec2) -> Option<Damage> {
+        let distance = center.distance(player_pos);
+        if distance > self.radius {
+            return None;
+        }
+        let falloff = 1.0 - distance / self.radius.max(f32::EPSILON);
+        let direction = (player_pos - center).normalize_or_zero();
+
+        Some(Damage {
+            amount: self.damage * falloff,
+            source: self.owner,
+            direction: if direction == Vec2::ZERO {
+                Vec2::Y
+            } else {
+                direction
+            },
+            knockback: self.knockback * falloff,
+            weapon: self.weapon,
+            kind: HitKind::Explosion,
+        })
+    }
+}
+
+/// System that sets off explosions, damaging the players and the destructible tiles around them.
+fn apply_explosions(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    explosions: Comp<Explosion>,
+    player_indexes: Comp<PlayerIdx>,
+    transforms: Comp<Transform>,
+    tile_layers: Comp<TileLayer>,
+    mut destructibles: CompMut<Destructible>,
+    mut player_events: ResMut<PlayerEvents>,
+    mut audio_events: ResMut<AudioEvents>,
+    mut camera_trauma: ResMut<CameraTrauma>,
+) {
+    let mut exploded = Vec::new();
+    for (ent, (explosion, transform)) in entities.iter_with((&explosions, &transforms)) {
+        let center = transform.translation.truncate();
+
+        for (player_ent, (_idx, player_transform)) in
+            entities.iter_with((&player_indexes, &transforms))
+        {
+            if let Some(damage) =
+                explosion.damage_at(center, player_transform.translation.truncate())
+            {
+                player_events.damage(player_ent, damage);
+            }
+        }
+
+        // Damage the destructible tiles with their centers inside of the explosion
+        for (_layer_ent, (layer, layer_transform)) in
+            entities.iter_with((&tile_layers, &transforms))
+        {
+            let tile_size = layer.tile_size;
+            let local_center = center - layer_transform.translation.truncate();
+            let min = ((local_center - explosion.radius) / tile_size)
+                .floor()
+                .max(Vec2::ZERO)
+                .as_uvec2();
+            let max = ((local_center + explosion.radius) / tile_size)
+                .ceil()
+                .max(Vec2::ZERO)
+                .as_uvec2()
+                .min(layer.grid_size);
+            for x in min.x..max.x {
+                for y in min.y..max.y {
+                    let pos = uvec2(x, y);
+                    let Some(destructible) = layer.get(pos).and_then(|tile| destructibles.get_mut(tile)) else {
+                        continue;
+                    };
+                    let tile_center = (pos.as_vec2() + 0.5) * tile_size;
+                    if let Some(damage) = explosion.damage_at(local_center, tile_center) {
+                        destructible.health -= damage.amount;
+                    }
+                }
+            }
+        }
+
+        if let Some((sound, volume)) = &explosion.sound {
+            audio_events.play_with_priority(
+                sound.clone(),
+                *volume,
+                Some(center),
+                SoundPriority::High,
+            );
+        }
+        camera_trauma.add_trauma(core_meta.camera.explosion_trauma);
+        exploded.push(ent);
+    }
+
+    for ent in exploded {
+        entities.kill(ent);
+    }
+}
+
+/// System that removes the destructible tiles that have run out of health from the map, breaking
+/// them into debris.
+///
+/// The colliders of the removed tiles are cleaned up by the next physics update, like those of any
+/// other despawned entity, so the rest of the collision world is left untouched.
+fn break_destructible_tiles(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    destructibles: Comp<Destructible>,
+    tiles: Comp<Tile>,
+    mut tile_layers: CompMut<TileLayer>,
+    mut transforms: CompMut<Transform>,
+    mut sprites: CompMut<AtlasSprite>,
+    mut bodies: CompMut<KinematicBody>,
+    mut lifetimes: CompMut<Lifetime>,
+    mut audio_events: ResMut<AudioEvents>,
+) {
+    let meta = &core_meta.destructible;
+    if !entities
+        .iter_with(&destructibles)
+        .any(|(_, destructible)| destructible.health <= 0.0)
+    {
+        return;
+    }
+
+    let layer_ents = entities
+        .iter_with(&tile_layers)
+        .map(|(ent, _)| ent)
+        .collect::<Vec<_>>();
+    for layer_ent in layer_ents {
+        let layer = tile_layers.get_mut(layer_ent).unwrap();
+        let layer_translation = transforms.get(layer_ent).unwrap().translation;
+
+        for y in 0..layer.grid_size.y {
+            for x in 0..layer.grid_size.x {
+                let pos = uvec2(x, y);
+                let Some(tile_ent) = layer.get(pos) else {
+                    continue;
+                };
+                if !destructibles
+                    .get(tile_ent)
+                    .map_or(false, |destructible| destructible.health <= 0.0)
+                {
+                    continue;
+                }
+
+                let tile_center =
+                    layer_translation + ((pos.as_vec2() + 0.5) * layer.tile_size).extend(0.1);
+                layer.set(pos, None);
+                let tile_idx = tiles.get(tile_ent).map_or(0, |tile| tile.idx);
+                entities.kill(tile_ent);
+
+                audio_events.play_at(
+                    meta.break_sound.clone(),
+                    meta.break_sound_volume,
+                    tile_center.truncate(),
+                );
+
+                // Scatter pieces of the tile upwards
+                for i in 0..meta.debris_count {
+                    let angle = std::f32::consts::PI * (i as f32 + 0.5) / meta.debris_count as f32;
+                    let debris_ent = entities.create();
+                    transforms.insert(
+                        debris_ent,
+                        Transform {
+                            translation: tile_center,
+                            scale: Vec3::splat(meta.debris_scale),
+                            ..default()
+                        },
+                    );
+                    sprites.insert(
+                        debris_ent,
+                        AtlasSprite {
+                            atlas: layer.atlas.clone(),
+                            index: tile_idx,
+                            ..default()
+                        },
+                    );
+                    bodies.insert(
+                        debris_ent,
+                        KinematicBody {
+                            shape: ColliderShape::Rectangle {
+                                size: layer.tile_size * meta.debris_scale,
+                            },
+                            velocity: Vec2::from_angle(angle) * meta.debris_speed,
+                            gravity: core_meta.physics.gravity,
+                            has_mass: true,
+                            has_friction: true,
+                            can_rotate: true,
+                            ..default()
+                        },
+                    );
+                    lifetimes.insert(debris_ent, Lifetime::new(meta.debris_lifetime));
+                }
+            }
+        }
+    }
+}
+
 /// System that will eliminate players that are intersecting with a damage region.
 fn kill_players_in_damage_region(
     entities: Res<Entities>,
@@ -40,6 +306,7 @@ fn kill_players_in_damage_region(
     transforms: Comp<Transform>,
     damage_regions: Comp<DamageRegion>,
     damage_region_owners: Comp<DamageRegionOwner>,
+    damage_region_weapons: Comp<DamageRegionWeapon>,
     bodies: Comp<KinematicBody>,
     mut player_events: ResMut<PlayerEvents>,
 ) {
@@ -59,8 +326,242 @@ fn kill_players_in_damage_region(
 
             let damage_rect = damage_region.collider_rect(transform.translation);
             if player_rect.overlaps(&damage_rect) {
-                player_events.kill(player_ent, Some(transform.translation.xy()));
+                player_events.kill_by(
+                    player_ent,
+                    Some(transform.translation.xy()),
+                    owner.map(|owner| owner.0),
+                    damage_region_weapons.get(ent).map(|weapon| weapon.0),
+                );
             }
         }
     }
 }
+
+/// System that hurts players that are touching a hazard.
+///
+/// Players that are already in hitstun aren't hit again until it wears off, so that standing in a
+/// hazard doesn't deal damage every frame.
+fn damage_players_in_hazards(
+    entities: Res<Entities>,
+    player_indexes: Comp<PlayerIdx>,
+    transforms: Comp<Transform>,
+    hazards: Comp<Hazard>,
+    hitstuns: Comp<states::hitstun::Hitstun>,
+    hits_received: Comp<HitReceived>,
+    grabbeds: Comp<states::grabbed::Grabbed>,
+    bodies: Comp<KinematicBody>,
+    mut player_events: ResMut<PlayerEvents>,
+) {
+    for (player_ent, (_idx, player_transform, body)) in
+        entities.iter_with((&player_indexes, &transforms, &bodies))
+    {
+        let player_rect = body.bounding_box(*player_transform);
+        let player_pos = player_transform.translation.truncate();
+        let hazard = entities
+            .iter_with((&hazards, &transforms))
+            .map(|(_ent, (hazard, transform))| {
+                (hazard, hazard.collider_rect(transform.translation))
+            })
+            .find(|(_, rect)| player_rect.overlaps(rect))
+            .map(|(hazard, _)| hazard);
+        let Some(hazard) = hazard else {
+            continue;
+        };
+
+        // Players held over a hazard are hurt by whoever is holding them
+        let grabber = grabbeds.get(player_ent).map(|x| x.grabber);
+
+        if hazard.instant_kill {
+            player_events.kill_by(
+                player_ent,
+                Some(player_pos - hazard.direction.normalize_or_zero()),
+                grabber,
+                None,
+            );
+        } else if !hitstuns.contains(player_ent) && !hits_received.contains(player_ent) {
+            player_events.damage(
+                player_ent,
+                Damage {
+                    amount: hazard.damage,
+                    source: grabber,
+                    direction: hazard.direction,
+                    knockback: 1.0,
+                    weapon: None,
+                    kind: HitKind::Hazard,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn explosion_damage_falls_off_with_distance() {
+        let explosion = Explosion {
+            radius: 100.0,
+            damage: 80.0,
+            knockback: 2.0,
+            ..default()
+        };
+        let center = vec2(50.0, 50.0);
+
+        let near = explosion.damage_at(center, vec2(75.0, 50.0)).unwrap();
+        let far = explosion.damage_at(center, vec2(50.0, -25.0)).unwrap();
+        assert_eq!(near.amount, 60.0);
+        assert_eq!(near.knockback, 1.5);
+        assert_eq!(far.amount, 20.0);
+        assert_eq!(far.knockback, 0.5);
+        assert!(explosion.damage_at(center, vec2(200.0, 50.0)).is_none());
+
+        // Each player is knocked away from the center
+        assert_eq!(near.direction, Vec2::X);
+        assert_eq!(far.direction, Vec2::NEG_Y);
+        assert_eq!(
+            explosion.damage_at(center, center).unwrap().direction,
+            Vec2::Y
+        );
+    }
+
+    #[test]
+    fn hazard_kills_are_not_credited_to_anyone() {
+        let mut test = SystemTest::new(
+            |world| {
+                world.init_resource::<PlayerEvents>();
+            },
+            |stages| {
+                stages.add_system_to_stage(CoreStage::PostUpdate, damage_players_in_hazards);
+            },
+        );
+
+        let player = test
+            .world
+            .run_initialized_system(
+                |mut entities: ResMut<Entities>,
+                 mut player_indexes: CompMut<PlayerIdx>,
+                 mut transforms: CompMut<Transform>,
+                 mut bodies: CompMut<KinematicBody>,
+                 mut hazards: CompMut<Hazard>| {
+                    let player = entities.create();
+                    player_indexes.insert(player, PlayerIdx(0));
+                    transforms.insert(player, Transform::from_translation(vec3(10.0, 0.0, 0.0)));
+                    bodies.insert(
+                        player,
+                        KinematicBody {
+                            shape: ColliderShape::Rectangle {
+                                size: vec2(10.0, 10.0),
+                            },
+                            ..default()
+                        },
+                    );
+
+                    let lava = entities.create();
+                    transforms.insert(lava, default());
+                    hazards.insert(
+                        lava,
+                        Hazard {
+                            size: vec2(20.0, 20.0),
+                            instant_kill: true,
+                            direction: Vec2::Y,
+                            ..default()
+                        },
+                    );
+                    Ok(player)
+                },
+            )
+            .unwrap();
+
+        test.run();
+
+        let player_events = test.world.resource::<PlayerEvents>();
+        let player_events = player_events.borrow();
+        let Some(PlayerEvent::Kill { player: victim, hit_from, killer, .. }) =
+            player_events.queue.front() else {
+            panic!("Player wasn't killed by the hazard");
+        };
+        assert_eq!(*victim, player);
+        assert_eq!(*killer, None);
+        // Lava knocks the player upwards, so the hit comes from below
+        assert_eq!(*hit_from, Some(vec2(10.0, -1.0)));
+    }
+
+    #[test]
+    fn explosion_removes_exactly_the_tiles_within_its_radius() {
+        const TILE_SIZE: f32 = 16.0;
+        const RADIUS: f32 = 36.0;
+
+        let mut test = SystemTest::new(
+            |world| {
+                world.init_resource::<CoreMetaArc>();
+                world.init_resource::<PlayerEvents>();
+                world.init_resource::<AudioEvents>();
+                world.init_resource::<CameraTrauma>();
+            },
+            |stages| {
+                stages
+                    .add_system_to_stage(CoreStage::PostUpdate, apply_explosions)
+                    .add_system_to_stage(CoreStage::PostUpdate, break_destructible_tiles);
+            },
+        );
+
+        // A 10x10 grid of destructible tiles, with an explosion at the middle of it
+        let center = Vec2::splat(5.0 * TILE_SIZE);
+        test.world
+            .run_initialized_system(
+                move |mut entities: ResMut<Entities>,
+                      mut tile_layers: CompMut<TileLayer>,
+                      mut tiles: CompMut<Tile>,
+                      mut destructibles: CompMut<Destructible>,
+                      mut transforms: CompMut<Transform>,
+                      mut explosions: CompMut<Explosion>| {
+                    let mut layer =
+                        TileLayer::new(UVec2::splat(10), Vec2::splat(TILE_SIZE), default());
+                    for x in 0..10 {
+                        for y in 0..10 {
+                            let tile = entities.create();
+                            tiles.insert(tile, default());
+                            destructibles.insert(tile, Destructible { health: 1.0 });
+                            layer.set(uvec2(x, y), Some(tile));
+                        }
+                    }
+                    let layer_ent = entities.create();
+                    tile_layers.insert(layer_ent, layer);
+                    transforms.insert(layer_ent, default());
+
+                    let explosion = entities.create();
+                    transforms.insert(explosion, Transform::from_translation(center.extend(0.0)));
+                    explosions.insert(
+                        explosion,
+                        Explosion {
+                            radius: RADIUS,
+                            damage: 1000.0,
+                            ..default()
+                        },
+                    );
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+        test.run();
+
+        test.world
+            .run_initialized_system(move |tile_layers: Comp<TileLayer>| {
+                let layer = tile_layers.iter().next().unwrap();
+                for x in 0..10 {
+                    for y in 0..10 {
+                        let pos = uvec2(x, y);
+                        let tile_center = (pos.as_vec2() + 0.5) * TILE_SIZE;
+                        let in_radius = tile_center.distance(center) < RADIUS;
+                        assert_eq!(layer.get(pos).is_none(), in_radius, "tile at {pos}");
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+    }
+}
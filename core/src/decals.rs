@@ -0,0 +1,220 @@
+//! Splatter decals left on the map where players are hit and killed.
+//!
+//! Decals are pushed onto the [`DecalEvents`] queue, and spawned at the end of the frame as
+//! entities in the world, so a restart clears them along with everything else. There is a limit to
+//! how many decals there may be at once, and once it is reached the oldest decal is moved to the
+//! new one's spot, instead of spawning another.
+//!
+//! The look of each decal is picked with a random number generator seeded from the hit, rather than
+//! the [`GlobalRng`][crate::random::GlobalRng], so that turning the decals off doesn't change how
+//! the rest of the match plays out.
+
+use std::collections::VecDeque;
+
+use crate::{prelude::*, random::GlobalRng};
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<DecalEvents>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, update_decals)
+        .add_system_to_stage(CoreStage::Last, spawn_decals);
+}
+
+/// How far behind the entity that was hit a decal is drawn, so that it is under the players.
+pub const DECAL_Z_OFFSET: f32 = -1.0;
+
+/// A request to spawn a decal.
+#[derive(Clone, Copy, Debug)]
+pub struct DecalRequest {
+    /// The position of the entity that was hit.
+    pub position: Vec3,
+    /// The direction that the hit came in, which the decal is splattered towards.
+    pub direction: Vec2,
+}
+
+/// Resource containing the decals that will be spawned at the end of the frame.
+#[derive(Clone, TypeUlid, Debug, Default)]
+#[ulid = "01GWV5KVQ6TS9RZ7S8P3YHZ4CN"]
+pub struct DecalEvents {
+    pub queue: VecDeque<DecalRequest>,
+}
+
+impl DecalEvents {
+    /// Splatter a decal around `position`, towards `direction`.
+    pub fn splatter(&mut self, position: Vec3, direction: Vec2) {
+        self.queue.push_back(DecalRequest {
+            position,
+            direction,
+        });
+    }
+}
+
+/// Component for a decal, which fades away as it ages.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GWV5KVQ7AKQ2V4J6NMZ8H0B3"]
+pub struct Decal {
+    /// The number of frames that the decal has been on the map.
+    pub age: u32,
+    /// The number of frames that the decal takes to fade away.
+    pub lifetime: u32,
+}
+
+impl Decal {
+    /// How far the decal has faded, from `0.0` when it is splattered to `1.0` when it is gone.
+    pub fn progress(&self) -> f32 {
+        self.age as f32 / self.lifetime.max(1) as f32
+    }
+}
+
+/// Fade the decals out as they age, and remove the ones that have faded away.
+fn update_decals(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    mut decals: CompMut<Decal>,
+    mut sprites: CompMut<AtlasSprite>,
+) {
+    let alpha = core_meta.decals.color.0[3];
+    let mut faded_decals = Vec::new();
+    for (decal_ent, (decal, sprite)) in entities.iter_with((&mut decals, &mut sprites)) {
+        decal.age += 1;
+        if decal.age >= decal.lifetime {
+            faded_decals.push(decal_ent);
+            continue;
+        }
+        sprite.color.set_a(alpha * (1.0 - decal.progress()));
+    }
+
+    for decal_ent in faded_decals {
+        entities.kill(decal_ent);
+    }
+}
+
+/// Spawn the decals in the [`DecalEvents`] queue, reusing the oldest decals once the limit is
+/// reached.
+fn spawn_decals(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    mut decal_events: ResMut<DecalEvents>,
+    mut decals: CompMut<Decal>,
+    mut transforms: CompMut<Transform>,
+    mut sprites: CompMut<AtlasSprite>,
+) {
+    let meta = &core_meta.decals;
+    let [r, g, b, a] = meta.color.0;
+    if !meta.enabled || meta.max_decals == 0 {
+        decal_events.queue.clear();
+        return;
+    }
+
+    while let Some(DecalRequest {
+        position,
+        direction,
+    }) = decal_events.queue.pop_front()
+    {
+        // Seed the look of the decal from where it was splattered
+        let seed = [position.x, position.y, direction.x, direction.y]
+            .into_iter()
+            .fold(0u64, |seed, x| seed.rotate_left(16) ^ x.to_bits() as u64);
+        let rng = GlobalRng::with_seed(seed);
+        let offset = direction.normalize_or_zero() * meta.scatter * rng.f32()
+            + Vec2::new(rng.f32_normalized(), rng.f32_normalized()) * meta.scatter / 2.0;
+
+        let decal_ent = if entities.iter_with(&decals).count() < meta.max_decals {
+            entities.create()
+        } else {
+            let Some((oldest, _)) = entities
+                .iter_with(&decals)
+                .max_by_key(|(_ent, decal)| decal.age)
+            else {
+                continue;
+            };
+            oldest
+        };
+
+        decals.insert(
+            decal_ent,
+            Decal {
+                age: 0,
+                lifetime: meta.lifetime_frames,
+            },
+        );
+        transforms.insert(
+            decal_ent,
+            Transform {
+                translation: (position.truncate() + offset).extend(position.z + DECAL_Z_OFFSET),
+                rotation: Quat::from_rotation_z(rng.f32() * std::f32::consts::TAU),
+                scale: Vec3::splat(meta.scale * (0.75 + rng.f32() * 0.5)),
+            },
+        );
+        sprites.insert(
+            decal_ent,
+            AtlasSprite {
+                atlas: meta.atlas.clone(),
+                index: rng.usize(..meta.frames.max(1)),
+                color: Color::rgba(r, g, b, a),
+                flip_x: rng.bool(),
+                ..default()
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn decals_are_capped_and_deterministic() {
+        let run = |enabled: bool| {
+            let mut core_meta = CoreMeta::default();
+            core_meta.decals.enabled = enabled;
+            core_meta.decals.max_decals = 5;
+            core_meta.decals.lifetime_frames = 100;
+            let mut test = SystemTest::new(
+                |world| {
+                    world.insert_resource(CoreMetaArc(Arc::new(core_meta)));
+                    world.init_resource::<DecalEvents>();
+                },
+                |stages| {
+                    stages
+                        .add_system_to_stage(CoreStage::Last, update_decals)
+                        .add_system_to_stage(CoreStage::Last, spawn_decals);
+                },
+            );
+
+            for i in 0..8 {
+                test.world
+                    .resource::<DecalEvents>()
+                    .borrow_mut()
+                    .splatter(vec3(i as f32 * 10.0, 0.0, 5.0), Vec2::X);
+                test.run();
+            }
+
+            test.world
+                .run_initialized_system(
+                    |entities: Res<Entities>, decals: Comp<Decal>, transforms: Comp<Transform>| {
+                        Ok(entities
+                            .iter_with((&decals, &transforms))
+                            .map(|(_ent, (decal, transform))| (decal.age, transform.translation))
+                            .collect::<Vec<_>>())
+                    },
+                )
+                .unwrap()
+        };
+
+        // The oldest decals were moved to make room for the newest ones
+        let decals = run(true);
+        assert_eq!(decals.len(), 5);
+        let mut ages = decals.iter().map(|(age, _)| *age).collect::<Vec<_>>();
+        ages.sort();
+        assert_eq!(ages, [0, 1, 2, 3, 4]);
+        assert!(decals.iter().all(|(_, position)| position.z == 4.0));
+
+        // The same hits splatter the same decals
+        assert_eq!(run(true), decals);
+        assert!(run(false).is_empty());
+    }
+}
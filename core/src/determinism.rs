@@ -0,0 +1,28 @@
+//! Helpers for keeping the simulation deterministic, so that every client in a networked game, and
+//! every replay, plays a match out the same way.
+//!
+//! [`Entities::iter_with`][bones_lib::prelude::Entities::iter_with] goes through entities in the
+//! order of their indexes. That is the same on every client that spawned and despawned the same
+//! entities, but the indexes are handed out first come first served, so the order depends on the
+//! order that the entities happened to be spawned in. Systems whose outcome depends on the order
+//! that they handle entities in, such as which of two players reaching for the same item on the
+//! same frame gets it, should go through the entities
+//! [`in_order_by_key`][DeterministicOrder::in_order_by_key] of something that identifies them
+//! regardless, like their [`PlayerIdx`][crate::player::PlayerIdx].
+
+/// Extension trait for going through the items of an iterator in a deterministic order.
+pub trait DeterministicOrder: Iterator + Sized {
+    /// Collect the items and go through them sorted by `key`.
+    ///
+    /// The sort is stable, so items with the same key keep the order that they came in.
+    fn in_order_by_key<K: Ord>(
+        self,
+        key: impl FnMut(&Self::Item) -> K,
+    ) -> std::vec::IntoIter<Self::Item> {
+        let mut items = self.collect::<Vec<_>>();
+        items.sort_by_key(key);
+        items.into_iter()
+    }
+}
+
+impl<I: Iterator> DeterministicOrder for I {}
@@ -11,6 +11,7 @@ pub mod player_spawner;
 pub mod sproinger;
 pub mod stomp_boots;
 pub mod sword;
+pub mod water;
 
 /// Marker component added to map elements that have been hydrated.
 #[derive(Clone, TypeUlid)]
@@ -34,4 +35,5 @@ pub fn install(session: &mut GameSession) {
     musket::install(session);
     stomp_boots::install(session);
     crate_item::install(session);
+    water::install(session);
 }
@@ -94,7 +94,7 @@ fn update_idle_crates(
     element_assets: BevyAssets<ElementMeta>,
     mut transforms: CompMut<Transform>,
     mut idle_crates: CompMut<IdleCrate>,
-    mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut bodies: CompMut<KinematicBody>,
     mut items_used: CompMut<ItemUsed>,
     mut items_dropped: CompMut<ItemDropped>,
@@ -137,6 +137,7 @@ fn update_idle_crates(
                     player,
                     offset: grab_offset.extend(0.1),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
 
@@ -146,18 +147,18 @@ fn update_idle_crates(
                 attachments.remove(entity);
 
                 let player_velocity = bodies.get(player).unwrap().velocity;
-                let player_sprite = sprites.get_mut(player).unwrap();
+                let facing_left = facings.get(player).map_or(false, |x| x.left);
                 let player_translation = transforms.get(player).unwrap().translation;
 
                 let body = bodies.get_mut(entity).unwrap();
 
-                let horizontal_flip_factor = if player_sprite.flip_x {
+                let horizontal_flip_factor = if facing_left {
                     Vec2::new(-1.0, 1.0)
                 } else {
                     Vec2::ONE
                 };
 
-                body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
+                body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
                 body.is_deactivated = false;
 
                 let transform = transforms.get_mut(entity).unwrap();
@@ -189,7 +190,7 @@ fn update_idle_crates(
             attachments.remove(entity);
 
             let player_translation = transforms.get(player).unwrap().translation;
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
@@ -197,7 +198,7 @@ fn update_idle_crates(
             body.is_deactivated = false;
             body.is_spawning = true;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
@@ -216,6 +217,7 @@ fn update_idle_crates(
 }
 
 fn update_thrown_crates(
+    simulation_rate: Res<SimulationRate>,
     entities: Res<Entities>,
     mut hydrated: CompMut<MapElementHydrated>,
     element_assets: BevyAssets<ElementMeta>,
@@ -259,7 +261,7 @@ fn update_thrown_crates(
             continue;
         };
 
-        thrown_crate.age += 1.0 / crate::FPS;
+        thrown_crate.age += simulation_rate.frame_time();
 
         let colliding_with_tile = {
             let collider = collision_world.get_collider(entity);
@@ -277,7 +279,11 @@ fn update_thrown_crates(
         if colliding_with_tile && !thrown_crate.was_colliding {
             thrown_crate.was_colliding = true;
             thrown_crate.crate_break_state += 1;
-            audio_events.play(bounce_sound.clone(), *bounce_sound_volume);
+            audio_events.play_at(
+                bounce_sound.clone(),
+                *bounce_sound_volume,
+                transform.translation.truncate(),
+            );
         } else if !colliding_with_tile {
             thrown_crate.was_colliding = false;
         }
@@ -300,7 +306,12 @@ fn update_thrown_crates(
             .collect::<Vec<_>>();
 
         for player_entity in &colliding_with_players {
-            player_events.kill(*player_entity, Some(transform.translation.xy()));
+            player_events.kill_by(
+                *player_entity,
+                Some(transform.translation.xy()),
+                Some(thrown_crate.owner),
+                Some(key!("crate")),
+            );
         }
 
         if !colliding_with_players.is_empty()
@@ -314,7 +325,11 @@ fn update_thrown_crates(
             let breaking_anim_fps = *breaking_anim_fps;
             let atlas = breaking_atlas.clone();
 
-            audio_events.play(break_sound.clone(), *break_sound_volume);
+            audio_events.play_at(
+                break_sound.clone(),
+                *break_sound_volume,
+                transform.translation.truncate(),
+            );
             commands.add(
                 move |mut entities: ResMut<Entities>,
                       mut transforms: CompMut<Transform>,
@@ -22,6 +22,8 @@ pub struct LitGrenade {
     pub spawner: Entity,
     /// How long the grenade has been lit.
     pub age: f32,
+    /// The player that lit the grenade.
+    pub owner: Option<Entity>,
 }
 
 fn hydrate(
@@ -102,7 +104,7 @@ fn update_idle_grenades(
     mut audio_events: ResMut<AudioEvents>,
     mut transforms: CompMut<Transform>,
     mut idle_grenades: CompMut<IdleGrenade>,
-    mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut animated_sprites: CompMut<AnimatedSprite>,
     mut bodies: CompMut<KinematicBody>,
     mut items_used: CompMut<ItemUsed>,
@@ -152,6 +154,7 @@ fn update_idle_grenades(
                     player,
                     offset: grab_offset.extend(0.1),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
 
@@ -168,7 +171,14 @@ fn update_idle_grenades(
                 commands.add(
                     move |mut idle: CompMut<IdleGrenade>, mut lit: CompMut<LitGrenade>| {
                         idle.remove(entity);
-                        lit.insert(entity, LitGrenade { spawner, age: 0.0 });
+                        lit.insert(
+                            entity,
+                            LitGrenade {
+                                spawner,
+                                age: 0.0,
+                                owner: Some(player),
+                            },
+                        );
                     },
                 );
             }
@@ -184,19 +194,18 @@ fn update_idle_grenades(
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
-            body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
-            body.angular_velocity =
-                *angular_velocity * if player_sprite.flip_x { -1.0 } else { 1.0 };
+            body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
+            body.angular_velocity = *angular_velocity * if facing_left { -1.0 } else { 1.0 };
 
             body.is_spawning = true;
 
@@ -208,13 +217,13 @@ fn update_idle_grenades(
 }
 
 fn update_lit_grenades(
+    simulation_rate: Res<SimulationRate>,
     entities: Res<Entities>,
     element_handles: Comp<ElementHandle>,
     element_assets: BevyAssets<ElementMeta>,
-    mut audio_events: ResMut<AudioEvents>,
     mut transforms: CompMut<Transform>,
     mut lit_grenades: CompMut<LitGrenade>,
-    mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut bodies: CompMut<KinematicBody>,
     mut items_dropped: CompMut<ItemDropped>,
     mut hydrated: CompMut<MapElementHydrated>,
@@ -238,8 +247,9 @@ fn update_lit_grenades(
             explosion_volume,
             throw_velocity,
             fuse_time,
-            damage_region_lifetime,
-            damage_region_size,
+            explosion_radius,
+            explosion_damage,
+            explosion_knockback,
             explosion_lifetime,
             explosion_atlas,
             explosion_fps,
@@ -250,7 +260,7 @@ fn update_lit_grenades(
             unreachable!();
         };
 
-        grenade.age += 1.0 / crate::FPS;
+        grenade.age += simulation_rate.frame_time();
         let spawner = grenade.spawner;
 
         if !emote_regions.contains(entity) {
@@ -258,7 +268,7 @@ fn update_lit_grenades(
                 entity,
                 EmoteRegion {
                     direction_sensitive: true,
-                    size: *damage_region_size * 2.0,
+                    size: Vec2::splat(*explosion_radius * 2.0),
                     emote: Emote::Alarm,
                     active: true,
                 },
@@ -286,6 +296,7 @@ fn update_lit_grenades(
                     player,
                     offset: grab_offset.extend(1.0),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
 
@@ -306,19 +317,18 @@ fn update_lit_grenades(
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
-            body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
-            body.angular_velocity =
-                *angular_velocity * if player_sprite.flip_x { -1.0 } else { 1.0 };
+            body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
+            body.angular_velocity = *angular_velocity * if facing_left { -1.0 } else { 1.0 };
 
             body.is_spawning = true;
 
@@ -329,16 +339,20 @@ fn update_lit_grenades(
 
         // If it's time to explode
         if grenade.age >= *fuse_time {
-            audio_events.play(explosion_sound.clone(), *explosion_volume);
-
             // Cause the item to respawn by un-hydrating it's spawner.
             hydrated.remove(spawner);
             let mut explosion_transform = *transforms.get(entity).unwrap();
             explosion_transform.translation.z += 1.0;
 
             // Clone types for move into closure
-            let damage_region_size = *damage_region_size;
-            let damage_region_lifetime = *damage_region_lifetime;
+            let explosion = Explosion {
+                radius: *explosion_radius,
+                damage: *explosion_damage,
+                knockback: *explosion_knockback,
+                owner: grenade.owner,
+                weapon: Some(key!("grenade")),
+                sound: Some((explosion_sound.clone(), *explosion_volume)),
+            };
             let explosion_lifetime = *explosion_lifetime;
             let explosion_atlas = explosion_atlas.clone();
             let explosion_fps = *explosion_fps;
@@ -346,23 +360,17 @@ fn update_lit_grenades(
             commands.add(
                 move |mut entities: ResMut<Entities>,
                       mut transforms: CompMut<Transform>,
-                      mut damage_regions: CompMut<DamageRegion>,
+                      mut explosions: CompMut<Explosion>,
                       mut lifetimes: CompMut<Lifetime>,
                       mut sprites: CompMut<AtlasSprite>,
                       mut animated_sprites: CompMut<AnimatedSprite>| {
                     // Despawn the grenade
                     entities.kill(entity);
 
-                    // Spawn the damage region
+                    // Spawn the explosion
                     let ent = entities.create();
                     transforms.insert(ent, explosion_transform);
-                    damage_regions.insert(
-                        ent,
-                        DamageRegion {
-                            size: damage_region_size,
-                        },
-                    );
-                    lifetimes.insert(ent, Lifetime::new(damage_region_lifetime));
+                    explosions.insert(ent, explosion);
 
                     // Spawn the explosion animation
                     let ent = entities.create();
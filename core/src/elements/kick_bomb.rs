@@ -95,7 +95,7 @@ fn hydrate(
 fn update_idle_kick_bombs(
     entities: Res<Entities>,
     mut commands: Commands,
-    mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     player_inventories: PlayerInventories,
     mut bodies: CompMut<KinematicBody>,
     mut items_used: CompMut<ItemUsed>,
@@ -149,6 +149,7 @@ fn update_idle_kick_bombs(
                     player,
                     offset: grab_offset.extend(1.0),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
 
@@ -181,19 +182,18 @@ fn update_idle_kick_bombs(
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
-            body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
-            body.angular_velocity =
-                *angular_velocity * if player_sprite.flip_x { -1.0 } else { 1.0 };
+            body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
+            body.angular_velocity = *angular_velocity * if facing_left { -1.0 } else { 1.0 };
 
             body.is_spawning = true;
 
@@ -205,6 +205,7 @@ fn update_idle_kick_bombs(
 }
 
 fn update_lit_kick_bombs(
+    simulation_rate: Res<SimulationRate>,
     entities: Res<Entities>,
     element_handles: Comp<ElementHandle>,
     element_assets: BevyAssets<ElementMeta>,
@@ -213,7 +214,7 @@ fn update_lit_kick_bombs(
     player_indexes: Comp<PlayerIdx>,
     mut audio_events: ResMut<AudioEvents>,
     mut lit_grenades: CompMut<LitKickBomb>,
-    mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut bodies: CompMut<KinematicBody>,
     mut items_dropped: CompMut<ItemDropped>,
     mut hydrated: CompMut<MapElementHydrated>,
@@ -250,7 +251,7 @@ fn update_lit_kick_bombs(
             unreachable!();
         };
 
-        kick_bomb.age += 1.0 / crate::FPS;
+        kick_bomb.age += simulation_rate.frame_time();
         let spawner = kick_bomb.spawner;
 
         let mut should_explode = false;
@@ -273,6 +274,7 @@ fn update_lit_kick_bombs(
                     player,
                     offset: grab_offset.extend(1.0),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
         }
@@ -285,20 +287,20 @@ fn update_lit_kick_bombs(
             let body = bodies.get_mut(entity).unwrap();
             let translation = transforms.get_mut(entity).unwrap().translation;
 
-            let player_sprite = sprites.get_mut(player_entity).unwrap();
+            let facing_left = facings.get(player_entity).map_or(false, |x| x.left);
             let player_translation = transforms.get(player_entity).unwrap().translation;
 
             let player_standing_left = player_translation.x <= translation.x;
 
             if body.velocity.x == 0.0 {
                 body.velocity = *throw_velocity;
-                if player_sprite.flip_x {
+                if facing_left {
                     body.velocity.x *= -1.0;
                 }
-            } else if player_standing_left && !player_sprite.flip_x {
+            } else if player_standing_left && !facing_left {
                 body.velocity.x = throw_velocity.x;
                 body.velocity.y = throw_velocity.y;
-            } else if !player_standing_left && player_sprite.flip_x {
+            } else if !player_standing_left && facing_left {
                 body.velocity.x = -throw_velocity.x;
                 body.velocity.y = throw_velocity.y;
             } else if kick_bomb.age >= *arm_delay {
@@ -316,19 +318,18 @@ fn update_lit_kick_bombs(
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
-            body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
-            body.angular_velocity =
-                *angular_velocity * if player_sprite.flip_x { -1.0 } else { 1.0 };
+            body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
+            body.angular_velocity = *angular_velocity * if facing_left { -1.0 } else { 1.0 };
 
             body.is_spawning = true;
 
@@ -339,13 +340,18 @@ fn update_lit_kick_bombs(
 
         // If it's time to explode
         if kick_bomb.age >= *fuse_time || should_explode {
-            audio_events.play(explosion_sound.clone(), *explosion_volume);
-
             // Cause the item to respawn by un-hydrating it's spawner.
             hydrated.remove(spawner);
             let mut explosion_transform = *transforms.get(entity).unwrap();
             explosion_transform.translation.z += 1.0;
 
+            audio_events.play_with_priority(
+                explosion_sound.clone(),
+                *explosion_volume,
+                Some(explosion_transform.translation.truncate()),
+                SoundPriority::High,
+            );
+
             // Clone types for move into closure
             let damage_region_size = *damage_region_size;
             let damage_region_lifetime = *damage_region_lifetime;
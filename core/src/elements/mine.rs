@@ -97,7 +97,7 @@ fn update_idle_mines(
     element_assets: BevyAssets<ElementMeta>,
     mut transforms: CompMut<Transform>,
     mut idle_mines: CompMut<IdleMine>,
-    mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut bodies: CompMut<KinematicBody>,
     mut items_used: CompMut<ItemUsed>,
     mut items_dropped: CompMut<ItemDropped>,
@@ -143,6 +143,7 @@ fn update_idle_mines(
                     player,
                     offset: grab_offset.extend(0.1),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
 
@@ -153,18 +154,18 @@ fn update_idle_mines(
                 attachments.remove(entity);
 
                 let player_velocity = bodies.get(player).unwrap().velocity;
-                let player_sprite = sprites.get_mut(player).unwrap();
+                let facing_left = facings.get(player).map_or(false, |x| x.left);
                 let player_translation = transforms.get(player).unwrap().translation;
 
                 let body = bodies.get_mut(entity).unwrap();
 
-                let horizontal_flip_factor = if player_sprite.flip_x {
+                let horizontal_flip_factor = if facing_left {
                     Vec2::new(-1.0, 1.0)
                 } else {
                     Vec2::ONE
                 };
 
-                body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
+                body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
                 body.is_deactivated = false;
 
                 let transform = transforms.get_mut(entity).unwrap();
@@ -189,21 +190,21 @@ fn update_idle_mines(
 
             let player_translation = transforms.get(dropped.player).unwrap().translation;
             let player_velocity = bodies.get(player).unwrap().velocity;
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
 
             let body = bodies.get_mut(entity).unwrap();
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
 
             if player_velocity != Vec2::ZERO {
-                body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
+                body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
             }
 
             body.is_spawning = true;
@@ -216,6 +217,7 @@ fn update_idle_mines(
 }
 
 fn update_thrown_mines(
+    simulation_rate: Res<SimulationRate>,
     entities: Res<Entities>,
     element_handles: Comp<ElementHandle>,
     element_assets: BevyAssets<ElementMeta>,
@@ -254,8 +256,8 @@ fn update_thrown_mines(
             unreachable!();
         };
 
-        let frame_time = 1.0 / crate::FPS;
-        thrown_mine.age += 1.0 / crate::FPS;
+        let frame_time = simulation_rate.frame_time();
+        thrown_mine.age += frame_time;
 
         if thrown_mine.age >= *arm_delay && thrown_mine.age - *arm_delay < frame_time {
             audio_events.play(arm_sound.clone(), *arm_sound_volume);
@@ -275,10 +277,20 @@ fn update_thrown_mines(
             let mine_transform = *transforms.get(entity).unwrap();
 
             for player in &colliding_with_players {
-                player_events.kill(*player, Some(mine_transform.translation.xy()));
+                player_events.kill_by(
+                    *player,
+                    Some(mine_transform.translation.xy()),
+                    None,
+                    Some(key!("mine")),
+                );
             }
 
-            audio_events.play(explosion_sound.clone(), *explosion_volume);
+            audio_events.play_with_priority(
+                explosion_sound.clone(),
+                *explosion_volume,
+                Some(mine_transform.translation.truncate()),
+                SoundPriority::High,
+            );
 
             hydrated.remove(thrown_mine.spawner);
 
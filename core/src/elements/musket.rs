@@ -10,8 +10,11 @@ pub fn install(session: &mut GameSession) {
 #[derive(Clone, Debug, TypeUlid, Default)]
 #[ulid = "01GQWRRV9HV52X9JAYYF1AFFS7"]
 pub struct Musket {
-    pub ammo: usize,
     pub cooldown_frame: usize,
+    /// Whether the musket is being charged up, for muskets that can be.
+    pub charging: bool,
+    /// The number of frames that the musket has been charged for.
+    pub charge_frames: u32,
 }
 
 fn hydrate(
@@ -21,6 +24,7 @@ fn hydrate(
     mut element_handles: CompMut<ElementHandle>,
     element_assets: BevyAssets<ElementMeta>,
     mut muskets: CompMut<Musket>,
+    mut ammos: CompMut<Ammo>,
     mut atlas_sprites: CompMut<AtlasSprite>,
     mut bodies: CompMut<KinematicBody>,
     mut transforms: CompMut<Transform>,
@@ -55,13 +59,8 @@ fn hydrate(
 
             let entity = entities.create();
             items.insert(entity, Item);
-            muskets.insert(
-                entity,
-                Musket {
-                    ammo: *max_ammo,
-                    cooldown_frame: 0,
-                },
-            );
+            muskets.insert(entity, Musket::default());
+            ammos.insert(entity, Ammo::full(*max_ammo));
             atlas_sprites.insert(entity, AtlasSprite::new(atlas.clone()));
             respawn_points.insert(entity, MapRespawnPoint(transform.translation));
             transforms.insert(entity, transform);
@@ -88,20 +87,28 @@ fn update(
     mut commands: Commands,
     element_handles: Comp<ElementHandle>,
     element_assets: BevyAssets<ElementMeta>,
+    bullet_assets: BevyAssets<BulletMeta>,
 
     mut muskets: CompMut<Musket>,
+    mut ammos: CompMut<Ammo>,
     mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut bodies: CompMut<KinematicBody>,
     mut transforms: CompMut<Transform>,
     mut audio_events: ResMut<AudioEvents>,
     mut player_layers: CompMut<PlayerLayers>,
 
     player_inventories: PlayerInventories,
+    player_indexes: Comp<PlayerIdx>,
+    player_inputs: Res<PlayerInputs>,
     mut items_used: CompMut<ItemUsed>,
     mut attachments: CompMut<PlayerBodyAttachment>,
     mut items_dropped: CompMut<ItemDropped>,
+    mut stats: ResMut<SessionStats>,
 ) {
-    for (entity, (musket, element_handle)) in entities.iter_with((&mut muskets, &element_handles)) {
+    for (entity, (musket, ammo, element_handle)) in
+        entities.iter_with((&mut muskets, &mut ammos, &element_handles))
+    {
         let Some(element_meta) = element_assets.get(&element_handle.get_bevy_handle()) else {
             continue;
         };
@@ -122,15 +129,18 @@ fn update(
             bullet_meta,
             shoot_sound,
             cooldown_frames,
+            reload_frames,
             empty_shoot_sound,
             shoot_sound_volume,
             empty_shoot_sound_volume,
+            charge,
             ..
         } = &element_meta.builtin else {
             unreachable!();
         };
 
         musket.cooldown_frame += 1;
+        ammo.update_reload();
 
         // If the item is being held
         if let Some(inventory) = player_inventories
@@ -139,7 +149,19 @@ fn update(
         {
             let player = inventory.player;
             let body = bodies.get_mut(entity).unwrap();
-            player_layers.get_mut(player).unwrap().fin_anim = *fin_anim;
+
+            // Turn the fin, and the musket in it, the way that the player is aiming
+            let aim_angle = player_indexes
+                .get(player)
+                .map(|idx| {
+                    let player_flip_x = facings.get(player).map_or(false, |x| x.left);
+                    let aim = player_inputs.players[idx.0].control.aim(player_flip_x);
+                    PlayerLayers::aim_angle_towards(aim)
+                })
+                .unwrap_or_default();
+            let layers = player_layers.get_mut(player).unwrap();
+            layers.fin_anim = *fin_anim;
+            layers.aim_angle = aim_angle;
 
             // Deactivate collisions while being held
             body.is_deactivated = true;
@@ -149,8 +171,9 @@ fn update(
                 entity,
                 PlayerBodyAttachment {
                     player,
-                    offset: grab_offset.extend(0.1),
+                    offset: Vec2::from_angle(aim_angle).rotate(*grab_offset).extend(0.1),
                     sync_animation: false,
+                    rotation: aim_angle,
                 },
             );
 
@@ -160,25 +183,86 @@ fn update(
             if item_used {
                 items_used.remove(entity);
             }
-            if item_used && can_fire {
-                // Empty
-                if musket.ammo.eq(&0) {
+
+            // How charged the shot being fired is, from 0 to 1, if one is being fired
+            let mut fired_charge = None;
+            if let Some(charge) = charge {
+                if item_used && can_fire && !ammo.is_reloading() {
+                    musket.charging = true;
+                }
+                if musket.charging {
+                    let control = player_indexes
+                        .get(player)
+                        .map(|idx| &player_inputs.players[idx.0].control);
+                    if let Some(control) = control.filter(|x| x.shoot_pressed) {
+                        musket.charge_frames = control.shoot_hold_frames.min(charge.frames);
+                    } else {
+                        // Fire once the shoot button is let go
+                        fired_charge =
+                            Some(musket.charge_frames as f32 / charge.frames.max(1) as f32);
+                        musket.charging = false;
+                        musket.charge_frames = 0;
+                    }
+                }
+
+                // Tint the musket as it charges up
+                let charge_level = musket.charge_frames as f32 / charge.frames.max(1) as f32;
+                let [r, g, b, a] = charge.color.0;
+                let color = Vec4::ONE.lerp(vec4(r, g, b, a), charge_level);
+                if let Some(sprite) = sprites.get_mut(entity) {
+                    sprite.color = Color::rgba(color.x, color.y, color.z, color.w);
+                }
+            } else if item_used && can_fire && !ammo.is_reloading() {
+                fired_charge = Some(0.0);
+            }
+
+            if let Some(charge_level) = fired_charge {
+                let Some(bullet) = bullet_assets.get(&bullet_meta.get_bevy_handle()) else {
+                    continue;
+                };
+
+                // Empty, so start reloading
+                if !ammo.shoot() {
                     audio_events.play(empty_shoot_sound.clone(), *empty_shoot_sound_volume);
+                    ammo.start_reload(*reload_frames);
                     continue;
                 }
 
-                // Reset fire cooldown and subtract ammo
+                if let Some(player_idx) = player_indexes.get(player) {
+                    stats.for_player_mut(player_idx.0).shots_fired += 1;
+                }
+
+                // Reset fire cooldown
                 musket.cooldown_frame = 0;
-                musket.ammo = musket.ammo.saturating_sub(1).clamp(0, musket.ammo);
-                audio_events.play(shoot_sound.clone(), *shoot_sound_volume);
+                let fully_charged = charge.as_ref().filter(|_| charge_level >= 1.0);
+                if let Some(charge) = fully_charged {
+                    audio_events.play(
+                        charge.charged_shoot_sound.clone(),
+                        charge.charged_shoot_sound_volume,
+                    );
+                } else {
+                    audio_events.play(shoot_sound.clone(), *shoot_sound_volume);
+                }
 
-                let player_sprite = sprites.get_mut(player).unwrap();
-                let player_flip_x = player_sprite.flip_x;
+                let player_flip_x = facings.get(player).map_or(false, |x| x.left);
+                let forward = if player_flip_x { Vec2::NEG_X } else { Vec2::X };
+                let aim = player_indexes
+                    .get(player)
+                    .map(|idx| player_inputs.players[idx.0].control.aim(player_flip_x))
+                    .unwrap_or(forward);
 
+                // The muzzle flash points the way the musket is aimed
                 let mut shoot_animation_transform = *transforms.get(entity).unwrap();
                 shoot_animation_transform.translation.z += 1.0;
-                shoot_animation_transform.translation.x +=
-                    if player_sprite.flip_x { -15.0 } else { 15.0 };
+                shoot_animation_transform.translation += (aim * 15.0).extend(0.0);
+                shoot_animation_transform.rotation =
+                    Quat::from_rotation_z(forward.angle_between(aim));
+
+                // Fully charged shots have a bigger muzzle flash
+                let mut flash_transform = shoot_animation_transform;
+                if let Some(charge) = fully_charged {
+                    flash_transform.scale *= charge.charged_shoot_scale;
+                }
 
                 let shoot_fps = *shoot_fps;
                 let shoot_frames = *shoot_frames;
@@ -186,19 +270,24 @@ fn update(
                 let shoot_atlas = shoot_atlas.clone();
 
                 let bullet_meta = bullet_meta.clone();
+                let mut projectile = Projectile::new(bullet, player_flip_x, aim, Some(player));
+                if let Some(charge) = charge {
+                    projectile.damage *= 1.0 + (charge.max_damage - 1.0) * charge_level;
+                    projectile.velocity *= 1.0 + (charge.max_speed - 1.0) * charge_level;
+                }
 
                 commands.add(
                     move |mut entities: ResMut<Entities>,
                           mut lifetimes: CompMut<Lifetime>,
                           mut sprites: CompMut<AtlasSprite>,
                           mut transforms: CompMut<Transform>,
-                          mut bullets: CompMut<Bullet>,
+                          mut projectiles: CompMut<Projectile>,
                           mut bullet_handles: CompMut<BulletHandle>,
                           mut animated_sprites: CompMut<AnimatedSprite>| {
                         // spawn fire animation
                         {
                             let ent = entities.create();
-                            transforms.insert(ent, shoot_animation_transform);
+                            transforms.insert(ent, flash_transform);
                             sprites.insert(
                                 ent,
                                 AtlasSprite {
@@ -223,12 +312,7 @@ fn update(
                         // spawn bullet
                         {
                             let ent = entities.create();
-                            bullets.insert(
-                                ent,
-                                Bullet {
-                                    direction: if player_flip_x { -1.0 } else { 1.0 },
-                                },
-                            );
+                            projectiles.insert(ent, projectile);
                             transforms.insert(ent, shoot_animation_transform);
                             bullet_handles.insert(ent, BulletHandle(bullet_meta.clone()));
                         }
@@ -243,29 +327,38 @@ fn update(
 
             items_dropped.remove(entity);
             attachments.remove(entity);
+            if let Some(layers) = player_layers.get_mut(player) {
+                layers.aim_angle = 0.0;
+            }
 
             // reload gun
-            musket.ammo = *max_ammo;
+            *ammo = Ammo::full(*max_ammo);
+
+            // Lose any charge, such as when the player is killed while charging a shot
+            musket.charging = false;
+            musket.charge_frames = 0;
+            if let Some(sprite) = sprites.get_mut(entity) {
+                sprite.color = Color::WHITE;
+            }
 
             let player_translation = transforms.get(dropped.player).unwrap().translation;
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
-            let player_sprite = sprites.get_mut(player).unwrap();
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if player_sprite.flip_x {
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
 
             if player_velocity != Vec2::ZERO {
-                body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
-                body.angular_velocity =
-                    *angular_velocity * if player_sprite.flip_x { -1.0 } else { 1.0 };
+                body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
+                body.angular_velocity = *angular_velocity * if facing_left { -1.0 } else { 1.0 };
             }
 
             body.is_spawning = true;
@@ -273,6 +366,8 @@ fn update(
             let transform = transforms.get_mut(entity).unwrap();
             transform.translation =
                 player_translation + (*grab_offset * horizontal_flip_factor).extend(0.0);
+            // Straighten out the musket from the way that it was aimed
+            transform.rotation = Quat::IDENTITY;
         }
     }
 }
@@ -1,10 +1,15 @@
 use crate::{prelude::*, MAX_PLAYERS};
 
 pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<RespawnDelays>();
+
     session
         .stages
         .add_system_to_stage(CoreStage::First, hydrate)
-        .add_system_to_stage(CoreStage::First, update);
+        .add_system_to_stage(CoreStage::First, update)
+        // Like the moving platforms, the respawn platforms catch their players after the physics
+        // update has pulled them down.
+        .add_system_to_stage(CoreStage::Last, update_respawn_platforms);
 }
 
 /// Marker component for player spawners.
@@ -13,10 +18,78 @@ pub fn install(session: &mut GameSession) {
 pub struct PlayerSpawner;
 
 /// Resource that stores the next spawner to use when spawning a player.
+///
+/// The spawners are only cycled through when there are no living opponents to spawn away from.
 #[derive(Clone, Debug, TypeUlid, Default)]
 #[ulid = "01GP4YVEQGVQATG3KSPC0SD37N"]
 pub struct CurrentSpawner(pub usize);
 
+/// Resource containing the frame that each player who is waiting to respawn may respawn on.
+#[derive(Clone, Debug, TypeUlid, Default)]
+#[ulid = "01GW92D80Z03CHQDRQGPATTEPX"]
+pub struct RespawnDelays {
+    pub respawn_on: [Option<u64>; MAX_PLAYERS],
+}
+
+impl RespawnDelays {
+    /// Make the player wait `delay_frames` from `frame` before respawning.
+    pub fn start(&mut self, player_idx: usize, frame: u64, delay_frames: u32) {
+        self.respawn_on[player_idx] = Some(frame + delay_frames as u64);
+    }
+
+    /// Get the number of frames that the player has left to wait on `frame`, if they are waiting to
+    /// respawn.
+    pub fn frames_remaining(&self, player_idx: usize, frame: u64) -> Option<u64> {
+        self.respawn_on[player_idx]
+            .map(|respawn_on| respawn_on.saturating_sub(frame))
+            .filter(|&remaining| remaining > 0)
+    }
+}
+
+/// Component for the platform that a player respawned on, spawned from the
+/// [`RespawnPlatformMeta`].
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GV3PFEXGBPGGFNJ3SK375BXD"]
+pub struct RespawnPlatform {
+    /// The player that respawned on the platform, who is the only one that can stand on it.
+    pub player: Entity,
+    /// The size of the platform in pixels.
+    pub size: Vec2,
+    /// The number of frames until the platform disappears, even if the player is still on it.
+    pub frames_remaining: u32,
+}
+
+impl RespawnPlatform {
+    /// Get the collision rectangle of the platform, given it's position.
+    pub fn rect(&self, position: Vec2) -> Rect {
+        Rect::new(position.x, position.y, self.size.x, self.size.y)
+    }
+}
+
+/// Get the index of the spawn point that is the farthest away from the closest of the `opponents`,
+/// to keep players from spawning right next to an enemy.
+///
+/// Returns `None` if there are no spawn points or no opponents to get away from.
+pub fn farthest_spawn_point(spawn_points: &[Vec3], opponents: &[Vec2]) -> Option<usize> {
+    if opponents.is_empty() {
+        return None;
+    }
+
+    let closest_opponent_distance = |point: &Vec3| {
+        opponents
+            .iter()
+            .map(|opponent| opponent.distance_squared(point.truncate()))
+            .fold(f32::INFINITY, f32::min)
+    };
+    spawn_points
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| {
+            closest_opponent_distance(a).total_cmp(&closest_opponent_distance(b))
+        })
+        .map(|(i, _)| i)
+}
+
 fn hydrate(
     entities: Res<Entities>,
     mut hydrated: CompMut<MapElementHydrated>,
@@ -47,35 +120,227 @@ fn update(
     player_spawners: Comp<PlayerSpawner>,
     mut player_indexes: CompMut<PlayerIdx>,
     mut transforms: CompMut<Transform>,
+    mut invulnerables: CompMut<Invulnerable>,
+    mut respawn_platforms: CompMut<RespawnPlatform>,
+    mut paths: CompMut<Path2d>,
+    killed_players: Comp<PlayerKilled>,
     player_inputs: Res<PlayerInputs>,
+    stocks: Res<Stocks>,
+    match_state: Res<MatchState>,
+    mut respawn_delays: ResMut<RespawnDelays>,
+    frame: Res<SimulationFrame>,
+    core_meta: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
 ) {
     let alive_players = entities
         .iter_with(&player_indexes)
         .map(|(_ent, pidx)| pidx.0)
         .collect::<Vec<_>>();
-    let spawn_points = entities
+    // Players in their death animation can't camp the spawn points
+    let mut living_players = entities
+        .iter_with((&player_indexes, &transforms))
+        .filter(|(ent, _)| !killed_players.contains(*ent))
+        .map(|(_ent, (pidx, transform))| (pidx.0, transform.translation.truncate()))
+        .collect::<Vec<_>>();
+    let mut spawn_points = entities
         .iter_with((&player_spawners, &transforms))
         .map(|(_ent, (_spawner, transform))| transform.translation)
         .collect::<Vec<_>>();
 
+    // Spawn players in the middle of the map if it doesn't have any spawners
+    if spawn_points.is_empty() {
+        let map_size = map.grid_size.as_vec2() * map.tile_size;
+        spawn_points.push((map_size / 2.0).extend(0.0));
+    }
+
     // For every player
     for i in 0..MAX_PLAYERS {
         let player = &player_inputs.players[i];
 
-        // If the player is active, but not alive
-        if player.active && !alive_players.contains(&i) {
-            // Increment the spawner index
-            current_spawner.0 += 1;
-            current_spawner.0 %= spawn_points.len().max(1);
+        // If the player is active, but not alive, is still in the match, and is done waiting
+        if player.active
+            && !alive_players.contains(&i)
+            && !stocks.is_eliminated(i)
+            && match_state.can_spawn(i)
+            && respawn_delays.frames_remaining(i, **frame).is_none()
+        {
+            respawn_delays.respawn_on[i] = None;
 
-            let Some(mut spawn_point) = spawn_points.get(current_spawner.0).copied() else { return };
+            // Spawn as far away from the opponents as possible, or at the next spawner if there is
+            // nobody to get away from. Teammates aren't a threat, so they may spawn close together.
+            let opponents = living_players
+                .iter()
+                .filter(|(idx, _)| player_inputs.are_opponents(*idx, i))
+                .map(|(_, pos)| *pos)
+                .collect::<Vec<_>>();
+            let spawner_idx =
+                farthest_spawn_point(&spawn_points, &opponents).unwrap_or_else(|| {
+                    current_spawner.0 += 1;
+                    current_spawner.0 %= spawn_points.len();
+                    current_spawner.0
+                });
+            let mut spawn_point = spawn_points[spawner_idx];
+            living_players.push((i, spawn_point.truncate()));
 
             // Make sure each player spawns at a different z level
             spawn_point.z += i as f32 * 0.1;
 
+            // Respawn up in the air on a platform, if the game has them
+            let platform_meta = core_meta.respawn.platform.as_ref();
+            if let Some(platform_meta) = platform_meta {
+                spawn_point.y += platform_meta.height;
+            }
+
             let player_ent = entities.create();
             player_indexes.insert(player_ent, PlayerIdx(i));
             transforms.insert(player_ent, Transform::from_translation(spawn_point));
+            invulnerables.insert(
+                player_ent,
+                Invulnerable {
+                    frames_remaining: core_meta.respawn.invulnerability_frames,
+                },
+            );
+
+            // The player falls onto the platform, which is drawn as a thick line
+            if let Some(platform_meta) = platform_meta {
+                let platform_ent = entities.create();
+                let mut position = spawn_point - Vec3::Y * platform_meta.height / 2.0;
+                position.z = spawn_point.z - 0.05;
+                transforms.insert(platform_ent, Transform::from_translation(position));
+                respawn_platforms.insert(
+                    platform_ent,
+                    RespawnPlatform {
+                        player: player_ent,
+                        size: platform_meta.size,
+                        frames_remaining: platform_meta.max_frames,
+                    },
+                );
+                let half_width = platform_meta.size.x / 2.0;
+                paths.insert(
+                    platform_ent,
+                    Path2d {
+                        color: platform_meta.color.0,
+                        points: vec![vec2(-half_width, 0.0), vec2(half_width, 0.0)],
+                        thickness: platform_meta.size.y,
+                        ..default()
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Hold the respawning players up on their platforms, until they move or act.
+fn update_respawn_platforms(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    mut respawn_platforms: CompMut<RespawnPlatform>,
+    mut bodies: CompMut<KinematicBody>,
+    mut transforms: CompMut<Transform>,
+    mut invulnerables: CompMut<Invulnerable>,
+) {
+    let mut finished = Vec::new();
+    for (platform_ent, platform) in entities.iter_with(&mut respawn_platforms) {
+        let player_ent = platform.player;
+        let Some(player_idx) = entities
+            .iter_with(&player_indexes)
+            .find(|(ent, _)| *ent == player_ent)
+            .map(|(_ent, idx)| idx.0)
+        else {
+            // The player has been despawned
+            finished.push(platform_ent);
+            continue;
+        };
+
+        // Moving or using any of the buttons gets the player off of the platform, which lets them
+        // drop through it
+        let control = &player_inputs.players[player_idx].control;
+        let acted = control.move_direction != Vec2::ZERO
+            || control.jump_pressed
+            || control.shoot_pressed
+            || control.grab_pressed
+            || control.slide_pressed
+            || control.block_pressed
+            || control.taunt_pressed
+            || control.emote.is_some();
+        platform.frames_remaining = platform.frames_remaining.saturating_sub(1);
+        if acted || platform.frames_remaining == 0 || killed_players.contains(player_ent) {
+            finished.push(platform_ent);
+            continue;
+        }
+
+        let Some(body) = bodies.get_mut(player_ent) else {
+            continue;
+        };
+        let platform_rect =
+            platform.rect(transforms.get(platform_ent).unwrap().translation.truncate());
+        let player_transform = transforms.get_mut(player_ent).unwrap();
+        let body_rect = body.bounding_box(*player_transform);
+        if is_standing_on(&platform_rect, &body_rect, body.velocity.y) {
+            player_transform.translation.y += platform_rect.max.y - body_rect.min.y;
+            body.velocity.y = 0.0;
+            body.is_on_ground = true;
+
+            // Keep the player safe while they get their bearings
+            let invulnerable = invulnerables
+                .get(player_ent)
+                .map_or(0, |x| x.frames_remaining)
+                .max(core_meta.respawn.invulnerability_frames);
+            invulnerables.insert(
+                player_ent,
+                Invulnerable {
+                    frames_remaining: invulnerable,
+                },
+            );
         }
     }
+
+    for platform_ent in finished {
+        entities.kill(platform_ent);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn respawn_avoids_enemies() {
+        let spawn_points = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(500.0, 0.0, 0.0),
+            vec3(250.0, 200.0, 0.0),
+        ];
+
+        // Next to an enemy at the first spawn point
+        assert_eq!(
+            farthest_spawn_point(&spawn_points, &[vec2(10.0, 0.0)]),
+            Some(1)
+        );
+        // Between enemies at both ends of the map
+        assert_eq!(
+            farthest_spawn_point(&spawn_points, &[vec2(0.0, 0.0), vec2(500.0, 0.0)]),
+            Some(2)
+        );
+        // Nobody to get away from
+        assert_eq!(farthest_spawn_point(&spawn_points, &[]), None);
+    }
+
+    #[test]
+    fn respawn_waits_for_the_delay() {
+        let mut delays = RespawnDelays::default();
+        delays.start(1, 100, 60);
+
+        assert_eq!(delays.frames_remaining(1, 100), Some(60));
+        assert_eq!(delays.frames_remaining(1, 159), Some(1));
+        assert_eq!(delays.frames_remaining(1, 160), None);
+        assert_eq!(delays.frames_remaining(0, 100), None);
+
+        // Without a delay, players respawn right away
+        delays.start(2, 100, 0);
+        assert_eq!(delays.frames_remaining(2, 100), None);
+    }
 }
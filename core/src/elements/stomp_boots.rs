@@ -90,6 +90,7 @@ fn update(
     mut transforms: CompMut<Transform>,
     mut stomp_boots: CompMut<StompBoots>,
     mut sprites: CompMut<AtlasSprite>,
+    facings: Comp<Facing>,
     mut bodies: CompMut<KinematicBody>,
     items_used: Comp<ItemUsed>,
     mut items_dropped: CompMut<ItemDropped>,
@@ -120,12 +121,11 @@ fn update(
             .find_map(|x| x.filter(|x| x.inventory == entity))
         {
             let player = inventory.player;
-            let player_sprite = sprites.get_mut(player).unwrap();
 
             let body = bodies.get_mut(entity).unwrap();
             body.is_deactivated = true;
 
-            let flip = player_sprite.flip_x;
+            let flip = facings.get(player).map_or(false, |x| x.left);
             let sprite = sprites.get_mut(entity).unwrap();
             sprite.flip_x = flip;
             let flip_factor = if flip { -1.0 } else { 1.0 };
@@ -155,6 +155,7 @@ fn update(
                             entity: player,
                             offset: Vec3::ZERO,
                             sync_animation: true,
+                            rotation: 0.0,
                         };
                         attachments.insert(attachment_ent, attachment);
                         sprites.insert(attachment_ent, AtlasSprite::new(player_decoration.clone()));
@@ -174,12 +175,11 @@ fn update(
             let player_velocity = bodies.get(player).unwrap().velocity;
 
             let body = bodies.get_mut(entity).unwrap();
-            let sprite = sprites.get_mut(entity).unwrap();
 
             // Re-activate physics
             body.is_deactivated = false;
 
-            let horizontal_flip_factor = if sprite.flip_x {
+            let horizontal_flip_factor = if facings.get(player).map_or(false, |x| x.left) {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
@@ -228,7 +228,12 @@ fn update_wearer(
                         .center()
                         .y
                 {
-                    player_events.kill(player, Some(player_transform.translation.xy()))
+                    player_events.kill_by(
+                        player,
+                        Some(player_transform.translation.xy()),
+                        Some(entity),
+                        Some(key!("stomp_boots")),
+                    )
                 }
             });
     }
@@ -93,6 +93,7 @@ fn hydrate(
 struct PendingDamageRegions(Vec<(Vec2, Vec2, Entity)>);
 
 fn update(
+    simulation_rate: Res<SimulationRate>,
     entities: Res<Entities>,
     element_handles: Comp<ElementHandle>,
     element_assets: BevyAssets<ElementMeta>,
@@ -109,6 +110,7 @@ fn update(
     mut player_events: ResMut<PlayerEvents>,
     mut commands: Commands,
     mut player_layers: CompMut<PlayerLayers>,
+    mut facings: CompMut<Facing>,
     mut transforms: CompMut<Transform>,
 ) {
     for (entity, (sword, element_handle)) in entities.iter_with((&mut swords, &element_handles)) {
@@ -123,12 +125,14 @@ fn update(
                       mut transforms: CompMut<Transform>,
                       mut damage_regions: CompMut<DamageRegion>,
                       mut damage_region_owners: CompMut<DamageRegionOwner>,
+                      mut damage_region_weapons: CompMut<DamageRegionWeapon>,
                       mut lifetimes: CompMut<Lifetime>| {
                     let entity = entities.create();
 
                     transforms.insert(entity, Transform::from_translation(pos));
                     damage_regions.insert(entity, DamageRegion { size });
                     damage_region_owners.insert(entity, DamageRegionOwner(owner));
+                    damage_region_weapons.insert(entity, DamageRegionWeapon(key!("sword")));
                     lifetimes.insert(entity, Lifetime::new(2.0 / 60.0));
                 },
             );
@@ -157,8 +161,7 @@ fn update(
             let body = bodies.get_mut(entity).unwrap();
             let sprite = sprites.get_mut(entity).unwrap();
             let player_translation = transforms.get(player).unwrap().translation;
-            let flip = sprite.flip_x;
-            let flip_factor = if flip { -1.0 } else { 1.0 };
+            let flip_factor = facings.get(player).map_or(1.0, |x| x.direction());
 
             let player_layer = player_layers.get_mut(player).unwrap();
             player_layer.fin_anim = *fin_anim;
@@ -172,6 +175,7 @@ fn update(
                     player,
                     offset: grab_offset.extend(1.0),
                     sync_animation: false,
+                    rotation: 0.0,
                 },
             );
 
@@ -184,6 +188,11 @@ fn update(
             match &mut sword.state {
                 SwordState::Idle => (),
                 SwordState::Swinging { frame } => {
+                    // Don't turn around mid-swing
+                    if let Some(facing) = facings.get_mut(player) {
+                        facing.hold(1);
+                    }
+
                     // If we're at the end of the swinging animation
                     if sprite.index >= 11 {
                         player_layer.fin_offset = Vec2::ZERO;
@@ -265,7 +274,7 @@ fn update(
             }
         } else {
             let body = bodies.get(entity).unwrap();
-            sword.dropped_time += 1.0 / crate::FPS;
+            sword.dropped_time += simulation_rate.frame_time();
 
             if body.velocity.length() >= *killing_speed {
                 let sword_transform = transforms.get(entity).unwrap();
@@ -280,7 +289,12 @@ fn update(
                         }
                     })
                     .for_each(|player| {
-                        player_events.kill(player, Some(sword_transform.translation.xy()))
+                        player_events.kill_by(
+                            player,
+                            Some(sword_transform.translation.xy()),
+                            None,
+                            Some(key!("sword")),
+                        )
                     });
             }
         }
@@ -304,15 +318,16 @@ fn update(
             // Put sword in rest position
             sprite.index = 0;
 
-            let horizontal_flip_factor = if sprite.flip_x {
+            let facing_left = facings.get(player).map_or(false, |x| x.left);
+            let horizontal_flip_factor = if facing_left {
                 Vec2::new(-1.0, 1.0)
             } else {
                 Vec2::ONE
             };
 
             if player_velocity != Vec2::ZERO {
-                body.velocity = *throw_velocity * horizontal_flip_factor + player_velocity;
-                body.angular_velocity = *angular_velocity * if sprite.flip_x { -1.0 } else { 1.0 };
+                body.velocity = thrown_item_velocity(*throw_velocity, facing_left, player_velocity);
+                body.angular_velocity = *angular_velocity * if facing_left { -1.0 } else { 1.0 };
             }
             body.is_spawning = true;
 
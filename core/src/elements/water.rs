@@ -0,0 +1,46 @@
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session
+        .stages
+        .add_system_to_stage(CoreStage::First, hydrate);
+}
+
+/// A rectangular region of water that players can swim in.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GVTTW8R1T78TKBQ0E4B7MJF6"]
+pub struct WaterRegion {
+    /// The size of the water region in pixels
+    pub size: Vec2,
+}
+
+impl WaterRegion {
+    /// Get the rectangle covered by this water region, given it's transform.
+    pub fn rect(&self, position: Vec3) -> Rect {
+        Rect::new(position.x, position.y, self.size.x, self.size.y)
+    }
+}
+
+fn hydrate(
+    entities: Res<Entities>,
+    mut hydrated: CompMut<MapElementHydrated>,
+    element_handles: Comp<ElementHandle>,
+    element_assets: BevyAssets<ElementMeta>,
+    mut water_regions: CompMut<WaterRegion>,
+) {
+    let mut not_hydrated_bitset = hydrated.bitset().clone();
+    not_hydrated_bitset.bit_not();
+    not_hydrated_bitset.bit_and(element_handles.bitset());
+
+    for entity in entities.iter_with_bitset(&not_hydrated_bitset) {
+        let element_handle = element_handles.get(entity).unwrap();
+        let Some(element_meta) = element_assets.get(&element_handle.get_bevy_handle()) else {
+            continue;
+        };
+
+        if let BuiltinElementKind::Water { size } = &element_meta.builtin {
+            hydrated.insert(entity, MapElementHydrated);
+            water_regions.insert(entity, WaterRegion { size: *size });
+        }
+    }
+}
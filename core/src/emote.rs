@@ -0,0 +1,140 @@
+//! Emotes that players pick from the emote wheel, which pop up above their heads.
+
+use std::collections::VecDeque;
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<EmoteEvents>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::PostUpdate, play_emotes);
+}
+
+/// Resource containing the queue of emotes that players have played.
+#[derive(Clone, TypeUlid, Debug, Default)]
+#[ulid = "01GTH6J3MY63V973YFXPCZM3Z0"]
+pub struct EmoteEvents {
+    /// Emotes that haven't been handled yet, oldest first.
+    pub queue: VecDeque<EmotePlayed>,
+}
+
+impl EmoteEvents {
+    /// The maximum number of emotes kept in the queue.
+    ///
+    /// If nothing is draining the queue, the oldest emotes are dropped to make room for new ones.
+    pub const MAX_LEN: usize = 32;
+
+    /// Add an emote to the queue.
+    pub fn send(&mut self, event: EmotePlayed) {
+        if self.queue.len() >= Self::MAX_LEN {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(event);
+    }
+
+    /// Remove and return all of the emotes in the queue.
+    pub fn drain(&mut self) -> impl Iterator<Item = EmotePlayed> + '_ {
+        self.queue.drain(..)
+    }
+}
+
+/// An emote that was sent to the [`EmoteEvents`] queue.
+#[derive(Clone, Copy, Debug)]
+pub struct EmotePlayed {
+    /// The player that emoted.
+    pub player: Entity,
+    /// The index of the emote on the emote wheel.
+    pub emote: usize,
+}
+
+/// Component tracking the emotes of a player.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GV7ANWST1PDV5PWWFJHSR2XW"]
+pub struct EmoteCooldown {
+    /// The number of frames left until the player can emote again.
+    pub frames_remaining: u32,
+    /// The entity showing the player's latest emote, if it may still be shown.
+    pub bubble: Option<Entity>,
+}
+
+/// Show the emotes that players picked above their heads, unless they emoted too recently.
+fn play_emotes(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    simulation_rate: Res<SimulationRate>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    mut emote_cooldowns: CompMut<EmoteCooldown>,
+    mut emote_events: ResMut<EmoteEvents>,
+    mut transforms: CompMut<Transform>,
+    mut atlas_sprites: CompMut<AtlasSprite>,
+    mut attachments: CompMut<Attachment>,
+    mut lifetimes: CompMut<Lifetime>,
+) {
+    let meta = &core_meta.emotes;
+
+    let players = entities
+        .iter_with(&player_indexes)
+        .map(|(ent, idx)| (ent, idx.0))
+        .collect::<Vec<_>>();
+    for (player_ent, player_idx) in players {
+        let mut cooldown = emote_cooldowns.get(player_ent).copied().unwrap_or_default();
+        cooldown.frames_remaining = cooldown.frames_remaining.saturating_sub(1);
+
+        let control = &player_inputs.players[player_idx].control;
+        let emote = control
+            .emote
+            .map(usize::from)
+            .filter(|&x| x < meta.wheel.len());
+        let can_emote = cooldown.frames_remaining == 0 && !killed_players.contains(player_ent);
+        if let Some(emote) = emote.filter(|_| can_emote) {
+            cooldown.frames_remaining = meta.cooldown_frames;
+
+            // Replace the last emote if it's still showing
+            if let Some(bubble) = cooldown.bubble.take() {
+                if attachments
+                    .get(bubble)
+                    .map_or(false, |x| x.entity == player_ent)
+                {
+                    entities.kill(bubble);
+                }
+            }
+
+            let emote_meta = &meta.wheel[emote];
+            let bubble = entities.create();
+            let transform = transforms.get(player_ent).copied().unwrap_or_default();
+            transforms.insert(bubble, transform);
+            atlas_sprites.insert(
+                bubble,
+                AtlasSprite {
+                    atlas: emote_meta.atlas.clone(),
+                    index: emote_meta.index,
+                    ..default()
+                },
+            );
+            attachments.insert(
+                bubble,
+                Attachment {
+                    entity: player_ent,
+                    offset: meta.offset.extend(1.0),
+                    sync_animation: false,
+                    rotation: 0.0,
+                },
+            );
+            lifetimes.insert(
+                bubble,
+                Lifetime::new(meta.duration_frames as f32 * simulation_rate.frame_time()),
+            );
+            cooldown.bubble = Some(bubble);
+
+            emote_events.send(EmotePlayed {
+                player: player_ent,
+                emote,
+            });
+        }
+
+        emote_cooldowns.insert(player_ent, cooldown);
+    }
+}
@@ -0,0 +1,175 @@
+//! Hit-stop, that briefly freezes the players involved in a hit to sell the impact.
+//!
+//! Instead of halting the whole session, which every client in a networked game would have to
+//! agree on the timing of, only the frozen entities are paused. Their physics bodies stop moving,
+//! and their animations hold on the frame that they were on, while the rest of the match plays on
+//! as usual. Whatever is attached to a frozen entity, such as a player's held item, is frozen along
+//! with it.
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session
+        .stages
+        // This must run after the hits for the frame have been handled.
+        .add_system_to_stage(CoreStage::PostUpdate, hold_frozen_animations)
+        .add_system_to_stage(CoreStage::Last, update_hit_stops);
+}
+
+/// Component added to an entity that is frozen by a hit.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GTV0REF8YBHQYFBS644NQN5V"]
+pub struct HitStop {
+    /// The number of frames until the entity moves again.
+    pub frames_remaining: u32,
+    /// The speed of the entity's animation from before it was frozen, which it is set back to when
+    /// the freeze wears off.
+    pub paused_fps: Option<f32>,
+}
+
+/// Freeze `entity` for `frames` frames, unless it is already frozen for longer.
+pub fn apply_hit_stop(hit_stops: &mut CompMut<HitStop>, entity: Entity, frames: u32) {
+    if frames == 0 {
+        return;
+    }
+    let mut hit_stop = hit_stops.get(entity).copied().unwrap_or_default();
+    hit_stop.frames_remaining = hit_stop.frames_remaining.max(frames);
+    hit_stops.insert(entity, hit_stop);
+}
+
+/// Spread the freeze to the entities attached to frozen ones, and hold their animations.
+fn hold_frozen_animations(
+    entities: Res<Entities>,
+    player_layers: Comp<PlayerLayers>,
+    attachments: Comp<Attachment>,
+    mut hit_stops: CompMut<HitStop>,
+    mut animated_sprites: CompMut<AnimatedSprite>,
+) {
+    let mut attached = Vec::new();
+    for (ent, hit_stop) in entities.iter_with(&hit_stops) {
+        if let Some(layers) = player_layers.get(ent) {
+            attached.extend([
+                (layers.fin_ent, hit_stop.frames_remaining),
+                (layers.face_ent, hit_stop.frames_remaining),
+            ]);
+        }
+    }
+    for (ent, attachment) in entities.iter_with(&attachments) {
+        if let Some(hit_stop) = hit_stops.get(attachment.entity) {
+            attached.push((ent, hit_stop.frames_remaining));
+        }
+    }
+    for (ent, frames) in attached {
+        apply_hit_stop(&mut hit_stops, ent, frames);
+    }
+
+    // The animation may have been switched since the last frame, in which case the new one is held
+    for (_ent, (hit_stop, sprite)) in entities.iter_with((&mut hit_stops, &mut animated_sprites)) {
+        if sprite.fps != 0.0 {
+            hit_stop.paused_fps = Some(sprite.fps);
+            sprite.fps = 0.0;
+        }
+    }
+}
+
+/// Count down the freezes, and set the animations of the entities that they wore off for going
+/// again.
+fn update_hit_stops(
+    entities: Res<Entities>,
+    mut hit_stops: CompMut<HitStop>,
+    mut animated_sprites: CompMut<AnimatedSprite>,
+) {
+    let mut thawed = Vec::new();
+    for (ent, hit_stop) in entities.iter_with(&mut hit_stops) {
+        hit_stop.frames_remaining = hit_stop.frames_remaining.saturating_sub(1);
+        if hit_stop.frames_remaining == 0 {
+            thawed.push((ent, hit_stop.paused_fps));
+        }
+    }
+
+    for (ent, paused_fps) in thawed {
+        hit_stops.remove(ent);
+        if let (Some(sprite), Some(fps)) = (animated_sprites.get_mut(ent), paused_fps) {
+            sprite.fps = fps;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn hit_stop_holds_animations_until_it_wears_off() {
+        let mut test = SystemTest::new(
+            |_world| (),
+            |stages| {
+                stages
+                    .add_system_to_stage(CoreStage::PostUpdate, hold_frozen_animations)
+                    .add_system_to_stage(CoreStage::Last, update_hit_stops);
+            },
+        );
+
+        let (player, item) = test
+            .world
+            .run_initialized_system(
+                |mut entities: ResMut<Entities>,
+                 mut hit_stops: CompMut<HitStop>,
+                 mut attachments: CompMut<Attachment>,
+                 mut animated_sprites: CompMut<AnimatedSprite>| {
+                    let player = entities.create();
+                    let item = entities.create();
+                    for ent in [player, item] {
+                        animated_sprites.insert(
+                            ent,
+                            AnimatedSprite {
+                                frames: (0..4).collect(),
+                                fps: 10.0,
+                                repeat: true,
+                                ..default()
+                            },
+                        );
+                    }
+                    attachments.insert(
+                        item,
+                        Attachment {
+                            entity: player,
+                            offset: Vec3::ZERO,
+                            sync_animation: false,
+                            rotation: 0.0,
+                        },
+                    );
+                    apply_hit_stop(&mut hit_stops, player, 3);
+                    // A shorter freeze doesn't cut a longer one short
+                    apply_hit_stop(&mut hit_stops, player, 1);
+                    Ok((player, item))
+                },
+            )
+            .unwrap();
+
+        let fps = |world: &mut World| {
+            world
+                .run_initialized_system(move |animated_sprites: Comp<AnimatedSprite>| {
+                    Ok([player, item].map(|ent| animated_sprites.get(ent).unwrap().fps))
+                })
+                .unwrap()
+        };
+        for _ in 0..2 {
+            test.run();
+            assert_eq!(fps(&mut test.world), [0.0, 0.0]);
+        }
+
+        // The attached item froze along with the player, and both thaw out together
+        test.run();
+        assert_eq!(fps(&mut test.world), [10.0, 10.0]);
+        let frozen = test
+            .world
+            .run_initialized_system(|entities: Res<Entities>, hit_stops: Comp<HitStop>| {
+                Ok(entities.iter_with(&hit_stops).count())
+            })
+            .unwrap();
+        assert_eq!(frozen, 0);
+    }
+}
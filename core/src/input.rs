@@ -2,6 +2,11 @@ use crate::{prelude::*, MAX_PLAYERS};
 
 pub fn install(session: &mut GameSession) {
     session.world.init_resource::<PlayerInputs>();
+    session.world.init_resource::<InputBuffer>();
+
+    session
+        .stages
+        .add_system_to_stage(CoreStage::First, buffer_player_input);
 }
 
 /// The inputs for each player in this simulation frame.
@@ -11,6 +16,34 @@ pub struct PlayerInputs {
     pub players: Vec<PlayerInput>,
 }
 
+impl PlayerInputs {
+    /// Whether two different players are on the same team.
+    ///
+    /// Players without a team are each on a team of their own, so they have no teammates.
+    pub fn are_teammates(&self, a: usize, b: usize) -> bool {
+        if a == b {
+            return false;
+        }
+        match (self.players[a].team, self.players[b].team) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Whether two different players are playing against each other, because they aren't on the
+    /// same team.
+    pub fn are_opponents(&self, a: usize, b: usize) -> bool {
+        a != b && !self.are_teammates(a, b)
+    }
+
+    /// Whether none of the given players are opponents, so they are all playing for the same side.
+    pub fn on_one_side(&self, players: &[usize]) -> bool {
+        players
+            .iter()
+            .all(|&a| players.iter().all(|&b| !self.are_opponents(a, b)))
+    }
+}
+
 impl Default for PlayerInputs {
     fn default() -> Self {
         Self {
@@ -35,10 +68,18 @@ pub struct PlayerInput {
     pub previous_control: PlayerControl,
     /// The editor inputs the player is making, if any.
     pub editor_input: Option<EditorInput>,
+    /// The color accent that the player's character is tinted with, if any.
+    pub color: Option<ColorMeta>,
+    /// The team that the player is on, if teams are being played.
+    ///
+    /// Players that aren't on a team play for themselves, as in a free-for-all.
+    pub team: Option<u8>,
+    /// How hard the bot controlling the player is, if the player is a bot.
+    pub bot: Option<BotDifficulty>,
 }
 
 /// Player control input state
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[repr(C)]
 pub struct PlayerControl {
     pub move_direction: Vec2,
@@ -50,12 +91,156 @@ pub struct PlayerControl {
 
     pub shoot_pressed: bool,
     pub shoot_just_pressed: bool,
+    /// The number of frames that shoot has been held down for, counting the frame that it was
+    /// pressed on, or zero if it isn't pressed.
+    #[serde(default)]
+    pub shoot_hold_frames: u32,
 
     pub grab_pressed: bool,
     pub grab_just_pressed: bool,
 
     pub slide_pressed: bool,
     pub slide_just_pressed: bool,
+
+    /// Whether the player is holding up their shield.
+    #[serde(default)]
+    pub block_pressed: bool,
+
+    #[serde(default)]
+    pub taunt_pressed: bool,
+    #[serde(default)]
+    pub taunt_just_pressed: bool,
+
+    /// The index of the emote that the player picked from the emote wheel on this frame, if any.
+    #[serde(default)]
+    pub emote: Option<u8>,
+
+    /// The direction that the player is aiming their weapon in, or zero to aim the way that they
+    /// are facing.
+    #[serde(default)]
+    pub aim_direction: Vec2,
+}
+
+impl PlayerControl {
+    /// Work out the presses, like `jump_just_pressed`, by comparing the held buttons to the
+    /// `previous` frame's controls.
+    ///
+    /// Every source of controls, from the live input to bots and replays, must press the buttons
+    /// this way, so that the presses only depend on which buttons are held on each frame.
+    pub fn detect_presses(&mut self, previous: &PlayerControl) {
+        self.jump_just_pressed = self.jump_pressed && !previous.jump_pressed;
+        self.grab_just_pressed = self.grab_pressed && !previous.grab_pressed;
+        self.shoot_just_pressed = self.shoot_pressed && !previous.shoot_pressed;
+        self.slide_just_pressed = self.slide_pressed && !previous.slide_pressed;
+        self.taunt_just_pressed = self.taunt_pressed && !previous.taunt_pressed;
+        self.shoot_hold_frames = if self.shoot_pressed {
+            previous.shoot_hold_frames.saturating_add(1)
+        } else {
+            0
+        };
+        let was_moving = previous.move_direction.length_squared() > f32::MIN_POSITIVE;
+        let is_moving = self.move_direction.length_squared() > f32::MIN_POSITIVE;
+        self.just_moved = !was_moving && is_moving;
+    }
+
+    /// Get the unit direction that the player is aiming in, given whether they are facing left.
+    pub fn aim(&self, flip_x: bool) -> Vec2 {
+        let aim = self.aim_direction.normalize_or_zero();
+        if aim != Vec2::ZERO {
+            aim
+        } else if flip_x {
+            Vec2::NEG_X
+        } else {
+            Vec2::X
+        }
+    }
+}
+
+/// Snap a direction to the nearest of the 8 compass directions, for aiming with digital input.
+///
+/// A zero direction stays zero.
+pub fn snap_to_8_directions(direction: Vec2) -> Vec2 {
+    if direction == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    let step = std::f32::consts::FRAC_PI_4;
+    let angle = (direction.y.atan2(direction.x) / step).round() * step;
+    let snapped = Vec2::from_angle(angle);
+    // Round off the float error, so that straight directions are exactly straight
+    vec2(
+        (snapped.x * 1e4).round() / 1e4,
+        (snapped.y * 1e4).round() / 1e4,
+    )
+}
+
+/// Resource that keeps each player's button presses around for a few frames until they are used.
+///
+/// This lets a player press jump a few frames before they land, for instance, and still jump once
+/// they hit the ground. While a press is buffered, the matching `*_just_pressed` field of the
+/// player's [`PlayerControl`] stays `true`, and systems that act on the press should consume it.
+#[derive(Clone, TypeUlid, Debug)]
+#[ulid = "01GTWK84EZEAWW901Y80REM935"]
+pub struct InputBuffer {
+    /// The number of frames that a button press is kept around for.
+    pub window: u32,
+    /// The buffered presses for each player.
+    pub players: [BufferedInput; MAX_PLAYERS],
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        Self {
+            window: 6,
+            players: default(),
+        }
+    }
+}
+
+/// The buffered button presses for a player, as the number of frames left until they expire.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BufferedInput {
+    pub jump: u32,
+    pub grab: u32,
+    pub shoot: u32,
+}
+
+impl BufferedInput {
+    /// Consume the buffered jump press, so that it isn't acted on again.
+    pub fn consume_jump(&mut self) {
+        self.jump = 0;
+    }
+
+    /// Consume the buffered grab press, so that it isn't acted on again.
+    pub fn consume_grab(&mut self) {
+        self.grab = 0;
+    }
+
+    /// Consume the buffered shoot press, so that it isn't acted on again.
+    pub fn consume_shoot(&mut self) {
+        self.shoot = 0;
+    }
+}
+
+/// Buffer new button presses, and keep the `*_just_pressed` controls set while they are buffered.
+fn buffer_player_input(mut player_inputs: ResMut<PlayerInputs>, mut buffer: ResMut<InputBuffer>) {
+    let window = buffer.window;
+
+    for (input, buffered) in player_inputs.players.iter_mut().zip(&mut buffer.players) {
+        let control = &mut input.control;
+
+        for (just_pressed, frames_left) in [
+            (&mut control.jump_just_pressed, &mut buffered.jump),
+            (&mut control.grab_just_pressed, &mut buffered.grab),
+            (&mut control.shoot_just_pressed, &mut buffered.shoot),
+        ] {
+            if *just_pressed {
+                *frames_left = window;
+            } else {
+                *frames_left = frames_left.saturating_sub(1);
+            }
+            *just_pressed = *frames_left > 0;
+        }
+    }
 }
 
 /// The editor inputs that a player may make.
@@ -86,3 +271,23 @@ pub enum EditorInput {
         id: String,
     },
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aim_snaps_to_8_directions() {
+        assert_eq!(snap_to_8_directions(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(snap_to_8_directions(vec2(0.2, 1.0)), Vec2::Y);
+        assert_eq!(snap_to_8_directions(vec2(-1.0, -0.1)), Vec2::NEG_X);
+
+        let diagonal = snap_to_8_directions(vec2(1.0, 0.8));
+        assert!(diagonal.abs_diff_eq(vec2(1.0, 1.0).normalize(), 0.001));
+
+        // Players that aren't aiming shoot the way they are facing
+        let control = PlayerControl::default();
+        assert_eq!(control.aim(true), Vec2::NEG_X);
+        assert_eq!(control.aim(false), Vec2::X);
+    }
+}
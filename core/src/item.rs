@@ -7,6 +7,96 @@ use crate::prelude::*;
 #[ulid = "01GP4DBSEB3R6ZNBNNTSY36GW4"]
 pub struct Item;
 
+/// Get the item that a player at `player_pos` grabs, out of the items that can be grabbed and their
+/// positions.
+///
+/// The player grabs the closest item that is within `grab_range` of them.
+pub fn item_in_grab_range(
+    player_pos: Vec2,
+    grab_range: f32,
+    items: impl IntoIterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    let mut closest = None;
+    for (item, item_pos) in items {
+        let distance = player_pos.distance(item_pos);
+        if distance > grab_range {
+            continue;
+        }
+        if closest.map_or(true, |(_, closest_distance)| distance < closest_distance) {
+            closest = Some((item, distance));
+        }
+    }
+
+    closest.map(|(item, _)| item)
+}
+
+/// Get the velocity of an item thrown by a player.
+///
+/// `throw_velocity` is the velocity that a standing player facing right throws the item at. It is
+/// mirrored for players facing left, and the player's own velocity is added to it.
+pub fn thrown_item_velocity(throw_velocity: Vec2, flip_x: bool, player_velocity: Vec2) -> Vec2 {
+    let horizontal_flip_factor = if flip_x {
+        Vec2::new(-1.0, 1.0)
+    } else {
+        Vec2::ONE
+    };
+
+    throw_velocity * horizontal_flip_factor + player_velocity
+}
+
+/// Component tracking the ammunition of a ranged weapon.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, TypeUlid)]
+#[ulid = "01GV2V24GM36MP8973NVGQ6R0S"]
+pub struct Ammo {
+    /// The number of shots left before the weapon needs to be reloaded.
+    pub count: usize,
+    /// The number of shots that the weapon holds when it is fully loaded.
+    pub max: usize,
+    /// The number of frames until the weapon is reloaded, if it is being reloaded.
+    pub reload_frames_remaining: Option<usize>,
+}
+
+impl Ammo {
+    /// Create a fully loaded weapon's ammo.
+    pub fn full(max: usize) -> Self {
+        Self {
+            count: max,
+            max,
+            reload_frames_remaining: None,
+        }
+    }
+
+    /// Whether the weapon is being reloaded.
+    pub fn is_reloading(&self) -> bool {
+        self.reload_frames_remaining.is_some()
+    }
+
+    /// Use up a shot, returning `false` if the weapon is empty or being reloaded.
+    pub fn shoot(&mut self) -> bool {
+        if self.count == 0 || self.is_reloading() {
+            return false;
+        }
+        self.count -= 1;
+        true
+    }
+
+    /// Start reloading the weapon, unless it is already full or being reloaded.
+    pub fn start_reload(&mut self, frames: usize) {
+        if self.count < self.max && !self.is_reloading() {
+            self.reload_frames_remaining = Some(frames);
+        }
+    }
+
+    /// Advance the reload by a frame, filling the weapon up once it is done.
+    pub fn update_reload(&mut self) {
+        match &mut self.reload_frames_remaining {
+            Some(0) => *self = Self::full(self.max),
+            Some(frames) => *frames -= 1,
+            None => (),
+        }
+    }
+}
+
 /// An intventory component, indicating another entity that the player is carrying.
 #[derive(Clone, TypeUlid, Default, Deref, DerefMut)]
 #[ulid = "01GP4D6M2QBSKZMEZMM22YGG41"]
@@ -76,3 +166,56 @@ pub struct ItemGrabbed;
 #[derive(Clone, Copy, TypeUlid)]
 #[ulid = "01GP4DJ84TFB8Z7H9VY7Y0R47H"]
 pub struct ItemUsed;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_items_in_range_are_grabbed() {
+        let mut entities = Entities::default();
+        let near = entities.create();
+        let far = entities.create();
+        let items = [(far, vec2(40.0, 0.0)), (near, vec2(-10.0, 10.0))];
+
+        assert_eq!(item_in_grab_range(Vec2::ZERO, 32.0, items), Some(near));
+        assert_eq!(item_in_grab_range(Vec2::ZERO, 50.0, items), Some(near));
+        assert_eq!(item_in_grab_range(vec2(30.0, 0.0), 32.0, items), Some(far));
+        assert_eq!(item_in_grab_range(vec2(0.0, 100.0), 32.0, items), None);
+    }
+
+    #[test]
+    fn reloading_refills_ammo() {
+        let mut ammo = Ammo::full(2);
+        assert!(ammo.shoot());
+        assert!(ammo.shoot());
+        assert!(!ammo.shoot());
+
+        ammo.start_reload(2);
+        for _ in 0..2 {
+            ammo.update_reload();
+            assert!(!ammo.shoot());
+        }
+        ammo.update_reload();
+        assert_eq!(ammo, Ammo::full(2));
+        assert!(ammo.shoot());
+    }
+
+    #[test]
+    fn thrown_items_follow_the_player_facing() {
+        let throw = vec2(5.0, 2.0);
+
+        assert_eq!(
+            thrown_item_velocity(throw, false, Vec2::ZERO),
+            vec2(5.0, 2.0)
+        );
+        assert_eq!(
+            thrown_item_velocity(throw, true, Vec2::ZERO),
+            vec2(-5.0, 2.0)
+        );
+        assert_eq!(
+            thrown_item_velocity(throw, true, vec2(-3.0, 1.0)),
+            vec2(-8.0, 3.0)
+        );
+    }
+}
@@ -13,6 +13,7 @@ pub mod bevy_prelude {
     pub use {
         crate::{
             input::EditorInput,
+            match_mode::{FriendlyFire, MatchMode},
             metadata::*,
             session::{GameSession, GameSessionInfo},
             MAX_PLAYERS,
@@ -21,26 +22,41 @@ pub mod bevy_prelude {
     };
 }
 
+pub mod animation;
+pub mod announcer;
 pub mod attachment;
+pub mod audio;
+pub mod bot;
 pub mod bullet;
 pub mod camera;
 pub mod damage;
 pub mod debug;
+pub mod decals;
+pub mod determinism;
 pub mod editor;
 pub mod elements;
+pub mod emote;
+pub mod hit_stop;
 pub mod input;
 pub mod item;
 pub mod lifetime;
 pub mod map;
+pub mod match_mode;
 pub mod math;
 pub mod metadata;
+pub mod particles;
 pub mod physics;
+pub mod platform;
 pub mod player;
 pub mod random;
 pub mod session;
+pub mod stats;
 pub mod testing;
+pub mod wind;
 
-/// The target fixed frames-per-second that the game sumulation runs at.
+/// The default fixed frames-per-second that the game sumulation runs at.
+///
+/// Sessions may be started at a different rate with [`session::GameSessionInfo::fps`].
 pub const FPS: f32 = 60.0;
 pub const MAX_PLAYERS: usize = 4;
 
@@ -48,16 +64,28 @@ pub const MAX_PLAYERS: usize = 4;
 pub fn install_modules(session: &mut session::GameSession) {
     testing::install(session);
     physics::install(session);
+    audio::install(session);
+    bot::install(session);
     input::install(session);
     map::install(session);
     elements::install(session);
     player::install(session);
+    emote::install(session);
+    match_mode::install(session);
+    stats::install(session);
     damage::install(session);
+    hit_stop::install(session);
+    wind::install(session);
+    platform::install(session);
     camera::install(session);
     lifetime::install(session);
+    particles::install(session);
+    decals::install(session);
+    announcer::install(session);
     random::install(session);
     debug::install(session);
     attachment::install(session);
+    animation::install(session);
     bullet::install(session);
     editor::install(session);
 }
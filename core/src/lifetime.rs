@@ -1,6 +1,6 @@
 //! Module providing entity lifetime components and systems
 
-use crate::{prelude::*, FPS};
+use crate::prelude::*;
 
 pub fn install(session: &mut GameSession) {
     session
@@ -34,10 +34,14 @@ impl Lifetime {
 }
 
 /// Despawns entities that have an expired lifetime
-fn lifetime_system(mut entities: ResMut<Entities>, mut lifetimes: CompMut<Lifetime>) {
+fn lifetime_system(
+    mut entities: ResMut<Entities>,
+    simulation_rate: Res<SimulationRate>,
+    mut lifetimes: CompMut<Lifetime>,
+) {
     let mut to_kill = Vec::new();
     for (entity, mut lifetime) in &mut entities.iter_with(&mut lifetimes) {
-        lifetime.age += 1.0 / FPS;
+        lifetime.age += simulation_rate.frame_time();
         if lifetime.age > lifetime.lifetime {
             to_kill.push(entity);
         }
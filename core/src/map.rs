@@ -21,6 +21,30 @@ pub struct MapSpawned(pub bool);
 #[ulid = "01GP9NY0Y50Y2A8M4A7E9NN8VE"]
 pub struct MapRespawnPoint(pub Vec3);
 
+/// The distance past the left, right, and bottom edges of the map that players and items are
+/// considered out of bounds, if the map doesn't set it's own blast zone.
+///
+/// Players that fall this far below the map are killed outright.
+pub const KILL_ZONE_BORDER: f32 = 500.0;
+
+/// The distance below the map that players leave it's default blast zone at.
+///
+/// This is well above the [`KILL_ZONE_BORDER`] that falling players are killed at, so that they
+/// are warned that they're out of bounds, and have a chance to get back, before they hit it.
+pub const BLAST_ZONE_DEPTH: f32 = KILL_ZONE_BORDER / 2.0;
+
+/// Component added to a player that has left the blast zone of the map.
+///
+/// If the player doesn't make it back in before the frames run out, they are knocked out.
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GWMEYSRJ8DN2Z87D90BTWNBN"]
+pub struct OutOfBounds {
+    /// The number of frames the player has left to get back into the blast zone.
+    pub frames_remaining: u32,
+    /// The direction from the blast zone to the player.
+    pub direction: Vec2,
+}
+
 /// Helper for getting the z-depth of the map layer with the given index.
 pub fn z_depth_for_map_layer(layer_idx: usize) -> f32 {
     // We start map layers at -900 and for ever layer we place a gap of 2 units in between
@@ -39,6 +63,12 @@ pub struct SpawnedMapMeta {
     pub grid_size: UVec2,
     pub tile_size: Vec2,
     pub layer_names: Arc<[String]>,
+    pub hazards: Arc<[HazardMeta]>,
+    pub platforms: Arc<[MovingPlatformMeta]>,
+    pub wind: Arc<[WindZoneMeta]>,
+    pub surfaces: SurfacesMeta,
+    pub blast_zone: Option<BlastZoneMeta>,
+    pub physics: MapPhysicsMeta,
 }
 
 impl Default for SpawnedMapMeta {
@@ -50,6 +80,12 @@ impl Default for SpawnedMapMeta {
             grid_size: default(),
             tile_size: default(),
             layer_names: Arc::new([]),
+            hazards: Arc::new([]),
+            platforms: Arc::new([]),
+            wind: Arc::new([]),
+            surfaces: default(),
+            blast_zone: None,
+            physics: default(),
         }
     }
 }
@@ -77,12 +113,18 @@ fn spawn_map(
     mut element_handles: CompMut<ElementHandle>,
     mut tile_collisions: CompMut<TileCollisionKind>,
     mut parallax_bg_sprites: CompMut<ParallaxBackgroundSprite>,
+    mut no_interpolation: CompMut<NoInterpolation>,
     mut sprites: CompMut<Sprite>,
     mut cameras: CompMut<Camera>,
     mut camera_shakes: CompMut<CameraShake>,
     mut camera_states: CompMut<CameraState>,
     mut spawned_map_layer_metas: CompMut<SpawnedMapLayerMeta>,
     mut spawned_map_meta: ResMut<SpawnedMapMeta>,
+    mut hazards: CompMut<Hazard>,
+    mut surface_materials: CompMut<SurfaceMaterial>,
+    mut moving_platforms: CompMut<MovingPlatform>,
+    mut wind_zones: CompMut<WindZone>,
+    mut destructibles: CompMut<Destructible>,
 ) {
     if map_spawned.0 {
         return;
@@ -96,6 +138,12 @@ fn spawn_map(
         grid_size: map.grid_size,
         tile_size: map.tile_size,
         layer_names: map.layers.iter().map(|x| x.id.to_string()).collect(),
+        hazards: map.hazards.iter().cloned().collect(),
+        platforms: map.platforms.iter().cloned().collect(),
+        wind: map.wind.iter().cloned().collect(),
+        surfaces: map.surfaces.clone(),
+        blast_zone: map.blast_zone,
+        physics: map.physics,
     };
 
     // Spawn the camera
@@ -135,6 +183,8 @@ fn spawn_map(
                     meta: layer.clone(),
                 },
             );
+            // The backgrounds follow the camera
+            no_interpolation.insert(ent, NoInterpolation);
         }
     }
 
@@ -162,6 +212,12 @@ fn spawn_map(
                         TileCollisionKind::SOLID
                     },
                 );
+                if tile_meta.material != SurfaceMaterial::Normal {
+                    surface_materials.insert(tile_ent, tile_meta.material);
+                }
+                if let Some(health) = tile_meta.destructible {
+                    destructibles.insert(tile_ent, Destructible { health });
+                }
             }
             let layer_ent = entities.create();
             spawned_map_layer_metas.insert(layer_ent, SpawnedMapLayerMeta { layer_idx });
@@ -184,6 +240,84 @@ fn spawn_map(
         }
     }
 
+    // Spawn hazards
+    for hazard_meta in &map.hazards {
+        let ent = entities.create();
+        transforms.insert(
+            ent,
+            Transform::from_translation(hazard_meta.pos.extend(0.0)),
+        );
+        hazards.insert(
+            ent,
+            Hazard {
+                size: hazard_meta.size,
+                damage: hazard_meta.damage,
+                instant_kill: hazard_meta.instant_kill,
+                direction: hazard_meta.direction,
+            },
+        );
+    }
+
+    // Spawn wind zones
+    for wind_meta in &map.wind {
+        let ent = entities.create();
+        transforms.insert(ent, Transform::from_translation(wind_meta.pos.extend(0.0)));
+        wind_zones.insert(
+            ent,
+            WindZone {
+                size: wind_meta.size,
+                force: wind_meta.force,
+                pattern: wind_meta.pattern,
+                current: default(),
+            },
+        );
+    }
+
+    // Spawn moving platforms
+    for platform_meta in &map.platforms {
+        let Some(start) = platform_meta.path.first() else {
+            continue;
+        };
+        let ent = entities.create();
+        // Put platforms in front of the map tiles
+        let z = z_depth_for_map_layer(map.layers.len());
+        transforms.insert(ent, Transform::from_translation(start.extend(z)));
+        moving_platforms.insert(ent, MovingPlatform::new(platform_meta));
+        if let Some(image) = &platform_meta.image {
+            sprites.insert(
+                ent,
+                Sprite {
+                    image: image.clone(),
+                    ..default()
+                },
+            );
+        }
+    }
+
+    // Spawn a hazard below the map that kills players who fall off of it
+    {
+        const FALL_OFF_HAZARD_HEIGHT: f32 = 10000.0;
+        let map_width = map.grid_size.x as f32 * map.tile_size.x;
+        let ent = entities.create();
+        transforms.insert(
+            ent,
+            Transform::from_translation(vec3(
+                map_width / 2.0,
+                -KILL_ZONE_BORDER - FALL_OFF_HAZARD_HEIGHT / 2.0,
+                0.0,
+            )),
+        );
+        hazards.insert(
+            ent,
+            Hazard {
+                size: vec2(map_width + KILL_ZONE_BORDER * 2.0, FALL_OFF_HAZARD_HEIGHT),
+                damage: 0.0,
+                instant_kill: true,
+                direction: Vec2::Y,
+            },
+        );
+    }
+
     // Update collision world with map tiles
     commands.add(|mut collision_world: CollisionWorld| {
         collision_world.update_tiles();
@@ -192,26 +326,53 @@ fn spawn_map(
 
 fn handle_out_of_bounds_players_and_items(
     entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
     mut transforms: CompMut<Transform>,
     player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    hitstuns: Comp<states::hitstun::Hitstun>,
+    grabbeds: Comp<states::grabbed::Grabbed>,
+    mut out_of_bounds: CompMut<OutOfBounds>,
+    mut invulnerables: CompMut<Invulnerable>,
     map: Res<LoadedMap>,
     mut player_events: ResMut<PlayerEvents>,
     map_respawn_points: Comp<MapRespawnPoint>,
 ) {
-    const KILL_ZONE_BORDER: f32 = 500.0;
-
-    let map_width = map.grid_size.x as f32 * map.tile_size.x;
-    let left_kill_zone = -KILL_ZONE_BORDER;
-    let right_kill_zone = map_width + KILL_ZONE_BORDER;
-    let bottom_kill_zone = -KILL_ZONE_BORDER;
+    let blast_zone = map.blast_zone_rect();
 
-    // Kill out of bounds players
+    // Knock out players that stay outside of the blast zone
     for (player_ent, (_player_idx, transform)) in entities.iter_with((&player_indexes, &transforms))
     {
-        let pos = transform.translation;
+        let pos = transform.translation.truncate();
+        if blast_zone.contains(pos) || killed_players.contains(player_ent) {
+            out_of_bounds.remove(player_ent);
+            continue;
+        }
 
-        if pos.x < left_kill_zone || pos.x > right_kill_zone || pos.y < bottom_kill_zone {
-            player_events.kill(player_ent, None);
+        let edge = pos.clamp(blast_zone.min, blast_zone.max);
+        let frames_remaining = out_of_bounds
+            .get(player_ent)
+            .map(|x| x.frames_remaining.saturating_sub(1))
+            .unwrap_or(core_meta.blast_zone.grace_frames);
+        out_of_bounds.insert(
+            player_ent,
+            OutOfBounds {
+                frames_remaining,
+                direction: (pos - edge).normalize_or_zero(),
+            },
+        );
+
+        if frames_remaining == 0 {
+            // There's no surviving the blast zone, even right after respawning
+            invulnerables.remove(player_ent);
+
+            // Credit the kill to whoever knocked the player out, if they're still reeling from it
+            // or are still being held by them
+            let killer = hitstuns
+                .get(player_ent)
+                .and_then(|x| x.attacker)
+                .or_else(|| grabbeds.get(player_ent).map(|x| x.grabber));
+            player_events.kill_by(player_ent, Some(edge), killer, Some(key!("blast_zone")));
         }
     }
 
@@ -219,9 +380,7 @@ fn handle_out_of_bounds_players_and_items(
     for (_ent, (respawn_point, transform)) in
         entities.iter_with((&map_respawn_points, &mut transforms))
     {
-        let pos = transform.translation;
-
-        if pos.x < left_kill_zone || pos.x > right_kill_zone || pos.y < bottom_kill_zone {
+        if !blast_zone.contains(transform.translation.truncate()) {
             transform.translation = respawn_point.0;
         }
     }
@@ -0,0 +1,660 @@
+//! Match modes and the rules that decide when a match is over.
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<MatchMode>();
+    session.world.init_resource::<FriendlyFire>();
+    session.world.init_resource::<Stocks>();
+    session.world.init_resource::<MatchTimer>();
+    session.world.init_resource::<Scores>();
+    session.world.init_resource::<MatchState>();
+
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, check_stock_match_end)
+        .add_system_to_stage(CoreStage::Last, update_match_timer)
+        .add_system_to_stage(CoreStage::Last, update_sudden_death);
+}
+
+/// Resource containing the rules of the match being played in the session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TypeUlid)]
+#[ulid = "01GTRZ586Q6HT0T4CCA9DSDNC7"]
+pub enum MatchMode {
+    /// Players respawn forever, and the match never ends on it's own.
+    #[default]
+    Endless,
+    /// Each player has a number of lives, and is eliminated once they have all been lost. The last
+    /// player standing wins.
+    Stock {
+        /// The number of lives that each player starts with.
+        stocks: u32,
+    },
+    /// Players respawn forever, and score a point for every kill. The player with the highest
+    /// score when the time runs out wins.
+    ///
+    /// If the players with the highest score are tied, the match goes into [sudden
+    /// death][MatchPhase::SuddenDeath].
+    Timed {
+        /// The length of the match, in seconds.
+        duration: u32,
+    },
+}
+
+/// Resource containing whether players can hurt their teammates.
+///
+/// This has no effect unless players are on teams.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TypeUlid, Deref)]
+#[ulid = "01GWF818PGQYQX61881G1HMTMQ"]
+pub struct FriendlyFire(pub bool);
+
+/// Resource tracking the lives that each player has left in a [`MatchMode::Stock`] match.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GTRVHTMGP076S9F86R74Y7Y5"]
+pub struct Stocks {
+    /// The number of lives each player has left, including the one they are playing.
+    pub remaining: [u32; MAX_PLAYERS],
+    /// The frame that each player lost their last life on, if they have been eliminated.
+    pub eliminated_on: [Option<u64>; MAX_PLAYERS],
+}
+
+impl Stocks {
+    /// Create the stocks that the players start the match with.
+    pub fn new(mode: MatchMode) -> Self {
+        let stocks = match mode {
+            MatchMode::Stock { stocks } => stocks,
+            MatchMode::Endless | MatchMode::Timed { .. } => 0,
+        };
+        Self {
+            remaining: [stocks; MAX_PLAYERS],
+            eliminated_on: default(),
+        }
+    }
+
+    /// Take a life away from a player on the given frame, eliminating them if it was their last.
+    pub fn lose_stock(&mut self, player_idx: usize, frame: u64) {
+        let remaining = &mut self.remaining[player_idx];
+        *remaining = remaining.saturating_sub(1);
+        if *remaining == 0 && self.eliminated_on[player_idx].is_none() {
+            self.eliminated_on[player_idx] = Some(frame);
+        }
+    }
+
+    /// Give an eliminated player a single life back, such as when they go into sudden death.
+    pub fn revive(&mut self, player_idx: usize) {
+        self.remaining[player_idx] = 1;
+        self.eliminated_on[player_idx] = None;
+    }
+
+    /// Whether the player has lost all of their lives.
+    pub fn is_eliminated(&self, player_idx: usize) -> bool {
+        self.eliminated_on[player_idx].is_some()
+    }
+}
+
+/// Resource counting down the time left in a [`MatchMode::Timed`] match.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GTEV17PW5JGVFYTQHPQKP10S"]
+pub struct MatchTimer {
+    /// The number of simulation frames until the time runs out.
+    pub frames_remaining: u32,
+}
+
+impl MatchTimer {
+    /// Create the timer that the match starts with, in a session that runs at `fps` frames per
+    /// second.
+    pub fn new(mode: MatchMode, fps: f32) -> Self {
+        let frames_remaining = match mode {
+            MatchMode::Timed { duration } => (duration as f32 * fps).round() as u32,
+            MatchMode::Endless | MatchMode::Stock { .. } => 0,
+        };
+        Self { frames_remaining }
+    }
+}
+
+/// Resource containing the number of kills that each player has scored.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GV5PNYPC30ZQWZ4FV5283A3J"]
+pub struct Scores(pub [u32; MAX_PLAYERS]);
+
+impl Scores {
+    /// Get the score of a player's side out of the given `players`, which is the total of their own
+    /// score and their teammates' scores.
+    pub fn side_score(
+        &self,
+        player_inputs: &PlayerInputs,
+        players: &[usize],
+        player_idx: usize,
+    ) -> u32 {
+        players
+            .iter()
+            .filter(|&&i| i == player_idx || player_inputs.are_teammates(i, player_idx))
+            .map(|&i| self.0[i])
+            .sum()
+    }
+
+    /// Get the players whose side has the highest score, see [`Scores::side_score`].
+    pub fn best_players(&self, player_inputs: &PlayerInputs, players: &[usize]) -> Vec<usize> {
+        let best = players
+            .iter()
+            .map(|&i| self.side_score(player_inputs, players, i))
+            .max();
+        players
+            .iter()
+            .copied()
+            .filter(|&i| Some(self.side_score(player_inputs, players, i)) == best)
+            .collect()
+    }
+
+    /// Get the player with the highest score, if there is one side ahead of all the others.
+    ///
+    /// Teammates score together, so when a team is in the lead, this is the first of it's players.
+    pub fn leader(&self, player_inputs: &PlayerInputs, players: &[usize]) -> Option<usize> {
+        let best = self.best_players(player_inputs, players);
+        best.first()
+            .copied()
+            .filter(|_| player_inputs.on_one_side(&best))
+    }
+}
+
+/// The result of a finished match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchResult {
+    /// The index of the player that won the match, or `None` if it was a draw.
+    ///
+    /// When a team wins, this is one of the players on it, see [`PlayerInputs::are_teammates`].
+    pub winner: Option<usize>,
+}
+
+/// The phase of the match that is being played.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MatchPhase {
+    /// The match is being played by it's normal rules.
+    #[default]
+    Regular,
+    /// The match ended in a tie, and the tied players are playing a round where the next kill wins.
+    ///
+    /// Only the tied players are spawned, with reduced health, and the play area shrinks over time.
+    SuddenDeath {
+        /// Whether each player is still in the round.
+        contenders: [bool; MAX_PLAYERS],
+        /// The number of frames since the round started.
+        age: u32,
+    },
+}
+
+/// Resource containing the progress of the match.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GTKGMQVN4ZW0KYYWB3V2BEYK"]
+pub struct MatchState {
+    /// The result of the match, once it is over.
+    pub result: Option<MatchResult>,
+    /// The phase of the match that is being played.
+    pub phase: MatchPhase,
+    /// The frame of the kill that decided the match, if it has been made.
+    ///
+    /// In a stock match, the kill comes a little before the match ends, when the victim loses their
+    /// last life at the end of their death animation.
+    pub decisive_kill: Option<u64>,
+}
+
+impl MatchState {
+    /// Whether the match is in sudden death.
+    pub fn is_sudden_death(&self) -> bool {
+        matches!(self.phase, MatchPhase::SuddenDeath { .. })
+    }
+
+    /// Whether the given player is allowed to spawn in the current phase of the match.
+    pub fn can_spawn(&self, player_idx: usize) -> bool {
+        match &self.phase {
+            MatchPhase::Regular => true,
+            MatchPhase::SuddenDeath { contenders, .. } => contenders[player_idx],
+        }
+    }
+
+    /// Start a sudden-death round between the given players.
+    pub fn start_sudden_death(&mut self, players: impl IntoIterator<Item = usize>) {
+        let mut contenders = [false; MAX_PLAYERS];
+        for player_idx in players {
+            contenders[player_idx] = true;
+        }
+        info!("Match is tied, going into sudden death");
+        self.phase = MatchPhase::SuddenDeath { contenders, age: 0 };
+    }
+
+    /// Whether killing the given player decides the match, by leaving at most one side in it.
+    pub fn is_decisive_kill(
+        &self,
+        mode: MatchMode,
+        stocks: &Stocks,
+        player_inputs: &PlayerInputs,
+        victim: usize,
+    ) -> bool {
+        if self.result.is_some() || self.decisive_kill.is_some() {
+            return false;
+        }
+
+        match (&self.phase, mode) {
+            (MatchPhase::SuddenDeath { contenders, .. }, _) => {
+                let others = (0..MAX_PLAYERS)
+                    .filter(|&i| i != victim && contenders[i])
+                    .collect::<Vec<_>>();
+                contenders[victim] && player_inputs.on_one_side(&others)
+            }
+            (MatchPhase::Regular, MatchMode::Stock { .. }) => {
+                let others = (0..MAX_PLAYERS)
+                    .filter(|&i| {
+                        i != victim && player_inputs.players[i].active && !stocks.is_eliminated(i)
+                    })
+                    .collect::<Vec<_>>();
+                stocks.remaining[victim] <= 1
+                    && !stocks.is_eliminated(victim)
+                    && player_inputs.on_one_side(&others)
+            }
+            // Timed matches are decided by the clock
+            (MatchPhase::Regular, MatchMode::Endless | MatchMode::Timed { .. }) => false,
+        }
+    }
+
+    /// Take a player out of the sudden-death round, if there is one, after they were killed.
+    pub fn knock_out(&mut self, player_idx: usize) {
+        if let MatchPhase::SuddenDeath { contenders, .. } = &mut self.phase {
+            contenders[player_idx] = false;
+        }
+    }
+
+    /// How close the match is to being decided, from `0.0` when it starts to `1.0` when the next
+    /// kill may end it, for the game to pace it's music to.
+    ///
+    /// Stock matches heat up as the player closest to elimination loses their lives, and timed
+    /// matches over the last [`INTENSITY_RAMP_SECONDS`] on the clock. Sudden death is always as
+    /// intense as it gets, and endless matches never heat up. The clock is counted in frames of the
+    /// session's `fps`.
+    pub fn intensity(
+        &self,
+        mode: MatchMode,
+        stocks: &Stocks,
+        timer: &MatchTimer,
+        player_inputs: &PlayerInputs,
+        fps: f32,
+    ) -> f32 {
+        if self.result.is_some() {
+            return 0.0;
+        }
+
+        match (&self.phase, mode) {
+            (MatchPhase::SuddenDeath { .. }, _) => 1.0,
+            (MatchPhase::Regular, MatchMode::Stock { stocks: lives }) => {
+                let fewest_lives = (0..MAX_PLAYERS)
+                    .filter(|&i| player_inputs.players[i].active && !stocks.is_eliminated(i))
+                    .map(|i| stocks.remaining[i])
+                    .min()
+                    .unwrap_or(lives);
+                let lives_lost = lives.saturating_sub(fewest_lives);
+                (lives_lost as f32 / lives.saturating_sub(1).max(1) as f32).clamp(0.0, 1.0)
+            }
+            (MatchPhase::Regular, MatchMode::Timed { .. }) => {
+                let ramp_frames = INTENSITY_RAMP_SECONDS * fps;
+                (1.0 - timer.frames_remaining as f32 / ramp_frames).clamp(0.0, 1.0)
+            }
+            (MatchPhase::Regular, MatchMode::Endless) => 0.0,
+        }
+    }
+}
+
+/// The time left on the clock of a [`MatchMode::Timed`] match when it starts heating up, see
+/// [`MatchState::intensity`].
+pub const INTENSITY_RAMP_SECONDS: f32 = 30.0;
+
+/// Get the area that the players must stay in, the given number of frames into sudden death.
+///
+/// The area starts out covering the whole map, and shrinks towards it's center after a delay.
+pub fn sudden_death_area(map: &MapMeta, meta: &SuddenDeathMeta, age: u32) -> Rect {
+    let map_size = map.grid_size.as_vec2() * map.tile_size;
+    let shrunk = age.saturating_sub(meta.shrink_delay_frames) as f32 * meta.shrink_speed * 2.0;
+    let size = (map_size - Vec2::splat(shrunk)).max(meta.min_area_size.min(map_size));
+    let center = map_size / 2.0;
+
+    Rect::new(center.x, center.y, size.x, size.y)
+}
+
+/// End a stock match once the players who haven't been eliminated are all on the same side.
+fn check_stock_match_end(
+    mode: Res<MatchMode>,
+    mut stocks: ResMut<Stocks>,
+    player_inputs: Res<PlayerInputs>,
+    mut match_state: ResMut<MatchState>,
+) {
+    if !matches!(*mode, MatchMode::Stock { .. })
+        || match_state.result.is_some()
+        // Sudden death decides the winner on it's own
+        || match_state.is_sudden_death()
+    {
+        return;
+    }
+
+    let players = (0..MAX_PLAYERS)
+        .filter(|&i| player_inputs.players[i].active)
+        .collect::<Vec<_>>();
+    // Nobody has been eliminated yet, so the match has only just started
+    if !players.iter().any(|&i| stocks.is_eliminated(i)) {
+        return;
+    }
+
+    let remaining = players
+        .iter()
+        .copied()
+        .filter(|&i| !stocks.is_eliminated(i))
+        .collect::<Vec<_>>();
+    match remaining.first() {
+        Some(&winner) if player_inputs.on_one_side(&remaining) => {
+            match_state.result = Some(MatchResult {
+                winner: Some(winner),
+            })
+        }
+        // The last players were eliminated at the same time, so they go into sudden death
+        None if !match_state.is_sudden_death() => {
+            let last_frame = players
+                .iter()
+                .filter_map(|&i| stocks.eliminated_on[i])
+                .max();
+            let tied = players
+                .iter()
+                .copied()
+                .filter(|&i| stocks.eliminated_on[i] == last_frame)
+                .collect::<Vec<_>>();
+            for &player_idx in &tied {
+                stocks.revive(player_idx);
+            }
+            match_state.start_sudden_death(tied);
+        }
+        _ => (),
+    }
+}
+
+/// Count down the time left in a timed match, ending it when the time runs out.
+fn update_match_timer(
+    mode: Res<MatchMode>,
+    scores: Res<Scores>,
+    player_inputs: Res<PlayerInputs>,
+    mut timer: ResMut<MatchTimer>,
+    mut match_state: ResMut<MatchState>,
+) {
+    if !matches!(*mode, MatchMode::Timed { .. })
+        || match_state.result.is_some()
+        || match_state.is_sudden_death()
+    {
+        return;
+    }
+
+    timer.frames_remaining = timer.frames_remaining.saturating_sub(1);
+    if timer.frames_remaining > 0 {
+        return;
+    }
+
+    let players = (0..MAX_PLAYERS)
+        .filter(|&i| player_inputs.players[i].active)
+        .collect::<Vec<_>>();
+    if let Some(winner) = scores.leader(&player_inputs, &players) {
+        match_state.result = Some(MatchResult {
+            winner: Some(winner),
+        });
+    } else {
+        match_state.start_sudden_death(scores.best_players(&player_inputs, &players));
+    }
+}
+
+/// Run the sudden-death round, ending the match once at most one of the tied players is left.
+fn update_sudden_death(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    transforms: Comp<Transform>,
+    mut healths: CompMut<Health>,
+    mut player_events: ResMut<PlayerEvents>,
+    mut match_state: ResMut<MatchState>,
+) {
+    if match_state.result.is_some() {
+        return;
+    }
+    let MatchPhase::SuddenDeath { contenders, age } = &mut match_state.phase else {
+        return;
+    };
+    let meta = &core_meta.sudden_death;
+
+    // Reset everybody's position by despawning all of the players, so that the contenders respawn
+    if *age == 0 {
+        for (player_ent, _player_idx) in entities.iter_with(&player_indexes) {
+            player_events.despawn(player_ent);
+        }
+    }
+    *age = age.saturating_add(1);
+    let age = *age;
+
+    let remaining = (0..MAX_PLAYERS)
+        .filter(|&i| contenders[i])
+        .collect::<Vec<_>>();
+    match remaining.first() {
+        Some(&winner) if player_inputs.on_one_side(&remaining) => {
+            match_state.result = Some(MatchResult {
+                winner: Some(winner),
+            });
+            return;
+        }
+        // The last contenders were killed on the same frame
+        None => {
+            match_state.result = Some(MatchResult { winner: None });
+            return;
+        }
+        _ => (),
+    }
+
+    let area = sudden_death_area(&map.0, meta, age);
+    for (player_ent, (_player_idx, transform, health)) in
+        entities.iter_with((&player_indexes, &transforms, &mut healths))
+    {
+        // Contenders play with reduced health, so that the round is over quickly
+        health.max = health.max.min(meta.health);
+        health.current = health.current.min(health.max);
+
+        if !health.is_depleted() && !area.contains(transform.translation.truncate()) {
+            player_events.kill(player_ent, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sudden_death_world(contenders: [usize; 2]) -> World {
+        let mut world = World::new();
+        world.init_resource::<CoreMetaArc>();
+        world.init_resource::<LoadedMap>();
+        world.init_resource::<PlayerEvents>();
+        world.init_resource::<PlayerInputs>();
+        world.init_resource::<MatchState>();
+        world
+            .resource::<MatchState>()
+            .borrow_mut()
+            .start_sudden_death(contenders);
+        world
+    }
+
+    fn run_sudden_death(world: &mut World) -> Option<MatchResult> {
+        world
+            .run_initialized_system(
+                |entities: Res<Entities>,
+                 core_meta: Res<CoreMetaArc>,
+                 map: Res<LoadedMap>,
+                 player_inputs: Res<PlayerInputs>,
+                 player_indexes: Comp<PlayerIdx>,
+                 transforms: Comp<Transform>,
+                 healths: CompMut<Health>,
+                 player_events: ResMut<PlayerEvents>,
+                 match_state: ResMut<MatchState>| {
+                    update_sudden_death(
+                        entities,
+                        core_meta,
+                        map,
+                        player_inputs,
+                        player_indexes,
+                        transforms,
+                        healths,
+                        player_events,
+                        match_state,
+                    );
+                    Ok(())
+                },
+            )
+            .unwrap();
+        world.resource::<MatchState>().borrow().result
+    }
+
+    #[test]
+    fn next_kill_wins_sudden_death() {
+        let mut world = sudden_death_world([0, 2]);
+        assert_eq!(run_sudden_death(&mut world), None);
+
+        world.resource::<MatchState>().borrow_mut().knock_out(2);
+        assert_eq!(
+            run_sudden_death(&mut world),
+            Some(MatchResult { winner: Some(0) })
+        );
+    }
+
+    #[test]
+    fn sudden_death_is_a_draw_when_both_players_die_together() {
+        let mut world = sudden_death_world([1, 3]);
+        assert_eq!(run_sudden_death(&mut world), None);
+
+        let mut match_state = world.resource::<MatchState>().borrow_mut();
+        match_state.knock_out(1);
+        match_state.knock_out(3);
+        drop(match_state);
+        assert_eq!(
+            run_sudden_death(&mut world),
+            Some(MatchResult { winner: None })
+        );
+    }
+
+    #[test]
+    fn only_the_last_kill_decides_the_match() {
+        let mode = MatchMode::Stock { stocks: 2 };
+        let mut stocks = Stocks::new(mode);
+        let mut player_inputs = PlayerInputs::default();
+        for player in &mut player_inputs.players[..3] {
+            player.active = true;
+        }
+        let mut match_state = MatchState::default();
+
+        // Players with lives left, or more than one opponent left, keep the match going
+        assert!(!match_state.is_decisive_kill(mode, &stocks, &player_inputs, 0));
+        stocks.lose_stock(0, 10);
+        assert!(!match_state.is_decisive_kill(mode, &stocks, &player_inputs, 0));
+
+        stocks.lose_stock(0, 20);
+        stocks.lose_stock(1, 30);
+        assert!(match_state.is_decisive_kill(mode, &stocks, &player_inputs, 1));
+        match_state.decisive_kill = Some(30);
+        assert!(!match_state.is_decisive_kill(mode, &stocks, &player_inputs, 2));
+
+        // Every kill in sudden death between two players is decisive
+        let mut match_state = MatchState::default();
+        match_state.start_sudden_death([1, 2]);
+        assert!(match_state.is_decisive_kill(MatchMode::default(), &stocks, &player_inputs, 2));
+        assert!(!match_state.is_decisive_kill(MatchMode::default(), &stocks, &player_inputs, 0));
+    }
+
+    #[test]
+    fn teammates_win_together() {
+        let mode = MatchMode::Stock { stocks: 1 };
+        let mut world = World::new();
+        world.insert_resource(mode);
+        world.insert_resource(Stocks::new(mode));
+        world.init_resource::<MatchState>();
+        let mut player_inputs = PlayerInputs::default();
+        for (player, team) in player_inputs.players.iter_mut().zip([0, 1, 0, 1]) {
+            player.active = true;
+            player.team = Some(team);
+        }
+        world.insert_resource(player_inputs.clone());
+        let mut check_match_end = |eliminated| {
+            world
+                .resource::<Stocks>()
+                .borrow_mut()
+                .lose_stock(eliminated, 10);
+            world
+                .run_initialized_system(
+                    |mode: Res<MatchMode>,
+                     stocks: ResMut<Stocks>,
+                     player_inputs: Res<PlayerInputs>,
+                     match_state: ResMut<MatchState>| {
+                        check_stock_match_end(mode, stocks, player_inputs, match_state);
+                        Ok(())
+                    },
+                )
+                .unwrap();
+            world.resource::<MatchState>().borrow().result
+        };
+
+        // The match goes on while each team has someone left, and a lone teammate isn't a threat
+        assert_eq!(check_match_end(1), None);
+        let stocks = Stocks::new(mode);
+        assert!(!MatchState::default().is_decisive_kill(mode, &stocks, &player_inputs, 1));
+        assert_eq!(check_match_end(3), Some(MatchResult { winner: Some(0) }));
+
+        // Teammates' kills count together
+        let scores = Scores([3, 2, 0, 2]);
+        let players = [0, 1, 2, 3];
+        assert_eq!(scores.leader(&player_inputs, &players), Some(1));
+        assert_eq!(scores.leader(&PlayerInputs::default(), &players), Some(0));
+        let scores = Scores([2, 2, 0, 0]);
+        assert_eq!(scores.leader(&player_inputs, &players), None);
+        assert_eq!(scores.best_players(&player_inputs, &players), players);
+    }
+
+    #[test]
+    fn intensity_rises_as_the_match_is_decided() {
+        let mode = MatchMode::Stock { stocks: 3 };
+        let mut stocks = Stocks::new(mode);
+        let timer = MatchTimer::default();
+        let mut player_inputs = PlayerInputs::default();
+        for player in &mut player_inputs.players[..2] {
+            player.active = true;
+        }
+        let mut match_state = MatchState::default();
+        let intensity = |match_state: &MatchState, stocks: &Stocks| {
+            match_state.intensity(mode, stocks, &timer, &player_inputs, crate::FPS)
+        };
+
+        // The player closest to elimination sets the pace
+        assert_eq!(intensity(&match_state, &stocks), 0.0);
+        stocks.lose_stock(1, 10);
+        assert_eq!(intensity(&match_state, &stocks), 0.5);
+        stocks.lose_stock(1, 20);
+        assert_eq!(intensity(&match_state, &stocks), 1.0);
+
+        // The clock only counts in timed matches, in frames of the session's rate
+        let timed = MatchMode::Timed { duration: 60 };
+        let fps = 2.0 * crate::FPS;
+        let mut timer = MatchTimer::new(timed, fps);
+        assert_eq!(timer.frames_remaining, (60.0 * fps) as u32);
+        assert_eq!(
+            match_state.intensity(timed, &stocks, &timer, &player_inputs, fps),
+            0.0
+        );
+        timer.frames_remaining = (INTENSITY_RAMP_SECONDS * fps) as u32 / 2;
+        assert_eq!(
+            match_state.intensity(timed, &stocks, &timer, &player_inputs, fps),
+            0.5
+        );
+
+        match_state.start_sudden_death([0, 1]);
+        assert_eq!(intensity(&match_state, &stocks), 1.0);
+        match_state.result = Some(MatchResult { winner: Some(0) });
+        assert_eq!(intensity(&match_state, &stocks), 0.0);
+    }
+}
@@ -8,11 +8,13 @@ mod common;
 mod element;
 mod map;
 mod player;
+mod validation;
 
 pub use common::*;
 pub use element::*;
 pub use map::*;
 pub use player::*;
+pub use validation::*;
 
 /// Resource containing the session's [`CoreMeta`].
 ///
@@ -39,14 +41,714 @@ impl ::bevy::prelude::Plugin for JumpyCoreAssetsPlugin {
 #[ulid = "01GNWT2Q8EZ5CEV3MHWNMGEEA6"]
 #[serde(deny_unknown_fields)]
 pub struct CoreMeta {
+    /// The version of the gameplay metadata.
+    ///
+    /// This should be bumped whenever a change to the metadata would make old replays play out
+    /// differently.
+    #[serde(default)]
+    pub version: u32,
     pub camera: CameraMeta,
     pub physics: PhysicsMeta,
+    pub respawn: RespawnMeta,
+    pub death: DeathMeta,
+    pub dash: DashMeta,
+    pub ledge_grab: LedgeGrabMeta,
+    pub hitstun: HitstunMeta,
+    pub hit_stop: HitStopMeta,
+    pub combo: ComboMeta,
+    pub shield: ShieldMeta,
+    pub taunt: TauntMeta,
+    pub emotes: EmotesMeta,
+    pub bots: BotMeta,
+    pub swim: SwimMeta,
+    pub sudden_death: SuddenDeathMeta,
+    pub grab: GrabMeta,
+    pub player_grab: PlayerGrabMeta,
+    pub blast_zone: BlastZoneGraceMeta,
+    pub particles: ParticlesMeta,
+    pub decals: DecalsMeta,
+    pub destructible: DestructibleMeta,
     pub players: Vec<Handle<PlayerMeta>>,
+    /// The color accents that players may pick from to tint their character.
+    #[serde(default)]
+    pub player_colors: Vec<ColorMeta>,
+    /// The color that the players on each team are tinted with, by team.
+    #[serde(default)]
+    pub team_colors: Vec<ColorMeta>,
     pub stable_maps: Vec<Handle<MapMeta>>,
     pub map_elements: Vec<Handle<ElementMeta>>,
     pub experimental_maps: Vec<Handle<MapMeta>>,
 }
 
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct RespawnMeta {
+    /// The number of frames that players are invulnerable for after they spawn.
+    pub invulnerability_frames: u32,
+    /// The number of frames between each flash of an invulnerable player's sprite.
+    pub flash_interval: u32,
+    /// The number of frames that players wait to respawn after their death animation is over.
+    pub delay_frames: u32,
+    /// The platform that players respawn on, if they respawn on one at all.
+    pub platform: Option<RespawnPlatformMeta>,
+}
+
+impl Default for RespawnMeta {
+    fn default() -> Self {
+        Self {
+            invulnerability_frames: 120,
+            flash_interval: 4,
+            delay_frames: 0,
+            platform: None,
+        }
+    }
+}
+
+/// A platform that players respawn on, above the spawn point.
+///
+/// Players are invulnerable while they stand on the platform, and it disappears as soon as they
+/// move or take an action. Only the respawning player can stand on it, and it can be dropped
+/// through.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct RespawnPlatformMeta {
+    /// The size of the platform, in pixels.
+    pub size: Vec2,
+    /// How far above the spawn point the player respawns, in pixels.
+    pub height: f32,
+    /// The color that the platform is drawn with.
+    pub color: ColorMeta,
+    /// The most frames that the player may stay on the platform for.
+    pub max_frames: u32,
+}
+
+impl Default for RespawnPlatformMeta {
+    fn default() -> Self {
+        Self {
+            size: vec2(48.0, 4.0),
+            height: 64.0,
+            color: ColorMeta([1.0, 1.0, 1.0, 0.8]),
+            max_frames: 240,
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct DashMeta {
+    /// The maximum number of frames between two taps of a direction for them to trigger a dash.
+    pub double_tap_frames: u32,
+    /// The distance that a dash covers, in pixels.
+    pub distance: f32,
+    /// The number of frames that a dash lasts for.
+    ///
+    /// Players are invulnerable for the duration of the dash.
+    pub duration_frames: u32,
+    /// The number of frames after a dash starts before the player can dash again.
+    pub cooldown_frames: u32,
+}
+
+impl Default for DashMeta {
+    fn default() -> Self {
+        Self {
+            double_tap_frames: 12,
+            distance: 96.0,
+            duration_frames: 8,
+            cooldown_frames: 40,
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct LedgeGrabMeta {
+    /// How far below the top of a falling player a ledge may be for them to grab it, in pixels.
+    pub grab_range: f32,
+    /// The offset of the top corner of a hanging player from the corner of the ledge.
+    ///
+    /// The horizontal offset is mirrored depending on which side of the player the ledge is on.
+    pub hang_offset: Vec2,
+    /// The number of frames that a player is invulnerable for after grabbing a ledge.
+    pub invulnerability_frames: u32,
+}
+
+impl Default for LedgeGrabMeta {
+    fn default() -> Self {
+        Self {
+            grab_range: 8.0,
+            hang_offset: vec2(0.0, 8.0),
+            invulnerability_frames: 20,
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct HitstunMeta {
+    /// The health that players start with.
+    ///
+    /// Non-lethal hits take away from the player's health, and the player is killed once it
+    /// reaches zero.
+    pub max_health: f32,
+    /// The speed that a player is knocked back at when hit.
+    pub knockback_speed: f32,
+    /// The amount that the knockback speed is multiplied by every frame.
+    pub knockback_decay: f32,
+    /// Points on the curve mapping the damage of a hit ( `x` ) to the number of frames of
+    /// hitstun ( `y` ).
+    ///
+    /// The hitstun length is linearly interpolated between the points, which must be sorted by
+    /// damage.
+    pub curve: Vec<Vec2>,
+    /// How far a player that is launched by a hit can bend their launch, by holding a direction
+    /// across it ( directional influence ).
+    ///
+    /// This is the fraction of the launch speed that is turned sideways when holding straight
+    /// across the launch, so `0.0` disables directional influence.
+    pub di_strength: f32,
+}
+
+impl Default for HitstunMeta {
+    fn default() -> Self {
+        Self {
+            max_health: 100.0,
+            knockback_speed: 8.0,
+            knockback_decay: 0.85,
+            curve: vec![vec2(0.0, 10.0), vec2(50.0, 30.0), vec2(100.0, 60.0)],
+            di_strength: 0.3,
+        }
+    }
+}
+
+impl HitstunMeta {
+    /// Get the number of frames of hitstun for a hit with the given damage.
+    pub fn hitstun_frames(&self, damage: f32) -> u32 {
+        sample_curve(&self.curve, damage).map_or(0, |frames| frames.round() as u32)
+    }
+
+    /// Bend the `knockback` velocity of a hit by the directional influence of the player that was
+    /// hit holding `move_direction`.
+    ///
+    /// Only the part of the input that is across the launch counts, and the launch keeps its
+    /// speed, so directional influence changes where the player flies, but not how fast.
+    pub fn directional_influence(&self, knockback: Vec2, move_direction: Vec2) -> Vec2 {
+        let speed = knockback.length();
+        if speed == 0.0 {
+            return knockback;
+        }
+        let launch = knockback / speed;
+        let across = launch.perp();
+        let influence = move_direction.clamp_length_max(1.0).dot(across) * self.di_strength;
+
+        (launch + across * influence).normalize_or_zero() * speed
+    }
+}
+
+/// The hit-stop that briefly freezes the players involved in a hit, to sell the impact.
+///
+/// Only the players that were hit, and the players that hit them, are frozen. The rest of the match
+/// plays on as usual.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct HitStopMeta {
+    /// The freeze for melee hits, such as specials and throws.
+    pub melee: HitStopFrames,
+    /// The freeze for hits from bullets and other projectiles.
+    pub projectile: HitStopFrames,
+    /// The freeze for hits from explosions.
+    pub explosion: HitStopFrames,
+    /// The freeze for hits from hazards, such as spikes.
+    pub hazard: HitStopFrames,
+    /// The freeze when a player is killed, which is usually longer than for any hit.
+    ///
+    /// This isn't scaled by the damage.
+    pub kill: HitStopFrames,
+    /// The number of frames added to the freeze of a hit for every point of damage it deals.
+    pub frames_per_damage: f32,
+    /// The longest that a hit may freeze the players for, in frames.
+    pub max_frames: u32,
+}
+
+/// The number of frames that the players involved in a hit are frozen for.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct HitStopFrames {
+    /// The frames that the player who was hit is frozen for.
+    pub victim: u32,
+    /// The frames that the player who dealt the hit is frozen for, if any.
+    pub attacker: u32,
+}
+
+impl Default for HitStopMeta {
+    fn default() -> Self {
+        Self {
+            melee: HitStopFrames {
+                victim: 4,
+                attacker: 4,
+            },
+            projectile: HitStopFrames {
+                victim: 2,
+                attacker: 0,
+            },
+            explosion: HitStopFrames {
+                victim: 4,
+                attacker: 0,
+            },
+            hazard: HitStopFrames {
+                victim: 2,
+                attacker: 0,
+            },
+            kill: HitStopFrames {
+                victim: 12,
+                attacker: 8,
+            },
+            frames_per_damage: 0.1,
+            max_frames: 12,
+        }
+    }
+}
+
+impl HitStopMeta {
+    /// Get the number of frames that a hit of the given kind and damage freezes the players for.
+    ///
+    /// Players that aren't frozen by the kind of hit at all aren't frozen by its damage either.
+    pub fn hit_frames(&self, kind: HitKind, damage: f32) -> HitStopFrames {
+        let base = match kind {
+            HitKind::Melee => self.melee,
+            HitKind::Projectile => self.projectile,
+            HitKind::Explosion => self.explosion,
+            HitKind::Hazard => self.hazard,
+        };
+        let extra = (damage.max(0.0) * self.frames_per_damage).round() as u32;
+        let scale = |frames: u32| {
+            if frames == 0 {
+                0
+            } else {
+                (frames + extra).min(self.max_frames)
+            }
+        };
+        HitStopFrames {
+            victim: scale(base.victim),
+            attacker: scale(base.attacker),
+        }
+    }
+}
+
+/// Linearly interpolate the `y` of a curve at `x`, between the points of the curve, which must be
+/// sorted by `x`.
+///
+/// Returns `None` if the curve has no points.
+fn sample_curve(curve: &[Vec2], x: f32) -> Option<f32> {
+    let first = curve.first()?;
+    if x <= first.x {
+        return Some(first.y);
+    }
+
+    for points in curve.windows(2) {
+        let (a, b) = (points[0], points[1]);
+        if x <= b.x {
+            let t = (x - a.x) / (b.x - a.x).max(f32::EPSILON);
+            return Some(a.y + (b.y - a.y) * t);
+        }
+    }
+
+    curve.last().map(|point| point.y)
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ComboMeta {
+    /// The number of frames after a player is hit that another hit continues the combo.
+    pub window_frames: u64,
+    /// Points on the curve mapping the number of hits already in a combo ( `x` ) to the amount
+    /// that the damage of the next hit is multiplied by ( `y` ).
+    ///
+    /// The multiplier is linearly interpolated between the points, which must be sorted by the
+    /// number of hits.
+    pub damage_scaling: Vec<Vec2>,
+}
+
+impl Default for ComboMeta {
+    fn default() -> Self {
+        Self {
+            window_frames: 60,
+            damage_scaling: vec![vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(6.0, 0.4)],
+        }
+    }
+}
+
+impl ComboMeta {
+    /// Get the amount that the damage of a hit is scaled by, given the number of hits that are
+    /// already in the combo.
+    pub fn damage_multiplier(&self, hits: u32) -> f32 {
+        sample_curve(&self.damage_scaling, hits as f32).unwrap_or(1.0)
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ShieldMeta {
+    /// The amount of damage that a full shield can absorb.
+    pub size: f32,
+    /// How much of the shield regenerates every frame that the player isn't blocking.
+    pub regen_rate: f32,
+    /// The fraction of the damage of a blocked hit that the shield absorbs.
+    pub damage_reduction: f32,
+    /// The fraction of the knockback of a blocked hit that the shield absorbs.
+    pub knockback_reduction: f32,
+    /// The number of frames that a player is stunned for when their shield breaks.
+    pub break_stun_frames: u32,
+    /// The sound played when a hit is blocked.
+    pub block_sound: Handle<AudioSource>,
+    pub block_sound_volume: f32,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TauntMeta {
+    /// The number of frames that a taunt lasts for, unless it is cancelled.
+    pub duration_frames: u32,
+    /// The sound played when a player taunts.
+    pub sound: Handle<AudioSource>,
+    pub sound_volume: f32,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EmotesMeta {
+    /// The number of frames after emoting until the player can emote again.
+    pub cooldown_frames: u32,
+    /// The number of frames that an emote is shown for.
+    pub duration_frames: u32,
+    /// Where emotes are shown, relative to the center of the player.
+    pub offset: Vec2,
+    /// The emotes on the emote wheel, clockwise from the top.
+    pub wheel: Vec<EmoteMeta>,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct BotMeta {
+    /// The number of frames that the easiest bots take to react to what is happening.
+    pub max_reaction_frames: u32,
+    /// The number of frames that the hardest bots take to react to what is happening.
+    pub min_reaction_frames: u32,
+    /// How close an opponent must be for a bot to attack them, in pixels.
+    pub attack_range: f32,
+    /// How close a bot may get to a hazard before it runs away from it, in pixels.
+    pub hazard_distance: f32,
+    /// How close a bot may get to the edge of the blast zone before it heads back, in pixels.
+    pub edge_distance: f32,
+    /// How far above a bot an opponent must be for the bot to jump towards them, in pixels.
+    pub jump_height: f32,
+    /// How far off the easiest bots aim, in degrees. Harder bots aim better.
+    pub max_aim_error: f32,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EmoteMeta {
+    /// The name of the emote, which its label is looked up with in the localization.
+    pub name: String,
+    pub atlas: Handle<Atlas>,
+    /// The index of the emote's sprite in the atlas.
+    #[serde(default)]
+    pub index: usize,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct SwimMeta {
+    /// The upward acceleration applied to players in water, on top of gravity.
+    pub buoyancy: f32,
+    /// The maximum speed that players sink at.
+    pub max_sink_speed: f32,
+    /// The maximum horizontal speed of swimming players.
+    pub swim_speed: f32,
+    /// The horizontal acceleration of swimming players.
+    pub accel_swim_speed: f32,
+    /// The upward speed a player gets when kicking with the jump button.
+    pub kick_speed: f32,
+    /// The number of frames that a player can stay in the water before they drown.
+    pub drown_frames: u32,
+}
+
+impl Default for SwimMeta {
+    fn default() -> Self {
+        Self {
+            buoyancy: 0.5,
+            max_sink_speed: 1.5,
+            swim_speed: 3.0,
+            accel_swim_speed: 0.5,
+            kick_speed: 5.0,
+            drown_frames: 600,
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct GrabMeta {
+    /// How far away from the center of a player an item may be for them to grab it, in pixels.
+    pub range: f32,
+}
+
+impl Default for GrabMeta {
+    fn default() -> Self {
+        Self { range: 32.0 }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct PlayerGrabMeta {
+    /// How far away from the center of a player an opponent may be for them to grab it, in pixels.
+    pub range: f32,
+    /// Where the grabbed player is held, relative to the grabber when they are facing right.
+    pub hold_offset: Vec2,
+    /// The number of frames that a player may hold an opponent before automatically throwing them.
+    pub max_hold_frames: u32,
+    /// The number of button presses it takes for a grabbed player to break free.
+    pub struggle_presses: u32,
+    /// The speed that both players are pushed apart with when a grab is broken.
+    pub break_speed: f32,
+    /// The damage dealt to a thrown player.
+    pub throw_damage: f32,
+    /// How hard a thrown player is knocked back, relative to the usual knockback speed.
+    pub throw_knockback: f32,
+}
+
+impl Default for PlayerGrabMeta {
+    fn default() -> Self {
+        Self {
+            range: 24.0,
+            hold_offset: vec2(16.0, 4.0),
+            max_hold_frames: 120,
+            struggle_presses: 8,
+            break_speed: 4.0,
+            throw_damage: 10.0,
+            throw_knockback: 1.5,
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DestructibleMeta {
+    /// The sound played when a destructible tile breaks.
+    pub break_sound: Handle<AudioSource>,
+    pub break_sound_volume: f32,
+    /// The number of pieces of debris that a tile breaks into.
+    pub debris_count: u32,
+    /// How fast the debris flies away from the broken tile, in pixels per frame.
+    pub debris_speed: f32,
+    /// The size of the debris, relative to the size of a tile.
+    pub debris_scale: f32,
+    /// How long the debris lasts for, in seconds.
+    pub debris_lifetime: f32,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ParticlesMeta {
+    /// The maximum number of particles that may be alive at once.
+    ///
+    /// New particles aren't spawned while the limit is reached.
+    pub max_particles: usize,
+    /// The dust kicked up when a player lands.
+    pub dust: ParticleMeta,
+    /// The sparks thrown off when a hit on a player is blocked.
+    pub sparks: ParticleMeta,
+}
+
+/// A burst of particles.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ParticleMeta {
+    pub atlas: Handle<Atlas>,
+    /// The number of frames in the atlas, which the particles play through over their lifetime.
+    pub frames: usize,
+    /// The number of particles in the burst.
+    pub count: u32,
+    /// How fast the particles fly away from where they are emitted, in pixels per frame.
+    pub speed: f32,
+    /// How much the speed of each particle may randomly differ from `speed`, in pixels per frame.
+    pub speed_variation: f32,
+    /// The angle, in degrees, of the cone that the particles are spread over, centered on the
+    /// direction that they are emitted in.
+    pub spread: f32,
+    /// How fast the particles fall, in pixels per frame squared.
+    pub gravity: f32,
+    /// The number of frames that each particle lives for, fading out as it ages.
+    pub lifetime_frames: u32,
+    /// The size of the particles, relative to the size of the atlas tiles.
+    pub scale: f32,
+}
+
+impl Default for ParticleMeta {
+    fn default() -> Self {
+        Self {
+            atlas: default(),
+            frames: 1,
+            count: 0,
+            speed: 0.0,
+            speed_variation: 0.0,
+            spread: 0.0,
+            gravity: 0.0,
+            lifetime_frames: 30,
+            scale: 1.0,
+        }
+    }
+}
+
+/// The splatter decals left on the map where players are hit and killed.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct DecalsMeta {
+    /// Whether to leave decals at all.
+    ///
+    /// Decals are entities in the simulation, so this is part of the metadata that every player
+    /// in a networked game shares, instead of a local setting.
+    pub enabled: bool,
+    /// The maximum number of decals that may be on the map at once.
+    ///
+    /// Once the limit is reached, the oldest decal is moved to make room for each new one.
+    pub max_decals: usize,
+    pub atlas: Handle<Atlas>,
+    /// The number of frames in the atlas, which each decal picks one of.
+    pub frames: usize,
+    /// The color that the decals are tinted with.
+    pub color: ColorMeta,
+    /// How far from the hit the decals may be splattered, in pixels.
+    pub scatter: f32,
+    /// The number of frames that each decal takes to fade away.
+    pub lifetime_frames: u32,
+    /// The size of the decals, relative to the size of the atlas tiles.
+    pub scale: f32,
+}
+
+impl Default for DecalsMeta {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_decals: 64,
+            atlas: default(),
+            frames: 1,
+            color: ColorMeta([1.0, 1.0, 1.0, 1.0]),
+            scatter: 8.0,
+            lifetime_frames: 300,
+            scale: 1.0,
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct BlastZoneGraceMeta {
+    /// The number of frames that a player may spend outside of the blast zone, trying to get back
+    /// in, before they are knocked out.
+    pub grace_frames: u32,
+}
+
+impl Default for BlastZoneGraceMeta {
+    fn default() -> Self {
+        Self { grace_frames: 30 }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct SuddenDeathMeta {
+    /// The health that players have during sudden death.
+    pub health: f32,
+    /// The number of frames into sudden death before the play area starts shrinking.
+    pub shrink_delay_frames: u32,
+    /// How fast each side of the play area moves inwards, in pixels per frame.
+    pub shrink_speed: f32,
+    /// The size that the play area stops shrinking at.
+    pub min_area_size: Vec2,
+}
+
+impl Default for SuddenDeathMeta {
+    fn default() -> Self {
+        Self {
+            health: 25.0,
+            shrink_delay_frames: 300,
+            shrink_speed: 0.5,
+            min_area_size: vec2(300.0, 200.0),
+        }
+    }
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct DeathMeta {
+    /// Whether killed players should be knocked away as a physics-driven ragdoll, instead of
+    /// playing a death animation.
+    pub ragdoll_on_death: bool,
+    /// The speed that a ragdoll is knocked away from whatever hit it.
+    pub ragdoll_knockback: f32,
+    /// How fast a ragdoll spins, in degrees per second.
+    pub ragdoll_spin: f32,
+    /// How long the corpse of a killed player lingers before it is despawned.
+    ///
+    /// A player can't respawn while their corpse is still around.
+    pub corpse_timeout: CorpseTimeout,
+}
+
+impl Default for DeathMeta {
+    fn default() -> Self {
+        Self {
+            ragdoll_on_death: false,
+            ragdoll_knockback: 10.0,
+            ragdoll_spin: 540.0,
+            corpse_timeout: default(),
+        }
+    }
+}
+
+/// How long the corpse of a killed player lingers, in [`DeathMeta::corpse_timeout`].
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub enum CorpseTimeout {
+    /// The corpse is despawned once it has been dead for the given number of frames.
+    After { frames: u64 },
+    /// The corpse stays until the match is over, so the player sits out the rest of it.
+    UntilMatchEnds,
+}
+
+impl Default for CorpseTimeout {
+    fn default() -> Self {
+        Self::After { frames: 80 }
+    }
+}
+
+impl CorpseTimeout {
+    /// Get the number of frames that the corpse lingers for, if it is ever despawned.
+    pub fn frames(&self) -> Option<u64> {
+        match self {
+            Self::After { frames } => Some(*frames),
+            Self::UntilMatchEnds => None,
+        }
+    }
+}
+
 #[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -61,6 +763,26 @@ pub struct CameraMeta {
     pub zoom_out_lerp_factor: f32,
     pub min_camera_size: Vec2,
     pub player_camera_box_size: Vec2,
+    /// The smallest height of the area that the camera shows, so that a lone player doesn't get an
+    /// extreme close-up.
+    pub min_height: f32,
+    /// The largest height of the area that the camera shows.
+    ///
+    /// The camera will never zoom out further than the size of the map, either.
+    pub max_height: f32,
+    /// Whether to split the screen into a panel for each player when they get far apart.
+    pub split_screen: bool,
+    /// The distance between the players at which the screen is split.
+    pub split_distance: f32,
+    /// The distance between the players at which the split screen is merged back together.
+    ///
+    /// This should be less than the `split_distance`, so that the screen doesn't flicker between
+    /// the two modes.
+    pub merge_distance: f32,
+    /// The camera trauma added when a player dies.
+    pub death_trauma: f32,
+    /// The camera trauma added when an [`Explosion`][crate::damage::Explosion] goes off.
+    pub explosion_trauma: f32,
 }
 
 impl Default for CameraMeta {
@@ -76,6 +798,13 @@ impl Default for CameraMeta {
             zoom_out_lerp_factor: 1.0,
             min_camera_size: Vec2::ZERO,
             player_camera_box_size: Vec2::ZERO,
+            min_height: 0.0,
+            max_height: f32::MAX,
+            split_screen: false,
+            split_distance: 800.0,
+            merge_distance: 500.0,
+            death_trauma: 0.0,
+            explosion_trauma: 0.0,
         }
     }
 }
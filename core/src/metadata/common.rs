@@ -57,3 +57,24 @@ impl<'de> serde::de::Visitor<'de> for ColorVisitor {
         ))
     }
 }
+
+/// Variations of a sound, that are picked from at random every time it is played, to keep it from
+/// getting grating when it is played over and over.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct SoundVariationsMeta {
+    /// Other sounds that may be played in place of the sound.
+    pub variations: Vec<Handle<AudioSource>>,
+    /// How far the pitch of the sound is raised or lowered at random, as a fraction of its pitch.
+    ///
+    /// With `0.1`, the sound plays at anywhere between 90% and 110% of its pitch.
+    pub pitch_jitter: f32,
+}
+
+impl SoundVariationsMeta {
+    /// Whether the sound is played the same every time.
+    pub fn is_empty(&self) -> bool {
+        self.variations.is_empty() && self.pitch_jitter == 0.0
+    }
+}
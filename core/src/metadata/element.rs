@@ -42,6 +42,8 @@ impl Default for ElementEditorMeta {
 #[serde(deny_unknown_fields)]
 pub struct BulletMeta {
     pub velocity: Vec2,
+    /// The damage dealt to a player hit by the bullet.
+    pub damage: f32,
     pub body_diameter: f32,
     pub atlas: Handle<Atlas>,
 
@@ -63,13 +65,22 @@ pub enum BuiltinElementKind {
     None,
     /// Player spawner
     PlayerSpawner,
+    /// A region of water that players can swim in
+    Water {
+        /// The size of the water region
+        size: Vec2,
+    },
     /// Grenades item
     Grenade {
         body_diameter: f32,
         fin_anim: Key,
         grab_offset: Vec2,
-        damage_region_size: Vec2,
-        damage_region_lifetime: f32,
+        /// The distance from the grenade that players are hit by the explosion within.
+        explosion_radius: f32,
+        /// The damage dealt to a player right next to the explosion.
+        explosion_damage: f32,
+        /// The knockback of a player right next to the explosion, relative to the usual knockback.
+        explosion_knockback: f32,
         throw_velocity: Vec2,
         explosion_lifetime: f32,
         explosion_frames: usize,
@@ -238,6 +249,9 @@ pub enum BuiltinElementKind {
 
         max_ammo: usize,
         cooldown_frames: usize,
+        /// The number of frames it takes to reload the musket after firing all of it's ammo.
+        #[serde(default)]
+        reload_frames: usize,
         bullet_meta: Handle<BulletMeta>,
 
         shoot_fps: f32,
@@ -248,5 +262,27 @@ pub enum BuiltinElementKind {
         shoot_atlas: Handle<Atlas>,
         shoot_sound: Handle<AudioSource>,
         empty_shoot_sound: Handle<AudioSource>,
+        /// Lets the musket be charged up by holding the shoot button, firing when it is released.
+        #[serde(default)]
+        charge: Option<ChargeMeta>,
     },
 }
+
+/// The charging of a weapon that fires a stronger shot the longer the shoot button is held.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ChargeMeta {
+    /// The number of frames that the shoot button has to be held for to fully charge a shot.
+    pub frames: u32,
+    /// The damage of a fully charged shot, relative to an uncharged one.
+    pub max_damage: f32,
+    /// The speed of a fully charged shot, relative to an uncharged one.
+    pub max_speed: f32,
+    /// The color that the weapon is tinted towards as it charges.
+    pub color: ColorMeta,
+    /// The sound played instead of the usual shoot sound when firing a fully charged shot.
+    pub charged_shoot_sound: Handle<AudioSource>,
+    pub charged_shoot_sound_volume: f32,
+    /// The size of the muzzle flash of a fully charged shot, relative to the usual one.
+    pub charged_shoot_scale: f32,
+}
@@ -17,6 +17,52 @@ pub struct MapMeta {
     pub tile_size: Vec2,
     /// The layers of the map
     pub layers: Vec<MapLayerMeta>,
+    /// The regions of the map that hurt players, such as spikes and lava
+    #[serde(default)]
+    pub hazards: Vec<HazardMeta>,
+    /// The platforms that move around the map, carrying players along
+    #[serde(default)]
+    pub platforms: Vec<MovingPlatformMeta>,
+    /// The regions of the map where the wind blows players, items, and projectiles around
+    #[serde(default)]
+    pub wind: Vec<WindZoneMeta>,
+    /// How slippery or sticky each of the surface materials of the map's tiles are
+    #[serde(default)]
+    pub surfaces: SurfacesMeta,
+    /// The area that players are knocked out of the game for leaving
+    ///
+    /// If this isn't set, the blast zone is [`KILL_ZONE_BORDER`] pixels past the left and right
+    /// edges of the map, and [`BLAST_ZONE_DEPTH`] pixels below it, with no limit at the top.
+    #[serde(default)]
+    pub blast_zone: Option<BlastZoneMeta>,
+    /// How gravity, air control, and friction feel on the map, such as for a low-gravity map
+    #[serde(default)]
+    pub physics: MapPhysicsMeta,
+}
+
+impl MapMeta {
+    /// Get the properties of the given surface material, with the map's ground friction applied.
+    pub fn surface(&self, material: SurfaceMaterial) -> SurfaceMeta {
+        let mut surface = self.surfaces.get(material);
+        surface.friction *= self.physics.ground_friction;
+        surface
+    }
+
+    /// Get the area that players are knocked out of the game for leaving.
+    pub fn blast_zone_rect(&self) -> Rect {
+        if let Some(blast_zone) = &self.blast_zone {
+            return Rect {
+                min: blast_zone.min,
+                max: blast_zone.max,
+            };
+        }
+
+        let map_width = self.grid_size.x as f32 * self.tile_size.x;
+        Rect {
+            min: vec2(-KILL_ZONE_BORDER, -BLAST_ZONE_DEPTH),
+            max: vec2(map_width + KILL_ZONE_BORDER, f32::INFINITY),
+        }
+    }
 }
 
 #[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Debug, Default)]
@@ -64,4 +110,285 @@ pub struct MapTileMeta {
     pub idx: u32,
     #[serde(default)]
     pub jump_through: bool,
+    #[serde(default)]
+    pub material: SurfaceMaterial,
+    /// The health of the tile, if it can be destroyed.
+    #[serde(default)]
+    pub destructible: Option<f32>,
+}
+
+/// A platform that moves along a path through the map.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MovingPlatformMeta {
+    /// The size of the platform, in pixels.
+    pub size: Vec2,
+    /// The points that the platform moves between, starting at the first one, and looping back to
+    /// it after the last one.
+    pub path: Vec<Vec2>,
+    /// How fast the platform moves, in pixels per frame.
+    pub speed: f32,
+    /// Whether players can drop down through the platform.
+    #[serde(default)]
+    pub kind: PlatformKind,
+    /// The image that the platform is drawn with, if any.
+    #[serde(default)]
+    pub image: Option<Handle<Image>>,
+}
+
+/// The kind of a [`MovingPlatformMeta`], which decides which ways players can pass through it.
+///
+/// Players can always jump up through a platform from below, and stand on top of it.
+#[derive(
+    BonesBevyAssetLoad, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub enum PlatformKind {
+    /// Players can drop down through the platform by holding down and jumping.
+    #[default]
+    OneWay,
+    /// Players can't drop down through the platform.
+    Floor,
+}
+
+/// How the surface materials of a map affect the bodies standing on them.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct SurfacesMeta {
+    pub ice: SurfaceMeta,
+    pub sticky: SurfaceMeta,
+}
+
+impl Default for SurfacesMeta {
+    fn default() -> Self {
+        Self {
+            ice: SurfaceMeta {
+                friction: 0.1,
+                acceleration: 0.25,
+                max_speed: 1.0,
+            },
+            sticky: SurfaceMeta {
+                friction: 3.0,
+                acceleration: 0.5,
+                max_speed: 0.5,
+            },
+        }
+    }
+}
+
+impl SurfacesMeta {
+    /// Get the properties of the given surface material.
+    pub fn get(&self, material: SurfaceMaterial) -> SurfaceMeta {
+        match material {
+            SurfaceMaterial::Normal | SurfaceMaterial::Conveyor { .. } => SurfaceMeta::default(),
+            SurfaceMaterial::Ice => self.ice,
+            SurfaceMaterial::Sticky => self.sticky,
+        }
+    }
+}
+
+/// How the physics of a map differ from the defaults in the [`PhysicsMeta`].
+///
+/// Everything other than the terminal velocity is a multiplier, so that the bodies with their own
+/// gravity, and the players with their own air speed, keep feeling different from each other.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct MapPhysicsMeta {
+    /// How strongly gravity pulls the bodies on the map down.
+    pub gravity: f32,
+    /// The fastest that bodies can fall, in pixels per frame, if it is different from the default.
+    pub terminal_velocity: Option<f32>,
+    /// How quickly the players can change direction in the air.
+    pub air_acceleration: f32,
+    /// How much the ground slows down the bodies on it, on top of their surface material.
+    pub ground_friction: f32,
+}
+
+impl Default for MapPhysicsMeta {
+    fn default() -> Self {
+        Self {
+            gravity: 1.0,
+            terminal_velocity: None,
+            air_acceleration: 1.0,
+            ground_friction: 1.0,
+        }
+    }
+}
+
+impl MapPhysicsMeta {
+    /// Get the vertical velocity of a falling body with the given gravity after a frame, given the
+    /// default terminal velocity.
+    pub fn fall(&self, velocity_y: f32, gravity: f32, terminal_velocity: f32) -> f32 {
+        let terminal_velocity = self.terminal_velocity.unwrap_or(terminal_velocity);
+        (velocity_y - gravity * self.gravity).max(-terminal_velocity)
+    }
+}
+
+/// How a surface material affects the bodies standing on it, relative to regular ground.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SurfaceMeta {
+    /// How much friction slows down the bodies on the surface.
+    pub friction: f32,
+    /// How fast players on the surface speed up when they start walking.
+    pub acceleration: f32,
+    /// How fast players can walk on the surface.
+    pub max_speed: f32,
+}
+
+impl Default for SurfaceMeta {
+    fn default() -> Self {
+        Self {
+            friction: 1.0,
+            acceleration: 1.0,
+            max_speed: 1.0,
+        }
+    }
+}
+
+impl SurfaceMeta {
+    /// Get the factor that bodies on the surface have their velocity multiplied by each frame,
+    /// given the factor for regular ground.
+    pub fn friction_lerp(&self, friction_lerp: f32) -> f32 {
+        (1.0 - (1.0 - friction_lerp) * self.friction).clamp(0.0, 1.0)
+    }
+
+    /// Slow a player that isn't walking down by `slowdown`, scaled by the surface friction.
+    pub fn slow_down(&self, velocity: f32, slowdown: f32) -> f32 {
+        let slowdown = slowdown * self.friction;
+        if velocity.is_sign_positive() {
+            (velocity - slowdown).max(0.0)
+        } else {
+            (velocity + slowdown).min(0.0)
+        }
+    }
+}
+
+/// A rectangular region of the map that hurts the players that touch it.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HazardMeta {
+    /// The center of the hazard, in pixels.
+    pub pos: Vec2,
+    /// The size of the hazard, in pixels.
+    pub size: Vec2,
+    /// The damage dealt to a player each time they are hit by the hazard.
+    #[serde(default)]
+    pub damage: f32,
+    /// Whether the hazard kills players outright, whatever health they have left.
+    #[serde(default)]
+    pub instant_kill: bool,
+    /// The direction that players are knocked in when they are hit, such as up out of lava.
+    #[serde(default = "default_hazard_direction")]
+    pub direction: Vec2,
+}
+
+fn default_hazard_direction() -> Vec2 {
+    Vec2::Y
+}
+
+/// A rectangular region of the map where the wind pushes the airborne players, the thrown items,
+/// and the projectiles.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WindZoneMeta {
+    /// The center of the wind zone, in pixels.
+    pub pos: Vec2,
+    /// The size of the wind zone, in pixels.
+    pub size: Vec2,
+    /// The direction and strength of the wind, as the velocity it adds every frame.
+    pub force: Vec2,
+    /// How the wind changes over time.
+    #[serde(default)]
+    pub pattern: WindPattern,
+}
+
+/// How the wind in a [`WindZoneMeta`] changes over time.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub enum WindPattern {
+    /// The wind always blows with the same force.
+    #[default]
+    Constant,
+    /// The wind swings back and forth between blowing with it's force and against it.
+    Oscillating {
+        /// The number of frames for the wind to swing back and forth once.
+        period_frames: u32,
+    },
+    /// The wind picks a new random strength, and blows with or against it's force, every so often.
+    Gusty {
+        /// The number of frames between each change of the wind.
+        interval_frames: u32,
+    },
+}
+
+/// The bounds of the blast zone of a map, in pixels.
+///
+/// The bottom of the blast zone should be above the [`KILL_ZONE_BORDER`] below the map, where
+/// falling players are killed without the grace period for getting back in.
+#[derive(BonesBevyAssetLoad, Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BlastZoneMeta {
+    /// The bottom-left corner of the blast zone.
+    pub min: Vec2,
+    /// The top-right corner of the blast zone.
+    pub max: Vec2,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn players_slide_further_on_ice() {
+        let surfaces = SurfacesMeta::default();
+        let frames_to_stop = |material| {
+            let surface = surfaces.get(material);
+            let mut velocity = 5.0;
+            let mut frames = 0;
+            while velocity != 0.0 {
+                velocity = surface.slow_down(velocity, 0.5);
+                frames += 1;
+            }
+            frames
+        };
+
+        assert_eq!(frames_to_stop(SurfaceMaterial::Normal), 10);
+        assert_eq!(frames_to_stop(SurfaceMaterial::Ice), 100);
+        assert!(frames_to_stop(SurfaceMaterial::Sticky) < 10);
+
+        // Friction on ice barely slows bodies down
+        let ice = surfaces.get(SurfaceMaterial::Ice);
+        assert!(ice.friction_lerp(0.85) > 0.98);
+        assert_eq!(
+            surfaces.get(SurfaceMaterial::Normal).friction_lerp(0.85),
+            0.85
+        );
+    }
+
+    #[test]
+    fn falling_players_leave_the_blast_zone_before_the_kill_line() {
+        let map = MapMeta {
+            grid_size: uvec2(20, 10),
+            tile_size: vec2(16.0, 16.0),
+            ..default()
+        };
+        let blast_zone = map.blast_zone_rect();
+
+        // There is room to fall out of the blast zone, and be warned, above the kill line
+        assert!(blast_zone.min.y > -KILL_ZONE_BORDER);
+        assert!(!blast_zone.contains(vec2(160.0, (blast_zone.min.y - KILL_ZONE_BORDER) / 2.0)));
+        assert!(blast_zone.contains(vec2(160.0, blast_zone.min.y + 1.0)));
+    }
+
+    #[test]
+    fn falls_are_capped_by_the_map_terminal_velocity() {
+        let physics = MapPhysicsMeta {
+            terminal_velocity: Some(10.0),
+            ..default()
+        };
+        assert_eq!(physics.fall(-9.8, 0.6, 30.0), -10.0);
+    }
 }
@@ -10,6 +10,9 @@ pub struct PlayerMeta {
     pub gravity: f32,
     pub sounds: PlayerSoundsMeta,
     pub stats: PlayerStatsMeta,
+    /// The character's special move, performed by attacking without an item, if it has one.
+    #[serde(default)]
+    pub special: Option<SpecialMoveMeta>,
     pub layers: PlayerLayersMeta,
 }
 
@@ -28,11 +31,31 @@ pub struct PlayerBodyLayerMeta {
     #[serde(deserialize_with = "deserialize_body_animations")]
     #[asset(deserialize_only)]
     pub animations: BodyAnimationsMeta,
+    /// Clips played when switching between particular animations, instead of snapping straight
+    /// from one to the other.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_arc")]
+    #[asset(deserialize_only)]
+    pub transitions: Arc<Vec<AnimationTransitionMeta>>,
+}
+
+/// A clip played when switching from one animation to another.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationTransitionMeta {
+    pub from: Key,
+    pub to: Key,
+    /// The animation played in between.
+    pub animation: Key,
+    /// The number of frames that the transition animation is played for.
+    pub frames: u32,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct BodyAnimationsMeta {
     pub body_offsets: Arc<std::collections::HashMap<Key, Vec<Vec2>>>,
+    /// The events tagged on each frame of the animations.
+    pub frame_events: Arc<std::collections::HashMap<Key, Vec<Vec<Key>>>>,
     pub frames: Arc<std::collections::HashMap<Key, AnimatedSprite>>,
 }
 
@@ -46,16 +69,75 @@ pub struct PlayerLayerMeta {
     pub animations: Arc<std::collections::HashMap<Key, AnimatedSprite>>,
 }
 
-#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+/// The movement stats of a character.
+///
+/// Any stat that a character leaves out falls back to the same value as the default characters.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
 #[serde(deny_unknown_fields)]
+#[serde(default)]
 pub struct PlayerStatsMeta {
     pub jump_speed: f32,
+    /// The number of extra times the player can jump before landing.
+    pub air_jumps: u32,
     pub slow_fall_speed: f32,
     pub air_speed: f32,
+    /// How quickly the player can change direction in the air.
     pub accel_air_speed: f32,
     pub walk_speed: f32,
     pub slowdown: f32,
     pub accel_walk_speed: f32,
+    /// The maximum speed that the player can crawl at while crouching.
+    pub crouch_speed: f32,
+    /// The height of the player's hitbox while crouching.
+    pub crouch_height: f32,
+    /// The maximum speed that the player slides down a wall at.
+    pub wall_slide_speed: f32,
+    /// The velocity the player is launched with when jumping off of a wall.
+    ///
+    /// The horizontal speed is always directed away from the wall.
+    pub wall_jump_speed: Vec2,
+    /// How hard the player is to knock back, with heavier players flying less far when hit.
+    pub weight: f32,
+}
+
+impl Default for PlayerStatsMeta {
+    fn default() -> Self {
+        Self {
+            jump_speed: 11.0,
+            air_jumps: 1,
+            slow_fall_speed: 1.5,
+            air_speed: 6.0,
+            accel_air_speed: 1.0,
+            walk_speed: 6.0,
+            slowdown: 0.8,
+            accel_walk_speed: 1.0,
+            crouch_speed: 2.0,
+            crouch_height: 32.0,
+            wall_slide_speed: 2.0,
+            wall_jump_speed: vec2(7.0, 10.0),
+            weight: 1.0,
+        }
+    }
+}
+
+/// A character's special move: a lunge that hurts the players that it runs into.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SpecialMoveMeta {
+    /// The animation played on the player's body during the move.
+    pub animation: Key,
+    /// The velocity of the player during the move, when they are facing right.
+    pub velocity: Vec2,
+    pub duration_frames: u32,
+    /// The number of frames after the start of the move until it can be used again.
+    pub cooldown_frames: u32,
+    /// The size of the hitbox just in front of the player.
+    pub hitbox_size: Vec2,
+    pub damage: f32,
+    /// How hard the players that are hit are knocked back, relative to the usual knockback speed.
+    pub knockback: f32,
+    pub sound: Handle<AudioSource>,
+    pub sound_volume: f32,
 }
 
 #[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
@@ -63,15 +145,26 @@ pub struct PlayerStatsMeta {
 pub struct PlayerSoundsMeta {
     pub land_volume: f32,
     pub land: Handle<AudioSource>,
+    #[serde(default)]
+    pub land_variations: SoundVariationsMeta,
 
     pub jump_volume: f32,
     pub jump: Handle<AudioSource>,
 
+    pub double_jump_volume: f32,
+    pub double_jump: Handle<AudioSource>,
+
     pub grab_volume: f32,
     pub grab: Handle<AudioSource>,
 
     pub drop_volume: f32,
     pub drop: Handle<AudioSource>,
+
+    /// Played on the frames of the player's animations tagged with a `footstep` event.
+    pub footstep_volume: f32,
+    pub footstep: Handle<AudioSource>,
+    #[serde(default)]
+    pub footstep_variations: SoundVariationsMeta,
 }
 
 fn deserialize_arc<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
@@ -117,6 +210,9 @@ fn deserialize_body_animations<'de, D: Deserializer<'de>>(
         pub idx: usize,
         #[serde(default)]
         pub offset: Vec2,
+        /// The gameplay events fired when the animation reaches this frame.
+        #[serde(default)]
+        pub events: Vec<Key>,
     }
 
     let body_sprite_anmations =
@@ -128,6 +224,20 @@ fn deserialize_body_animations<'de, D: Deserializer<'de>>(
             .map(|(k, v)| (*k, v.frames.iter().map(|x| x.offset).collect::<Vec<_>>()))
             .collect(),
     );
+    let frame_events = Arc::new(
+        body_sprite_anmations
+            .iter()
+            .map(|(k, v)| {
+                (
+                    *k,
+                    v.frames
+                        .iter()
+                        .map(|x| x.events.clone())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect(),
+    );
     let frames = Arc::new(
         body_sprite_anmations
             .into_iter()
@@ -148,6 +258,7 @@ fn deserialize_body_animations<'de, D: Deserializer<'de>>(
 
     Ok(BodyAnimationsMeta {
         body_offsets,
+        frame_events,
         frames,
     })
 }
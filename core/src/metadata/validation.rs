@@ -0,0 +1,193 @@
+//! Checks that the references in the game metadata point to things that actually exist.
+
+use ::bevy::asset::{AssetServer, Assets, HandleId, LoadState};
+
+use super::*;
+
+/// The animations that the player states play on the player's body.
+pub const PLAYER_BODY_ANIMATIONS: &[&str] = &[
+    "idle",
+    "walk",
+    "crouch",
+    "rise",
+    "fall",
+    "double_jump",
+    "wall_slide",
+    "dash",
+    "ledge_grab",
+    "flinch",
+    "swim",
+    "taunt",
+    "death_spine",
+    "death_belly",
+    "death_back",
+    "death_front",
+];
+
+/// The animations that are played on the player's fin, on top of the body animations.
+pub const PLAYER_FIN_ANIMATIONS: &[&str] = &["grab_1", "grab_2"];
+
+/// The animations that are played on the player's face, on top of the body animations.
+pub const PLAYER_FACE_ANIMATIONS: &[&str] = &["emote_alarm"];
+
+/// A reference in the metadata to something that doesn't exist.
+#[derive(Clone, Debug)]
+pub struct MissingReference {
+    /// The file that the reference is in.
+    pub file: String,
+    /// The field in the file that contains the reference.
+    pub field: String,
+    /// What is missing.
+    pub missing: String,
+}
+
+/// Error containing every broken reference found while validating the metadata.
+#[derive(Clone, Debug, Default)]
+pub struct MetadataValidationError {
+    pub missing: Vec<MissingReference>,
+}
+
+impl std::fmt::Display for MetadataValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Found {} broken reference(s) in the game metadata:",
+            self.missing.len()
+        )?;
+        for reference in &self.missing {
+            writeln!(
+                f,
+                "  - {}: `{}` is missing {}",
+                reference.file, reference.field, reference.missing
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MetadataValidationError {}
+
+/// Check that every player in the core metadata, and every animation and sound that the players
+/// need, exists.
+///
+/// Assets that are still loading are skipped, since it isn't known yet whether they exist.
+pub fn validate_core_meta(
+    core: &CoreMeta,
+    player_assets: &Assets<PlayerMeta>,
+    asset_server: &AssetServer,
+) -> Result<(), MetadataValidationError> {
+    let mut error = MetadataValidationError::default();
+    let path_of = |id: HandleId| {
+        asset_server
+            .get_handle_path(id)
+            .map(|path| path.path().display().to_string())
+            .unwrap_or_else(|| "<unknown file>".into())
+    };
+
+    for (i, player_handle) in core.players.iter().enumerate() {
+        let handle = player_handle.get_bevy_handle();
+        let Some(player) = player_assets.get(&handle) else {
+            if asset_server.get_load_state(handle.id) == LoadState::Failed {
+                error.missing.push(MissingReference {
+                    file: "core metadata".into(),
+                    field: format!("players[{i}]"),
+                    missing: format!("the player file `{}`", path_of(handle.id)),
+                });
+            }
+            continue;
+        };
+        let file = path_of(handle.id);
+
+        // Animations
+        let body_animations = &player.layers.body.animations.frames;
+        let layers = [
+            ("layers.body.animations", &[][..], &**body_animations),
+            (
+                "layers.fin.animations",
+                PLAYER_FIN_ANIMATIONS,
+                &*player.layers.fin.animations,
+            ),
+            (
+                "layers.face.animations",
+                PLAYER_FACE_ANIMATIONS,
+                &*player.layers.face.animations,
+            ),
+        ];
+        for (field, extra_animations, animations) in layers {
+            for name in PLAYER_BODY_ANIMATIONS.iter().chain(extra_animations) {
+                let key = Key::new(name).unwrap();
+                if !animations.contains_key(&key) {
+                    error.missing.push(MissingReference {
+                        file: file.clone(),
+                        field: field.into(),
+                        missing: format!("the `{name}` animation"),
+                    });
+                }
+            }
+        }
+
+        // Transitions
+        for (i, transition) in player.layers.body.transitions.iter().enumerate() {
+            for name in [transition.from, transition.to, transition.animation] {
+                if !body_animations.contains_key(&name) {
+                    error.missing.push(MissingReference {
+                        file: file.clone(),
+                        field: format!("layers.body.transitions[{i}]"),
+                        missing: format!("the animation {name:?}"),
+                    });
+                }
+            }
+        }
+
+        // Special move
+        if let Some(special) = &player.special {
+            if !body_animations.contains_key(&special.animation) {
+                error.missing.push(MissingReference {
+                    file: file.clone(),
+                    field: "special.animation".into(),
+                    missing: format!("the animation {:?}", special.animation),
+                });
+            }
+        }
+
+        // Sounds
+        let sounds = &player.sounds;
+        let variations = [
+            ("sounds.land_variations", &sounds.land_variations),
+            ("sounds.footstep_variations", &sounds.footstep_variations),
+        ]
+        .into_iter()
+        .flat_map(|(field, meta)| {
+            meta.variations
+                .iter()
+                .map(move |sound| (format!("{field}.variations"), sound))
+        });
+        for (field, sound) in [
+            ("sounds.land", &sounds.land),
+            ("sounds.jump", &sounds.jump),
+            ("sounds.double_jump", &sounds.double_jump),
+            ("sounds.grab", &sounds.grab),
+            ("sounds.drop", &sounds.drop),
+            ("sounds.footstep", &sounds.footstep),
+        ]
+        .map(|(field, sound)| (field.to_string(), sound))
+        .into_iter()
+        .chain(variations)
+        {
+            let id = sound.get_bevy_handle_untyped().id;
+            if asset_server.get_load_state(id) == LoadState::Failed {
+                error.missing.push(MissingReference {
+                    file: file.clone(),
+                    field,
+                    missing: format!("the sound file `{}`", path_of(id)),
+                });
+            }
+        }
+    }
+
+    if error.missing.is_empty() {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
@@ -0,0 +1,258 @@
+//! Short-lived sprite particles, such as dust and sparks.
+//!
+//! Particles are spawned in bursts by pushing them onto the [`ParticleEvents`] queue, or
+//! continuously by a [`ParticleEmitter`]. They are regular entities in the world, and their random
+//! spread comes from the [`GlobalRng`], so they play out the same way for every player in a
+//! networked game.
+
+use std::collections::VecDeque;
+
+use crate::{prelude::*, random::GlobalRng};
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<ParticleEvents>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, update_particle_emitters)
+        .add_system_to_stage(CoreStage::Last, update_particles)
+        .add_system_to_stage(CoreStage::Last, spawn_particles);
+}
+
+/// A request to spawn a burst of particles.
+#[derive(Clone, Debug)]
+pub struct ParticleBurst {
+    pub particle: ParticleMeta,
+    /// Where the particles are spawned.
+    pub position: Vec3,
+    /// The direction that the particles fly off in, spread over the cone of the particle's
+    /// `spread`.
+    pub direction: Vec2,
+}
+
+/// Resource containing the bursts of particles that will be spawned at the end of the frame.
+#[derive(Clone, TypeUlid, Debug, Default)]
+#[ulid = "01GWQ7D0ZEKJ3T9RGZ2W6R1Y5C"]
+pub struct ParticleEvents {
+    pub queue: VecDeque<ParticleBurst>,
+}
+
+impl ParticleEvents {
+    /// Spawn a burst of particles at `position`, flying off in `direction`.
+    pub fn emit(&mut self, particle: &ParticleMeta, position: Vec3, direction: Vec2) {
+        self.queue.push_back(ParticleBurst {
+            particle: particle.clone(),
+            position,
+            direction,
+        });
+    }
+}
+
+/// Component for an entity that continuously emits particles from its position.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GWQ7D0ZFM1P5S8XHE5N3QW9A"]
+pub struct ParticleEmitter {
+    pub particle: ParticleMeta,
+    /// The direction that the particles are emitted in.
+    pub direction: Vec2,
+    /// The number of frames between each burst of particles.
+    pub interval: u32,
+    /// The number of frames until the next burst of particles.
+    pub timer: u32,
+}
+
+/// Component for a single particle.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GWQ7D0ZFX4VJ0B6T2A8MKE70"]
+pub struct Particle {
+    /// The velocity of the particle in pixels per frame.
+    pub velocity: Vec2,
+    /// How fast the particle falls, in pixels per frame squared.
+    pub gravity: f32,
+    /// The number of frames that the particle has been alive for.
+    pub age: u32,
+    /// The number of frames that the particle lives for.
+    pub lifetime: u32,
+    /// The number of frames in the particle's atlas.
+    pub frames: usize,
+}
+
+impl Particle {
+    /// How far the particle is through its life, from `0.0` when it spawns to `1.0` when it dies.
+    pub fn progress(&self) -> f32 {
+        self.age as f32 / self.lifetime.max(1) as f32
+    }
+}
+
+fn update_particle_emitters(
+    entities: Res<Entities>,
+    transforms: Comp<Transform>,
+    mut emitters: CompMut<ParticleEmitter>,
+    mut particle_events: ResMut<ParticleEvents>,
+) {
+    for (_ent, (emitter, transform)) in entities.iter_with((&mut emitters, &transforms)) {
+        if emitter.timer > 0 {
+            emitter.timer -= 1;
+            continue;
+        }
+        emitter.timer = emitter.interval;
+        particle_events.emit(&emitter.particle, transform.translation, emitter.direction);
+    }
+}
+
+/// Move the particles, fading them out and playing through their atlas as they age.
+fn update_particles(
+    mut entities: ResMut<Entities>,
+    mut particles: CompMut<Particle>,
+    mut transforms: CompMut<Transform>,
+    mut sprites: CompMut<AtlasSprite>,
+) {
+    let mut dead_particles = Vec::new();
+    for (particle_ent, (particle, transform, sprite)) in
+        entities.iter_with((&mut particles, &mut transforms, &mut sprites))
+    {
+        particle.age += 1;
+        if particle.age >= particle.lifetime {
+            dead_particles.push(particle_ent);
+            continue;
+        }
+
+        particle.velocity.y -= particle.gravity;
+        transform.translation += particle.velocity.extend(0.0);
+
+        let progress = particle.progress();
+        sprite.index = ((progress * particle.frames as f32) as usize).min(particle.frames - 1);
+        sprite.color.set_a(1.0 - progress);
+    }
+
+    for particle_ent in dead_particles {
+        entities.kill(particle_ent);
+    }
+}
+
+/// Spawn the bursts of particles in the [`ParticleEvents`] queue, up to the particle limit.
+fn spawn_particles(
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    rng: Res<GlobalRng>,
+    mut particle_events: ResMut<ParticleEvents>,
+    mut particles: CompMut<Particle>,
+    mut transforms: CompMut<Transform>,
+    mut sprites: CompMut<AtlasSprite>,
+) {
+    let max_particles = core_meta.particles.max_particles;
+    let mut particle_count = entities.iter_with(&particles).count();
+
+    while let Some(burst) = particle_events.queue.pop_front() {
+        let ParticleBurst {
+            particle,
+            position,
+            direction,
+        } = burst;
+        let direction = direction.normalize_or_zero();
+        let direction = if direction == Vec2::ZERO {
+            Vec2::Y
+        } else {
+            direction
+        };
+
+        for _ in 0..particle.count {
+            if particle_count >= max_particles {
+                // Drop the rest of the particles, rather than let them pile up
+                particle_events.queue.clear();
+                return;
+            }
+            particle_count += 1;
+
+            let angle = rng.f32_normalized() * particle.spread.to_radians() / 2.0;
+            let speed = particle.speed + rng.f32_normalized() * particle.speed_variation;
+
+            let particle_ent = entities.create();
+            particles.insert(
+                particle_ent,
+                Particle {
+                    velocity: Vec2::from_angle(angle).rotate(direction) * speed,
+                    gravity: particle.gravity,
+                    age: 0,
+                    lifetime: particle.lifetime_frames,
+                    frames: particle.frames.max(1),
+                },
+            );
+            transforms.insert(
+                particle_ent,
+                Transform {
+                    translation: position,
+                    scale: Vec3::splat(particle.scale),
+                    ..default()
+                },
+            );
+            sprites.insert(
+                particle_ent,
+                AtlasSprite {
+                    atlas: particle.atlas.clone(),
+                    ..default()
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn particles_are_capped_and_deterministic() {
+        let run = || {
+            let mut core_meta = CoreMeta::default();
+            core_meta.particles.max_particles = 10;
+            let mut test = SystemTest::new(
+                |world| {
+                    world.insert_resource(CoreMetaArc(Arc::new(core_meta)));
+                    world.init_resource::<GlobalRng>();
+                    world.init_resource::<ParticleEvents>();
+                },
+                |stages| {
+                    stages
+                        .add_system_to_stage(CoreStage::Last, update_particles)
+                        .add_system_to_stage(CoreStage::Last, spawn_particles);
+                },
+            );
+
+            let particle = ParticleMeta {
+                count: 4,
+                speed: 2.0,
+                speed_variation: 1.0,
+                spread: 90.0,
+                lifetime_frames: 100,
+                ..default()
+            };
+            for _ in 0..5 {
+                test.world.resource::<ParticleEvents>().borrow_mut().emit(
+                    &particle,
+                    Vec3::ZERO,
+                    Vec2::Y,
+                );
+                test.run();
+            }
+
+            test.world
+                .run_initialized_system(
+                    |entities: Res<Entities>,
+                     particles: Comp<Particle>,
+                     transforms: Comp<Transform>| {
+                        Ok(entities
+                            .iter_with((&particles, &transforms))
+                            .map(|(_, (_, transform))| transform.translation)
+                            .collect::<Vec<_>>())
+                    },
+                )
+                .unwrap()
+        };
+
+        let positions = run();
+        assert_eq!(positions.len(), 10);
+        assert_eq!(positions, run());
+    }
+}
@@ -39,6 +39,30 @@ pub fn install(session: &mut GameSession) {
         .add_system_to_stage(PhysicsStage::Update, update_kinematic_bodies);
 }
 
+/// Component containing the material of a map tile's surface, which changes how the bodies
+/// standing on it move.
+///
+/// How slippery or sticky each material is is set by the map's [`SurfacesMeta`].
+#[derive(
+    BonesBevyAssetLoad, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, TypeUlid,
+)]
+#[ulid = "01GVJNNN315XGQKHVA9HAYH45Q"]
+#[serde(deny_unknown_fields)]
+pub enum SurfaceMaterial {
+    /// Regular ground.
+    #[default]
+    Normal,
+    /// Slippery ground, that is hard to speed up or slow down on.
+    Ice,
+    /// Sticky ground, that slows down everything on it.
+    Sticky,
+    /// A conveyor belt, that carries everything on it along.
+    Conveyor {
+        /// The horizontal speed that bodies are carried at, in pixels per frame.
+        speed: f32,
+    },
+}
+
 /// A kinematic physics body
 ///
 /// Used primarily for players and things that need to walk around, detect what kind of platform
@@ -60,6 +84,8 @@ pub struct KinematicBody {
     pub was_on_ground: bool,
     /// Will be `true` if the body is currently on top of a platform/jumpthrough tile
     pub is_on_platform: bool,
+    /// The material of the ground that the body is standing on.
+    pub surface: SurfaceMaterial,
     /// If this is `true` the body will be affected by gravity
     pub has_mass: bool,
     pub has_friction: bool,
@@ -72,6 +98,9 @@ pub struct KinematicBody {
     /// This is important to make sure that it falls through JumpThrough platforms if it happens to
     /// spawn inside of one.
     pub is_spawning: bool,
+    /// Whether the body is nudged around the corners of tiles that it only just clips, so that it
+    /// slides into gaps and past ledges instead of catching on them.
+    pub corner_correction: bool,
 }
 
 impl KinematicBody {
@@ -116,10 +145,64 @@ fn hydrate_physics_bodies(
     }
 }
 
+/// The furthest that a body is nudged around the corner of a tile that it only just clipped, in
+/// pixels.
+const CORNER_CORRECTION: u32 = 4;
+
+/// The nudges to try, across the direction of movement, when a body clips the corner of a tile.
+///
+/// The nudges go from smallest to largest, and towards `preferred` before away from it, or towards
+/// the negative side first if there is no preference. The order is fixed so that every client in a
+/// networked game resolves the collision the same way.
+fn corner_nudges(preferred: f32) -> impl Iterator<Item = f32> {
+    let sign = if preferred > 0.0 { 1.0 } else { -1.0 };
+    (1..=CORNER_CORRECTION).flat_map(move |step| [step as f32 * sign, step as f32 * -sign])
+}
+
+/// Nudge a body that ran into a solid tile while moving in `direction` around the corner of it, if
+/// it only just clipped the corner, preferring to nudge it the way of `preferred`.
+///
+/// Returns whether the body was nudged.
+fn nudge_around_corner(
+    collision_world: &mut CollisionWorld,
+    transforms: &mut CompMut<Transform>,
+    entity: Entity,
+    shape: ColliderShape,
+    direction: Vec2,
+    preferred: f32,
+) -> bool {
+    let transform = *transforms.get(entity).unwrap();
+    let across = vec2(direction.y.abs(), direction.x.abs());
+    let is_clear = |offset: Vec2| {
+        let mut transform = transform;
+        transform.translation += offset.extend(0.0);
+        collision_world.tile_collision(transform, shape) != TileCollisionKind::SOLID
+    };
+
+    // The body has to fit at the nudged spot, and be able to keep moving from there
+    let Some(nudge) = corner_nudges(preferred)
+        .map(|x| across * x)
+        .find(|&nudge| is_clear(nudge) && is_clear(nudge + direction))
+    else {
+        return false;
+    };
+
+    if nudge.x != 0.0 {
+        collision_world.move_horizontal(transforms, entity, nudge.x);
+    } else {
+        collision_world.move_vertical(transforms, entity, nudge.y);
+    }
+    true
+}
+
 /// Update physics for kinematic bodies.
 fn update_kinematic_bodies(
     game: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
     entities: Res<Entities>,
+    simulation_rate: Res<SimulationRate>,
+    surface_materials: Comp<SurfaceMaterial>,
+    hit_stops: Comp<HitStop>,
     mut bodies: CompMut<KinematicBody>,
     mut collision_world: CollisionWorld,
     mut transforms: CompMut<Transform>,
@@ -133,6 +216,11 @@ fn update_kinematic_bodies(
             collision_world.colliders.get_mut(entity).unwrap().disabled = false;
         }
 
+        // Bodies frozen by a hit hold still, keeping their velocity for when they thaw out
+        if hit_stops.contains(entity) {
+            continue;
+        }
+
         if body.has_mass {
             // Shove objects out of walls
             loop {
@@ -195,12 +283,39 @@ fn update_kinematic_bodies(
             collision_world.descent(entity);
         }
 
+        // The horizontal movement is resolved before the vertical movement, so that a body running
+        // into a wall keeps falling, and a body hitting its head keeps moving sideways. A body that
+        // only just clips the corner of a tile, either way, is nudged around it instead of stopping.
         if collision_world.move_horizontal(&mut transforms, entity, body.velocity.x) {
-            body.velocity.x *= -body.bounciness;
+            let nudged = body.corner_correction
+                && nudge_around_corner(
+                    &mut collision_world,
+                    &mut transforms,
+                    entity,
+                    body.shape,
+                    vec2(body.velocity.x.signum(), 0.0),
+                    body.velocity.y,
+                );
+            if !nudged {
+                body.velocity.x *= -body.bounciness;
+            }
         }
 
         if collision_world.move_vertical(&mut transforms, entity, body.velocity.y) {
-            body.velocity.y *= -body.bounciness;
+            // Only heads are nudged around corners, landing on the edge of a tile still lands
+            let nudged = body.corner_correction
+                && body.velocity.y > 0.0
+                && nudge_around_corner(
+                    &mut collision_world,
+                    &mut transforms,
+                    entity,
+                    body.shape,
+                    Vec2::Y,
+                    body.velocity.x,
+                );
+            if !nudged {
+                body.velocity.y *= -body.bounciness;
+            }
         }
 
         // Check ground collision
@@ -210,18 +325,26 @@ fn update_kinematic_bodies(
 
             body.was_on_ground = body.is_on_ground;
 
-            let tile = collision_world.tile_collision(transform, body.shape);
+            let (tile_ent, tile) = collision_world
+                .tile_collision_entity(transform, body.shape)
+                .map(|(ent, kind)| (Some(ent), kind))
+                .unwrap_or_default();
 
             let on_jump_through_tile = tile == TileCollisionKind::JUMP_THROUGH;
             body.is_on_ground = tile != TileCollisionKind::EMPTY
                 && !collision_world.get_collider(entity).seen_wood
                 && !(on_jump_through_tile && body.fall_through);
             body.is_on_platform = body.is_on_ground && on_jump_through_tile;
+            body.surface = tile_ent
+                .filter(|_| body.is_on_ground)
+                .and_then(|ent| surface_materials.get(ent).copied())
+                .unwrap_or_default();
         }
 
         if body.is_on_ground {
             if body.has_friction {
-                body.velocity.x *= game.physics.friction_lerp;
+                let surface = map.surface(body.surface);
+                body.velocity.x *= surface.friction_lerp(game.physics.friction_lerp);
 
                 if body.velocity.x.abs() <= game.physics.stop_threshold {
                     body.velocity.x = 0.0;
@@ -235,12 +358,17 @@ fn update_kinematic_bodies(
             }
         }
 
-        if !body.is_on_ground && body.has_mass {
-            body.velocity.y -= body.gravity;
+        // Get carried along by conveyor belts
+        if let SurfaceMaterial::Conveyor { speed } = body.surface {
+            collision_world.move_horizontal(&mut transforms, entity, speed);
+        }
 
-            if body.velocity.y < -game.physics.terminal_velocity {
-                body.velocity.y = -game.physics.terminal_velocity;
-            }
+        if !body.is_on_ground && body.has_mass {
+            body.velocity.y = map.physics.fall(
+                body.velocity.y,
+                body.gravity,
+                game.physics.terminal_velocity,
+            );
         }
 
         if body.can_rotate {
@@ -250,6 +378,7 @@ fn update_kinematic_bodies(
                 body.angular_velocity,
                 body.is_on_ground,
                 body.shape,
+                *simulation_rate,
             );
         }
     }
@@ -262,6 +391,7 @@ fn apply_rotation(
     angular_velocity: f32,
     is_on_ground: bool,
     collider_shape: ColliderShape,
+    simulation_rate: SimulationRate,
 ) {
     let mut angle = transform.rotation.to_euler(EulerRot::XYZ).2;
 
@@ -270,8 +400,233 @@ fn apply_rotation(
             angle += velocity.x.abs() * angular_velocity;
         }
     } else {
-        angle += (angular_velocity * crate::FPS).to_radians();
+        angle += (angular_velocity * *simulation_rate).to_radians();
     }
 
     transform.rotation = Quat::from_rotation_z(angle);
 }
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn corner_nudges_are_tried_in_a_fixed_order() {
+        // Smallest first, towards the way the body is moving
+        let nudges = corner_nudges(3.0).collect::<Vec<_>>();
+        assert_eq!(nudges[..4], [1.0, -1.0, 2.0, -2.0]);
+        assert_eq!(nudges.len(), CORNER_CORRECTION as usize * 2);
+        assert_eq!(corner_nudges(-3.0).next(), Some(-1.0));
+
+        // Bodies that aren't moving either way break the tie the same way every time
+        assert_eq!(corner_nudges(0.0).next(), Some(-1.0));
+        assert_eq!(corner_nudges(-0.0).next(), Some(-1.0));
+    }
+
+    /// How high a player that just jumped, on a map with the given physics, rises before they start
+    /// falling again.
+    fn jump_height(physics: MapPhysicsMeta) -> f32 {
+        let mut test = physics_test(LoadedMap(Arc::new(MapMeta {
+            physics,
+            ..default()
+        })));
+
+        // The jump states launch the player up at their jump speed
+        let player = test
+            .world
+            .run_initialized_system(
+                |mut entities: ResMut<Entities>,
+                 mut bodies: CompMut<KinematicBody>,
+                 mut transforms: CompMut<Transform>| {
+                    let player = entities.create();
+                    bodies.insert(
+                        player,
+                        KinematicBody {
+                            shape: ColliderShape::Rectangle {
+                                size: vec2(20.0, 30.0),
+                            },
+                            velocity: vec2(0.0, PlayerStatsMeta::default().jump_speed),
+                            gravity: 0.6,
+                            has_mass: true,
+                            ..default()
+                        },
+                    );
+                    transforms.insert(player, default());
+                    Ok(player)
+                },
+            )
+            .unwrap();
+
+        let body = |world: &World| {
+            let bodies = world.components.get::<KinematicBody>();
+            let bodies = bodies.borrow();
+            *bodies.get(player).unwrap()
+        };
+        while body(&test.world).velocity.y > 0.0 {
+            test.run();
+        }
+
+        let transforms = test.world.components.get::<Transform>();
+        let transforms = transforms.borrow();
+        transforms.get(player).unwrap().translation.y
+    }
+
+    /// A test of the physics systems, on the given map.
+    fn physics_test(map: LoadedMap) -> SystemTest {
+        SystemTest::new(
+            |world| {
+                world.init_resource::<RapierContext>();
+                world.init_resource::<SimulationRate>();
+                world.insert_resource(CoreMetaArc(Arc::new(CoreMeta {
+                    physics: PhysicsMeta {
+                        terminal_velocity: 30.0,
+                        ..default()
+                    },
+                    ..default()
+                })));
+                world.insert_resource(map);
+            },
+            |stages| {
+                stages
+                    .add_system_to_stage(CoreStage::Update, hydrate_physics_bodies)
+                    .add_system_to_stage(CoreStage::Update, update_kinematic_bodies);
+            },
+        )
+    }
+
+    /// A test of the physics systems, with solid, 16 pixel tiles at the given grid positions.
+    fn tile_test(tiles: &[UVec2]) -> SystemTest {
+        let mut test = physics_test(default());
+
+        let tiles = tiles.to_vec();
+        test.world
+            .run_initialized_system(
+                move |mut entities: ResMut<Entities>,
+                      mut tile_layers: CompMut<TileLayer>,
+                      mut collision_kinds: CompMut<TileCollisionKind>| {
+                    let mut layer = TileLayer::new(uvec2(10, 10), Vec2::splat(16.0), default());
+                    for &pos in &tiles {
+                        let tile = entities.create();
+                        collision_kinds.insert(tile, TileCollisionKind::SOLID);
+                        layer.set(pos, Some(tile));
+                    }
+                    let layer_ent = entities.create();
+                    tile_layers.insert(layer_ent, layer);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        test.world
+            .run_initialized_system(|mut collision_world: CollisionWorld| {
+                collision_world.update_tiles();
+                Ok(())
+            })
+            .unwrap();
+
+        test
+    }
+
+    /// Spawn a player sized body, that is nudged around corners, and run the physics until it
+    /// stops rising or the frames run out. Returns where the body ended up, and the body.
+    fn move_body(
+        test: &mut SystemTest,
+        position: Vec2,
+        velocity: Vec2,
+        frames: usize,
+    ) -> (Vec2, KinematicBody) {
+        let player = test
+            .world
+            .run_initialized_system(
+                move |mut entities: ResMut<Entities>,
+                      mut bodies: CompMut<KinematicBody>,
+                      mut transforms: CompMut<Transform>| {
+                    let player = entities.create();
+                    bodies.insert(
+                        player,
+                        KinematicBody {
+                            shape: ColliderShape::Rectangle {
+                                size: vec2(20.0, 30.0),
+                            },
+                            velocity,
+                            gravity: 0.6,
+                            has_mass: true,
+                            corner_correction: true,
+                            ..default()
+                        },
+                    );
+                    transforms.insert(player, Transform::from_translation(position.extend(0.0)));
+                    Ok(player)
+                },
+            )
+            .unwrap();
+
+        let body = |world: &World| {
+            let bodies = world.components.get::<KinematicBody>();
+            let bodies = bodies.borrow();
+            *bodies.get(player).unwrap()
+        };
+        for _ in 0..frames {
+            test.run();
+            if velocity.y > 0.0 && body(&test.world).velocity.y <= 0.0 {
+                break;
+            }
+        }
+
+        let transforms = test.world.components.get::<Transform>();
+        let transforms = transforms.borrow();
+        (
+            transforms.get(player).unwrap().translation.truncate(),
+            body(&test.world),
+        )
+    }
+
+    #[test]
+    fn jumps_are_nudged_around_ceiling_corners_only_when_they_just_clip_them() {
+        // A ceiling of two tiles, from x 64 to 96 and y 64 to 80, with a seam between them at x 80
+        let jump = |x: f32| {
+            let mut test = tile_test(&[uvec2(4, 4), uvec2(5, 4)]);
+            move_body(&mut test, vec2(x, 48.0), vec2(0.0, 10.0), 100).0
+        };
+
+        // Clipping the corner by 3 pixels slides the body out from under the ceiling
+        let clipped = jump(57.0);
+        assert!(clipped.x < 57.0);
+        assert!(clipped.y > 95.0);
+
+        // Clipping it by more than the corner correction is a bonk
+        let bonked = jump(60.0);
+        assert_eq!(bonked.x, 60.0);
+        assert!(bonked.y < 49.0);
+
+        // The seam between the tiles isn't a corner to be nudged into
+        let seam = jump(80.0);
+        assert_eq!(seam.x, 80.0);
+        assert!(seam.y < 49.0);
+    }
+
+    #[test]
+    fn bodies_slide_over_the_seams_between_floor_tiles() {
+        let floor = (0..10).map(|x| uvec2(x, 0)).collect::<Vec<_>>();
+        let mut test = tile_test(&floor);
+        let (position, body) = move_body(&mut test, vec2(20.0, 31.1), vec2(3.0, 0.0), 40);
+
+        // The body crossed the seams without catching on them, or being nudged up over them
+        assert_eq!(body.velocity.x, 3.0);
+        assert!((position.x - 140.0).abs() < 0.01);
+        assert!(position.y > 30.9 && position.y < 31.2);
+        assert!(body.is_on_ground);
+    }
+
+    #[test]
+    fn players_jump_higher_in_low_gravity() {
+        let normal = jump_height(default());
+        let low_gravity = jump_height(MapPhysicsMeta {
+            gravity: 0.5,
+            ..default()
+        });
+        assert!(normal > 0.0);
+        assert!(low_gravity > normal * 1.9);
+    }
+}
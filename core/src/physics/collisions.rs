@@ -781,7 +781,6 @@ impl<'a> CollisionWorld<'a> {
     /// >
     /// > [`tile_collision`][Self::tile_collision] will report a collision if the collider shape is
     /// > perfectly lined up along the edge of a tile, but `tile_collision_point` won't.
-    #[allow(unused)]
     pub fn tile_collision_point(&self, pos: Vec2) -> TileCollisionKind {
         for (entity, tile_layer) in self.entities.iter_with(&self.tile_layers) {
             let TileLayer { tile_size, .. } = tile_layer;
@@ -804,6 +803,18 @@ impl<'a> CollisionWorld<'a> {
     /// Get the [`TileCollisionKind`] of the first tile detected colliding with the `shape` at the
     /// given `transform`.
     pub fn tile_collision(&self, transform: Transform, shape: ColliderShape) -> TileCollisionKind {
+        self.tile_collision_entity(transform, shape)
+            .map(|(_, kind)| kind)
+            .unwrap_or_default()
+    }
+
+    /// Get the entity and [`TileCollisionKind`] of the first tile detected colliding with the
+    /// `shape` at the given `transform`, if any.
+    pub fn tile_collision_entity(
+        &self,
+        transform: Transform,
+        shape: ColliderShape,
+    ) -> Option<(Entity, TileCollisionKind)> {
         self.ctx
             .query_pipeline
             .intersection_with_shape(
@@ -821,8 +832,7 @@ impl<'a> CollisionWorld<'a> {
                 }),
             )
             .map(|x| RapierUserData::entity(self.ctx.collider_set.get(x).unwrap().user_data))
-            .and_then(|e| self.tile_collision_kinds.get(e).copied())
-            .unwrap_or_default()
+            .and_then(|e| Some((e, self.tile_collision_kinds.get(e).copied()?)))
     }
 
     /// Get the collider for the given entity.
@@ -0,0 +1,503 @@
+//! Moving platforms that carry the players standing on them.
+//!
+//! Platforms are one-way: players can jump up through them from below, and land on top of them.
+//! Players can also drop down through [`PlatformKind::OneWay`] platforms by holding down and
+//! jumping, which only lets them pass through the platform that they were standing on.
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session
+        .stages
+        // Platforms move after the physics update, so that they can catch the players that
+        // gravity just pulled off of them.
+        .add_system_to_stage(CoreStage::Last, update_moving_platforms);
+}
+
+/// How far above or below the top of a platform the feet of a player may be for them to be standing
+/// on it, in pixels.
+const STANDING_TOLERANCE: f32 = 1.0;
+
+/// The number of frames that a player dropping through a platform passes through it for.
+pub const DROP_THROUGH_FRAMES: u32 = 12;
+
+/// Component for a platform that moves along a path, carrying the players standing on it.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GW0FPGYZJ8TDB9Z22VXS49H5"]
+pub struct MovingPlatform {
+    /// The size of the platform in pixels.
+    pub size: Vec2,
+    /// The points that the platform moves between, looping back to the first one after the last.
+    pub path: Vec<Vec2>,
+    /// How fast the platform moves, in pixels per frame.
+    pub speed: f32,
+    /// Whether players can drop down through the platform.
+    pub kind: PlatformKind,
+    /// The index of the point in the path that the platform is moving towards.
+    pub target: usize,
+}
+
+impl MovingPlatform {
+    pub fn new(meta: &MovingPlatformMeta) -> Self {
+        Self {
+            size: meta.size,
+            path: meta.path.clone(),
+            speed: meta.speed,
+            kind: meta.kind,
+            target: 1.min(meta.path.len().saturating_sub(1)),
+        }
+    }
+
+    /// Get the collision rectangle of the platform, given it's position.
+    pub fn rect(&self, position: Vec2) -> Rect {
+        Rect::new(position.x, position.y, self.size.x, self.size.y)
+    }
+
+    /// Move the platform at `position` towards the next point in it's path, returning it's new
+    /// position.
+    pub fn advance(&mut self, position: Vec2) -> Vec2 {
+        let Some(&target) = self.path.get(self.target) else {
+            return position;
+        };
+
+        let to_target = target - position;
+        if to_target.length() <= self.speed {
+            self.target = (self.target + 1) % self.path.len();
+            target
+        } else {
+            position + to_target.normalize() * self.speed
+        }
+    }
+
+    /// Whether a body with the bounding box `body_rect`, moving vertically at `velocity_y`, is
+    /// standing on top of the platform at `position`.
+    pub fn is_standing_on(&self, position: Vec2, body_rect: &Rect, velocity_y: f32) -> bool {
+        is_standing_on(&self.rect(position), body_rect, velocity_y)
+    }
+
+    /// Whether a body standing on the platform, that is trying to fall through platforms, drops
+    /// down through this one.
+    pub fn drops_through(&self, fall_through: bool) -> bool {
+        fall_through && self.kind == PlatformKind::OneWay
+    }
+}
+
+/// Whether a body with the bounding box `body_rect`, moving vertically at `velocity_y`, is standing
+/// on top of the platform covering `rect`.
+///
+/// Only the top of the platform is solid, so bodies can jump up through it from below.
+pub fn is_standing_on(rect: &Rect, body_rect: &Rect, velocity_y: f32) -> bool {
+    let top = rect.max.y;
+
+    // Bodies moving upwards, such as players jumping, aren't standing on anything
+    velocity_y <= 0.0
+        && body_rect.max.x > rect.min.x
+        && body_rect.min.x < rect.max.x
+        && body_rect.min.y <= top + STANDING_TOLERANCE
+        && body_rect.min.y >= top + velocity_y - STANDING_TOLERANCE
+}
+
+/// Whether a body with the bounding box `body_rect`, that was just pushed out of the way of a
+/// platform, is still stuck inside the side of any of the platforms covering `platform_rects`.
+///
+/// Bodies that only just touch a platform, or are standing on top of it, aren't stuck.
+pub fn is_squeezed(platform_rects: impl IntoIterator<Item = Rect>, body_rect: &Rect) -> bool {
+    platform_rects.into_iter().any(|rect| {
+        let overlap_x = body_rect.max.x.min(rect.max.x) - body_rect.min.x.max(rect.min.x);
+        let overlap_y = body_rect.max.y.min(rect.max.y) - body_rect.min.y.max(rect.min.y);
+        overlap_x > STANDING_TOLERANCE && overlap_y > STANDING_TOLERANCE
+    })
+}
+
+/// Component added to a player that is dropping down through a [`MovingPlatform`].
+///
+/// Only the platform being dropped through is passed through, the player still lands on any others.
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GW1N1MSHY4D7CP9V3G3PSD02"]
+pub struct PlatformDrop {
+    /// The platform that the player is dropping through.
+    pub platform: Entity,
+    /// The number of frames left that the player passes through the platform for.
+    pub frames_remaining: u32,
+}
+
+impl PlatformDrop {
+    pub fn new(platform: Entity) -> Self {
+        Self {
+            platform,
+            frames_remaining: DROP_THROUGH_FRAMES,
+        }
+    }
+
+    /// Count down a frame of the drop, returning whether the player passes through the platform on
+    /// it.
+    pub fn tick(&mut self) -> bool {
+        if self.frames_remaining == 0 {
+            return false;
+        }
+        self.frames_remaining -= 1;
+        true
+    }
+}
+
+/// Component added to a player that is standing on a [`MovingPlatform`].
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GV9ECYZ90EG4QFQA3QQY27N4"]
+pub struct PlatformRider {
+    /// The velocity of the platform on the last frame, which the player keeps when they leave it.
+    pub velocity: Vec2,
+}
+
+fn update_moving_platforms(
+    entities: Res<Entities>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    mut platforms: CompMut<MovingPlatform>,
+    mut riders: CompMut<PlatformRider>,
+    mut drops: CompMut<PlatformDrop>,
+    mut bodies: CompMut<KinematicBody>,
+    mut transforms: CompMut<Transform>,
+    mut collision_world: CollisionWorld,
+    mut player_events: ResMut<PlayerEvents>,
+) {
+    // Move the platforms. They are resolved one after another in the order of their entities, which
+    // is the same on every client, so everybody agrees on which platform pushes a player first.
+    let mut moved_platforms = Vec::new();
+    for (platform_ent, (platform, transform)) in
+        entities.iter_with((&mut platforms, &mut transforms))
+    {
+        let old_position = transform.translation.truncate();
+        let new_position = platform.advance(old_position);
+        transform.translation = new_position.extend(transform.translation.z);
+        moved_platforms.push((platform_ent, old_position, new_position));
+    }
+
+    // The players are carried in the order of their player index, so that everybody agrees on
+    // which of the players squeezed on the same frame is crushed first
+    let players = entities
+        .iter_with((&player_indexes, &mut bodies))
+        .in_order_by_key(|(_ent, (player_idx, _body))| player_idx.0);
+    for (player_ent, (_player_idx, body)) in players {
+        let transform = *transforms.get(player_ent).unwrap();
+        let body_rect = body.bounding_box(transform);
+        let drop = drops.get_mut(player_ent).map(|x| (x.platform, x.tick()));
+        let dropping_through = match drop {
+            Some((platform_ent, true)) => Some(platform_ent),
+            Some((_, false)) => {
+                drops.remove(player_ent);
+                None
+            }
+            None => None,
+        };
+
+        let mut riding = None;
+        let mut pushed = false;
+        let mut crushed = false;
+        for &(platform_ent, old_position, new_position) in &moved_platforms {
+            let platform = platforms.get(platform_ent).unwrap();
+            let delta = new_position - old_position;
+            if dropping_through == Some(platform_ent) {
+                continue;
+            }
+
+            if platform.is_standing_on(old_position, &body_rect, body.velocity.y) {
+                if platform.drops_through(body.fall_through) {
+                    // The frame that the player starts dropping on is the first of the drop
+                    let mut drop = PlatformDrop::new(platform_ent);
+                    drop.tick();
+                    drops.insert(player_ent, drop);
+                    continue;
+                }
+
+                // Ride along with the platform, staying on top of it
+                collision_world.move_horizontal(&mut transforms, player_ent, delta.x);
+                let new_top = platform.rect(new_position).max.y;
+                let dy = new_top - body_rect.min.y;
+                if collision_world.move_vertical(&mut transforms, player_ent, dy) && dy > 0.0 {
+                    // The platform pushed the player into the ceiling
+                    crushed = true;
+                }
+                riding = Some((delta, platform.kind));
+                break;
+            }
+
+            // Push players out of the way of the side of the platform
+            let platform_rect = platform.rect(new_position);
+            if delta.x != 0.0 && platform_rect.overlaps(&body_rect) {
+                let push = if delta.x > 0.0 {
+                    platform_rect.max.x - body_rect.min.x
+                } else {
+                    platform_rect.min.x - body_rect.max.x
+                };
+                if collision_world.move_horizontal(&mut transforms, player_ent, push) {
+                    // The platform pushed the player into a wall
+                    crushed = true;
+                }
+                pushed = true;
+            }
+        }
+
+        // A player pushed out of the way of one platform, right into the side of another, is
+        // caught between them
+        if pushed && !crushed {
+            let body_rect = body.bounding_box(*transforms.get(player_ent).unwrap());
+            crushed = is_squeezed(
+                moved_platforms
+                    .iter()
+                    .map(|&(platform_ent, _, new_position)| {
+                        platforms.get(platform_ent).unwrap().rect(new_position)
+                    }),
+                &body_rect,
+            );
+        }
+
+        if let Some((velocity, kind)) = riding {
+            body.velocity.y = 0.0;
+            body.is_on_ground = true;
+            body.is_on_platform = kind == PlatformKind::OneWay;
+            riders.insert(player_ent, PlatformRider { velocity });
+        } else if let Some(rider) = riders.remove(player_ent) {
+            // Keep the platform's momentum when jumping or walking off of it
+            body.velocity.x += rider.velocity.x;
+            body.velocity.y += rider.velocity.y.max(0.0);
+        }
+
+        if crushed && !killed_players.contains(player_ent) {
+            player_events.kill_by(
+                player_ent,
+                Some(transforms.get(player_ent).unwrap().translation.truncate()),
+                None,
+                Some(key!("crush")),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    const BODY_SIZE: Vec2 = Vec2::new(20.0, 30.0);
+
+    /// Where a player standing on the platform, at the spot the player is put at, is in relation to
+    /// the platform.
+    const STANDING_OFFSET: Vec2 = Vec2::new(10.0, 8.0 + BODY_SIZE.y / 2.0);
+
+    /// Create a test of the platform system, with the `platform` at the origin and a player standing
+    /// on top of it, returning the platform and the player as well.
+    fn platform_test(platform: MovingPlatform) -> (SystemTest, Entity, Entity) {
+        let mut test = SystemTest::new(
+            |world| {
+                world.init_resource::<PlayerEvents>();
+            },
+            |stages| {
+                stages.add_system_to_stage(CoreStage::Last, update_moving_platforms);
+            },
+        );
+
+        let (platform_ent, player) = test
+            .world
+            .run_initialized_system(
+                move |mut entities: ResMut<Entities>,
+                      mut platforms: CompMut<MovingPlatform>,
+                      mut player_indexes: CompMut<PlayerIdx>,
+                      mut bodies: CompMut<KinematicBody>,
+                      mut colliders: CompMut<Collider>,
+                      mut actors: CompMut<Actor>,
+                      mut transforms: CompMut<Transform>| {
+                    let platform_ent = entities.create();
+                    platforms.insert(platform_ent, platform.clone());
+                    transforms.insert(platform_ent, default());
+
+                    let player = entities.create();
+                    let shape = ColliderShape::Rectangle { size: BODY_SIZE };
+                    player_indexes.insert(player, PlayerIdx(0));
+                    bodies.insert(player, KinematicBody { shape, ..default() });
+                    colliders.insert(player, Collider { shape, ..default() });
+                    actors.insert(player, Actor);
+                    transforms.insert(
+                        player,
+                        Transform::from_translation(STANDING_OFFSET.extend(0.0)),
+                    );
+                    Ok((platform_ent, player))
+                },
+            )
+            .unwrap();
+
+        (test, platform_ent, player)
+    }
+
+    /// Run a frame, after moving the player the way their body's velocity takes them, like the
+    /// physics update does.
+    fn run_frame(test: &mut SystemTest, player: Entity) {
+        test.world
+            .run_initialized_system(
+                move |mut bodies: CompMut<KinematicBody>, mut transforms: CompMut<Transform>| {
+                    let body = bodies.get(player).unwrap();
+                    transforms.get_mut(player).unwrap().translation += body.velocity.extend(0.0);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        test.run();
+    }
+
+    fn position(world: &World, entity: Entity) -> Vec2 {
+        let transforms = world.components.get::<Transform>();
+        let transforms = transforms.borrow();
+        transforms.get(entity).unwrap().translation.truncate()
+    }
+
+    fn body(world: &World, entity: Entity) -> KinematicBody {
+        let bodies = world.components.get::<KinematicBody>();
+        let bodies = bodies.borrow();
+        *bodies.get(entity).unwrap()
+    }
+
+    fn set_velocity(world: &mut World, entity: Entity, velocity: Vec2) {
+        let bodies = world.components.get::<KinematicBody>();
+        let mut bodies = bodies.borrow_mut();
+        bodies.get_mut(entity).unwrap().velocity = velocity;
+    }
+
+    #[test]
+    fn standing_player_tracks_the_platform() {
+        let (mut test, platform, player) = platform_test(MovingPlatform {
+            size: vec2(64.0, 16.0),
+            path: vec![vec2(0.0, 0.0), vec2(100.0, 50.0)],
+            speed: 2.0,
+            kind: PlatformKind::OneWay,
+            target: 1,
+        });
+        let gravity = 0.6;
+
+        for _ in 0..50 {
+            // Gravity pulls the player down during the physics update
+            let velocity = body(&test.world, player).velocity;
+            set_velocity(&mut test.world, player, velocity - vec2(0.0, gravity));
+            run_frame(&mut test, player);
+
+            // The player stays at the same spot on the platform
+            let offset = position(&test.world, player) - position(&test.world, platform);
+            assert!(
+                offset.abs_diff_eq(STANDING_OFFSET, 0.001),
+                "Player is at {offset} from the platform"
+            );
+            let body = body(&test.world, player);
+            assert!(body.is_on_ground && body.is_on_platform);
+            assert_eq!(body.velocity.y, 0.0);
+        }
+        assert!(position(&test.world, platform).x > 50.0);
+
+        // Jumping players aren't carried along, but keep the platform's momentum
+        let jumped_from = position(&test.world, player);
+        set_velocity(&mut test.world, player, vec2(0.0, 5.0));
+        run_frame(&mut test, player);
+        assert_eq!(position(&test.world, player), jumped_from + vec2(0.0, 5.0));
+        assert!(body(&test.world, player).velocity.x > 0.0);
+        assert!(!test
+            .world
+            .components
+            .get::<PlatformRider>()
+            .borrow()
+            .contains(player));
+    }
+
+    #[test]
+    fn players_jump_up_and_drop_down_through_one_way_platforms() {
+        let platform = MovingPlatform {
+            size: vec2(64.0, 16.0),
+            kind: PlatformKind::OneWay,
+            ..default()
+        };
+        let body_size = vec2(20.0, 30.0);
+
+        // Jumping up through the platform from below, the player isn't caught by it
+        let rising = Rect::new(0.0, 10.0, body_size.x, body_size.y);
+        assert!(!platform.is_standing_on(Vec2::ZERO, &rising, 4.0));
+        // Until they come back down onto it
+        let landing = Rect::new(0.0, 8.0 + body_size.y / 2.0, body_size.x, body_size.y);
+        assert!(platform.is_standing_on(Vec2::ZERO, &landing, -1.0));
+
+        // Holding down and jumping drops them through, unless the platform is a floor
+        assert!(!platform.drops_through(false));
+        assert!(platform.drops_through(true));
+        let floor = MovingPlatform {
+            kind: PlatformKind::Floor,
+            ..platform.clone()
+        };
+        assert!(!floor.drops_through(true));
+
+        // The drop only lasts a moment
+        let mut drop = PlatformDrop::new(Entity::new(0, 0));
+        let frames = std::iter::from_fn(|| drop.tick().then_some(())).count();
+        assert_eq!(frames as u32, DROP_THROUGH_FRAMES);
+    }
+
+    #[test]
+    fn dropping_players_pass_through_the_platform_for_a_moment() {
+        let platform = MovingPlatform {
+            size: vec2(64.0, 16.0),
+            path: vec![Vec2::ZERO],
+            kind: PlatformKind::OneWay,
+            ..default()
+        };
+        let set_fall_through = |world: &mut World, player: Entity, fall_through: bool| {
+            let bodies = world.components.get::<KinematicBody>();
+            let mut bodies = bodies.borrow_mut();
+            bodies.get_mut(player).unwrap().fall_through = fall_through;
+        };
+        let is_riding = |world: &World, player: Entity| {
+            let riders = world.components.get::<PlatformRider>();
+            let riders = riders.borrow();
+            riders.contains(player)
+        };
+
+        // A player held at the spot they were standing on, after holding down and jumping for a
+        // frame, isn't caught by the platform until the drop is over
+        let (mut test, _platform, player) = platform_test(platform.clone());
+        set_fall_through(&mut test.world, player, true);
+        let mut frames = 0;
+        while frames <= DROP_THROUGH_FRAMES {
+            run_frame(&mut test, player);
+            set_fall_through(&mut test.world, player, false);
+            if is_riding(&test.world, player) {
+                break;
+            }
+            frames += 1;
+        }
+        assert_eq!(frames, DROP_THROUGH_FRAMES);
+        assert!(body(&test.world, player).is_on_platform);
+
+        // A falling player is long gone by then
+        let (mut test, _platform, player) = platform_test(platform);
+        set_fall_through(&mut test.world, player, true);
+        for _ in 0..DROP_THROUGH_FRAMES + 1 {
+            let velocity = body(&test.world, player).velocity;
+            set_velocity(&mut test.world, player, velocity - vec2(0.0, 0.6));
+            run_frame(&mut test, player);
+            set_fall_through(&mut test.world, player, false);
+            assert!(!is_riding(&test.world, player));
+        }
+        assert!(position(&test.world, player).y < STANDING_OFFSET.y - BODY_SIZE.y);
+    }
+
+    #[test]
+    fn player_between_platforms_is_squeezed() {
+        let body_rect = Rect::new(0.0, 0.0, 20.0, 30.0);
+        let left = Rect::new(-20.0, 0.0, 20.0, 16.0);
+
+        // Pushed flush against the side of a platform
+        assert!(!is_squeezed([left], &body_rect));
+
+        // Pushed into the side of another one
+        let right = Rect::new(18.0, 0.0, 20.0, 16.0);
+        assert!(is_squeezed([left, right], &body_rect));
+
+        // Standing on top of one
+        let below = Rect::new(0.0, -22.5, 64.0, 16.0);
+        assert!(!is_squeezed([below], &body_rect));
+    }
+}
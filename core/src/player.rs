@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use crate::{item::ItemGrabbed, physics::KinematicBody, prelude::*};
+use crate::{item::ItemGrabbed, physics::KinematicBody, prelude::*, random::GlobalRng};
 
 mod state;
 use bones_lib::animation::AnimationBankSprite;
@@ -8,6 +8,7 @@ pub use state::*;
 
 pub fn install(session: &mut GameSession) {
     state::install(session);
+    session.world.init_resource::<KillEvents>();
 
     // Add other player systems
     session
@@ -16,7 +17,10 @@ pub fn install(session: &mut GameSession) {
         .add_system_to_stage(CoreStage::PostUpdate, handle_player_events)
         .add_system_to_stage(CoreStage::PostUpdate, play_itemless_fin_animations)
         .add_system_to_stage(CoreStage::PostUpdate, player_facial_animations)
-        .add_system_to_stage(CoreStage::Last, update_player_layers);
+        .add_system_to_stage(CoreStage::PostUpdate, play_footstep_sounds)
+        .add_system_to_stage(CoreStage::Last, update_player_layers)
+        .add_system_to_stage(CoreStage::Last, update_invulnerable_players)
+        .add_system_to_stage(CoreStage::Last, drop_expired_combos);
 }
 
 /// The player index, for example Player 1, Player 2, and so on.
@@ -33,10 +37,94 @@ pub struct PlayerLayers {
     pub fin_offset: Vec2,
     pub face_ent: Entity,
     pub face_anim: Key,
+    /// How far the fin, and the item held in it, are turned up from straight ahead to aim, in
+    /// radians. Negative angles aim down.
+    pub aim_angle: f32,
 }
 
 impl PlayerLayers {
     pub const FIN_Z_OFFSET: f32 = 2.0;
+
+    /// Get the [`aim_angle`][Self::aim_angle] that points the fin in the given direction.
+    ///
+    /// The angle is the same whichever way the player is facing, because the fin is flipped along
+    /// with the player's sprite.
+    pub fn aim_angle_towards(aim: Vec2) -> f32 {
+        if aim == Vec2::ZERO {
+            return 0.0;
+        }
+        Vec2::X.angle_between(vec2(aim.x.abs(), aim.y))
+    }
+}
+
+/// How far the movement input has to be pushed for the player to turn around.
+pub const FACING_DEADZONE: f32 = 0.2;
+
+/// The number of frames in a row that the player has to be pushing against the way they are facing
+/// before they turn around, so that a flick of the stick doesn't flip them back and forth.
+pub const FACING_HYSTERESIS_FRAMES: u32 = 2;
+
+/// Component containing the direction that a player is facing.
+///
+/// This is the one place that the player's direction is decided, and their sprite is flipped to
+/// match it at the end of the player state update, so everything that moves, aims, and animates
+/// the player agrees on which way they are facing.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GWGDK1EZWWKX5Q02MHPCB252"]
+pub struct Facing {
+    /// Whether the player is facing left.
+    pub left: bool,
+    /// The number of frames in a row that the player has been pushing against the way they are
+    /// facing.
+    pub turning_frames: u32,
+    /// The number of frames that the player can't turn around for, such as while attacking.
+    pub held_frames: u32,
+}
+
+impl Facing {
+    /// Get the horizontal direction that the player is facing, `-1.0` for left and `1.0` for
+    /// right.
+    pub fn direction(&self) -> f32 {
+        if self.left {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Turn to face the way that the player is moving, once they have been moving that way for
+    /// long enough.
+    pub fn update(&mut self, move_direction: f32) {
+        let wants_left = if move_direction > FACING_DEADZONE {
+            false
+        } else if move_direction < -FACING_DEADZONE {
+            true
+        } else {
+            self.turning_frames = 0;
+            return;
+        };
+        if wants_left == self.left || self.held_frames > 0 {
+            self.turning_frames = 0;
+            return;
+        }
+
+        self.turning_frames += 1;
+        if self.turning_frames >= FACING_HYSTERESIS_FRAMES {
+            self.left = wants_left;
+            self.turning_frames = 0;
+        }
+    }
+
+    /// Turn to face the given way right away, such as when grabbing onto a ledge.
+    pub fn set(&mut self, left: bool) {
+        self.left = left;
+        self.turning_frames = 0;
+    }
+
+    /// Stop the player from turning around for the next `frames` frames.
+    pub fn hold(&mut self, frames: u32) {
+        self.held_frames = self.held_frames.max(frames);
+    }
 }
 
 /// A component representing the current emote state of a player.
@@ -101,6 +189,204 @@ impl Emote {
 #[ulid = "01GP49AK25A8S9G2GYNAVE4PTN"]
 pub struct PlayerKilled {
     pub hit_from: Option<Vec2>,
+    /// The player that made the kill, if any.
+    pub killer: Option<Entity>,
+    /// The kind of weapon used for the kill, if any.
+    pub weapon: Option<Key>,
+}
+
+/// Component that protects a player from being killed, usually added for a short time after they
+/// spawn.
+///
+/// The player's sprite will flash until the invulnerability wears off.
+#[derive(Clone, TypeUlid, Debug)]
+#[ulid = "01GTHGBX8T7SZFDVNQPMPJ7BCZ"]
+pub struct Invulnerable {
+    /// The number of frames until the player can be killed again.
+    pub frames_remaining: u32,
+}
+
+/// Resource containing the queue of player kills, for displaying in a killfeed.
+#[derive(Clone, TypeUlid, Debug, Default)]
+#[ulid = "01GTXTJXDPPYZGS6HMSWV7PJQQ"]
+pub struct KillEvents {
+    /// Kills that haven't been handled yet, oldest first.
+    pub queue: VecDeque<KillEvent>,
+}
+
+impl KillEvents {
+    /// The maximum number of kills kept in the queue.
+    ///
+    /// If nothing is draining the queue, the oldest kills are dropped to make room for new ones.
+    pub const MAX_LEN: usize = 32;
+
+    /// Add a kill to the queue.
+    pub fn send(&mut self, event: KillEvent) {
+        if self.queue.len() >= Self::MAX_LEN {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(event);
+    }
+
+    /// Remove and return all of the kills in the queue.
+    pub fn drain(&mut self) -> impl Iterator<Item = KillEvent> + '_ {
+        self.queue.drain(..)
+    }
+}
+
+/// A player kill that was sent to the [`KillEvents`] queue.
+#[derive(Clone, Debug)]
+pub struct KillEvent {
+    /// The player that was killed.
+    pub victim: Entity,
+    /// The player that made the kill.
+    ///
+    /// This is `None` for suicides and environmental deaths, such as falling out of the map.
+    pub killer: Option<Entity>,
+    /// The kind of weapon used for the kill, if any.
+    pub weapon: Option<Key>,
+    /// Whether the player was killed by a teammate.
+    ///
+    /// Team kills don't score a point for the killer.
+    pub team_kill: bool,
+}
+
+/// Component containing the health of a player.
+///
+/// Non-lethal [`Damage`] takes away from the player's health, and the player is killed once it
+/// reaches zero.
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GV37BZZ9WTYJ6ZQ5NJM0YW1W"]
+pub struct Health {
+    /// The player's remaining health.
+    pub current: f32,
+    /// The health that the player spawned with.
+    pub max: f32,
+}
+
+impl Health {
+    /// Create a full health bar with the given maximum.
+    pub fn full(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Take the given amount of damage, returning whether it was lethal.
+    pub fn take_damage(&mut self, amount: f32) -> bool {
+        self.current -= amount;
+        self.is_depleted()
+    }
+
+    /// Whether the player has no health left.
+    pub fn is_depleted(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// Component containing how hard a player is to knock back, from their character's
+/// [`PlayerStatsMeta::weight`].
+///
+/// Bodies without a weight are knocked back as if they had the default weight of `1.0`.
+#[derive(Clone, Copy, Debug, TypeUlid)]
+#[ulid = "01GWV8R3N6K2T5QZ7XJ4MDBH9F"]
+pub struct Weight(pub f32);
+
+impl Default for Weight {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl Weight {
+    /// The lowest weight a player can have, so that nobody is knocked back infinitely far.
+    pub const MIN: f32 = 0.1;
+
+    /// Scale the velocity that the player is knocked back, or otherwise pushed around, with by how
+    /// heavy they are.
+    pub fn knockback(&self, velocity: Vec2) -> Vec2 {
+        velocity / self.0.max(Self::MIN)
+    }
+}
+
+/// Component tracking the hits that a player has taken in quick succession.
+///
+/// Each hit in a combo deals less damage than the last, following the [`ComboMeta`] damage scaling,
+/// and the combo is dropped once the player goes long enough without being hit.
+#[derive(Clone, Copy, Debug, Default, TypeUlid)]
+#[ulid = "01GWT3K1M8D3Q6YF0ZB2RN4EVS"]
+pub struct ComboState {
+    /// The number of hits in the combo.
+    pub hits: u32,
+    /// The frame that the last hit of the combo landed on.
+    pub last_hit_frame: u64,
+}
+
+impl ComboState {
+    /// Whether the combo has been dropped by the given frame.
+    pub fn is_expired(&self, frame: u64, meta: &ComboMeta) -> bool {
+        frame.saturating_sub(self.last_hit_frame) > meta.window_frames
+    }
+
+    /// Add a hit on the given frame to the combo, starting a new one if it was dropped, and return
+    /// the amount that the damage of the hit is scaled by.
+    pub fn hit(&mut self, frame: u64, meta: &ComboMeta) -> f32 {
+        if self.is_expired(frame, meta) {
+            self.hits = 0;
+        }
+        let multiplier = meta.damage_multiplier(self.hits);
+        self.hits += 1;
+        self.last_hit_frame = frame;
+        multiplier
+    }
+}
+
+/// Damage dealt to a player by a [`PlayerEvent::Damage`].
+#[derive(Clone, Debug)]
+pub struct Damage {
+    /// The amount of health to take away from the player.
+    pub amount: f32,
+    /// The entity that dealt the damage, if any.
+    pub source: Option<Entity>,
+    /// The direction that the player was hit in, which they are knocked back in.
+    pub direction: Vec2,
+    /// How hard the player is knocked back, relative to the usual knockback speed.
+    pub knockback: f32,
+    /// The kind of weapon that dealt the damage, if any, recorded if the damage is lethal.
+    pub weapon: Option<Key>,
+    /// The kind of hit that dealt the damage, which decides how long the hit-stop lasts.
+    pub kind: HitKind,
+}
+
+/// The kinds of hits that [`Damage`] is dealt by, which each have their own [`HitStopMeta`]
+/// freeze.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HitKind {
+    /// A hit from another player up close, such as a special or a throw.
+    #[default]
+    Melee,
+    /// A hit from a bullet or other projectile.
+    Projectile,
+    /// A hit from an explosion.
+    Explosion,
+    /// A hit from a hazard in the map, such as spikes.
+    Hazard,
+}
+
+/// Component added to a player that took non-lethal [`Damage`], putting them into hitstun.
+///
+/// The hit is applied, and the component removed, during the next state transition.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GVP9GV8H4MRTRBB9SEK0HXCS"]
+pub struct HitReceived {
+    /// The amount of health that was taken away from the player.
+    pub damage: f32,
+    /// The direction that the player is knocked back in.
+    pub direction: Vec2,
+    /// How hard the player is knocked back, relative to the usual knockback speed.
+    pub knockback: f32,
+    /// The player that dealt the damage, if any.
+    pub source: Option<Entity>,
+    /// The least number of frames of hitstun that the hit causes, whatever its damage.
+    pub stun_frames: u32,
 }
 
 /// Resource containing the player event queue.
@@ -129,7 +415,30 @@ impl PlayerEvents {
 
     #[inline]
     pub fn kill(&mut self, player: Entity, hit_from: Option<Vec2>) {
-        self.queue.push_back(PlayerEvent::Kill { player, hit_from })
+        self.kill_by(player, hit_from, None, None)
+    }
+
+    /// Kill a player, recording who killed them and with what for the [`KillEvents`] queue.
+    #[inline]
+    pub fn kill_by(
+        &mut self,
+        player: Entity,
+        hit_from: Option<Vec2>,
+        killer: Option<Entity>,
+        weapon: Option<Key>,
+    ) {
+        self.queue.push_back(PlayerEvent::Kill {
+            player,
+            hit_from,
+            killer,
+            weapon,
+        })
+    }
+
+    /// Damage a player, killing them if they run out of [`Health`].
+    #[inline]
+    pub fn damage(&mut self, player: Entity, damage: Damage) {
+        self.queue.push_back(PlayerEvent::Damage { player, damage })
     }
 
     #[inline]
@@ -147,7 +456,16 @@ pub enum PlayerEvent {
     Kill {
         player: Entity,
         hit_from: Option<Vec2>,
+        /// The player that made the kill, if any.
+        killer: Option<Entity>,
+        /// The kind of weapon used for the kill, if any.
+        weapon: Option<Key>,
     },
+    /// Take health away from a player.
+    ///
+    /// Sub-lethal damage puts the player into hitstun, and lethal damage kills them, crediting the
+    /// source of the damage with the kill.
+    Damage { player: Entity, damage: Damage },
     /// Despawn a player.
     ///
     /// > **Note:** This is different than the [`Kill`][Self::Kill] event in that it immediately
@@ -176,10 +494,45 @@ fn handle_player_events(
     attachments: Comp<Attachment>,
     player_indexes: Comp<PlayerIdx>,
     player_layers: Comp<PlayerLayers>,
+    invulnerables: Comp<Invulnerable>,
+    transforms: Comp<Transform>,
+    core_meta: Res<CoreMetaArc>,
+    mut healths: CompMut<Health>,
+    mut hits_received: CompMut<HitReceived>,
+    player_inputs: Res<PlayerInputs>,
+    friendly_fire: Res<FriendlyFire>,
+    mut particle_events: ResMut<ParticleEvents>,
+    mut decal_events: ResMut<DecalEvents>,
+    mut hit_stops: CompMut<HitStop>,
+    frame: Res<SimulationFrame>,
+    mut combos: CompMut<ComboState>,
+    player_states: Comp<PlayerState>,
+    mut shields: CompMut<states::block::Shield>,
+    mut audio_events: ResMut<AudioEvents>,
+    mut stats: ResMut<SessionStats>,
 ) {
+    let max_health = core_meta.hitstun.max_health;
+
+    // Whether the player was hit by a teammate that isn't allowed to hurt them
+    let is_blocked_friendly_fire = |player: Entity, source: Option<Entity>| {
+        if **friendly_fire {
+            return false;
+        }
+        let (Some(player_idx), Some(source_idx)) =
+            (player_indexes.get(player), source.and_then(|x| player_indexes.get(x))) else {
+            return false;
+        };
+        player_inputs.are_teammates(player_idx.0, source_idx.0)
+    };
+
     while let Some(event) = player_events.queue.pop_front() {
         match event {
-            PlayerEvent::Kill { player, hit_from } => {
+            PlayerEvent::Kill {
+                player,
+                hit_from,
+                killer,
+                weapon,
+            } => {
                 if players_killed.contains(player) {
                     // No need to kill him again
                     continue;
@@ -191,14 +544,147 @@ fn handle_player_events(
                     continue;
                 };
 
+                if invulnerables.contains(player) || is_blocked_friendly_fire(player, killer) {
+                    // The player can't be killed right now
+                    continue;
+                }
+
                 debug!("Killing player: {}", idx.0);
 
-                // Drop any items the player was carrying
-                player_events
-                    .queue
-                    .push_front(PlayerEvent::SetInventory { player, item: None });
+                // Kills are always lethal, whatever health the player had left
+                healths.insert(
+                    player,
+                    Health {
+                        current: 0.0,
+                        max: healths.get(player).map(|x| x.max).unwrap_or(max_health),
+                    },
+                );
 
-                players_killed.insert(player, PlayerKilled { hit_from });
+                players_killed.insert(
+                    player,
+                    PlayerKilled {
+                        hit_from,
+                        killer,
+                        weapon,
+                    },
+                );
+            }
+            PlayerEvent::Damage { player, damage } => {
+                // Dead and non-player entities can't be damaged
+                if !player_indexes.contains(player) || players_killed.contains(player) {
+                    continue;
+                }
+                // Neither can invulnerable players, or teammates without friendly fire, who throw
+                // off sparks instead
+                if invulnerables.contains(player) || is_blocked_friendly_fire(player, damage.source)
+                {
+                    if let Some(transform) = transforms.get(player) {
+                        particle_events.emit(
+                            &core_meta.particles.sparks,
+                            transform.translation,
+                            -damage.direction,
+                        );
+                    }
+                    continue;
+                }
+
+                // Hits in quick succession deal less and less damage
+                let mut combo = combos.get(player).copied().unwrap_or_default();
+                let mut damage = damage;
+                damage.amount *= combo.hit(**frame, &core_meta.combo);
+                combos.insert(player, combo);
+
+                // Blocking players take a fraction of the hit, at the cost of their shield
+                let blocking = player_states
+                    .get(player)
+                    .map_or(false, |x| x.current == states::block::ID);
+                let mut shield_broken = false;
+                if let Some(shield) = shields.get_mut(player).filter(|_| blocking) {
+                    shield_broken = shield.block(&mut damage, &core_meta.shield);
+                    if let Some(transform) = transforms.get(player) {
+                        audio_events.play_with_priority(
+                            core_meta.shield.block_sound.clone(),
+                            core_meta.shield.block_sound_volume,
+                            Some(transform.translation.truncate()),
+                            SoundPriority::High,
+                        );
+                        particle_events.emit(
+                            &core_meta.particles.sparks,
+                            transform.translation,
+                            -damage.direction,
+                        );
+                    }
+                }
+
+                let mut health = healths
+                    .get(player)
+                    .copied()
+                    .unwrap_or_else(|| Health::full(max_health));
+                let health_before = health.current.max(0.0);
+                let lethal = health.take_damage(damage.amount);
+                healths.insert(player, health);
+
+                // Freeze the players involved in the hit for a moment to sell the impact
+                let hit_stop = core_meta.hit_stop.hit_frames(damage.kind, damage.amount);
+                apply_hit_stop(&mut hit_stops, player, hit_stop.victim);
+                if let Some(source) = damage.source.filter(|&x| x != player) {
+                    if player_indexes.contains(source) {
+                        apply_hit_stop(&mut hit_stops, source, hit_stop.attacker);
+                    }
+                }
+
+                // Credit the source with the hit, unless it hit itself or a teammate
+                let player_idx = player_indexes.get(player).unwrap().0;
+                if let Some(source_idx) = damage.source.and_then(|x| player_indexes.get(x)) {
+                    if source_idx.0 != player_idx
+                        && !player_inputs.are_teammates(source_idx.0, player_idx)
+                    {
+                        let source_stats = stats.for_player_mut(source_idx.0);
+                        source_stats.damage_dealt += damage.amount.min(health_before);
+                        source_stats.longest_combo = source_stats.longest_combo.max(combo.hits);
+                    }
+                }
+
+                if lethal {
+                    // Knock the player away from where the hit came from
+                    let hit_from = transforms.get(player).map(|transform| {
+                        transform.translation.truncate() - damage.direction.normalize_or_zero()
+                    });
+                    player_events.queue.push_front(PlayerEvent::Kill {
+                        player,
+                        hit_from,
+                        killer: damage.source,
+                        weapon: damage.weapon,
+                    });
+                } else if blocking && !shield_broken {
+                    // Blocked hits only push the player back a little
+                    if let Some(shield) = shields.get_mut(player) {
+                        shield.pushback = Some(
+                            damage.direction.normalize_or_zero()
+                                * core_meta.hitstun.knockback_speed
+                                * damage.knockback,
+                        );
+                    }
+                } else {
+                    if let Some(transform) = transforms.get(player) {
+                        decal_events.splatter(transform.translation, damage.direction);
+                    }
+                    hits_received.insert(
+                        player,
+                        HitReceived {
+                            damage: damage.amount,
+                            direction: damage.direction,
+                            knockback: damage.knockback,
+                            source: damage.source,
+                            // Breaking a player's shield leaves them open for a while
+                            stun_frames: if shield_broken {
+                                core_meta.shield.break_stun_frames
+                            } else {
+                                0
+                            },
+                        },
+                    );
+                }
             }
             PlayerEvent::Despawn { player } => {
                 if player_indexes.contains(player) {
@@ -230,6 +716,9 @@ fn handle_player_events(
                 // If there is a new item, grab it
                 if let Some(item) = item {
                     items_grabbed.insert(item, ItemGrabbed);
+                    if let Some(player_idx) = player_indexes.get(player) {
+                        stats.for_player_mut(player_idx.0).items_picked_up += 1;
+                    }
                 }
 
                 // Update the inventory
@@ -246,6 +735,77 @@ fn handle_player_events(
     }
 }
 
+/// Get the color to draw a player's sprites with, tinted by the player's color accent.
+pub fn player_sprite_color(accent: Option<ColorMeta>, alpha: f32) -> Color {
+    let [r, g, b, a] = accent.map(|x| x.0).unwrap_or([1.0; 4]);
+    Color::rgba(r, g, b, a * alpha)
+}
+
+/// Drop the combos of players that haven't been hit for long enough.
+fn drop_expired_combos(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    frame: Res<SimulationFrame>,
+    mut combos: CompMut<ComboState>,
+) {
+    let expired = entities
+        .iter_with(&combos)
+        .filter(|(_ent, combo)| combo.is_expired(**frame, &core_meta.combo))
+        .map(|(ent, _)| ent)
+        .collect::<Vec<_>>();
+    for ent in expired {
+        combos.remove(ent);
+    }
+}
+
+/// Count down the invulnerability of players, flashing their sprites until it wears off.
+fn update_invulnerable_players(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    mut invulnerables: CompMut<Invulnerable>,
+    player_layers: Comp<PlayerLayers>,
+    mut sprites: CompMut<AtlasSprite>,
+) {
+    let flash_interval = core_meta.respawn.flash_interval.max(1);
+    let mut no_longer_invulnerable = Vec::new();
+
+    for (player_ent, invulnerable) in entities.iter_with(&mut invulnerables) {
+        invulnerable.frames_remaining = invulnerable.frames_remaining.saturating_sub(1);
+
+        // Alternate between faded and fully visible, ending on visible
+        let visible = invulnerable.frames_remaining == 0
+            || (invulnerable.frames_remaining / flash_interval) % 2 == 0;
+        let alpha = if visible { 1.0 } else { 0.3 };
+        // Dead players are drawn without their color accent
+        let accent = player_indexes
+            .get(player_ent)
+            .filter(|_| !killed_players.contains(player_ent))
+            .and_then(|idx| player_inputs.players[idx.0].color);
+        let color = player_sprite_color(accent, alpha);
+
+        let mut sprite_ents = vec![player_ent];
+        if let Some(layers) = player_layers.get(player_ent) {
+            sprite_ents.extend([layers.fin_ent, layers.face_ent]);
+        }
+        for ent in sprite_ents {
+            if let Some(sprite) = sprites.get_mut(ent) {
+                sprite.color = color;
+            }
+        }
+
+        if invulnerable.frames_remaining == 0 {
+            no_longer_invulnerable.push(player_ent);
+        }
+    }
+
+    for player_ent in no_longer_invulnerable {
+        invulnerables.remove(player_ent);
+    }
+}
+
 fn hydrate_players(
     mut entities: ResMut<Entities>,
     player_inputs: Res<PlayerInputs>,
@@ -254,12 +814,19 @@ fn hydrate_players(
     mut player_states: CompMut<PlayerState>,
     mut inventories: CompMut<Inventory>,
     mut animation_bank_sprites: CompMut<AnimationBankSprite>,
+    mut animation_frame_events: CompMut<AnimationFrameEvents>,
+    mut animation_transitions: CompMut<AnimationTransitions>,
     mut atlas_sprites: CompMut<AtlasSprite>,
     mut kinematic_bodies: CompMut<KinematicBody>,
+    mut facings: CompMut<Facing>,
     mut player_layers: CompMut<PlayerLayers>,
     mut player_body_attachments: CompMut<PlayerBodyAttachment>,
     mut transforms: CompMut<Transform>,
     mut emote_states: CompMut<EmoteState>,
+    mut healths: CompMut<Health>,
+    mut weights: CompMut<Weight>,
+    mut shields: CompMut<states::block::Shield>,
+    core_meta: Res<CoreMetaArc>,
 ) {
     let mut not_hydrated_bitset = player_states.bitset().clone();
     not_hydrated_bitset.bit_not();
@@ -274,6 +841,7 @@ fn hydrate_players(
     for player_entity in entities.iter_with_bitset(&not_hydrated_bitset) {
         let player_idx = player_indexes.get(player_entity).unwrap();
         let player_handle = &player_inputs.players[player_idx.0].selected_player;
+        let color = player_sprite_color(player_inputs.players[player_idx.0].color, 1.0);
 
         let Some(meta) = player_assets.get(&player_handle.get_bevy_handle()) else {
             continue;
@@ -286,14 +854,30 @@ fn hydrate_players(
         };
 
         player_states.insert(player_entity, default());
+        facings.insert(player_entity, default());
         emote_states.insert(player_entity, default());
+        healths.insert(player_entity, Health::full(core_meta.hitstun.max_health));
+        weights.insert(player_entity, Weight(meta.stats.weight));
+        shields.insert(
+            player_entity,
+            states::block::Shield::full(&core_meta.shield),
+        );
         animation_bank_sprites.insert(player_entity, animation_bank_sprite);
+        animation_frame_events.insert(
+            player_entity,
+            AnimationFrameEvents(meta.layers.body.animations.frame_events.clone()),
+        );
+        animation_transitions.insert(
+            player_entity,
+            AnimationTransitions(meta.layers.body.transitions.clone()),
+        );
         inventories.insert(player_entity, default());
 
         atlas_sprites.insert(
             player_entity,
             AtlasSprite {
                 atlas: meta.layers.body.atlas.clone(),
+                color,
                 ..default()
             },
         );
@@ -306,6 +890,7 @@ fn hydrate_players(
                 has_mass: true,
                 has_friction: false,
                 gravity: meta.gravity,
+                corner_correction: true,
                 ..default()
             },
         );
@@ -321,6 +906,7 @@ fn hydrate_players(
                 fin_offset: Vec2::ZERO,
                 face_anim: key!("idle"),
                 face_ent: face_entity,
+                aim_angle: 0.0,
             },
         );
 
@@ -330,6 +916,7 @@ fn hydrate_players(
             fin_entity,
             AtlasSprite {
                 atlas: meta.layers.fin.atlas.clone(),
+                color,
                 ..default()
             },
         );
@@ -347,6 +934,7 @@ fn hydrate_players(
                 player: player_entity,
                 offset: meta.layers.fin.offset.extend(PlayerLayers::FIN_Z_OFFSET),
                 sync_animation: false,
+                rotation: 0.0,
             },
         );
 
@@ -356,6 +944,7 @@ fn hydrate_players(
             face_entity,
             AtlasSprite {
                 atlas: meta.layers.face.atlas.clone(),
+                color,
                 ..default()
             },
         );
@@ -373,6 +962,7 @@ fn hydrate_players(
                 player: player_entity,
                 offset: meta.layers.face.offset.extend(0.01),
                 sync_animation: false,
+                rotation: 0.0,
             },
         );
     }
@@ -393,28 +983,59 @@ fn play_itemless_fin_animations(
 
         if inventory.is_none() {
             player_layers.fin_anim = animation_bank.current;
+            player_layers.aim_angle = 0.0;
         }
     }
 }
 
+/// Play the footstep sounds of players whose animations reached a `footstep` frame.
+fn play_footstep_sounds(
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    player_assets: BevyAssets<PlayerMeta>,
+    transforms: Comp<Transform>,
+    animation_events: Res<AnimationEvents>,
+    rng: Res<GlobalRng>,
+    mut audio_events: ResMut<AudioEvents>,
+) {
+    for event in animation_events.with_key(key!("footstep")) {
+        let Some(player_idx) = player_indexes.get(event.entity) else {
+            continue;
+        };
+        let meta_handle = player_inputs.players[player_idx.0]
+            .selected_player
+            .get_bevy_handle();
+        let (Some(meta), Some(transform)) =
+            (player_assets.get(&meta_handle), transforms.get(event.entity)) else {
+            continue;
+        };
+        audio_events.play_variation(
+            &rng,
+            &meta.sounds.footstep,
+            &meta.sounds.footstep_variations,
+            meta.sounds.footstep_volume,
+            Some(transform.translation.truncate()),
+            SoundPriority::Low,
+        );
+    }
+}
+
 fn player_facial_animations(
     entities: Res<Entities>,
     mut player_layers: CompMut<PlayerLayers>,
     emote_regions: Comp<EmoteRegion>,
     transforms: Comp<Transform>,
-    atlas_sprites: Comp<AtlasSprite>,
+    facings: Comp<Facing>,
     mut emote_states: CompMut<EmoteState>,
     players_killed: Comp<PlayerKilled>,
     animation_bank_sprites: Comp<AnimationBankSprite>,
 ) {
-    for (player_ent, (player_layer, atlas_sprite, animation_bank, emote_state)) in entities
-        .iter_with((
-            &mut player_layers,
-            &atlas_sprites,
-            &animation_bank_sprites,
-            &mut emote_states,
-        ))
-    {
+    for (player_ent, (player_layer, facing, animation_bank, emote_state)) in entities.iter_with((
+        &mut player_layers,
+        &facings,
+        &animation_bank_sprites,
+        &mut emote_states,
+    )) {
         if players_killed.contains(player_ent) {
             *emote_state = EmoteState::Neutral;
             player_layer.face_anim = animation_bank.current;
@@ -430,7 +1051,7 @@ fn player_facial_animations(
             }
             let emote_pos = transform.translation.truncate();
 
-            let direction = if atlas_sprite.flip_x { -1.0f32 } else { 1.0 };
+            let direction = facing.direction();
             let is_facing_region = direction.signum() != (player_pos.x - emote_pos.x).signum();
             if !emote_region.direction_sensitive || is_facing_region {
                 let emote_rect = Rect::new(
@@ -487,8 +1108,162 @@ fn update_player_layers(
         let fin_attachment = player_body_attachments.get_mut(layers.fin_ent).unwrap();
         fin_attachment.offset.x = total_fin_offset.x;
         fin_attachment.offset.y = total_fin_offset.y;
+        fin_attachment.rotation = layers.aim_angle;
 
         let fin_bank = animation_bank_sprites.get_mut(layers.fin_ent).unwrap();
         fin_bank.current = layers.fin_anim;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn only_the_third_hit_is_lethal() {
+        let mut health = Health::full(100.0);
+
+        assert!(!health.take_damage(40.0));
+        assert!(!health.take_damage(40.0));
+        assert!(!health.is_depleted());
+        assert!(health.take_damage(40.0));
+        assert!(health.is_depleted());
+    }
+
+    #[test]
+    fn non_lethal_damage_puts_players_into_hitstun() {
+        let mut test = SystemTest::new(
+            |world| {
+                world.init_resource::<CoreMetaArc>();
+                world.init_resource::<PlayerEvents>();
+                world.init_resource::<PlayerInputs>();
+                world.init_resource::<FriendlyFire>();
+                world.init_resource::<ParticleEvents>();
+                world.init_resource::<DecalEvents>();
+                world.init_resource::<SimulationFrame>();
+                world.init_resource::<AudioEvents>();
+                world.init_resource::<SessionStats>();
+            },
+            |stages| {
+                stages
+                    .add_system_to_stage(CoreStage::PostUpdate, handle_player_events)
+                    .add_system_to_stage(CoreStage::Last, states::hitstun::player_state_transition);
+            },
+        );
+
+        let player = test
+            .world
+            .run_initialized_system(
+                |mut entities: ResMut<Entities>,
+                 mut player_indexes: CompMut<PlayerIdx>,
+                 mut player_states: CompMut<PlayerState>,
+                 mut healths: CompMut<Health>| {
+                    let player = entities.create();
+                    player_indexes.insert(player, PlayerIdx(0));
+                    player_states.insert(player, default());
+                    healths.insert(player, Health::full(100.0));
+                    Ok(player)
+                },
+            )
+            .unwrap();
+        let hit = |test: &mut SystemTest, amount: f32| {
+            test.world
+                .run_initialized_system(move |mut player_events: ResMut<PlayerEvents>| {
+                    player_events.damage(
+                        player,
+                        Damage {
+                            amount,
+                            source: None,
+                            direction: Vec2::X,
+                            knockback: 1.0,
+                            weapon: None,
+                            kind: default(),
+                        },
+                    );
+                    Ok(())
+                })
+                .unwrap();
+            test.run();
+        };
+
+        // The hit is received by the hitstun state, instead of killing the player
+        hit(&mut test, 40.0);
+        test.world
+            .run_initialized_system(
+                move |player_states: Comp<PlayerState>,
+                      healths: Comp<Health>,
+                      hitstuns: Comp<states::hitstun::Hitstun>,
+                      players_killed: Comp<PlayerKilled>| {
+                    assert_eq!(
+                        player_states.get(player).unwrap().current,
+                        states::hitstun::ID
+                    );
+                    assert!(hitstuns.get(player).unwrap().knockback.unwrap().x > 0.0);
+                    let health = healths.get(player).unwrap();
+                    assert!(health.current > 0.0 && health.current < health.max);
+                    assert!(!players_killed.contains(player));
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+        // Until the player runs out of health
+        hit(&mut test, 1000.0);
+        test.world
+            .run_initialized_system(
+                move |players_killed: Comp<PlayerKilled>, hits_received: Comp<HitReceived>| {
+                    assert!(players_killed.contains(player));
+                    assert!(!hits_received.contains(player));
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn rapid_hits_scale_down_and_spaced_hits_reset() {
+        let meta = ComboMeta {
+            window_frames: 30,
+            damage_scaling: vec![vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(3.0, 0.5)],
+        };
+        let mut combo = ComboState::default();
+
+        // Hits in quick succession deal less and less damage
+        let rapid = [0, 10, 20, 30, 40].map(|frame| combo.hit(frame, &meta));
+        assert_eq!(rapid, [1.0, 1.0, 0.75, 0.5, 0.5]);
+        assert_eq!(combo.hits, 5);
+
+        // Waiting out the window drops the combo, so the next hit deals full damage again
+        assert!(combo.is_expired(71, &meta));
+        assert_eq!(combo.hit(71, &meta), 1.0);
+        assert_eq!(combo.hits, 1);
+        let spaced = [150, 250, 350].map(|frame| combo.hit(frame, &meta));
+        assert_eq!(spaced, [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn facing_ignores_single_frame_flips() {
+        let mut facing = Facing::default();
+
+        // A flick of the stick, or a jittery direction change, doesn't turn the player around
+        for move_direction in [-1.0, 1.0, -1.0, 0.0, -1.0, 0.1, -0.15] {
+            facing.update(move_direction);
+            assert!(!facing.left);
+        }
+
+        // Holding the other way for long enough does
+        for _ in 0..FACING_HYSTERESIS_FRAMES {
+            facing.update(-1.0);
+        }
+        assert!(facing.left);
+
+        // But not while the player is attacking
+        facing.hold(5);
+        for _ in 0..5 {
+            facing.update(1.0);
+        }
+        assert!(facing.left);
+    }
+}
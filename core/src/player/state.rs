@@ -4,7 +4,7 @@ pub use stage::*;
 mod stage;
 
 use states::*;
-mod states;
+pub mod states;
 
 /// The state of the player controller.
 #[derive(Clone, TypeUlid, Default)]
@@ -39,14 +39,43 @@ pub fn install(session: &mut GameSession) {
 
     session
         .stages
-        .add_system_to_stage(CoreStage::Last, update_player_state_age);
+        .add_system_to_stage(CoreStage::Last, update_player_state_age)
+        .add_system_to_stage(CoreStage::First, dash::detect_dash_requests)
+        .add_system_to_stage(CoreStage::First, midair::reset_air_jumps);
 
     add_state_module!(session, default);
     add_state_module!(session, idle);
     add_state_module!(session, crouch);
     add_state_module!(session, midair);
     add_state_module!(session, walk);
+    add_state_module!(session, wall_slide);
+    add_state_module!(session, dash);
+    add_state_module!(session, special);
+    add_state_module!(session, ledge_grab);
+    add_state_module!(session, block);
+    add_state_module!(session, taunt);
+    add_state_module!(session, grabbing);
+    add_state_module!(session, grabbed);
+    add_state_module!(session, hitstun);
+    add_state_module!(session, swim);
     add_state_module!(session, dead);
+
+    // This must run after every state has had the chance to turn the player around.
+    session
+        .stages
+        .add_system_to_stage(CoreStage::PreUpdate, flip_players_to_facing);
+}
+
+/// Flip the player sprites to match the way the players are facing.
+fn flip_players_to_facing(
+    entities: Res<Entities>,
+    mut facings: CompMut<Facing>,
+    mut sprites: CompMut<AtlasSprite>,
+) {
+    for (_ent, (facing, sprite)) in entities.iter_with((&mut facings, &mut sprites)) {
+        sprite.flip_x = facing.left;
+        facing.held_frames = facing.held_frames.saturating_sub(1);
+    }
 }
 
 fn update_player_state_age(entities: Res<Entities>, mut player_states: CompMut<PlayerState>) {
@@ -58,10 +87,13 @@ fn update_player_state_age(entities: Res<Entities>, mut player_states: CompMut<P
 fn use_drop_or_grab_items(
     player_ent: Entity,
     meta: &PlayerMeta,
+    grab_range: f32,
     control: &PlayerControl,
+    buffered_input: &mut BufferedInput,
     inventory: &Inventory,
-    collision_world: &CollisionWorld,
+    entities: &Entities,
     items: &Comp<Item>,
+    transforms: &Comp<Transform>,
     held_items: &[Entity],
     player_events: &mut PlayerEvents,
     audio_events: &mut AudioEvents,
@@ -70,20 +102,19 @@ fn use_drop_or_grab_items(
     if control.grab_just_pressed {
         if inventory.is_none() {
             // If we don't have an item
-            let colliders = collision_world
-                // Get all things colliding with the player
-                .actor_collisions(player_ent)
-                .into_iter()
-                // Filter out anything not an item
-                .filter(|ent| items.contains(*ent))
+            let player_pos = transforms.get(player_ent).unwrap().translation.truncate();
+            let free_items = entities
+                .iter_with((items, transforms))
                 // Filter out any items held by other players
-                .filter(|ent| !held_items.contains(ent))
-                .collect::<Vec<_>>();
+                .filter(|(ent, _)| !held_items.contains(ent))
+                .map(|(ent, (_item, transform))| (ent, transform.translation.truncate()));
+
+            // Grab the closest item we can reach
+            if let Some(item) = item_in_grab_range(player_pos, grab_range, free_items) {
+                buffered_input.consume_grab();
 
-            // Grab the first item we are touching
-            if let Some(item) = colliders.get(0) {
                 // Add the item to the player inventory
-                player_events.set_inventory(player_ent, Some(*item));
+                player_events.set_inventory(player_ent, Some(item));
 
                 // Play grab sound
                 audio_events.play(meta.sounds.grab.clone(), meta.sounds.grab_volume);
@@ -92,6 +123,7 @@ fn use_drop_or_grab_items(
         // If we are already carrying an item
         } else {
             // Drop it
+            buffered_input.consume_grab();
             player_events.set_inventory(player_ent, None);
 
             // Play drop sound
@@ -101,6 +133,7 @@ fn use_drop_or_grab_items(
 
     // If we are using an item
     if control.shoot_just_pressed && inventory.is_some() {
+        buffered_input.consume_shoot();
         player_events.use_item(player_ent);
     }
 }
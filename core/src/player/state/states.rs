@@ -1,8 +1,18 @@
 use super::*;
 
+pub mod block;
 pub mod crouch;
+pub mod dash;
 pub mod dead;
 pub mod default;
+pub mod grabbed;
+pub mod grabbing;
+pub mod hitstun;
 pub mod idle;
+pub mod ledge_grab;
 pub mod midair;
+pub mod special;
+pub mod swim;
+pub mod taunt;
 pub mod walk;
+pub mod wall_slide;
@@ -0,0 +1,136 @@
+use super::*;
+
+pub const ID: Key = key!("core::block");
+
+/// Component containing a player's shield, which blocks most of the damage and knockback of the
+/// hits that the player takes while blocking.
+///
+/// The shield drains as it blocks hits, and regenerates while the player isn't blocking. If it runs
+/// out, the shield breaks and the player is stunned.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GWV4JX2B3N9H7ZQ5T1CD6YRA"]
+pub struct Shield {
+    /// The amount of damage that the shield can still absorb.
+    pub amount: f32,
+    /// The knockback velocity of the latest blocked hit, if it hasn't been applied yet.
+    pub pushback: Option<Vec2>,
+}
+
+impl Shield {
+    /// Create a full shield.
+    pub fn full(meta: &ShieldMeta) -> Self {
+        Self {
+            amount: meta.size,
+            pushback: None,
+        }
+    }
+
+    /// Block part of a hit, draining the shield by the damage that it absorbs, and return whether
+    /// the shield broke.
+    pub fn block(&mut self, damage: &mut Damage, meta: &ShieldMeta) -> bool {
+        let absorbed = (damage.amount * meta.damage_reduction).min(self.amount);
+        self.amount -= absorbed;
+        damage.amount -= absorbed;
+        damage.knockback *= 1.0 - meta.knockback_reduction;
+        self.amount <= 0.0
+    }
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    shields: Comp<Shield>,
+    bodies: Comp<KinematicBody>,
+    mut player_states: CompMut<PlayerState>,
+) {
+    for (player_ent, (state, player_idx, body)) in
+        entities.iter_with((&mut player_states, &player_indexes, &bodies))
+    {
+        let control = &player_inputs.players[player_idx.0].control;
+        let has_shield = shields.get(player_ent).map_or(false, |x| x.amount > 0.0);
+        let can_block = control.block_pressed && body.is_on_ground && has_shield;
+
+        if state.current == ID {
+            if !can_block {
+                state.current = idle::ID;
+            }
+        } else if can_block && (state.current == idle::ID || state.current == walk::ID) {
+            state.current = ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_states: Comp<PlayerState>,
+    weights: Comp<Weight>,
+    mut shields: CompMut<Shield>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+) {
+    let meta = &core_meta.shield;
+
+    for (player_ent, (state, shield, animation, body)) in
+        entities.iter_with((&player_states, &mut shields, &mut animations, &mut bodies))
+    {
+        if state.current != ID {
+            // Regenerate the shield while it's lowered
+            shield.amount = (shield.amount + meta.regen_rate).min(meta.size);
+            shield.pushback = None;
+            continue;
+        }
+
+        if state.age == 0 {
+            animation.current = key!("crouch");
+        }
+
+        // Get pushed back by blocked hits, less far for heavier characters, and otherwise hold
+        // still behind the shield
+        if let Some(pushback) = shield.pushback.take() {
+            body.velocity = weights
+                .get(player_ent)
+                .copied()
+                .unwrap_or_default()
+                .knockback(pushback);
+        }
+        body.velocity.x *= core_meta.hitstun.knockback_decay;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shield_absorbs_hits_until_it_breaks() {
+        let meta = ShieldMeta {
+            size: 10.0,
+            damage_reduction: 0.8,
+            knockback_reduction: 0.75,
+            ..default()
+        };
+        let mut shield = Shield::full(&meta);
+        let hit = || Damage {
+            amount: 10.0,
+            source: None,
+            direction: Vec2::X,
+            knockback: 1.0,
+            weapon: None,
+            kind: HitKind::Melee,
+        };
+
+        let mut damage = hit();
+        assert!(!shield.block(&mut damage, &meta));
+        assert!((damage.amount - 2.0).abs() < 0.001);
+        assert!((damage.knockback - 0.25).abs() < 0.001);
+        assert!((shield.amount - 2.0).abs() < 0.001);
+
+        // The shield can only absorb what it has left
+        let mut damage = hit();
+        assert!(shield.block(&mut damage, &meta));
+        assert!((damage.amount - 8.0).abs() < 0.001);
+        assert_eq!(shield.amount, 0.0);
+    }
+}
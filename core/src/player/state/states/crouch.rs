@@ -2,6 +2,14 @@ use super::*;
 
 pub const ID: Key = key!("core::crouch");
 
+/// Component added to crouching players, remembering how much their hitbox was shrunk so that it
+/// can be restored when they stop crouching.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GVYCH9DEQWV3F6M9PEYXQV0E"]
+pub struct CrouchHitbox {
+    pub height_reduction: f32,
+}
+
 pub fn player_state_transition(
     entities: Res<Entities>,
     player_inputs: Res<PlayerInputs>,
@@ -26,29 +34,91 @@ pub fn player_state_transition(
 pub fn handle_player_state(
     entities: Res<Entities>,
     player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
     player_states: Comp<PlayerState>,
     player_indexes: Comp<PlayerIdx>,
+    player_assets: BevyAssets<PlayerMeta>,
+    mut crouch_hitboxes: CompMut<CrouchHitbox>,
+    mut transforms: CompMut<Transform>,
+    mut facings: CompMut<Facing>,
     mut animations: CompMut<AnimationBankSprite>,
     mut bodies: CompMut<KinematicBody>,
 ) {
-    for (_ent, (state, player_idx, animation, body)) in entities.iter_with((
-        &player_states,
-        &player_indexes,
-        &mut animations,
-        &mut bodies,
-    )) {
+    // Restore the hitbox of players that have stopped crouching, whatever state they went to
+    let stopped_crouching = entities
+        .iter_with((&player_states, &crouch_hitboxes))
+        .filter(|(_ent, (state, _hitbox))| state.current != ID)
+        .map(|(ent, _)| ent)
+        .collect::<Vec<_>>();
+    for player_ent in stopped_crouching {
+        let height_reduction = crouch_hitboxes.remove(player_ent).unwrap().height_reduction;
+        let body = bodies.get_mut(player_ent).unwrap();
+        let transform = transforms.get_mut(player_ent).unwrap();
+
+        if let ColliderShape::Rectangle { size } = &mut body.shape {
+            size.y += height_reduction;
+        }
+        // Keep the player's feet where they are
+        transform.translation.y += height_reduction / 2.0;
+    }
+
+    for (player_ent, (state, player_idx, animation, facing, body, transform)) in
+        entities.iter_with((
+            &player_states,
+            &player_indexes,
+            &mut animations,
+            &mut facings,
+            &mut bodies,
+            &mut transforms,
+        ))
+    {
         if state.current != ID {
             continue;
         }
+        let meta_handle = player_inputs.players[player_idx.0]
+            .selected_player
+            .get_bevy_handle();
+        let Some(meta) = player_assets.get(&meta_handle) else {
+            continue;
+        };
 
         if state.age == 0 {
             animation.current = key!("crouch");
+
+            // Shrink the hitbox, keeping the player's feet where they are
+            if !crouch_hitboxes.contains(player_ent) {
+                if let ColliderShape::Rectangle { size } = &mut body.shape {
+                    let height_reduction = (size.y - meta.stats.crouch_height).max(0.0);
+                    size.y -= height_reduction;
+                    transform.translation.y -= height_reduction / 2.0;
+                    crouch_hitboxes.insert(player_ent, CrouchHitbox { height_reduction });
+                }
+            }
         }
 
         let control = &player_inputs.players[player_idx.0].control;
 
         if control.jump_just_pressed {
+            input_buffer.players[player_idx.0].consume_jump();
             body.fall_through = true;
         }
+
+        // Crawl slowly in the movement direction
+        body.velocity.x += meta.stats.accel_walk_speed * control.move_direction.x;
+        body.velocity.x = body
+            .velocity
+            .x
+            .clamp(-meta.stats.crouch_speed, meta.stats.crouch_speed);
+
+        if control.move_direction.x == 0.0 {
+            if body.velocity.x.is_sign_positive() {
+                body.velocity.x = (body.velocity.x - meta.stats.slowdown).max(0.0);
+            } else {
+                body.velocity.x = (body.velocity.x + meta.stats.slowdown).min(0.0);
+            }
+        }
+
+        // Point in movement direction
+        facing.update(control.move_direction.x);
     }
 }
@@ -0,0 +1,177 @@
+use super::*;
+
+pub const ID: Key = key!("core::dash");
+
+/// Component added to a player that has double-tapped a direction, asking to dash.
+///
+/// The request is removed during the next state transition, whether or not the player was able to
+/// dash.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GV4K4QN3PBQCN7YYT9NHNRWR"]
+pub struct DashRequested {
+    /// The horizontal direction of the dash, either `1.0` or `-1.0`.
+    pub direction: f32,
+}
+
+/// Component that tracks the double-tap detection and dash cooldown of a player.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GT9JSCKYFDMFYSWX2QS73DK0"]
+pub struct Dasher {
+    /// The direction of the last tap, or `0.0` if there isn't a tap waiting for a second one.
+    pub last_tap_direction: f32,
+    /// The number of frames since the last tap.
+    pub frames_since_tap: u32,
+    /// The number of frames left until the player can dash again.
+    pub cooldown_remaining: u32,
+    /// Whether the player may still dash before landing.
+    pub air_dash_available: bool,
+    /// The direction of the current dash.
+    pub direction: f32,
+}
+
+impl Default for Dasher {
+    fn default() -> Self {
+        Self {
+            last_tap_direction: 0.0,
+            frames_since_tap: 0,
+            cooldown_remaining: 0,
+            air_dash_available: true,
+            direction: 1.0,
+        }
+    }
+}
+
+/// Detect double-taps of a horizontal direction, and request a dash for the player.
+pub fn detect_dash_requests(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    bodies: Comp<KinematicBody>,
+    mut dashers: CompMut<Dasher>,
+    mut dash_requests: CompMut<DashRequested>,
+) {
+    let double_tap_frames = core_meta.dash.double_tap_frames;
+
+    for (player_ent, (player_idx, body)) in entities.iter_with((&player_indexes, &bodies)) {
+        if !dashers.contains(player_ent) {
+            dashers.insert(player_ent, default());
+        }
+        let dasher = dashers.get_mut(player_ent).unwrap();
+        let control = &player_inputs.players[player_idx.0].control;
+
+        dasher.frames_since_tap = dasher.frames_since_tap.saturating_add(1);
+        dasher.cooldown_remaining = dasher.cooldown_remaining.saturating_sub(1);
+        if body.is_on_ground {
+            dasher.air_dash_available = true;
+        }
+
+        if !control.just_moved || control.move_direction.x.abs() < 0.5 {
+            continue;
+        }
+        let direction = control.move_direction.x.signum();
+
+        if direction == dasher.last_tap_direction && dasher.frames_since_tap <= double_tap_frames {
+            dash_requests.insert(player_ent, DashRequested { direction });
+            dasher.last_tap_direction = 0.0;
+        } else {
+            dasher.last_tap_direction = direction;
+            dasher.frames_since_tap = 0;
+        }
+    }
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    mut player_states: CompMut<PlayerState>,
+    bodies: Comp<KinematicBody>,
+    mut dashers: CompMut<Dasher>,
+    mut dash_requests: CompMut<DashRequested>,
+) {
+    for (player_ent, (state, player_idx, body, dasher)) in
+        entities.iter_with((&mut player_states, &player_indexes, &bodies, &mut dashers))
+    {
+        let control = &player_inputs.players[player_idx.0].control;
+
+        if state.current == ID {
+            let finished = state.age >= core_meta.dash.duration_frames as u64;
+            // Jumping or attacking cancels the dash
+            let cancelled = control.jump_just_pressed || control.shoot_just_pressed;
+
+            if finished || cancelled {
+                state.current = if body.is_on_ground {
+                    idle::ID
+                } else {
+                    midair::ID
+                };
+            }
+            continue;
+        }
+
+        let Some(request) = dash_requests.remove(player_ent) else {
+            continue;
+        };
+
+        let can_dash_from_state =
+            [idle::ID, walk::ID, crouch::ID, midair::ID, wall_slide::ID].contains(&state.current);
+        let can_dash =
+            dasher.cooldown_remaining == 0 && (body.is_on_ground || dasher.air_dash_available);
+
+        if can_dash_from_state && can_dash {
+            dasher.direction = request.direction;
+            state.current = ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_states: Comp<PlayerState>,
+    mut dashers: CompMut<Dasher>,
+    mut invulnerables: CompMut<Invulnerable>,
+    mut facings: CompMut<Facing>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+) {
+    let dash_meta = &core_meta.dash;
+    let duration_frames = dash_meta.duration_frames.max(1);
+
+    for (player_ent, (state, dasher, animation, facing, body)) in entities.iter_with((
+        &player_states,
+        &mut dashers,
+        &mut animations,
+        &mut facings,
+        &mut bodies,
+    )) {
+        if state.current != ID {
+            continue;
+        }
+
+        if state.age == 0 {
+            animation.current = key!("dash");
+            dasher.cooldown_remaining = dash_meta.cooldown_frames;
+            if !body.is_on_ground {
+                dasher.air_dash_available = false;
+            }
+
+            // The player can't be hurt while dashing
+            let frames_remaining = invulnerables
+                .get(player_ent)
+                .map(|x| x.frames_remaining)
+                .unwrap_or_default()
+                .max(duration_frames);
+            invulnerables.insert(player_ent, Invulnerable { frames_remaining });
+        }
+
+        // Move at a constant speed, ignoring gravity
+        body.velocity = vec2(
+            dasher.direction * dash_meta.distance / duration_frames as f32,
+            0.0,
+        );
+        facing.set(dasher.direction < 0.0);
+    }
+}
@@ -2,52 +2,604 @@ use super::*;
 
 pub const ID: Key = key!("core::dead");
 
+/// The number of frames that a death lasts, before the player loses a life and starts waiting to
+/// respawn.
+///
+/// The player's corpse may linger for longer, or vanish sooner, depending on the
+/// [`CorpseTimeout`].
+pub const DEATH_FRAMES: u64 = 80;
+
+/// Component on a dead player pointing to the ragdoll that was spawned in its place.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GTW5QGX7JXJFWXSCDYC0D0S1"]
+pub struct PlayerRagdoll(pub Entity);
+
 pub fn player_state_transition(
     entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
     killed_players: Comp<PlayerKilled>,
+    healths: Comp<Health>,
+    player_indexes: Comp<PlayerIdx>,
+    inventories: Comp<Inventory>,
     mut player_states: CompMut<PlayerState>,
+    mut air_jumps: CompMut<AirJumps>,
+    mut combos: CompMut<ComboState>,
+    mut player_events: ResMut<PlayerEvents>,
+    mut kill_events: ResMut<KillEvents>,
+    mut scores: ResMut<Scores>,
+    mut stats: ResMut<SessionStats>,
+    mut match_state: ResMut<MatchState>,
+    match_mode: Res<MatchMode>,
+    stocks: Res<Stocks>,
+    frame: Res<SimulationFrame>,
+    mut camera_trauma: ResMut<CameraTrauma>,
+    player_inputs: Res<PlayerInputs>,
 ) {
-    for (_ent, (state, _killed)) in entities.iter_with((&mut player_states, &killed_players)) {
+    // Players killed on the same frame are scored in the order of their player index
+    let players = entities
+        .iter_with((&mut player_states, &killed_players))
+        .in_order_by_key(|(player_ent, _)| player_indexes.get(*player_ent).map(|x| x.0));
+    for (player_ent, (state, killed)) in players {
+        // Only players that have run out of health die
+        if !healths.get(player_ent).map_or(true, Health::is_depleted) {
+            continue;
+        }
+
+        if state.current != ID {
+            // Killing yourself doesn't count as a kill
+            let killer = killed.killer.filter(|&killer| killer != player_ent);
+            let killer_idx = killer.and_then(|killer| player_indexes.get(killer));
+            let team_kill = match (killer_idx, player_indexes.get(player_ent)) {
+                (Some(killer_idx), Some(player_idx)) => {
+                    player_inputs.are_teammates(killer_idx.0, player_idx.0)
+                }
+                _ => false,
+            };
+            kill_events.send(KillEvent {
+                victim: player_ent,
+                killer,
+                weapon: killed.weapon,
+                team_kill,
+            });
+            if let Some(killer_idx) = killer_idx.filter(|_| !team_kill) {
+                scores.0[killer_idx.0] += 1;
+                stats.for_player_mut(killer_idx.0).kills += 1;
+            }
+            if let Some(player_idx) = player_indexes.get(player_ent) {
+                stats.for_player_mut(player_idx.0).deaths += 1;
+                if match_state.is_decisive_kill(*match_mode, &stocks, &player_inputs, player_idx.0)
+                {
+                    match_state.decisive_kill = Some(**frame);
+                }
+                match_state.knock_out(player_idx.0);
+            }
+            camera_trauma.add_trauma(core_meta.camera.death_trauma);
+
+            // Don't carry the jump counter or the combo over to the player's next life
+            air_jumps.remove(player_ent);
+            combos.remove(player_ent);
+
+            // Drop any item the player was carrying where they died
+            if inventories.get(player_ent).map_or(false, |x| x.is_some()) {
+                player_events.set_inventory(player_ent, None);
+            }
+        }
+
         state.current = ID;
     }
 }
 
 pub fn handle_player_state(
-    entities: Res<Entities>,
+    mut entities: ResMut<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    frame: Res<SimulationFrame>,
+    match_mode: Res<MatchMode>,
+    mut stocks: ResMut<Stocks>,
+    mut respawn_delays: ResMut<player_spawner::RespawnDelays>,
+    player_indexes: Comp<PlayerIdx>,
     player_states: Comp<PlayerState>,
     killed_players: Comp<PlayerKilled>,
-    sprites: Comp<AtlasSprite>,
-    transform: Comp<Transform>,
+    player_layers: Comp<PlayerLayers>,
+    facings: Comp<Facing>,
+    weights: Comp<Weight>,
+    mut sprites: CompMut<AtlasSprite>,
+    mut transforms: CompMut<Transform>,
+    mut bodies: CompMut<KinematicBody>,
+    mut ragdolls: CompMut<PlayerRagdoll>,
     mut animations: CompMut<AnimationBankSprite>,
     mut player_events: ResMut<PlayerEvents>,
+    mut decal_events: ResMut<DecalEvents>,
+    mut hit_stops: CompMut<HitStop>,
 ) {
-    for (player_ent, (state, animation, killed_player)) in
-        entities.iter_with((&player_states, &mut animations, &killed_players))
-    {
+    let mut new_ragdolls = Vec::new();
+
+    // Players that die on the same frame lose their lives in the order of their player index
+    let players = entities
+        .iter_with((&player_states, &mut animations, &killed_players))
+        .in_order_by_key(|(player_ent, _)| player_indexes.get(*player_ent).map(|x| x.0));
+    for (player_ent, (state, animation, killed_player)) in players {
         if state.current != ID {
             continue;
         };
 
         if state.age == 0 {
-            let sprite = sprites.get(player_ent).unwrap();
-            let player_on_right = !sprite.flip_x;
-            let transform = transform.get(player_ent).unwrap();
-
-            animation.current = match killed_player.hit_from {
-                Some(hit_from)
-                    if {
-                        let is_hit_right = transform.translation.x < hit_from.x;
-                        (player_on_right && is_hit_right) || (!player_on_right && !is_hit_right)
-                    } =>
-                {
-                    key!("death_spine")
+            // Corpses are drawn without the player's color accent
+            let mut sprite_ents = vec![player_ent];
+            if let Some(layers) = player_layers.get(player_ent) {
+                sprite_ents.extend([layers.fin_ent, layers.face_ent]);
+            }
+            for ent in sprite_ents {
+                if let Some(sprite) = sprites.get_mut(ent) {
+                    sprite.color = player_sprite_color(None, 1.0);
                 }
-                _ => key!("death_belly"),
-            };
+            }
+
+            if let Some(transform) = transforms.get(player_ent) {
+                let direction = killed_player
+                    .hit_from
+                    .map(|hit_from| transform.translation.truncate() - hit_from)
+                    .unwrap_or(Vec2::Y);
+                decal_events.splatter(transform.translation, direction);
+            }
+
+            // Kills freeze the players for longer than any hit
+            let kill_stop = core_meta.hit_stop.kill;
+            apply_hit_stop(&mut hit_stops, player_ent, kill_stop.victim);
+            if let Some(killer) = killed_player.killer.filter(|&x| x != player_ent) {
+                apply_hit_stop(&mut hit_stops, killer, kill_stop.attacker);
+            }
+
+            if core_meta.death.ragdoll_on_death {
+                new_ragdolls.push((player_ent, killed_player.hit_from));
+            } else {
+                let player_on_right = facings.get(player_ent).map_or(true, |x| !x.left);
+                let player_pos = transforms.get(player_ent).unwrap().translation.truncate();
+                let velocity = bodies.get(player_ent).unwrap().velocity;
+
+                animation.current = match killed_player.hit_from {
+                    Some(hit_from) => death_animation(
+                        player_on_right,
+                        knockback_direction(player_pos, velocity, hit_from),
+                    ),
+                    None => key!("death_belly"),
+                };
+            }
+        }
+
+        // The death is timed in frames, so it lasts as long however fast the ragdoll is falling. A
+        // corpse that vanishes early cuts it short.
+        let corpse_frames = core_meta.death.corpse_timeout.frames();
+        let death_frames = corpse_frames.map_or(DEATH_FRAMES, |x| x.min(DEATH_FRAMES));
+        if state.age == death_frames {
+            // The player respawns if they have any lives left, once the respawn delay is over, and
+            // their corpse is gone
+            if let Some(player_idx) = player_indexes.get(player_ent) {
+                if let MatchMode::Stock { .. } = *match_mode {
+                    stocks.lose_stock(player_idx.0, **frame);
+                }
+                if !stocks.is_eliminated(player_idx.0) {
+                    respawn_delays.start(player_idx.0, **frame, core_meta.respawn.delay_frames);
+                }
+            }
         }
 
-        if state.age >= 80 {
+        if corpse_frames.map_or(false, |frames| state.age >= frames) {
             player_events.despawn(player_ent);
+            if let Some(ragdoll) = ragdolls.get(player_ent) {
+                entities.kill(ragdoll.0);
+            }
+        }
+    }
+
+    // Replace players that were just killed with ragdolls
+    for (player_ent, hit_from) in new_ragdolls {
+        let transform = *transforms.get(player_ent).unwrap();
+        let sprite = sprites.get(player_ent).unwrap().clone();
+        let body = bodies.get(player_ent).unwrap().clone();
+
+        // Knock the ragdoll away from whatever hit it, or straight up if we don't know
+        let direction = hit_from
+            .map(|hit_from| {
+                knockback_direction(transform.translation.truncate(), body.velocity, hit_from)
+            })
+            .filter(|direction| *direction != Vec2::ZERO)
+            .unwrap_or(Vec2::Y);
+        let death_meta = &core_meta.death;
+        // Light characters are launched further towards the blast zone, in the same direction
+        let velocity = weights
+            .get(player_ent)
+            .copied()
+            .unwrap_or_default()
+            .knockback(direction * death_meta.ragdoll_knockback);
+
+        let ragdoll_ent = entities.create();
+        transforms.insert(ragdoll_ent, transform);
+        sprites.insert(ragdoll_ent, sprite);
+        bodies.insert(
+            ragdoll_ent,
+            KinematicBody {
+                velocity,
+                angular_velocity: -direction.x.signum() * death_meta.ragdoll_spin,
+                can_rotate: true,
+                has_mass: true,
+                has_friction: true,
+                is_spawning: true,
+                corner_correction: false,
+                ..body
+            },
+        );
+        ragdolls.insert(player_ent, PlayerRagdoll(ragdoll_ent));
+        apply_hit_stop(&mut hit_stops, ragdoll_ent, core_meta.hit_stop.kill.victim);
+
+        // Hide the player, now that the ragdoll has taken its place
+        let mut hidden_ents = vec![player_ent];
+        if let Some(layers) = player_layers.get(player_ent) {
+            hidden_ents.extend([layers.fin_ent, layers.face_ent]);
+        }
+        for ent in hidden_ents {
+            if let Some(sprite) = sprites.get_mut(ent) {
+                sprite.color = Color::rgba(1.0, 1.0, 1.0, 0.0);
+            }
+        }
+    }
+}
+
+/// How much the player's velocity at the time of death contributes to the knockback direction,
+/// relative to the direction of the hit.
+const VELOCITY_KNOCKBACK_WEIGHT: f32 = 0.5;
+
+/// The minimum angle, in degrees from the horizontal, that a knockback is considered vertical.
+const VERTICAL_KNOCKBACK_ANGLE: f32 = 60.0;
+
+/// Get the direction that a player is knocked in when hit from the given position, taking into
+/// account the momentum they had when they were hit.
+fn knockback_direction(player_pos: Vec2, velocity: Vec2, hit_from: Vec2) -> Vec2 {
+    let hit_direction = (player_pos - hit_from).normalize_or_zero();
+
+    (hit_direction + velocity.normalize_or_zero() * VELOCITY_KNOCKBACK_WEIGHT).normalize_or_zero()
+}
+
+/// Pick the death animation for a player knocked in the given direction.
+fn death_animation(player_on_right: bool, knockback: Vec2) -> Key {
+    if knockback == Vec2::ZERO {
+        return key!("death_belly");
+    }
+
+    let angle = knockback.y.abs().atan2(knockback.x.abs()).to_degrees();
+    if angle >= VERTICAL_KNOCKBACK_ANGLE {
+        // Knocked down onto the belly by a hit from above, or up onto the back by a hit from below
+        if knockback.y < 0.0 {
+            key!("death_front")
+        } else {
+            key!("death_back")
+        }
+    } else {
+        // Knocked backwards by a hit from the front, or forwards by a hit from behind
+        let knocked_right = knockback.x > 0.0;
+        if knocked_right != player_on_right {
+            key!("death_spine")
+        } else {
+            key!("death_belly")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    /// Insert the resources that the dying state transition needs.
+    fn init_kill_world(world: &mut World) {
+        world.init_resource::<CoreMetaArc>();
+        world.init_resource::<PlayerEvents>();
+        world.init_resource::<KillEvents>();
+        world.init_resource::<Scores>();
+        world.init_resource::<SessionStats>();
+        world.init_resource::<MatchState>();
+        world.init_resource::<MatchMode>();
+        world.init_resource::<Stocks>();
+        world.init_resource::<SimulationFrame>();
+        world.init_resource::<CameraTrauma>();
+        world.init_resource::<PlayerInputs>();
+    }
+
+    /// Insert the resources that the dead state needs, with the given death metadata.
+    fn init_death_world(world: &mut World, death: DeathMeta) {
+        world.insert_resource(CoreMetaArc(Arc::new(CoreMeta { death, ..default() })));
+        world.init_resource::<PlayerEvents>();
+        world.init_resource::<SimulationFrame>();
+        world.init_resource::<MatchMode>();
+        world.init_resource::<Stocks>();
+        world.init_resource::<player_spawner::RespawnDelays>();
+        world.init_resource::<DecalEvents>();
+    }
+
+    #[test]
+    fn held_item_is_dropped_on_death() {
+        let mut test = SystemTest::new(init_kill_world, |stages| {
+            stages.add_system_to_stage(CoreStage::Update, player_state_transition);
+        });
+
+        let player = test
+            .world
+            .run_initialized_system(
+                |mut entities: ResMut<Entities>,
+                 mut player_indexes: CompMut<PlayerIdx>,
+                 mut player_states: CompMut<PlayerState>,
+                 mut killed_players: CompMut<PlayerKilled>,
+                 mut healths: CompMut<Health>,
+                 mut inventories: CompMut<Inventory>| {
+                    let item = entities.create();
+                    let player = entities.create();
+                    player_indexes.insert(player, PlayerIdx(0));
+                    player_states.insert(player, default());
+                    killed_players.insert(
+                        player,
+                        PlayerKilled {
+                            hit_from: None,
+                            killer: None,
+                            weapon: None,
+                        },
+                    );
+                    healths.insert(
+                        player,
+                        Health {
+                            current: 0.0,
+                            max: 100.0,
+                        },
+                    );
+                    inventories.insert(player, Inventory(Some(item)));
+                    Ok(player)
+                },
+            )
+            .unwrap();
+
+        test.run();
+
+        let player_events = test.world.resource::<PlayerEvents>();
+        let player_events = player_events.borrow();
+        assert!(player_events.queue.iter().any(|event| matches!(
+            event,
+            PlayerEvent::SetInventory { player: p, item: None } if *p == player
+        )));
+    }
+
+    #[test]
+    fn simultaneous_kills_dont_depend_on_spawn_order() {
+        let kills = |spawn_order: [usize; 3]| {
+            let mut test = SystemTest::new(init_kill_world, |stages| {
+                stages.add_system_to_stage(CoreStage::Update, player_state_transition);
+            });
+
+            // Every player is killed by the next one, on the same frame
+            let players = test
+                .world
+                .run_initialized_system(
+                    move |mut entities: ResMut<Entities>,
+                          mut player_indexes: CompMut<PlayerIdx>,
+                          mut player_states: CompMut<PlayerState>,
+                          mut killed_players: CompMut<PlayerKilled>,
+                          mut healths: CompMut<Health>| {
+                        let mut players = [Entity::new(0, 0); 3];
+                        for player_idx in spawn_order {
+                            players[player_idx] = entities.create();
+                        }
+                        for (player_idx, &player) in players.iter().enumerate() {
+                            player_indexes.insert(player, PlayerIdx(player_idx));
+                            player_states.insert(player, default());
+                            killed_players.insert(
+                                player,
+                                PlayerKilled {
+                                    hit_from: None,
+                                    killer: Some(players[(player_idx + 1) % 3]),
+                                    weapon: None,
+                                },
+                            );
+                            healths.insert(
+                                player,
+                                Health {
+                                    current: 0.0,
+                                    max: 100.0,
+                                },
+                            );
+                        }
+                        Ok(players)
+                    },
+                )
+                .unwrap();
+
+            test.run();
+
+            let player_idx = |ent| players.iter().position(|&x| x == ent);
+            let kill_events = test.world.resource::<KillEvents>();
+            let kill_events = kill_events.borrow();
+            kill_events
+                .queue
+                .iter()
+                .map(|kill| (player_idx(kill.victim), kill.killer.and_then(player_idx)))
+                .collect::<Vec<_>>()
+        };
+
+        let expected = vec![(Some(0), Some(1)), (Some(1), Some(2)), (Some(2), Some(0))];
+        for spawn_order in [[0, 1, 2], [2, 0, 1], [1, 2, 0], [2, 1, 0]] {
+            assert_eq!(kills(spawn_order), expected);
         }
     }
+
+    #[test]
+    fn corpse_is_despawned_after_the_timeout() {
+        let despawned_at = |corpse_timeout: CorpseTimeout| {
+            let mut test = SystemTest::new(
+                |world| {
+                    init_death_world(
+                        world,
+                        DeathMeta {
+                            corpse_timeout,
+                            ..default()
+                        },
+                    )
+                },
+                |stages| {
+                    stages.add_system_to_stage(CoreStage::Update, handle_player_state);
+                },
+            );
+
+            let player = test
+                .world
+                .run_initialized_system(
+                    |mut entities: ResMut<Entities>,
+                     mut player_states: CompMut<PlayerState>,
+                     mut killed_players: CompMut<PlayerKilled>,
+                     mut animations: CompMut<AnimationBankSprite>| {
+                        let player = entities.create();
+                        player_states.insert(
+                            player,
+                            PlayerState {
+                                current: ID,
+                                age: 1,
+                                last: ID,
+                            },
+                        );
+                        killed_players.insert(
+                            player,
+                            PlayerKilled {
+                                hit_from: None,
+                                killer: None,
+                                weapon: None,
+                            },
+                        );
+                        animations.insert(
+                            player,
+                            AnimationBankSprite {
+                                current: key!("death_belly"),
+                                animations: default(),
+                                last_animation: default(),
+                            },
+                        );
+                        Ok(player)
+                    },
+                )
+                .unwrap();
+
+            (1..500).find(|_| {
+                test.run();
+                let player_events = test.world.resource::<PlayerEvents>();
+                let despawned = player_events.borrow().queue.iter().any(
+                    |event| matches!(event, PlayerEvent::Despawn { player: p } if *p == player),
+                );
+                test.world
+                    .run_initialized_system(|mut player_states: CompMut<PlayerState>| {
+                        player_states.get_mut(player).unwrap().age += 1;
+                        Ok(())
+                    })
+                    .unwrap();
+                despawned
+            })
+        };
+
+        assert_eq!(despawned_at(default()), Some(80));
+        assert_eq!(
+            despawned_at(CorpseTimeout::After { frames: 200 }),
+            Some(200)
+        );
+        assert_eq!(despawned_at(CorpseTimeout::After { frames: 20 }), Some(20));
+        assert_eq!(despawned_at(CorpseTimeout::UntilMatchEnds), None);
+    }
+
+    #[test]
+    fn death_animation_ignores_weight() {
+        // Kill a player of the given weight, that was knocked back by the same hit as everyone
+        // else, returning their death animation and the velocity of their ragdoll
+        let die = |weight: Weight, ragdoll_on_death: bool| {
+            let mut test = SystemTest::new(
+                |world| {
+                    init_death_world(
+                        world,
+                        DeathMeta {
+                            ragdoll_on_death,
+                            ..default()
+                        },
+                    )
+                },
+                |stages| {
+                    stages.add_system_to_stage(CoreStage::Update, handle_player_state);
+                },
+            );
+
+            let player = test
+                .world
+                .run_initialized_system(
+                    move |mut entities: ResMut<Entities>,
+                          mut player_states: CompMut<PlayerState>,
+                          mut killed_players: CompMut<PlayerKilled>,
+                          mut weights: CompMut<Weight>,
+                          mut sprites: CompMut<AtlasSprite>,
+                          mut animations: CompMut<AnimationBankSprite>,
+                          mut bodies: CompMut<KinematicBody>,
+                          mut transforms: CompMut<Transform>| {
+                        let player = entities.create();
+                        player_states.insert(
+                            player,
+                            PlayerState {
+                                current: ID,
+                                ..default()
+                            },
+                        );
+                        killed_players.insert(
+                            player,
+                            PlayerKilled {
+                                hit_from: Some(vec2(-10.0, 0.0)),
+                                killer: None,
+                                weapon: None,
+                            },
+                        );
+                        weights.insert(player, weight);
+                        sprites.insert(player, default());
+                        animations.insert(
+                            player,
+                            AnimationBankSprite {
+                                current: key!("idle"),
+                                animations: default(),
+                                last_animation: default(),
+                            },
+                        );
+                        bodies.insert(
+                            player,
+                            KinematicBody {
+                                velocity: weight.knockback(vec2(1.0, 4.0)),
+                                ..default()
+                            },
+                        );
+                        transforms.insert(player, default());
+                        Ok(player)
+                    },
+                )
+                .unwrap();
+
+            test.run();
+
+            let animations = test.world.components.get::<AnimationBankSprite>();
+            let animation = animations.borrow().get(player).unwrap().current;
+            let ragdoll = test.world.components.get::<PlayerRagdoll>();
+            let ragdoll = ragdoll.borrow().get(player).map(|x| x.0);
+            let bodies = test.world.components.get::<KinematicBody>();
+            let velocity = ragdoll.map(|ragdoll| bodies.borrow().get(ragdoll).unwrap().velocity);
+            (animation, velocity)
+        };
+
+        // Light and heavy characters are moving at different speeds when killed by the same hit,
+        // but fall the same way
+        let (light, _) = die(Weight(0.5), false);
+        let (heavy, _) = die(Weight(2.0), false);
+        assert_eq!(light, key!("death_belly"));
+        assert_eq!(light, heavy);
+
+        // Light characters' ragdolls fly further towards the blast zone, in the same direction
+        let light = die(Weight(0.5), true).1.unwrap();
+        let heavy = die(Weight(2.0), true).1.unwrap();
+        assert!(light.length() > heavy.length() * 3.9);
+        assert!(light.normalize().abs_diff_eq(heavy.normalize(), 0.001));
+    }
 }
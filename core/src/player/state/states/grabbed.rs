@@ -0,0 +1,89 @@
+use super::*;
+
+pub const ID: Key = key!("core::grabbed");
+
+/// Component added to a player that is being held by another player, who has the
+/// [`Grabbing`](grabbing::Grabbing) component.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GVARKPG2S0F20BGM5N9736R9"]
+pub struct Grabbed {
+    /// The player holding this one.
+    pub grabber: Entity,
+    /// The number of times the player has pressed a button trying to break free.
+    pub struggle: u32,
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    grabbeds: Comp<Grabbed>,
+    mut player_states: CompMut<PlayerState>,
+) {
+    for (player_ent, state) in entities.iter_with(&mut player_states) {
+        if state.current == ID && !grabbeds.contains(player_ent) {
+            state.current = idle::ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    player_states: Comp<PlayerState>,
+    transforms: Comp<Transform>,
+    mut grabbings: CompMut<grabbing::Grabbing>,
+    mut grabbeds: CompMut<Grabbed>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+) {
+    let grab_meta = &core_meta.player_grab;
+
+    let mut breaks = Vec::new();
+    for (player_ent, (state, player_idx, grabbed, animation)) in entities.iter_with((
+        &player_states,
+        &player_indexes,
+        &mut grabbeds,
+        &mut animations,
+    )) {
+        if state.current != ID {
+            continue;
+        }
+
+        if state.age == 0 {
+            animation.current = key!("flinch");
+        }
+
+        // Mash buttons to break free
+        let control = &player_inputs.players[player_idx.0].control;
+        let struggled = control.jump_just_pressed
+            || control.grab_just_pressed
+            || control.shoot_just_pressed
+            || control.just_moved;
+        if state.age > 0 && struggled {
+            grabbed.struggle += 1;
+        }
+        if grabbed.struggle >= grab_meta.struggle_presses {
+            breaks.push((player_ent, grabbed.grabber));
+        }
+    }
+
+    // Push both players apart when the grab breaks
+    for (victim, grabber) in breaks {
+        grabbeds.remove(victim);
+        grabbings.remove(grabber);
+
+        let (Some(victim_transform), Some(grabber_transform)) =
+            (transforms.get(victim), transforms.get(grabber)) else {
+            continue;
+        };
+        let offset =
+            victim_transform.translation.truncate() - grabber_transform.translation.truncate();
+        let direction = vec2(if offset.x < 0.0 { -1.0 } else { 1.0 }, 0.0);
+        for (player, direction) in [(victim, direction), (grabber, -direction)] {
+            if let Some(body) = bodies.get_mut(player) {
+                body.velocity = direction * grab_meta.break_speed;
+            }
+        }
+    }
+}
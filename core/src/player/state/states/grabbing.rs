@@ -0,0 +1,220 @@
+use super::*;
+
+pub const ID: Key = key!("core::grabbing");
+
+/// The states that a player can be grabbed out of.
+const GRABBABLE_STATES: &[Key] = &[
+    idle::ID,
+    walk::ID,
+    crouch::ID,
+    midair::ID,
+    wall_slide::ID,
+    ledge_grab::ID,
+    block::ID,
+];
+
+/// Component added to a player that is holding another player, who has the
+/// [`Grabbed`](grabbed::Grabbed) component.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GWMF2YX2TQT157A3KZT9YWZ0"]
+pub struct Grabbing {
+    /// The player being held.
+    pub victim: Entity,
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    friendly_fire: Res<FriendlyFire>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_indexes: Comp<PlayerIdx>,
+    inventories: Comp<Inventory>,
+    items: Comp<Item>,
+    transforms: Comp<Transform>,
+    invulnerables: Comp<Invulnerable>,
+    killed_players: Comp<PlayerKilled>,
+    mut player_states: CompMut<PlayerState>,
+    mut grabbings: CompMut<Grabbing>,
+    mut grabbeds: CompMut<grabbed::Grabbed>,
+) {
+    // Let go once either player has left the grab, whether they were hit, killed or despawned
+    let grabs = entities
+        .iter_with(&grabbings)
+        .map(|(grabber, grabbing)| (grabber, grabbing.victim))
+        .collect::<Vec<_>>();
+    for (grabber, victim) in grabs {
+        let grabber_state = player_states.get(grabber).map(|x| x.current);
+        let victim_state = player_states.get(victim).map(|x| x.current);
+        if grabber_state != Some(ID) || victim_state != Some(grabbed::ID) {
+            grabbings.remove(grabber);
+            grabbeds.remove(victim);
+        }
+    }
+
+    for (player_ent, (state, _player_idx)) in
+        entities.iter_with((&mut player_states, &player_indexes))
+    {
+        if state.current == ID && !grabbings.contains(player_ent) {
+            state.current = idle::ID;
+        }
+    }
+
+    // Grab opponents that are in reach, when there isn't an item to grab instead
+    let held_items = entities
+        .iter_with(&inventories)
+        .filter_map(|(_ent, inventory)| inventory.0)
+        .collect::<Vec<_>>();
+    let players = entities
+        .iter_with((&player_indexes, &transforms))
+        .map(|(ent, (idx, transform))| (ent, idx.0, transform.translation.truncate()))
+        .collect::<Vec<_>>();
+    for &(player_ent, player_idx, player_pos) in &players {
+        let control = &player_inputs.players[player_idx].control;
+        let current = player_states.get(player_ent).map(|x| x.current);
+        let can_grab = control.grab_just_pressed
+            && (current == Some(idle::ID) || current == Some(walk::ID))
+            && inventories.get(player_ent).map_or(true, |x| x.is_none());
+        if !can_grab {
+            continue;
+        }
+
+        let free_items = entities
+            .iter_with((&items, &transforms))
+            .filter(|(ent, _)| !held_items.contains(ent))
+            .map(|(ent, (_item, transform))| (ent, transform.translation.truncate()));
+        if item_in_grab_range(player_pos, core_meta.grab.range, free_items).is_some() {
+            continue;
+        }
+
+        let opponents = players
+            .iter()
+            .filter(|&&(ent, idx, _)| {
+                let grabbable = player_states
+                    .get(ent)
+                    .map_or(false, |x| GRABBABLE_STATES.contains(&x.current));
+                let protected_teammate =
+                    !**friendly_fire && player_inputs.are_teammates(player_idx, idx);
+                ent != player_ent
+                    && grabbable
+                    && !invulnerables.contains(ent)
+                    && !killed_players.contains(ent)
+                    && !protected_teammate
+            })
+            .map(|&(ent, _, pos)| (ent, pos));
+        let range = core_meta.player_grab.range;
+        let Some(victim) = item_in_grab_range(player_pos, range, opponents) else {
+            continue;
+        };
+
+        input_buffer.players[player_idx].consume_grab();
+        grabbings.insert(player_ent, Grabbing { victim });
+        grabbeds.insert(
+            victim,
+            grabbed::Grabbed {
+                grabber: player_ent,
+                struggle: 0,
+            },
+        );
+        player_states.get_mut(player_ent).unwrap().current = ID;
+        player_states.get_mut(victim).unwrap().current = grabbed::ID;
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_indexes: Comp<PlayerIdx>,
+    player_states: Comp<PlayerState>,
+    player_assets: BevyAssets<PlayerMeta>,
+    facings: Comp<Facing>,
+    transforms: Comp<Transform>,
+    mut grabbings: CompMut<Grabbing>,
+    mut grabbeds: CompMut<grabbed::Grabbed>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+    mut player_events: ResMut<PlayerEvents>,
+    mut audio_events: ResMut<AudioEvents>,
+) {
+    let grab_meta = &core_meta.player_grab;
+
+    let mut holds = Vec::new();
+    let mut throws = Vec::new();
+    for (player_ent, (state, player_idx, grabbing, animation, body)) in entities.iter_with((
+        &player_states,
+        &player_indexes,
+        &grabbings,
+        &mut animations,
+        &mut bodies,
+    )) {
+        if state.current != ID {
+            continue;
+        }
+
+        if state.age == 0 {
+            animation.current = key!("idle");
+        }
+
+        // Stand still while holding the other player
+        body.velocity.x = 0.0;
+
+        let control = &player_inputs.players[player_idx.0].control;
+        let facing = facings.get(player_ent).map_or(1.0, |x| x.direction());
+
+        // The press that started the grab can't also throw
+        let throw_pressed =
+            state.age > 0 && (control.grab_just_pressed || control.shoot_just_pressed);
+        if throw_pressed || state.age >= grab_meta.max_hold_frames as u64 {
+            let buffered_input = &mut input_buffer.players[player_idx.0];
+            buffered_input.consume_grab();
+            buffered_input.consume_shoot();
+
+            // Throw in the held direction, or straight ahead
+            let direction = snap_to_8_directions(control.move_direction);
+            let direction = if direction == Vec2::ZERO {
+                vec2(facing, 0.0)
+            } else {
+                direction
+            };
+            throws.push((player_ent, player_idx.0, grabbing.victim, direction));
+        } else {
+            let position = transforms.get(player_ent).unwrap().translation.truncate();
+            let hold_position =
+                position + vec2(grab_meta.hold_offset.x * facing, grab_meta.hold_offset.y);
+            holds.push((grabbing.victim, hold_position));
+        }
+    }
+
+    // Pull held players to where they are held, letting the physics keep them out of the walls
+    for (victim, hold_position) in holds {
+        if let (Some(body), Some(transform)) = (bodies.get_mut(victim), transforms.get(victim)) {
+            body.velocity = hold_position - transform.translation.truncate();
+        }
+    }
+
+    // Thrown players are knocked back like any other hit, putting them into hitstun
+    for (grabber, grabber_idx, victim, direction) in throws {
+        grabbings.remove(grabber);
+        grabbeds.remove(victim);
+        player_events.damage(
+            victim,
+            Damage {
+                amount: grab_meta.throw_damage,
+                source: Some(grabber),
+                direction,
+                knockback: grab_meta.throw_knockback,
+                weapon: Some(key!("throw")),
+                kind: HitKind::Melee,
+            },
+        );
+
+        let meta_handle = player_inputs.players[grabber_idx]
+            .selected_player
+            .get_bevy_handle();
+        if let Some(meta) = player_assets.get(&meta_handle) {
+            audio_events.play(meta.sounds.drop.clone(), meta.sounds.drop_volume);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use super::*;
+
+pub const ID: Key = key!("core::hitstun");
+
+/// Component tracking the hitstun of a player that was hit.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GTT64P3TH4PN45T4PN5W2DKQ"]
+pub struct Hitstun {
+    /// The number of frames until the player can move again.
+    pub frames_remaining: u32,
+    /// The knockback velocity of the latest hit, if it hasn't been applied yet.
+    pub knockback: Option<Vec2>,
+    /// The player that dealt the latest hit, if any.
+    ///
+    /// If the player is knocked out of the map while they are in hitstun, this player is credited
+    /// with the kill.
+    pub attacker: Option<Entity>,
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_indexes: Comp<PlayerIdx>,
+    weights: Comp<Weight>,
+    invulnerables: Comp<Invulnerable>,
+    killed_players: Comp<PlayerKilled>,
+    mut player_states: CompMut<PlayerState>,
+    mut hits_received: CompMut<HitReceived>,
+    mut hitstuns: CompMut<Hitstun>,
+) {
+    let meta = &core_meta.hitstun;
+
+    for (player_ent, (state, _player_idx)) in
+        entities.iter_with((&mut player_states, &player_indexes))
+    {
+        if let Some(hit) = hits_received.remove(player_ent) {
+            // Dead and invulnerable players can't be hit
+            if killed_players.contains(player_ent) || invulnerables.contains(player_ent) {
+                continue;
+            }
+
+            // Heavier characters are knocked back less far
+            let knockback = weights
+                .get(player_ent)
+                .copied()
+                .unwrap_or_default()
+                .knockback(
+                    hit.direction.normalize_or_zero() * meta.knockback_speed * hit.knockback,
+                );
+
+            // The damage has already been taken from the player's health, so the hit isn't lethal
+            hitstuns.insert(
+                player_ent,
+                Hitstun {
+                    frames_remaining: meta.hitstun_frames(hit.damage).max(hit.stun_frames),
+                    knockback: Some(knockback),
+                    attacker: hit.source,
+                },
+            );
+            state.current = ID;
+            continue;
+        }
+
+        if state.current != ID {
+            continue;
+        }
+
+        let stunned = hitstuns
+            .get(player_ent)
+            .map(|x| x.frames_remaining > 0)
+            .unwrap_or_default();
+        if !stunned {
+            hitstuns.remove(player_ent);
+            state.current = idle::ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    player_states: Comp<PlayerState>,
+    hit_stops: Comp<HitStop>,
+    mut hitstuns: CompMut<Hitstun>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+) {
+    let knockback_decay = core_meta.hitstun.knockback_decay;
+
+    for (player_ent, (state, hitstun, animation, body)) in
+        entities.iter_with((&player_states, &mut hitstuns, &mut animations, &mut bodies))
+    {
+        if state.current != ID {
+            continue;
+        }
+
+        if state.age == 0 {
+            animation.current = key!("flinch");
+        }
+
+        // Hold still until the hit-stop is over. The launch only comes after it, so the player
+        // has the length of the freeze to pick their directional influence.
+        if hit_stops.contains(player_ent) {
+            continue;
+        }
+
+        // Get knocked back by the latest hit, bent by the direction that the player is holding
+        if let Some(knockback) = hitstun.knockback.take() {
+            let move_direction = player_indexes.get(player_ent).map_or(Vec2::ZERO, |idx| {
+                player_inputs.players[idx.0].control.move_direction
+            });
+            body.velocity = core_meta
+                .hitstun
+                .directional_influence(knockback, move_direction);
+        }
+
+        // Slow down, ignoring any player input until the hitstun wears off
+        body.velocity.x *= knockback_decay;
+        hitstun.frames_remaining = hitstun.frames_remaining.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn light_characters_fly_further() {
+        // How far a player of the given weight slides in the first frames after the same hit
+        let knockback_distance = |weight: f32| {
+            let mut test = SystemTest::new(
+                |world| {
+                    world.init_resource::<CoreMetaArc>();
+                    world.init_resource::<PlayerInputs>();
+                },
+                |stages| {
+                    stages
+                        .add_system_to_stage(CoreStage::PreUpdate, player_state_transition)
+                        .add_system_to_stage(CoreStage::Update, handle_player_state);
+                },
+            );
+
+            let player = test
+                .world
+                .run_initialized_system(
+                    move |mut entities: ResMut<Entities>,
+                          mut player_indexes: CompMut<PlayerIdx>,
+                          mut player_states: CompMut<PlayerState>,
+                          mut weights: CompMut<Weight>,
+                          mut hits_received: CompMut<HitReceived>,
+                          mut animations: CompMut<AnimationBankSprite>,
+                          mut bodies: CompMut<KinematicBody>| {
+                        let player = entities.create();
+                        player_indexes.insert(player, PlayerIdx(0));
+                        player_states.insert(player, default());
+                        weights.insert(player, Weight(weight));
+                        hits_received.insert(
+                            player,
+                            HitReceived {
+                                damage: 10.0,
+                                direction: vec2(2.0, 1.0),
+                                knockback: 1.0,
+                                source: None,
+                                stun_frames: 20,
+                            },
+                        );
+                        animations.insert(
+                            player,
+                            AnimationBankSprite {
+                                current: key!("idle"),
+                                animations: default(),
+                                last_animation: default(),
+                            },
+                        );
+                        bodies.insert(player, default());
+                        Ok(player)
+                    },
+                )
+                .unwrap();
+
+            let mut distance = 0.0;
+            for _ in 0..10 {
+                test.run();
+                let bodies = test.world.components.get::<KinematicBody>();
+                distance += bodies.borrow().get(player).unwrap().velocity.x;
+            }
+            distance
+        };
+
+        let light = knockback_distance(0.8);
+        let heavy = knockback_distance(1.2);
+        assert!(heavy > 0.0);
+        assert!((light / heavy - 1.5).abs() < 0.001);
+
+        // Weightless characters still only fly so far
+        assert_eq!(knockback_distance(0.0), knockback_distance(Weight::MIN));
+    }
+}
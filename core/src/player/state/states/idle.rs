@@ -31,6 +31,7 @@ pub fn player_state_transition(
 pub fn handle_player_state(
     entities: Res<Entities>,
     player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
     player_indexes: Comp<PlayerIdx>,
     player_states: Comp<PlayerState>,
     player_assets: BevyAssets<PlayerMeta>,
@@ -38,9 +39,11 @@ pub fn handle_player_state(
     mut sprites: CompMut<AnimationBankSprite>,
     mut bodies: CompMut<KinematicBody>,
     items: Comp<Item>,
+    transforms: Comp<Transform>,
+    core_meta: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
     mut player_events: ResMut<PlayerEvents>,
     mut audio_events: ResMut<AudioEvents>,
-    collision_world: CollisionWorld,
 ) {
     // Collect a list of items that are being held by players
     let held_items = entities
@@ -48,13 +51,16 @@ pub fn handle_player_state(
         .filter_map(|(_ent, inventory)| inventory.0)
         .collect::<Vec<_>>();
 
-    let players = entities.iter_with((
-        &player_states,
-        &player_indexes,
-        &mut sprites,
-        &mut bodies,
-        &mut inventories,
-    ));
+    let players = entities
+        .iter_with((
+            &player_states,
+            &player_indexes,
+            &mut sprites,
+            &mut bodies,
+            &mut inventories,
+        ))
+        // Players reaching for the same item grab it in the order of their player index
+        .in_order_by_key(|(_ent, (_state, player_idx, ..))| player_idx.0);
     for (player_ent, (player_state, player_idx, animation, body, inventory)) in players {
         if player_state.current != ID {
             continue;
@@ -77,10 +83,13 @@ pub fn handle_player_state(
         use_drop_or_grab_items(
             player_ent,
             meta,
+            core_meta.grab.range,
             control,
+            &mut input_buffer.players[player_idx.0],
             inventory,
-            &collision_world,
+            &entities,
             &items,
+            &transforms,
             &held_items,
             &mut player_events,
             &mut audio_events,
@@ -88,6 +97,8 @@ pub fn handle_player_state(
 
         // If we are jumping
         if control.jump_just_pressed {
+            input_buffer.players[player_idx.0].consume_jump();
+
             // Play jump sound
             audio_events.play(meta.sounds.jump.clone(), meta.sounds.jump_volume);
 
@@ -95,13 +106,11 @@ pub fn handle_player_state(
             body.velocity.y = meta.stats.jump_speed;
         }
 
-        // Since we are idling, slide
+        // Since we are idling, slide, for longer on slippery ground
         if body.velocity.x != 0.0 {
-            if body.velocity.x.is_sign_positive() {
-                body.velocity.x = (body.velocity.x - meta.stats.slowdown).max(0.0);
-            } else {
-                body.velocity.x = (body.velocity.x + meta.stats.slowdown).min(0.0);
-            }
+            body.velocity.x = map
+                .surface(body.surface)
+                .slow_down(body.velocity.x, meta.stats.slowdown);
         }
     }
 }
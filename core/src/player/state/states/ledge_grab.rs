@@ -0,0 +1,204 @@
+use super::*;
+
+pub const ID: Key = key!("core::ledge_grab");
+
+/// Component added to a player that is hanging from a ledge.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GW05FB92M77Z426GP3EDHZ11"]
+pub struct LedgeGrab {
+    /// The position of the top corner of the ledge.
+    pub corner: Vec2,
+    /// The side of the player that the ledge is on, `1.0` for the right and `-1.0` for the left.
+    pub direction: f32,
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    map: Res<LoadedMap>,
+    player_indexes: Comp<PlayerIdx>,
+    mut player_states: CompMut<PlayerState>,
+    mut ledge_grabs: CompMut<LedgeGrab>,
+    bodies: Comp<KinematicBody>,
+    mut transforms: CompMut<Transform>,
+    collision_world: CollisionWorld,
+) {
+    for (player_ent, (state, player_idx, body, transform)) in entities.iter_with((
+        &mut player_states,
+        &player_indexes,
+        &bodies,
+        &mut transforms,
+    )) {
+        let control = &player_inputs.players[player_idx.0].control;
+
+        if state.current == ID {
+            let Some(ledge_grab) = ledge_grabs.get(player_ent) else {
+                state.current = midair::ID;
+                continue;
+            };
+
+            let climbing = control.move_direction.y > 0.5;
+            let dropping = control.move_direction.y < -0.5
+                || control.move_direction.x * ledge_grab.direction < -0.5;
+
+            if climbing {
+                // Climb up onto the ledge, landing on it in the next frame
+                if let ColliderShape::Rectangle { size } = body.shape {
+                    transform.translation.x =
+                        ledge_grab.corner.x + ledge_grab.direction * size.x / 2.0;
+                    transform.translation.y = ledge_grab.corner.y + size.y / 2.0;
+                }
+            }
+            if climbing || dropping {
+                ledge_grabs.remove(player_ent);
+                state.current = midair::ID;
+            }
+            continue;
+        }
+
+        // Only falling players may grab a ledge, and only if they are reaching towards it
+        if ![midair::ID, wall_slide::ID].contains(&state.current)
+            || body.velocity.y > 0.0
+            || control.move_direction.x.abs() < 0.5
+            || control.move_direction.y < -0.5
+        {
+            continue;
+        }
+        let ColliderShape::Rectangle { size } = body.shape else {
+            continue;
+        };
+        let direction = control.move_direction.x.signum();
+        let Some(corner) = find_ledge(
+            transform.translation.truncate(),
+            size,
+            direction,
+            core_meta.ledge_grab.grab_range,
+            map.tile_size,
+            &collision_world,
+        ) else {
+            continue;
+        };
+
+        // Only one player may hang from a ledge at a time
+        let contested = entities
+            .iter_with(&ledge_grabs)
+            .any(|(_ent, other)| other.corner == corner && other.direction == direction);
+        if contested {
+            continue;
+        }
+
+        ledge_grabs.insert(player_ent, LedgeGrab { corner, direction });
+        state.current = ID;
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_states: Comp<PlayerState>,
+    mut ledge_grabs: CompMut<LedgeGrab>,
+    mut invulnerables: CompMut<Invulnerable>,
+    mut transforms: CompMut<Transform>,
+    mut facings: CompMut<Facing>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+) {
+    let meta = &core_meta.ledge_grab;
+
+    // Let go of the ledge for players that have left the ledge grab state some other way, such as
+    // by being killed
+    let released = entities
+        .iter_with((&player_states, &ledge_grabs))
+        .filter(|(_ent, (state, _ledge_grab))| state.current != ID)
+        .map(|(ent, _)| ent)
+        .collect::<Vec<_>>();
+    for player_ent in released {
+        ledge_grabs.remove(player_ent);
+    }
+
+    for (player_ent, (state, ledge_grab, animation, facing, body, transform)) in
+        entities.iter_with((
+            &player_states,
+            &ledge_grabs,
+            &mut animations,
+            &mut facings,
+            &mut bodies,
+            &mut transforms,
+        ))
+    {
+        if state.current != ID {
+            continue;
+        }
+        let ColliderShape::Rectangle { size } = body.shape else {
+            continue;
+        };
+
+        if state.age == 0 {
+            animation.current = key!("ledge_grab");
+
+            // The player can't be hurt while grabbing onto the ledge
+            let frames_remaining = invulnerables
+                .get(player_ent)
+                .map(|x| x.frames_remaining)
+                .unwrap_or_default()
+                .max(meta.invulnerability_frames);
+            invulnerables.insert(player_ent, Invulnerable { frames_remaining });
+        }
+
+        // Hang at a fixed offset from the ledge
+        let top_corner = ledge_grab.corner
+            + vec2(
+                ledge_grab.direction * meta.hang_offset.x,
+                meta.hang_offset.y,
+            );
+        transform.translation.x = top_corner.x - ledge_grab.direction * size.x / 2.0;
+        transform.translation.y = top_corner.y - size.y / 2.0;
+        body.velocity = Vec2::ZERO;
+
+        // Face the ledge
+        facing.set(ledge_grab.direction < 0.0);
+    }
+}
+
+/// Find the corner of a ledge that a player with the given position and body size could grab onto,
+/// on the side of the player in the given direction.
+fn find_ledge(
+    player_pos: Vec2,
+    body_size: Vec2,
+    direction: f32,
+    grab_range: f32,
+    tile_size: Vec2,
+    collision_world: &CollisionWorld,
+) -> Option<Vec2> {
+    let top = player_pos.y + body_size.y / 2.0;
+    // A point just past the player's reaching hand
+    let hand_x = player_pos.x + direction * (body_size.x / 2.0 + 1.0);
+
+    // The hand must be inside of a solid tile
+    if collision_world.tile_collision_point(vec2(hand_x, top - 1.0)) != TileCollisionKind::SOLID {
+        return None;
+    }
+
+    // The top of that tile must be within reach
+    let ledge_y = ((top - 1.0) / tile_size.y).floor() * tile_size.y + tile_size.y;
+    if ledge_y - top > grab_range {
+        return None;
+    }
+
+    // And there must be room on top of the tile
+    if collision_world.tile_collision_point(vec2(hand_x, ledge_y + 1.0)) != TileCollisionKind::EMPTY
+    {
+        return None;
+    }
+
+    // The corner is on the side of the tile facing the player
+    let tile_x = (hand_x / tile_size.x).floor() * tile_size.x;
+    let corner_x = if direction > 0.0 {
+        tile_x
+    } else {
+        tile_x + tile_size.x
+    };
+
+    Some(vec2(corner_x, ledge_y))
+}
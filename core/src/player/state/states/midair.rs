@@ -1,7 +1,37 @@
+use crate::random::GlobalRng;
+
 use super::*;
 
 pub const ID: Key = key!("core::midair");
 
+/// Component tracking how many more times a player can jump before they land.
+///
+/// Players without this component have all of their air jumps available.
+#[derive(Clone, Debug, TypeUlid)]
+#[ulid = "01GVYRAE0PD1F49JXJV8AXQ0QK"]
+pub struct AirJumps {
+    pub jumps_remaining: u32,
+}
+
+/// Give players their air jumps back when they touch the ground, whatever state they are in.
+///
+/// Players can land without going through the midair state, such as while in hitstun, or when a
+/// dash ends on the ground.
+pub fn reset_air_jumps(
+    entities: Res<Entities>,
+    bodies: Comp<KinematicBody>,
+    mut air_jumps: CompMut<AirJumps>,
+) {
+    let grounded = entities
+        .iter_with((&air_jumps, &bodies))
+        .filter(|(_ent, (_air_jumps, body))| body.is_on_ground)
+        .map(|(ent, _)| ent)
+        .collect::<Vec<_>>();
+    for ent in grounded {
+        air_jumps.remove(ent);
+    }
+}
+
 pub fn player_state_transition(
     entities: Res<Entities>,
     player_inputs: Res<PlayerInputs>,
@@ -9,9 +39,13 @@ pub fn player_state_transition(
     player_assets: BevyAssets<PlayerMeta>,
     mut player_states: CompMut<PlayerState>,
     bodies: Comp<KinematicBody>,
+    transforms: Comp<Transform>,
+    core_meta: Res<CoreMetaArc>,
+    rng: Res<GlobalRng>,
     mut audio_events: ResMut<AudioEvents>,
+    mut particle_events: ResMut<ParticleEvents>,
 ) {
-    for (_ent, (player_idx, player_state, body)) in
+    for (player_ent, (player_idx, player_state, body)) in
         entities.iter_with((&player_indexes, &mut player_states, &bodies))
     {
         let meta_handle = player_inputs.players[player_idx.0]
@@ -26,7 +60,20 @@ pub fn player_state_transition(
 
         if body.is_on_ground {
             // Play land sound
-            audio_events.play(meta.sounds.land.clone(), meta.sounds.land_volume);
+            audio_events.play_variation(
+                &rng,
+                &meta.sounds.land,
+                &meta.sounds.land_variations,
+                meta.sounds.land_volume,
+                None,
+                SoundPriority::Low,
+            );
+            // Kick up some dust at the player's feet
+            if let Some(transform) = transforms.get(player_ent) {
+                let feet = body.bounding_box(*transform).min.y;
+                let position = vec3(transform.translation.x, feet, transform.translation.z);
+                particle_events.emit(&core_meta.particles.dust, position, Vec2::Y);
+            }
             // Switch to idle state
             player_state.current = idle::ID;
         }
@@ -36,17 +83,21 @@ pub fn player_state_transition(
 pub fn handle_player_state(
     entities: Res<Entities>,
     player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
     player_indexes: Comp<PlayerIdx>,
     player_states: Comp<PlayerState>,
     player_assets: BevyAssets<PlayerMeta>,
     mut inventories: CompMut<Inventory>,
-    mut sprites: CompMut<AtlasSprite>,
+    mut facings: CompMut<Facing>,
     mut animations: CompMut<AnimationBankSprite>,
     mut bodies: CompMut<KinematicBody>,
+    mut air_jumps: CompMut<AirJumps>,
     items: Comp<Item>,
+    transforms: Comp<Transform>,
+    core_meta: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
     mut player_events: ResMut<PlayerEvents>,
     mut audio_events: ResMut<AudioEvents>,
-    collision_world: CollisionWorld,
 ) {
     // Collect a list of items that are being held by players
     let held_items = entities
@@ -54,15 +105,18 @@ pub fn handle_player_state(
         .filter_map(|(_ent, inventory)| inventory.0)
         .collect::<Vec<_>>();
 
-    let players = entities.iter_with((
-        &player_states,
-        &player_indexes,
-        &mut animations,
-        &mut sprites,
-        &mut bodies,
-        &mut inventories,
-    ));
-    for (player_ent, (player_state, player_idx, animation, sprite, body, inventory)) in players {
+    let players = entities
+        .iter_with((
+            &player_states,
+            &player_indexes,
+            &mut animations,
+            &mut facings,
+            &mut bodies,
+            &mut inventories,
+        ))
+        // Players reaching for the same item grab it in the order of their player index
+        .in_order_by_key(|(_ent, (_state, player_idx, ..))| player_idx.0);
+    for (player_ent, (player_state, player_idx, animation, facing, body, inventory)) in players {
         if player_state.current != ID {
             continue;
         }
@@ -74,8 +128,34 @@ pub fn handle_player_state(
         };
         let control = &player_inputs.players[player_idx.0].control;
 
+        // Jump again while in the air
+        let jumps_remaining = air_jumps
+            .get(player_ent)
+            .map(|x| x.jumps_remaining)
+            .unwrap_or(meta.stats.air_jumps);
+        if control.jump_just_pressed && jumps_remaining > 0 {
+            input_buffer.players[player_idx.0].consume_jump();
+            air_jumps.insert(
+                player_ent,
+                AirJumps {
+                    jumps_remaining: jumps_remaining - 1,
+                },
+            );
+
+            audio_events.play(
+                meta.sounds.double_jump.clone(),
+                meta.sounds.double_jump_volume,
+            );
+
+            body.velocity.y = meta.stats.jump_speed;
+            animation.current = key!("double_jump");
+        }
+
         if body.velocity.y > 0.0 {
-            animation.current = key!("rise");
+            // Keep flipping until we start falling
+            if animation.current != key!("double_jump") {
+                animation.current = key!("rise");
+            }
         } else {
             animation.current = key!("fall");
         }
@@ -83,10 +163,13 @@ pub fn handle_player_state(
         use_drop_or_grab_items(
             player_ent,
             meta,
+            core_meta.grab.range,
             control,
+            &mut input_buffer.players[player_idx.0],
             inventory,
-            &collision_world,
+            &entities,
             &items,
+            &transforms,
             &held_items,
             &mut player_events,
             &mut audio_events,
@@ -98,7 +181,8 @@ pub fn handle_player_state(
         }
 
         // Walk in movement direction
-        body.velocity.x += meta.stats.accel_air_speed * control.move_direction.x;
+        body.velocity.x +=
+            meta.stats.accel_air_speed * map.physics.air_acceleration * control.move_direction.x;
         if control.move_direction.x.is_sign_positive() {
             body.velocity.x = body.velocity.x.min(meta.stats.air_speed);
         } else {
@@ -117,10 +201,61 @@ pub fn handle_player_state(
         body.fall_through = control.move_direction.y < -0.5 && control.jump_pressed;
 
         // Point in movement direction
-        if control.move_direction.x > 0.0 {
-            sprite.flip_x = false;
-        } else if control.move_direction.x < 0.0 {
-            sprite.flip_x = true;
-        }
+        facing.update(control.move_direction.x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::SystemTest;
+
+    use super::*;
+
+    #[test]
+    fn landing_outside_of_midair_gives_air_jumps_back() {
+        let mut test = SystemTest::new(
+            |_world| (),
+            |stages| {
+                stages.add_system_to_stage(CoreStage::Update, reset_air_jumps);
+            },
+        );
+
+        let (landed, falling) = test
+            .world
+            .run_initialized_system(
+                |mut entities: ResMut<Entities>,
+                 mut player_states: CompMut<PlayerState>,
+                 mut bodies: CompMut<KinematicBody>,
+                 mut air_jumps: CompMut<AirJumps>| {
+                    let mut spawn = |is_on_ground| {
+                        let player = entities.create();
+                        player_states.insert(
+                            player,
+                            PlayerState {
+                                current: hitstun::ID,
+                                ..default()
+                            },
+                        );
+                        bodies.insert(
+                            player,
+                            KinematicBody {
+                                is_on_ground,
+                                ..default()
+                            },
+                        );
+                        air_jumps.insert(player, AirJumps { jumps_remaining: 0 });
+                        player
+                    };
+                    Ok((spawn(true), spawn(false)))
+                },
+            )
+            .unwrap();
+
+        test.run();
+
+        let air_jumps = test.world.components.get::<AirJumps>();
+        let air_jumps = air_jumps.borrow();
+        assert!(air_jumps.get(landed).is_none());
+        assert!(air_jumps.get(falling).is_some());
     }
 }
@@ -0,0 +1,157 @@
+use super::*;
+
+pub const ID: Key = key!("core::special");
+
+/// Component tracking the special move of a player whose character has one.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GTPV5V191NJ1N9WCJNF7TXA3"]
+pub struct SpecialMove {
+    /// The number of frames left until the player can use their special move again.
+    pub cooldown_remaining: u32,
+    /// The players that were already hit by the current move, who can't be hit by it again.
+    pub hits: Vec<Entity>,
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    player_inputs: Res<PlayerInputs>,
+    player_assets: BevyAssets<PlayerMeta>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_indexes: Comp<PlayerIdx>,
+    inventories: Comp<Inventory>,
+    bodies: Comp<KinematicBody>,
+    mut player_states: CompMut<PlayerState>,
+    mut specials: CompMut<SpecialMove>,
+) {
+    for (player_ent, (state, player_idx, body)) in
+        entities.iter_with((&mut player_states, &player_indexes, &bodies))
+    {
+        let meta_handle = player_inputs.players[player_idx.0]
+            .selected_player
+            .get_bevy_handle();
+        let special_meta = player_assets
+            .get(&meta_handle)
+            .and_then(|x| x.special.as_ref());
+        let Some(special_meta) = special_meta else {
+            continue;
+        };
+        if !specials.contains(player_ent) {
+            specials.insert(player_ent, default());
+        }
+        let special = specials.get_mut(player_ent).unwrap();
+
+        if state.current == ID {
+            if state.age >= special_meta.duration_frames as u64 {
+                state.current = if body.is_on_ground {
+                    idle::ID
+                } else {
+                    midair::ID
+                };
+            }
+            continue;
+        }
+
+        // Attacking without an item uses the special move
+        let control = &player_inputs.players[player_idx.0].control;
+        let can_special_from_state =
+            [idle::ID, walk::ID, crouch::ID, midair::ID].contains(&state.current);
+        let empty_handed = inventories.get(player_ent).map_or(true, |x| x.is_none());
+        if control.shoot_just_pressed
+            && empty_handed
+            && can_special_from_state
+            && special.cooldown_remaining == 0
+        {
+            input_buffer.players[player_idx.0].consume_shoot();
+            special.hits.clear();
+            state.current = ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    player_inputs: Res<PlayerInputs>,
+    player_assets: BevyAssets<PlayerMeta>,
+    player_indexes: Comp<PlayerIdx>,
+    player_states: Comp<PlayerState>,
+    killed_players: Comp<PlayerKilled>,
+    facings: Comp<Facing>,
+    transforms: Comp<Transform>,
+    mut specials: CompMut<SpecialMove>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+    mut player_events: ResMut<PlayerEvents>,
+    mut audio_events: ResMut<AudioEvents>,
+) {
+    for (_player_ent, special) in entities.iter_with(&mut specials) {
+        special.cooldown_remaining = special.cooldown_remaining.saturating_sub(1);
+    }
+
+    let player_rects = entities
+        .iter_with((&player_indexes, &transforms, &bodies))
+        .filter(|(ent, _)| !killed_players.contains(*ent))
+        .map(|(ent, (_idx, transform, body))| (ent, body.bounding_box(*transform)))
+        .collect::<Vec<_>>();
+
+    for (player_ent, (state, player_idx, special, animation, body)) in entities.iter_with((
+        &player_states,
+        &player_indexes,
+        &mut specials,
+        &mut animations,
+        &mut bodies,
+    )) {
+        if state.current != ID {
+            continue;
+        }
+        let meta_handle = player_inputs.players[player_idx.0]
+            .selected_player
+            .get_bevy_handle();
+        let special_meta = player_assets
+            .get(&meta_handle)
+            .and_then(|x| x.special.as_ref());
+        let Some(special_meta) = special_meta else {
+            continue;
+        };
+
+        if state.age == 0 {
+            animation.current = special_meta.animation;
+            special.cooldown_remaining = special_meta.cooldown_frames;
+            audio_events.play(special_meta.sound.clone(), special_meta.sound_volume);
+        }
+
+        // Lunge forward, ignoring gravity
+        let facing = facings.get(player_ent).map_or(1.0, |x| x.direction());
+        body.velocity = vec2(special_meta.velocity.x * facing, special_meta.velocity.y);
+
+        // Hurt the players that are just in front of us
+        let transform = transforms.get(player_ent).unwrap();
+        let body_rect = body.bounding_box(*transform);
+        let hitbox_size = special_meta.hitbox_size;
+        let hitbox_center = transform.translation.truncate()
+            + vec2(facing * (body_rect.width() + hitbox_size.x) / 2.0, 0.0);
+        let hitbox = Rect::new(
+            hitbox_center.x,
+            hitbox_center.y,
+            hitbox_size.x,
+            hitbox_size.y,
+        );
+        for &(other, other_rect) in &player_rects {
+            if other == player_ent || special.hits.contains(&other) || !hitbox.overlaps(&other_rect)
+            {
+                continue;
+            }
+            special.hits.push(other);
+            player_events.damage(
+                other,
+                Damage {
+                    amount: special_meta.damage,
+                    source: Some(player_ent),
+                    direction: vec2(facing, 0.0),
+                    knockback: special_meta.knockback,
+                    weapon: Some(key!("special")),
+                    kind: HitKind::Melee,
+                },
+            );
+        }
+    }
+}
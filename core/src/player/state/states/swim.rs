@@ -0,0 +1,124 @@
+use super::*;
+
+pub const ID: Key = key!("core::swim");
+
+/// Component tracking how long a swimming player has been in the water.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GTQE7PZHYJPVH2KNNZGWQV2Q"]
+pub struct Breath {
+    /// The number of frames that the player has been in the water for.
+    pub frames_underwater: u32,
+}
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    player_indexes: Comp<PlayerIdx>,
+    transforms: Comp<Transform>,
+    water_regions: Comp<water::WaterRegion>,
+    mut player_states: CompMut<PlayerState>,
+    mut breaths: CompMut<Breath>,
+) {
+    for (player_ent, (state, _player_idx, transform)) in
+        entities.iter_with((&mut player_states, &player_indexes, &transforms))
+    {
+        let can_swim_from_state =
+            [idle::ID, walk::ID, crouch::ID, midair::ID, wall_slide::ID].contains(&state.current);
+        if state.current != ID && !can_swim_from_state {
+            continue;
+        }
+
+        let player_pos = transform.translation.truncate();
+        let in_water = entities.iter_with((&water_regions, &transforms)).any(
+            |(_ent, (water, water_transform))| {
+                water.rect(water_transform.translation).contains(player_pos)
+            },
+        );
+
+        if state.current == ID {
+            if !in_water {
+                breaths.remove(player_ent);
+                state.current = midair::ID;
+            }
+        } else if in_water {
+            breaths.insert(player_ent, default());
+            state.current = ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_indexes: Comp<PlayerIdx>,
+    player_states: Comp<PlayerState>,
+    player_assets: BevyAssets<PlayerMeta>,
+    mut breaths: CompMut<Breath>,
+    mut facings: CompMut<Facing>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+    mut player_events: ResMut<PlayerEvents>,
+    mut audio_events: ResMut<AudioEvents>,
+) {
+    let swim_meta = &core_meta.swim;
+
+    for (player_ent, (state, player_idx, breath, animation, facing, body)) in entities.iter_with((
+        &player_states,
+        &player_indexes,
+        &mut breaths,
+        &mut animations,
+        &mut facings,
+        &mut bodies,
+    )) {
+        if state.current != ID {
+            continue;
+        }
+        let meta_handle = player_inputs.players[player_idx.0]
+            .selected_player
+            .get_bevy_handle();
+        let Some(meta) = player_assets.get(&meta_handle) else {
+            continue;
+        };
+        let control = &player_inputs.players[player_idx.0].control;
+
+        if state.age == 0 {
+            animation.current = key!("swim");
+        }
+
+        // Drown if we stay under for too long
+        breath.frames_underwater = breath.frames_underwater.saturating_add(1);
+        if breath.frames_underwater > swim_meta.drown_frames {
+            player_events.kill(player_ent, None);
+        }
+
+        // Float, and sink slowly
+        body.velocity.y += swim_meta.buoyancy;
+        body.velocity.y = body.velocity.y.max(-swim_meta.max_sink_speed);
+
+        // Kick upwards
+        if control.jump_just_pressed {
+            input_buffer.players[player_idx.0].consume_jump();
+            audio_events.play(meta.sounds.jump.clone(), meta.sounds.jump_volume);
+            body.velocity.y = body.velocity.y.max(swim_meta.kick_speed);
+        }
+
+        // Swim in movement direction
+        body.velocity.x += swim_meta.accel_swim_speed * control.move_direction.x;
+        body.velocity.x = body
+            .velocity
+            .x
+            .clamp(-swim_meta.swim_speed, swim_meta.swim_speed);
+
+        if control.move_direction.x == 0.0 {
+            if body.velocity.x.is_sign_positive() {
+                body.velocity.x = (body.velocity.x - meta.stats.slowdown).max(0.0);
+            } else {
+                body.velocity.x = (body.velocity.x + meta.stats.slowdown).min(0.0);
+            }
+        }
+
+        // Point in movement direction
+        facing.update(control.move_direction.x);
+    }
+}
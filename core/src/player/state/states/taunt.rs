@@ -0,0 +1,75 @@
+use super::*;
+
+pub const ID: Key = key!("core::taunt");
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    inventories: Comp<Inventory>,
+    bodies: Comp<KinematicBody>,
+    mut player_states: CompMut<PlayerState>,
+) {
+    for (player_ent, (state, player_idx, body)) in
+        entities.iter_with((&mut player_states, &player_indexes, &bodies))
+    {
+        let control = &player_inputs.players[player_idx.0].control;
+
+        if state.current == ID {
+            // Any movement cancels the taunt, while being hit is handled by the hitstun state
+            let cancelled = control.move_direction != Vec2::ZERO
+                || control.jump_just_pressed
+                || control.shoot_just_pressed
+                || control.grab_just_pressed;
+            let finished = state.age >= core_meta.taunt.duration_frames as u64;
+
+            if cancelled || finished || !body.is_on_ground {
+                state.current = idle::ID;
+            }
+            continue;
+        }
+
+        // Players can only taunt while standing on the ground with their hands free
+        let empty_handed = inventories.get(player_ent).map_or(true, |x| x.is_none());
+        if control.taunt_just_pressed
+            && body.is_on_ground
+            && empty_handed
+            && (state.current == idle::ID || state.current == walk::ID)
+        {
+            state.current = ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    core_meta: Res<CoreMetaArc>,
+    player_states: Comp<PlayerState>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+    transforms: Comp<Transform>,
+    mut audio_events: ResMut<AudioEvents>,
+) {
+    for (player_ent, (state, animation, body)) in
+        entities.iter_with((&player_states, &mut animations, &mut bodies))
+    {
+        if state.current != ID {
+            continue;
+        }
+
+        if state.age == 0 {
+            animation.current = key!("taunt");
+            if let Some(transform) = transforms.get(player_ent) {
+                audio_events.play_at(
+                    core_meta.taunt.sound.clone(),
+                    core_meta.taunt.sound_volume,
+                    transform.translation.truncate(),
+                );
+            }
+        }
+
+        // Stand still, leaving ourselves open to attack
+        body.velocity.x = 0.0;
+    }
+}
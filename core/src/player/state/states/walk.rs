@@ -31,17 +31,20 @@ pub fn player_state_transition(
 pub fn handle_player_state(
     entities: Res<Entities>,
     player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
     player_indexes: Comp<PlayerIdx>,
     player_states: Comp<PlayerState>,
     player_assets: BevyAssets<PlayerMeta>,
     mut inventories: CompMut<Inventory>,
-    mut sprites: CompMut<AtlasSprite>,
+    mut facings: CompMut<Facing>,
     mut animations: CompMut<AnimationBankSprite>,
     mut bodies: CompMut<KinematicBody>,
     items: Comp<Item>,
+    transforms: Comp<Transform>,
+    core_meta: Res<CoreMetaArc>,
+    map: Res<LoadedMap>,
     mut player_events: ResMut<PlayerEvents>,
     mut audio_events: ResMut<AudioEvents>,
-    collision_world: CollisionWorld,
 ) {
     // Collect a list of items that are being held by players
     let held_items = entities
@@ -49,15 +52,18 @@ pub fn handle_player_state(
         .filter_map(|(_ent, inventory)| inventory.0)
         .collect::<Vec<_>>();
 
-    let players = entities.iter_with((
-        &player_states,
-        &player_indexes,
-        &mut animations,
-        &mut sprites,
-        &mut bodies,
-        &mut inventories,
-    ));
-    for (player_ent, (player_state, player_idx, animation, sprite, body, inventory)) in players {
+    let players = entities
+        .iter_with((
+            &player_states,
+            &player_indexes,
+            &mut animations,
+            &mut facings,
+            &mut bodies,
+            &mut inventories,
+        ))
+        // Players reaching for the same item grab it in the order of their player index
+        .in_order_by_key(|(_ent, (_state, player_idx, ..))| player_idx.0);
+    for (player_ent, (player_state, player_idx, animation, facing, body, inventory)) in players {
         if player_state.current != ID {
             continue;
         }
@@ -77,10 +83,13 @@ pub fn handle_player_state(
         use_drop_or_grab_items(
             player_ent,
             meta,
+            core_meta.grab.range,
             control,
+            &mut input_buffer.players[player_idx.0],
             inventory,
-            &collision_world,
+            &entities,
             &items,
+            &transforms,
             &held_items,
             &mut player_events,
             &mut audio_events,
@@ -88,25 +97,26 @@ pub fn handle_player_state(
 
         // If we are jumping
         if control.jump_just_pressed {
+            input_buffer.players[player_idx.0].consume_jump();
+
             audio_events.play(meta.sounds.jump.clone(), meta.sounds.jump_volume);
 
             // Move up
             body.velocity.y = meta.stats.jump_speed;
         }
 
-        // Walk in movement direction
-        body.velocity.x += meta.stats.accel_walk_speed * control.move_direction.x;
+        // Walk in movement direction, with as much grip as the ground gives us
+        let surface = map.surface(body.surface);
+        let walk_speed = meta.stats.walk_speed * surface.max_speed;
+        body.velocity.x +=
+            meta.stats.accel_walk_speed * surface.acceleration * control.move_direction.x;
         if control.move_direction.x.is_sign_positive() {
-            body.velocity.x = body.velocity.x.min(meta.stats.walk_speed);
+            body.velocity.x = body.velocity.x.min(walk_speed);
         } else {
-            body.velocity.x = body.velocity.x.max(-meta.stats.walk_speed);
+            body.velocity.x = body.velocity.x.max(-walk_speed);
         }
 
         // Point in movement direction
-        if control.move_direction.x > 0.0 {
-            sprite.flip_x = false;
-        } else if control.move_direction.x < 0.0 {
-            sprite.flip_x = true;
-        }
+        facing.update(control.move_direction.x);
     }
 }
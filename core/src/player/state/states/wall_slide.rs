@@ -0,0 +1,120 @@
+use super::*;
+
+pub const ID: Key = key!("core::wall_slide");
+
+pub fn player_state_transition(
+    entities: Res<Entities>,
+    player_inputs: Res<PlayerInputs>,
+    player_indexes: Comp<PlayerIdx>,
+    mut player_states: CompMut<PlayerState>,
+    bodies: Comp<KinematicBody>,
+    transforms: Comp<Transform>,
+    collision_world: CollisionWorld,
+) {
+    for (_ent, (state, player_idx, body, transform)) in
+        entities.iter_with((&mut player_states, &player_indexes, &bodies, &transforms))
+    {
+        if state.current != ID && state.current != midair::ID {
+            continue;
+        }
+        let control = &player_inputs.players[player_idx.0].control;
+        let pressed_into_wall =
+            wall_direction(control, body, transform, &collision_world).is_some();
+
+        if state.current == midair::ID {
+            // Start sliding if we are falling while pressed into a wall
+            if pressed_into_wall && body.velocity.y <= 0.0 && !body.is_on_ground {
+                state.current = ID;
+            }
+        } else if body.is_on_ground {
+            state.current = idle::ID;
+        } else if !pressed_into_wall || body.velocity.y > 0.0 {
+            state.current = midair::ID;
+        }
+    }
+}
+
+pub fn handle_player_state(
+    entities: Res<Entities>,
+    player_inputs: Res<PlayerInputs>,
+    mut input_buffer: ResMut<InputBuffer>,
+    player_indexes: Comp<PlayerIdx>,
+    player_states: Comp<PlayerState>,
+    player_assets: BevyAssets<PlayerMeta>,
+    transforms: Comp<Transform>,
+    mut facings: CompMut<Facing>,
+    mut animations: CompMut<AnimationBankSprite>,
+    mut bodies: CompMut<KinematicBody>,
+    mut audio_events: ResMut<AudioEvents>,
+    collision_world: CollisionWorld,
+) {
+    let players = entities.iter_with((
+        &player_states,
+        &player_indexes,
+        &transforms,
+        &mut animations,
+        &mut facings,
+        &mut bodies,
+    ));
+    for (_player_ent, (player_state, player_idx, transform, animation, facing, body)) in players {
+        if player_state.current != ID {
+            continue;
+        }
+        let meta_handle = player_inputs.players[player_idx.0]
+            .selected_player
+            .get_bevy_handle();
+        let Some(meta) = player_assets.get(&meta_handle) else {
+            continue;
+        };
+        let control = &player_inputs.players[player_idx.0].control;
+        let Some(wall_direction) = wall_direction(control, body, transform, &collision_world) else {
+            continue;
+        };
+
+        if player_state.age == 0 {
+            animation.current = key!("wall_slide");
+        }
+
+        // Face away from the wall
+        facing.set(wall_direction > 0.0);
+
+        // Jump off of the wall
+        if control.jump_just_pressed {
+            input_buffer.players[player_idx.0].consume_jump();
+
+            audio_events.play(meta.sounds.jump.clone(), meta.sounds.jump_volume);
+
+            body.velocity = vec2(
+                -wall_direction * meta.stats.wall_jump_speed.x,
+                meta.stats.wall_jump_speed.y,
+            );
+            continue;
+        }
+
+        // Slide slowly down the wall
+        body.velocity.x = 0.0;
+        body.velocity.y = body.velocity.y.max(-meta.stats.wall_slide_speed);
+    }
+}
+
+/// Get the direction of the wall that the player is pressing into, if any.
+///
+/// Returns `1.0` if the wall is on the right, and `-1.0` if it is on the left.
+fn wall_direction(
+    control: &PlayerControl,
+    body: &KinematicBody,
+    transform: &Transform,
+    collision_world: &CollisionWorld,
+) -> Option<f32> {
+    if control.move_direction.x.abs() < 0.5 {
+        return None;
+    }
+    let direction = control.move_direction.x.signum();
+
+    // Check for a solid tile right next to the player
+    let mut transform = *transform;
+    transform.translation.x += direction;
+    let is_wall = collision_world.tile_collision(transform, body.shape) == TileCollisionKind::SOLID;
+
+    is_wall.then_some(direction)
+}
@@ -1,7 +1,11 @@
 pub use {
+    // Our audio events shadow the ones from the bones prelude.
+    crate::audio::{AudioEvent, AudioEvents},
     crate::{
-        attachment::*, bullet::*, camera::*, damage::*, debug::*, debug::*, elements::*, input::*,
-        item::*, lifetime::*, map::*, math::*, metadata::*, physics::*, player::*, session::*,
+        animation::*, announcer::*, attachment::*, audio::*, bot::*, bullet::*, camera::*,
+        damage::*, debug::*, debug::*, decals::*, determinism::*, elements::*, emote::*,
+        hit_stop::*, input::*, item::*, lifetime::*, map::*, match_mode::*, math::*, metadata::*,
+        particles::*, physics::*, platform::*, player::*, session::*, stats::*, wind::*,
         MAX_PLAYERS,
     },
     bones_bevy_asset::{BevyAssets, BonesBevyAsset, BonesBevyAssetLoad},
@@ -11,6 +11,16 @@ pub struct GlobalRng(AtomicRng);
 
 impl Default for GlobalRng {
     fn default() -> Self {
-        Self(AtomicRng::with_seed(7))
+        Self::with_seed(Self::DEFAULT_SEED)
+    }
+}
+
+impl GlobalRng {
+    /// The seed used when no seed has been specified.
+    pub const DEFAULT_SEED: u64 = 7;
+
+    /// Create a random number generator with the given seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self(AtomicRng::with_seed(seed))
     }
 }
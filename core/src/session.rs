@@ -14,6 +14,49 @@ pub struct GameSession {
     pub info: GameSessionInfo,
 }
 
+/// Resource containing the number of simulation frames that have been run in the session.
+#[derive(Clone, TypeUlid, Deref, DerefMut, Debug, Default)]
+#[ulid = "01GTB74G2ZM5TXPCVGH4T6G8P8"]
+pub struct SimulationFrame(pub u64);
+
+/// Resource containing the number of simulation frames that are run per second in the session,
+/// from [`GameSessionInfo::fps`].
+///
+/// Anything that lasts for a time in seconds must be counted in frames of this rate, instead of
+/// [`crate::FPS`], to last as long in sessions run at a different rate. Most of the gameplay is
+/// still tuned per frame though, so only the default rate is supported for now, see
+/// [`is_supported`][Self::is_supported].
+#[derive(Clone, Copy, TypeUlid, Deref, DerefMut, Debug)]
+#[ulid = "01GW3V9Q7K2M8XDR4TNFJ5HBCA"]
+pub struct SimulationRate(pub f32);
+
+impl Default for SimulationRate {
+    fn default() -> Self {
+        Self(crate::FPS)
+    }
+}
+
+impl SimulationRate {
+    /// Whether sessions can be run at the given number of frames per second.
+    ///
+    /// Only [`crate::FPS`] is supported. The velocities, gravity, and friction of the physics, and
+    /// the durations that are counted in frames, like the hitstun and the respawn delay, are all
+    /// tuned per frame, so the game would play faster or slower at any other rate.
+    pub fn is_supported(fps: f32) -> bool {
+        fps == crate::FPS
+    }
+
+    /// The time, in seconds, that a simulation frame lasts.
+    pub fn frame_time(&self) -> f32 {
+        1.0 / self.0
+    }
+
+    /// The number of simulation frames in the given number of seconds.
+    pub fn frames(&self, seconds: f32) -> u64 {
+        (seconds * self.0).round() as u64
+    }
+}
+
 /// Information needed to start a game session.
 #[derive(Debug, Clone)]
 pub struct GameSessionInfo {
@@ -23,11 +66,43 @@ pub struct GameSessionInfo {
     pub map_meta: MapMeta,
     /// The player selections.
     pub player_info: [Option<Handle<PlayerMeta>>; MAX_PLAYERS],
+    /// The color accent picked by each player, if any.
+    pub player_colors: [Option<ColorMeta>; MAX_PLAYERS],
+    /// The team that each player is on, if teams are being played.
+    ///
+    /// Players that aren't on a team play for themselves.
+    pub player_teams: [Option<u8>; MAX_PLAYERS],
+    /// Whether players can hurt their teammates.
+    pub friendly_fire: FriendlyFire,
+    /// The rules of the match.
+    pub mode: MatchMode,
+    /// The seed for the session's random number generator.
+    ///
+    /// Two sessions started with the same info and inputs will play out identically.
+    pub seed: u64,
+    /// The number of simulation frames that are run per second.
+    ///
+    /// This is fixed for the whole session, since a replay only plays back the same way at the rate
+    /// it was recorded at. Changing it mid-session is not supported, and neither are rates other
+    /// than [`crate::FPS`] yet, see [`SimulationRate::is_supported`].
+    pub fps: f32,
 }
 
 impl GameSession {
     /// Create a new game session
+    ///
+    /// # Panics
+    ///
+    /// Panics if the session's [`fps`][GameSessionInfo::fps] isn't
+    /// [supported][SimulationRate::is_supported].
     pub fn new(mut info: GameSessionInfo) -> Self {
+        assert!(
+            SimulationRate::is_supported(info.fps),
+            "Game sessions can't be run at {} frames per second, only at {}",
+            info.fps,
+            crate::FPS
+        );
+
         // Create session
         let mut session = Self {
             world: default(),
@@ -44,10 +119,16 @@ impl GameSession {
             stage.initialize(&mut session.world);
         }
 
-        // Initialize time resource
+        // Initialize time and frame resources
         session.world.init_resource::<Time>();
+        session.world.init_resource::<SimulationFrame>();
+        session.world.insert_resource(SimulationRate(info.fps));
         // Initialize bevy world resource with an empty bevy world
         session.world.init_resource::<BevyWorld>();
+        // Seed the random number generator
+        session
+            .world
+            .insert_resource(crate::random::GlobalRng::with_seed(info.seed));
         // Set the map
         session
             .world
@@ -61,8 +142,21 @@ impl GameSession {
                 player_inputs.players[i].active = true;
                 player_inputs.players[i].selected_player = player;
             }
+            player_inputs.players[i].team = info.player_teams[i];
+            // Teammates are tinted the same color, so that they can tell who is on their side
+            player_inputs.players[i].color = info.player_teams[i]
+                .and_then(|team| info.meta.team_colors.get(team as usize).copied())
+                .or(info.player_colors[i]);
         }
 
+        // Set the match rules
+        session.world.insert_resource(info.mode);
+        session.world.insert_resource(info.friendly_fire);
+        session.world.insert_resource(Stocks::new(info.mode));
+        session
+            .world
+            .insert_resource(MatchTimer::new(info.mode, info.fps));
+
         session.set_metadata(info.meta);
 
         session
@@ -83,6 +177,7 @@ impl GameSession {
         update(&mut inputs)
     }
 
+    /// Restart the session with the same info, including the random seed.
     pub fn restart(&mut self) {
         *self = Self::new(self.info.clone());
     }
@@ -112,7 +207,8 @@ impl GameSession {
         let time_resource = self.world.resource::<Time>();
         time_resource
             .borrow_mut()
-            .advance_exact(std::time::Duration::from_secs_f32(crate::FPS));
+            .advance_exact(std::time::Duration::from_secs_f32(1.0 / self.info.fps));
+        **self.world.resource::<SimulationFrame>().borrow_mut() += 1;
 
         self.world.maintain();
 
@@ -135,6 +231,11 @@ impl GameSession {
         }
     }
 
+    /// Get the number of simulation frames that have been run in the session.
+    pub fn frame(&self) -> u64 {
+        **self.world.resource::<SimulationFrame>().borrow()
+    }
+
     /// Export the current map metadata by scanning the world entities. This means that the export
     /// will include any modifications to the map made at runtime ( most likely by the editor ).
     pub fn export_map(&self) -> MapMeta {
@@ -144,6 +245,8 @@ impl GameSession {
                   tile_layers: Comp<TileLayer>,
                   spawned_map_layer_metas: Comp<SpawnedMapLayerMeta>,
                   tile_collisions: Comp<TileCollisionKind>,
+                  surface_materials: Comp<SurfaceMaterial>,
+                  destructibles: Comp<Destructible>,
                   tiles: Comp<Tile>,
                   transforms: Comp<Transform>,
                   element_handles: Comp<ElementHandle>| {
@@ -181,6 +284,11 @@ impl GameSession {
                                     pos: UVec2::new(x, y),
                                     idx: tile.idx as u32,
                                     jump_through: collision == TileCollisionKind::JUMP_THROUGH,
+                                    material: surface_materials
+                                        .get(ent)
+                                        .copied()
+                                        .unwrap_or_default(),
+                                    destructible: destructibles.get(ent).map(|x| x.health),
                                 }
                             })
                         })
@@ -208,6 +316,12 @@ impl GameSession {
                     grid_size: map_meta.grid_size,
                     tile_size: map_meta.tile_size,
                     layers,
+                    hazards: map_meta.hazards.to_vec(),
+                    platforms: map_meta.platforms.to_vec(),
+                    wind: map_meta.wind.to_vec(),
+                    surfaces: map_meta.surfaces.clone(),
+                    blast_zone: map_meta.blast_zone,
+                    physics: map_meta.physics,
                 })
             };
 
@@ -215,14 +329,353 @@ impl GameSession {
     }
 
     /// Snapshot the world state
-    pub fn snapshot(&self) -> World {
-        self.world.clone()
+    pub fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            world: self.world.clone(),
+        }
     }
 
-    /// Restore the world state
+    /// Restore the world state from a snapshot.
     ///
-    /// Will write the current state to `world`.
-    pub fn restore(&mut self, world: &mut World) {
-        std::mem::swap(&mut self.world, world)
+    /// The snapshot is left untouched, so it may be restored again later.
+    pub fn restore(&mut self, snapshot: &SessionSnapshot) {
+        self.world = snapshot.world.clone();
+    }
+
+    /// Hash the parts of the world state that must be identical in any two sessions that were
+    /// started with the same info and given the same inputs.
+    ///
+    /// This is a combination of the hashes in [`GameSession::state_hashes`].
+    pub fn state_hash(&self) -> u64 {
+        self.state_hashes().combined()
+    }
+
+    /// Hash each of the parts of the world state that the determinism audit compares.
+    pub fn state_hashes(&self) -> StateHashes {
+        world_state_hashes(&self.world)
+    }
+}
+
+/// Hash each of the parts of the state of a session's world, see [`GameSession::state_hashes`].
+fn world_state_hashes(world: &World) -> StateHashes {
+    use std::hash::{Hash, Hasher};
+
+    let (transforms, player_states) = world
+        .run_initialized_system(
+            |entities: Res<Entities>,
+             transforms: Comp<Transform>,
+             player_states: Comp<PlayerState>| {
+                let mut transform_hasher = StateHasher::default();
+                for (ent, transform) in entities.iter_with(&transforms) {
+                    ent.index().hash(&mut transform_hasher);
+                    for value in transform
+                        .translation
+                        .to_array()
+                        .into_iter()
+                        .chain(transform.rotation.to_array())
+                        .chain(transform.scale.to_array())
+                    {
+                        value.to_bits().hash(&mut transform_hasher);
+                    }
+                }
+
+                let mut state_hasher = StateHasher::default();
+                for (ent, state) in entities.iter_with(&player_states) {
+                    ent.index().hash(&mut state_hasher);
+                    state.current.hash(&mut state_hasher);
+                    state.last.hash(&mut state_hasher);
+                    state.age.hash(&mut state_hasher);
+                }
+
+                Ok((transform_hasher.finish(), state_hasher.finish()))
+            },
+        )
+        .unwrap();
+
+    // Sample a copy of the random number generator, so that hashing doesn't advance it.
+    let rng = (**world.resource::<crate::random::GlobalRng>().borrow()).clone();
+    let rng = rng.u64(..);
+
+    StateHashes {
+        frame: **world.resource::<SimulationFrame>().borrow(),
+        transforms,
+        player_states,
+        rng,
+    }
+}
+
+/// The hasher used for the state hashes, which are compared between the clients in a networked
+/// match, so they have to come out the same in every build of the game.
+///
+/// This is 64-bit FNV-1a, which, unlike the standard library's default hasher, won't change
+/// between Rust versions. Integers are hashed in little-endian byte order, and `usize`s as `u64`s,
+/// so that the hashes don't depend on the platform either.
+struct StateHasher(u64);
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Hashes of the parts of a session's world state, used to check that two sessions are playing
+/// out identically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateHashes {
+    /// The simulation frame the hashes were taken on.
+    pub frame: u64,
+    /// Hash of the transforms of every entity.
+    pub transforms: u64,
+    /// Hash of the state of every player.
+    pub player_states: u64,
+    /// A sample from the random number generator.
+    pub rng: u64,
+}
+
+impl StateHashes {
+    /// Combine the hashes into a single hash.
+    pub fn combined(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = StateHasher::default();
+        (self.transforms, self.player_states, self.rng).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Get the name of the first part of the state that differs between the two sets of hashes, if
+    /// any.
+    pub fn first_difference(&self, other: &StateHashes) -> Option<&'static str> {
+        if self.transforms != other.transforms {
+            Some("Transform")
+        } else if self.player_states != other.player_states {
+            Some("PlayerState")
+        } else if self.rng != other.rng {
+            Some("GlobalRng")
+        } else {
+            None
+        }
+    }
+}
+
+/// A snapshot of the complete state of a game session, created with [`GameSession::snapshot`].
+///
+/// The snapshot contains the entire ECS world, including all entities and components, the random
+/// number generator, and the frame count, so restoring it will make the simulation play out
+/// exactly the same way from that point, given the same inputs.
+#[derive(Clone)]
+pub struct SessionSnapshot {
+    world: World,
+}
+
+impl SessionSnapshot {
+    /// Get the world that was captured in the snapshot.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Hash the state of the session in the snapshot, the same way as
+    /// [`GameSession::state_hash`].
+    pub fn state_hash(&self) -> u64 {
+        world_state_hashes(&self.world).combined()
+    }
+
+    /// Get the translation and rotation of the entities in the snapshot, by the index of their
+    /// entity.
+    ///
+    /// Unlike the rest of the snapshot, these are plain data, so they can be sent to another client
+    /// to correct its own snapshot of the same frame with [`set_placements`][Self::set_placements].
+    pub fn placements(&self) -> Vec<(u32, Vec3, Quat)> {
+        self.world
+            .run_initialized_system(|entities: Res<Entities>, transforms: Comp<Transform>| {
+                Ok(entities
+                    .iter_with(&transforms)
+                    .map(|(ent, x)| (ent.index(), x.translation, x.rotation))
+                    .collect())
+            })
+            .unwrap()
+    }
+
+    /// Set the translation and rotation of the entities in the snapshot, by the index of their
+    /// entity.
+    ///
+    /// Entities that don't exist in the snapshot, or don't have a transform in it, are left out.
+    pub fn set_placements(&mut self, placements: &[(u32, Vec3, Quat)]) {
+        let placements = placements
+            .iter()
+            .map(|&(ent, translation, rotation)| (ent, (translation, rotation)))
+            .collect::<std::collections::HashMap<_, _>>();
+        self.world
+            .run_initialized_system(
+                move |entities: Res<Entities>, mut transforms: CompMut<Transform>| {
+                    for (ent, transform) in entities.iter_with(&mut transforms) {
+                        if let Some(&(translation, rotation)) = placements.get(&ent.index()) {
+                            transform.translation = translation;
+                            transform.rotation = rotation;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        random::GlobalRng,
+        testing::{step, test_session},
+    };
+
+    /// Collect the state of the session that should be reproduced by restoring a snapshot.
+    fn state(session: &GameSession) -> (Vec<(Vec3, Key, u64, Key)>, u64) {
+        let players = session
+            .world
+            .run_initialized_system(
+                |entities: Res<Entities>,
+                 transforms: Comp<Transform>,
+                 player_states: Comp<PlayerState>| {
+                    Ok(entities
+                        .iter_with((&transforms, &player_states))
+                        .map(|(_ent, (transform, state))| {
+                            (transform.translation, state.current, state.age, state.last)
+                        })
+                        .collect::<Vec<_>>())
+                },
+            )
+            .unwrap();
+
+        // Sampling the random number generator lets us compare its state.
+        let rng_sample = session.world.resource::<GlobalRng>().borrow().u64(..);
+
+        (players, rng_sample)
+    }
+
+    #[test]
+    fn state_hash_tracks_divergence() {
+        let mut a = test_session();
+        let mut b = test_session();
+        assert_eq!(a.state_hash(), b.state_hash());
+
+        for _ in 0..30 {
+            step(&mut a);
+            step(&mut b);
+        }
+        assert_eq!(a.state_hashes(), b.state_hashes());
+
+        // Hashing must not change the state it is hashing.
+        assert_eq!(a.state_hash(), a.state_hash());
+
+        step(&mut a);
+        step(&mut b);
+        b.world
+            .run_initialized_system(
+                |entities: Res<Entities>, mut transforms: CompMut<Transform>| {
+                    if let Some((_ent, transform)) = entities.iter_with(&mut transforms).next() {
+                        transform.translation.x += 1.0;
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            a.state_hashes().first_difference(&b.state_hashes()),
+            Some("Transform")
+        );
+    }
+
+    #[test]
+    fn restored_snapshot_is_deterministic() {
+        let mut session = test_session();
+        let snapshot = session.snapshot();
+
+        for _ in 0..60 {
+            step(&mut session);
+        }
+        let first_run = state(&session);
+
+        session.restore(&snapshot);
+        for _ in 0..60 {
+            step(&mut session);
+        }
+        let second_run = state(&session);
+
+        assert_eq!(first_run, second_run);
+
+        // Make sure the simulation actually did something.
+        session.restore(&snapshot);
+        assert_ne!(state(&session), first_run);
+    }
+
+    #[test]
+    fn snapshot_placements_can_be_copied_between_sessions() {
+        let mut a = test_session();
+        let mut b = test_session();
+        for _ in 0..30 {
+            step(&mut a);
+            step(&mut b);
+        }
+        let mut moved = a.snapshot();
+        let mut placements = moved.placements();
+        placements[0].1.x += 10.0;
+        moved.set_placements(&placements);
+        a.restore(&moved);
+        assert_ne!(a.state_hash(), b.state_hash());
+
+        // Placing the entities where they are in the other session brings it back in line with it
+        let mut snapshot = b.snapshot();
+        snapshot.set_placements(&a.snapshot().placements());
+        b.restore(&snapshot);
+        assert_eq!(a.state_hashes(), b.state_hashes());
+    }
+
+    #[test]
+    fn state_hasher_is_fnv_1a() {
+        use std::hash::{Hash, Hasher};
+
+        let hash = |value: &dyn Fn(&mut StateHasher)| {
+            let mut hasher = StateHasher::default();
+            value(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&|_| ()), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(&|x| x.write(b"foobar")), 0x8594_4171_f739_67e8);
+        assert_eq!(
+            hash(&|x| 0x0102_0304_u32.hash(x)),
+            hash(&|x| x.write(&[4, 3, 2, 1]))
+        );
     }
 }
@@ -0,0 +1,144 @@
+//! Statistics about how each player has played over the course of a session.
+//!
+//! The stats are collected by the systems that handle the kills, damage, and items, and are read
+//! by the end-of-match scoreboard, and by headless runs for balance telemetry. They are part of the
+//! session's world, so restarting the session resets them.
+
+use crate::prelude::*;
+
+pub fn install(session: &mut GameSession) {
+    session.world.init_resource::<SessionStats>();
+
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, update_distance_traveled);
+}
+
+/// The stats that a player has racked up over the course of a session.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerStats {
+    /// The number of opponents that the player has killed.
+    pub kills: u32,
+    /// The number of times that the player has died, including by their own hand.
+    pub deaths: u32,
+    /// The total health that the player has taken away from their opponents.
+    pub damage_dealt: f32,
+    /// The most hits in a single combo that the player has landed on an opponent.
+    pub longest_combo: u32,
+    /// The distance that the player has moved while alive, in pixels.
+    pub distance_traveled: f32,
+    /// The number of items that the player has picked up.
+    pub items_picked_up: u32,
+    /// The number of shots that the player has fired from their weapons.
+    pub shots_fired: u32,
+}
+
+/// Resource containing the stats of each player in the session.
+///
+/// Unlike the [`Scores`], the stats are tracked in every match mode.
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GX2D7QH5M4V9ZK3TRB8WNC6E"]
+pub struct SessionStats {
+    pub players: [PlayerStats; MAX_PLAYERS],
+    /// The entity and position of each living player on the last frame, for measuring how far they
+    /// move on the next.
+    pub last_positions: [Option<(Entity, Vec2)>; MAX_PLAYERS],
+}
+
+impl SessionStats {
+    /// Get the stats of the player with the given index.
+    pub fn for_player(&self, player_idx: usize) -> &PlayerStats {
+        &self.players[player_idx]
+    }
+
+    /// Get the stats of the player with the given index, for updating them.
+    pub fn for_player_mut(&mut self, player_idx: usize) -> &mut PlayerStats {
+        &mut self.players[player_idx]
+    }
+
+    /// Rank the given players for the scoreboard, returning each player's index with their place,
+    /// starting from `1`, best first.
+    ///
+    /// The winner of the match comes first, and the other players are ordered by the most kills and
+    /// then the fewest deaths. Players with the same kills and deaths share a place.
+    pub fn standings(
+        &self,
+        result: MatchResult,
+        players: impl IntoIterator<Item = usize>,
+    ) -> Vec<(usize, usize)> {
+        let sort_key = |player_idx: usize| {
+            let stats = self.for_player(player_idx);
+            (
+                result.winner != Some(player_idx),
+                std::cmp::Reverse(stats.kills),
+                stats.deaths,
+            )
+        };
+        let mut players = players.into_iter().collect::<Vec<_>>();
+        players.sort_by_key(|&i| sort_key(i));
+
+        let mut standings = Vec::<(usize, usize)>::with_capacity(players.len());
+        for (i, &player_idx) in players.iter().enumerate() {
+            let place = match standings.last() {
+                Some(&(previous, place)) if sort_key(previous) == sort_key(player_idx) => place,
+                _ => i + 1,
+            };
+            standings.push((player_idx, place));
+        }
+        standings
+    }
+}
+
+/// Add up the distance that each living player has moved since the last frame.
+fn update_distance_traveled(
+    entities: Res<Entities>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    transforms: Comp<Transform>,
+    mut stats: ResMut<SessionStats>,
+) {
+    let mut positions = [None; MAX_PLAYERS];
+    for (player_ent, (player_idx, transform)) in entities.iter_with((&player_indexes, &transforms))
+    {
+        if !killed_players.contains(player_ent) {
+            positions[player_idx.0] = Some((player_ent, transform.translation.truncate()));
+        }
+    }
+
+    for (player_idx, position) in positions.iter().enumerate() {
+        // Respawning moves the player to a new entity, which doesn't count as traveling.
+        if let (Some((ent, position)), Some((last_ent, last_position))) =
+            (position, stats.last_positions[player_idx])
+        {
+            if *ent == last_ent {
+                stats.players[player_idx].distance_traveled += position.distance(last_position);
+            }
+        }
+    }
+    stats.last_positions = positions;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tied_players_share_a_place() {
+        let mut stats = SessionStats::default();
+        for (player_idx, kills, deaths) in [(0, 2, 1), (1, 3, 0), (2, 2, 1), (3, 0, 4)] {
+            let player = stats.for_player_mut(player_idx);
+            player.kills = kills;
+            player.deaths = deaths;
+        }
+
+        // The winner comes first, even without the most kills
+        let won = MatchResult { winner: Some(3) };
+        assert_eq!(
+            stats.standings(won, 0..4),
+            vec![(3, 1), (1, 2), (0, 3), (2, 3)]
+        );
+
+        let draw = MatchResult { winner: None };
+        assert_eq!(stats.standings(draw, [0, 2]), vec![(0, 1), (2, 1)]);
+    }
+}
@@ -13,3 +13,142 @@ pub fn install(session: &mut GameSession) {
 //         dbg!(trans.translation.truncate());
 //     }
 // }
+
+/// A world with a few of the game's systems added to the core stages, for testing those systems on
+/// their own.
+#[cfg(test)]
+pub struct SystemTest {
+    pub world: World,
+    pub stages: SystemStages,
+}
+
+#[cfg(test)]
+impl SystemTest {
+    /// Create a world, inserting the resources that the systems need with `init_world`, and
+    /// initialize the systems that `add_systems` adds to the stages.
+    ///
+    /// The entities that the systems are tested on can be spawned in the [`world`][Self::world]
+    /// before they are [`run`][Self::run].
+    pub fn new(
+        init_world: impl FnOnce(&mut World),
+        add_systems: impl FnOnce(&mut SystemStages),
+    ) -> Self {
+        let mut world = World::default();
+        init_world(&mut world);
+        let mut stages = SystemStages::with_core_stages();
+        add_systems(&mut stages);
+        for stage in &mut stages.stages {
+            stage.initialize(&mut world);
+        }
+
+        Self { world, stages }
+    }
+
+    /// Run each of the stages once, like a frame of the game.
+    pub fn run(&mut self) {
+        for stage in &mut self.stages.stages {
+            stage.run(&mut self.world).unwrap();
+        }
+    }
+}
+
+/// The game's core metadata, which the [`test_session`] is played with.
+#[cfg(any(test, feature = "test-fixtures"))]
+const TEST_CORE_META: &str = include_str!("../../assets/default.core.yaml");
+
+/// The character that every player in a [`test_session`] plays as.
+#[cfg(any(test, feature = "test-fixtures"))]
+const TEST_PLAYER_META: &str = include_str!("../../assets/player/skins/fishy/fishy.player.yaml");
+
+/// Create a session that runs all of the game's systems, with every player playing on a small map
+/// with a floor across the bottom of it, for testing code that snapshots, rolls back, or plays back
+/// sessions.
+///
+/// The session is played with the game's core metadata and one of it's characters. The map has no
+/// elements, so that nothing that needs their metadata is spawned.
+#[cfg(any(test, feature = "test-fixtures"))]
+pub fn test_session() -> GameSession {
+    let meta = serde_yaml::from_str::<CoreMeta>(TEST_CORE_META).unwrap();
+    let player = meta.players[0].clone();
+
+    let grid_size = uvec2(24, 12);
+    let floor = MapLayerMeta {
+        id: "floor".into(),
+        tilemap: Some(default()),
+        tiles: (0..grid_size.x)
+            .map(|x| MapTileMeta {
+                pos: uvec2(x, 0),
+                idx: 0,
+                jump_through: false,
+                material: default(),
+                destructible: None,
+            })
+            .collect(),
+        elements: default(),
+    };
+
+    let mut session = GameSession::new(GameSessionInfo {
+        meta: Arc::new(meta),
+        map_meta: MapMeta {
+            name: "Test".into(),
+            grid_size,
+            tile_size: vec2(16.0, 16.0),
+            layers: vec![floor],
+            ..default()
+        },
+        player_info: std::array::from_fn(|_| Some(player.clone())),
+        player_colors: default(),
+        player_teams: default(),
+        friendly_fire: default(),
+        mode: default(),
+        seed: 42,
+        fps: crate::FPS,
+    });
+    session.scratch_world = Some(test_bevy_world(&player));
+
+    session
+}
+
+/// Create the bevy world that a [`test_session`] is advanced with, containing the metadata of the
+/// character that its players play as.
+#[cfg(any(test, feature = "test-fixtures"))]
+fn test_bevy_world(player: &Handle<PlayerMeta>) -> ::bevy::ecs::world::World {
+    use ::bevy::{
+        app::App,
+        asset::{AssetPlugin, Assets},
+        window::Windows,
+    };
+
+    let mut app = App::new();
+    app.add_plugin(AssetPlugin::default())
+        .add_plugin(crate::metadata::JumpyCoreAssetsPlugin)
+        .init_resource::<Windows>();
+    let player_meta = serde_yaml::from_str::<PlayerMeta>(TEST_PLAYER_META).unwrap();
+    app.world
+        .resource_mut::<Assets<PlayerMeta>>()
+        .set_untracked(player.get_bevy_handle(), player_meta);
+
+    std::mem::take(&mut app.world)
+}
+
+/// Simulate a frame of a [`test_session`].
+///
+/// Between frames, the bevy world with the character metadata is kept in the session's scratch
+/// world, so that the session can be stepped on it's own.
+#[cfg(any(test, feature = "test-fixtures"))]
+pub fn step(session: &mut GameSession) {
+    use ::bevy::asset::Assets;
+
+    // A restarted session has a new, empty scratch world
+    let mut bevy_world = session
+        .scratch_world
+        .take()
+        .filter(|world| world.contains_resource::<Assets<PlayerMeta>>())
+        .unwrap_or_else(|| {
+            let player = session.info.meta.players[0].clone();
+            test_bevy_world(&player)
+        });
+    session.scratch_world = Some(default());
+    session.advance(&mut bevy_world);
+    session.scratch_world = Some(bevy_world);
+}
@@ -0,0 +1,147 @@
+//! Wind zones, that push the players, thrown items, and projectiles inside of them around.
+
+use crate::{prelude::*, random::GlobalRng};
+
+pub fn install(session: &mut GameSession) {
+    session
+        .stages
+        // The wind blows after the player states have moved the players, so that it isn't undone by
+        // their speed limits before the physics update.
+        .add_system_to_stage(CoreStage::PostUpdate, update_wind_zones)
+        .add_system_to_stage(CoreStage::PostUpdate, apply_wind);
+}
+
+/// Component for a region of the map where the wind blows, spawned from a [`WindZoneMeta`].
+#[derive(Clone, Debug, Default, TypeUlid)]
+#[ulid = "01GVFSQ54TAHB6Z9B9FQXZSJJH"]
+pub struct WindZone {
+    /// The size of the zone in pixels.
+    pub size: Vec2,
+    /// The direction and strength of the wind, as the velocity it adds every frame.
+    pub force: Vec2,
+    /// How the wind changes over time.
+    pub pattern: WindPattern,
+    /// The force that the wind is blowing with on the current frame.
+    pub current: Vec2,
+}
+
+impl WindZone {
+    /// Get the rectangle covered by this wind zone, given it's transform.
+    pub fn rect(&self, position: Vec3) -> Rect {
+        Rect::new(position.x, position.y, self.size.x, self.size.y)
+    }
+
+    /// Update the force that the wind is blowing with for the given simulation frame.
+    ///
+    /// Gusty wind only changes on the frames that it is due to, using the random number generator.
+    pub fn update(&mut self, frame: u64, rng: &GlobalRng) {
+        self.current = match self.pattern {
+            WindPattern::Constant => self.force,
+            WindPattern::Oscillating { period_frames } => {
+                let t = (frame % period_frames.max(1) as u64) as f32 / period_frames.max(1) as f32;
+                self.force * (t * std::f32::consts::TAU).sin()
+            }
+            WindPattern::Gusty { interval_frames } => {
+                if frame % interval_frames.max(1) as u64 != 0 {
+                    return;
+                }
+                let direction = if rng.bool() { 1.0 } else { -1.0 };
+                self.force * direction * (0.5 + rng.f32() * 0.5)
+            }
+        };
+    }
+}
+
+fn update_wind_zones(
+    entities: Res<Entities>,
+    frame: Res<SimulationFrame>,
+    rng: Res<GlobalRng>,
+    mut wind_zones: CompMut<WindZone>,
+) {
+    for (_ent, wind_zone) in entities.iter_with(&mut wind_zones) {
+        wind_zone.update(**frame, &rng);
+    }
+}
+
+fn apply_wind(
+    entities: Res<Entities>,
+    wind_zones: Comp<WindZone>,
+    weights: Comp<Weight>,
+    transforms: Comp<Transform>,
+    mut bodies: CompMut<KinematicBody>,
+    mut projectiles: CompMut<Projectile>,
+) {
+    let zones = entities
+        .iter_with((&wind_zones, &transforms))
+        .map(|(_ent, (wind_zone, transform))| {
+            (wind_zone.rect(transform.translation), wind_zone.current)
+        })
+        .collect::<Vec<_>>();
+    if zones.is_empty() {
+        return;
+    }
+    let wind_at = |position: Vec3| {
+        zones
+            .iter()
+            .filter(|(rect, _)| rect.contains(position.truncate()))
+            .fold(Vec2::ZERO, |wind, (_, force)| wind + *force)
+    };
+
+    // Only the bodies in the air are blown around, so that players and items don't slide along the
+    // ground
+    for (ent, (body, transform)) in entities.iter_with((&mut bodies, &transforms)) {
+        if body.is_on_ground || body.is_deactivated || !body.has_mass {
+            continue;
+        }
+        // Heavier characters are blown around less
+        let force = wind_at(transform.translation);
+        body.velocity += weights
+            .get(ent)
+            .copied()
+            .unwrap_or_default()
+            .knockback(force);
+    }
+
+    for (_ent, (projectile, transform)) in entities.iter_with((&mut projectiles, &transforms)) {
+        projectile.velocity += wind_at(transform.translation);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wind_patterns_change_over_time() {
+        let rng = GlobalRng::with_seed(42);
+        let mut wind_zone = WindZone {
+            force: vec2(1.0, 0.0),
+            pattern: WindPattern::Oscillating { period_frames: 100 },
+            ..default()
+        };
+
+        // Oscillating wind swings from blowing with it's force to against it
+        wind_zone.update(25, &rng);
+        assert!(wind_zone.current.abs_diff_eq(vec2(1.0, 0.0), 0.001));
+        wind_zone.update(75, &rng);
+        assert!(wind_zone.current.abs_diff_eq(vec2(-1.0, 0.0), 0.001));
+
+        // Gusty wind holds steady between gusts
+        wind_zone.pattern = WindPattern::Gusty {
+            interval_frames: 60,
+        };
+        wind_zone.update(60, &rng);
+        let gust = wind_zone.current;
+        assert!(gust.x.abs() >= 0.5 && gust.x.abs() <= 1.0);
+        for frame in 61..120 {
+            wind_zone.update(frame, &rng);
+            assert_eq!(wind_zone.current, gust);
+        }
+
+        // And blows the same way for the same seed
+        let mut replayed = wind_zone.clone();
+        replayed.update(120, &GlobalRng::with_seed(7));
+        wind_zone.update(120, &GlobalRng::with_seed(7));
+        assert_eq!(replayed.current, wind_zone.current);
+    }
+}
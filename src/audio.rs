@@ -1,8 +1,9 @@
-use std::time::Duration;
+use std::{collections::VecDeque, time::Duration};
 
 use bevy_kira_audio::{
     AudioApp, AudioChannel, AudioControl, AudioInstance, AudioSource, PlaybackState,
 };
+use jumpy_core::{announcer::Announcement, audio::SoundPriority};
 use rand::{seq::SliceRandom, thread_rng};
 
 use crate::{metadata::GameMeta, prelude::*};
@@ -14,10 +15,33 @@ impl Plugin for JumpyAudioPlugin {
         app.add_plugin(bevy_kira_audio::AudioPlugin)
             .init_resource::<CurrentMusic>()
             .init_resource::<ShuffledPlaylist>()
+            .init_resource::<SpatialAudioSettings>()
+            .init_resource::<MusicSettings>()
+            .init_resource::<VolumeSettings>()
+            .init_resource::<LoopingSounds>()
+            .init_resource::<SoundVoices>()
+            .insert_resource(AnnouncerVoice {
+                enabled: !ENGINE_CONFIG.no_announcer,
+                ..default()
+            })
+            .insert_resource(MusicLayers {
+                enabled: !ENGINE_CONFIG.no_adaptive_music,
+                ..default()
+            })
             .add_audio_channel::<MusicChannel>()
             .add_audio_channel::<EffectsChannel>()
+            .add_audio_channel::<AnnouncerChannel>()
             .add_startup_system(setup_audio_defaults)
-            .add_system(music_system.run_if_resource_exists::<GameMeta>());
+            .add_enter_system(EngineState::MainMenu, start_menu_music)
+            .add_system(update_volume_settings)
+            .add_system(stop_pending_sounds)
+            .add_system(play_announcer_lines.before(update_volume_settings))
+            .add_system(music_system.run_if_resource_exists::<GameMeta>())
+            .add_system(
+                update_music_layers
+                    .run_if_resource_exists::<GameMeta>()
+                    .after(music_system),
+            );
     }
 }
 
@@ -25,25 +49,642 @@ impl Plugin for JumpyAudioPlugin {
 pub struct MusicChannel;
 #[derive(Resource)]
 pub struct EffectsChannel;
+#[derive(Resource)]
+pub struct AnnouncerChannel;
+
+/// The base volume of the [`MusicChannel`], before applying the [`VolumeSettings`].
+pub const MUSIC_CHANNEL_VOLUME: f64 = 0.12;
+/// The base volume of the [`EffectsChannel`].
+pub const EFFECTS_CHANNEL_VOLUME: f64 = 0.1;
+/// The base volume of the [`AnnouncerChannel`], which is turned up with the sound effects.
+pub const ANNOUNCER_CHANNEL_VOLUME: f64 = 0.3;
+
+/// The user's volume settings.
+///
+/// All of the volumes are multipliers in the range `0.0..=1.0`. Sound effects are played at
+/// `master * effects * event_volume`, and music is played at `master * music`.
+///
+/// The volume settings are saved as part of the [`Settings`], and this resource is updated to match
+/// them.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct VolumeSettings {
+    pub master: f32,
+    pub effects: f32,
+    pub music: f32,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            effects: 1.0,
+            music: 1.0,
+        }
+    }
+}
+
+impl VolumeSettings {
+    /// Set the master volume, clamped to `0.0..=1.0`.
+    pub fn set_master(&mut self, volume: f32) {
+        self.master = volume.clamp(0.0, 1.0);
+    }
+
+    /// Set the sound effects volume, clamped to `0.0..=1.0`.
+    pub fn set_effects(&mut self, volume: f32) {
+        self.effects = volume.clamp(0.0, 1.0);
+    }
+
+    /// Set the music volume, clamped to `0.0..=1.0`.
+    pub fn set_music(&mut self, volume: f32) {
+        self.music = volume.clamp(0.0, 1.0);
+    }
+
+    /// The multiplier to apply to the volume of sound effects.
+    pub fn effects_volume(&self) -> f32 {
+        self.master * self.effects
+    }
+
+    /// The multiplier to apply to the volume of music.
+    pub fn music_volume(&self) -> f32 {
+        self.master * self.music
+    }
+}
+
+/// Settings controlling how positional sound effects are attenuated and panned based on their
+/// distance from the game camera.
+#[derive(Resource, Clone, Debug)]
+pub struct SpatialAudioSettings {
+    /// Sounds closer than this distance to the camera are played at full volume.
+    pub min_distance: f32,
+    /// Sounds further than this distance from the camera are not audible at all.
+    pub max_distance: f32,
+    /// The exponent of the falloff curve between `min_distance` and `max_distance`.
+    ///
+    /// `1.0` gives a linear falloff, while higher values make the volume drop more quickly as the
+    /// sound moves away from the camera.
+    pub rolloff: f32,
+    /// The horizontal distance from the camera at which a sound is panned as far as it can be.
+    pub pan_distance: f32,
+    /// How far the sound can be panned to either side, from `0.0` ( no panning ) to `1.0` ( fully
+    /// panned into one speaker ).
+    pub pan_strength: f32,
+}
+
+impl Default for SpatialAudioSettings {
+    fn default() -> Self {
+        Self {
+            min_distance: 200.0,
+            max_distance: 1500.0,
+            rolloff: 1.5,
+            pan_distance: 600.0,
+            pan_strength: 0.6,
+        }
+    }
+}
+
+impl SpatialAudioSettings {
+    /// Get the volume multiplier for a sound that is `distance` away from the listener.
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        if distance <= self.min_distance {
+            1.0
+        } else if distance >= self.max_distance {
+            0.0
+        } else {
+            let t = (distance - self.min_distance) / (self.max_distance - self.min_distance);
+            (1.0 - t).powf(self.rolloff)
+        }
+    }
+
+    /// Get the stereo panning for a sound that is `offset_x` units to the right of the listener.
+    ///
+    /// Returns a value from `0.0` ( hard left ) to `1.0` ( hard right ), where `0.5` is centered.
+    pub fn panning(&self, offset_x: f32) -> f32 {
+        let side = (offset_x / self.pan_distance).clamp(-1.0, 1.0);
+        0.5 + side * 0.5 * self.pan_strength.clamp(0.0, 1.0)
+    }
+}
+
+/// The kind of music that is playing on the [`MusicChannel`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MusicKind {
+    /// No music is playing.
+    #[default]
+    Silent,
+    /// The menu theme, which loops until it is replaced.
+    Menu,
+    /// The in-game playlist, which moves on to the next song whenever one finishes.
+    Match,
+}
 
 #[derive(Resource, Clone, Debug, Default)]
 pub struct CurrentMusic {
     pub instance: Handle<AudioInstance>,
     pub idx: usize,
+    pub kind: MusicKind,
+}
+
+/// Settings for music playback.
+#[derive(Resource, Clone, Debug)]
+pub struct MusicSettings {
+    /// How long it takes to crossfade between the menu music and the match music.
+    pub crossfade_duration: Duration,
+}
+
+impl Default for MusicSettings {
+    fn default() -> Self {
+        Self {
+            crossfade_duration: Duration::from_secs_f32(1.5),
+        }
+    }
 }
 
 #[derive(Resource, Deref, DerefMut, Clone, Debug, Default)]
 pub struct ShuffledPlaylist(pub Vec<AssetHandle<AudioSource>>);
 
+/// Resource that layers the [stems][crate::metadata::MusicLayersMeta] of the match music on top of
+/// it, as the match heats up.
+///
+/// The stems are started along with the song, and play in sync with it the whole time, but are
+/// only heard once the [`intensity`][Self::intensity] of the match reaches theirs. They are only
+/// faded in and out at the start of a bar, so that they come in on the beat.
+#[derive(Resource, Clone, Debug)]
+pub struct MusicLayers {
+    /// Whether to play the stems at all.
+    pub enabled: bool,
+    /// How long it takes for a stem to fade in or out.
+    pub fade_duration: Duration,
+    /// How close the match is to being decided, see [`Session::match_intensity`].
+    pub intensity: f32,
+    /// The song that the stems are playing along with.
+    song: Handle<AudioInstance>,
+    /// The length of a bar of the song, in seconds.
+    bar_duration: f64,
+    /// The bar of the song that the stems were last faded in or out on, if they have been.
+    last_bar: Option<u64>,
+    stems: Vec<PlayingStem>,
+}
+
+#[derive(Clone, Debug)]
+struct PlayingStem {
+    instance: Handle<AudioInstance>,
+    /// The intensity that the stem is heard at.
+    intensity: f32,
+    audible: bool,
+}
+
+impl Default for MusicLayers {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fade_duration: Duration::from_secs_f32(2.0),
+            intensity: 0.0,
+            song: default(),
+            bar_duration: 0.0,
+            last_bar: None,
+            stems: default(),
+        }
+    }
+}
+
+impl MusicLayers {
+    /// Fade the stems out right away, without waiting for the end of the bar, such as when the
+    /// match is restarted.
+    pub fn reset(&mut self) {
+        self.intensity = 0.0;
+        self.last_bar = None;
+    }
+
+    /// Decide which stems are heard at the given position in the song, in seconds, returning
+    /// the stems that have to be faded in or out.
+    fn update_stems(&mut self, position: f64) -> Vec<(Handle<AudioInstance>, bool)> {
+        let bar = (position / self.bar_duration.max(f64::EPSILON)) as u64;
+        if self.last_bar == Some(bar) {
+            return Vec::new();
+        }
+        self.last_bar = Some(bar);
+
+        let intensity = self.intensity;
+        self.stems
+            .iter_mut()
+            .filter(|stem| stem.audible != (intensity >= stem.intensity))
+            .map(|stem| {
+                stem.audible = !stem.audible;
+                (stem.instance.clone(), stem.audible)
+            })
+            .collect()
+    }
+}
+
+/// Audio instances that have been told to stop, but that don't exist yet.
+///
+/// A sound's instance is only added to the [`Assets<AudioInstance>`] once the audio backend has
+/// started playing it, which is at least a frame after it was played, so a sound that is stopped
+/// right after being played is stopped once its instance shows up.
+///
+/// An instance that still doesn't exist after [`PendingStops::MAX_FRAMES`] never will, because its
+/// sound failed to play, so it stops being waited for.
+#[derive(Clone, Debug, Default)]
+pub struct PendingStops(Vec<(Handle<AudioInstance>, u32)>);
+
+impl PendingStops {
+    /// How many frames to wait for an instance to exist before giving up on stopping it.
+    pub const MAX_FRAMES: u32 = 60;
+
+    /// Stop the instance, or remember to stop it once it exists.
+    pub fn stop(
+        &mut self,
+        instance: Handle<AudioInstance>,
+        audio_instances: &mut Assets<AudioInstance>,
+    ) {
+        if !stop_instance(&instance, audio_instances) {
+            self.0.push((instance, 0));
+        }
+    }
+
+    /// Stop the instances that have shown up since they were told to stop.
+    pub fn retry(&mut self, audio_instances: &mut Assets<AudioInstance>) {
+        self.retry_with(|instance| stop_instance(instance, audio_instances));
+    }
+
+    /// Try to stop each waiting instance with `stop`, which returns whether the instance existed,
+    /// and forget about the ones that have been waited for too long.
+    fn retry_with(&mut self, mut stop: impl FnMut(&Handle<AudioInstance>) -> bool) {
+        self.0.retain_mut(|(instance, frames)| {
+            if stop(instance) {
+                return false;
+            }
+            *frames += 1;
+            if *frames >= Self::MAX_FRAMES {
+                warn!("Audio instance never started, so it couldn't be stopped");
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Whether there are no instances waiting to be stopped.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Stop the instance if it exists, returning whether it did.
+fn stop_instance(
+    instance: &Handle<AudioInstance>,
+    audio_instances: &mut Assets<AudioInstance>,
+) -> bool {
+    match audio_instances.get_mut(instance) {
+        Some(playing) => {
+            playing.stop(AudioTween::default());
+            true
+        }
+        None => false,
+    }
+}
+
+/// A looping sound that was started by the game session.
+#[derive(Clone, Debug)]
+pub struct LoopingSound {
+    /// The audio instance playing the loop.
+    pub instance: Handle<AudioInstance>,
+    /// The volume the loop was requested at, before applying the [`VolumeSettings`].
+    pub volume: f32,
+}
+
+/// The looping sounds that are currently playing, by the handle ID they were started with.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LoopingSounds {
+    loops: HashMap<u64, LoopingSound>,
+    /// The loops that have been stopped before their instances existed.
+    stopping: PendingStops,
+}
+
+impl LoopingSounds {
+    /// Start tracking a loop, returning the loop that was previously tracked with the same ID.
+    pub fn insert(&mut self, handle_id: u64, sound: LoopingSound) -> Option<LoopingSound> {
+        self.loops.insert(handle_id, sound)
+    }
+
+    /// Stop tracking a loop, returning it if it was being tracked.
+    pub fn remove(&mut self, handle_id: u64) -> Option<LoopingSound> {
+        self.loops.remove(&handle_id)
+    }
+
+    /// Stop tracking all loops, returning an iterator over them.
+    pub fn drain(&mut self) -> impl Iterator<Item = LoopingSound> + '_ {
+        self.loops.drain().map(|(_, sound)| sound)
+    }
+
+    /// Iterate over the loops that are being tracked.
+    pub fn iter(&self) -> impl Iterator<Item = &LoopingSound> {
+        self.loops.values()
+    }
+
+    /// Whether or not there are any loops being tracked.
+    pub fn is_empty(&self) -> bool {
+        self.loops.is_empty()
+    }
+
+    /// Stop a loop from playing, once its instance exists if it was only just started.
+    pub fn stop(&mut self, sound: LoopingSound, audio_instances: &mut Assets<AudioInstance>) {
+        self.stopping.stop(sound.instance, audio_instances);
+    }
+
+    /// Stop all of the loops.
+    pub fn stop_all(&mut self, audio_instances: &mut Assets<AudioInstance>) {
+        let sounds = self.drain().collect::<Vec<_>>();
+        for sound in sounds {
+            self.stop(sound, audio_instances);
+        }
+    }
+}
+
+/// Stop the sounds that were stopped before their audio instances existed, once they do.
+fn stop_pending_sounds(
+    mut looping_sounds: ResMut<LoopingSounds>,
+    mut sound_voices: ResMut<SoundVoices>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if !looping_sounds.stopping.is_empty() {
+        looping_sounds.stopping.retry(&mut audio_instances);
+    }
+    if !sound_voices.stopping.is_empty() {
+        sound_voices.stopping.retry(&mut audio_instances);
+    }
+}
+
+/// The sound effects that are playing, excluding the looping sounds, so that the least important
+/// ones can be cut off when too many are playing at once.
+#[derive(Resource, Clone, Debug)]
+pub struct SoundVoices {
+    /// The most sound effects that may play at once.
+    pub max_voices: usize,
+    /// The voices that are playing, oldest first.
+    voices: Vec<SoundVoice>,
+    /// The stolen voices that are waiting for their instances to exist, to be stopped.
+    stopping: PendingStops,
+}
+
+#[derive(Clone, Debug)]
+struct SoundVoice {
+    instance: Handle<AudioInstance>,
+    priority: SoundPriority,
+}
+
+/// Where a new sound effect can be played, as decided by [`SoundVoices::make_room`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VoiceSlot {
+    /// There is a free voice to play the sound with.
+    Free,
+    /// The voice playing the given instance has to be stopped to play the sound.
+    Steal(Handle<AudioInstance>),
+    /// Every voice is playing a more important sound, so the sound is dropped.
+    Full,
+}
+
+impl Default for SoundVoices {
+    fn default() -> Self {
+        Self {
+            max_voices: 16,
+            voices: default(),
+            stopping: default(),
+        }
+    }
+}
+
+impl SoundVoices {
+    /// Forget the voices that have finished playing.
+    ///
+    /// Voices whose instances don't exist yet, because the audio backend hasn't started them, are
+    /// still about to play.
+    pub fn retain_playing(&mut self, audio_instances: &Assets<AudioInstance>) {
+        self.voices.retain(|voice| {
+            audio_instances
+                .get(&voice.instance)
+                .map_or(true, |instance| {
+                    !matches!(instance.state(), PlaybackState::Stopped)
+                })
+        });
+    }
+
+    /// Stop the voice that was stolen by [`make_room`][Self::make_room], once its instance exists
+    /// if it was only just played.
+    pub fn stop(
+        &mut self,
+        instance: Handle<AudioInstance>,
+        audio_instances: &mut Assets<AudioInstance>,
+    ) {
+        self.stopping.stop(instance, audio_instances);
+    }
+
+    /// Find a voice to play a sound with the given priority with, stealing the oldest of the least
+    /// important voices if they are all taken.
+    ///
+    /// A voice is only stolen if it is playing a sound that is at most as important as the new one.
+    pub fn make_room(&mut self, priority: SoundPriority) -> VoiceSlot {
+        if self.voices.len() < self.max_voices {
+            return VoiceSlot::Free;
+        }
+        let lowest = self
+            .voices
+            .iter()
+            .enumerate()
+            .min_by_key(|(i, voice)| (voice.priority, *i))
+            .filter(|(_, voice)| voice.priority <= priority)
+            .map(|(i, _)| i);
+        match lowest {
+            Some(i) => VoiceSlot::Steal(self.voices.remove(i).instance),
+            None => VoiceSlot::Full,
+        }
+    }
+
+    /// Keep track of a sound that was played with the voice from [`make_room`][Self::make_room].
+    pub fn push(&mut self, instance: Handle<AudioInstance>, priority: SoundPriority) {
+        self.voices.push(SoundVoice { instance, priority });
+    }
+}
+
+/// Resource that plays the announcer's voice lines on the [`AnnouncerChannel`], one at a time,
+/// turning the music and sound effects down while they play.
+///
+/// Lines that come in while another is playing wait their turn, most important first. Once too
+/// many are waiting, the least important ones are dropped, but lines with a
+/// [`SoundPriority::High`] priority, like the winner of the match, are always played.
+#[derive(Resource, Clone, Debug)]
+pub struct AnnouncerVoice {
+    /// Whether to play the announcer's lines at all.
+    pub enabled: bool,
+    /// The shortest time from the start of one line to the start of the next.
+    pub cooldown: Duration,
+    /// The most lines that may be waiting to be played.
+    pub max_pending: usize,
+    /// The volume multiplier of the music and sound effects while a line is playing.
+    pub duck_volume: f64,
+    /// How long the music and sound effects take to fade down and back up.
+    pub duck_fade: Duration,
+    /// The lines waiting to be played, oldest first.
+    pending: VecDeque<(Announcement, Handle<AudioSource>)>,
+    /// The time that the next line may start at.
+    ready_at: Duration,
+    /// The line that is playing, if any.
+    playing: Option<(Announcement, Handle<AudioInstance>)>,
+}
+
+impl Default for AnnouncerVoice {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cooldown: Duration::from_secs_f32(1.5),
+            max_pending: 3,
+            duck_volume: 0.4,
+            duck_fade: Duration::from_secs_f32(0.2),
+            pending: default(),
+            ready_at: Duration::ZERO,
+            playing: None,
+        }
+    }
+}
+
+impl AnnouncerVoice {
+    /// Queue the line for an announcement to be played once the lines before it are done.
+    pub fn push(&mut self, announcement: Announcement, line: Handle<AudioSource>) {
+        let priority = announcement.priority();
+        if self.pending.len() >= self.max_pending {
+            let lowest = self
+                .pending
+                .iter()
+                .map(|(announcement, _)| announcement.priority())
+                .enumerate()
+                .min_by_key(|(i, priority)| (*priority, *i))
+                .filter(|(_, lowest)| *lowest <= priority && *lowest != SoundPriority::High)
+                .map(|(i, _)| i);
+            match lowest {
+                Some(i) => {
+                    self.pending.remove(i);
+                }
+                None if priority == SoundPriority::High => (),
+                None => return,
+            }
+        }
+        self.pending.push_back((announcement, line));
+    }
+
+    /// Drop the lines that are waiting to be played.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Take the line to play next, the oldest of the most important ones, if the cooldown since the
+    /// last line is over.
+    fn next(&mut self, now: Duration) -> Option<(Announcement, Handle<AudioSource>)> {
+        if now < self.ready_at {
+            return None;
+        }
+        let (i, _) = self
+            .pending
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, (announcement, _))| {
+                (announcement.priority(), std::cmp::Reverse(*i))
+            })?;
+        self.ready_at = now + self.cooldown;
+        self.pending.remove(i)
+    }
+
+    /// The announcement whose line is playing, if any.
+    pub fn playing(&self) -> Option<Announcement> {
+        self.playing.as_ref().map(|(announcement, _)| *announcement)
+    }
+
+    /// The volume multiplier of the music and sound effects, which are ducked while a line plays.
+    pub fn duck(&self) -> f64 {
+        if self.playing.is_some() {
+            self.duck_volume
+        } else {
+            1.0
+        }
+    }
+}
+
 fn setup_audio_defaults(
     music: Res<AudioChannel<MusicChannel>>,
     effects: Res<AudioChannel<EffectsChannel>>,
+    announcer: Res<AudioChannel<AnnouncerChannel>>,
 ) {
-    music.set_volume(0.12);
-    effects.set_volume(0.1);
+    music.set_volume(MUSIC_CHANNEL_VOLUME);
+    effects.set_volume(EFFECTS_CHANNEL_VOLUME);
+    announcer.set_volume(ANNOUNCER_CHANNEL_VOLUME);
 }
 
-/// Loops through all the game music as the game is on.
+/// Apply changes to the volume settings to the audio that is already playing.
+fn update_volume_settings(
+    volume_settings: Res<VolumeSettings>,
+    announcer_voice: Res<AnnouncerVoice>,
+    music: Res<AudioChannel<MusicChannel>>,
+    announcer: Res<AudioChannel<AnnouncerChannel>>,
+) {
+    if !volume_settings.is_changed() {
+        return;
+    }
+
+    // The music, the announcer, and looping sound effects are the only things that play long
+    // enough to need updating. The looping sounds also follow the session's pause and time scale,
+    // so they are updated along with those by the session's `update_looping_sounds`, and the other
+    // sound effects are short enough that they only get the volume settings when they are played.
+    music.set_volume(
+        MUSIC_CHANNEL_VOLUME * volume_settings.music_volume() as f64 * announcer_voice.duck(),
+    );
+    announcer.set_volume(ANNOUNCER_CHANNEL_VOLUME * volume_settings.effects_volume() as f64);
+}
+
+/// Play the lines queued up in the [`AnnouncerVoice`], ducking the music and sound effects under
+/// them.
+fn play_announcer_lines(
+    time: Res<Time>,
+    volume_settings: Res<VolumeSettings>,
+    mut voice: ResMut<AnnouncerVoice>,
+    audio_instances: Res<Assets<AudioInstance>>,
+    music: Res<AudioChannel<MusicChannel>>,
+    effects: Res<AudioChannel<EffectsChannel>>,
+    announcer: Res<AudioChannel<AnnouncerChannel>>,
+) {
+    let ducked = voice.playing.is_some();
+    let finished = voice.playing.as_ref().map_or(false, |(_, line)| {
+        audio_instances.get(line).map_or(true, |instance| {
+            matches!(instance.state(), PlaybackState::Stopped)
+        })
+    });
+    if finished {
+        voice.playing = None;
+    }
+    if !voice.enabled {
+        voice.clear();
+    }
+    if voice.playing.is_none() {
+        if let Some((announcement, line)) = voice.next(time.elapsed()) {
+            voice.playing = Some((announcement, announcer.play(line).handle()));
+        }
+    }
+
+    if ducked != voice.playing.is_some() {
+        let duck = voice.duck();
+        music
+            .set_volume(MUSIC_CHANNEL_VOLUME * volume_settings.music_volume() as f64 * duck)
+            .fade_in(AudioTween::linear(voice.duck_fade));
+        effects
+            .set_volume(EFFECTS_CHANNEL_VOLUME * duck)
+            .fade_in(AudioTween::linear(voice.duck_fade));
+    }
+}
+
+/// Crossfade to the menu music whenever the main menu is shown, including once the game has
+/// finished loading.
+fn start_menu_music(mut music: MusicManager) {
+    music.crossfade_to_menu_music();
+}
+
+/// Loops through the game music playlist while a match is being played.
 fn music_system(
     game: Res<GameMeta>,
     mut playlist: ResMut<ShuffledPlaylist>,
@@ -57,6 +698,11 @@ fn music_system(
         **playlist = songs;
     }
 
+    // The menu music loops on it's own, so we only need to manage the match playlist.
+    if current_music.kind != MusicKind::Match || playlist.is_empty() {
+        return;
+    }
+
     if let Some(instance) = audio_instances.get(&current_music.instance) {
         if let PlaybackState::Stopped = instance.state() {
             current_music.idx += 1;
@@ -67,13 +713,367 @@ fn music_system(
                 .linear_fade_in(Duration::from_secs_f32(0.5))
                 .handle();
         }
-    } else if let Some(song) = playlist.get(0) {
-        if current_music.instance == default() {
-            current_music.instance = music
-                .play(song.inner.clone_weak())
-                .linear_fade_in(Duration::from_secs_f32(0.5))
-                .handle();
-            current_music.idx = 0;
+    } else if current_music.instance == default() {
+        current_music.idx %= playlist.len();
+        current_music.instance = music
+            .play(playlist[current_music.idx].inner.clone_weak())
+            .linear_fade_in(Duration::from_secs_f32(0.5))
+            .handle();
+    }
+}
+
+/// Start the stems of the match song that is playing, and fade them in and out with the intensity
+/// of the match.
+fn update_music_layers(
+    game: Res<GameMeta>,
+    playlist: Res<ShuffledPlaylist>,
+    current_music: Res<CurrentMusic>,
+    mut layers: ResMut<MusicLayers>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    music: Res<AudioChannel<MusicChannel>>,
+) {
+    let position = audio_instances
+        .get(&current_music.instance)
+        .and_then(|instance| match instance.state() {
+            PlaybackState::Playing { position } => Some(position),
+            _ => None,
+        })
+        .filter(|_| layers.enabled && current_music.kind == MusicKind::Match);
+    let Some(position) = position else {
+        layers.song = default();
+        for stem in layers.stems.drain(..) {
+            if let Some(instance) = audio_instances.get_mut(&stem.instance) {
+                instance.stop(AudioTween::default());
+            }
+        }
+        return;
+    };
+
+    // Start the stems of a new song where the song is at, so that they are in sync with it
+    if layers.song != current_music.instance {
+        let song_id = playlist.get(current_music.idx).map(|x| x.inner.id);
+        let meta = game
+            .music_layers
+            .iter()
+            .find(|x| Some(x.song.inner.id) == song_id);
+
+        layers.song = current_music.instance.clone();
+        layers.last_bar = None;
+        layers.bar_duration = meta.map_or(0.0, |x| {
+            60.0 / x.bpm.max(1.0) as f64 * x.beats_per_bar.max(1) as f64
+        });
+        layers.stems = meta
+            .into_iter()
+            .flat_map(|x| &x.stems)
+            .map(|stem| PlayingStem {
+                instance: music
+                    .play(stem.stem.inner.clone_weak())
+                    .start_from(position)
+                    .with_volume(0.0)
+                    .handle(),
+                intensity: stem.intensity,
+                audible: false,
+            })
+            .collect();
+    }
+
+    let fade_duration = layers.fade_duration;
+    for (stem, audible) in layers.update_stems(position) {
+        if let Some(instance) = audio_instances.get_mut(&stem) {
+            let volume = if audible { 1.0 } else { 0.0 };
+            instance.set_volume(volume, AudioTween::linear(fade_duration));
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::asset::{AssetPlugin, HandleId};
+
+    use super::*;
+
+    /// Get the audio instances of an app that hasn't played any sounds.
+    fn audio_instances() -> Assets<AudioInstance> {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<AudioInstance>();
+        app.world
+            .remove_resource::<Assets<AudioInstance>>()
+            .unwrap()
+    }
+
+    fn looping_sound() -> LoopingSound {
+        LoopingSound {
+            instance: Handle::weak(HandleId::random::<AudioInstance>()),
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_announcer_lines_wait_their_turn() {
+        let mut voice = AnnouncerVoice {
+            cooldown: Duration::from_secs(1),
+            max_pending: 2,
+            ..default()
+        };
+        let line = Handle::<AudioSource>::weak(HandleId::random::<AudioSource>());
+        let multi_kill = |kills| Announcement::MultiKill {
+            player_idx: 0,
+            kills,
+        };
+        let victory = |winner| Announcement::Victory {
+            winner: Some(winner),
+        };
+        let next = |voice: &mut AnnouncerVoice, secs| {
+            voice.next(Duration::from_secs(secs)).map(|(x, _)| x)
+        };
+
+        // Once the queue is full, the oldest of the least important lines make way for new ones
+        voice.push(multi_kill(2), line.clone());
+        voice.push(Announcement::LastStock { player_idx: 1 }, line.clone());
+        voice.push(multi_kill(3), line.clone());
+        voice.push(victory(0), line.clone());
+
+        // The most important line goes first, and the next waits for the cooldown
+        assert_eq!(next(&mut voice, 0), Some(victory(0)));
+        assert_eq!(next(&mut voice, 0), None);
+        assert_eq!(
+            next(&mut voice, 1),
+            Some(Announcement::LastStock { player_idx: 1 })
+        );
+
+        // High priority lines are never dropped, even past the limit
+        for winner in 0..3 {
+            voice.push(victory(winner), line.clone());
+        }
+        for winner in 0..3 {
+            assert_eq!(next(&mut voice, 2 + winner as u64), Some(victory(winner)));
+        }
+        assert_eq!(next(&mut voice, 5), None);
+    }
+
+    #[test]
+    fn test_stopped_loop_is_not_tracked() {
+        let mut loops = LoopingSounds::default();
+
+        loops.insert(0, looping_sound());
+        loops.insert(1, looping_sound());
+        assert!(loops.remove(0).is_some());
+        assert!(loops.remove(1).is_some());
+
+        assert!(loops.is_empty());
+        assert!(loops.remove(0).is_none());
+    }
+
+    #[test]
+    fn test_replaced_loop_is_returned() {
+        let mut loops = LoopingSounds::default();
+        let first = looping_sound();
+
+        assert!(loops.insert(0, first.clone()).is_none());
+        let replaced = loops.insert(0, looping_sound()).unwrap();
+        assert_eq!(replaced.instance, first.instance);
+
+        loops.remove(0);
+        assert!(loops.is_empty());
+    }
+
+    #[test]
+    fn test_loop_stopped_on_the_frame_it_started_is_stopped_later() {
+        let mut loops = LoopingSounds::default();
+        let mut audio_instances = audio_instances();
+
+        // The loop's instance doesn't exist until the audio backend has started it
+        loops.insert(0, looping_sound());
+        let sound = loops.remove(0).unwrap();
+        loops.stop(sound, &mut audio_instances);
+        assert!(loops.is_empty());
+        assert_eq!(loops.stopping.0.len(), 1);
+
+        // So it keeps waiting to be stopped until it does
+        loops.stopping.retry(&mut audio_instances);
+        assert_eq!(loops.stopping.0.len(), 1);
+
+        loops.insert(1, looping_sound());
+        loops.stop_all(&mut audio_instances);
+        assert_eq!(loops.stopping.0.len(), 2);
+    }
+
+    #[test]
+    fn test_drained_loops_are_not_tracked() {
+        let mut loops = LoopingSounds::default();
+
+        loops.insert(0, looping_sound());
+        loops.insert(1, looping_sound());
+        assert_eq!(loops.drain().count(), 2);
+
+        assert!(loops.is_empty());
+    }
+
+    #[test]
+    fn test_least_important_oldest_voice_is_stolen() {
+        let mut voices = SoundVoices {
+            max_voices: 3,
+            ..default()
+        };
+        let instance = || Handle::weak(HandleId::random::<AudioInstance>());
+        let play = |voices: &mut SoundVoices, priority| {
+            let slot = voices.make_room(priority);
+            if slot != VoiceSlot::Full {
+                voices.push(instance(), priority);
+            }
+            slot
+        };
+
+        let [oldest, footstep] = [instance(), instance()];
+        voices.push(oldest.clone(), SoundPriority::Normal);
+        voices.push(footstep.clone(), SoundPriority::Low);
+        voices.push(instance(), SoundPriority::Low);
+
+        // The oldest footstep makes room, even though there is an older, more important sound
+        assert_eq!(
+            play(&mut voices, SoundPriority::High),
+            VoiceSlot::Steal(footstep)
+        );
+        assert!(matches!(
+            play(&mut voices, SoundPriority::Low),
+            VoiceSlot::Steal(_)
+        ));
+        assert!(matches!(
+            play(&mut voices, SoundPriority::Normal),
+            VoiceSlot::Steal(_)
+        ));
+
+        // Less important sounds are dropped, rather than cutting off more important ones
+        assert_eq!(play(&mut voices, SoundPriority::Low), VoiceSlot::Full);
+        assert_eq!(
+            play(&mut voices, SoundPriority::High),
+            VoiceSlot::Steal(oldest)
+        );
+        assert!(matches!(
+            play(&mut voices, SoundPriority::High),
+            VoiceSlot::Steal(_)
+        ));
+        assert_eq!(play(&mut voices, SoundPriority::Normal), VoiceSlot::Full);
+        assert_eq!(voices.voices.len(), 3);
+
+        // But important cues are never dropped
+        assert!(matches!(
+            play(&mut voices, SoundPriority::High),
+            VoiceSlot::Steal(_)
+        ));
+    }
+
+    #[test]
+    fn test_voices_that_havent_started_yet_are_playing() {
+        let mut voices = SoundVoices {
+            max_voices: 2,
+            ..default()
+        };
+        let mut audio_instances = audio_instances();
+
+        // Several sounds played on the same frame don't have instances yet, but still take voices
+        for _ in 0..2 {
+            assert_eq!(voices.make_room(SoundPriority::Normal), VoiceSlot::Free);
+            voices.push(
+                Handle::weak(HandleId::random::<AudioInstance>()),
+                SoundPriority::Normal,
+            );
+            voices.retain_playing(&audio_instances);
+        }
+        assert_eq!(voices.voices.len(), 2);
+
+        // And a stolen voice is stopped once its instance exists
+        let VoiceSlot::Steal(stolen) = voices.make_room(SoundPriority::High) else {
+            panic!("No voice was stolen");
+        };
+        voices.stop(stolen, &mut audio_instances);
+        assert_eq!(voices.stopping.0.len(), 1);
+        voices.stopping.retry(&mut audio_instances);
+        assert_eq!(voices.stopping.0.len(), 1);
+    }
+
+    #[test]
+    fn test_pending_stops_stop_started_instances_and_expire_the_rest() {
+        let mut stops = PendingStops::default();
+        let mut audio_instances = audio_instances();
+        let starts = Handle::weak(HandleId::random::<AudioInstance>());
+        let never_starts = Handle::weak(HandleId::random::<AudioInstance>());
+        stops.stop(starts.clone(), &mut audio_instances);
+        stops.stop(never_starts, &mut audio_instances);
+
+        // Neither instance is playing yet, so neither is stopped
+        let mut playing = HashSet::default();
+        let mut stopped = Vec::new();
+        let retry = |stops: &mut PendingStops, playing: &HashSet<_>, stopped: &mut Vec<_>| {
+            stops.retry_with(|instance| {
+                let exists = playing.contains(instance);
+                if exists {
+                    stopped.push(instance.clone());
+                }
+                exists
+            })
+        };
+        retry(&mut stops, &playing, &mut stopped);
+        assert!(stopped.is_empty());
+        assert_eq!(stops.0.len(), 2);
+
+        // The instance that starts playing is stopped, once
+        playing.insert(starts.clone());
+        retry(&mut stops, &playing, &mut stopped);
+        retry(&mut stops, &playing, &mut stopped);
+        assert_eq!(stopped, vec![starts]);
+        assert_eq!(stops.0.len(), 1);
+
+        // And the one that never does is given up on
+        for _ in 3..PendingStops::MAX_FRAMES - 1 {
+            retry(&mut stops, &playing, &mut stopped);
+        }
+        assert_eq!(stops.0.len(), 1);
+        retry(&mut stops, &playing, &mut stopped);
+        assert!(stops.is_empty());
+        assert_eq!(stopped.len(), 1);
+    }
+
+    #[test]
+    fn test_stems_come_in_on_the_bar() {
+        let stem = || PlayingStem {
+            instance: Handle::weak(HandleId::random::<AudioInstance>()),
+            intensity: 0.0,
+            audible: false,
+        };
+        let mut layers = MusicLayers {
+            bar_duration: 2.0,
+            stems: vec![
+                PlayingStem {
+                    intensity: 0.5,
+                    ..stem()
+                },
+                PlayingStem {
+                    intensity: 1.0,
+                    ..stem()
+                },
+            ],
+            ..default()
+        };
+        let audible =
+            |layers: &MusicLayers| layers.stems.iter().map(|x| x.audible).collect::<Vec<_>>();
+
+        // The first stem comes in as soon as the song starts
+        layers.intensity = 0.5;
+        assert_eq!(layers.update_stems(0.5).len(), 1);
+        assert_eq!(audible(&layers), [true, false]);
+
+        // But the second waits for the next bar
+        layers.intensity = 1.0;
+        assert!(layers.update_stems(1.9).is_empty());
+        assert_eq!(layers.update_stems(2.1).len(), 1);
+        assert_eq!(audible(&layers), [true, true]);
+
+        // Restarting fades everything out right away
+        layers.reset();
+        assert_eq!(layers.update_stems(2.2).len(), 2);
+        assert_eq!(audible(&layers), [false, false]);
+    }
+}
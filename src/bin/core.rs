@@ -93,7 +93,10 @@ fn load(
             None,
             None,
         ],
+        player_colors: default(),
+        mode: default(),
         meta: Arc::new(meta),
+        seed: jumpy_core::random::GlobalRng::DEFAULT_SEED,
     });
 
     commands.insert_resource(Session(session));
@@ -163,7 +166,7 @@ fn update_game(world: &mut World) {
 }
 
 #[derive(Resource, Default)]
-struct Snapshot(pub Option<bones::World>);
+struct Snapshot(pub Option<jumpy_core::session::SessionSnapshot>);
 
 fn snapshot_restore(
     mut snapshot: ResMut<Snapshot>,
@@ -179,8 +182,8 @@ fn snapshot_restore(
     }
 
     if keyboard.just_pressed(KeyCode::F10) {
-        if let Some(mut snapshot) = snapshot.0.clone() {
-            session.restore(&mut snapshot);
+        if let Some(snapshot) = &snapshot.0 {
+            session.restore(snapshot);
         }
     }
 }
@@ -192,22 +195,28 @@ fn play_sounds(audio: Res<bevy_kira_audio::Audio>, session: Option<Res<Session>>
     // Get the sound queue out of the world
     let queue = session
         .world
-        .run_initialized_system(move |mut audio_events: bones::ResMut<bones::AudioEvents>| {
-            Ok(audio_events.queue.drain(..).collect::<Vec<_>>())
-        })
+        .run_initialized_system(
+            move |mut audio_events: bones::ResMut<jumpy_core::audio::AudioEvents>| {
+                Ok(audio_events.queue.drain(..).collect::<Vec<_>>())
+            },
+        )
         .unwrap();
 
     // Play all the sounds in the queue
     for event in queue {
         match event {
-            bones::AudioEvent::PlaySound {
+            jumpy_core::audio::AudioEvent::PlaySound {
                 sound_source,
                 volume,
+                ..
             } => {
                 audio
                     .play(sound_source.get_bevy_handle_untyped().typed())
                     .with_volume(volume.into());
             }
+            // Looping sounds aren't needed for testing the core.
+            jumpy_core::audio::AudioEvent::PlayLoop { .. }
+            | jumpy_core::audio::AudioEvent::StopLoop { .. } => (),
         }
     }
 }
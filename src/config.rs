@@ -41,6 +41,60 @@ pub struct EngineConfig {
     /// `module=level` items.
     #[arg(short = 'l', long, default_value = DEFAULT_LOG_LEVEL)]
     pub log_level: String,
+
+    /// The number of simulation frames to run per second
+    ///
+    /// Replays are recorded with this rate, and only play back at the same one. The gameplay is
+    /// still tuned per frame, so no other rate than the default is supported yet.
+    #[arg(long, default_value_t = jumpy_core::FPS, value_parser = parse_simulation_rate)]
+    pub simulation_rate: f32,
+
+    /// Fill the default players after the first one with bots of this difficulty, from 0.0 to 1.0
+    ///
+    /// The default players are added to sessions started without any players, such as from the
+    /// editor.
+    #[arg(long)]
+    pub bot_difficulty: Option<f32>,
+
+    /// Play the kill that decides a match in real time, instead of in slow motion
+    #[arg(long)]
+    pub no_slow_motion: bool,
+
+    /// Don't replay the local player's death in slow motion while they wait to respawn
+    #[arg(long)]
+    pub no_kill_cam: bool,
+
+    /// Don't layer extra stems on top of the match music as the match heats up
+    #[arg(long)]
+    pub no_adaptive_music: bool,
+
+    /// Don't have the announcer call out the start, the big moments, and the winner of a match
+    #[arg(long)]
+    pub no_announcer: bool,
+
+    /// Hand local players whose gamepad is disconnected to a bot, instead of pausing the game
+    #[arg(long)]
+    pub bot_on_disconnect: bool,
+
+    /// The number of frames between the snapshots taken while playing back a replay
+    ///
+    /// Seeking in a replay starts from the closest snapshot, so shorter intervals make seeking
+    /// faster, but use more memory.
+    #[arg(long, default_value_t = crate::replay::ReplayPlayer::DEFAULT_KEYFRAME_INTERVAL)]
+    pub replay_keyframe_interval: u64,
+
+    /// Play the matches started from the menu as networked matches, over UDP, between the clients
+    /// at these addresses
+    ///
+    /// Every client must be started with the same comma-separated list of addresses, which
+    /// includes its own, and pick the same map and players. The first address is the host's.
+    #[arg(long, value_delimiter = ',')]
+    pub netplay_addresses: Vec<std::net::SocketAddr>,
+
+    /// The index of this client's address in the `netplay_addresses`, which is also the index of the
+    /// player that it controls
+    #[arg(long, default_value_t = 0)]
+    pub netplay_client: usize,
 }
 
 impl EngineConfig {
@@ -77,10 +131,32 @@ impl EngineConfig {
             game_asset: "default.game.yaml".into(),
             log_level: DEFAULT_LOG_LEVEL.into(),
             sync_test_check_distance: 0,
+            simulation_rate: jumpy_core::FPS,
+            bot_difficulty: None,
+            no_slow_motion: false,
+            no_kill_cam: false,
+            no_adaptive_music: false,
+            no_announcer: false,
+            bot_on_disconnect: false,
+            replay_keyframe_interval: crate::replay::ReplayPlayer::DEFAULT_KEYFRAME_INTERVAL,
+            netplay_addresses: Vec::new(),
+            netplay_client: 0,
         }
     }
 }
 
+/// Parse the `simulation_rate` argument, rejecting the rates that sessions can't be run at.
+fn parse_simulation_rate(arg: &str) -> Result<f32, String> {
+    let fps = arg.parse::<f32>().map_err(|e| e.to_string())?;
+    if !jumpy_core::session::SimulationRate::is_supported(fps) {
+        return Err(format!(
+            "only {} frames per second is supported, since the gameplay is tuned per frame",
+            jumpy_core::FPS
+        ));
+    }
+    Ok(fps)
+}
+
 #[cfg(any(target_arch = "wasm32", test))]
 /// Parse the query string as returned by `web_sys::window()?.location().search()?` and get a
 /// specific key out of it.
@@ -0,0 +1,361 @@
+//! Running game sessions without rendering or audio, for dedicated servers and automated testing.
+//!
+//! The headless app only loads the game metadata and simulates the sessions, with the player
+//! inputs coming from an [`InputSource`], such as a script or an AI, instead of the local players.
+//!
+//! The sessions are advanced by the same [`update_game`] system as in the rendered game, and the
+//! renderer never touches the simulation. The inputs are pressed and delayed by the
+//! [`NetSettings`] the same way as the local players' are, so a session given the same info and
+//! inputs plays out the same way bit-for-bit in both.
+
+use jumpy_core::input::PlayerInputs;
+
+use crate::{netplay::NetSettings, prelude::*};
+
+/// Plugin that simulates the game sessions, without the renderer, the audio, or the local player
+/// input collection of the [`JumpySessionPlugin`].
+pub struct JumpyHeadlessPlugin {
+    /// Whether to simulate the sessions in real time, like a server would, instead of simulating
+    /// one frame every time the app updates.
+    pub realtime: bool,
+}
+
+impl Plugin for JumpyHeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        let mut stage = SystemStage::single_threaded()
+            .with_system(update_headless_input)
+            .with_system(update_game)
+            .with_system(discard_session_sounds);
+        if self.realtime {
+            stage = stage.with_run_criteria(session_fixed_timestep);
+        }
+
+        app.add_plugin(jumpy_core::metadata::JumpyCoreAssetsPlugin)
+            .init_resource::<SessionRunState>()
+            .init_resource::<TimeScale>()
+            .init_resource::<RollbackSettings>()
+            .init_resource::<Rollback>()
+            .init_resource::<RenderInterpolation>()
+            .init_resource::<RollbackSmoothing>()
+            .init_resource::<NetSettings>()
+            .init_resource::<InputDelay>()
+            .init_resource::<HeadlessInput>()
+            .add_event::<SessionEvent>()
+            .add_system(load_headless_core_meta)
+            .add_stage_before(CoreStage::Update, SessionStage::Update, stage);
+    }
+}
+
+/// Create an app that runs the game sessions headless, loading the assets from `asset_folder`.
+///
+/// The app loads the `core_meta` file, and inserts it as the [`CoreMetaArc`] resource once it has
+/// loaded. Sessions may then be started with [`start_headless_session`].
+pub fn headless_app(asset_folder: &str, core_meta: &str, realtime: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(bevy::log::LogPlugin::default())
+        .add_plugin(AssetPlugin {
+            asset_folder: asset_folder.into(),
+            watch_for_changes: false,
+        })
+        // The metadata refers to images and sounds, which are never used without the renderer and
+        // the audio, but their handles still need an asset type.
+        .add_asset::<Image>()
+        .add_asset::<AudioSource>()
+        .add_plugin(JumpyHeadlessPlugin { realtime });
+
+    let handle = app.world.resource::<AssetServer>().load(core_meta);
+    app.insert_resource(HeadlessCoreMeta(handle));
+
+    app
+}
+
+/// Start the main headless game session, replacing it if it is already running.
+pub fn start_headless_session(world: &mut World, info: GameSessionInfo) {
+    world
+        .get_resource_or_insert_with(Sessions::default)
+        .insert(SessionId::MAIN, Session(GameSession::new(info)));
+    world.send_event(SessionEvent::Started(SessionId::MAIN));
+}
+
+/// Resource containing the handle to the core metadata loaded by the [`headless_app`].
+#[derive(Resource)]
+pub struct HeadlessCoreMeta(pub Handle<CoreMeta>);
+
+/// A source of player inputs for a headless game session, such as a script or an AI.
+///
+/// Only the held buttons need to be written, since the presses are worked out from them afterwards.
+pub trait InputSource: Send + Sync + 'static {
+    /// Write the controls of the players for the given simulation frame.
+    fn update(&mut self, frame: u64, inputs: &mut PlayerInputs);
+
+    /// Whether the controls have already been delayed, like the ones recorded from a session, so
+    /// that the input delay mustn't be applied to them again.
+    fn is_delayed(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(u64, &mut PlayerInputs) + Send + Sync + 'static> InputSource for F {
+    fn update(&mut self, frame: u64, inputs: &mut PlayerInputs) {
+        self(frame, inputs)
+    }
+}
+
+// Replays can be played back on a server, to check the results of a match
+impl InputSource for InputPlayback {
+    fn update(&mut self, frame: u64, inputs: &mut PlayerInputs) {
+        if let Some(controls) = self.controls(frame) {
+            for (input, control) in inputs.players.iter_mut().zip(controls) {
+                input.control = control.clone();
+            }
+        }
+    }
+
+    fn is_delayed(&self) -> bool {
+        true
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn update(&mut self, frame: u64, inputs: &mut PlayerInputs) {
+        self.apply(frame, inputs);
+    }
+}
+
+/// Resource containing where the player inputs of the main headless session come from.
+///
+/// Without a source, the players don't press anything.
+#[derive(Resource, Default)]
+pub struct HeadlessInput(pub Option<Box<dyn InputSource>>);
+
+impl HeadlessInput {
+    pub fn new(source: impl InputSource) -> Self {
+        Self(Some(Box::new(source)))
+    }
+}
+
+/// Insert the [`CoreMetaArc`] once the core metadata has loaded.
+fn load_headless_core_meta(
+    mut commands: Commands,
+    handle: Option<Res<HeadlessCoreMeta>>,
+    core_meta: Option<Res<CoreMetaArc>>,
+    core_assets: Res<Assets<CoreMeta>>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    if core_meta.is_some() {
+        return;
+    }
+    if let Some(meta) = core_assets.get(&handle.0) {
+        commands.insert_resource(CoreMetaArc(Arc::new(meta.clone())));
+    }
+}
+
+/// Give the main session the inputs from the [`HeadlessInput`], pressing and delaying them like
+/// the local players' inputs are.
+fn update_headless_input(
+    sessions: Option<ResMut<Sessions>>,
+    mut input: ResMut<HeadlessInput>,
+    rollback_settings: Res<RollbackSettings>,
+    mut rollback: ResMut<Rollback>,
+    net_settings: Res<NetSettings>,
+    mut input_delay: ResMut<InputDelay>,
+) {
+    let Some(mut sessions) = sessions else {
+        return;
+    };
+    let Some(session) = sessions.main_mut() else {
+        return;
+    };
+    let Some(source) = &mut input.0 else {
+        return;
+    };
+
+    let frame = session.frame();
+    let delay_frames = if source.is_delayed() {
+        0
+    } else {
+        net_settings.input_delay_frames
+    };
+    // With rollback, the controls are delayed when they are given to it instead
+    let hold_back = delay_frames > 0 && !rollback_settings.is_enabled();
+
+    session.update_input(|inputs| {
+        // The presses are worked out against the controls collected on the last frame, instead of
+        // the ones that were held back, like they are for the local players
+        if hold_back {
+            for (input, control) in inputs
+                .players
+                .iter_mut()
+                .zip(input_delay.live_controls(frame))
+            {
+                input.control = control.clone();
+            }
+        }
+        let previous = inputs
+            .players
+            .iter()
+            .map(|input| input.control.clone())
+            .collect::<Vec<_>>();
+        source.update(frame, inputs);
+        for (input, previous) in inputs.players.iter_mut().zip(&previous) {
+            input.control.detect_presses(previous);
+        }
+
+        if rollback_settings.is_enabled() {
+            for (i, input) in inputs.players.iter().enumerate() {
+                rollback.add_local_input(frame, delay_frames, i, input.control.clone());
+            }
+        } else if hold_back {
+            let controls = inputs
+                .players
+                .iter()
+                .map(|input| input.control.clone())
+                .collect();
+            let delayed = input_delay.delay(frame, delay_frames, controls);
+            for (input, control) in inputs.players.iter_mut().zip(delayed) {
+                input.control = control;
+            }
+        }
+    });
+}
+
+/// Throw away the sounds played by the sessions, so that they don't pile up without the audio.
+fn discard_session_sounds(sessions: Option<ResMut<Sessions>>) {
+    let Some(mut sessions) = sessions else {
+        return;
+    };
+    for (_id, session) in sessions.iter_mut() {
+        let audio_events = session.world.resource::<jumpy_core::audio::AudioEvents>();
+        audio_events.borrow_mut().queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jumpy_core::{input::PlayerControl, testing::test_session};
+
+    use super::*;
+
+    /// Create a world for the session systems to advance a test session in, with the players'
+    /// inputs delayed by `input_delay_frames`.
+    fn game_world(input_delay_frames: u64) -> World {
+        let mut session = test_session();
+        // The bevy world with the player metadata is kept in the test session's scratch world
+        let mut world = session.scratch_world.replace(default()).unwrap();
+        let mut sessions = Sessions::default();
+        sessions.insert(SessionId::MAIN, Session(session));
+
+        world.insert_resource(sessions);
+        world.insert_resource(NetSettings { input_delay_frames });
+        world.init_resource::<InputDelay>();
+        world.init_resource::<RollbackSettings>();
+        world.init_resource::<Rollback>();
+        world.init_resource::<RollbackSmoothing>();
+        world.init_resource::<RenderInterpolation>();
+        world.init_resource::<SessionRunState>();
+        world.init_resource::<Events<SessionEvent>>();
+        world
+    }
+
+    /// Play `frames` frames of a test session headless, with the inputs from `script`.
+    fn play_headless(
+        script: &ScriptedInput,
+        input_delay_frames: u64,
+        frames: u64,
+    ) -> jumpy_core::session::StateHashes {
+        let mut world = game_world(input_delay_frames);
+        world.insert_resource(HeadlessInput::new(script.clone()));
+        let mut stage = SystemStage::single_threaded()
+            .with_system(update_headless_input)
+            .with_system(update_game);
+        for _ in 0..frames {
+            stage.run(&mut world);
+        }
+
+        let session = world.resource::<Sessions>().main().unwrap();
+        assert_eq!(session.frame(), frames);
+        session.state_hashes()
+    }
+
+    #[test]
+    fn headless_sessions_play_out_like_rendered_ones() {
+        const FRAMES: u64 = 40;
+        const INPUT_DELAY_FRAMES: u64 = 3;
+
+        // The script only holds the buttons down, leaving the presses to be worked out
+        let script = ScriptedInput::default()
+            .move_in(0, 0, Vec2::X)
+            .press_jump(4, 0)
+            .release_jump(6, 0)
+            .press_jump(7, 0)
+            .move_in(20, 0, Vec2::NEG_X)
+            .release_jump(30, 0);
+
+        // The rendered game plays the script through the local players' input
+        let mut world = game_world(INPUT_DELAY_FRAMES);
+        world.insert_resource(script.clone());
+        world.init_resource::<CurrentEditorInput>();
+        world.init_resource::<LocalPlayer>();
+        world.init_resource::<InputSettings>();
+        world.init_resource::<SpectatorMode>();
+        world.init_resource::<InputRecorder>();
+        world.init_resource::<InputPlayback>();
+        world.init_resource::<EmoteWheels>();
+        world.insert_resource(CurrentState(InGameState::Playing));
+        let mut stage = SystemStage::single_threaded()
+            .with_system(update_input)
+            .with_system(update_game);
+        for _ in 0..FRAMES {
+            stage.run(&mut world);
+        }
+        let rendered = world.resource::<Sessions>().main().unwrap().state_hashes();
+
+        assert_eq!(play_headless(&script, INPUT_DELAY_FRAMES, FRAMES), rendered);
+        assert_ne!(play_headless(&script, 0, FRAMES), rendered);
+    }
+
+    #[test]
+    fn input_source_controls_the_main_session() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_event::<SessionEvent>()
+            .init_resource::<RollbackSettings>()
+            .init_resource::<Rollback>()
+            .init_resource::<NetSettings>()
+            .init_resource::<InputDelay>()
+            .insert_resource(HeadlessInput::new(
+                |frame: u64, inputs: &mut PlayerInputs| {
+                    inputs.players[0].control.move_direction = Vec2::X;
+                    inputs.players[1].control.jump_pressed = frame == 0;
+                },
+            ))
+            .add_system(update_headless_input);
+
+        start_headless_session(
+            &mut app.world,
+            GameSessionInfo {
+                meta: default(),
+                map_meta: default(),
+                player_info: default(),
+                player_colors: default(),
+                player_teams: default(),
+                friendly_fire: default(),
+                mode: default(),
+                seed: 42,
+                fps: jumpy_core::FPS,
+            },
+        );
+        app.update();
+
+        let mut sessions = app.world.resource_mut::<Sessions>();
+        let session = sessions.main_mut().unwrap();
+        session.update_input(|inputs| {
+            assert_eq!(inputs.players[0].control.move_direction, Vec2::X);
+            assert!(inputs.players[1].control.jump_pressed);
+            assert_eq!(inputs.players[2].control, PlayerControl::default());
+        });
+    }
+}
@@ -4,7 +4,18 @@ pub struct JumpyPlayerInputPlugin;
 
 impl Plugin for JumpyPlayerInputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(InputManagerPlugin::<PlayerAction>::default());
+        app.add_plugin(InputManagerPlugin::<PlayerAction>::default())
+            .init_resource::<InputSettings>()
+            .init_resource::<EmoteWheels>()
+            .insert_resource(ControllerAssignments {
+                on_disconnect: if ENGINE_CONFIG.bot_on_disconnect {
+                    ControllerDisconnect::Bot
+                } else {
+                    ControllerDisconnect::Pause
+                },
+                ..default()
+            })
+            .add_system(assign_controllers.run_if_resource_exists::<CoreMetaArc>());
     }
 }
 
@@ -12,12 +23,421 @@ impl Plugin for JumpyPlayerInputPlugin {
 #[derive(Debug, Copy, Clone, Actionlike, Deserialize, Eq, PartialEq, Hash)]
 pub enum PlayerAction {
     Move,
+    /// Aiming with a dedicated stick, instead of with the movement direction.
+    Aim,
     Jump,
     Shoot,
     Grab,
     Slide,
+    Block,
+    Taunt,
+    /// Opening the emote wheel, picking an emote with the movement input when it is released.
+    Emote,
+}
+
+/// Settings for how player input is collected.
+///
+/// The input settings are saved as part of the [`Settings`], and this resource is updated to match
+/// them.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct InputSettings {
+    /// Movement input shorter than this is ignored, so that analog stick drift doesn't move the
+    /// player.
+    pub move_dead_zone: f32,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            move_dead_zone: 0.15,
+        }
+    }
+}
+
+impl InputSettings {
+    /// Apply the radial movement dead zone to a movement direction.
+    pub fn apply_move_dead_zone(&self, direction: Vec2) -> Vec2 {
+        if direction.length() < self.move_dead_zone {
+            Vec2::ZERO
+        } else {
+            direction
+        }
+    }
+}
+
+/// Bevy resource containing the state of the emote wheel of each local player.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct EmoteWheels {
+    pub players: [EmoteWheel; MAX_PLAYERS],
+}
+
+/// The emote wheel of a player, which is open while they hold the emote button.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmoteWheel {
+    pub open: bool,
+    /// The emote that will be played when the emote button is released, if any.
+    pub selection: Option<usize>,
+}
+
+/// Get the emote on the emote wheel that the movement input points at, if any.
+///
+/// The emotes are laid out evenly around the wheel, clockwise from the top.
+pub fn emote_wheel_selection(direction: Vec2, emote_count: usize) -> Option<usize> {
+    if direction == Vec2::ZERO || emote_count == 0 {
+        return None;
+    }
+
+    // The clockwise angle from straight up, in turns
+    let turns = direction.x.atan2(direction.y) / std::f32::consts::TAU;
+    let slice = (turns * emote_count as f32 + 0.5).floor() as isize;
+    Some(slice.rem_euclid(emote_count as isize) as usize)
 }
 
 /// Bevy resource containing the editor action to perform for this frame.
 #[derive(Resource, Default, Deref, DerefMut)]
 pub struct CurrentEditorInput(Option<jumpy_core::input::EditorInput>);
+
+/// Bevy resource recording which player slot the local client controls.
+///
+/// The editor input is always given to this player. In local games every player is controlled on
+/// this machine, but in networked games we only write the input for the local player, and the
+/// input for the other players comes from the network.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct LocalPlayer {
+    /// The index of the local player's slot.
+    pub idx: usize,
+    /// Whether or not we are in a networked game.
+    pub networked: bool,
+}
+
+impl LocalPlayer {
+    /// Whether or not the local client should write the input for the given player.
+    pub fn controls_player(&self, player_idx: usize) -> bool {
+        !self.networked || player_idx == self.idx
+    }
+}
+
+/// Resource assigning the gamepads to the player slots in local co-op games.
+///
+/// While a local game session is running, pressing a button on a gamepad that isn't assigned to
+/// anyone adds a player to the session in the next free slot, playing with that gamepad. When an
+/// assigned gamepad is disconnected, the game is paused or the player is handed to a bot, and the
+/// player gets it back when the gamepad is reconnected.
+///
+/// The assignments are kept when the session is restarted, and dropped when it is stopped.
+/// Outside of a session, such as in the menus, each player slot uses the gamepad with the same
+/// index.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ControllerAssignments {
+    /// What happens to a player whose gamepad is disconnected mid-session.
+    pub on_disconnect: ControllerDisconnect,
+    /// The gamepad assigned to each player slot, if any.
+    slots: [Option<ControllerAssignment>; MAX_PLAYERS],
+    /// Whether the assignments are in effect, which they are while a local session is running.
+    active: bool,
+}
+
+/// A gamepad assigned to a player slot.
+#[derive(Clone, Debug)]
+pub struct ControllerAssignment {
+    pub gamepad: Gamepad,
+    /// Whether the gamepad is connected.
+    pub connected: bool,
+    /// The character that the player plays as, for adding them back to the session when it is
+    /// restarted.
+    pub selection: bones::Handle<PlayerMeta>,
+}
+
+/// What happens to a player whose gamepad is disconnected mid-session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControllerDisconnect {
+    /// Pause the game, until the gamepad is reconnected or the players decide to go on without it.
+    #[default]
+    Pause,
+    /// Let a bot play for the player until the gamepad is reconnected.
+    Bot,
+}
+
+impl ControllerAssignments {
+    /// The gamepad that the player slots without a gamepad listen to, which is never connected.
+    ///
+    /// An input map without a gamepad would take the input of every gamepad instead.
+    pub const UNASSIGNED_GAMEPAD: Gamepad = Gamepad { id: usize::MAX };
+
+    /// Get the gamepad assigned to the given player slot, if any.
+    pub fn get(&self, player_idx: usize) -> Option<&ControllerAssignment> {
+        self.slots[player_idx].as_ref()
+    }
+
+    /// Get the player slot that the given gamepad is assigned to, if any.
+    pub fn slot_of(&self, gamepad: Gamepad) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|x| x.as_ref().map_or(false, |x| x.gamepad == gamepad))
+    }
+
+    /// Get the gamepad that the given player slot's input collector should listen to.
+    pub fn gamepad(&self, player_idx: usize) -> Gamepad {
+        if !self.active {
+            return Gamepad { id: player_idx };
+        }
+        self.get(player_idx)
+            .map_or(Self::UNASSIGNED_GAMEPAD, |x| x.gamepad)
+    }
+
+    /// Put the assignments in effect for a new session, assigning the connected gamepads to the
+    /// slots with the same index, for the given players that are already in it.
+    pub fn start(
+        &mut self,
+        players: impl IntoIterator<Item = (usize, bones::Handle<PlayerMeta>)>,
+        is_connected: impl Fn(Gamepad) -> bool,
+    ) {
+        self.slots = default();
+        self.active = true;
+        for (player_idx, selection) in players {
+            let gamepad = Gamepad { id: player_idx };
+            if is_connected(gamepad) {
+                self.slots[player_idx] = Some(ControllerAssignment {
+                    gamepad,
+                    connected: true,
+                    selection,
+                });
+            }
+        }
+    }
+
+    /// Drop all of the assignments, going back to each slot using the gamepad with the same index.
+    pub fn stop(&mut self) {
+        self.slots = default();
+        self.active = false;
+    }
+
+    /// Assign an unassigned gamepad to the first free player slot, returning the slot.
+    ///
+    /// Slots that are already assigned are never free, and `is_free` decides about the rest, such
+    /// as whether a player is using the slot with the keyboard.
+    pub fn claim(
+        &mut self,
+        gamepad: Gamepad,
+        selection: impl FnOnce(usize) -> bones::Handle<PlayerMeta>,
+        is_free: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        if !self.active || self.slot_of(gamepad).is_some() {
+            return None;
+        }
+        let player_idx = (0..MAX_PLAYERS).find(|&i| self.slots[i].is_none() && is_free(i))?;
+        self.slots[player_idx] = Some(ControllerAssignment {
+            gamepad,
+            connected: true,
+            selection: selection(player_idx),
+        });
+        Some(player_idx)
+    }
+
+    /// Mark an assigned gamepad as connected or disconnected, returning the slot that it is
+    /// assigned to if it changed.
+    pub fn set_connected(&mut self, gamepad: Gamepad, connected: bool) -> Option<usize> {
+        let player_idx = self.slot_of(gamepad)?;
+        let assignment = self.slots[player_idx].as_mut().unwrap();
+        (assignment.connected != connected).then(|| {
+            assignment.connected = connected;
+            player_idx
+        })
+    }
+}
+
+/// Assign gamepads to the player slots as they are pressed, connected, and disconnected, and keep
+/// the player input collectors listening to the assigned gamepads.
+///
+/// Players that join this way are added to the main session with the [`SessionManager`]. Networked
+/// games have fixed player slots, so the gamepads aren't reassigned in them.
+fn assign_controllers(
+    mut assignments: ResMut<ControllerAssignments>,
+    // The session manager sends session events, so it can't be used while reading them
+    mut session_params: ParamSet<(SessionManager, EventReader<SessionEvent>)>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    local_player: Res<LocalPlayer>,
+    default_bots: Res<DefaultBots>,
+    in_game_state: Option<Res<CurrentState<InGameState>>>,
+    mut collectors: Query<(&PlayerInputCollector, &mut InputMap<PlayerAction>)>,
+) {
+    let restarted = session_params
+        .p1()
+        .iter()
+        .any(|x| matches!(x, SessionEvent::Restarted(SessionId::MAIN)));
+    let mut session_manager = session_params.p0();
+
+    let session_players = |session_manager: &SessionManager| {
+        session_manager.session().map(|session| {
+            let player_inputs = session.world.resource::<jumpy_core::input::PlayerInputs>();
+            let player_inputs = player_inputs.borrow();
+            player_inputs.players.clone()
+        })
+    };
+
+    let in_session = session_manager.session().is_some() && !local_player.networked;
+    if in_session && !assignments.active {
+        let players = session_players(&session_manager).unwrap_or_default();
+        assignments.start(
+            players
+                .into_iter()
+                .enumerate()
+                .filter(|(_, input)| input.active && input.bot.is_none())
+                .map(|(i, input)| (i, input.selected_player)),
+            |gamepad| gamepads.contains(gamepad),
+        );
+    } else if !in_session && assignments.active {
+        assignments.stop();
+    }
+
+    // Restarting the session only keeps the players that it was started with, so add back the ones
+    // that joined since
+    if restarted && assignments.active {
+        let players = session_players(&session_manager).unwrap_or_default();
+        let bot = default_bots.0.unwrap_or_default();
+        for player_idx in 0..MAX_PLAYERS {
+            let Some(assignment) = assignments.get(player_idx).cloned() else {
+                continue;
+            };
+            if !players[player_idx].active {
+                session_manager.add_player(player_idx, assignment.selection);
+            }
+            if !assignment.connected && assignments.on_disconnect == ControllerDisconnect::Bot {
+                set_bot(&mut session_manager, player_idx, Some(bot));
+            }
+        }
+    }
+
+    for event in gamepad_events.iter() {
+        let connected = match event.event_type {
+            GamepadEventType::Connected => true,
+            GamepadEventType::Disconnected => false,
+            _ => continue,
+        };
+        let Some(player_idx) = assignments.set_connected(event.gamepad, connected) else {
+            continue;
+        };
+        info!(
+            "Gamepad {} of player {player_idx} {}",
+            event.gamepad.id,
+            if connected {
+                "reconnected"
+            } else {
+                "disconnected"
+            }
+        );
+        match assignments.on_disconnect {
+            ControllerDisconnect::Pause => {
+                let playing = in_game_state
+                    .as_ref()
+                    .map_or(false, |x| x.0 == InGameState::Playing);
+                if !connected && playing {
+                    session_manager
+                        .commands
+                        .insert_resource(NextState(InGameState::Paused));
+                }
+            }
+            ControllerDisconnect::Bot => {
+                let bot = (!connected).then(|| default_bots.0.unwrap_or_default());
+                set_bot(&mut session_manager, player_idx, bot);
+            }
+        }
+    }
+
+    // Players can only join while the game is being played, not from the pause menu
+    let playing = in_game_state.map_or(false, |x| x.0 == InGameState::Playing);
+    let characters = session_manager.core_meta_arc.players.clone();
+    let players = session_players(&session_manager).filter(|_| playing && !characters.is_empty());
+    if let Some(players) = players {
+        for button in gamepad_buttons.get_just_pressed() {
+            let Some(player_idx) = assignments.claim(
+                button.gamepad,
+                |i| characters[i % characters.len()].clone(),
+                |i| !players[i].active,
+            ) else {
+                continue;
+            };
+            info!(
+                "Gamepad {} joined as player {player_idx}",
+                button.gamepad.id
+            );
+            let selection = assignments.get(player_idx).unwrap().selection.clone();
+            session_manager.add_player(player_idx, selection);
+        }
+    }
+
+    if assignments.is_changed() {
+        for (player_idx, mut input_map) in &mut collectors {
+            let gamepad = assignments.gamepad(player_idx.0);
+            if input_map.gamepad() != Some(gamepad) {
+                input_map.set_gamepad(gamepad);
+            }
+        }
+    }
+}
+
+/// Hand a player in the main session to a bot of the given difficulty, or back to the player.
+fn set_bot(
+    session_manager: &mut SessionManager,
+    player_idx: usize,
+    bot: Option<jumpy_core::bot::BotDifficulty>,
+) {
+    if let Some(session) = session_manager.session_mut() {
+        session.update_input(|inputs| inputs.players[player_idx].bot = bot);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn emote_wheel_is_laid_out_clockwise_from_the_top() {
+        assert_eq!(emote_wheel_selection(Vec2::Y, 4), Some(0));
+        assert_eq!(emote_wheel_selection(Vec2::X, 4), Some(1));
+        assert_eq!(emote_wheel_selection(Vec2::NEG_Y, 4), Some(2));
+        assert_eq!(emote_wheel_selection(Vec2::NEG_X, 4), Some(3));
+        // Slices are centered on their direction
+        assert_eq!(emote_wheel_selection(vec2(-0.3, 1.0), 4), Some(0));
+        assert_eq!(emote_wheel_selection(vec2(1.0, 0.9), 4), Some(1));
+        assert_eq!(emote_wheel_selection(Vec2::ZERO, 4), None);
+        assert_eq!(emote_wheel_selection(Vec2::Y, 0), None);
+    }
+
+    #[test]
+    fn gamepads_claim_free_player_slots() {
+        let mut assignments = ControllerAssignments::default();
+        assert_eq!(assignments.gamepad(2), Gamepad { id: 2 });
+
+        // Player 0 started the session on gamepad 0, and player 1 on the keyboard
+        assignments.start([(0, default()), (1, default())], |gamepad| gamepad.id == 0);
+        assert_eq!(assignments.gamepad(0), Gamepad { id: 0 });
+        assert_eq!(
+            assignments.gamepad(1),
+            ControllerAssignments::UNASSIGNED_GAMEPAD
+        );
+
+        // A new gamepad skips the slot that the keyboard player is in
+        let claim = |assignments: &mut ControllerAssignments, id| {
+            assignments.claim(Gamepad { id }, |_| default(), |i| i > 1)
+        };
+        assert_eq!(claim(&mut assignments, 5), Some(2));
+        assert_eq!(claim(&mut assignments, 5), None);
+        assert_eq!(claim(&mut assignments, 0), None);
+        assert_eq!(assignments.gamepad(2), Gamepad { id: 5 });
+
+        // Disconnecting keeps the slot, so that the gamepad gets it back when it reconnects
+        assert_eq!(assignments.set_connected(Gamepad { id: 5 }, false), Some(2));
+        assert_eq!(assignments.set_connected(Gamepad { id: 5 }, false), None);
+        assert_eq!(claim(&mut assignments, 6), Some(3));
+        assert_eq!(assignments.set_connected(Gamepad { id: 5 }, true), Some(2));
+        assert_eq!(claim(&mut assignments, 7), None);
+
+        assignments.stop();
+        assert_eq!(assignments.slot_of(Gamepad { id: 5 }), None);
+        assert_eq!(claim(&mut assignments, 5), None);
+    }
+}
@@ -0,0 +1,520 @@
+//! Slow-motion replays of the local player's death, shown while they wait to respawn.
+//!
+//! The kill cam records the controls that the main session simulates every frame with, along with
+//! a snapshot of it every few frames. When the main session is rolled back, only the frames that
+//! the [`Rollback`] has confirmed are recorded, from the snapshots and controls that it kept for
+//! them, so that a clip never shows a frame that was simulated with mispredicted inputs. When the
+//! local player is killed, the separate
+//! [`SessionId::KILL_CAM`] session is restored to the snapshot before the start of the clip, and
+//! simulated on from there with the recorded controls, one frame at a time as the clip plays. It is
+//! rendered in place of the main session, and never receives any input of it's own, so it can't
+//! have any effect on the main session, or on what the other players in a networked game see.
+
+use std::collections::VecDeque;
+
+use jumpy_core::session::SessionSnapshot;
+
+use crate::prelude::*;
+
+pub struct JumpyKillCamPlugin;
+
+impl Plugin for JumpyKillCamPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KillCam {
+            enabled: !ENGINE_CONFIG.no_kill_cam,
+            ..default()
+        });
+    }
+}
+
+/// Resource that records the main session, and plays back the local player's death in slow motion.
+///
+/// The kill cam is only played when there is a single local player to show it to. In local games
+/// with several players sharing the screen, or while spectating, nothing is recorded.
+#[derive(Resource)]
+pub struct KillCam {
+    /// Whether to record and play the kill cam at all.
+    pub enabled: bool,
+    /// The number of seconds before the kill that the clip starts on.
+    ///
+    /// Early in a match there may not be this much history yet, in which case the clip starts on
+    /// the first frame that was recorded.
+    pub clip_seconds: f32,
+    /// The number of seconds after the kill that the clip keeps playing for.
+    pub after_seconds: f32,
+    /// How fast the clip plays, relative to the simulation.
+    pub speed: f32,
+    /// The number of frames between each snapshot of the main session.
+    ///
+    /// Every snapshot holds a copy of the whole session world, so a shorter interval uses more
+    /// memory, but leaves less frames to simulate when a clip starts.
+    pub keyframe_interval: u64,
+    /// The state of the main session before some of the latest frames, oldest first.
+    keyframes: VecDeque<(u64, SessionSnapshot)>,
+    /// The controls that each of the latest frames was simulated with, oldest first.
+    controls: VecDeque<(u64, Vec<PlayerControl>)>,
+    /// The latest frame that the recorded history can bring the kill cam session to.
+    recorded_frame: Option<u64>,
+    /// Whether each player's character had been killed on the last recorded frame.
+    killed: [bool; MAX_PLAYERS],
+    /// The clip that is playing, if any.
+    clip: Option<KillCamClip>,
+}
+
+/// A kill cam clip that is being played.
+#[derive(Clone, Copy, Debug)]
+struct KillCamClip {
+    /// The player whose death is being shown.
+    player_idx: usize,
+    /// The first frame of the clip.
+    start: u64,
+    /// The last frame of the clip.
+    end: u64,
+    /// The number of frames since the start of the clip that have been shown.
+    position: f32,
+    /// Whether the kill cam session has been restored to one of the keyframes for this clip yet.
+    restored: bool,
+}
+
+impl Default for KillCam {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            clip_seconds: 1.0,
+            after_seconds: 1.0 / 3.0,
+            speed: 0.4,
+            keyframe_interval: 15,
+            keyframes: default(),
+            controls: default(),
+            recorded_frame: None,
+            killed: default(),
+            clip: None,
+        }
+    }
+}
+
+impl KillCam {
+    /// Whether a clip is playing.
+    pub fn is_playing(&self) -> bool {
+        self.clip.is_some()
+    }
+
+    /// Stop the clip early, if one is playing.
+    pub fn stop(&mut self) {
+        self.clip = None;
+    }
+
+    /// Forget the recorded history and stop the clip.
+    pub fn reset(&mut self) {
+        self.keyframes.clear();
+        self.controls.clear();
+        self.recorded_frame = None;
+        self.killed = default();
+        self.clip = None;
+    }
+
+    /// Record the main session after it simulated a frame, and advance the clip, returning the
+    /// frame that the kill cam should show, if a clip is playing.
+    ///
+    /// `rollback` is the [`Rollback`] that the main session is simulated with, if it is rolled back,
+    /// in which case only the frames that it confirmed are recorded. `killed` is whether each
+    /// player's character has been killed, see
+    /// [`Session::killed_players`], and `watching` is the local player that the kill cam is shown
+    /// to, if there is one. A clip starts when that player is killed, and stops once it is over,
+    /// or the player has respawned.
+    pub fn update(
+        &mut self,
+        session: &GameSession,
+        rollback: Option<&Rollback>,
+        killed: [Option<bool>; MAX_PLAYERS],
+        watching: Option<usize>,
+    ) -> Option<u64> {
+        let Some(watching) = watching.filter(|_| self.enabled) else {
+            self.reset();
+            return None;
+        };
+        let frame = session.frame();
+        let seconds_to_frames = |seconds: f32| (seconds * session.info.fps).round() as u64;
+
+        // The session was restarted or rewound, so the history doesn't apply to it anymore
+        if self
+            .recorded_frame
+            .map_or(false, |recorded| recorded > frame)
+        {
+            self.reset();
+        }
+        if let Some(rollback) = rollback {
+            for (confirmed, snapshot, controls) in rollback.confirmed_frames() {
+                if self
+                    .recorded_frame
+                    .map_or(false, |recorded| recorded != confirmed)
+                {
+                    self.reset();
+                }
+                self.record_keyframe(confirmed, || snapshot.clone());
+                self.controls.push_back((confirmed, controls.to_vec()));
+                self.recorded_frame = Some(confirmed + 1);
+            }
+        } else {
+            // Without rollback, the frame that was just simulated is already final
+            match self.recorded_frame {
+                Some(recorded) if recorded + 1 != frame => self.reset(),
+                Some(recorded) => {
+                    // The player inputs are left as they were for the frame that was just simulated
+                    let player_inputs = session.world.resource::<jumpy_core::input::PlayerInputs>();
+                    let controls = player_inputs
+                        .borrow()
+                        .players
+                        .iter()
+                        .map(|input| input.control.clone())
+                        .collect();
+                    self.controls.push_back((recorded, controls));
+                }
+                None => (),
+            }
+            self.record_keyframe(frame, || session.snapshot());
+            self.recorded_frame = Some(frame);
+        }
+        let recorded = self.recorded_frame.unwrap_or(frame);
+
+        let was_killed = std::mem::replace(&mut self.killed, killed.map(|x| x == Some(true)));
+        if self.clip.is_none() && self.killed[watching] && !was_killed[watching] {
+            let first = self.keyframes.front().map_or(frame, |(first, _)| *first);
+            self.clip = Some(KillCamClip {
+                player_idx: watching,
+                start: frame
+                    .saturating_sub(seconds_to_frames(self.clip_seconds))
+                    .max(first)
+                    .min(recorded),
+                end: frame + seconds_to_frames(self.after_seconds),
+                position: 0.0,
+                restored: false,
+            });
+        }
+        if let Some(clip) = self.clip {
+            if killed[clip.player_idx] == Some(false) || clip.player_idx != watching {
+                self.clip = None;
+            }
+        }
+
+        // Only keep the frames that may still have to be shown, starting from the keyframe before
+        let keep_from = match &self.clip {
+            Some(clip) => clip.start + clip.position as u64,
+            None => frame.saturating_sub(seconds_to_frames(self.clip_seconds)),
+        };
+        while self
+            .keyframes
+            .get(1)
+            .map_or(false, |(x, _)| *x <= keep_from)
+        {
+            self.keyframes.pop_front();
+        }
+        let oldest = self.keyframes.front().map_or(frame, |(x, _)| *x);
+        while self.controls.front().map_or(false, |(x, _)| *x < oldest) {
+            self.controls.pop_front();
+        }
+
+        let clip = self.clip.as_mut()?;
+        let shown = (clip.start + clip.position as u64).min(recorded);
+        if shown > clip.end {
+            self.clip = None;
+            return None;
+        }
+        clip.position += self.speed;
+
+        Some(shown)
+    }
+
+    /// Keep a snapshot of the main session from before `frame`, if it is time for a keyframe.
+    fn record_keyframe(&mut self, frame: u64, snapshot: impl FnOnce() -> SessionSnapshot) {
+        if self.keyframes.is_empty() || frame % self.keyframe_interval.max(1) == 0 {
+            self.keyframes.push_back((frame, snapshot()));
+        }
+    }
+
+    /// Bring the kill cam session to `frame` of the playing clip, using `step` to simulate each
+    /// frame after setting the recorded controls for it.
+    ///
+    /// The session is restored to the closest keyframe before `frame`, unless it is already
+    /// between that keyframe and `frame`. Returns `false` if the frame isn't recorded anymore.
+    pub fn show(
+        &mut self,
+        session: &mut GameSession,
+        frame: u64,
+        mut step: impl FnMut(&mut GameSession),
+    ) -> bool {
+        let Some(clip) = &mut self.clip else {
+            return false;
+        };
+        let Some((keyframe, snapshot)) = self.keyframes.iter().rev().find(|(x, _)| *x <= frame)
+        else {
+            return false;
+        };
+
+        let current = session.frame();
+        if !clip.restored || current < *keyframe || current > frame {
+            session.restore(snapshot);
+            clip.restored = true;
+        }
+
+        while session.frame() < frame {
+            let simulating = session.frame();
+            let Some((_, controls)) = self.controls.iter().find(|(x, _)| *x == simulating) else {
+                return false;
+            };
+            session.update_input(|inputs| {
+                for (input, control) in inputs.players.iter_mut().zip(controls) {
+                    input.control = control.clone();
+                }
+            });
+            step(session);
+        }
+
+        true
+    }
+}
+
+/// Record the main session for the [`KillCam`], and show the kill cam session in it's place while a
+/// clip is playing.
+///
+/// This must run after the main session has been advanced.
+pub fn update_kill_cam(world: &mut World) {
+    let Some(mut kill_cam) = world.remove_resource::<KillCam>() else {
+        return;
+    };
+    if let Some(mut sessions) = world.remove_resource::<Sessions>() {
+        show_kill_cam(world, &mut sessions, &mut kill_cam);
+        world.insert_resource(sessions);
+    }
+    world.insert_resource(kill_cam);
+}
+
+/// Update the [`KillCam`] from the main session, and simulate the kill cam session up to the frame
+/// that it shows.
+fn show_kill_cam(world: &mut World, sessions: &mut Sessions, kill_cam: &mut KillCam) {
+    let Some(main) = sessions.main() else {
+        return;
+    };
+
+    // Only show the kill cam to a single player, who isn't watching the match play out anyway
+    let local_player = world.resource::<LocalPlayer>();
+    let watching = if world.resource::<SpectatorMode>().is_spectating()
+        || world
+            .get_resource::<InputPlayback>()
+            .map_or(false, |x| x.is_playing())
+        || main.match_result().is_some()
+    {
+        None
+    } else if local_player.networked {
+        Some(local_player.idx)
+    } else {
+        let player_inputs = main.world.resource::<jumpy_core::input::PlayerInputs>();
+        let player_inputs = player_inputs.borrow();
+        let mut humans = (0..MAX_PLAYERS).filter(|&i| {
+            let input = &player_inputs.players[i];
+            input.active && input.bot.is_none()
+        });
+        match (humans.next(), humans.next()) {
+            (Some(player_idx), None) => Some(player_idx),
+            _ => None,
+        }
+    };
+
+    // The kill that decides the match has it's own slow motion
+    if main.decisive_kill().is_some() {
+        kill_cam.stop();
+    }
+
+    let killed = main.killed_players();
+    let info = main.info.clone();
+    let rollback = world
+        .resource::<RollbackSettings>()
+        .is_enabled()
+        .then(|| world.get_resource::<Rollback>())
+        .flatten();
+    let shown = kill_cam.update(main, rollback, killed, watching);
+
+    if let Some(frame) = shown {
+        if sessions.get_mut(SessionId::KILL_CAM).is_none() {
+            sessions.insert(SessionId::KILL_CAM, Session(GameSession::new(info)));
+            // The clip is simulated here, with the recorded controls, not with the other sessions
+            sessions.set_paused(SessionId::KILL_CAM, true);
+            sessions.set_rendered(SessionId::KILL_CAM);
+        }
+        let session = sessions.get_mut(SessionId::KILL_CAM).unwrap();
+        if kill_cam.show(session, frame, |session| session.advance(world)) {
+            return;
+        }
+        kill_cam.stop();
+    }
+
+    if sessions.remove(SessionId::KILL_CAM).is_some() && sessions.rendered() == SessionId::KILL_CAM
+    {
+        sessions.set_rendered(SessionId::MAIN);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jumpy_core::{
+        player::PlayerIdx,
+        testing::{step, test_session},
+    };
+
+    use super::*;
+
+    /// Update the kill cam, and bring the kill cam session to the frame that it shows.
+    fn shown_frame(
+        kill_cam: &mut KillCam,
+        clip_session: &mut GameSession,
+        session: &GameSession,
+        killed: [Option<bool>; MAX_PLAYERS],
+    ) -> Option<u64> {
+        let frame = kill_cam.update(session, None, killed, Some(1))?;
+        assert!(kill_cam.show(clip_session, frame, step));
+        assert_eq!(clip_session.frame(), frame);
+        Some(frame)
+    }
+
+    /// Get where the first player is along the x axis.
+    fn position(session: &GameSession) -> f32 {
+        session
+            .world
+            .run_initialized_system(
+                |entities: bones::Res<bones::Entities>,
+                 player_indexes: bones::Comp<PlayerIdx>,
+                 transforms: bones::Comp<bones::Transform>| {
+                    Ok(entities
+                        .iter_with((&player_indexes, &transforms))
+                        .find(|(_ent, (idx, _))| idx.0 == 0)
+                        .map_or(0.0, |(_ent, (_, transform))| transform.translation.x))
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn kill_cam_replays_what_history_there_is() {
+        let mut kill_cam = KillCam {
+            clip_seconds: 1.0,
+            after_seconds: 5.0 / jumpy_core::FPS,
+            speed: 0.5,
+            keyframe_interval: 4,
+            ..default()
+        };
+        let alive = [Some(false); MAX_PLAYERS];
+        let mut killed = alive;
+        killed[1] = Some(true);
+
+        // Player 1 is killed before a full clip's worth of frames has been recorded
+        let mut session = test_session();
+        let mut clip_session = test_session();
+        let mut positions = Vec::new();
+        for frame in 0..10 {
+            positions.push(position(&session));
+            assert!(kill_cam.update(&session, None, alive, Some(1)).is_none());
+            let direction = if frame % 3 == 0 { Vec2::X } else { Vec2::ZERO };
+            session.update_input(|inputs| inputs.players[0].control.move_direction = direction);
+            step(&mut session);
+        }
+        let mut shown = Vec::new();
+        let mut shown_positions = Vec::new();
+        while let Some(frame) = shown_frame(&mut kill_cam, &mut clip_session, &session, killed) {
+            shown.push(frame);
+            shown_positions.push(position(&clip_session));
+            positions.push(position(&session));
+            session.update_input(|inputs| inputs.players[0].control.move_direction = Vec2::ZERO);
+            step(&mut session);
+        }
+
+        // So the clip starts from the first frame, and plays in slow motion past the kill
+        assert_eq!(shown.first(), Some(&0));
+        assert_eq!(shown.last(), Some(&15));
+        assert_eq!(shown.len(), 32);
+        assert!(!kill_cam.is_playing());
+
+        // Simulating the clip with the recorded controls plays out the same way as the main session
+        for (frame, shown_position) in shown.iter().zip(&shown_positions) {
+            assert_eq!(*shown_position, positions[*frame as usize]);
+        }
+        assert_ne!(shown_positions.first(), shown_positions.last());
+
+        // Only a snapshot every few frames is kept
+        assert!(kill_cam.keyframes.iter().all(|(frame, _)| frame % 4 == 0));
+
+        // Other players' deaths aren't shown, and respawning stops the clip
+        let mut killed = alive;
+        killed[2] = Some(true);
+        assert!(kill_cam.update(&session, None, killed, Some(1)).is_none());
+        step(&mut session);
+        killed[1] = Some(true);
+        assert!(kill_cam.update(&session, None, killed, Some(1)).is_some());
+        step(&mut session);
+        assert!(kill_cam.update(&session, None, alive, Some(1)).is_none());
+    }
+
+    #[test]
+    fn kill_cam_only_replays_confirmed_frames() {
+        const MAX_ROLLBACK_FRAMES: usize = 8;
+        const INPUT_LATENESS: u64 = 4;
+        let mut kill_cam = KillCam {
+            clip_seconds: 3.0 / jumpy_core::FPS,
+            after_seconds: 5.0 / jumpy_core::FPS,
+            speed: 1.0,
+            keyframe_interval: 4,
+            ..default()
+        };
+        let alive = [Some(false); MAX_PLAYERS];
+        let mut killed = alive;
+        killed[1] = Some(true);
+        let control = |frame: u64| PlayerControl {
+            move_direction: if frame % 3 == 0 { Vec2::X } else { Vec2::ZERO },
+            ..default()
+        };
+
+        // Where the first player really is on each frame
+        let mut reference = test_session();
+        let mut positions = Vec::new();
+        for frame in 0..60 {
+            positions.push(position(&reference));
+            reference.update_input(|inputs| inputs.players[0].control = control(frame));
+            step(&mut reference);
+        }
+
+        // The first player's inputs arrive a few frames late, so the main session mispredicts them
+        let mut session = test_session();
+        let mut rollback = Rollback::default();
+        let mut predicted = Vec::new();
+        let mut advance = |session: &mut GameSession, rollback: &mut Rollback| {
+            predicted.push(position(session));
+            if let Some(late) = session.frame().checked_sub(INPUT_LATENESS) {
+                assert!(rollback.add_remote_input(late, 0, control(late)));
+            }
+            rollback.advance(session, MAX_ROLLBACK_FRAMES, step);
+        };
+        for _ in 0..20 {
+            advance(&mut session, &mut rollback);
+            assert!(kill_cam
+                .update(&session, Some(&rollback), alive, Some(1))
+                .is_none());
+        }
+
+        let mut clip_session = test_session();
+        let mut shown = Vec::new();
+        while let Some(frame) = kill_cam.update(&session, Some(&rollback), killed, Some(1)) {
+            // Only the frames that can't be rolled back anymore are shown
+            assert!(frame + MAX_ROLLBACK_FRAMES as u64 <= session.frame());
+            assert!(kill_cam.show(&mut clip_session, frame, step));
+            assert_eq!(position(&clip_session), positions[frame as usize]);
+            shown.push(frame);
+            advance(&mut session, &mut rollback);
+        }
+
+        // With the controls that were confirmed for them, not the mispredicted ones
+        assert_eq!(shown.first(), Some(&12));
+        assert_eq!(shown.last(), Some(&25));
+        assert!(shown
+            .iter()
+            .any(|frame| predicted[*frame as usize] != positions[*frame as usize]));
+    }
+}
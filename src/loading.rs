@@ -1,6 +1,5 @@
-use bevy::ecs::system::SystemParam;
+use bevy::{asset::LoadState, ecs::system::SystemParam};
 use bevy_egui::{egui, EguiContext};
-use bevy_fluent::Locale;
 use leafwing_input_manager::{
     axislike::{AxisType, SingleAxis},
     prelude::InputMap,
@@ -22,6 +21,7 @@ impl Plugin for JumpyLoadingPlugin {
         // Configure hot reload
         if ENGINE_CONFIG.hot_reload {
             app.add_system_to_stage(CoreStage::Last, hot_reload_game)
+                .add_system_to_stage(CoreStage::Last, hot_reload_core_meta)
                 .add_system_set_to_stage(
                     CoreStage::Last,
                     ConditionSet::new().run_in_state(EngineState::InGame).into(),
@@ -40,6 +40,7 @@ fn core_assets_loaded(
     core_assets: Res<Assets<CoreMeta>>,
     player_assets: Res<Assets<PlayerMeta>>,
     atlas_assets: Res<Assets<TextureAtlas>>,
+    asset_server: Res<AssetServer>,
 ) -> bool {
     // The game asset
     let Some(game) = game_assets.get(&game_handle) else {
@@ -51,7 +52,12 @@ fn core_assets_loaded(
     };
     // The player assets
     for player in &core.players {
-        let Some(player) = player_assets.get(&player.get_bevy_handle()) else {
+        let handle = player.get_bevy_handle();
+        // Stop waiting if the player failed to load, so that the loader can report it
+        if asset_server.get_load_state(handle.id) == LoadState::Failed {
+            return true;
+        }
+        let Some(player) = player_assets.get(&handle) else {
             return false;
         };
 
@@ -94,6 +100,7 @@ pub struct GameLoader<'w, 's> {
     storage: ResMut<'w, Storage>,
     player_assets: ResMut<'w, Assets<PlayerMeta>>,
     texture_atlas_assets: Res<'w, Assets<TextureAtlas>>,
+    asset_server: Res<'w, AssetServer>,
 }
 
 impl<'w, 's> GameLoader<'w, 's> {
@@ -123,6 +130,18 @@ impl<'w, 's> GameLoader<'w, 's> {
         let game = game_assets.get_mut(&game_handle).unwrap();
         let core = core_assets.get_mut(&game.core).unwrap();
 
+        // Fail loudly at startup if the metadata references anything that doesn't exist, instead of
+        // in the middle of a match.
+        if let Err(e) =
+            jumpy_core::metadata::validate_core_meta(core, &self.player_assets, &self.asset_server)
+        {
+            if is_hot_reload {
+                error!("{e}");
+            } else {
+                panic!("{e}");
+            }
+        }
+
         // Hot reload preparation
         if is_hot_reload {
             // Since we are modifying the game asset, which will trigger another asset changed
@@ -147,9 +166,13 @@ impl<'w, 's> GameLoader<'w, 's> {
             egui_ctx.ctx_mut().set_fonts(egui_fonts.clone());
             commands.insert_resource(EguiFontDefinitions(egui_fonts));
 
+            // Load the settings, and write them back, so that settings that were missing from
+            // storage, or couldn't be read, are replaced with valid ones
+            let settings = Settings::get_stored_or_default(game, &mut storage);
+            storage.set(Settings::STORAGE_KEY, &settings);
+            storage.save();
+
             // Spawn player input collectors.
-            let settings = storage.get(Settings::STORAGE_KEY);
-            let settings = settings.as_ref().unwrap_or(&game.default_settings);
             for player in 0..MAX_PLAYERS {
                 commands.spawn((
                     Name::new(format!("Player Input Collector {player}")),
@@ -161,17 +184,14 @@ impl<'w, 's> GameLoader<'w, 's> {
                 ));
             }
 
+            // Set the locale resource
+            commands.insert_resource(settings_locale(&settings, &game.translations));
+            commands.insert_resource(settings);
+
             // Transition to the main menu when we are done
             commands.insert_resource(NextState(EngineState::MainMenu));
         }
 
-        // Set the locale resource
-        let translations = &game.translations;
-        commands.insert_resource(
-            Locale::new(translations.detected_locale.clone())
-                .with_default(translations.default_locale.clone()),
-        );
-
         let mut visuals = egui::Visuals::dark();
         visuals.widgets = game.ui_theme.widgets.get_egui_widget_style();
         visuals.window_fill = game.ui_theme.debug_window_fill.into_egui();
@@ -277,6 +297,124 @@ impl<'w, 's> GameLoader<'w, 's> {
     }
 }
 
+/// Reasons that a hot reloaded [`CoreMeta`] may be rejected.
+#[derive(thiserror::Error, Debug)]
+pub enum CoreMetaReloadError {
+    #[error("The list of {0} changed, which needs a restart to take effect")]
+    StructuralChange(&'static str),
+    #[error("Invalid value for `{field}`: {reason}")]
+    InvalidValue {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+/// Check that the `new` core metadata can replace the `current` metadata in running game sessions.
+///
+/// Only tuning values may be changed while the game is running. Adding or removing players, maps,
+/// or elements needs a restart.
+pub fn validate_core_meta_reload(
+    current: &CoreMeta,
+    new: &CoreMeta,
+) -> Result<(), CoreMetaReloadError> {
+    use CoreMetaReloadError::*;
+
+    if new.players.len() != current.players.len() {
+        return Err(StructuralChange("players"));
+    }
+    if new.stable_maps.len() != current.stable_maps.len()
+        || new.experimental_maps.len() != current.experimental_maps.len()
+    {
+        return Err(StructuralChange("maps"));
+    }
+    if new.map_elements.len() != current.map_elements.len() {
+        return Err(StructuralChange("map elements"));
+    }
+
+    let checks = [
+        (
+            new.camera.default_height > 0.0,
+            "camera.default_height",
+            "must be positive",
+        ),
+        (
+            new.camera.min_height <= new.camera.max_height,
+            "camera.min_height",
+            "must not be more than camera.max_height",
+        ),
+        (
+            new.camera.merge_distance <= new.camera.split_distance,
+            "camera.merge_distance",
+            "must not be more than camera.split_distance",
+        ),
+        (
+            new.physics.terminal_velocity > 0.0,
+            "physics.terminal_velocity",
+            "must be positive",
+        ),
+        (
+            new.hitstun.max_health > 0.0,
+            "hitstun.max_health",
+            "must be positive",
+        ),
+        (
+            (0.0..=1.0).contains(&new.hitstun.knockback_decay),
+            "hitstun.knockback_decay",
+            "must be between 0 and 1",
+        ),
+    ];
+    for (valid, field, reason) in checks {
+        if !valid {
+            return Err(InvalidValue { field, reason });
+        }
+    }
+
+    Ok(())
+}
+
+/// System to hot reload changes to the core metadata into the running game sessions.
+///
+/// Changes that fail [`validate_core_meta_reload`] are logged and ignored, leaving the current
+/// metadata in place.
+fn hot_reload_core_meta(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<CoreMeta>>,
+    game: Option<Res<GameMeta>>,
+    core_assets: Res<Assets<CoreMeta>>,
+    core_meta: Option<Res<CoreMetaArc>>,
+    sessions: Option<ResMut<Sessions>>,
+) {
+    let (Some(game), Some(core_meta)) = (game, core_meta) else {
+        events.clear();
+        return;
+    };
+    let modified = events.iter().any(
+        |event| matches!(event, AssetEvent::Modified { handle } if *handle == game.core.inner),
+    );
+    if !modified {
+        return;
+    }
+    let Some(new_meta) = core_assets.get(&game.core.inner) else {
+        return;
+    };
+
+    if let Err(e) = validate_core_meta_reload(&core_meta.0, new_meta) {
+        error!("Not hot reloading core metadata: {e}");
+        return;
+    }
+
+    let meta = Arc::new(new_meta.clone());
+    if let Some(mut sessions) = sessions {
+        for (_id, session) in sessions.iter_mut() {
+            session.set_metadata(meta.clone());
+            // Keep the new metadata if the session is restarted
+            session.info.meta = meta.clone();
+        }
+    }
+    commands.insert_resource(CoreMetaArc(meta));
+    info!("Hot reloaded core metadata");
+}
+
 fn menu_input_map() -> InputMap<MenuAction> {
     InputMap::default()
         .set_gamepad(Gamepad::new(0))
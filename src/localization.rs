@@ -1,9 +1,16 @@
-use std::borrow::Borrow;
+use std::{borrow::Borrow, collections::BTreeSet};
 
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 use bevy_fluent::prelude::*;
-use fluent::FluentArgs;
+use fluent::{FluentArgs, FluentResource};
 use fluent_content::{Content, Request};
+use fluent_syntax::ast;
+use unic_langid::LanguageIdentifier;
+
+use crate::metadata::{Settings, TranslationsMeta};
 
 /// Plugin for initializing and loading the [`Localization`] resource.
 pub struct JumpyLocalizationPlugin;
@@ -14,7 +21,8 @@ impl Plugin for JumpyLocalizationPlugin {
             .init_resource::<Locale>()
             .insert_resource(Localization::new());
 
-        app.add_system(load_locales);
+        app.add_system(load_locales)
+            .add_system(report_missing_messages);
     }
 }
 
@@ -44,6 +52,47 @@ where
     }
 }
 
+/// Create the [`Locale`] for the language picked in the settings, or the language of the user's
+/// system if none was picked, falling back to the default locale of the translations.
+pub fn settings_locale(settings: &Settings, translations: &TranslationsMeta) -> Locale {
+    let requested = settings
+        .locale
+        .clone()
+        .unwrap_or_else(|| translations.detected_locale.clone());
+    Locale::new(requested).with_default(translations.default_locale.clone())
+}
+
+/// Get the name of the language of a locale bundle, in that language, for picking it from a list.
+pub fn language_name(bundle: &BundleAsset) -> String {
+    bundle
+        .get_message("language-name")
+        .and_then(|message| message.value())
+        .map(|pattern| {
+            bundle
+                .format_pattern(pattern, None, &mut Vec::new())
+                .into_owned()
+        })
+        .unwrap_or_else(|| bundle.locales[0].to_string())
+}
+
+/// Get the IDs of the messages in the `resources` that are in the default locale, but that a locale
+/// doesn't have.
+fn missing_messages<'a>(
+    resources: impl IntoIterator<Item = &'a FluentResource>,
+    in_default: impl Fn(&str) -> bool,
+    has_message: impl Fn(&str) -> bool,
+) -> BTreeSet<&'a str> {
+    resources
+        .into_iter()
+        .flat_map(|resource| resource.entries())
+        .filter_map(|entry| match entry {
+            ast::Entry::Message(message) => Some(message.id.name),
+            _ => None,
+        })
+        .filter(|id| in_default(id) && !has_message(id))
+        .collect()
+}
+
 /// Watch for locale [`BundleAsset`] load events and add any new bundles to the [`Localization`]
 /// resource.
 fn load_locales(
@@ -112,3 +161,71 @@ fn load_locales(
         *localization = new_localization;
     }
 }
+
+/// Warn about the messages that haven't been translated from the default locale yet, as the locales
+/// are loaded.
+///
+/// Untranslated messages fall back to the default locale, so the game still works, but whoever
+/// maintains the translation should know that it is out of date. Each message is only reported
+/// once.
+fn report_missing_messages(
+    locale: Res<Locale>,
+    bundles: Res<Assets<BundleAsset>>,
+    resources: Res<Assets<ResourceAsset>>,
+    mut bundle_events: EventReader<AssetEvent<BundleAsset>>,
+    mut resource_events: EventReader<AssetEvent<ResourceAsset>>,
+    mut reported: Local<HashSet<(LanguageIdentifier, String)>>,
+) {
+    let bundles_changed = bundle_events.iter().count() > 0;
+    let resources_changed = resource_events.iter().count() > 0;
+    if !bundles_changed && !resources_changed {
+        return;
+    }
+    let Some(default_bundle) = locale
+        .default
+        .as_ref()
+        .and_then(|default| bundles.iter().find(|(_, x)| &x.locales[0] == default))
+        .map(|(_, x)| x)
+    else {
+        return;
+    };
+
+    for (_, bundle) in bundles.iter() {
+        let bundle_locale = &bundle.locales[0];
+        let missing = missing_messages(
+            resources.iter().map(|(_, resource)| &**resource),
+            |id| default_bundle.has_message(id),
+            |id| bundle.has_message(id),
+        )
+        .into_iter()
+        .filter(|id| reported.insert((bundle_locale.clone(), id.to_string())))
+        .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            warn!(
+                "Locale {bundle_locale} is missing translations for: {}",
+                missing.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_untranslated_messages_are_missing() {
+        let resource = |source: &str| FluentResource::try_new(source.to_string()).unwrap();
+        let en = resource("hello = Hello\nbye = Bye\n-brand = Jumpy\n");
+        let fr = resource("hello = Bonjour\nmerci = Merci\n");
+        let in_en = |id: &str| ["hello", "bye"].contains(&id);
+        let in_fr = |id: &str| ["hello", "merci"].contains(&id);
+
+        // Messages that the default locale doesn't have aren't missing from the others
+        assert_eq!(
+            missing_messages([&en, &fr], in_en, in_fr),
+            BTreeSet::from(["bye"])
+        );
+        assert!(missing_messages([&en, &fr], in_en, in_en).is_empty());
+    }
+}
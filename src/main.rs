@@ -22,11 +22,16 @@ pub mod assets;
 pub mod audio;
 pub mod config;
 pub mod debug;
+pub mod headless;
 pub mod input;
+pub mod kill_cam;
 pub mod loading;
 pub mod localization;
 pub mod metadata;
+pub mod netplay;
 pub mod platform;
+pub mod replay;
+pub mod rollback;
 pub mod session;
 pub mod ui;
 
@@ -106,6 +111,8 @@ pub fn main() {
         .add_plugin(bevy_framepace::FramepacePlugin)
         .add_plugin(JumpyPlayerInputPlugin)
         .add_plugin(JumpySessionPlugin)
+        .add_plugin(JumpyReplayPlugin)
+        .add_plugin(JumpyKillCamPlugin)
         .add_plugin(JumpyUiPlugin)
         .add_plugin(JumpyAudioPlugin)
         .add_plugin(JumpyPlatformPlugin)
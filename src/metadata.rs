@@ -1,5 +1,7 @@
 //! Data structures for things like assets and settings that can be serialized and deserialized.
 
+use jumpy_core::announcer::Announcement;
+
 use crate::prelude::*;
 
 mod localization;
@@ -26,6 +28,74 @@ pub struct GameMeta {
     #[asset(deserialize_only)]
     pub default_settings: settings::Settings,
     pub playlist: Vec<AssetHandle<AudioSource>>,
+    /// The music that loops while in the menus.
+    pub menu_music: AssetHandle<AudioSource>,
+    /// Stems that are layered on top of the songs in the playlist as the match heats up.
+    #[serde(default)]
+    pub music_layers: Vec<MusicLayersMeta>,
+    /// The voice lines that the announcer calls out the big moments of a match with.
+    #[serde(default)]
+    pub announcer: AnnouncerMeta,
+}
+
+/// The sets of voice lines that are used for each [`Announcement`], one of which is picked from the
+/// set whenever the announcement is made.
+///
+/// The lines are swapped out along with the rest of the game metadata, so a translation or a theme
+/// can give the announcer a voice of it's own. Announcements with no lines are not called out.
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct AnnouncerMeta {
+    pub match_start: Vec<AssetHandle<AudioSource>>,
+    /// The lines for each length of multi-kill streak, starting with double kills. Longer streaks
+    /// than there are lines for use the last of them.
+    pub multi_kill: Vec<Vec<AssetHandle<AudioSource>>>,
+    pub last_stock: Vec<AssetHandle<AudioSource>>,
+    pub victory: Vec<AssetHandle<AudioSource>>,
+    pub draw: Vec<AssetHandle<AudioSource>>,
+}
+
+impl AnnouncerMeta {
+    /// Get the set of lines to pick from for an announcement.
+    pub fn lines(&self, announcement: Announcement) -> &[AssetHandle<AudioSource>] {
+        match announcement {
+            Announcement::MatchStart => &self.match_start,
+            Announcement::MultiKill { kills, .. } => {
+                let streak = kills.saturating_sub(2) as usize;
+                self.multi_kill
+                    .get(streak)
+                    .or_else(|| self.multi_kill.last())
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+            }
+            Announcement::LastStock { .. } => &self.last_stock,
+            Announcement::Victory { winner: Some(_) } => &self.victory,
+            Announcement::Victory { winner: None } => &self.draw,
+        }
+    }
+}
+
+/// The stems that are layered on top of one of the songs in the playlist, see
+/// [`MusicLayers`][crate::audio::MusicLayers].
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MusicLayersMeta {
+    /// The song in the playlist that the stems are layered on top of.
+    pub song: AssetHandle<AudioSource>,
+    /// The tempo of the song, in beats per minute.
+    pub bpm: f32,
+    /// The number of beats in a bar of the song.
+    pub beats_per_bar: u32,
+    pub stems: Vec<MusicStemMeta>,
+}
+
+#[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MusicStemMeta {
+    /// The audio of the stem, which starts at the same time as the song.
+    pub stem: AssetHandle<AudioSource>,
+    /// The match intensity, from `0.0` to `1.0`, that the stem is heard at and above.
+    pub intensity: f32,
 }
 
 #[derive(BonesBevyAssetLoad, Deserialize, Clone, Debug)]
@@ -1,35 +1,98 @@
-use std::borrow::Cow;
-
-use bevy::prelude::Gamepad;
-use leafwing_input_manager::{axislike::VirtualDPad, prelude::InputMap, user_input::InputKind};
+use bevy::prelude::{warn, Gamepad, GamepadButtonType, Resource};
+use leafwing_input_manager::{
+    axislike::{DualAxis, VirtualDPad},
+    prelude::InputMap,
+    user_input::InputKind,
+};
 use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
 
-use crate::{input::PlayerAction, platform::Storage};
+use crate::{
+    audio::VolumeSettings,
+    input::{InputSettings, PlayerAction},
+    platform::Storage,
+};
 
 use super::GameMeta;
 
 /// Global settings, stored and accessed through [`crate::platform::Storage`]
-#[derive(Deserialize, Serialize, Debug, Clone)]
+///
+/// The settings in use are kept in a resource of this type, which is saved to storage whenever it
+/// is changed. The [`VolumeSettings`] and [`InputSettings`] resources, and the game window, are
+/// updated to match it.
+#[derive(Resource, Deserialize, Serialize, Debug, Clone)]
 pub struct Settings {
     /// The player controller bindings
     pub player_controls: PlayerControlMethods,
     /// The address of the matchmaking server to connect to for online games.
     pub matchmaking_server: String,
+    /// The language that the game is shown in, or `None` to use the language of the user's system.
+    #[serde(default)]
+    pub locale: Option<LanguageIdentifier>,
+    #[serde(default)]
+    pub volume: VolumeSettings,
+    #[serde(default)]
+    pub input: InputSettings,
+    #[serde(default)]
+    pub display: DisplaySettings,
+}
+
+/// Settings for how the game window is shown.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Whether the game fills the screen, instead of being shown in a window.
+    pub fullscreen: bool,
 }
 
 impl Settings {
     /// The key used to store the settings in the [`crate::platform::Storage`] resource.
     pub const STORAGE_KEY: &'static str = "settings";
 
-    pub fn get_stored_or_default<'w>(
-        game: &'w GameMeta,
-        storage: &'w mut Storage,
-    ) -> Cow<'w, Self> {
-        if let Some(settings) = storage.get::<Self>(Self::STORAGE_KEY) {
-            Cow::Owned(settings)
-        } else {
-            Cow::Borrowed(&game.default_settings)
+    /// Get the stored settings, with the defaults from the game metadata for any that are missing,
+    /// such as ones that were added after the settings were saved.
+    ///
+    /// If the stored settings can't be read, the defaults are used instead.
+    pub fn get_stored_or_default(game: &GameMeta, storage: &mut Storage) -> Self {
+        let defaults = &game.default_settings;
+        let stored = match storage.try_get::<serde_yaml::Value>(Self::STORAGE_KEY) {
+            Ok(Some(stored)) => stored,
+            Ok(None) => return defaults.clone(),
+            Err(e) => {
+                warn!("Couldn't read the stored settings, using the defaults: {e}");
+                return defaults.clone();
+            }
+        };
+
+        Self::merge_stored(defaults, stored).unwrap_or_else(|e| {
+            warn!("The stored settings are invalid, using the defaults: {e}");
+            defaults.clone()
+        })
+    }
+
+    /// Get the `stored` settings, with the `defaults` filled in for the ones that are missing.
+    fn merge_stored(defaults: &Self, stored: serde_yaml::Value) -> Result<Self, serde_yaml::Error> {
+        let mut settings = serde_yaml::to_value(defaults).expect("Serialize default settings");
+        merge_yaml(&mut settings, stored);
+        serde_yaml::from_value(settings)
+    }
+}
+
+/// Overwrite the values in `base` with the ones in `overrides`, going into the mappings that both
+/// have, so that the keys missing from nested mappings, like new bindings, keep their base values.
+fn merge_yaml(base: &mut serde_yaml::Value, overrides: serde_yaml::Value) {
+    match (base, overrides) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_yaml(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
         }
+        (base, overrides) => *base = overrides,
     }
 }
 
@@ -56,9 +119,14 @@ impl PlayerControlMethods {
             input_map.insert(ctrls.grab, PlayerAction::Grab);
             input_map.insert(ctrls.shoot, PlayerAction::Shoot);
             input_map.insert(ctrls.slide, PlayerAction::Slide);
+            input_map.insert(ctrls.block, PlayerAction::Block);
+            input_map.insert(ctrls.taunt, PlayerAction::Taunt);
+            input_map.insert(ctrls.emote, PlayerAction::Emote);
         };
 
         add_controls(&self.gamepad);
+        // Gamepads can aim freely with the right stick
+        input_map.insert(DualAxis::right_stick(), PlayerAction::Aim);
 
         match player_idx {
             0 => add_controls(&self.keyboard1),
@@ -68,9 +136,50 @@ impl PlayerControlMethods {
 
         input_map
     }
+
+    /// Bind an input to the binding at `binding_idx` of one of the controls, in the order of
+    /// [`PlayerControls::bindings_mut`].
+    ///
+    /// `controls_idx` is the controls to bind it in, in the order of the columns of the controls
+    /// settings: keyboard 1, keyboard 2, then the gamepad.
+    ///
+    /// If another action is already bound to the same input, it is given the old input of this
+    /// binding, so that two actions are never bound to the same input. This is done in all of the
+    /// controls that are merged into the same player's [input map][Self::get_input_map] as these
+    /// ones, and the same action in them is bound to the new input if it was bound to the old one,
+    /// like the keyboard bindings that default to the gamepad ones.
+    pub fn rebind(&mut self, controls_idx: usize, binding_idx: usize, input: InputKind) {
+        let mut controls = [
+            self.keyboard1.bindings_mut(),
+            self.keyboard2.bindings_mut(),
+            self.gamepad.bindings_mut(),
+        ];
+        // Every player has the gamepad controls, and the first two have a keyboard's as well
+        let merged: &[usize] = match controls_idx {
+            0 => &[0, 2],
+            1 => &[1, 2],
+            _ => &[0, 1, 2],
+        };
+        let controls_idx = controls_idx.min(2);
+        let old_input = *controls[controls_idx][binding_idx];
+
+        for &idx in merged {
+            for (i, binding) in controls[idx].iter_mut().enumerate() {
+                if i != binding_idx && **binding == input {
+                    **binding = old_input;
+                } else if i == binding_idx && **binding == old_input {
+                    **binding = input;
+                }
+            }
+        }
+    }
 }
 
 /// Binds inputs to player actions
+///
+/// The actions that were added after the first bindings were saved default to their gamepad
+/// bindings, which every player has as well as their keyboard ones, so the defaults never clash
+/// with the keys a player has bound.
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct PlayerControls {
     pub movement: VirtualDPad,
@@ -78,4 +187,148 @@ pub struct PlayerControls {
     pub grab: InputKind,
     pub shoot: InputKind,
     pub slide: InputKind,
+    #[serde(default = "default_block")]
+    pub block: InputKind,
+    #[serde(default = "default_taunt")]
+    pub taunt: InputKind,
+    #[serde(default = "default_emote")]
+    pub emote: InputKind,
+}
+
+fn default_block() -> InputKind {
+    InputKind::GamepadButton(GamepadButtonType::LeftTrigger)
+}
+
+fn default_taunt() -> InputKind {
+    InputKind::GamepadButton(GamepadButtonType::DPadUp)
+}
+
+fn default_emote() -> InputKind {
+    InputKind::GamepadButton(GamepadButtonType::DPadDown)
+}
+
+impl PlayerControls {
+    /// Get all of the bindings, in the order: move up, move down, move left, move right, jump,
+    /// grab, shoot, slide, block, taunt, emote.
+    pub fn bindings_mut(&mut self) -> [&mut InputKind; 11] {
+        [
+            &mut self.movement.up,
+            &mut self.movement.down,
+            &mut self.movement.left,
+            &mut self.movement.right,
+            &mut self.jump,
+            &mut self.grab,
+            &mut self.shoot,
+            &mut self.slide,
+            &mut self.block,
+            &mut self.taunt,
+            &mut self.emote,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::KeyCode;
+    use leafwing_input_manager::{prelude::UserInput, Actionlike};
+
+    use super::*;
+
+    fn default_settings() -> Settings {
+        let game: serde_yaml::Value =
+            serde_yaml::from_str(include_str!("../../assets/default.game.yaml")).unwrap();
+        serde_yaml::from_value(game["default_settings"].clone()).unwrap()
+    }
+
+    /// Get the actions that are bound to an input in a player's input map.
+    fn bound_actions(input_map: &InputMap<PlayerAction>, input: InputKind) -> Vec<PlayerAction> {
+        PlayerAction::variants()
+            .filter(|action| {
+                input_map
+                    .get(*action)
+                    .iter()
+                    .any(|x| *x == UserInput::Single(input))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rebinding_swaps_with_the_other_controls_of_the_player() {
+        const TAUNT_ROW: usize = 9;
+        const JUMP_ROW: usize = 4;
+        const KEYBOARD1_COLUMN: usize = 0;
+        const GAMEPAD_COLUMN: usize = 2;
+        let left_trigger = InputKind::GamepadButton(GamepadButtonType::LeftTrigger);
+        let dpad_up = InputKind::GamepadButton(GamepadButtonType::DPadUp);
+
+        // Settings saved before blocking and taunting could be bound, so the first keyboard player
+        // blocks and taunts with the gamepad buttons
+        let mut controls = default_settings().player_controls;
+        controls.keyboard1.block = default_block();
+        controls.keyboard1.taunt = default_taunt();
+
+        // Taunting with the left trigger on the gamepad moves blocking off of it, for the keyboard
+        // player that it is merged with too
+        controls.rebind(GAMEPAD_COLUMN, TAUNT_ROW, left_trigger);
+        for player_idx in 0..2 {
+            let input_map = controls.get_input_map(player_idx);
+            assert_eq!(
+                bound_actions(&input_map, left_trigger),
+                [PlayerAction::Taunt]
+            );
+        }
+        let input_map = controls.get_input_map(0);
+        assert_eq!(bound_actions(&input_map, dpad_up), [PlayerAction::Block]);
+
+        // The first keyboard's controls aren't merged with the second's
+        let space = InputKind::Keyboard(KeyCode::Space);
+        let comma = InputKind::Keyboard(KeyCode::Comma);
+        controls.rebind(KEYBOARD1_COLUMN, JUMP_ROW, comma);
+        let input_map = controls.get_input_map(0);
+        assert_eq!(bound_actions(&input_map, comma), [PlayerAction::Jump]);
+        assert!(bound_actions(&input_map, space).is_empty());
+        let input_map = controls.get_input_map(1);
+        assert_eq!(bound_actions(&input_map, comma), [PlayerAction::Jump]);
+    }
+
+    #[test]
+    fn settings_from_before_new_bindings_keep_their_values() {
+        let defaults = default_settings();
+
+        // Settings saved before blocking, taunting and emoting could be bound
+        let stored = serde_yaml::from_str(
+            "
+            matchmaking_server: localhost:8943
+            player_controls:
+              keyboard1:
+                movement:
+                  up: !Keyboard I
+                  down: !Keyboard K
+                  left: !Keyboard J
+                  right: !Keyboard L
+                jump: !Keyboard Z
+                grab: !Keyboard V
+                shoot: !Keyboard C
+                slide: !Keyboard B
+            locale: fr-FR
+            volume:
+              master: 0.5
+            ",
+        )
+        .unwrap();
+        let settings = Settings::merge_stored(&defaults, stored).unwrap();
+
+        let keyboard1 = &settings.player_controls.keyboard1;
+        assert_eq!(keyboard1.jump, InputKind::Keyboard(KeyCode::Z));
+        assert_eq!(keyboard1.movement.up, InputKind::Keyboard(KeyCode::I));
+        assert_eq!(keyboard1.block, defaults.player_controls.keyboard1.block);
+        assert_eq!(keyboard1.emote, defaults.player_controls.keyboard1.emote);
+        assert_eq!(
+            settings.player_controls.keyboard2.jump,
+            defaults.player_controls.keyboard2.jump
+        );
+        assert_eq!(settings.locale, Some("fr-FR".parse().unwrap()));
+        assert_eq!(settings.volume.master, 0.5);
+        assert_eq!(settings.volume.music, defaults.volume.music);
+    }
 }
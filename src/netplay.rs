@@ -0,0 +1,835 @@
+//! Keeping the sessions of the clients in a networked match in sync.
+//!
+//! The clients send each other their local players' inputs over a [`Transport`], which may be a
+//! socket, or, in tests, a loopback to other sessions in the same process. The [`InputSync`]
+//! feeds the inputs that arrive into the [`Rollback`], which rolls the session back when they
+//! don't match what was predicted. When some of a player's inputs are lost on the way, the client
+//! that they play on is asked to send them again.
+//!
+//! Every so often, the clients send each other the hash of their session state on a frame that
+//! all of the inputs are known for. A client whose hash doesn't match the host's has gone out of
+//! sync, such as by missing some of the inputs, and resyncs by asking the host for its snapshot of
+//! the last frame that they agreed on, and the controls of every player since, and re-simulating
+//! from there.
+//!
+//! Only part of the host's snapshot can be sent, because most of the session's world can't be
+//! serialized. The host sends where each entity is, which the client puts into its own snapshot of
+//! that frame, so resyncing fixes a client that was given the wrong controls, or whose entities were
+//! moved out of place. A client whose simulation itself plays out differently from the host's,
+//! given the same controls, ends up out of sync again after re-simulating. When that happens, the
+//! client stops resyncing and reports the desync as
+//! [`unrecoverable`][InputSync::unrecoverable_desync], since only fixing the non-determinism can
+//! bring it back in line.
+//!
+//! The main session plays a networked match while the [`Netplay`] resource exists, which
+//! [`SessionManager::start_networked`] inserts. There is no matchmaking yet, so the clients are
+//! connected directly with the [`netplay_addresses`][EngineConfig::netplay_addresses] that the game
+//! is started with.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use jumpy_core::{input::PlayerControl, session::SessionSnapshot};
+
+use crate::prelude::*;
+
+pub mod message;
+pub mod transport;
+
+pub use message::*;
+pub use transport::*;
+
+/// The number of frames of each local player's controls that are sent in each message, so that
+/// the inputs make it through even if a few messages are lost.
+pub const SENT_INPUT_FRAMES: usize = 8;
+
+/// The index of the client that hosts the match, whose session the other clients resync with.
+pub const HOST_CLIENT_IDX: usize = 0;
+
+/// The number of frames of controls that are sent in each message when resyncing, or when sending
+/// lost inputs again.
+pub const RESYNC_CHUNK_FRAMES: usize = 240;
+
+/// The number of frames that the main session may be rolled back by in a networked match, unless
+/// the [`RollbackSettings`] already allow more.
+pub const NETPLAY_ROLLBACK_FRAMES: usize = 8;
+
+/// The most frames that the host may delay the inputs by.
+///
+/// Larger delays in the host's settings are ignored, since they would make the match unplayable.
+pub const MAX_INPUT_DELAY_FRAMES: u64 = 60;
+
+/// The number of sync checks that the hashes are kept for, while waiting to be compared.
+const KEPT_SYNC_CHECKS: u64 = 8;
+
+/// Resource containing the settings of a networked match, which every client must play with.
+///
+/// The host picks the settings and shares them with the other clients over the [`InputSync`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NetSettings {
+    /// The number of frames that the local players' inputs are delayed by before they take effect.
+    ///
+    /// Delaying the local inputs gives the remote players' inputs for the same frames time to
+    /// arrive, so that less frames need to be rolled back, or, without rollback, so that the
+    /// session doesn't have to wait for them. Every client delays its inputs by the same number
+    /// of frames, so that none of them get to react sooner than the others.
+    pub input_delay_frames: u64,
+}
+
+/// Resource containing the networked match that the main session is playing.
+///
+/// While this exists, the session systems send the local player's controls to the other clients,
+/// and feed theirs into the [`Rollback`], so the main session must be simulated with rollback.
+#[derive(Resource)]
+pub struct Netplay {
+    pub sync: InputSync,
+    pub transport: Box<dyn Transport>,
+    /// The index of the player that is controlled by this client.
+    pub player_idx: usize,
+}
+
+impl Netplay {
+    /// Join a networked match of the `session` over the `transport`, with the player that has the
+    /// same index as this client.
+    ///
+    /// When this client is the host, the match is played with the given `net_settings`, otherwise
+    /// with the host's.
+    pub fn new(
+        session: &GameSession,
+        transport: Box<dyn Transport>,
+        net_settings: NetSettings,
+    ) -> Self {
+        let mut sync = InputSync::for_session(session);
+        let player_idx = transport.client_idx();
+        if player_idx == HOST_CLIENT_IDX {
+            sync.share_net_settings(&*transport, net_settings);
+        }
+        sync.join(&*transport, player_idx);
+        Self {
+            sync,
+            transport,
+            player_idx,
+        }
+    }
+
+    /// Tell the other clients that the local player has left the match.
+    pub fn leave(&self) {
+        self.sync.leave(&*self.transport, self.player_idx);
+    }
+
+    /// Add the controls of the local player, collected on the given frame, see
+    /// [`InputSync::add_local_input`].
+    pub fn add_local_input(&mut self, rollback: &mut Rollback, frame: u64, control: PlayerControl) {
+        self.sync
+            .add_local_input(rollback, frame, self.player_idx, control);
+    }
+
+    /// Send the latest controls of the local player to the other clients.
+    pub fn send_inputs(&self) {
+        self.sync.send_inputs(&*self.transport);
+    }
+
+    /// Handle the messages that have arrived from the other clients, and advance the session by a
+    /// frame with the [`Rollback`], resyncing it with the host's first if it went out of sync.
+    ///
+    /// Returns the number of frames that were re-simulated by the rollback.
+    pub fn advance(
+        &mut self,
+        session: &mut GameSession,
+        rollback: &mut Rollback,
+        max_rollback_frames: usize,
+        mut step: impl FnMut(&mut GameSession),
+    ) -> usize {
+        self.sync.receive(&*self.transport, rollback);
+        self.sync.apply_resync(session, rollback, &mut step);
+        let resimulated = rollback.advance(session, max_rollback_frames, &mut step);
+        self.sync
+            .update_sync_checks(&*self.transport, session, rollback);
+        resimulated
+    }
+}
+
+/// Connect to the other clients of the networked match that the game was started with, if any.
+///
+/// See [`netplay_addresses`][EngineConfig::netplay_addresses].
+pub fn connect_from_config() -> Option<Box<dyn Transport>> {
+    let addresses = &ENGINE_CONFIG.netplay_addresses;
+    if addresses.is_empty() {
+        return None;
+    }
+    if addresses.len() > MAX_PLAYERS {
+        error!("A networked match can't have more than {MAX_PLAYERS} clients");
+        return None;
+    }
+    let client_idx = ENGINE_CONFIG.netplay_client;
+    if client_idx >= addresses.len() {
+        error!(
+            "Can't play as client {client_idx} in a networked match of {} clients",
+            addresses.len()
+        );
+        return None;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    match UdpTransport::bind(client_idx, addresses.clone()) {
+        Ok(transport) => Some(Box::new(transport)),
+        Err(e) => {
+            error!("Error binding to {}: {e}", addresses[client_idx]);
+            None
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        error!("Networked matches can't be played on the web yet");
+        None
+    }
+}
+
+/// Keeps the inputs of the clients in a networked match in sync.
+///
+/// The controls of the local players are sent to the other clients, and the controls that arrive
+/// from them are fed into the [`Rollback`]. The clients' session states are compared every
+/// [`sync_check_interval`][Self::sync_check_interval] frames, and resynced when they differ.
+pub struct InputSync {
+    /// The number of frames between the checks that the clients are still in sync, or `0` to not
+    /// check.
+    ///
+    /// This defaults to one second's worth of frames at [`jumpy_core::FPS`], see
+    /// [`for_session`][Self::for_session] for a match that runs at a different rate.
+    pub sync_check_interval: u64,
+    /// The settings of the match, which the clients other than the host get from it.
+    net_settings: NetSettings,
+    /// The latest controls of each local player, by the frame that they are for, oldest first.
+    local_controls: [VecDeque<(u64, PlayerControl)>; MAX_PLAYERS],
+    /// The latest frame that the controls of each remote player have been received for.
+    ///
+    /// The controls are only received in order, so the controls of every frame from the first one
+    /// received up to this one are known.
+    received: [Option<u64>; MAX_PLAYERS],
+    /// The frame that each remote player's controls were last asked to be sent again from.
+    resend_requested: [Option<u64>; MAX_PLAYERS],
+    /// The client that each remote player joined the match from, until they leave it.
+    ///
+    /// Only the client that a player joined from may send their inputs.
+    player_clients: [Option<usize>; MAX_PLAYERS],
+    /// The known controls of every player, with their presses, by frame.
+    ///
+    /// These are kept for the whole match, for the host to resync the other clients with.
+    match_controls: BTreeMap<u64, [Option<PlayerControl>; MAX_PLAYERS]>,
+    /// The next frame to check that the clients are in sync on.
+    next_sync_check: u64,
+    /// The hashes of this client's session states, by frame.
+    local_hashes: BTreeMap<u64, u64>,
+    /// The hashes of the other clients' session states, by client and frame, that haven't been
+    /// compared yet.
+    remote_hashes: BTreeMap<(usize, u64), u64>,
+    /// Snapshots of this client's session on the frames that have been checked, since the last one
+    /// that it agreed with the host on, or, on the host, for as long as the hashes are kept.
+    checkpoints: BTreeMap<u64, SessionSnapshot>,
+    /// The last frame that this client's session agreed with the host's on.
+    last_agreed: Option<u64>,
+    /// The resync that has been asked for, if any.
+    resync: Option<Resync>,
+    /// The frame that this client last resynced from.
+    resynced_from: Option<u64>,
+    /// The frame that this client was found to be out of sync with the host on, until it resyncs.
+    pub desync_frame: Option<u64>,
+    /// Whether this client went out of sync with the host again after resyncing, without agreeing
+    /// with it on any frame in between, so resyncing can't fix it.
+    pub unrecoverable_desync: bool,
+    /// The number of times that this client has resynced with the host.
+    pub resync_count: u32,
+}
+
+impl Default for InputSync {
+    fn default() -> Self {
+        Self {
+            sync_check_interval: jumpy_core::FPS as u64,
+            net_settings: default(),
+            local_controls: default(),
+            received: default(),
+            resend_requested: default(),
+            player_clients: default(),
+            match_controls: default(),
+            next_sync_check: 0,
+            local_hashes: default(),
+            remote_hashes: default(),
+            checkpoints: default(),
+            last_agreed: None,
+            resync: None,
+            resynced_from: None,
+            desync_frame: None,
+            unrecoverable_desync: false,
+            resync_count: 0,
+        }
+    }
+}
+
+/// A resync with the host's session that has been asked for.
+struct Resync {
+    /// The frame that the session is re-simulated from.
+    from_frame: u64,
+    /// The controls that have been received from the host.
+    controls: BTreeMap<u64, [Option<PlayerControl>; MAX_PLAYERS]>,
+    /// Where the entities were in the host's session on the frame resynced from, if the host
+    /// still had a snapshot of it.
+    placements: Option<Vec<(u32, Vec3, Quat)>>,
+    /// Whether all of the controls have been received.
+    done: bool,
+}
+
+impl InputSync {
+    /// Create the input sync for a networked match of the given session, checking that the clients
+    /// are in sync once a second.
+    pub fn for_session(session: &GameSession) -> Self {
+        Self {
+            sync_check_interval: (session.info.fps.round() as u64).max(1),
+            ..default()
+        }
+    }
+
+    /// Tell the other clients that a local player has joined the match.
+    pub fn join(&self, transport: &dyn Transport, player_idx: usize) {
+        let message = NetMessage::Join {
+            player_idx: player_idx as u8,
+        };
+        transport.send_reliable(&message.encode());
+    }
+
+    /// Tell the other clients that a local player has left the match.
+    pub fn leave(&self, transport: &dyn Transport, player_idx: usize) {
+        let message = NetMessage::Leave {
+            player_idx: player_idx as u8,
+        };
+        transport.send_reliable(&message.encode());
+    }
+
+    /// Get the settings of the match.
+    ///
+    /// Until the host's settings have arrived, these are the defaults.
+    pub fn net_settings(&self) -> NetSettings {
+        self.net_settings
+    }
+
+    /// Play the match with the given settings, and send them to the other clients to play with.
+    ///
+    /// This must only be called on the host, the other clients ignore settings that don't come from
+    /// the host. It should be called before any inputs are sent, so that the clients delay their
+    /// inputs by the same amount from the first frame on.
+    pub fn share_net_settings(&mut self, transport: &dyn Transport, net_settings: NetSettings) {
+        if transport.client_idx() != HOST_CLIENT_IDX {
+            warn!("Only the host can choose the settings of the match");
+            return;
+        }
+        self.net_settings = net_settings;
+        let message = NetMessage::MatchSettings {
+            input_delay_frames: net_settings.input_delay_frames,
+        };
+        transport.send_reliable(&message.encode());
+    }
+
+    /// Add the controls of a local player, collected on the given frame, to the [`Rollback`], and
+    /// to the controls that are sent to the other clients.
+    ///
+    /// The controls are delayed by the [`input_delay_frames`][NetSettings::input_delay_frames] of
+    /// the match.
+    pub fn add_local_input(
+        &mut self,
+        rollback: &mut Rollback,
+        frame: u64,
+        player_idx: usize,
+        control: PlayerControl,
+    ) {
+        let input_delay = self.net_settings.input_delay_frames;
+        let delayed_frame = frame + input_delay;
+        let controls = &mut self.local_controls[player_idx];
+        controls.push_back((delayed_frame, control.clone()));
+        while controls.len() > SENT_INPUT_FRAMES {
+            controls.pop_front();
+        }
+        rollback.add_local_input(frame, input_delay, player_idx, control);
+
+        // Keep the control with the presses that the rollback worked out for it
+        self.match_controls.entry(delayed_frame).or_default()[player_idx] =
+            Some(rollback.controls(delayed_frame)[player_idx].clone());
+    }
+
+    /// Send the latest controls of the local players to the other clients.
+    pub fn send_inputs(&self, transport: &dyn Transport) {
+        for (player_idx, controls) in self.local_controls.iter().enumerate() {
+            let Some(&(frame, _)) = controls.back() else {
+                continue;
+            };
+            // Only the frames leading up to the latest one can be sent together
+            let mut sent = controls
+                .iter()
+                .rev()
+                .zip((0..=frame).rev())
+                .take_while(|((control_frame, _), frame)| control_frame == frame)
+                .map(|((_, control), _)| control.clone())
+                .collect::<Vec<_>>();
+            sent.reverse();
+
+            let message = NetMessage::Inputs {
+                player_idx: player_idx as u8,
+                frame,
+                controls: sent,
+            };
+            transport.send_unreliable(&message.encode());
+        }
+    }
+
+    /// Send the hashes of this client's session states to the other clients, every
+    /// [`sync_check_interval`][Self::sync_check_interval] frames, to check that they are still in
+    /// sync.
+    ///
+    /// A frame is checked once the controls of all of the frames before it are known, so that it
+    /// won't be rolled back anymore. This must be called after the session has been advanced, so
+    /// that the rollback's snapshots include the corrections for the inputs that arrived.
+    pub fn update_sync_checks(
+        &mut self,
+        transport: &dyn Transport,
+        session: &GameSession,
+        rollback: &Rollback,
+    ) {
+        if self.sync_check_interval == 0 {
+            return;
+        }
+
+        loop {
+            let frame = self.next_sync_check;
+            let confirmed = frame == 0 || self.confirmed_frame().map_or(false, |x| x + 1 >= frame);
+            if !confirmed || frame >= session.frame() {
+                break;
+            }
+            self.next_sync_check += self.sync_check_interval;
+
+            // The frame may have been confirmed too late to still have a snapshot to check
+            let Some(snapshot) = rollback.snapshot(frame) else {
+                continue;
+            };
+            let hash = snapshot.state_hash();
+            self.local_hashes.insert(frame, hash);
+            self.checkpoints.insert(frame, snapshot.clone());
+            transport.send_reliable(&NetMessage::SyncCheck { frame, hash }.encode());
+            self.check_sync(transport, frame);
+        }
+
+        // Forget the hashes that are too old to be compared anymore
+        let oldest = self
+            .next_sync_check
+            .saturating_sub(KEPT_SYNC_CHECKS * self.sync_check_interval);
+        self.local_hashes.retain(|x, _| *x >= oldest);
+        self.remote_hashes.retain(|(_, x), _| *x >= oldest);
+        // The host keeps the snapshots for as long as the clients may ask to resync from them
+        if transport.client_idx() == HOST_CLIENT_IDX {
+            self.checkpoints.retain(|x, _| *x >= oldest);
+        }
+    }
+
+    /// Get the latest frame that the controls of all of the remote players have been received for.
+    pub fn confirmed_frame(&self) -> Option<u64> {
+        self.player_clients
+            .iter()
+            .zip(&self.received)
+            .filter(|(client, _)| client.is_some())
+            .map(|(_, received)| *received)
+            .min()
+            .flatten()
+    }
+
+    /// Handle the messages that have arrived from the other clients.
+    pub fn receive(&mut self, transport: &dyn Transport, rollback: &mut Rollback) {
+        let messages = std::iter::from_fn(|| transport.recv_reliable())
+            .chain(std::iter::from_fn(|| transport.recv_unreliable()));
+        for received in messages {
+            let message = match NetMessage::decode(&received.bytes) {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!("Ignoring message from client {}: {e}", received.from);
+                    continue;
+                }
+            };
+            if let NetMessage::Join { player_idx }
+            | NetMessage::Leave { player_idx }
+            | NetMessage::Inputs { player_idx, .. }
+            | NetMessage::InputsRequest { player_idx, .. }
+            | NetMessage::ResyncInputs { player_idx, .. } = message
+            {
+                if player_idx as usize >= MAX_PLAYERS {
+                    warn!(
+                        "Ignoring message from client {} for player {player_idx}, which doesn't \
+                        exist",
+                        received.from
+                    );
+                    continue;
+                }
+            }
+
+            match message {
+                NetMessage::Join { player_idx } => {
+                    let client = &mut self.player_clients[player_idx as usize];
+                    let local = !self.local_controls[player_idx as usize].is_empty();
+                    match *client {
+                        Some(owner) if owner != received.from => warn!(
+                            "Ignoring player {player_idx} joining from client {}, who already \
+                            joined from client {owner}",
+                            received.from
+                        ),
+                        _ if local => warn!(
+                            "Ignoring player {player_idx} joining from client {}, who is played \
+                            on this client",
+                            received.from
+                        ),
+                        _ => {
+                            info!("Player {player_idx} joined from client {}", received.from);
+                            *client = Some(received.from);
+                        }
+                    }
+                }
+                NetMessage::Leave { player_idx } => {
+                    let client = &mut self.player_clients[player_idx as usize];
+                    if *client == Some(received.from) {
+                        info!("Player {player_idx} left from client {}", received.from);
+                        *client = None;
+                    } else {
+                        warn!(
+                            "Ignoring player {player_idx} leaving from client {}, who they didn't \
+                            join from",
+                            received.from
+                        );
+                    }
+                }
+                NetMessage::MatchSettings { input_delay_frames } => {
+                    if received.from != HOST_CLIENT_IDX {
+                        continue;
+                    }
+                    if input_delay_frames > MAX_INPUT_DELAY_FRAMES {
+                        warn!("Ignoring the host's input delay of {input_delay_frames} frames");
+                        continue;
+                    }
+                    info!("Playing with an input delay of {input_delay_frames} frame(s)");
+                    self.net_settings = NetSettings { input_delay_frames };
+                }
+                NetMessage::Inputs {
+                    player_idx,
+                    frame,
+                    controls,
+                } => {
+                    if self.player_clients[player_idx as usize] != Some(received.from) {
+                        warn!(
+                            "Ignoring inputs for player {player_idx} from client {}, who they \
+                            didn't join from",
+                            received.from
+                        );
+                        continue;
+                    }
+                    self.receive_inputs(transport, rollback, player_idx as usize, frame, controls);
+                }
+                NetMessage::InputsRequest {
+                    player_idx,
+                    from_frame,
+                } => {
+                    // Only the client that the player plays on has all of their controls
+                    if !self.local_controls[player_idx as usize].is_empty() {
+                        self.resend_inputs(transport, player_idx as usize, from_frame);
+                    }
+                }
+                NetMessage::SyncCheck { frame, hash } => {
+                    // Clients only compare their hashes with the host's
+                    if transport.client_idx() == HOST_CLIENT_IDX || received.from == HOST_CLIENT_IDX
+                    {
+                        self.remote_hashes.insert((received.from, frame), hash);
+                        self.check_sync(transport, frame);
+                    }
+                }
+                NetMessage::ResyncRequest { from_frame } => {
+                    if transport.client_idx() == HOST_CLIENT_IDX {
+                        self.send_resync(transport, received.from, from_frame);
+                    }
+                }
+                NetMessage::ResyncInputs {
+                    client_idx,
+                    player_idx,
+                    frame,
+                    controls,
+                } => {
+                    let Some(resync) = self.resync.as_mut().filter(|_| {
+                        received.from == HOST_CLIENT_IDX
+                            && client_idx as usize == transport.client_idx()
+                    }) else {
+                        continue;
+                    };
+                    let Some(end_frame) = frame.checked_add(1) else {
+                        warn!("Ignoring resync inputs that end after the last frame");
+                        continue;
+                    };
+                    let first_frame = end_frame.saturating_sub(controls.len() as u64);
+                    for (frame, control) in (first_frame..).zip(controls) {
+                        resync.controls.entry(frame).or_default()[player_idx as usize] =
+                            Some(control);
+                    }
+                }
+                NetMessage::ResyncPlacements {
+                    client_idx,
+                    frame,
+                    placements,
+                } => {
+                    if let Some(resync) = &mut self.resync {
+                        if received.from == HOST_CLIENT_IDX
+                            && client_idx as usize == transport.client_idx()
+                            && resync.from_frame == frame
+                        {
+                            resync.placements = Some(placements);
+                        }
+                    }
+                }
+                NetMessage::ResyncDone {
+                    client_idx,
+                    from_frame,
+                } => {
+                    if let Some(resync) = &mut self.resync {
+                        if received.from == HOST_CLIENT_IDX
+                            && client_idx as usize == transport.client_idx()
+                            && resync.from_frame == from_frame
+                        {
+                            resync.done = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        // The players of the clients that have disconnected have left the match
+        for (player_idx, client) in self.player_clients.iter_mut().enumerate() {
+            if let Some(client_idx) = client.filter(|x| !transport.is_connected(*x)) {
+                warn!("Player {player_idx} left, client {client_idx} disconnected");
+                *client = None;
+            }
+        }
+    }
+
+    /// Resync the session with the host's, once all of the controls that were asked for have
+    /// arrived from the host.
+    ///
+    /// The session is restored to the last frame that it agreed with the host's on, with its
+    /// entities placed where they were in the host's session, and re-simulated up to the present
+    /// with the host's controls, using `step` like [`Rollback::advance`]. Returns whether the
+    /// session was resynced.
+    pub fn apply_resync(
+        &mut self,
+        session: &mut GameSession,
+        rollback: &mut Rollback,
+        step: impl FnMut(&mut GameSession),
+    ) -> bool {
+        if !self.resync.as_ref().map_or(false, |x| x.done) {
+            return false;
+        }
+        let resync = self.resync.take().unwrap();
+        let Some(mut snapshot) = self.checkpoints.get(&resync.from_frame).cloned() else {
+            error!(
+                "Can't resync from frame {}, which there is no snapshot of",
+                resync.from_frame
+            );
+            return false;
+        };
+
+        // The presses aren't sent, so they have to be worked out again
+        for (frame, controls) in &resync.controls {
+            for (player_idx, control) in controls.iter().enumerate() {
+                let Some(mut control) = control.clone() else {
+                    continue;
+                };
+                let previous = frame
+                    .checked_sub(1)
+                    .and_then(|frame| self.match_controls.get(&frame))
+                    .and_then(|x| x[player_idx].clone())
+                    .unwrap_or_default();
+                control.detect_presses(&previous);
+                self.match_controls.entry(*frame).or_default()[player_idx] = Some(control);
+            }
+        }
+        let controls = self
+            .match_controls
+            .range(resync.from_frame..)
+            .map(|(frame, controls)| (*frame, controls.clone()))
+            .collect();
+        if let Some(placements) = &resync.placements {
+            snapshot.set_placements(placements);
+        }
+        rollback.resync(session, &snapshot, controls, step);
+
+        // The checks since the frame resynced from were of the state that was out of sync
+        self.local_hashes.retain(|x, _| *x <= resync.from_frame);
+        self.checkpoints.retain(|x, _| *x <= resync.from_frame);
+        self.desync_frame = None;
+        self.resynced_from = Some(resync.from_frame);
+        self.resync_count += 1;
+        info!("Resynced with the host from frame {}", resync.from_frame);
+        true
+    }
+
+    /// Add the controls of a remote player for the frames up to `frame` that haven't been received
+    /// yet to the [`Rollback`].
+    ///
+    /// The controls must follow on from the ones received before. When some frames in between
+    /// are missing, the client that the player plays on is asked to send them again, and the
+    /// controls after them are dropped until they arrive.
+    fn receive_inputs(
+        &mut self,
+        transport: &dyn Transport,
+        rollback: &mut Rollback,
+        player_idx: usize,
+        frame: u64,
+        controls: Vec<PlayerControl>,
+    ) {
+        let Some(end_frame) = frame.checked_add(1) else {
+            warn!("Ignoring inputs for player {player_idx} that end after the last frame");
+            return;
+        };
+        let Some(first_frame) = end_frame.checked_sub(controls.len() as u64) else {
+            warn!("Ignoring inputs for player {player_idx} that start before the first frame");
+            return;
+        };
+        if let Some(received) = self.received[player_idx] {
+            let next_frame = received + 1;
+            if first_frame > next_frame && self.resend_requested[player_idx] != Some(next_frame) {
+                warn!("Lost the inputs for player {player_idx} from frame {next_frame}");
+                self.resend_requested[player_idx] = Some(next_frame);
+                let message = NetMessage::InputsRequest {
+                    player_idx: player_idx as u8,
+                    from_frame: next_frame,
+                };
+                transport.send_reliable(&message.encode());
+            }
+        }
+
+        for (frame, mut control) in (first_frame..).zip(controls) {
+            if self.received[player_idx].map_or(false, |x| frame != x + 1) {
+                continue;
+            }
+            // The presses aren't sent, so they have to be worked out again
+            let previous = frame
+                .checked_sub(1)
+                .map(|frame| rollback.controls(frame)[player_idx].clone())
+                .unwrap_or_default();
+            control.detect_presses(&previous);
+
+            self.match_controls.entry(frame).or_default()[player_idx] = Some(control.clone());
+            rollback.add_remote_input(frame, player_idx, control);
+            self.received[player_idx] = Some(frame);
+        }
+    }
+
+    /// Send the controls of a local player from `from_frame` on again, for the clients that lost
+    /// them.
+    fn resend_inputs(&self, transport: &dyn Transport, player_idx: usize, from_frame: u64) {
+        let controls = (from_frame..=u64::MAX)
+            .map_while(|frame| self.match_controls.get(&frame)?[player_idx].clone())
+            .collect::<Vec<_>>();
+        for (i, chunk) in controls.chunks(RESYNC_CHUNK_FRAMES).enumerate() {
+            let message = NetMessage::Inputs {
+                player_idx: player_idx as u8,
+                frame: from_frame + (i * RESYNC_CHUNK_FRAMES + chunk.len()) as u64 - 1,
+                controls: chunk.to_vec(),
+            };
+            transport.send_reliable(&message.encode());
+        }
+    }
+
+    /// Send the host's snapshot of `from_frame`, and the controls of every player from then on, to a
+    /// client that asked to resync.
+    fn send_resync(&self, transport: &dyn Transport, client_idx: usize, from_frame: u64) {
+        info!("Sending client {client_idx} the controls to resync from frame {from_frame}");
+        for player_idx in 0..MAX_PLAYERS {
+            // The requested frame comes from the other client, so it may be the very last one
+            let controls = (from_frame..=u64::MAX)
+                .map_while(|frame| self.match_controls.get(&frame)?[player_idx].clone())
+                .collect::<Vec<_>>();
+            for (i, chunk) in controls.chunks(RESYNC_CHUNK_FRAMES).enumerate() {
+                let message = NetMessage::ResyncInputs {
+                    client_idx: client_idx as u8,
+                    player_idx: player_idx as u8,
+                    frame: from_frame + (i * RESYNC_CHUNK_FRAMES + chunk.len()) as u64 - 1,
+                    controls: chunk.to_vec(),
+                };
+                transport.send_reliable(&message.encode());
+            }
+        }
+
+        // The rest of the host's snapshot can't be sent, see the module docs
+        if let Some(snapshot) = self.checkpoints.get(&from_frame) {
+            let message = NetMessage::ResyncPlacements {
+                client_idx: client_idx as u8,
+                frame: from_frame,
+                placements: snapshot.placements(),
+            };
+            transport.send_reliable(&message.encode());
+        }
+
+        let message = NetMessage::ResyncDone {
+            client_idx: client_idx as u8,
+            from_frame,
+        };
+        transport.send_reliable(&message.encode());
+    }
+
+    /// Compare the local and remote state hashes of `frame`, once both are known, and ask the
+    /// host to resync if this client is out of sync with it.
+    fn check_sync(&mut self, transport: &dyn Transport, frame: u64) {
+        let Some(&local) = self.local_hashes.get(&frame) else {
+            return;
+        };
+        let remote = self
+            .remote_hashes
+            .iter()
+            .filter(|((_, x), _)| *x == frame)
+            .map(|(&(client_idx, _), &hash)| (client_idx, hash))
+            .collect::<Vec<_>>();
+
+        for (client_idx, hash) in remote {
+            self.remote_hashes.remove(&(client_idx, frame));
+            if transport.client_idx() == HOST_CLIENT_IDX {
+                if hash != local {
+                    warn!("Client {client_idx} is out of sync with the host on frame {frame}");
+                }
+            } else if hash == local {
+                self.last_agreed = Some(frame);
+                self.checkpoints.retain(|x, _| *x >= frame);
+            } else {
+                error!("Out of sync with the host on frame {frame}");
+                self.desync_frame.get_or_insert(frame);
+                self.request_resync(transport);
+            }
+        }
+    }
+
+    /// Ask the host for the controls to resync with, unless a resync is already underway.
+    ///
+    /// If the session already resynced from the last frame that it agreed with the host's on, it
+    /// went out of sync again with the host's own controls, so it isn't resynced again.
+    fn request_resync(&mut self, transport: &dyn Transport) {
+        if self.resync.is_some() || self.unrecoverable_desync {
+            return;
+        }
+        let Some(from_frame) = self.last_agreed else {
+            error!("Can't resync, the session never agreed with the host's");
+            return;
+        };
+        if self.resynced_from == Some(from_frame) {
+            error!(
+                "Still out of sync with the host after resyncing from frame {from_frame}, the \
+                simulation isn't deterministic"
+            );
+            self.unrecoverable_desync = true;
+            return;
+        }
+        self.resync = Some(Resync {
+            from_frame,
+            controls: default(),
+            placements: None,
+            done: false,
+        });
+        transport.send_reliable(&NetMessage::ResyncRequest { from_frame }.encode());
+    }
+}
@@ -0,0 +1,459 @@
+//! The messages that clients send each other during a networked match.
+//!
+//! Messages are encoded by hand into a compact binary layout, starting with the
+//! [`PROTOCOL_VERSION`], so that clients running different versions of the game notice that they
+//! can't understand each other instead of misreading the messages.
+
+use jumpy_core::input::PlayerControl;
+
+use crate::prelude::*;
+
+/// The version of the message layout.
+///
+/// This must be bumped whenever the layout of any message changes.
+pub const PROTOCOL_VERSION: u8 = 5;
+
+/// Errors that may occur while decoding a [`NetMessage`].
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum MessageError {
+    #[error("Message protocol version {found} is not supported, expected version {expected}")]
+    UnsupportedVersion { found: u8, expected: u8 },
+    #[error("Unknown message kind {0}")]
+    UnknownKind(u8),
+    #[error("Message ended unexpectedly")]
+    Truncated,
+    #[error("The {0} in the message is out of range")]
+    OutOfRange(&'static str),
+}
+
+/// A message sent between the clients of a networked match.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetMessage {
+    /// A player has joined the match on the sending client.
+    Join { player_idx: u8 },
+    /// A player on the sending client has left the match.
+    Leave { player_idx: u8 },
+    /// The controls of a player on the sending client, for the frames up to and including
+    /// `frame`, oldest first.
+    ///
+    /// A few of the previous frames are sent along with the latest, so that the inputs make it
+    /// through even if some of the messages are lost.
+    Inputs {
+        player_idx: u8,
+        frame: u64,
+        controls: Vec<PlayerControl>,
+    },
+    /// Ask the client that a player plays on to send their controls from `from_frame` on again,
+    /// because some of them were lost on the way.
+    InputsRequest { player_idx: u8, from_frame: u64 },
+    /// The hash of the sending client's session state at the start of `frame`, used to check that
+    /// the clients haven't gone out of sync.
+    SyncCheck { frame: u64, hash: u64 },
+    /// Ask the host for its snapshot of `from_frame`, and the controls of every player from then on,
+    /// to re-simulate the session from the last frame that it agreed with the host's on.
+    ResyncRequest { from_frame: u64 },
+    /// The controls of a player for the frames up to and including `frame`, oldest first, sent by
+    /// the host to the client with the index `client_idx` for it to resync with.
+    ResyncInputs {
+        client_idx: u8,
+        player_idx: u8,
+        frame: u64,
+        controls: Vec<PlayerControl>,
+    },
+    /// The translation and rotation of the entities in the host's session at the start of `frame`,
+    /// by the index of their entity, sent to the client with the index `client_idx` for it to
+    /// resync from.
+    ResyncPlacements {
+        client_idx: u8,
+        frame: u64,
+        placements: Vec<(u32, Vec3, Quat)>,
+    },
+    /// All of the controls that the client with the index `client_idx` asked for to resync from
+    /// `from_frame` have been sent.
+    ResyncDone { client_idx: u8, from_frame: u64 },
+    /// The [`NetSettings`][super::NetSettings] that the host has picked for the match.
+    MatchSettings { input_delay_frames: u64 },
+}
+
+const JOIN: u8 = 0;
+const LEAVE: u8 = 1;
+const INPUTS: u8 = 2;
+const SYNC_CHECK: u8 = 3;
+const RESYNC_REQUEST: u8 = 4;
+const RESYNC_INPUTS: u8 = 5;
+const RESYNC_DONE: u8 = 6;
+const MATCH_SETTINGS: u8 = 7;
+const INPUTS_REQUEST: u8 = 8;
+const RESYNC_PLACEMENTS: u8 = 9;
+
+// The bits of the flags that start each encoded control.
+const MOVING: u16 = 1 << 0;
+const JUMP: u16 = 1 << 1;
+const SHOOT: u16 = 1 << 2;
+const GRAB: u16 = 1 << 3;
+const SLIDE: u16 = 1 << 4;
+const BLOCK: u16 = 1 << 5;
+const TAUNT: u16 = 1 << 6;
+const HAS_EMOTE: u16 = 1 << 7;
+const HAS_MOVE_DIRECTION: u16 = 1 << 8;
+const HAS_AIM_DIRECTION: u16 = 1 << 9;
+
+impl NetMessage {
+    /// Encode the message into bytes to send over a [`Transport`][super::Transport].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![PROTOCOL_VERSION];
+        match self {
+            NetMessage::Join { player_idx } => bytes.extend([JOIN, *player_idx]),
+            NetMessage::Leave { player_idx } => bytes.extend([LEAVE, *player_idx]),
+            NetMessage::Inputs {
+                player_idx,
+                frame,
+                controls,
+            } => {
+                bytes.extend([INPUTS, *player_idx]);
+                write_controls(&mut bytes, *frame, controls);
+            }
+            NetMessage::InputsRequest {
+                player_idx,
+                from_frame,
+            } => {
+                bytes.extend([INPUTS_REQUEST, *player_idx]);
+                write_varint(&mut bytes, *from_frame);
+            }
+            NetMessage::SyncCheck { frame, hash } => {
+                bytes.push(SYNC_CHECK);
+                write_varint(&mut bytes, *frame);
+                bytes.extend(hash.to_le_bytes());
+            }
+            NetMessage::ResyncRequest { from_frame } => {
+                bytes.push(RESYNC_REQUEST);
+                write_varint(&mut bytes, *from_frame);
+            }
+            NetMessage::ResyncInputs {
+                client_idx,
+                player_idx,
+                frame,
+                controls,
+            } => {
+                bytes.extend([RESYNC_INPUTS, *client_idx, *player_idx]);
+                write_controls(&mut bytes, *frame, controls);
+            }
+            NetMessage::ResyncPlacements {
+                client_idx,
+                frame,
+                placements,
+            } => {
+                bytes.extend([RESYNC_PLACEMENTS, *client_idx]);
+                write_varint(&mut bytes, *frame);
+                write_varint(&mut bytes, placements.len() as u64);
+                for (entity_idx, translation, rotation) in placements {
+                    write_varint(&mut bytes, u64::from(*entity_idx));
+                    for value in translation
+                        .to_array()
+                        .into_iter()
+                        .chain(rotation.to_array())
+                    {
+                        bytes.extend(value.to_le_bytes());
+                    }
+                }
+            }
+            NetMessage::ResyncDone {
+                client_idx,
+                from_frame,
+            } => {
+                bytes.extend([RESYNC_DONE, *client_idx]);
+                write_varint(&mut bytes, *from_frame);
+            }
+            NetMessage::MatchSettings { input_delay_frames } => {
+                bytes.push(MATCH_SETTINGS);
+                write_varint(&mut bytes, *input_delay_frames);
+            }
+        }
+        bytes
+    }
+
+    /// Decode a message that was encoded with [`encode`][Self::encode].
+    pub fn decode(bytes: &[u8]) -> Result<Self, MessageError> {
+        let mut reader = Reader(bytes);
+        let version = reader.u8()?;
+        if version != PROTOCOL_VERSION {
+            return Err(MessageError::UnsupportedVersion {
+                found: version,
+                expected: PROTOCOL_VERSION,
+            });
+        }
+
+        Ok(match reader.u8()? {
+            JOIN => NetMessage::Join {
+                player_idx: reader.u8()?,
+            },
+            LEAVE => NetMessage::Leave {
+                player_idx: reader.u8()?,
+            },
+            INPUTS => {
+                let player_idx = reader.u8()?;
+                let (frame, controls) = reader.controls()?;
+                NetMessage::Inputs {
+                    player_idx,
+                    frame,
+                    controls,
+                }
+            }
+            INPUTS_REQUEST => NetMessage::InputsRequest {
+                player_idx: reader.u8()?,
+                from_frame: reader.varint()?,
+            },
+            SYNC_CHECK => NetMessage::SyncCheck {
+                frame: reader.varint()?,
+                hash: u64::from_le_bytes(reader.bytes()?),
+            },
+            RESYNC_REQUEST => NetMessage::ResyncRequest {
+                from_frame: reader.varint()?,
+            },
+            RESYNC_INPUTS => {
+                let client_idx = reader.u8()?;
+                let player_idx = reader.u8()?;
+                let (frame, controls) = reader.controls()?;
+                NetMessage::ResyncInputs {
+                    client_idx,
+                    player_idx,
+                    frame,
+                    controls,
+                }
+            }
+            RESYNC_PLACEMENTS => {
+                let client_idx = reader.u8()?;
+                let frame = reader.varint()?;
+                let count = reader.varint()?;
+                // Don't trust the count to allocate with, it may be garbage
+                let mut placements = Vec::new();
+                for _ in 0..count {
+                    let entity_idx = u32::try_from(reader.varint()?)
+                        .map_err(|_| MessageError::OutOfRange("entity index"))?;
+                    let translation = vec3(reader.f32()?, reader.f32()?, reader.f32()?);
+                    let rotation =
+                        Quat::from_xyzw(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?);
+                    placements.push((entity_idx, translation, rotation));
+                }
+                NetMessage::ResyncPlacements {
+                    client_idx,
+                    frame,
+                    placements,
+                }
+            }
+            RESYNC_DONE => NetMessage::ResyncDone {
+                client_idx: reader.u8()?,
+                from_frame: reader.varint()?,
+            },
+            MATCH_SETTINGS => NetMessage::MatchSettings {
+                input_delay_frames: reader.varint()?,
+            },
+            kind => return Err(MessageError::UnknownKind(kind)),
+        })
+    }
+}
+
+/// Write a number in as few bytes as it needs, seven bits at a time.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Write the controls for the frames up to and including `frame`.
+fn write_controls(bytes: &mut Vec<u8>, frame: u64, controls: &[PlayerControl]) {
+    write_varint(bytes, frame);
+    write_varint(bytes, controls.len() as u64);
+    for control in controls {
+        write_control(bytes, control);
+    }
+}
+
+/// Write the held buttons and directions of a control.
+///
+/// The presses, like `jump_just_pressed`, aren't written, because they can be worked out from the
+/// controls of the frame before. The directions are written exactly, instead of being quantized,
+/// so that every client simulates the same controls as the client that they came from.
+fn write_control(bytes: &mut Vec<u8>, control: &PlayerControl) {
+    let mut flags = 0u16;
+    for (held, flag) in [
+        (control.moving, MOVING),
+        (control.jump_pressed, JUMP),
+        (control.shoot_pressed, SHOOT),
+        (control.grab_pressed, GRAB),
+        (control.slide_pressed, SLIDE),
+        (control.block_pressed, BLOCK),
+        (control.taunt_pressed, TAUNT),
+        (control.emote.is_some(), HAS_EMOTE),
+        (control.move_direction != Vec2::ZERO, HAS_MOVE_DIRECTION),
+        (control.aim_direction != Vec2::ZERO, HAS_AIM_DIRECTION),
+    ] {
+        if held {
+            flags |= flag;
+        }
+    }
+    bytes.extend(flags.to_le_bytes());
+
+    if let Some(emote) = control.emote {
+        bytes.push(emote);
+    }
+    for direction in [control.move_direction, control.aim_direction] {
+        if direction != Vec2::ZERO {
+            bytes.extend(direction.x.to_le_bytes());
+            bytes.extend(direction.y.to_le_bytes());
+        }
+    }
+}
+
+/// Helper for reading the parts of an encoded message.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], MessageError> {
+        if self.0.len() < N {
+            return Err(MessageError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, MessageError> {
+        self.bytes::<1>().map(|x| x[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, MessageError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MessageError::Truncated)
+    }
+
+    fn direction(&mut self, present: bool) -> Result<Vec2, MessageError> {
+        if !present {
+            return Ok(Vec2::ZERO);
+        }
+        let x = f32::from_le_bytes(self.bytes()?);
+        let y = f32::from_le_bytes(self.bytes()?);
+        Ok(vec2(x, y))
+    }
+
+    fn f32(&mut self) -> Result<f32, MessageError> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+
+    fn controls(&mut self) -> Result<(u64, Vec<PlayerControl>), MessageError> {
+        let frame = self.varint()?;
+        let count = self.varint()?;
+        // Don't trust the count to allocate with, it may be garbage
+        let mut controls = Vec::new();
+        for _ in 0..count {
+            controls.push(self.control()?);
+        }
+        Ok((frame, controls))
+    }
+
+    fn control(&mut self) -> Result<PlayerControl, MessageError> {
+        let flags = u16::from_le_bytes(self.bytes()?);
+        let emote = if flags & HAS_EMOTE != 0 {
+            Some(self.u8()?)
+        } else {
+            None
+        };
+        let move_direction = self.direction(flags & HAS_MOVE_DIRECTION != 0)?;
+        let aim_direction = self.direction(flags & HAS_AIM_DIRECTION != 0)?;
+        Ok(PlayerControl {
+            move_direction,
+            moving: flags & MOVING != 0,
+            jump_pressed: flags & JUMP != 0,
+            shoot_pressed: flags & SHOOT != 0,
+            grab_pressed: flags & GRAB != 0,
+            slide_pressed: flags & SLIDE != 0,
+            block_pressed: flags & BLOCK != 0,
+            taunt_pressed: flags & TAUNT != 0,
+            emote,
+            aim_direction,
+            ..default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn messages_survive_encoding() {
+        let control = PlayerControl {
+            move_direction: vec2(0.3, -1.0),
+            moving: true,
+            jump_pressed: true,
+            emote: Some(2),
+            ..default()
+        };
+        let messages = [
+            NetMessage::Join { player_idx: 1 },
+            NetMessage::Inputs {
+                player_idx: 1,
+                frame: 300,
+                controls: vec![PlayerControl::default(), control],
+            },
+            NetMessage::InputsRequest {
+                player_idx: 1,
+                from_frame: 296,
+            },
+            NetMessage::SyncCheck {
+                frame: 300,
+                hash: 0xdead_beef_1234,
+            },
+            NetMessage::ResyncPlacements {
+                client_idx: 1,
+                frame: 240,
+                placements: vec![(3, vec3(12.5, -3.0, 1.0), Quat::from_rotation_z(0.5))],
+            },
+            NetMessage::ResyncDone {
+                client_idx: 1,
+                from_frame: 240,
+            },
+            NetMessage::MatchSettings {
+                input_delay_frames: 3,
+            },
+        ];
+        for message in messages {
+            assert_eq!(NetMessage::decode(&message.encode()), Ok(message));
+        }
+
+        // A control with nothing held fits in the flags
+        let inputs = NetMessage::Inputs {
+            player_idx: 0,
+            frame: 1,
+            controls: vec![default()],
+        };
+        assert_eq!(inputs.encode().len(), 7);
+
+        let mut old = inputs.encode();
+        old[0] = 0;
+        assert_eq!(
+            NetMessage::decode(&old),
+            Err(MessageError::UnsupportedVersion {
+                found: 0,
+                expected: PROTOCOL_VERSION
+            })
+        );
+        assert_eq!(
+            NetMessage::decode(&inputs.encode()[..4]),
+            Err(MessageError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//! The [`Transport`] that carries messages between the clients of a networked match, and its
+//! backends.
+
+/// A message received from another client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Received {
+    /// The index of the client that sent the message.
+    pub from: usize,
+    pub bytes: Vec<u8>,
+}
+
+/// A way of sending messages to, and receiving them from, the other clients in a networked match.
+///
+/// Clients are identified by their index in the match, and messages are sent to all of the other
+/// clients. The transport only moves bytes around, the messages themselves are encoded by the
+/// [`NetMessage`][super::NetMessage].
+pub trait Transport: Send + Sync {
+    /// The index of this client in the match.
+    fn client_idx(&self) -> usize;
+    /// Send a message that must arrive, in the order that it was sent in.
+    fn send_reliable(&self, message: &[u8]);
+    /// Send a message that may be lost, or arrive out of order, but is sent without waiting for
+    /// the messages before it.
+    fn send_unreliable(&self, message: &[u8]);
+    /// Take the next reliable message that has arrived, if any.
+    fn recv_reliable(&self) -> Option<Received>;
+    /// Take the next unreliable message that has arrived, if any.
+    fn recv_unreliable(&self) -> Option<Received>;
+    /// Whether the client with the given index is still connected to this one.
+    ///
+    /// Once a client has disconnected, no more messages are sent to it or received from it.
+    fn is_connected(&self, client_idx: usize) -> bool;
+}
+
+#[cfg(test)]
+pub use loopback::LoopbackTransport;
+
+/// A [`Transport`] between sessions in the same process, for testing networked matches.
+#[cfg(test)]
+mod loopback {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use bevy::prelude::default;
+
+    use super::*;
+
+    /// The messages waiting to be received by each client of a [`LoopbackTransport`].
+    #[derive(Default)]
+    struct Mailboxes {
+        reliable: Vec<VecDeque<Received>>,
+        unreliable: Vec<VecDeque<Received>>,
+    }
+
+    impl Mailboxes {
+        fn post(&mut self, reliable: bool, from: usize, message: &[u8]) {
+            let mailboxes = if reliable {
+                &mut self.reliable
+            } else {
+                &mut self.unreliable
+            };
+            for (client_idx, mailbox) in mailboxes.iter_mut().enumerate() {
+                if client_idx != from {
+                    mailbox.push_back(Received {
+                        from,
+                        bytes: message.to_vec(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// A [`Transport`] between clients in the same process, that delivers every message immediately
+    /// and in order.
+    ///
+    /// This lets several sessions play a networked match against each other in tests, without any
+    /// sockets, and deterministically.
+    #[derive(Clone)]
+    pub struct LoopbackTransport {
+        client_idx: usize,
+        mailboxes: Arc<Mutex<Mailboxes>>,
+    }
+
+    impl LoopbackTransport {
+        /// Create the transports of `client_count` clients that are connected to each other.
+        pub fn connect(client_count: usize) -> Vec<Self> {
+            let mailboxes = Arc::new(Mutex::new(Mailboxes {
+                reliable: vec![default(); client_count],
+                unreliable: vec![default(); client_count],
+            }));
+            (0..client_count)
+                .map(|client_idx| Self {
+                    client_idx,
+                    mailboxes: mailboxes.clone(),
+                })
+                .collect()
+        }
+    }
+
+    impl Transport for LoopbackTransport {
+        fn client_idx(&self) -> usize {
+            self.client_idx
+        }
+
+        fn send_reliable(&self, message: &[u8]) {
+            let mut mailboxes = self.mailboxes.lock().unwrap();
+            mailboxes.post(true, self.client_idx, message);
+        }
+
+        fn send_unreliable(&self, message: &[u8]) {
+            let mut mailboxes = self.mailboxes.lock().unwrap();
+            mailboxes.post(false, self.client_idx, message);
+        }
+
+        fn recv_reliable(&self) -> Option<Received> {
+            let mut mailboxes = self.mailboxes.lock().unwrap();
+            mailboxes.reliable[self.client_idx].pop_front()
+        }
+
+        fn recv_unreliable(&self) -> Option<Received> {
+            let mut mailboxes = self.mailboxes.lock().unwrap();
+            mailboxes.unreliable[self.client_idx].pop_front()
+        }
+
+        fn is_connected(&self, _client_idx: usize) -> bool {
+            true
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use udp::UdpTransport;
+
+/// A [`Transport`] that sends UDP datagrams straight to the other clients.
+///
+/// Other backends, such as one on top of Steam's networking, would implement the trait in the
+/// same way.
+#[cfg(not(target_arch = "wasm32"))]
+mod udp {
+    use std::{
+        collections::{BTreeMap, VecDeque},
+        io,
+        net::{SocketAddr, UdpSocket},
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use bevy::prelude::{default, warn};
+
+    use super::*;
+
+    /// How long to wait for a reliable message to be acknowledged before sending it again.
+    const RESEND_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// The number of times that a reliable message is sent again before giving up on the client
+    /// that it is sent to, and disconnecting from it.
+    const MAX_RESENDS: u32 = 50;
+
+    /// How far ahead of the next reliable message expected from a client the messages that arrive
+    /// early may be numbered, to be held on to until it arrives.
+    ///
+    /// Messages further ahead are dropped without being acknowledged, so they are sent again later.
+    const EARLY_WINDOW: u32 = 1024;
+
+    /// The largest datagram that is received.
+    const MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+
+    // The first byte of each datagram, saying what kind it is.
+    const UNRELIABLE: u8 = 0;
+    const RELIABLE: u8 = 1;
+    const ACK: u8 = 2;
+
+    /// A [`Transport`] over UDP.
+    ///
+    /// Reliable messages are numbered, and sent again until the receiving client acknowledges
+    /// them. The receiving client hands them out in order of their numbers, holding on to the ones
+    /// that arrived before the messages in front of them. The numbers wrap around, so they are
+    /// compared by how far apart they are instead of by their size.
+    ///
+    /// A client that doesn't acknowledge a message after it has been sent [`MAX_RESENDS`] times is
+    /// disconnected from.
+    ///
+    /// The socket is polled whenever messages are received.
+    pub struct UdpTransport {
+        client_idx: usize,
+        socket: UdpSocket,
+        /// The address of each client, by its index, including this one.
+        addresses: Vec<SocketAddr>,
+        /// The number of times that a reliable message is sent again before giving up.
+        max_resends: u32,
+        state: Mutex<UdpState>,
+    }
+
+    /// A reliable message that hasn't been acknowledged yet.
+    struct Unacknowledged {
+        /// When the message was last sent.
+        sent_at: Instant,
+        /// The number of times that the message has been sent again.
+        resends: u32,
+        datagram: Vec<u8>,
+    }
+
+    #[derive(Default)]
+    struct UdpState {
+        /// The number of the next reliable message sent to each client.
+        next_sent: Vec<u32>,
+        /// The reliable messages sent to each client that haven't been acknowledged yet, by their
+        /// number.
+        unacknowledged: Vec<BTreeMap<u32, Unacknowledged>>,
+        /// The number of the next reliable message expected from each client.
+        next_received: Vec<u32>,
+        /// The reliable messages from each client that arrived before the ones in front of them.
+        early: Vec<BTreeMap<u32, Vec<u8>>>,
+        /// Whether each client has been disconnected from.
+        disconnected: Vec<bool>,
+        reliable: VecDeque<Received>,
+        unreliable: VecDeque<Received>,
+        /// The buffer that datagrams are received into.
+        buffer: Vec<u8>,
+    }
+
+    impl UdpTransport {
+        /// Bind to the address of this client in `addresses`, which are the addresses of all of
+        /// the clients in the match, by their index.
+        pub fn bind(client_idx: usize, addresses: Vec<SocketAddr>) -> io::Result<Self> {
+            let socket = UdpSocket::bind(addresses[client_idx])?;
+            socket.set_nonblocking(true)?;
+            let client_count = addresses.len();
+            Ok(Self {
+                client_idx,
+                socket,
+                addresses,
+                max_resends: MAX_RESENDS,
+                state: Mutex::new(UdpState {
+                    next_sent: vec![0; client_count],
+                    unacknowledged: vec![default(); client_count],
+                    next_received: vec![0; client_count],
+                    early: vec![default(); client_count],
+                    disconnected: vec![false; client_count],
+                    buffer: vec![0; MAX_DATAGRAM_SIZE],
+                    ..default()
+                }),
+            })
+        }
+
+        fn send_to(&self, client_idx: usize, datagram: &[u8]) {
+            if let Err(e) = self.socket.send_to(datagram, self.addresses[client_idx]) {
+                warn!("Error sending datagram to client {client_idx}: {e}");
+            }
+        }
+
+        fn other_clients(&self) -> impl Iterator<Item = usize> + '_ {
+            (0..self.addresses.len()).filter(|&x| x != self.client_idx)
+        }
+
+        /// Read the datagrams that have arrived, and send the reliable messages that haven't been
+        /// acknowledged in time again.
+        fn poll(&self, state: &mut UdpState) {
+            loop {
+                let (len, address) = match self.socket.recv_from(&mut state.buffer) {
+                    Ok(x) => x,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        warn!("Error receiving datagram: {e}");
+                        break;
+                    }
+                };
+                let Some(from) = self.addresses.iter().position(|x| *x == address) else {
+                    warn!("Ignoring datagram from unknown address {address}");
+                    continue;
+                };
+                if state.disconnected[from] {
+                    continue;
+                }
+                let datagram = &state.buffer[..len];
+                match datagram {
+                    [UNRELIABLE, message @ ..] => state.unreliable.push_back(Received {
+                        from,
+                        bytes: message.to_vec(),
+                    }),
+                    [RELIABLE, a, b, c, d, message @ ..] => {
+                        let number = u32::from_le_bytes([*a, *b, *c, *d]);
+                        // Messages from before the next one have already been received, and are
+                        // only acknowledged again
+                        let ahead = number.wrapping_sub(state.next_received[from]);
+                        let already_received = ahead > u32::MAX / 2;
+                        if !already_received && ahead >= EARLY_WINDOW {
+                            continue;
+                        }
+                        let mut ack = vec![ACK];
+                        ack.extend(number.to_le_bytes());
+                        self.send_to(from, &ack);
+
+                        if !already_received {
+                            state.early[from].insert(number, message.to_vec());
+                        }
+                        while let Some(message) =
+                            state.early[from].remove(&state.next_received[from])
+                        {
+                            state.next_received[from] = state.next_received[from].wrapping_add(1);
+                            state.reliable.push_back(Received {
+                                from,
+                                bytes: message,
+                            });
+                        }
+                    }
+                    [ACK, a, b, c, d] => {
+                        state.unacknowledged[from].remove(&u32::from_le_bytes([*a, *b, *c, *d]));
+                    }
+                    _ => warn!("Ignoring malformed datagram from client {from}"),
+                }
+            }
+
+            let now = Instant::now();
+            for client_idx in self.other_clients() {
+                let mut gave_up = false;
+                for message in state.unacknowledged[client_idx].values_mut() {
+                    if now - message.sent_at < RESEND_INTERVAL {
+                        continue;
+                    }
+                    if message.resends >= self.max_resends {
+                        gave_up = true;
+                        break;
+                    }
+                    message.sent_at = now;
+                    message.resends += 1;
+                    self.send_to(client_idx, &message.datagram);
+                }
+
+                if gave_up {
+                    warn!("Client {client_idx} stopped acknowledging messages, disconnecting");
+                    state.disconnected[client_idx] = true;
+                    state.unacknowledged[client_idx].clear();
+                    state.early[client_idx].clear();
+                }
+            }
+        }
+    }
+
+    impl Transport for UdpTransport {
+        fn client_idx(&self) -> usize {
+            self.client_idx
+        }
+
+        fn send_reliable(&self, message: &[u8]) {
+            let mut state = self.state.lock().unwrap();
+            for client_idx in self.other_clients() {
+                if state.disconnected[client_idx] {
+                    continue;
+                }
+                let number = state.next_sent[client_idx];
+                state.next_sent[client_idx] = number.wrapping_add(1);
+
+                let mut datagram = vec![RELIABLE];
+                datagram.extend(number.to_le_bytes());
+                datagram.extend(message);
+                self.send_to(client_idx, &datagram);
+                let message = Unacknowledged {
+                    sent_at: Instant::now(),
+                    resends: 0,
+                    datagram,
+                };
+                state.unacknowledged[client_idx].insert(number, message);
+            }
+        }
+
+        fn send_unreliable(&self, message: &[u8]) {
+            let mut datagram = vec![UNRELIABLE];
+            datagram.extend(message);
+            let state = self.state.lock().unwrap();
+            for client_idx in self.other_clients() {
+                if !state.disconnected[client_idx] {
+                    self.send_to(client_idx, &datagram);
+                }
+            }
+        }
+
+        fn recv_reliable(&self) -> Option<Received> {
+            let mut state = self.state.lock().unwrap();
+            self.poll(&mut state);
+            state.reliable.pop_front()
+        }
+
+        fn recv_unreliable(&self) -> Option<Received> {
+            let mut state = self.state.lock().unwrap();
+            self.poll(&mut state);
+            state.unreliable.pop_front()
+        }
+
+        fn is_connected(&self, client_idx: usize) -> bool {
+            let state = self.state.lock().unwrap();
+            state.disconnected.get(client_idx) == Some(&false)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        /// Bind the transport of client 0, with a plain socket standing in for client 1, that the
+        /// datagrams are sent from by hand.
+        fn connect() -> (UdpTransport, UdpSocket) {
+            let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+            peer.set_read_timeout(Some(Duration::from_millis(10)))
+                .unwrap();
+            let addresses = vec!["127.0.0.1:0".parse().unwrap(), peer.local_addr().unwrap()];
+            let transport = UdpTransport::bind(0, addresses).unwrap();
+            peer.connect(transport.socket.local_addr().unwrap())
+                .unwrap();
+            (transport, peer)
+        }
+
+        /// Receive the next datagram sent to the peer, if one arrives in time.
+        fn recv(peer: &UdpSocket) -> Option<Vec<u8>> {
+            let mut buffer = [0; 64];
+            let len = peer.recv(&mut buffer).ok()?;
+            Some(buffer[..len].to_vec())
+        }
+
+        fn reliable(number: u32, message: &[u8]) -> Vec<u8> {
+            let mut datagram = vec![RELIABLE];
+            datagram.extend(number.to_le_bytes());
+            datagram.extend(message);
+            datagram
+        }
+
+        fn ack(number: u32) -> Vec<u8> {
+            let mut datagram = vec![ACK];
+            datagram.extend(number.to_le_bytes());
+            datagram
+        }
+
+        #[test]
+        fn reliable_messages_are_acknowledged_and_put_in_order() {
+            let (transport, peer) = connect();
+
+            // The second message overtakes the first
+            peer.send(&reliable(1, b"second")).unwrap();
+            peer.send(&reliable(0, b"first")).unwrap();
+            let mut received = Vec::new();
+            let mut acks = Vec::new();
+            for _ in 0..100 {
+                received.extend(transport.recv_reliable().map(|x| x.bytes));
+                acks.extend(recv(&peer));
+                if received.len() == 2 && acks.len() == 2 {
+                    break;
+                }
+            }
+            assert_eq!(received, [b"first".to_vec(), b"second".to_vec()]);
+            acks.sort();
+            assert_eq!(acks, [ack(0), ack(1)]);
+
+            // A message sent again, because its acknowledgement was lost, is only acknowledged
+            // again
+            peer.send(&reliable(0, b"first")).unwrap();
+            let acked = (0..100).find_map(|_| {
+                assert_eq!(transport.recv_reliable(), None);
+                recv(&peer)
+            });
+            assert_eq!(acked, Some(ack(0)));
+            assert_eq!(transport.recv_reliable(), None);
+        }
+
+        #[test]
+        fn unacknowledged_messages_are_sent_again() {
+            let (transport, peer) = connect();
+
+            transport.send_reliable(b"hello");
+            let sent_at = Instant::now();
+            assert_eq!(recv(&peer), Some(reliable(0, b"hello")));
+
+            // Until the peer acknowledges it, the message is sent again whenever the transport is
+            // polled after the resend interval
+            let resent = (0..100).find_map(|_| {
+                transport.recv_unreliable();
+                recv(&peer)
+            });
+            assert_eq!(resent, Some(reliable(0, b"hello")));
+            assert!(sent_at.elapsed() >= RESEND_INTERVAL);
+
+            peer.send(&ack(0)).unwrap();
+            let polled_at = Instant::now();
+            while polled_at.elapsed() < RESEND_INTERVAL * 3 {
+                transport.recv_unreliable();
+                assert_eq!(recv(&peer), None);
+            }
+        }
+
+        #[test]
+        fn reliable_message_numbers_wrap_around() {
+            let (transport, peer) = connect();
+            transport.state.lock().unwrap().next_received[1] = u32::MAX;
+
+            // A message too far ahead of the next one is neither held on to nor acknowledged, and
+            // the numbers wrap around after the next one
+            peer.send(&reliable(EARLY_WINDOW, b"too early")).unwrap();
+            peer.send(&reliable(0, b"second")).unwrap();
+            peer.send(&reliable(u32::MAX, b"first")).unwrap();
+            let mut received = Vec::new();
+            let mut acks = Vec::new();
+            for _ in 0..100 {
+                received.extend(transport.recv_reliable().map(|x| x.bytes));
+                acks.extend(recv(&peer));
+                if received.len() == 2 && acks.len() == 2 {
+                    break;
+                }
+            }
+            assert_eq!(received, [b"first".to_vec(), b"second".to_vec()]);
+            acks.sort();
+            assert_eq!(acks, [ack(0), ack(u32::MAX)]);
+            assert!(transport.state.lock().unwrap().early[1].is_empty());
+        }
+
+        #[test]
+        fn client_that_never_acknowledges_is_disconnected() {
+            let (mut transport, peer) = connect();
+            transport.max_resends = 2;
+
+            transport.send_reliable(b"hello");
+            let mut sent = Vec::new();
+            let sent_at = Instant::now();
+            while transport.is_connected(1) && sent_at.elapsed() < RESEND_INTERVAL * 10 {
+                transport.recv_unreliable();
+                sent.extend(recv(&peer));
+            }
+            assert!(!transport.is_connected(1));
+            assert_eq!(sent, vec![reliable(0, b"hello"); 3]);
+
+            // Nothing is sent to it anymore
+            transport.send_reliable(b"goodbye");
+            transport.send_unreliable(b"goodbye");
+            assert_eq!(recv(&peer), None);
+        }
+    }
+}
@@ -49,12 +49,12 @@ pub struct ClientMatchInfo {
 }
 
 bitfield::bitfield! {
-    /// A player's controller inputs densely packed into a single u16.
+    /// A player's controller inputs densely packed into a single u64.
     ///
     /// This is used when sending player inputs across the network.
     #[derive(bytemuck::Pod, bytemuck::Zeroable, Copy, Clone, PartialEq, Eq, Reflect)]
     #[repr(transparent)]
-    pub struct DensePlayerControl(u32);
+    pub struct DensePlayerControl(u64);
     impl Debug;
     jump_pressed, set_jump_pressed: 0;
     shoot_pressed, set_shoot_pressed: 1;
@@ -66,17 +66,23 @@ bitfield::bitfield! {
     /// This value is only relevant if `wants_to_set_pause` is true, and it indicates whether the
     /// player wants to pause or unpause the game.
     pause_value, set_pause_value: 17;
+    from into DenseMoveDirection, aim_direction, set_aim_direction: 29, 18;
+    block_pressed, set_block_pressed: 30;
+    taunt_pressed, set_taunt_pressed: 31;
+    /// The emote picked from the emote wheel on this frame, plus one, or zero if none was picked.
+    u8, emote, set_emote: 34, 32;
 }
 
 impl Default for DensePlayerControl {
     fn default() -> Self {
         let mut control = Self(0);
         control.set_move_direction(default());
+        control.set_aim_direction(default());
         control
     }
 }
 
-/// A newtype around [`Vec2`] that implements [`From<u16>`] and [`Into<u16>`] as a way to compress
+/// A newtype around [`Vec2`] that implements [`From<u64>`] and [`Into<u64>`] as a way to compress
 /// user stick input for use in [`DensePlayerControl`].
 #[derive(Debug, Deref, DerefMut, Default)]
 struct DenseMoveDirection(pub Vec2);
@@ -85,14 +91,14 @@ struct DenseMoveDirection(pub Vec2);
 /// [`DenseMoveDirection`].
 type MoveDirQuant = Quantized<IntRange<u32, 0b111111, -1, 1>>;
 
-impl From<u32> for DenseMoveDirection {
-    fn from(bits: u32) -> Self {
+impl From<u64> for DenseMoveDirection {
+    fn from(bits: u64) -> Self {
         // maximum movement value representable, we use 6 bits to represent each movement direction.
         let max = 0b111111;
         // The first six bits represent the x movement
-        let x_move_bits = bits & max;
+        let x_move_bits = (bits & max) as u32;
         // The second six bits represents the y movement
-        let y_move_bits = (bits >> 6) & max;
+        let y_move_bits = ((bits >> 6) & max) as u32;
 
         // Round near-zero values to zero
         let mut x = MoveDirQuant::from_raw(x_move_bits).to_f32();
@@ -108,10 +114,10 @@ impl From<u32> for DenseMoveDirection {
     }
 }
 
-impl From<DenseMoveDirection> for u32 {
+impl From<DenseMoveDirection> for u64 {
     fn from(dir: DenseMoveDirection) -> Self {
-        let x_bits = MoveDirQuant::from_f32(dir.x).raw();
-        let y_bits = MoveDirQuant::from_f32(dir.y).raw();
+        let x_bits = MoveDirQuant::from_f32(dir.x).raw() as u64;
+        let y_bits = MoveDirQuant::from_f32(dir.y).raw() as u64;
 
         x_bits | (y_bits << 6)
     }
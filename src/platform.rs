@@ -18,7 +18,13 @@ pub struct JumpyPlatformPlugin;
 impl Plugin for JumpyPlatformPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Storage>()
-            .add_system(load_storage.run_in_state(EngineState::LoadingPlatformStorage));
+            .add_system(load_storage.run_in_state(EngineState::LoadingPlatformStorage))
+            .add_system(apply_settings.run_if_resource_exists::<Settings>())
+            .add_system(
+                save_settings
+                    .run_if_resource_exists::<Settings>()
+                    .after(apply_settings),
+            );
     }
 }
 
@@ -46,9 +52,95 @@ pub fn load_storage(
     }
 }
 
+/// Update the resources and the window that the [`Settings`] configure to match them, whenever they
+/// are changed.
+fn apply_settings(
+    settings: Res<Settings>,
+    mut volume_settings: ResMut<VolumeSettings>,
+    mut input_settings: ResMut<InputSettings>,
+    mut windows: ResMut<Windows>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    // Only touch the resources that changed, so that they don't look changed to their systems
+    if *volume_settings != settings.volume {
+        *volume_settings = settings.volume;
+    }
+    if *input_settings != settings.input {
+        *input_settings = settings.input.clone();
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = if settings.display.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+}
+
+/// Save the [`Settings`] to storage whenever they are changed.
+fn save_settings(settings: Res<Settings>, mut storage: ResMut<Storage>) {
+    // The settings are written back when they are first loaded, see `GameLoader`
+    if settings.is_changed() && !settings.is_added() {
+        storage.set(Settings::STORAGE_KEY, &*settings);
+        storage.save();
+    }
+}
+
 /// The type of the inner data in [`Storage`]
 type StorageData = HashMap<String, serde_yaml::Value>;
 
+/// The version of the layout of the [`Storage`] data, which is bumped whenever what is stored
+/// changes in a way that older data has to be migrated.
+pub const STORAGE_VERSION: u64 = 1;
+
+/// The key that the version of the [`Storage`] data is stored under.
+const VERSION_KEY: &str = "version";
+
+/// The migrations that upgrade the [`Storage`] data from each version to the next, starting from
+/// the data that was stored before it had a version.
+const MIGRATIONS: [fn(&mut StorageData); STORAGE_VERSION as usize] = [move_volume_into_settings];
+
+/// Move the volume settings, which used to be stored on their own, into the [`Settings`].
+fn move_volume_into_settings(data: &mut StorageData) {
+    let Some(volume) = data.remove("volume_settings") else {
+        return;
+    };
+    let settings = data
+        .entry(Settings::STORAGE_KEY.into())
+        .or_insert_with(|| serde_yaml::Mapping::new().into());
+    if let serde_yaml::Value::Mapping(settings) = settings {
+        settings.insert("volume".into(), volume);
+    }
+}
+
+/// Upgrade the [`Storage`] data to the [`STORAGE_VERSION`], returning whether it was changed.
+///
+/// Data from a newer version of the game is left as it is.
+fn migrate_storage(data: &mut StorageData) -> bool {
+    let version = data
+        .get(VERSION_KEY)
+        .and_then(|x| x.as_u64())
+        .unwrap_or_default();
+    if version >= STORAGE_VERSION {
+        if version > STORAGE_VERSION {
+            warn!("Storage is from a newer version of the game, some settings may not load");
+        }
+        return false;
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(data);
+    }
+    data.insert(VERSION_KEY.into(), STORAGE_VERSION.into());
+    true
+}
+
 /// Resource for accessing platform specific persistent storage apis through a simple interface.
 #[derive(Resource)]
 pub struct Storage {
@@ -87,10 +179,17 @@ impl Storage {
         // If we are waiting on a data load response
         if let Some(receiver) = &mut self.data_receiver {
             // If the data has been loaded
-            if let Ok(data) = receiver.try_recv() {
+            if let Ok(mut data) = receiver.try_recv() {
                 // Set the local data and clear the load receiver
+                let migrated = migrate_storage(&mut data);
                 self.data = Some(data);
                 self.data_receiver = None;
+
+                // Rewrite storage that was missing, unreadable, or from an older version, so that
+                // it is valid on the next load
+                if migrated {
+                    self.try_save().ok();
+                }
             }
         }
     }
@@ -275,10 +374,7 @@ enum StorageRequest {
 /// Native platform support
 #[cfg(not(target_arch = "wasm32"))]
 mod native {
-    use std::{
-        fs,
-        io::{Read, Write},
-    };
+    use std::{fs, io::Write};
 
     use async_channel::Sender;
     use bevy::{
@@ -310,23 +406,26 @@ mod native {
                     match request {
                         StorageRequest::Load { result_sender } => {
                             let data = if file_path.exists() {
-                                let mut file = fs::OpenOptions::new()
-                                    .read(true)
-                                    .open(&file_path)
-                                    .expect("Open storage file");
-
-                                let mut contents = Vec::new();
-                                file.read_to_end(&mut contents).expect("Read storage file");
+                                let data = fs::read(&file_path)
+                                    .map_err(|e| e.to_string())
+                                    .and_then(|contents| {
+                                        serde_yaml::from_slice(&contents).map_err(|e| e.to_string())
+                                    });
 
-                                match serde_yaml::from_slice(&contents) {
+                                match data {
                                     Ok(data) => data,
                                     Err(e) => {
+                                        // Keep the unreadable file around, in case the user wants
+                                        // to recover anything from it
+                                        let backup_path = file_path.with_extension("yml.bak");
                                         error!(
-                                            "Error deserializing storage file. Ignoring existing \
-                                            data and overwriting on next attempt to save: {}",
+                                            "Error reading storage file, moving it to {} and \
+                                            starting over with the default settings: {}",
+                                            backup_path.display(),
                                             e
                                         );
-                                        Default::default()
+                                        fs::rename(&file_path, backup_path).ok();
+                                        HashMap::new()
                                     }
                                 }
                             } else {
@@ -396,8 +495,8 @@ mod wasm {
                                 Ok(data) => data,
                                 Err(e) => {
                                     error!(
-                                        "Error deserializing storage, ignoring existing data \
-                                        and overwriting on next attempt to set data in storage: {}",
+                                        "Error deserializing storage, starting over with the \
+                                        default settings: {}",
                                         e
                                     );
 
@@ -427,3 +526,34 @@ mod wasm {
         sender
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_old_storage_is_migrated() {
+        let mut data = StorageData::default();
+        data.insert(
+            "volume_settings".into(),
+            serde_yaml::to_value(VolumeSettings {
+                music: 0.5,
+                ..default()
+            })
+            .unwrap(),
+        );
+
+        assert!(migrate_storage(&mut data));
+        assert_eq!(data[VERSION_KEY], serde_yaml::Value::from(STORAGE_VERSION));
+        assert!(!data.contains_key("volume_settings"));
+        let volume =
+            serde_yaml::from_value::<VolumeSettings>(data[Settings::STORAGE_KEY]["volume"].clone())
+                .unwrap();
+        assert_eq!(volume.music, 0.5);
+
+        // Up to date storage is left alone
+        let migrated = data.clone();
+        assert!(!migrate_storage(&mut data));
+        assert_eq!(data, migrated);
+    }
+}
@@ -4,11 +4,15 @@ pub use crate::{
     camera::*,
     config::*,
     debug::*,
+    headless::*,
     input::*,
+    kill_cam::*,
     loading::*,
     localization::*,
     metadata::*,
     platform::*,
+    replay::*,
+    rollback::*,
     session::*,
     ui::{input::MenuAction, *},
     *,
@@ -0,0 +1,753 @@
+//! Recording and playback of player inputs.
+//!
+//! Because the game simulation is deterministic, a match can be replayed exactly by starting a
+//! session with the same seed, map, and players, and feeding it the same inputs on every frame.
+
+use std::collections::BTreeMap;
+
+use jumpy_core::{input::PlayerControl, session::SessionSnapshot};
+
+use crate::prelude::*;
+
+pub struct JumpyReplayPlugin;
+
+impl Plugin for JumpyReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputRecorder>()
+            .init_resource::<InputPlayback>()
+            .insert_resource(ReplayPlayer::new(ENGINE_CONFIG.replay_keyframe_interval));
+    }
+}
+
+/// The version of the [`Replay`] file format.
+///
+/// This must be bumped whenever the layout of the replay file changes.
+pub const REPLAY_FORMAT_VERSION: u32 = 1;
+
+/// The file extension of [`Replay`] files.
+pub const REPLAY_EXTENSION: &str = "jumpyreplay";
+
+/// Errors that may occur while saving or loading an [`InputRecording`] or a [`Replay`].
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    #[error("Error reading or writing recording file: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Error serializing or deserializing recording: {0}")]
+    SerializationError(#[from] serde_yaml::Error),
+    #[error("Replay file format version {found} is not supported, expected version {expected}")]
+    UnsupportedFormat { found: u32, expected: u32 },
+    #[error(
+        "Replay was recorded with metadata version {found}, but the game has version {expected}, \
+        so it wouldn't play back the same way"
+    )]
+    IncompatibleMetadata { found: u32, expected: u32 },
+    #[error(
+        "Replay was recorded at {found} frames per second, but the game is running at {expected}, \
+        so it wouldn't play back the same way"
+    )]
+    MismatchedSimulationRate { found: f32, expected: f32 },
+    #[error("The map `{0}` that the replay was recorded on could not be found")]
+    MapNotFound(String),
+    #[error("Player {player_idx} selected player number {selection}, but there are only {count}")]
+    PlayerNotFound {
+        player_idx: usize,
+        selection: usize,
+        count: usize,
+    },
+    #[error("Player {player_idx} is playing a character that isn't in the game's metadata")]
+    UnknownPlayer { player_idx: usize },
+}
+
+/// The player controls for every frame of a game session.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InputRecording {
+    /// The random seed of the recorded session.
+    pub seed: u64,
+    /// The number of frames that the controls were delayed by before they took effect, see
+    /// [`NetSettings::input_delay_frames`][crate::netplay::NetSettings::input_delay_frames].
+    #[serde(default)]
+    pub input_delay_frames: u64,
+    /// The controls for each player, by frame number, from before they were delayed.
+    pub frames: BTreeMap<u64, Vec<PlayerControl>>,
+}
+
+impl InputRecording {
+    /// Save the recording to a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &std::path::Path) -> Result<(), ReplayError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_yaml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Load a recording from a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> Result<Self, ReplayError> {
+        let contents = std::fs::read(path)?;
+
+        Ok(serde_yaml::from_slice(&contents)?)
+    }
+
+    /// Get the path that recordings are saved to by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn default_path() -> std::path::PathBuf {
+        let project_dirs = directories::ProjectDirs::from("org", "FishFolk", "Jumpy")
+            .expect("Identify system data dir path");
+        project_dirs.data_dir().join("recording.yml")
+    }
+}
+
+/// A complete recording of a match, that can be shared and played back on another machine with the
+/// same game assets.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Replay {
+    /// The version of the replay file format, see [`REPLAY_FORMAT_VERSION`].
+    pub format_version: u32,
+    /// The [`CoreMeta::version`] of the metadata the match was played with.
+    pub metadata_version: u32,
+    /// The name of the map that the match was played on.
+    pub map: String,
+    /// The index in the core metadata's player list of the player selected by each player, if they
+    /// were playing.
+    pub players: Vec<Option<usize>>,
+    /// The color accent picked by each player, if any.
+    #[serde(default)]
+    pub player_colors: Vec<Option<ColorMeta>>,
+    /// The team that each player was on, if teams were being played.
+    #[serde(default)]
+    pub player_teams: Vec<Option<u8>>,
+    /// Whether players could hurt their teammates.
+    #[serde(default)]
+    pub friendly_fire: FriendlyFire,
+    /// The rules of the match.
+    #[serde(default)]
+    pub mode: MatchMode,
+    /// The number of simulation frames per second that the match was played at.
+    #[serde(default = "default_fps")]
+    pub fps: f32,
+    /// The seed and the inputs of the match.
+    pub recording: InputRecording,
+}
+
+fn default_fps() -> f32 {
+    jumpy_core::FPS
+}
+
+/// The beginning of a replay file, used to check the format version before trying to load the rest.
+#[derive(Deserialize)]
+struct ReplayHeader {
+    format_version: u32,
+}
+
+impl Replay {
+    /// Create a replay of a session from the recording of it's inputs.
+    ///
+    /// Players are stored by their index in the core metadata, so this fails if a player's
+    /// character isn't listed there.
+    pub fn new(info: &GameSessionInfo, recording: InputRecording) -> Result<Self, ReplayError> {
+        let players = info
+            .player_info
+            .iter()
+            .enumerate()
+            .map(|(player_idx, selected)| {
+                selected
+                    .as_ref()
+                    .map(|selected| {
+                        info.meta
+                            .players
+                            .iter()
+                            .position(|player| player.path == selected.path)
+                            .ok_or(ReplayError::UnknownPlayer { player_idx })
+                    })
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            format_version: REPLAY_FORMAT_VERSION,
+            metadata_version: info.meta.version,
+            map: info.map_meta.name.clone(),
+            players,
+            player_colors: info.player_colors.to_vec(),
+            player_teams: info.player_teams.to_vec(),
+            friendly_fire: info.friendly_fire,
+            mode: info.mode,
+            fps: info.fps,
+            recording,
+        })
+    }
+
+    /// Save the replay to a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &std::path::Path) -> Result<(), ReplayError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_yaml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Load a replay from a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path) -> Result<Self, ReplayError> {
+        let contents = std::fs::read(path)?;
+
+        let header: ReplayHeader = serde_yaml::from_slice(&contents)?;
+        if header.format_version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedFormat {
+                found: header.format_version,
+                expected: REPLAY_FORMAT_VERSION,
+            });
+        }
+
+        Ok(serde_yaml::from_slice(&contents)?)
+    }
+
+    /// Get the path that replays are saved to by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn default_path() -> std::path::PathBuf {
+        let project_dirs = directories::ProjectDirs::from("org", "FishFolk", "Jumpy")
+            .expect("Identify system data dir path");
+        project_dirs
+            .data_dir()
+            .join("replay")
+            .with_extension(REPLAY_EXTENSION)
+    }
+
+    /// Get the info needed to start a session that plays out like the recorded one, and the
+    /// playback that will feed it the recorded inputs.
+    ///
+    /// `fps` is the simulation rate that the game runs sessions at. Replays can only be played back
+    /// at the rate they were recorded at.
+    pub fn playback(
+        self,
+        core_meta: &Arc<CoreMeta>,
+        map_assets: &Assets<MapMeta>,
+        fps: f32,
+    ) -> Result<(GameSessionInfo, InputPlayback), ReplayError> {
+        if self.metadata_version != core_meta.version {
+            return Err(ReplayError::IncompatibleMetadata {
+                found: self.metadata_version,
+                expected: core_meta.version,
+            });
+        }
+        if self.fps != fps {
+            return Err(ReplayError::MismatchedSimulationRate {
+                found: self.fps,
+                expected: fps,
+            });
+        }
+
+        let map_meta = core_meta
+            .stable_maps
+            .iter()
+            .chain(&core_meta.experimental_maps)
+            .filter_map(|handle| map_assets.get(&handle.get_bevy_handle()))
+            .find(|map| map.name == self.map)
+            .cloned()
+            .ok_or_else(|| ReplayError::MapNotFound(self.map.clone()))?;
+
+        let mut player_info = <[Option<bones::Handle<PlayerMeta>>; MAX_PLAYERS]>::default();
+        for (player_idx, selection) in self.players.iter().enumerate().take(MAX_PLAYERS) {
+            let Some(selection) = *selection else {
+                continue;
+            };
+            let player = core_meta
+                .players
+                .get(selection)
+                .ok_or(ReplayError::PlayerNotFound {
+                    player_idx,
+                    selection,
+                    count: core_meta.players.len(),
+                })?;
+            player_info[player_idx] = Some(player.clone());
+        }
+
+        let mut player_colors = <[Option<ColorMeta>; MAX_PLAYERS]>::default();
+        for (color, recorded) in player_colors.iter_mut().zip(&self.player_colors) {
+            *color = *recorded;
+        }
+        let mut player_teams = <[Option<u8>; MAX_PLAYERS]>::default();
+        for (team, recorded) in player_teams.iter_mut().zip(&self.player_teams) {
+            *team = *recorded;
+        }
+
+        let info = GameSessionInfo {
+            meta: core_meta.clone(),
+            map_meta,
+            player_info,
+            player_colors,
+            player_teams,
+            friendly_fire: self.friendly_fire,
+            mode: self.mode,
+            seed: self.recording.seed,
+            fps: self.fps,
+        };
+        let mut playback = InputPlayback::default();
+        playback.start(self.recording);
+
+        Ok((info, playback))
+    }
+}
+
+/// Resource used to record the player inputs of a game session.
+#[derive(Resource, Default)]
+pub struct InputRecorder {
+    recording: Option<InputRecording>,
+}
+
+impl InputRecorder {
+    /// Start recording the inputs of a session with the given seed, that are delayed by
+    /// `input_delay_frames`.
+    ///
+    /// This should be called right before the session is started, so that the recording starts on
+    /// the first frame.
+    pub fn start(&mut self, seed: u64, input_delay_frames: u64) {
+        self.recording = Some(InputRecording {
+            seed,
+            input_delay_frames,
+            ..default()
+        });
+    }
+
+    /// Stop recording, returning the recording if we were recording.
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    /// Whether or not we are currently recording.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Record the player controls for the given frame, if we are recording.
+    ///
+    /// If the frame has already been recorded, it is overwritten.
+    pub fn record(&mut self, frame: u64, controls: Vec<PlayerControl>) {
+        if let Some(recording) = &mut self.recording {
+            recording.frames.insert(frame, controls);
+        }
+    }
+}
+
+/// Resource used to feed recorded inputs back into a game session, instead of the live controls.
+#[derive(Resource, Default)]
+pub struct InputPlayback {
+    recording: Option<InputRecording>,
+}
+
+impl InputPlayback {
+    /// Start playing back a recording.
+    ///
+    /// The session should be restarted with the recording's seed, map, and players, for the
+    /// replay to match the recorded session.
+    pub fn start(&mut self, recording: InputRecording) {
+        self.recording = Some(recording);
+    }
+
+    /// Stop playing back the recording.
+    pub fn stop(&mut self) {
+        self.recording = None;
+    }
+
+    /// Whether or not a recording is being played back.
+    pub fn is_playing(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Get the recorded player controls that take effect on the given frame.
+    ///
+    /// With an [`input_delay_frames`][InputRecording::input_delay_frames], these are the controls
+    /// recorded that many frames before. Nothing is held on the frames before the first recorded
+    /// controls take effect.
+    pub fn controls(&self, frame: u64) -> Option<Vec<PlayerControl>> {
+        let recording = self.recording.as_ref()?;
+        let player_count = recording.frames.get(&frame)?.len();
+        Some(
+            frame
+                .checked_sub(recording.input_delay_frames)
+                .and_then(|frame| recording.frames.get(&frame))
+                .cloned()
+                .unwrap_or_else(|| vec![default(); player_count]),
+        )
+    }
+
+    /// Get the last frame that has recorded controls, if a recording is being played back.
+    pub fn last_frame(&self) -> Option<u64> {
+        self.recording
+            .as_ref()
+            .and_then(|recording| recording.frames.keys().next_back())
+            .copied()
+    }
+}
+
+/// Resource used to seek around in the main session while an [`InputPlayback`] is playing.
+///
+/// As the recording plays, the session is snapshotted every
+/// [`keyframe_interval`][Self::keyframe_interval] frames. Seeking restores the closest of these
+/// keyframes before the target frame, and then fast-simulates the frames in between with the
+/// recorded inputs, so the session ends up exactly as it would have been if it had played up to
+/// the target frame.
+///
+/// Pausing, stepping, and playing back at different speeds work the same way as for any other
+/// session, with the [`SessionManager`] and the [`TimeScale`].
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    /// The number of frames between each keyframe.
+    ///
+    /// Every keyframe holds a copy of the whole session world, so a shorter interval uses more
+    /// memory, but leaves less frames to simulate when seeking.
+    pub keyframe_interval: u64,
+    /// The state of the session before each keyframe was simulated, by frame number.
+    keyframes: BTreeMap<u64, SessionSnapshot>,
+    /// The frame to seek to on the next update, if any.
+    seek_to: Option<u64>,
+}
+
+impl Default for ReplayPlayer {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_KEYFRAME_INTERVAL)
+    }
+}
+
+impl ReplayPlayer {
+    /// The default [`keyframe_interval`][Self::keyframe_interval], five seconds at the default
+    /// simulation rate.
+    pub const DEFAULT_KEYFRAME_INTERVAL: u64 = 300;
+
+    /// Create a replay player that takes a keyframe every `keyframe_interval` frames.
+    pub fn new(keyframe_interval: u64) -> Self {
+        Self {
+            keyframe_interval: keyframe_interval.max(1),
+            keyframes: default(),
+            seek_to: None,
+        }
+    }
+
+    /// Forget all of the keyframes, such as when a different recording starts playing.
+    pub fn reset(&mut self) {
+        self.keyframes.clear();
+        self.seek_to = None;
+    }
+
+    /// Seek the main session to the given frame on the next update.
+    ///
+    /// Frames after the end of the recording seek to it's last frame.
+    pub fn seek(&mut self, frame: u64) {
+        self.seek_to = Some(frame);
+    }
+
+    /// Get the frames that have keyframes, oldest first.
+    pub fn keyframes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.keyframes.keys().copied()
+    }
+
+    /// Snapshot the session if it is on a keyframe that hasn't been taken yet.
+    ///
+    /// This must be called before the session's inputs for the frame are set.
+    pub fn record_keyframe(&mut self, session: &GameSession) {
+        let frame = session.frame();
+        if frame % self.keyframe_interval == 0 {
+            self.keyframes
+                .entry(frame)
+                .or_insert_with(|| session.snapshot());
+        }
+    }
+
+    /// Seek the session to `frame`, using `step` to simulate each frame after setting the recorded
+    /// inputs for it.
+    ///
+    /// The session is restored to the closest keyframe before the target frame, unless it is
+    /// already between that keyframe and the target. Keyframes are taken of the frames that are
+    /// simulated on the way.
+    pub fn seek_session(
+        &mut self,
+        session: &mut GameSession,
+        playback: &InputPlayback,
+        frame: u64,
+        mut step: impl FnMut(&mut GameSession),
+    ) {
+        let Some(last_frame) = playback.last_frame() else {
+            return;
+        };
+        let target = frame.min(last_frame);
+
+        let current = session.frame();
+        match self.keyframes.range(..=target).next_back() {
+            Some((&keyframe, snapshot)) if current < keyframe || current > target => {
+                session.restore(snapshot);
+            }
+            None if current > target => {
+                warn!("Can't seek back to frame {target}, it is before the first keyframe");
+                return;
+            }
+            _ => (),
+        }
+
+        while session.frame() < target {
+            let frame = session.frame();
+            let Some(controls) = playback.controls(frame) else {
+                break;
+            };
+            self.record_keyframe(session);
+            session.update_input(|inputs| {
+                for (input, control) in inputs.players.iter_mut().zip(controls) {
+                    input.control = control;
+                }
+            });
+            step(session);
+        }
+    }
+}
+
+/// Seek the main session to the frame requested from the [`ReplayPlayer`], and take the keyframes
+/// of the [`InputPlayback`] as it plays.
+///
+/// This must run before the inputs of the frame are given to the session.
+pub fn update_replay_player(world: &mut World) {
+    let Some(mut player) = world.remove_resource::<ReplayPlayer>() else {
+        return;
+    };
+    let playback = world.remove_resource::<InputPlayback>().unwrap_or_default();
+    let sessions = world.remove_resource::<Sessions>();
+
+    if let Some(mut sessions) = sessions.filter(|_| playback.is_playing()) {
+        let mut seeked = false;
+        if let Some(session) = sessions.main_mut() {
+            if let Some(frame) = player.seek_to.take() {
+                seeked = true;
+                let start = session.frame();
+                player.seek_session(session, &playback, frame, |session| session.advance(world));
+                info!("Seeked replay from frame {start} to {}", session.frame());
+
+                // Don't play all of the sounds of the skipped frames at once
+                let audio_events = session.world.resource::<jumpy_core::audio::AudioEvents>();
+                audio_events.borrow_mut().queue.clear();
+            } else {
+                player.record_keyframe(session);
+            }
+        }
+
+        // Seeking back from the end of the match unfreezes the session
+        let match_over = sessions
+            .main()
+            .map_or(false, |x| x.match_result().is_some());
+        if seeked && !match_over {
+            sessions.set_paused(SessionId::MAIN, false);
+        }
+        world.insert_resource(sessions);
+    } else {
+        player.reset();
+        if let Some(sessions) = sessions {
+            world.insert_resource(sessions);
+        }
+    }
+
+    world.insert_resource(playback);
+    world.insert_resource(player);
+}
+
+/// Resource containing a script of player controls, for testing gameplay without playing it by
+/// hand.
+///
+/// While the resource is present, the main session takes it's player controls from the script
+/// instead of the live input or an [`InputPlayback`]. A player's control stays the same from the
+/// frame that the script changes it on, until the next change.
+///
+/// Scripts are built with the helper methods, which must be called in frame order:
+///
+/// ```ignore
+/// let script = ScriptedInput::default()
+///     .press_jump(0, 0)
+///     .release_jump(1, 0)
+///     .move_in(1, 0, Vec2::X)
+///     .move_in(30, 0, Vec2::ZERO);
+/// ```
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ScriptedInput {
+    /// The control that each player switches to, by the frame that they switch to it on.
+    pub players: [BTreeMap<u64, PlayerControl>; MAX_PLAYERS],
+}
+
+impl ScriptedInput {
+    /// Change the control of `player` from `frame` on, starting from the control they have on
+    /// that frame.
+    pub fn at(
+        mut self,
+        frame: u64,
+        player: usize,
+        change: impl FnOnce(&mut PlayerControl),
+    ) -> Self {
+        let mut control = self.control(frame, player);
+        change(&mut control);
+        self.players[player].insert(frame, control);
+        self
+    }
+
+    pub fn press_jump(self, frame: u64, player: usize) -> Self {
+        self.at(frame, player, |control| control.jump_pressed = true)
+    }
+
+    pub fn release_jump(self, frame: u64, player: usize) -> Self {
+        self.at(frame, player, |control| control.jump_pressed = false)
+    }
+
+    pub fn press_shoot(self, frame: u64, player: usize) -> Self {
+        self.at(frame, player, |control| control.shoot_pressed = true)
+    }
+
+    pub fn release_shoot(self, frame: u64, player: usize) -> Self {
+        self.at(frame, player, |control| control.shoot_pressed = false)
+    }
+
+    pub fn press_grab(self, frame: u64, player: usize) -> Self {
+        self.at(frame, player, |control| control.grab_pressed = true)
+    }
+
+    pub fn release_grab(self, frame: u64, player: usize) -> Self {
+        self.at(frame, player, |control| control.grab_pressed = false)
+    }
+
+    /// Hold the movement input in `direction`, or let go of it with a zero direction.
+    pub fn move_in(self, frame: u64, player: usize, direction: Vec2) -> Self {
+        self.at(frame, player, |control| control.move_direction = direction)
+    }
+
+    /// Get the scripted control of `player` on `frame`.
+    pub fn control(&self, frame: u64, player: usize) -> PlayerControl {
+        self.players[player]
+            .range(..=frame)
+            .next_back()
+            .map(|(_frame, control)| control.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the last frame that the script changes anything on.
+    pub fn last_frame(&self) -> Option<u64> {
+        self.players
+            .iter()
+            .filter_map(|frames| frames.keys().next_back())
+            .max()
+            .copied()
+    }
+
+    /// Set the player controls to the scripted ones for `frame`, pressing the buttons the same way
+    /// the live input does.
+    pub fn apply(&self, frame: u64, inputs: &mut jumpy_core::input::PlayerInputs) {
+        for (player, input) in inputs.players.iter_mut().enumerate() {
+            let mut control = self.control(frame, player);
+            control.detect_presses(&input.control);
+            input.control = control;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jumpy_core::{
+        input::PlayerInputs,
+        testing::{step, test_session},
+    };
+
+    use super::*;
+
+    /// Play the session from it's current frame up to `frame`, like the game does while the
+    /// recording is playing.
+    fn play(
+        session: &mut GameSession,
+        player: &mut ReplayPlayer,
+        playback: &InputPlayback,
+        to: u64,
+    ) {
+        while session.frame() < to {
+            player.record_keyframe(session);
+            let controls = playback.controls(session.frame()).unwrap();
+            session.update_input(|inputs| {
+                for (input, control) in inputs.players.iter_mut().zip(controls) {
+                    input.control = control;
+                }
+            });
+            step(session);
+        }
+    }
+
+    #[test]
+    fn seeking_matches_continuous_playback() {
+        let mut recording = InputRecording::default();
+        for frame in 0..100u64 {
+            let direction = Vec2::new((frame % 7) as f32 - 3.0, (frame % 3) as f32 - 1.0);
+            let controls = (0..MAX_PLAYERS)
+                .map(|player| PlayerControl {
+                    move_direction: direction * player as f32,
+                    ..default()
+                })
+                .collect();
+            recording.frames.insert(frame, controls);
+        }
+        let mut playback = InputPlayback::default();
+        playback.start(recording);
+
+        let mut reference = test_session();
+        let mut reference_player = ReplayPlayer::new(16);
+        play(&mut reference, &mut reference_player, &playback, 50);
+        let at_50 = reference.state_hashes();
+        play(&mut reference, &mut reference_player, &playback, 90);
+        let at_90 = reference.state_hashes();
+
+        // Seeking forward from the start simulates the recording up to the frame
+        let mut session = test_session();
+        let mut player = ReplayPlayer::new(16);
+        player.seek_session(&mut session, &playback, 50, step);
+        assert_eq!(session.state_hashes(), at_50);
+        assert_eq!(player.keyframes().collect::<Vec<_>>(), [0, 16, 32, 48]);
+
+        // Seeking back restores a keyframe and plays on from there
+        play(&mut session, &mut player, &playback, 70);
+        player.seek_session(&mut session, &playback, 50, step);
+        assert_eq!(session.state_hashes(), at_50);
+        play(&mut session, &mut player, &playback, 90);
+        assert_eq!(session.state_hashes(), at_90);
+
+        // Seeking past the end stops on the last recorded frame
+        player.seek_session(&mut session, &playback, 1000, step);
+        assert_eq!(session.frame(), 99);
+    }
+
+    #[test]
+    fn scripted_controls_last_until_changed() {
+        let script = ScriptedInput::default()
+            .press_jump(2, 0)
+            .move_in(2, 1, Vec2::X)
+            .release_jump(3, 0)
+            .press_shoot(3, 0)
+            .at(5, 1, |control| control.slide_pressed = true)
+            .move_in(10, 1, Vec2::ZERO);
+        assert_eq!(script.last_frame(), Some(10));
+
+        let mut inputs = PlayerInputs::default();
+        let mut pressed_jump = Vec::new();
+        for frame in 0..12 {
+            script.apply(frame, &mut inputs);
+            if inputs.players[0].control.jump_just_pressed {
+                pressed_jump.push(frame);
+            }
+
+            let player_0 = &inputs.players[0].control;
+            let player_1 = &inputs.players[1].control;
+            assert_eq!(player_0.jump_pressed, frame == 2);
+            assert_eq!(player_0.shoot_pressed, frame >= 3);
+            assert_eq!(player_1.just_moved, frame == 2);
+            assert_eq!(player_1.slide_just_pressed, frame == 5);
+            assert_eq!(
+                player_1.move_direction.x,
+                (2..10).contains(&frame) as u8 as f32
+            );
+        }
+        assert_eq!(pressed_jump, [2]);
+        assert_eq!(inputs.players[0].control.shoot_hold_frames, 9);
+    }
+}
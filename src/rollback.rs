@@ -0,0 +1,936 @@
+//! Rollback of the main game session when remote inputs arrive late.
+//!
+//! In a networked game, the inputs of the remote players for a frame often arrive after that frame
+//! has already been simulated. Until they arrive, the remote players are predicted to keep holding
+//! whatever they were last known to hold. When an input arrives that doesn't match the prediction,
+//! the session is restored to the snapshot taken before that frame, and re-simulated up to the
+//! present with the corrected inputs. The entities that the correction moves are eased into their
+//! new positions on screen by the [`RollbackSmoothing`].
+//!
+//! The sounds of the frames that are re-simulated have already been played, so only the ones queued
+//! up by frames that are simulated for the first time are kept. The announcements of re-simulated
+//! frames are all kept, with the frame they were made on, so that the game can skip the ones that it
+//! has already made, but still make the ones that only happen with the corrected inputs.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use jumpy_core::{
+    announcer::{Announcement, Announcer},
+    audio::{AudioEvent, AudioEvents},
+    input::PlayerControl,
+    session::SessionSnapshot,
+};
+
+use crate::prelude::*;
+
+/// Resource containing the rollback tunables.
+#[derive(Resource, Clone, Debug)]
+pub struct RollbackSettings {
+    /// The number of frames that the session may be rolled back by.
+    ///
+    /// Inputs that arrive later than this are dropped. Rollback is disabled when this is `0`.
+    pub max_rollback_frames: usize,
+    /// The furthest, in pixels, that a rollback may move an entity while still being smoothed out
+    /// on screen.
+    ///
+    /// Entities that are corrected by more than this snap to their new position right away.
+    pub max_smoothing_distance: f32,
+}
+
+impl Default for RollbackSettings {
+    fn default() -> Self {
+        Self {
+            max_rollback_frames: 0,
+            max_smoothing_distance: 48.0,
+        }
+    }
+}
+
+impl RollbackSettings {
+    /// Whether the session is being simulated with rollback.
+    pub fn is_enabled(&self) -> bool {
+        self.max_rollback_frames > 0
+    }
+}
+
+/// Resource containing the snapshots and inputs needed to roll back the main game session.
+#[derive(Resource, Default)]
+pub struct Rollback {
+    /// The most recent simulated frames, oldest first.
+    history: VecDeque<RollbackFrame>,
+    /// The frames that the last [`advance`][Self::advance] dropped from the history, oldest first.
+    confirmed: Vec<RollbackFrame>,
+    /// The known player controls, by the frame they are for.
+    inputs: BTreeMap<u64, [Option<PlayerControl>; MAX_PLAYERS]>,
+    /// The latest controls of each player that are older than any frame in `inputs`.
+    base_controls: [Option<PlayerControl>; MAX_PLAYERS],
+    /// The earliest frame that must be re-simulated, if any.
+    rollback_to: Option<u64>,
+    /// Statistics about the recent rollbacks.
+    stats: RollbackStats,
+}
+
+/// Statistics about the recent rollbacks, for diagnosing laggy networked matches.
+#[derive(Clone, Debug)]
+pub struct RollbackStats {
+    /// The simulation rate of the session that is being rolled back, in frames per second.
+    pub fps: f32,
+    /// The number of frames that were re-simulated on each of the most recent frames, oldest
+    /// first.
+    pub depths: VecDeque<u64>,
+    /// The number of frames that the latest input of each remote player arrived late by, or `None`
+    /// for players that no input has been received from.
+    pub input_lateness: [Option<u64>; MAX_PLAYERS],
+}
+
+impl Default for RollbackStats {
+    fn default() -> Self {
+        Self {
+            fps: jumpy_core::FPS,
+            depths: default(),
+            input_lateness: default(),
+        }
+    }
+}
+
+impl RollbackStats {
+    /// The number of seconds that the rollback depths are kept for.
+    pub const HISTORY_SECONDS: f32 = 4.0;
+
+    /// The number of frames that the rollback depths are kept for.
+    pub fn history_frames(&self) -> usize {
+        (Self::HISTORY_SECONDS * self.fps) as usize
+    }
+
+    /// The total number of frames that were re-simulated over the last second.
+    pub fn rollback_frames_last_second(&self) -> u64 {
+        self.depths.iter().rev().take(self.fps as usize).sum()
+    }
+
+    /// Convert a number of frames into milliseconds.
+    pub fn frames_to_ms(&self, frames: u64) -> f32 {
+        frames as f32 * 1000.0 / self.fps
+    }
+
+    fn push_depth(&mut self, depth: u64) {
+        self.depths.push_back(depth);
+        while self.depths.len() > self.history_frames() {
+            self.depths.pop_front();
+        }
+    }
+}
+
+/// A frame in the rollback ring buffer.
+struct RollbackFrame {
+    /// The frame number.
+    frame: u64,
+    /// The state of the session before the frame was simulated.
+    snapshot: SessionSnapshot,
+    /// The controls the frame was simulated with.
+    controls: Vec<PlayerControl>,
+}
+
+/// The sounds and announcements that a session has queued up, but the game hasn't played yet.
+struct QueuedOutput {
+    audio: VecDeque<AudioEvent>,
+    announcements: VecDeque<(u64, Announcement)>,
+}
+
+impl QueuedOutput {
+    /// Take the queued up sounds and announcements out of the session.
+    fn take(session: &GameSession) -> Self {
+        let audio = std::mem::take(&mut session.world.resource::<AudioEvents>().borrow_mut().queue);
+        let announcements =
+            std::mem::take(&mut session.world.resource::<Announcer>().borrow_mut().queue);
+        Self {
+            audio,
+            announcements,
+        }
+    }
+
+    /// Replace whatever sounds the session has queued up with these sounds, and queue up these
+    /// announcements before the ones that the session has made since they were taken.
+    fn put_back(self, session: &GameSession) {
+        session.world.resource::<AudioEvents>().borrow_mut().queue = self.audio;
+        let announcer = session.world.resource::<Announcer>();
+        let mut announcer = announcer.borrow_mut();
+        let resimulated = std::mem::replace(&mut announcer.queue, self.announcements);
+        announcer.queue.extend(resimulated);
+    }
+}
+
+impl Rollback {
+    /// Forget all frames and inputs, such as when the session is restarted.
+    pub fn reset(&mut self) {
+        *self = default();
+    }
+
+    /// Forget all frames and inputs, but keep the statistics, after the session was restarted or
+    /// restored to a different frame than the one that was simulated next.
+    ///
+    /// The inputs for the frames from `frame` on are kept if the session jumped ahead to it, but
+    /// not if it went back, since they were for the frames that came before.
+    fn forget_history(&mut self, frame: u64) {
+        let went_back = self.history.back().map_or(false, |x| frame <= x.frame);
+        let mut inputs = std::mem::take(&mut self.inputs);
+        let stats = std::mem::take(&mut self.stats);
+        self.reset();
+        self.stats = stats;
+        if !went_back {
+            inputs.retain(|x, _| *x >= frame);
+            self.inputs = inputs;
+        }
+    }
+
+    /// Get the statistics about the recent rollbacks.
+    pub fn stats(&self) -> &RollbackStats {
+        &self.stats
+    }
+
+    /// Whether the next [`advance`][Self::advance] will roll back, because of a mispredicted input.
+    pub fn is_rollback_pending(&self) -> bool {
+        self.rollback_to.is_some()
+    }
+
+    /// Get the frames that the last [`advance`][Self::advance] confirmed, oldest first, along with
+    /// the snapshot of the session from before each of them was simulated, and the controls that
+    /// they were simulated with.
+    ///
+    /// A frame is confirmed once it is too old to be rolled back to, so it won't be simulated again
+    /// with different controls.
+    pub fn confirmed_frames(
+        &self,
+    ) -> impl Iterator<Item = (u64, &SessionSnapshot, &[PlayerControl])> + '_ {
+        self.confirmed
+            .iter()
+            .map(|x| (x.frame, &x.snapshot, x.controls.as_slice()))
+    }
+
+    /// Get the snapshot of the session from before the given frame was simulated, if it is recent
+    /// enough to still be kept.
+    pub fn snapshot(&self, frame: u64) -> Option<&SessionSnapshot> {
+        self.history
+            .iter()
+            .find(|x| x.frame == frame)
+            .map(|x| &x.snapshot)
+    }
+
+    /// Add the controls of a local player, collected on the given frame.
+    ///
+    /// The controls will be used `input_delay` frames later.
+    pub fn add_local_input(
+        &mut self,
+        frame: u64,
+        input_delay: u64,
+        player_idx: usize,
+        mut control: PlayerControl,
+    ) {
+        // The session was restarted, so the inputs known so far were from the old match
+        if self.history.back().map_or(false, |x| frame <= x.frame) {
+            self.forget_history(frame);
+        }
+        let frame = frame + input_delay;
+
+        // The presses were detected against the controls of the current frame, but with the
+        // delay, they have to be detected against the controls of the frame before this one.
+        let previous = frame
+            .checked_sub(1)
+            .map(|frame| self.controls(frame)[player_idx].clone())
+            .unwrap_or_default();
+        control.detect_presses(&previous);
+
+        self.inputs.entry(frame).or_default()[player_idx] = Some(control);
+    }
+
+    /// Add the controls of a remote player for the given frame.
+    ///
+    /// If the frame has already been simulated with different controls, the session will be
+    /// rolled back to it on the next [`advance`][Self::advance]. Returns `false` if the frame is
+    /// too old to roll back to, in which case the input is dropped.
+    pub fn add_remote_input(
+        &mut self,
+        frame: u64,
+        player_idx: usize,
+        control: PlayerControl,
+    ) -> bool {
+        let next_frame = self.history.back().map_or(0, |x| x.frame + 1);
+        self.stats.input_lateness[player_idx] = Some(next_frame.saturating_sub(frame));
+
+        if let Some(oldest) = self.history.front() {
+            if frame < oldest.frame {
+                warn!(
+                    "Dropping input for frame {frame}, which is older than the oldest rollback \
+                    frame {}",
+                    oldest.frame
+                );
+                return false;
+            }
+        }
+
+        let mispredicted = self
+            .history
+            .iter()
+            .find(|x| x.frame == frame)
+            .map(|x| x.controls[player_idx] != control)
+            .unwrap_or(false);
+        if mispredicted {
+            self.rollback_to = Some(self.rollback_to.map_or(frame, |x| x.min(frame)));
+        }
+
+        self.inputs.entry(frame).or_default()[player_idx] = Some(control);
+        true
+    }
+
+    /// Get the controls of each player for the given frame.
+    ///
+    /// Players whose controls for the frame aren't known yet are predicted to keep holding what
+    /// they held in their last known controls, without pressing anything new.
+    pub fn controls(&self, frame: u64) -> Vec<PlayerControl> {
+        (0..MAX_PLAYERS)
+            .map(|player_idx| {
+                if let Some(control) = self
+                    .inputs
+                    .get(&frame)
+                    .and_then(|controls| controls[player_idx].clone())
+                {
+                    return control;
+                }
+
+                let last_known = self
+                    .inputs
+                    .range(..frame)
+                    .rev()
+                    .find_map(|(_frame, controls)| controls[player_idx].as_ref())
+                    .or(self.base_controls[player_idx].as_ref());
+                last_known
+                    .map(|control| PlayerControl {
+                        just_moved: false,
+                        jump_just_pressed: false,
+                        shoot_just_pressed: false,
+                        grab_just_pressed: false,
+                        slide_just_pressed: false,
+                        taunt_just_pressed: false,
+                        emote: None,
+                        ..control.clone()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Simulate the next frame of the session with `step`, first rolling back and re-simulating the
+    /// frames that were simulated with the wrong inputs.
+    ///
+    /// Returns the number of frames that were re-simulated.
+    pub fn advance(
+        &mut self,
+        session: &mut GameSession,
+        max_rollback_frames: usize,
+        mut step: impl FnMut(&mut GameSession),
+    ) -> u64 {
+        let frame = session.frame();
+        self.confirmed.clear();
+
+        // The session was restarted or restored, so the history doesn't apply to it anymore
+        if self.history.back().map_or(false, |x| x.frame + 1 != frame) {
+            self.forget_history(frame);
+        }
+
+        let mut resimulated = 0;
+        if let Some(rollback_to) = self.rollback_to.take() {
+            if let Some(idx) = self.history.iter().position(|x| x.frame == rollback_to) {
+                let queued = QueuedOutput::take(session);
+                session.restore(&self.history[idx].snapshot);
+                self.history.truncate(idx);
+                for frame in rollback_to..frame {
+                    self.simulate_frame(session, frame, &mut step);
+                    resimulated += 1;
+                }
+                queued.put_back(session);
+            }
+        }
+
+        self.simulate_frame(session, frame, &mut step);
+        self.stats.fps = session.info.fps;
+        self.stats.push_depth(resimulated);
+
+        // Forget frames that are too old to roll back to
+        while self.history.len() > max_rollback_frames {
+            self.confirmed.extend(self.history.pop_front());
+        }
+        if let Some(oldest) = self.history.front() {
+            let newer = self.inputs.split_off(&oldest.frame);
+            for controls in std::mem::replace(&mut self.inputs, newer).into_values() {
+                for (base, control) in self.base_controls.iter_mut().zip(controls) {
+                    if control.is_some() {
+                        *base = control;
+                    }
+                }
+            }
+        }
+
+        resimulated
+    }
+
+    /// Restore the session to an earlier `snapshot`, and re-simulate it up to the frame that it was
+    /// on with the given `controls`, which replace the ones that were known for those frames.
+    ///
+    /// This is used to bring a session that has gone out of sync back in line with the host's,
+    /// when the frames to re-simulate are too far back to be rolled back to.
+    pub fn resync(
+        &mut self,
+        session: &mut GameSession,
+        snapshot: &SessionSnapshot,
+        controls: BTreeMap<u64, [Option<PlayerControl>; MAX_PLAYERS]>,
+        mut step: impl FnMut(&mut GameSession),
+    ) {
+        let frame = session.frame();
+        let queued = QueuedOutput::take(session);
+        session.restore(snapshot);
+
+        for (control_frame, controls) in controls {
+            let known = self.inputs.entry(control_frame).or_default();
+            for (known, control) in known.iter_mut().zip(controls) {
+                if control.is_some() {
+                    *known = control;
+                }
+            }
+        }
+        self.history.clear();
+        self.rollback_to = None;
+        for frame in session.frame()..frame {
+            self.simulate_frame(session, frame, &mut step);
+        }
+        queued.put_back(session);
+    }
+
+    /// Snapshot the session and simulate a frame with the known controls for it.
+    fn simulate_frame(
+        &mut self,
+        session: &mut GameSession,
+        frame: u64,
+        step: &mut impl FnMut(&mut GameSession),
+    ) {
+        let controls = self.controls(frame);
+        session.update_input(|inputs| {
+            for (input, control) in inputs.players.iter_mut().zip(&controls) {
+                input.control = control.clone();
+            }
+        });
+        self.history.push_back(RollbackFrame {
+            frame,
+            snapshot: session.snapshot(),
+            controls,
+        });
+        step(session);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jumpy_core::{
+        player::PlayerIdx,
+        testing::{step, test_session},
+    };
+
+    use crate::netplay::{
+        InputSync, LoopbackTransport, NetMessage, NetSettings, Transport, HOST_CLIENT_IDX,
+    };
+
+    use super::*;
+
+    fn moving_right() -> PlayerControl {
+        PlayerControl {
+            move_direction: Vec2::X,
+            jump_pressed: true,
+            jump_just_pressed: true,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn resimulation_matches_reference() {
+        const FRAMES: u64 = 12;
+        const LATE_FRAME: u64 = 3;
+        const ARRIVAL_FRAME: u64 = 7;
+
+        // The remote input arrives in time
+        let mut reference = test_session();
+        let mut rollback = Rollback::default();
+        for frame in 0..FRAMES {
+            rollback.add_local_input(frame, 0, 0, moving_right());
+            if frame == LATE_FRAME {
+                assert!(rollback.add_remote_input(LATE_FRAME, 1, moving_right()));
+            }
+            assert_eq!(rollback.advance(&mut reference, 8, step), 0);
+        }
+
+        // The remote input arrives several frames late
+        let mut late = test_session();
+        let mut rollback = Rollback::default();
+        let mut resimulated = 0;
+        for frame in 0..FRAMES {
+            rollback.add_local_input(frame, 0, 0, moving_right());
+            if frame == ARRIVAL_FRAME {
+                assert!(rollback.add_remote_input(LATE_FRAME, 1, moving_right()));
+            }
+            resimulated += rollback.advance(&mut late, 8, step);
+        }
+
+        assert_eq!(resimulated, ARRIVAL_FRAME - LATE_FRAME);
+        let stats = rollback.stats();
+        assert_eq!(stats.input_lateness[1], Some(ARRIVAL_FRAME - LATE_FRAME));
+        assert_eq!(stats.input_lateness[2], None);
+        assert_eq!(stats.rollback_frames_last_second(), resimulated);
+        assert_eq!(stats.depths.len(), FRAMES as usize);
+        assert_eq!(late.frame(), reference.frame());
+        assert_eq!(late.state_hashes(), reference.state_hashes());
+
+        // Without the remote input, the session plays out differently
+        let mut missing = test_session();
+        let mut rollback = Rollback::default();
+        for frame in 0..FRAMES {
+            rollback.add_local_input(frame, 0, 0, moving_right());
+            rollback.advance(&mut missing, 8, step);
+        }
+        assert_ne!(missing.state_hash(), reference.state_hash());
+    }
+
+    #[test]
+    fn resimulated_frames_dont_queue_sounds_again() {
+        // Every frame queues up a sound, as if something happened on it
+        let step_with_sound = |session: &mut GameSession| {
+            let frame = session.frame();
+            step(session);
+            let audio_events = session.world.resource::<AudioEvents>();
+            audio_events.borrow_mut().stop_loop(frame);
+        };
+
+        let mut session = test_session();
+        let mut rollback = Rollback::default();
+        let mut played = Vec::new();
+        for frame in 0..12 {
+            rollback.add_local_input(frame, 0, 0, moving_right());
+            if frame == 7 {
+                assert!(rollback.add_remote_input(3, 1, moving_right()));
+            }
+            rollback.advance(&mut session, 8, step_with_sound);
+
+            // The game only gets around to playing the sounds every other frame
+            if frame % 2 == 0 {
+                let audio_events = session.world.resource::<AudioEvents>();
+                played.extend(
+                    audio_events
+                        .borrow_mut()
+                        .queue
+                        .drain(..)
+                        .filter_map(|x| match x {
+                            AudioEvent::StopLoop { handle_id } => Some(handle_id),
+                            // The sounds of the game itself
+                            _ => None,
+                        }),
+                );
+            }
+        }
+
+        // Each frame's sound is played once, even though frames 3 to 6 were simulated twice
+        assert_eq!(played, (0..11).collect::<Vec<_>>());
+        assert_eq!(rollback.stats().depths.iter().sum::<u64>(), 4);
+    }
+
+    #[test]
+    fn resimulated_frames_keep_their_announcements() {
+        // Every frame that player 1 is moving on is announced
+        let step_with_announcement = |session: &mut GameSession| {
+            let frame = session.frame();
+            let moving = session
+                .world
+                .resource::<jumpy_core::input::PlayerInputs>()
+                .borrow()
+                .players[1]
+                .control
+                .move_direction
+                != Vec2::ZERO;
+            step(session);
+            if moving {
+                let announcer = session.world.resource::<Announcer>();
+                announcer
+                    .borrow_mut()
+                    .queue
+                    .push_back((frame, Announcement::LastStock { player_idx: 1 }));
+            }
+        };
+
+        let mut session = test_session();
+        let mut rollback = Rollback::default();
+        let mut announced = Vec::new();
+        for frame in 0..12 {
+            rollback.add_local_input(frame, 0, 0, moving_right());
+            if frame == 7 {
+                assert!(rollback.add_remote_input(3, 1, moving_right()));
+            }
+            rollback.advance(&mut session, 8, step_with_announcement);
+
+            let announcer = session.world.resource::<Announcer>();
+            announced.extend(
+                announcer
+                    .borrow_mut()
+                    .drain()
+                    .filter(|(_, x)| matches!(x, Announcement::LastStock { player_idx: 1 }))
+                    .map(|(frame, _)| frame),
+            );
+        }
+
+        // Player 1 was only found to have been moving since frame 3 on frame 7, but those frames
+        // are still announced
+        assert_eq!(announced, (3..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn restarting_forgets_the_old_match() {
+        let mut session = test_session();
+        let mut rollback = Rollback::default();
+        for frame in 0..10 {
+            rollback.add_local_input(frame, 2, 0, moving_right());
+            assert!(rollback.add_remote_input(frame, 1, moving_right()));
+            rollback.advance(&mut session, 4, step);
+        }
+
+        // The players aren't holding anything at the start of the new match
+        session = test_session();
+        rollback.add_local_input(0, 2, 0, PlayerControl::default());
+        rollback.advance(&mut session, 4, step);
+        assert!(rollback
+            .controls(1)
+            .iter()
+            .all(|x| *x == PlayerControl::default()));
+        assert!(rollback
+            .controls(2)
+            .iter()
+            .all(|x| *x == PlayerControl::default()));
+        assert!(rollback
+            .controls(20)
+            .iter()
+            .all(|x| *x == PlayerControl::default()));
+    }
+
+    #[test]
+    fn inputs_older_than_max_rollback_are_dropped() {
+        let mut session = test_session();
+        let mut rollback = Rollback::default();
+        for _ in 0..10 {
+            rollback.advance(&mut session, 4, step);
+        }
+
+        assert!(!rollback.add_remote_input(5, 1, moving_right()));
+        assert!(rollback.add_remote_input(6, 1, moving_right()));
+        assert_eq!(rollback.advance(&mut session, 4, step), 4);
+    }
+
+    #[test]
+    fn local_input_is_delayed() {
+        let mut rollback = Rollback::default();
+        rollback.add_local_input(0, 2, 0, moving_right());
+
+        assert_eq!(rollback.controls(1)[0], PlayerControl::default());
+        let control = &rollback.controls(2)[0];
+        assert_eq!(control.move_direction, Vec2::X);
+        assert!(control.jump_just_pressed);
+        // The press isn't repeated while the control is predicted
+        assert!(rollback.controls(3)[0].jump_pressed);
+        assert!(!rollback.controls(3)[0].jump_just_pressed);
+    }
+
+    /// Get where the first player is along the x axis.
+    fn player_x(session: &mut GameSession) -> f32 {
+        session
+            .world
+            .run_initialized_system(
+                |entities: bones::Res<bones::Entities>,
+                 player_indexes: bones::Comp<PlayerIdx>,
+                 transforms: bones::Comp<bones::Transform>| {
+                    Ok(entities
+                        .iter_with((&player_indexes, &transforms))
+                        .find(|(_ent, (idx, _))| idx.0 == 0)
+                        .map_or(0.0, |(_ent, (_, transform))| transform.translation.x))
+                },
+            )
+            .unwrap()
+    }
+
+    /// Simulate a frame with the given controls, returning where the first player ends up.
+    fn step_with(session: &mut GameSession, controls: Vec<PlayerControl>) -> f32 {
+        session.update_input(|inputs| {
+            for (input, control) in inputs.players.iter_mut().zip(controls) {
+                input.control = control;
+            }
+        });
+        step(session);
+        player_x(session)
+    }
+
+    #[test]
+    fn input_delay_shifts_when_controls_take_effect() {
+        const DELAY: u64 = 3;
+        let frames = 0..12;
+        let collected = || {
+            let mut controls = vec![PlayerControl::default(); MAX_PLAYERS];
+            controls[0] = moving_right();
+            controls
+        };
+
+        // Where the first player is on each frame when they don't hold anything
+        let mut session = test_session();
+        let standing_still = frames
+            .clone()
+            .map(|_| step_with(&mut session, vec![PlayerControl::default(); MAX_PLAYERS]))
+            .collect::<Vec<_>>();
+
+        // The first player holds right from the first frame on, but only starts moving once the
+        // controls take effect. Without rollback, the controls are held back before they are given
+        // to the session.
+        let mut session = test_session();
+        let mut input_delay = InputDelay::default();
+        let expected = frames
+            .clone()
+            .map(|frame| step_with(&mut session, input_delay.delay(frame, DELAY, collected())))
+            .collect::<Vec<_>>();
+        assert_eq!(expected[..DELAY as usize], standing_still[..DELAY as usize]);
+        assert_ne!(
+            expected[DELAY as usize + 1..],
+            standing_still[DELAY as usize + 1..]
+        );
+
+        // With rollback, the rollback delays them
+        let mut session = test_session();
+        let mut rollback = Rollback::default();
+        let positions = frames
+            .clone()
+            .map(|frame| {
+                rollback.add_local_input(frame, DELAY, 0, moving_right());
+                rollback.advance(&mut session, 8, step);
+                player_x(&mut session)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions, expected);
+
+        // And a replay plays back with the delay that it was recorded with
+        let mut recording = InputRecording {
+            input_delay_frames: DELAY,
+            ..default()
+        };
+        for frame in frames.clone() {
+            recording.frames.insert(frame, collected());
+        }
+        let mut playback = InputPlayback::default();
+        playback.start(recording);
+        let mut session = test_session();
+        let positions = frames
+            .map(|frame| step_with(&mut session, playback.controls(frame).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, expected);
+    }
+
+    /// A client in a networked match between sessions in the same process.
+    type TestClient<'a> = (&'a LoopbackTransport, GameSession, Rollback, InputSync);
+
+    fn connect_clients(
+        transports: &[LoopbackTransport],
+        sync_check_interval: u64,
+    ) -> Vec<TestClient<'_>> {
+        transports
+            .iter()
+            .map(|transport| {
+                let mut sync = InputSync {
+                    sync_check_interval,
+                    ..default()
+                };
+                if transport.client_idx() == HOST_CLIENT_IDX {
+                    sync.share_net_settings(
+                        transport,
+                        NetSettings {
+                            input_delay_frames: 1,
+                        },
+                    );
+                }
+                sync.join(transport, transport.client_idx());
+                (transport, test_session(), Rollback::default(), sync)
+            })
+            .collect()
+    }
+
+    /// Play a frame of the match, where each client has one player, who changes direction part way
+    /// through, and `laggy_client` only hears from the others every few frames.
+    fn play_frame(clients: &mut [TestClient], frame: u64, laggy_client: usize) {
+        for (transport, session, rollback, sync) in clients {
+            let client_idx = transport.client_idx();
+            if client_idx != laggy_client || frame % 4 == 0 {
+                sync.receive(*transport, rollback);
+                sync.apply_resync(session, rollback, step);
+            }
+            let control = if frame < 12 + client_idx as u64 {
+                moving_right()
+            } else {
+                PlayerControl {
+                    move_direction: Vec2::NEG_X,
+                    ..default()
+                }
+            };
+            sync.add_local_input(rollback, frame, client_idx, control);
+            sync.send_inputs(*transport);
+            rollback.advance(session, 8, step);
+            sync.update_sync_checks(*transport, session, rollback);
+        }
+    }
+
+    /// Let all of the inputs and sync checks that are still on their way arrive.
+    fn finish_match(clients: &mut [TestClient]) {
+        for (transport, session, rollback, sync) in &mut *clients {
+            sync.receive(*transport, rollback);
+            rollback.advance(session, 8, step);
+            sync.update_sync_checks(*transport, session, rollback);
+        }
+        for (transport, _, rollback, sync) in clients {
+            sync.receive(*transport, rollback);
+        }
+    }
+
+    #[test]
+    fn clients_stay_in_sync_over_loopback() {
+        let transports = LoopbackTransport::connect(2);
+        let mut clients = connect_clients(&transports, 8);
+        for frame in 0..30 {
+            play_frame(&mut clients, frame, 1);
+        }
+        finish_match(&mut clients);
+
+        // The second client had to roll back when the first player turned around, and once all of
+        // the inputs have arrived, the sessions end up in the same state
+        for (_, _, _, sync) in &clients {
+            assert_eq!(sync.net_settings().input_delay_frames, 1);
+            assert_eq!(sync.confirmed_frame(), Some(30));
+            assert_eq!(sync.desync_frame, None);
+        }
+        assert_eq!(clients[0].1.state_hashes(), clients[1].1.state_hashes());
+        assert!(clients[1].2.stats().depths.iter().sum::<u64>() > 0);
+    }
+
+    #[test]
+    fn remote_inputs_are_only_taken_from_the_client_that_owns_the_player() {
+        let transports = LoopbackTransport::connect(3);
+        let mut clients = connect_clients(&transports, 0);
+        let send = |client_idx: usize, player_idx: u8, frame: u64| {
+            let message = NetMessage::Inputs {
+                player_idx,
+                frame,
+                controls: vec![moving_right()],
+            };
+            transports[client_idx].send_unreliable(&message.encode());
+        };
+
+        // The third client tries to play as the second client's player, and sends inputs for a
+        // frame that no other frame can come after
+        send(2, 1, 0);
+        send(2, 2, u64::MAX);
+        let (transport, _, rollback, sync) = &mut clients[0];
+        sync.receive(*transport, rollback);
+        assert_eq!(sync.confirmed_frame(), None);
+        assert_eq!(rollback.controls(0)[1].move_direction, Vec2::ZERO);
+
+        send(1, 1, 0);
+        send(2, 2, 0);
+        sync.receive(*transport, rollback);
+        assert_eq!(sync.confirmed_frame(), Some(0));
+        assert_eq!(rollback.controls(0)[1].move_direction, Vec2::X);
+    }
+
+    #[test]
+    fn lost_inputs_are_sent_again() {
+        let transports = LoopbackTransport::connect(2);
+        let mut clients = connect_clients(&transports, 0);
+        let (host, client) = clients.split_at_mut(1);
+        let (host, client) = (&mut host[0], &mut client[0]);
+        client.3.receive(client.0, &mut client.2);
+
+        // Only the first and the last of the client's inputs messages make it through
+        for frame in 0..12 {
+            client
+                .3
+                .add_local_input(&mut client.2, frame, 1, moving_right());
+            if frame == 0 || frame == 11 {
+                client.3.send_inputs(client.0);
+            }
+        }
+        host.3.receive(host.0, &mut host.2);
+        assert_eq!(host.3.confirmed_frame(), Some(1));
+
+        // The host asks for the frames in between again
+        client.3.receive(client.0, &mut client.2);
+        host.3.receive(host.0, &mut host.2);
+        assert_eq!(host.3.confirmed_frame(), Some(12));
+    }
+
+    /// Move the first player in the session along the x axis, outside of the simulation.
+    fn knock_out_of_place(session: &mut GameSession, distance: f32) {
+        session
+            .world
+            .run_initialized_system(
+                |entities: bones::Res<bones::Entities>,
+                 player_indexes: bones::Comp<PlayerIdx>,
+                 mut transforms: bones::CompMut<bones::Transform>| {
+                    for (_ent, (idx, transform)) in
+                        entities.iter_with((&player_indexes, &mut transforms))
+                    {
+                        if idx.0 == 0 {
+                            transform.translation.x += distance;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn out_of_sync_client_resyncs_with_host() {
+        let transports = LoopbackTransport::connect(2);
+        let mut clients = connect_clients(&transports, 8);
+        for frame in 0..60 {
+            play_frame(&mut clients, frame, 0);
+
+            // Knock the second client's player out of place, as if it had missed an input
+            if frame == 20 {
+                knock_out_of_place(&mut clients[1].1, 100.0);
+            }
+        }
+        finish_match(&mut clients);
+
+        let sync = &clients[1].3;
+        assert_eq!(sync.resync_count, 1);
+        assert_eq!(sync.desync_frame, None);
+        assert!(!sync.unrecoverable_desync);
+        assert_eq!(clients[0].1.state_hashes(), clients[1].1.state_hashes());
+    }
+
+    #[test]
+    fn non_deterministic_client_gives_up_resyncing() {
+        let transports = LoopbackTransport::connect(2);
+        let mut clients = connect_clients(&transports, 8);
+        for frame in 0..60 {
+            play_frame(&mut clients, frame, 0);
+
+            // The second client's simulation drifts on every frame, as if it weren't deterministic,
+            // so re-simulating it with the host's controls doesn't bring it back in line
+            if frame >= 20 {
+                knock_out_of_place(&mut clients[1].1, 0.5);
+            }
+        }
+        finish_match(&mut clients);
+
+        let sync = &clients[1].3;
+        assert_eq!(sync.resync_count, 1);
+        assert!(sync.unrecoverable_desync);
+        assert!(sync.desync_frame.is_some());
+        assert_ne!(clients[0].1.state_hashes(), clients[1].1.state_hashes());
+    }
+}
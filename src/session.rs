@@ -1,4 +1,15 @@
-use crate::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use bevy::ecs::schedule::ShouldRun;
+use bones_bevy_renderer::BevyBonesEntity;
+
+use crate::{
+    netplay::{NetSettings, Netplay, Transport, NETPLAY_ROLLBACK_FRAMES},
+    prelude::*,
+};
 
 pub struct JumpySessionPlugin;
 
@@ -7,172 +18,3041 @@ pub struct JumpySessionPlugin;
 pub enum SessionStage {
     /// Update the game session.
     Update,
+    /// Move the rendered entities part of the way between their last two simulated transforms.
+    Interpolate,
+    /// Put back the simulated transforms, after the renderer has synced them with the game session.
+    RestoreTransforms,
+    /// Shake the game cameras, after the renderer has synced them with the game session.
+    CameraShake,
 }
 
 impl Plugin for JumpySessionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(bones_bevy_renderer::BonesRendererPlugin::<Session>::with_sync_time(false))
+        app.add_plugin(bones_bevy_renderer::BonesRendererPlugin::<Sessions>::with_sync_time(false))
             .add_plugin(jumpy_core::metadata::JumpyCoreAssetsPlugin)
             .init_resource::<CurrentEditorInput>()
+            .init_resource::<LocalPlayer>()
+            .init_resource::<SessionRunState>()
+            .init_resource::<TimeScale>()
+            .insert_resource(FinalKillSlowMotion {
+                enabled: !ENGINE_CONFIG.no_slow_motion,
+                ..default()
+            })
+            .init_resource::<DefaultPlayerCount>()
+            .insert_resource(DefaultBots(
+                ENGINE_CONFIG
+                    .bot_difficulty
+                    .map(jumpy_core::bot::BotDifficulty),
+            ))
+            .init_resource::<SpectatorMode>()
+            .init_resource::<CameraShake>()
+            .init_resource::<DeterminismAudit>()
+            .init_resource::<RollbackSettings>()
+            .init_resource::<Rollback>()
+            .init_resource::<NetSettings>()
+            .init_resource::<InputDelay>()
+            .init_resource::<RenderInterpolation>()
+            .init_resource::<RollbackSmoothing>()
+            .add_event::<SessionEvent>()
+            .add_system(update_final_kill_slow_motion)
+            .add_system(update_looping_sounds)
+            .add_enter_system(InGameState::Paused, pause_session)
+            .add_exit_system(InGameState::Paused, resume_session)
             .add_stage_before(
                 CoreStage::Update,
                 SessionStage::Update,
                 SystemStage::single_threaded()
                     .with_system(
-                        ensure_2_players
+                        ensure_default_players
                             .run_in_state(EngineState::InGame)
-                            .run_in_state(InGameState::Playing),
+                            .run_in_state(InGameState::Playing)
+                            .run_if(session_should_advance),
                     )
+                    .with_system(update_replay_player)
                     .with_system(update_input)
+                    .with_system(sync_determinism_audit_input)
+                    .with_system(
+                        record_render_interpolation
+                            .run_in_state(EngineState::InGame)
+                            .run_in_state(InGameState::Playing)
+                            .run_if(session_should_advance),
+                    )
                     .with_system(
                         update_game
                             .run_in_state(EngineState::InGame)
-                            .run_in_state(InGameState::Playing),
+                            .run_in_state(InGameState::Playing)
+                            .run_if(session_should_advance),
+                    )
+                    .with_system(
+                        update_kill_cam
+                            .run_in_state(EngineState::InGame)
+                            .run_in_state(InGameState::Playing)
+                            .run_if(session_should_advance),
                     )
+                    .with_system(check_determinism_audit)
+                    .with_system(update_spectator_camera)
+                    .with_system(collect_camera_trauma)
                     .with_system(play_sounds)
-                    .with_run_criteria(FixedTimestep::step(1.0 / jumpy_core::FPS as f64)),
+                    .with_system(play_announcements)
+                    .with_system(update_music_intensity)
+                    .with_run_criteria(session_fixed_timestep),
+            )
+            .add_stage_after(
+                CoreStage::Update,
+                SessionStage::Interpolate,
+                SystemStage::single_threaded().with_system(interpolate_transforms),
+            )
+            .add_stage_after(
+                CoreStage::Last,
+                SessionStage::RestoreTransforms,
+                SystemStage::single_threaded().with_system(restore_simulated_transforms),
+            )
+            .add_stage_after(
+                SessionStage::RestoreTransforms,
+                SessionStage::CameraShake,
+                SystemStage::single_threaded().with_system(apply_camera_shake),
             );
     }
 }
 
-/// A resource containing an in-progress game session.
-#[derive(Resource, Deref, DerefMut)]
+/// An in-progress game session.
+#[derive(Deref, DerefMut)]
 pub struct Session(pub GameSession);
 
-// Give bones_bevy_render plugin access to the bones world in our game session.
-impl bones_bevy_renderer::HasBonesWorld for Session {
+impl Session {
+    /// Get the seed of the session's random number generator.
+    ///
+    /// Restarting the session keeps the same seed, so this can be used to reproduce a match.
+    pub fn seed(&self) -> u64 {
+        self.info.seed
+    }
+
+    /// Get the result of the match being played in this session, once it is over.
+    pub fn match_result(&self) -> Option<jumpy_core::match_mode::MatchResult> {
+        self.world
+            .resource::<jumpy_core::match_mode::MatchState>()
+            .borrow()
+            .result
+    }
+
+    /// Get the ammo of the ranged weapon that each player is holding, if any.
+    pub fn player_ammo(&self) -> [Option<jumpy_core::item::Ammo>; MAX_PLAYERS] {
+        self.world
+            .run_initialized_system(
+                |player_inventories: jumpy_core::item::PlayerInventories,
+                 ammo: bones::Comp<jumpy_core::item::Ammo>| {
+                    Ok(std::array::from_fn(|i| {
+                        player_inventories[i].and_then(|inv| ammo.get(inv.inventory).copied())
+                    }))
+                },
+            )
+            .unwrap()
+    }
+
+    /// Get whether each player is outside of the map's blast zone, about to be knocked out.
+    pub fn players_out_of_bounds(&self) -> [Option<jumpy_core::map::OutOfBounds>; MAX_PLAYERS] {
+        self.world
+            .run_initialized_system(
+                |entities: bones::Res<bones::Entities>,
+                 player_indexes: bones::Comp<jumpy_core::player::PlayerIdx>,
+                 out_of_bounds: bones::Comp<jumpy_core::map::OutOfBounds>| {
+                    let mut players = [None; MAX_PLAYERS];
+                    for (_ent, (player_idx, out_of_bounds)) in
+                        entities.iter_with((&player_indexes, &out_of_bounds))
+                    {
+                        players[player_idx.0] = Some(*out_of_bounds);
+                    }
+                    Ok(players)
+                },
+            )
+            .unwrap()
+    }
+
+    /// Get whether each player's character has been killed, or `None` for the players that don't
+    /// have a character, such as while they wait to respawn.
+    pub fn killed_players(&self) -> [Option<bool>; MAX_PLAYERS] {
+        self.world
+            .run_initialized_system(
+                |entities: bones::Res<bones::Entities>,
+                 player_indexes: bones::Comp<jumpy_core::player::PlayerIdx>,
+                 killed_players: bones::Comp<jumpy_core::player::PlayerKilled>| {
+                    let mut players = [None; MAX_PLAYERS];
+                    for (ent, player_idx) in entities.iter_with(&player_indexes) {
+                        players[player_idx.0] = Some(killed_players.contains(ent));
+                    }
+                    Ok(players)
+                },
+            )
+            .unwrap()
+    }
+
+    /// Get the number of frames that each player has left to wait before respawning, if they are
+    /// waiting to respawn.
+    pub fn player_respawn_delays(&self) -> [Option<u64>; MAX_PLAYERS] {
+        let frame = self.frame();
+        let delays = self
+            .world
+            .resource::<jumpy_core::elements::player_spawner::RespawnDelays>();
+        let delays = delays.borrow();
+        std::array::from_fn(|i| delays.frames_remaining(i, frame))
+    }
+
+    /// Get the number of hits in the combo that each player is taking.
+    pub fn player_combos(&self) -> [u32; MAX_PLAYERS] {
+        self.world
+            .run_initialized_system(
+                |entities: bones::Res<bones::Entities>,
+                 player_indexes: bones::Comp<jumpy_core::player::PlayerIdx>,
+                 combos: bones::Comp<jumpy_core::player::ComboState>| {
+                    let mut players = [0; MAX_PLAYERS];
+                    for (_ent, (player_idx, combo)) in
+                        entities.iter_with((&player_indexes, &combos))
+                    {
+                        players[player_idx.0] = combo.hits;
+                    }
+                    Ok(players)
+                },
+            )
+            .unwrap()
+    }
+
+    /// Whether the match being played in this session is in sudden death.
+    pub fn is_sudden_death(&self) -> bool {
+        self.world
+            .resource::<jumpy_core::match_mode::MatchState>()
+            .borrow()
+            .is_sudden_death()
+    }
+
+    /// How close the match being played in this session is to being decided, see
+    /// [`MatchState::intensity`][jumpy_core::match_mode::MatchState::intensity].
+    pub fn match_intensity(&self) -> f32 {
+        let fps = self.info.fps;
+        self.world
+            .run_initialized_system(
+                move |match_state: bones::Res<jumpy_core::match_mode::MatchState>,
+                      match_mode: bones::Res<MatchMode>,
+                      stocks: bones::Res<jumpy_core::match_mode::Stocks>,
+                      timer: bones::Res<jumpy_core::match_mode::MatchTimer>,
+                      player_inputs: bones::Res<jumpy_core::input::PlayerInputs>| {
+                    Ok(match_state.intensity(*match_mode, &stocks, &timer, &player_inputs, fps))
+                },
+            )
+            .unwrap()
+    }
+
+    /// Get the frame of the kill that decided the match being played in this session, if it has
+    /// been made.
+    pub fn decisive_kill(&self) -> Option<u64> {
+        self.world
+            .resource::<jumpy_core::match_mode::MatchState>()
+            .borrow()
+            .decisive_kill
+    }
+
+    /// Get the stats of each player that is playing in this session, or `None` for the empty
+    /// player slots.
+    pub fn player_stats(&self) -> [Option<jumpy_core::stats::PlayerStats>; MAX_PLAYERS] {
+        self.world
+            .run_initialized_system(
+                |stats: bones::Res<jumpy_core::stats::SessionStats>,
+                 player_inputs: bones::Res<jumpy_core::input::PlayerInputs>| {
+                    Ok(std::array::from_fn(|i| {
+                        player_inputs.players[i]
+                            .active
+                            .then_some(*stats.for_player(i))
+                    }))
+                },
+            )
+            .unwrap()
+    }
+
+    /// Save a [`Replay`] of this session, made from the recording of it's inputs.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_replay(
+        &self,
+        path: &std::path::Path,
+        recording: InputRecording,
+    ) -> Result<(), ReplayError> {
+        Replay::new(&self.info, recording)?.save(path)
+    }
+}
+
+/// The ID of a game session in the [`Sessions`] resource.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionId(pub u32);
+
+impl SessionId {
+    /// The ID of the main game session, the one that the local players are playing in.
+    pub const MAIN: SessionId = SessionId(0);
+
+    /// The ID of the shadow session that the [`DeterminismAudit`] advances alongside the main
+    /// session.
+    pub const AUDIT: SessionId = SessionId(u32::MAX);
+
+    /// The ID of the session that the [`KillCam`] shows the local player's death in.
+    pub const KILL_CAM: SessionId = SessionId(u32::MAX - 1);
+}
+
+/// Resource containing all of the in-progress game sessions.
+///
+/// This resource only exists while there is at least one session. Sessions are started and stopped
+/// with the [`SessionManager`].
+///
+/// Usually there is only the [`MAIN`][SessionId::MAIN] session, but other sessions may be run
+/// alongside it, for instance to compare replays side by side. Only the main session receives the
+/// local player input and plays sounds.
+#[derive(Resource, Default)]
+pub struct Sessions {
+    sessions: BTreeMap<SessionId, Session>,
+    paused: BTreeSet<SessionId>,
+    rendered: SessionId,
+}
+
+impl Sessions {
+    /// Get a session by it's ID.
+    pub fn get(&self, id: SessionId) -> Option<&Session> {
+        self.sessions.get(&id)
+    }
+
+    /// Get a session by it's ID.
+    pub fn get_mut(&mut self, id: SessionId) -> Option<&mut Session> {
+        self.sessions.get_mut(&id)
+    }
+
+    /// Add a session, replacing any session that already has the same ID.
+    ///
+    /// This is usually done with [`SessionManager::start_session`], which also sends the
+    /// [`SessionEvent`]s for it.
+    pub fn insert(&mut self, id: SessionId, session: Session) {
+        self.sessions.insert(id, session);
+        self.paused.remove(&id);
+    }
+
+    /// Remove a session right away, returning it if it existed.
+    ///
+    /// Unlike [`SessionManager::stop_session`], this doesn't send a [`SessionEvent`], so it is
+    /// meant for sessions that only exist for the game's own use, like the [`KillCam`] session.
+    ///
+    /// The renderer needs a session to render, so the last session is never removed, and `None` is
+    /// returned for it instead. Stopping it with the [`SessionManager`] removes the whole resource.
+    pub fn remove(&mut self, id: SessionId) -> Option<Session> {
+        if self.sessions.len() == 1 && self.sessions.contains_key(&id) {
+            return None;
+        }
+        self.paused.remove(&id);
+        self.sessions.remove(&id)
+    }
+
+    /// Get the main session.
+    pub fn main(&self) -> Option<&Session> {
+        self.get(SessionId::MAIN)
+    }
+
+    /// Get the main session.
+    pub fn main_mut(&mut self) -> Option<&mut Session> {
+        self.get_mut(SessionId::MAIN)
+    }
+
+    /// Iterate over the sessions, in order of their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (SessionId, &Session)> {
+        self.sessions.iter().map(|(id, session)| (*id, session))
+    }
+
+    /// Iterate over the sessions, in order of their IDs.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (SessionId, &mut Session)> {
+        self.sessions.iter_mut().map(|(id, session)| (*id, session))
+    }
+
+    /// Whether the given session has been paused on it's own, with
+    /// [`SessionManager::set_session_paused`].
+    pub fn is_paused(&self, id: SessionId) -> bool {
+        self.paused.contains(&id)
+    }
+
+    /// Pause or resume a single session, independently of the others.
+    pub fn set_paused(&mut self, id: SessionId, paused: bool) {
+        if paused {
+            self.paused.insert(id);
+        } else {
+            self.paused.remove(&id);
+        }
+    }
+
+    /// Get the ID of the session that is rendered to the screen.
+    pub fn rendered(&self) -> SessionId {
+        self.rendered
+    }
+
+    /// Set the session that is rendered to the screen.
+    ///
+    /// Only one session can be rendered at a time. If the session doesn't exist, the session with
+    /// the lowest ID is rendered instead.
+    pub fn set_rendered(&mut self, id: SessionId) {
+        self.rendered = id;
+    }
+
+    /// Get the ID of the session that is actually rendered, falling back to the session with the
+    /// lowest ID if the [`rendered`][Self::rendered] one doesn't exist.
+    fn rendered_id(&self) -> SessionId {
+        if self.sessions.contains_key(&self.rendered) {
+            self.rendered
+        } else {
+            *self
+                .sessions
+                .keys()
+                .next()
+                .expect("Sessions resource without any sessions")
+        }
+    }
+
+    /// Get the session that is actually rendered.
+    fn rendered_session_mut(&mut self) -> &mut Session {
+        let id = self.rendered_id();
+        self.sessions.get_mut(&id).unwrap()
+    }
+}
+
+// Give bones_bevy_render plugin access to the bones world of the rendered game session.
+impl bones_bevy_renderer::HasBonesWorld for Sessions {
     fn world(&mut self) -> &mut bones::World {
-        &mut self.0.world
+        &mut self.rendered_session_mut().0.world
+    }
+}
+
+/// Whether or not the game session is paused.
+///
+/// While paused, none of the game sessions advance, but the [`Sessions`] resource is kept around so
+/// that they can be resumed.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SessionRunState {
+    pub paused: bool,
+    /// Whether a single frame should be simulated while the session is paused.
+    pub step_requested: bool,
+}
+
+impl SessionRunState {
+    /// The volume multiplier applied to the session's sound effects while the session is paused.
+    pub const PAUSED_EFFECTS_VOLUME: f32 = 0.3;
+
+    /// Get the volume multiplier to apply to the session's sound effects.
+    pub fn effects_volume(&self) -> f32 {
+        if self.paused {
+            Self::PAUSED_EFFECTS_VOLUME
+        } else {
+            1.0
+        }
+    }
+}
+
+/// The speed that the game session is simulated at, relative to real time.
+///
+/// This can be used to slow down or speed up the game for replays and practice, but networked
+/// sessions must keep it at `1.0`, or the players will get out of sync.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TimeScale(f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl TimeScale {
+    /// The slowest allowed time scale.
+    pub const MIN: f32 = 0.25;
+    /// The fastest allowed time scale.
+    pub const MAX: f32 = 4.0;
+
+    /// Create a time scale, clamped to the range [`MIN`][Self::MIN]..=[`MAX`][Self::MAX].
+    pub fn new(scale: f32) -> Self {
+        Self(scale.clamp(Self::MIN, Self::MAX))
+    }
+
+    /// Set the time scale, clamped to the range [`MIN`][Self::MIN]..=[`MAX`][Self::MAX].
+    pub fn set(&mut self, scale: f32) {
+        *self = Self::new(scale);
+    }
+
+    /// Get the time scale.
+    pub fn get(&self) -> f32 {
+        self.0
+    }
+}
+
+/// Resource for the slow motion that plays after the kill that decides a match, before the session
+/// is frozen and the scoreboard is shown.
+///
+/// The slow motion only changes the [`TimeScale`], and the sounds are pitched down with it. It is
+/// skipped in networked sessions, which must keep running in real time.
+#[derive(Resource, Clone, Debug)]
+pub struct FinalKillSlowMotion {
+    /// Whether to play the slow motion at all.
+    pub enabled: bool,
+    /// The time scale that the game is slowed down to.
+    pub time_scale: f32,
+    /// How long the slow motion lasts, in real time.
+    pub duration: Duration,
+    /// The real time left in the slow motion, and the time scale to go back to after it, while it
+    /// is playing.
+    playing: Option<(Duration, TimeScale)>,
+}
+
+impl Default for FinalKillSlowMotion {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            time_scale: TimeScale::MIN,
+            duration: Duration::from_secs_f32(1.5),
+            playing: None,
+        }
+    }
+}
+
+impl FinalKillSlowMotion {
+    /// Whether the slow motion is playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing.is_some()
+    }
+
+    /// Start the slow motion, unless it is disabled, already playing, or the session is networked.
+    pub fn start(&mut self, time_scale: &mut TimeScale, networked: bool) {
+        if !self.enabled || networked || self.is_playing() {
+            return;
+        }
+        self.playing = Some((self.duration, *time_scale));
+        time_scale.set(self.time_scale);
+    }
+
+    /// Advance the slow motion by the given real time, returning the time scale to go back to once
+    /// it is over.
+    pub fn tick(&mut self, delta: Duration) -> Option<TimeScale> {
+        let (remaining, _) = self.playing.as_mut()?;
+        *remaining = remaining.saturating_sub(delta);
+        if remaining.is_zero() {
+            self.stop()
+        } else {
+            None
+        }
+    }
+
+    /// Stop the slow motion early, returning the time scale to go back to if it was playing.
+    pub fn stop(&mut self) -> Option<TimeScale> {
+        self.playing.take().map(|(_, time_scale)| time_scale)
+    }
+}
+
+/// Resource that controls whether the local players are spectating the main game session instead of
+/// playing in it.
+///
+/// While spectating, the controls of the local players aren't written to the session, but the
+/// simulation keeps running, so that networked players or an input playback can be watched.
+///
+/// The local players' controls drive the spectator camera instead, see [`SpectatorMode::fly`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SpectatorMode {
+    /// The local players are playing the game.
+    #[default]
+    Off,
+    /// Spectate with a camera that follows the living players.
+    Follow,
+    /// Spectate with a camera that stays wherever it is put.
+    FreeFly {
+        /// The position of the center of the camera.
+        position: Vec2,
+        /// The height of the area the camera shows.
+        height: f32,
+        /// The player that the camera is snapped to, instead of staying at the `position`.
+        following: Option<usize>,
+    },
+}
+
+/// The spectator camera controls for a single frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpectatorInput {
+    /// The direction to pan the camera in.
+    pub pan: Vec2,
+    /// The direction to zoom the camera in, `1.0` to zoom out and `-1.0` to zoom in.
+    pub zoom: f32,
+    /// Whether the button for following a player, or going back to flying freely, was just
+    /// pressed.
+    pub toggle_follow: bool,
+    /// Whether the button for following the next living player was just pressed.
+    pub next_player: bool,
+}
+
+impl SpectatorMode {
+    /// How far the free-fly camera pans every frame, relative to the height it shows.
+    pub const PAN_SPEED: f32 = 0.02;
+    /// How much the free-fly camera zooms every frame, relative to the height it shows.
+    pub const ZOOM_SPEED: f32 = 0.02;
+
+    /// Whether the local players are spectating.
+    pub fn is_spectating(&self) -> bool {
+        *self != SpectatorMode::Off
+    }
+
+    /// Move the free-fly camera with the spectator's input for a frame.
+    ///
+    /// Using the controls while following the living players switches to the free-fly camera,
+    /// starting from the `camera_position` and `camera_height` of the actual camera. Panning while
+    /// snapped to a player lets go of them. The camera is kept inside of the `bounds`, with a
+    /// height in the `heights` range.
+    pub fn fly(
+        &mut self,
+        input: SpectatorInput,
+        camera_position: Vec2,
+        camera_height: f32,
+        living_players: &[usize],
+        bounds: Rect,
+        heights: std::ops::RangeInclusive<f32>,
+    ) {
+        if *self == SpectatorMode::Follow && input != default() {
+            *self = SpectatorMode::FreeFly {
+                position: camera_position,
+                height: camera_height,
+                following: None,
+            };
+        }
+        let SpectatorMode::FreeFly {
+            position,
+            height,
+            following,
+        } = self
+        else {
+            return;
+        };
+
+        if input.toggle_follow || (input.pan != Vec2::ZERO && following.is_some()) {
+            *following = match *following {
+                Some(_) => {
+                    *position = camera_position;
+                    None
+                }
+                None if input.toggle_follow => living_players.first().copied(),
+                None => None,
+            };
+        }
+        if input.next_player {
+            let current = *following;
+            *following = living_players
+                .iter()
+                .copied()
+                .find(|&player_idx| current.map_or(true, |x| player_idx > x))
+                .or_else(|| living_players.first().copied());
+        }
+
+        *height = (*height * (1.0 + input.zoom * Self::ZOOM_SPEED))
+            .clamp(*heights.start(), heights.end().max(*heights.start()));
+        *position =
+            (*position + input.pan * *height * Self::PAN_SPEED).clamp(bounds.min, bounds.max);
+    }
+}
+
+/// Resource containing how much the game camera is shaking.
+///
+/// Trauma added in the game session with [`CameraTrauma`][jumpy_core::camera::CameraTrauma] is
+/// collected into this every frame, but it may also be added to directly.
+#[derive(Resource, Clone, Debug)]
+pub struct CameraShake {
+    /// How much the camera is shaking, from `0.0` to `1.0`.
+    ///
+    /// The camera is moved in proportion to the square of the trauma, so small amounts of trauma
+    /// are subtle, and large amounts are violent.
+    pub trauma: f32,
+    /// How much trauma wears off each second.
+    pub decay: f32,
+    /// The furthest the camera may be moved from it's position.
+    pub max_offset: Vec2,
+    /// The furthest the camera may be rotated, in radians.
+    pub max_angle: f32,
+    /// How quickly the camera shakes back and forth.
+    pub frequency: f32,
+    /// The time that has passed in the shake noise.
+    pub time: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.0,
+            max_offset: vec2(12.0, 12.0),
+            max_angle: 4f32.to_radians(),
+            frequency: 20.0,
+            time: 0.0,
+        }
+    }
+}
+
+impl CameraShake {
+    /// Shake the camera, with `1.0` being the most violent shake.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+}
+
+/// The number of players that are added to a game session started without any players, such as
+/// from the editor.
+#[derive(Resource, Clone, Copy, Debug, Deref, DerefMut)]
+pub struct DefaultPlayerCount(pub usize);
+
+impl Default for DefaultPlayerCount {
+    fn default() -> Self {
+        Self(2)
     }
 }
 
+/// The difficulty of the bots that play as the default players after the first one, if they are
+/// bots.
+///
+/// Without bots, all of the default players are controlled by the local players.
+#[derive(Resource, Clone, Copy, Debug, Default, Deref, DerefMut)]
+pub struct DefaultBots(pub Option<jumpy_core::bot::BotDifficulty>);
+
+/// Resource that enables the determinism audit.
+///
+/// While enabled, a shadow copy of the main game session is advanced in lockstep with it, using
+/// the same inputs, and the [`state hashes`][GameSession::state_hashes] of the two are compared
+/// after every frame. When they diverge, the frame and the part of the state that differs is
+/// logged and recorded in [`DeterminismAudit::divergence`], and the audit is disabled.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct DeterminismAudit {
+    /// Whether the audit is running.
+    pub enabled: bool,
+    /// The first divergence found by the audit, if any.
+    pub divergence: Option<Divergence>,
+}
+
+/// The point where two game sessions that should have been identical stopped being identical.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// The first frame that the sessions differed on.
+    pub frame: u64,
+    /// The name of the component or resource that differed.
+    pub component: &'static str,
+}
+
+/// Event sent by the [`SessionManager`] when a game session is started, stopped, or changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// A session was started.
+    Started(SessionId),
+    /// A session was restarted with the same settings.
+    Restarted(SessionId),
+    /// A session was stopped.
+    ///
+    /// No [`PlayerLeft`][Self::PlayerLeft] events are sent for the players in the session.
+    Stopped(SessionId),
+    /// A player joined a session, either when it was started or by
+    /// [`SessionManager::add_session_player`].
+    PlayerJoined {
+        session: SessionId,
+        player_idx: usize,
+    },
+    /// A player was removed from a session by [`SessionManager::remove_session_player`].
+    PlayerLeft {
+        session: SessionId,
+        player_idx: usize,
+    },
+    /// The match in a session ended in a tie, and went into a sudden-death round.
+    SuddenDeathStarted(SessionId),
+    /// The match in a session ended, according to it's [`MatchMode`].
+    MatchEnded {
+        session: SessionId,
+        /// The index of the player that won, or `None` if the match was a draw.
+        winner: Option<usize>,
+    },
+}
+
 /// Helper for creating and stopping game sessions.
 #[derive(SystemParam)]
 pub struct SessionManager<'w, 's> {
     pub commands: Commands<'w, 's>,
     pub menu_camera: Query<'w, 's, &'static mut Camera, With<MenuCamera>>,
-    pub session: Option<ResMut<'w, Session>>,
+    pub sessions: Option<ResMut<'w, Sessions>>,
     pub core_meta_arc: Res<'w, CoreMetaArc>,
+    pub music: MusicManager<'w, 's>,
+    pub looping_sounds: ResMut<'w, LoopingSounds>,
+    pub audio_instances: ResMut<'w, Assets<AudioInstance>>,
+    pub run_state: ResMut<'w, SessionRunState>,
+    pub events: EventWriter<'w, 's, SessionEvent>,
 }
 
 impl<'w, 's> SessionManager<'w, 's> {
-    /// Start a game session
+    /// Get the main game session, if it has been started.
+    pub fn session(&self) -> Option<&Session> {
+        self.sessions.as_ref().and_then(|x| x.main())
+    }
+
+    /// Get the main game session, if it has been started.
+    pub fn session_mut(&mut self) -> Option<&mut Session> {
+        self.sessions.as_mut().and_then(|x| x.main_mut())
+    }
+
+    /// Start the main game session, replacing it if it is already running.
     pub fn start(&mut self, info: GameSessionInfo) {
-        let session = Session(GameSession::new(info));
-        self.commands.insert_resource(session);
+        self.start_session(SessionId::MAIN, info);
         self.menu_camera.for_each_mut(|mut x| x.is_active = false);
+        self.music.crossfade_to_match_music();
+        *self.run_state = default();
+    }
+
+    /// Start the main game session as a networked match with the other clients on the `transport`,
+    /// replacing it if it is already running.
+    ///
+    /// Every client must start the match with the same `info`. This client controls the player with
+    /// its index in the match, and the other players' controls arrive over the network, so the
+    /// session is simulated with rollback.
+    pub fn start_networked(&mut self, info: GameSessionInfo, transport: Box<dyn Transport>) {
+        self.start(info);
+        self.commands.add(move |world: &mut World| {
+            let mut rollback_settings =
+                world.get_resource_or_insert_with(RollbackSettings::default);
+            rollback_settings.max_rollback_frames = rollback_settings
+                .max_rollback_frames
+                .max(NETPLAY_ROLLBACK_FRAMES);
+            let net_settings = world
+                .get_resource::<NetSettings>()
+                .copied()
+                .unwrap_or_default();
+            let Some(session) = world
+                .get_resource::<Sessions>()
+                .and_then(|x| x.main())
+            else {
+                return;
+            };
+            let netplay = Netplay::new(session, transport, net_settings);
+            world.insert_resource(LocalPlayer {
+                idx: netplay.player_idx,
+                networked: true,
+            });
+            world.insert_resource(netplay);
+        });
+    }
+
+    /// Start a game session with the given ID, replacing any session that already has that ID.
+    ///
+    /// Unlike [`start`][Self::start], this leaves the music and menu camera alone, so it can be
+    /// used to start extra sessions alongside the main one.
+    pub fn start_session(&mut self, id: SessionId, info: GameSessionInfo) {
+        self.events.send(SessionEvent::Started(id));
+        for (player_idx, _) in info
+            .player_info
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| player.is_some())
+        {
+            self.events.send(SessionEvent::PlayerJoined {
+                session: id,
+                player_idx,
+            });
+        }
+
+        let session = Session(GameSession::new(info));
+        // Apply the change with the rest of the commands, so that stopping and starting sessions in
+        // the same frame happens in order.
+        self.commands.add(move |world: &mut World| {
+            world
+                .get_resource_or_insert_with(Sessions::default)
+                .insert(id, session);
+        });
     }
 
-    /// Restart a game session without changing the settings
+    /// Restart the main game session without changing the settings
+    ///
+    /// This leaves the music alone, so restarting in the middle of a crossfade will let the
+    /// crossfade finish normally.
+    ///
+    /// Any looping sounds started by the old game are stopped, and the music calms back down.
     pub fn restart(&mut self) {
-        if let Some(session) = self.session.as_mut() {
+        if let Some(session) = self.session_mut() {
+            session.restart();
+            self.looping_sounds.stop_all(&mut self.audio_instances);
+            self.music.layers.reset();
+            self.events.send(SessionEvent::Restarted(SessionId::MAIN));
+            // Unfreeze the session if it was frozen at the end of a match
+            self.set_session_paused(SessionId::MAIN, false);
+        }
+    }
+
+    /// Restart the game session with the given ID without changing the settings.
+    pub fn restart_session(&mut self, id: SessionId) {
+        if id == SessionId::MAIN {
+            self.restart();
+        } else if let Some(session) = self.sessions.as_mut().and_then(|x| x.get_mut(id)) {
             session.restart();
+            self.events.send(SessionEvent::Restarted(id));
+            self.set_session_paused(id, false);
         }
     }
 
-    /// Stop a game session
+    /// Stop all of the game sessions and go back to the menu camera and music.
     pub fn stop(&mut self) {
-        self.commands.remove_resource::<Session>();
+        if let Some(sessions) = &self.sessions {
+            self.events
+                .send_batch(sessions.iter().map(|(id, _)| SessionEvent::Stopped(id)));
+        }
+        self.commands.remove_resource::<Sessions>();
+        self.commands.add(|world: &mut World| {
+            if let Some(netplay) = world.remove_resource::<Netplay>() {
+                netplay.leave();
+                world.insert_resource(LocalPlayer::default());
+            }
+        });
         self.menu_camera.for_each_mut(|mut x| x.is_active = true);
+        self.music.crossfade_to_menu_music();
+        self.looping_sounds.stop_all(&mut self.audio_instances);
+        *self.run_state = default();
+    }
+
+    /// Stop the game session with the given ID, leaving any other sessions running.
+    pub fn stop_session(&mut self, id: SessionId) {
+        if id == SessionId::MAIN {
+            self.looping_sounds.stop_all(&mut self.audio_instances);
+        }
+        if self.sessions.as_ref().and_then(|x| x.get(id)).is_some() {
+            self.events.send(SessionEvent::Stopped(id));
+        }
+        self.commands.add(move |world: &mut World| {
+            let is_empty = {
+                let Some(mut sessions) = world.get_resource_mut::<Sessions>() else {
+                    return;
+                };
+                sessions.sessions.remove(&id);
+                sessions.paused.remove(&id);
+                sessions.sessions.is_empty()
+            };
+
+            // The renderer needs a session to render, so remove the resource with the last session
+            if is_empty {
+                world.remove_resource::<Sessions>();
+            }
+        });
+    }
+
+    /// Add a player to the main game session mid-match, playing as the given character.
+    ///
+    /// See [`add_session_player`][Self::add_session_player].
+    pub fn add_player(&mut self, player_idx: usize, selection: bones::Handle<PlayerMeta>) {
+        self.add_session_player(SessionId::MAIN, player_idx, selection);
+    }
+
+    /// Add a player to a running game session, playing as the given character.
+    ///
+    /// The player spawns at the next player spawner, with the same brief invulnerability as a
+    /// player that has respawned. Nothing happens if the player is already in the session.
+    pub fn add_session_player(
+        &mut self,
+        id: SessionId,
+        player_idx: usize,
+        selection: bones::Handle<PlayerMeta>,
+    ) {
+        let Some(session) = self.sessions.as_mut().and_then(|x| x.get_mut(id)) else {
+            return;
+        };
+
+        let was_active = session.update_input(|inputs| {
+            let input = &mut inputs.players[player_idx];
+            if !input.active {
+                input.selected_player = selection;
+            }
+            std::mem::replace(&mut input.active, true)
+        });
+        if was_active {
+            warn!("Player {player_idx} is already in session {id:?}");
+            return;
+        }
+
+        self.events.send(SessionEvent::PlayerJoined {
+            session: id,
+            player_idx,
+        });
+    }
+
+    /// Remove a player from the main game session mid-match.
+    ///
+    /// See [`remove_session_player`][Self::remove_session_player].
+    pub fn remove_player(&mut self, player_idx: usize) {
+        self.remove_session_player(SessionId::MAIN, player_idx);
+    }
+
+    /// Remove a player from a running game session, despawning their character.
+    ///
+    /// When the last player leaves, the session is stopped. If it was the main session, the game
+    /// returns to the main menu.
+    pub fn remove_session_player(&mut self, id: SessionId, player_idx: usize) {
+        let Some(session) = self.sessions.as_mut().and_then(|x| x.get_mut(id)) else {
+            return;
+        };
+
+        let (was_active, any_active) = session.update_input(|inputs| {
+            let was_active = std::mem::replace(&mut inputs.players[player_idx].active, false);
+            (was_active, inputs.players.iter().any(|x| x.active))
+        });
+        if !was_active {
+            return;
+        }
+
+        session
+            .world
+            .run_initialized_system(
+                move |entities: bones::Res<bones::Entities>,
+                      player_indexes: bones::Comp<jumpy_core::player::PlayerIdx>,
+                      mut player_events: bones::ResMut<jumpy_core::player::PlayerEvents>| {
+                    for (player_ent, idx) in entities.iter_with(&player_indexes) {
+                        if idx.0 == player_idx {
+                            player_events.despawn(player_ent);
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+        self.events.send(SessionEvent::PlayerLeft {
+            session: id,
+            player_idx,
+        });
+
+        // Everyone has left
+        if !any_active {
+            if id == SessionId::MAIN {
+                self.stop();
+                self.commands
+                    .insert_resource(NextState(EngineState::MainMenu));
+            } else {
+                self.stop_session(id);
+            }
+        }
+    }
+
+    /// Pause or resume a single game session, independently of the others.
+    ///
+    /// This is separate from [`pause`][Self::pause], which pauses all of the sessions.
+    pub fn set_session_paused(&mut self, id: SessionId, paused: bool) {
+        if let Some(sessions) = self.sessions.as_mut() {
+            sessions.set_paused(id, paused);
+        }
+    }
+
+    /// Pause the game sessions, freezing the simulation without stopping it.
+    ///
+    /// Sound effects from the session will be ducked until the session is resumed.
+    pub fn pause(&mut self) {
+        // Only write to the run state when it changes, so change detection stays accurate.
+        if !self.run_state.paused {
+            self.run_state.paused = true;
+        }
+    }
+
+    /// Resume the paused game sessions.
+    pub fn resume(&mut self) {
+        if self.run_state.paused {
+            self.run_state.paused = false;
+            self.run_state.step_requested = false;
+        }
+    }
+
+    /// Advance the paused game sessions by a single frame, for debugging.
+    ///
+    /// This does nothing if the session isn't paused. The step is also held until the pause menu is
+    /// closed, because the simulation never runs while it is open.
+    pub fn step_once(&mut self) {
+        if self.run_state.paused {
+            self.run_state.step_requested = true;
+        }
+    }
+}
+
+/// Helper for starting, stopping, and crossfading the game music.
+#[derive(SystemParam)]
+pub struct MusicManager<'w, 's> {
+    pub channel: Res<'w, AudioChannel<MusicChannel>>,
+    pub current_music: ResMut<'w, CurrentMusic>,
+    pub playlist: Res<'w, ShuffledPlaylist>,
+    pub settings: Res<'w, MusicSettings>,
+    pub layers: ResMut<'w, MusicLayers>,
+    pub game: Option<Res<'w, GameMeta>>,
+    #[system_param(ignore)]
+    _phantom: PhantomData<(&'w (), &'s ())>,
+}
+
+impl<'w, 's> MusicManager<'w, 's> {
+    /// Immediately start playing a track, stopping any other music.
+    pub fn start(&mut self, track: Handle<AudioSource>, kind: MusicKind) {
+        self.crossfade(track, kind, Duration::ZERO);
+    }
+
+    /// Fade out and stop the music.
+    pub fn stop(&mut self, fade_out: Duration) {
+        self.channel.stop().fade_out(AudioTween::linear(fade_out));
+        *self.current_music = CurrentMusic {
+            idx: self.current_music.idx,
+            ..default()
+        };
+    }
+
+    /// Fade out whatever is playing, while fading in `track` over the given duration.
+    ///
+    /// This may be called in the middle of another crossfade: everything still playing on the music
+    /// channel, including tracks that have not finished fading in or out yet, will be faded out
+    /// from whatever volume it is currently at.
+    pub fn crossfade(&mut self, track: Handle<AudioSource>, kind: MusicKind, duration: Duration) {
+        self.channel.stop().fade_out(AudioTween::linear(duration));
+
+        let mut command = self.channel.play(track);
+        command.linear_fade_in(duration);
+        // The match playlist is advanced by the music system when a song finishes, but the menu
+        // music just loops.
+        if kind == MusicKind::Menu {
+            command.looped();
+        }
+        self.current_music.instance = command.handle();
+        self.current_music.kind = kind;
+    }
+
+    /// Crossfade to the next song in the match playlist, if match music isn't already playing.
+    pub fn crossfade_to_match_music(&mut self) {
+        if self.current_music.kind == MusicKind::Match {
+            return;
+        }
+        let duration = self.settings.crossfade_duration;
+
+        if self.playlist.is_empty() {
+            // The music system will start the playlist as soon as it is ready.
+            self.stop(duration);
+            self.current_music.kind = MusicKind::Match;
+        } else {
+            let idx = (self.current_music.idx + 1) % self.playlist.len();
+            let track = self.playlist[idx].inner.clone_weak();
+            self.crossfade(track, MusicKind::Match, duration);
+            self.current_music.idx = idx;
+        }
+    }
+
+    /// Crossfade to the menu music, if it isn't already playing.
+    pub fn crossfade_to_menu_music(&mut self) {
+        if self.current_music.kind == MusicKind::Menu {
+            return;
+        }
+        let Some(track) = self.game.as_ref().map(|x| x.menu_music.inner.clone_weak()) else {
+            return;
+        };
+
+        self.crossfade(track, MusicKind::Menu, self.settings.crossfade_duration);
     }
 }
 
-/// Helper system to make sure there are two players on the board, if ever the game is in the middle
+/// Helper system to make sure there are players on the board, if ever the game is in the middle
 /// of playing and there are no players on the board.
 ///
 /// This is primarily for the editor, which may be started without going through the player
 /// selection screen.
-fn ensure_2_players(session: Option<ResMut<Session>>, core_meta: Res<CoreMetaArc>) {
-    if let Some(session) = session {
+fn ensure_default_players(
+    sessions: Option<ResMut<Sessions>>,
+    core_meta: Res<CoreMetaArc>,
+    player_count: Res<DefaultPlayerCount>,
+    default_bots: Res<DefaultBots>,
+) {
+    if let Some(session) = sessions.as_ref().and_then(|x| x.main()) {
         let player_inputs = session.world.resource::<jumpy_core::input::PlayerInputs>();
         let mut player_inputs = player_inputs.borrow_mut();
 
-        if player_inputs.players.iter().all(|x| !x.active) {
-            for i in 0..2 {
-                player_inputs.players[i].active = true;
-                player_inputs.players[i].selected_player = core_meta.players[i].clone();
-            }
+        activate_default_players(
+            &mut player_inputs,
+            &core_meta.players,
+            player_count.0,
+            default_bots.0,
+        );
+    }
+}
+
+/// Activate the first `count` players with the default player selections, if no players are
+/// active.
+///
+/// If a `bot` difficulty is given, every player but the first is played by a bot of that
+/// difficulty. The count is clamped to the number of players in the metadata and in the inputs.
+fn activate_default_players(
+    inputs: &mut jumpy_core::input::PlayerInputs,
+    players: &[bones::Handle<PlayerMeta>],
+    count: usize,
+    bot: Option<jumpy_core::bot::BotDifficulty>,
+) {
+    if inputs.players.iter().any(|x| x.active) {
+        return;
+    }
+
+    let available = players.len().min(inputs.players.len());
+    if count > available {
+        warn!("Requested {count} default players, but only {available} are available");
+    }
+
+    for (i, (input, player)) in inputs
+        .players
+        .iter_mut()
+        .zip(players)
+        .take(count)
+        .enumerate()
+    {
+        input.active = true;
+        input.selected_player = player.clone();
+        // The first player is left to the local player
+        if i > 0 {
+            input.bot = bot;
         }
     }
 }
 
 /// Update the input to the game session.
-fn update_input(
-    session: Option<ResMut<Session>>,
+pub fn update_input(
+    sessions: Option<ResMut<Sessions>>,
     player_input_collectors: Query<(&PlayerInputCollector, &ActionState<PlayerAction>)>,
     mut current_editor_input: ResMut<CurrentEditorInput>,
+    local_player: Res<LocalPlayer>,
+    input_settings: Res<InputSettings>,
+    spectator_mode: Res<SpectatorMode>,
+    mut recorder: ResMut<InputRecorder>,
+    mut playback: ResMut<InputPlayback>,
+    scripted_input: Option<Res<ScriptedInput>>,
+    rollback_settings: Res<RollbackSettings>,
+    mut rollback: ResMut<Rollback>,
+    net_settings: Res<NetSettings>,
+    mut netplay: Option<ResMut<Netplay>>,
+    mut input_delay: ResMut<InputDelay>,
+    mut emote_wheels: ResMut<EmoteWheels>,
+    core_meta: Option<Res<CoreMetaArc>>,
+    in_game_state: Res<CurrentState<InGameState>>,
+    run_state: Res<SessionRunState>,
+    mut paused_in_menu: Local<bool>,
 ) {
-    let Some(mut session) = session else {
+    let Some(mut sessions) = sessions else {
+        return;
+    };
+    let Some(session) = sessions.main_mut() else {
         return;
     };
 
+    // The session doesn't advance while it is paused, so the controls would only overwrite the
+    // ones collected for the same frame, unless a single frame is being stepped
+    if run_state.paused && !run_state.step_requested {
+        *paused_in_menu |= in_game_state.0 == InGameState::Paused;
+        return;
+    }
+    let resumed_from_menu = std::mem::take(&mut *paused_in_menu);
+
     let mut editor_input = current_editor_input.take();
+    let frame = session.frame();
+    let emote_count = core_meta.map_or(0, |x| x.emotes.wheel.len());
 
-    session.update_input(|inputs| {
-        // TODO: Properly handle which player is taking the editor input, which is important in
-        // networked multiplayer.
-        inputs.players[0].editor_input = editor_input.take();
+    // Go back to live input once we reach the end of the recording
+    if playback.is_playing() && playback.controls(frame).is_none() {
+        info!("Finished input playback at frame {frame}");
+        playback.stop();
+    }
 
-        for (player_idx, action_state) in &player_input_collectors {
-            let control = &mut inputs.players[player_idx.0].control;
+    // The played back controls have already been delayed like they were when they were recorded,
+    // and a networked match is played with the host's settings
+    let delay_frames = if playback.is_playing() {
+        0
+    } else if let Some(netplay) = &netplay {
+        netplay.sync.net_settings().input_delay_frames
+    } else {
+        net_settings.input_delay_frames
+    };
+    // With rollback, the controls are delayed when they are given to it instead
+    let hold_back = delay_frames > 0 && !rollback_settings.is_enabled();
 
-            let jump_pressed = action_state.pressed(PlayerAction::Jump);
-            control.jump_just_pressed = jump_pressed && !control.jump_pressed;
-            control.jump_pressed = jump_pressed;
+    session.update_input(|inputs| {
+        route_editor_input(inputs, &local_player, editor_input.take());
 
-            let grab_pressed = action_state.pressed(PlayerAction::Grab);
-            control.grab_just_pressed = grab_pressed && !control.grab_pressed;
-            control.grab_pressed = grab_pressed;
+        // Collect the controls on top of the ones collected on the last frame, instead of the ones
+        // that were held back, so that the presses are worked out the same as without the delay
+        if hold_back {
+            for (input, control) in inputs
+                .players
+                .iter_mut()
+                .zip(input_delay.live_controls(frame))
+            {
+                input.control = control.clone();
+            }
+        }
 
-            let shoot_pressed = action_state.pressed(PlayerAction::Shoot);
-            control.shoot_just_pressed = shoot_pressed && !control.shoot_pressed;
-            control.shoot_pressed = shoot_pressed;
+        if let Some(script) = &scripted_input {
+            script.apply(frame, inputs);
+        } else if let Some(controls) = playback.controls(frame) {
+            for (input, control) in inputs.players.iter_mut().zip(controls) {
+                input.control = control;
+            }
+        } else if spectator_mode.is_spectating() {
+            // Let go of whatever the local players were holding when they started spectating
+            if spectator_mode.is_changed() {
+                for (i, input) in inputs.players.iter_mut().enumerate() {
+                    if local_player.controls_player(i) {
+                        input.control = default();
+                    }
+                }
+            }
+        } else {
+            update_live_input(
+                inputs,
+                &local_player,
+                &input_settings,
+                &player_input_collectors,
+                &mut emote_wheels,
+                emote_count,
+            );
 
-            let was_moving = control.move_direction.length_squared() > f32::MIN_POSITIVE;
-            control.move_direction = action_state.axis_pair(PlayerAction::Move).unwrap().xy();
-            let is_moving = control.move_direction.length_squared() > f32::MIN_POSITIVE;
-            control.just_moved = !was_moving && is_moving;
+            // The buttons pressed to use the pause menu mustn't be acted on when the game resumes,
+            // so they are only held down, and must be pressed again once the menu is closed.
+            if resumed_from_menu {
+                for (i, input) in inputs.players.iter_mut().enumerate() {
+                    if local_player.controls_player(i) {
+                        release_presses(&mut input.control);
+                        emote_wheels.players[i] = default();
+                    }
+                }
+            }
+        }
+
+        // With rollback, the controls are applied when the frame is simulated, after the input delay
+        if let Some(netplay) = netplay.as_mut().filter(|_| rollback_settings.is_enabled()) {
+            let control = inputs.players[netplay.player_idx].control.clone();
+            netplay.add_local_input(&mut rollback, frame, control);
+        } else if rollback_settings.is_enabled() {
+            for (i, input) in inputs.players.iter().enumerate() {
+                if local_player.controls_player(i) {
+                    rollback.add_local_input(frame, delay_frames, i, input.control.clone());
+                }
+            }
+        }
+
+        if recorder.is_recording() {
+            let controls = inputs
+                .players
+                .iter()
+                .map(|input| input.control.clone())
+                .collect();
+            recorder.record(frame, controls);
+        }
+
+        if hold_back {
+            let controls = inputs
+                .players
+                .iter()
+                .map(|input| input.control.clone())
+                .collect();
+            let delayed = input_delay.delay(frame, delay_frames, controls);
+            for (input, control) in inputs.players.iter_mut().zip(delayed) {
+                input.control = control;
+            }
         }
     });
+
+    if let Some(netplay) = netplay.filter(|_| rollback_settings.is_enabled()) {
+        netplay.send_inputs();
+    }
+}
+
+/// Resource that holds back the player controls by the
+/// [`input_delay_frames`][NetSettings::input_delay_frames], when the main session isn't simulated
+/// with rollback, which delays the controls itself.
+#[derive(Resource, Default)]
+pub struct InputDelay {
+    /// The frame that controls were last collected on.
+    frame: Option<u64>,
+    /// The controls that were last collected, before they were delayed.
+    live: Vec<jumpy_core::input::PlayerControl>,
+    /// The controls that are being held back, by the frame that they take effect on.
+    pending: BTreeMap<u64, Vec<jumpy_core::input::PlayerControl>>,
+}
+
+impl InputDelay {
+    /// Get the controls that were last collected before `frame`, before they were delayed.
+    ///
+    /// Nothing is held if the session didn't continue from the frame they were collected on, such
+    /// as when it was restarted.
+    pub fn live_controls(&self, frame: u64) -> &[jumpy_core::input::PlayerControl] {
+        if self.continues_to(frame) {
+            &self.live
+        } else {
+            &[]
+        }
+    }
+
+    /// Hold back the `controls` collected on `frame` by `delay_frames`, and get the controls that
+    /// take effect on `frame` instead.
+    ///
+    /// Nothing is held on the first frames, before the first controls take effect.
+    pub fn delay(
+        &mut self,
+        frame: u64,
+        delay_frames: u64,
+        controls: Vec<jumpy_core::input::PlayerControl>,
+    ) -> Vec<jumpy_core::input::PlayerControl> {
+        // The held back controls were for a session that has since been restarted or restored
+        if !self.continues_to(frame) {
+            self.pending.clear();
+        }
+        self.frame = Some(frame);
+
+        let player_count = controls.len();
+        self.live = controls.clone();
+        self.pending.insert(frame + delay_frames, controls);
+        self.pending.retain(|x, _| *x >= frame);
+        self.pending
+            .get(&frame)
+            .cloned()
+            .unwrap_or_else(|| vec![default(); player_count])
+    }
+
+    /// Whether controls are being collected for `frame` after the last ones, which may be for the
+    /// same frame when the session didn't advance in between.
+    fn continues_to(&self, frame: u64) -> bool {
+        self.frame.map_or(false, |x| x == frame || x + 1 == frame)
+    }
+}
+
+/// Give the editor input to the local player, if they are in the session.
+fn route_editor_input(
+    inputs: &mut jumpy_core::input::PlayerInputs,
+    local_player: &LocalPlayer,
+    editor_input: Option<jumpy_core::input::EditorInput>,
+) {
+    if let Some(player) = inputs.players.get_mut(local_player.idx) {
+        player.editor_input = editor_input;
+    }
+}
+
+/// Clear the buttons that were just pressed in the player's control, and keep the ones that are
+/// held down.
+fn release_presses(control: &mut jumpy_core::input::PlayerControl) {
+    control.jump_just_pressed = false;
+    control.grab_just_pressed = false;
+    control.shoot_just_pressed = false;
+    control.slide_just_pressed = false;
+    control.taunt_just_pressed = false;
+    control.just_moved = false;
+    control.emote = None;
+}
+
+/// Update the player controls from the player input collectors.
+fn update_live_input(
+    inputs: &mut jumpy_core::input::PlayerInputs,
+    local_player: &LocalPlayer,
+    input_settings: &InputSettings,
+    player_input_collectors: &Query<(&PlayerInputCollector, &ActionState<PlayerAction>)>,
+    emote_wheels: &mut EmoteWheels,
+    emote_count: usize,
+) {
+    for (player_idx, action_state) in player_input_collectors {
+        // Other players' input comes from the network in networked games
+        if !local_player.controls_player(player_idx.0) {
+            continue;
+        }
+
+        let control = &mut inputs.players[player_idx.0].control;
+        let previous = control.clone();
+
+        control.jump_pressed = action_state.pressed(PlayerAction::Jump);
+        control.grab_pressed = action_state.pressed(PlayerAction::Grab);
+        control.block_pressed = action_state.pressed(PlayerAction::Block);
+        control.taunt_pressed = action_state.pressed(PlayerAction::Taunt);
+        control.shoot_pressed = action_state.pressed(PlayerAction::Shoot);
+        control.move_direction = input_settings
+            .apply_move_dead_zone(action_state.axis_pair(PlayerAction::Move).unwrap().xy());
+        control.detect_presses(&previous);
+
+        // While the emote wheel is open the movement input picks an emote, which is played when
+        // the wheel is closed
+        let wheel = &mut emote_wheels.players[player_idx.0];
+        control.emote = None;
+        if action_state.pressed(PlayerAction::Emote) {
+            wheel.open = true;
+            if let Some(selection) = emote_wheel_selection(control.move_direction, emote_count) {
+                wheel.selection = Some(selection);
+            }
+            control.move_direction = Vec2::ZERO;
+            control.just_moved = false;
+        } else if wheel.open {
+            control.emote = wheel.selection.map(|x| x as u8);
+            *wheel = default();
+        }
+
+        // Aim freely with the aim stick if it's being used, or else in one of 8 directions with
+        // the movement input
+        let aim_stick = action_state
+            .axis_pair(PlayerAction::Aim)
+            .map(|axis| input_settings.apply_move_dead_zone(axis.xy()))
+            .unwrap_or_default();
+        control.aim_direction = if aim_stick != Vec2::ZERO {
+            aim_stick.normalize()
+        } else {
+            jumpy_core::input::snap_to_8_directions(control.move_direction)
+        };
+    }
+}
+
+/// Update the game session simulations.
+pub fn update_game(world: &mut World) {
+    let Some(mut sessions) = world.remove_resource::<Sessions>() else {
+        return;
+    };
+
+    let rollback_settings = world.resource::<RollbackSettings>().clone();
+    let mut rollback = world.remove_resource::<Rollback>().unwrap_or_default();
+    let mut netplay = world.remove_resource::<Netplay>();
+    let mut smoothing = world
+        .remove_resource::<RollbackSmoothing>()
+        .unwrap_or_default();
+    let renders_main = sessions.rendered_id() == SessionId::MAIN;
+
+    // Advance the game sessions that haven't been paused on their own
+    let Sessions {
+        sessions: session_map,
+        paused,
+        ..
+    } = &mut sessions;
+    for (id, session) in session_map.iter_mut() {
+        if paused.contains(id) {
+            continue;
+        }
+        let was_over = session.match_result().is_some();
+        let was_sudden_death = session.is_sudden_death();
+        let was_decided = session.decisive_kill().is_some();
+
+        // Only the main session receives remote inputs, so only it needs to be rolled back
+        if *id == SessionId::MAIN && rollback_settings.is_enabled() {
+            // Catch where the entities were predicted to be on this frame, and where the rollback
+            // corrects them to, so that the correction can be smoothed out on screen
+            let frame = session.frame();
+            let predicted = rollback
+                .is_rollback_pending()
+                .then(|| smoothed_translations(session));
+            let mut corrected = None;
+            let max_rollback_frames = rollback_settings.max_rollback_frames;
+            let step = |session: &mut GameSession| {
+                session.advance(world);
+                if predicted.is_some() && session.frame() == frame {
+                    corrected = Some(smoothed_translations(session));
+                }
+            };
+            // The remote players' inputs arrive over the network in a networked match
+            let resimulated = match &mut netplay {
+                Some(netplay) => netplay.advance(session, &mut rollback, max_rollback_frames, step),
+                None => rollback.advance(session, max_rollback_frames, step),
+            };
+            if resimulated > 0 {
+                trace!("Rolled back and re-simulated {resimulated} frame(s)");
+            }
+
+            smoothing.advance(frame);
+            if let (Some(predicted), Some(corrected)) = (predicted, corrected) {
+                smoothing.add_correction(
+                    &predicted,
+                    &corrected,
+                    rollback_settings.max_smoothing_distance,
+                );
+
+                // The offsets now make up for the difference, so interpolate from where the
+                // entities really were on the previous frame
+                if renders_main {
+                    let mut interpolation = world.resource_mut::<RenderInterpolation>();
+                    for (ent, translation) in corrected {
+                        if let Some(previous) = interpolation.previous.get_mut(&ent) {
+                            previous.translation = translation;
+                        }
+                    }
+                }
+            }
+        } else {
+            session.advance(world);
+        }
+
+        // Slow down to show off the kill that decides the match, in the session that is rendered
+        if *id == SessionId::MAIN && session.decisive_kill().is_some() && !was_decided {
+            if let Some(mut slow_motion) = world.remove_resource::<FinalKillSlowMotion>() {
+                let mut time_scale = world.get_resource_or_insert_with(TimeScale::default);
+                slow_motion.start(&mut time_scale, rollback_settings.is_enabled());
+                world.insert_resource(slow_motion);
+            }
+        }
+
+        if session.is_sudden_death() && !was_sudden_death {
+            info!("Match in session {id:?} went into sudden death");
+            world.send_event(SessionEvent::SuddenDeathStarted(*id));
+        }
+        if let Some(result) = session.match_result().filter(|_| !was_over) {
+            info!("Match in session {id:?} ended, winner: {:?}", result.winner);
+            world.send_event(SessionEvent::MatchEnded {
+                session: *id,
+                winner: result.winner,
+            });
+
+            // Freeze the session on the final frame, until it is restarted, once the slow motion is
+            // over
+            let slowed_down = *id == SessionId::MAIN
+                && world
+                    .get_resource::<FinalKillSlowMotion>()
+                    .map_or(false, |x| x.is_playing());
+            if !slowed_down {
+                paused.insert(*id);
+            }
+        }
+    }
+    world.insert_resource(rollback);
+    world.insert_resource(smoothing);
+    if let Some(netplay) = netplay {
+        world.insert_resource(netplay);
+    }
+
+    // Finish the requested frame step
+    let mut run_state = world.resource_mut::<SessionRunState>();
+    if run_state.step_requested {
+        run_state.step_requested = false;
+        if let Some(session) = sessions.main() {
+            info!("Stepped game session to frame {}", session.frame());
+        }
+    }
+
+    world.insert_resource(sessions);
+}
+
+/// Give the determinism audit session the same input as the main session.
+fn sync_determinism_audit_input(sessions: Option<ResMut<Sessions>>, audit: Res<DeterminismAudit>) {
+    if !audit.enabled {
+        return;
+    }
+    let Some(mut sessions) = sessions else {
+        return;
+    };
+    let Some(inputs) = sessions
+        .main()
+        .map(|session| {
+            session
+                .world
+                .resource::<jumpy_core::input::PlayerInputs>()
+                .borrow()
+                .clone()
+        })
+    else {
+        return;
+    };
+    if let Some(shadow) = sessions.get_mut(SessionId::AUDIT) {
+        shadow.update_input(|shadow_inputs| *shadow_inputs = inputs);
+    }
 }
 
-/// Update the game session simulation.
-fn update_game(world: &mut World) {
-    let Some(mut session) = world.remove_resource::<Session>() else {
+/// Compare the main session with the determinism audit session, and start or stop the audit
+/// session as the audit is enabled and disabled.
+fn check_determinism_audit(
+    mut commands: Commands,
+    sessions: Option<ResMut<Sessions>>,
+    mut audit: ResMut<DeterminismAudit>,
+) {
+    let Some(mut sessions) = sessions else {
         return;
     };
+    if !audit.enabled || sessions.main().is_none() {
+        sessions.sessions.remove(&SessionId::AUDIT);
+        // The audit session mustn't keep the resource around once the main session is stopped
+        if sessions.sessions.is_empty() {
+            commands.remove_resource::<Sessions>();
+        }
+        return;
+    }
+    let main = sessions.main().unwrap();
 
-    // Advance the game session
-    session.advance(world);
+    let main_hashes = main.state_hashes();
+    let shadow_hashes = sessions
+        .get(SessionId::AUDIT)
+        .map(|shadow| shadow.state_hashes())
+        // The audit session is out of sync if the main session was restarted or restored
+        .filter(|hashes| hashes.frame == main_hashes.frame);
 
-    world.insert_resource(session);
+    let Some(shadow_hashes) = shadow_hashes else {
+        // Start a new audit session from the current state of the main session
+        let snapshot = main.snapshot();
+        let mut shadow = GameSession::new(main.info.clone());
+        shadow.restore(&snapshot);
+        sessions.sessions.insert(SessionId::AUDIT, Session(shadow));
+        return;
+    };
+
+    if let Some(component) = main_hashes.first_difference(&shadow_hashes) {
+        let divergence = Divergence {
+            frame: main_hashes.frame,
+            component,
+        };
+        error!(
+            "Determinism audit: sessions diverged on frame {} in `{}`",
+            divergence.frame, divergence.component
+        );
+        audit.divergence = Some(divergence);
+        audit.enabled = false;
+        sessions.sessions.remove(&SessionId::AUDIT);
+    }
 }
 
-/// Play sounds from the game session.
-fn play_sounds(audio: Res<AudioChannel<EffectsChannel>>, session: Option<Res<Session>>) {
-    let Some(session) = session else {
+/// Read the spectator camera controls from the local players' input.
+///
+/// The players pan with their movement input, zoom in with jump and out with slide, snap to a
+/// player and back with grab, and follow the next living player with shoot.
+fn spectator_input(
+    local_player: &LocalPlayer,
+    input_settings: &InputSettings,
+    player_input_collectors: &Query<(&PlayerInputCollector, &ActionState<PlayerAction>)>,
+    held_buttons: &mut [bool; 2],
+) -> SpectatorInput {
+    let mut input = SpectatorInput::default();
+    let mut held = [false; 2];
+    for (player_idx, action_state) in player_input_collectors {
+        if !local_player.controls_player(player_idx.0) {
+            continue;
+        }
+
+        input.pan += input_settings
+            .apply_move_dead_zone(action_state.axis_pair(PlayerAction::Move).unwrap().xy());
+        if action_state.pressed(PlayerAction::Jump) {
+            input.zoom -= 1.0;
+        }
+        if action_state.pressed(PlayerAction::Slide) {
+            input.zoom += 1.0;
+        }
+        held[0] |= action_state.pressed(PlayerAction::Grab);
+        held[1] |= action_state.pressed(PlayerAction::Shoot);
+    }
+
+    // The buttons are checked once per simulation frame, so they are only counted as pressed on
+    // the first frame they are held
+    input.pan = input.pan.clamp_length_max(1.0);
+    input.zoom = input.zoom.clamp(-1.0, 1.0);
+    input.toggle_follow = held[0] && !held_buttons[0];
+    input.next_player = held[1] && !held_buttons[1];
+    *held_buttons = held;
+
+    input
+}
+
+/// Move the camera of the main game session while spectating.
+fn update_spectator_camera(
+    sessions: Option<ResMut<Sessions>>,
+    mut spectator_mode: ResMut<SpectatorMode>,
+    local_player: Res<LocalPlayer>,
+    input_settings: Res<InputSettings>,
+    player_input_collectors: Query<(&PlayerInputCollector, &ActionState<PlayerAction>)>,
+    mut held_buttons: Local<[bool; 2]>,
+) {
+    let Some(session) = sessions.as_ref().and_then(|x| x.main()) else {
+        return;
+    };
+    let mode = *spectator_mode;
+    let mode_changed = spectator_mode.is_changed();
+    let input = if mode.is_spectating() {
+        spectator_input(
+            &local_player,
+            &input_settings,
+            &player_input_collectors,
+            &mut held_buttons,
+        )
+    } else {
+        *held_buttons = default();
+        default()
+    };
+
+    let mode = session
+        .world
+        .run_initialized_system(
+            move |entities: bones::Res<bones::Entities>,
+                  core_meta: bones::Res<CoreMetaArc>,
+                  map: bones::Res<jumpy_core::map::LoadedMap>,
+                  window: bones::Res<bones::Window>,
+                  player_indexes: bones::Comp<jumpy_core::player::PlayerIdx>,
+                  killed_players: bones::Comp<jumpy_core::player::PlayerKilled>,
+                  transforms: bones::Comp<bones::Transform>,
+                  mut cameras: bones::CompMut<bones::Camera>,
+                  mut camera_shakes: bones::CompMut<bones::CameraShake>,
+                  mut camera_states: bones::CompMut<jumpy_core::camera::CameraState>| {
+                let Some((_ent, (camera, camera_shake, camera_state))) = entities
+                    .iter_with((&mut cameras, &mut camera_shakes, &mut camera_states))
+                    .next()
+                else {
+                    return Ok(mode);
+                };
+                let meta = &core_meta.camera;
+
+                let mut living_players = entities
+                    .iter_with((&player_indexes, &transforms))
+                    .filter(|(ent, _)| !killed_players.contains(*ent))
+                    .map(|(_ent, (idx, transform))| (idx.0, transform.translation.truncate()))
+                    .collect::<Vec<_>>();
+                living_players.sort_by_key(|(player_idx, _)| *player_idx);
+
+                // Keep the free-fly camera over the map
+                let map_size = map.grid_size.as_vec2() * map.tile_size;
+                let bounds = Rect::from_corners(Vec2::ZERO, map_size);
+                let mut mode = mode;
+                mode.fly(
+                    input,
+                    camera_shake.center.truncate(),
+                    camera.height,
+                    &living_players.iter().map(|x| x.0).collect::<Vec<_>>(),
+                    bounds,
+                    meta.min_height..=meta.max_height.min(map_size.y),
+                );
+
+                match mode {
+                    SpectatorMode::Off => {
+                        // Hand the camera back to the game once we stop spectating
+                        if mode_changed {
+                            camera_state.disable_controller = false;
+                        }
+                    }
+                    SpectatorMode::FreeFly {
+                        position,
+                        height,
+                        following,
+                    } => {
+                        camera_state.disable_controller = true;
+
+                        // Glide to the followed player, or to where the camera was flown to
+                        let target = following
+                            .and_then(|player_idx| {
+                                living_players.iter().find(|(idx, _)| *idx == player_idx)
+                            })
+                            .map_or(position, |(_idx, pos)| pos.clamp(bounds.min, bounds.max));
+                        let center = camera_shake.center.truncate();
+                        let center = center + (target - center) * meta.move_lerp_factor;
+                        camera_shake.center = center.extend(camera_shake.center.z);
+                        camera.height += (height - camera.height) * meta.zoom_in_lerp_factor;
+                    }
+                    SpectatorMode::Follow => {
+                        camera_state.disable_controller = true;
+
+                        let positions = living_players
+                            .iter()
+                            .map(|(_idx, position)| *position)
+                            .collect::<Vec<_>>();
+                        if positions.is_empty() {
+                            return Ok(mode);
+                        }
+
+                        // Track the centroid of the living players
+                        let centroid =
+                            positions.iter().copied().sum::<Vec2>() / positions.len() as f32;
+                        let center = camera_shake.center.truncate();
+                        let center = center + (centroid - center) * meta.move_lerp_factor;
+                        camera_shake.center = center.extend(camera_shake.center.z);
+
+                        // Zoom to keep all of them in frame
+                        let viewport_size = camera
+                            .viewport
+                            .map(|x| x.size.as_vec2())
+                            .unwrap_or(window.size);
+                        let aspect = viewport_size.x / viewport_size.y.max(1.0);
+                        let border = vec2(
+                            meta.border_left + meta.border_right,
+                            meta.border_top + meta.border_bottom,
+                        );
+                        let extent = positions
+                            .iter()
+                            .map(|pos| (*pos - centroid).abs() * 2.0)
+                            .fold(Vec2::ZERO, Vec2::max)
+                            + border;
+                        let extent = extent.max(meta.min_camera_size);
+                        let target_height = extent.y.max(extent.x / aspect);
+
+                        let height_diff = target_height - camera.height;
+                        camera.height += if height_diff > 0.0 {
+                            height_diff * meta.zoom_out_lerp_factor
+                        } else {
+                            height_diff * meta.zoom_in_lerp_factor
+                        };
+                    }
+                }
+
+                Ok(mode)
+            },
+        )
+        .unwrap();
+
+    // Only write the mode when it changes, so change detection stays accurate.
+    if mode != *spectator_mode {
+        *spectator_mode = mode;
+    }
+}
+
+/// Take the camera trauma out of the game sessions, shaking the camera for the main session.
+fn collect_camera_trauma(sessions: Option<Res<Sessions>>, mut camera_shake: ResMut<CameraShake>) {
+    let Some(sessions) = sessions else {
+        return;
+    };
+
+    for (id, session) in sessions.iter() {
+        let camera_trauma = session.world.resource::<jumpy_core::camera::CameraTrauma>();
+        let trauma = camera_trauma.borrow_mut().take();
+
+        if id == SessionId::MAIN && trauma > 0.0 {
+            camera_shake.add_trauma(trauma);
+        }
+    }
+}
+
+/// Shake the game cameras, and let the trauma wear off.
+///
+/// The shake runs at the [`TimeScale`], so that it looks right in slow motion, and freezes while the
+/// game is paused.
+fn apply_camera_shake(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    run_state: Res<SessionRunState>,
+    sessions: Option<Res<Sessions>>,
+    mut camera_shake: ResMut<CameraShake>,
+    mut cameras: Query<&mut Transform, (With<Camera>, With<BevyBonesEntity>)>,
+) {
+    if sessions.is_none() {
+        camera_shake.trauma = 0.0;
+        return;
+    }
+    if run_state.paused || camera_shake.trauma <= 0.0 {
+        return;
+    }
+    let delta = time.delta_seconds() * time_scale.get();
+    camera_shake.time += delta * camera_shake.frequency;
+    let t = camera_shake.time;
+
+    let shake = camera_shake.trauma * camera_shake.trauma;
+    let offset = camera_shake.max_offset * shake * vec2(shake_noise(t, 0.0), shake_noise(t, 13.0));
+    let angle = camera_shake.max_angle * shake * shake_noise(t, 29.0);
+    for mut transform in &mut cameras {
+        transform.translation += offset.extend(0.0);
+        transform.rotate_z(angle);
+    }
+
+    camera_shake.trauma = (camera_shake.trauma - camera_shake.decay * delta).max(0.0);
+}
+
+/// Smooth noise in the range `-1.0..=1.0`, made from a few sine waves that don't line up.
+///
+/// The `seed` offsets the noise, so that each axis of the shake moves differently.
+fn shake_noise(t: f32, seed: f32) -> f32 {
+    (t + seed).sin() * 0.5
+        + (t * 2.31 + seed * 1.7).sin() * 0.3
+        + (t * 4.13 + seed * 2.9).sin() * 0.2
+}
+
+/// Entities that move further than this in a single frame, in pixels, are teleported instead of
+/// being smoothed out, so that they don't streak across the screen when they respawn.
+const MAX_INTERPOLATED_DISTANCE: f32 = 100.0;
+
+/// Resource used to smooth out the movement of the rendered session's entities, when the display
+/// refreshes faster than the simulation runs.
+///
+/// Right before the renderer syncs the game session, the entities are moved part of the way
+/// between their transforms on the previous and the current simulation frame. The simulated
+/// transforms are put back right after, so this doesn't affect the deterministic simulation.
+///
+/// Cameras, and entities with the [`NoInterpolation`][jumpy_core::camera::NoInterpolation]
+/// component, are always rendered where they were simulated.
+#[derive(Resource, Default)]
+pub struct RenderInterpolation {
+    /// How far between the previous and the current simulation frame to render the entities, from
+    /// `0.0` to `1.0`.
+    pub alpha: f32,
+    /// The simulation frame that the previous transforms were recorded on.
+    frame: u64,
+    /// The transforms of the entities on the previous simulation frame.
+    previous: HashMap<bones::Entity, bones::Transform>,
+    /// The simulated transforms of the entities that were moved for rendering.
+    simulated: Vec<(bones::Entity, bones::Transform)>,
+}
+
+/// Record the transforms of the rendered session's entities, before it advances to the next frame.
+fn record_render_interpolation(
+    sessions: Option<ResMut<Sessions>>,
+    mut interpolation: ResMut<RenderInterpolation>,
+) {
+    let Some(mut sessions) = sessions else {
+        return;
+    };
+    let session = sessions.rendered_session_mut();
+
+    let transforms = session
+        .world
+        .run_initialized_system(
+            |entities: bones::Res<bones::Entities>, transforms: bones::Comp<bones::Transform>| {
+                Ok(entities
+                    .iter_with(&transforms)
+                    .map(|(ent, transform)| (ent, *transform))
+                    .collect::<Vec<_>>())
+            },
+        )
+        .unwrap();
+    interpolation.frame = session.frame();
+    interpolation.previous.clear();
+    interpolation.previous.extend(transforms);
+}
+
+/// Move the rendered session's entities between their previous and current transforms, and by
+/// their [`RollbackSmoothing`] offsets, so that the renderer picks up the smoothed out transforms.
+fn interpolate_transforms(
+    sessions: Option<ResMut<Sessions>>,
+    run_state: Res<SessionRunState>,
+    mut interpolation: ResMut<RenderInterpolation>,
+    mut smoothing: ResMut<RollbackSmoothing>,
+) {
+    let Some(mut sessions) = sessions else {
+        return;
+    };
+    // Only the main session is rolled back, so the offsets don't apply to the other ones
+    let offsets = if sessions.rendered_id() == SessionId::MAIN {
+        std::mem::take(&mut smoothing.offsets)
+    } else {
+        default()
+    };
+    let session = sessions.rendered_session_mut();
+
+    // Only smooth out the step from the recorded frame to the next one. This skips sessions that are
+    // paused, restarted, or edited, which would otherwise jitter back and forth.
+    let interpolate = !run_state.paused && session.frame() == interpolation.frame + 1;
+    if !interpolate && offsets.is_empty() {
+        smoothing.offsets = offsets;
+        return;
+    }
+
+    let alpha = interpolation.alpha;
+    let previous = std::mem::take(&mut interpolation.previous);
+    let mut simulated = std::mem::take(&mut interpolation.simulated);
+    let (previous, simulated, offsets) = session
+        .world
+        .run_initialized_system(
+            move |entities: bones::Res<bones::Entities>,
+                  cameras: bones::Comp<bones::Camera>,
+                  no_interpolation: bones::Comp<jumpy_core::camera::NoInterpolation>,
+                  mut transforms: bones::CompMut<bones::Transform>| {
+                for (ent, transform) in entities.iter_with(&mut transforms) {
+                    if cameras.contains(ent) || no_interpolation.contains(ent) {
+                        continue;
+                    }
+                    let prev = previous.get(&ent).filter(|prev| {
+                        interpolate
+                            && prev.translation.distance(transform.translation)
+                                <= MAX_INTERPOLATED_DISTANCE
+                    });
+                    let offset = offsets.get(&ent);
+                    if prev.is_none() && offset.is_none() {
+                        continue;
+                    }
+
+                    simulated.push((ent, *transform));
+                    if let Some(prev) = prev {
+                        transform.translation = prev.translation.lerp(transform.translation, alpha);
+                        transform.rotation = prev.rotation.slerp(transform.rotation, alpha);
+                        transform.scale = prev.scale.lerp(transform.scale, alpha);
+                    }
+                    if let Some(offset) = offset {
+                        transform.translation += *offset;
+                    }
+                }
+                Ok((previous, simulated, offsets))
+            },
+        )
+        .unwrap();
+    interpolation.previous = previous;
+    interpolation.simulated = simulated;
+    smoothing.offsets = offsets;
+}
+
+/// Put back the simulated transforms that were changed by [`interpolate_transforms`].
+fn restore_simulated_transforms(
+    sessions: Option<ResMut<Sessions>>,
+    mut interpolation: ResMut<RenderInterpolation>,
+) {
+    let Some(mut sessions) = sessions else {
+        interpolation.simulated.clear();
         return;
     };
+    if interpolation.simulated.is_empty() {
+        return;
+    }
 
-    // Get the sound queue out of the world
-    let queue = session
+    let simulated = std::mem::take(&mut interpolation.simulated);
+    let simulated = sessions
+        .rendered_session_mut()
         .world
-        .run_initialized_system(move |mut audio_events: bones::ResMut<bones::AudioEvents>| {
-            Ok(audio_events.queue.drain(..).collect::<Vec<_>>())
+        .run_initialized_system(move |mut transforms: bones::CompMut<bones::Transform>| {
+            for &(ent, transform) in &simulated {
+                if let Some(simulated_transform) = transforms.get_mut(ent) {
+                    *simulated_transform = transform;
+                }
+            }
+            Ok(simulated)
         })
         .unwrap();
 
+    // Keep the allocation around for the next frame
+    interpolation.simulated = simulated;
+    interpolation.simulated.clear();
+}
+
+/// The part of a rollback correction that is still smoothed out after each simulation frame.
+const SMOOTHING_DECAY: f32 = 0.8;
+
+/// Offsets shorter than this, in pixels, are too small to see and are dropped.
+const MIN_SMOOTHING_OFFSET: f32 = 0.1;
+
+/// Resource used to hide the corrections that rollbacks make to the main session's entities.
+///
+/// When a rollback moves an entity away from where it was predicted to be, the entity is rendered
+/// offset back towards the predicted position, and the offset shrinks over the following frames.
+/// Like the [`RenderInterpolation`], the offsets are only applied while the session is rendered,
+/// and never change the simulation. Corrections larger than the
+/// [`max_smoothing_distance`][RollbackSettings::max_smoothing_distance] snap right away.
+#[derive(Resource, Default)]
+pub struct RollbackSmoothing {
+    /// The frame that the main session is expected to be on the next time it advances.
+    frame: u64,
+    /// How far each entity is rendered from where it was simulated.
+    offsets: HashMap<bones::Entity, Vec3>,
+}
+
+impl RollbackSmoothing {
+    /// Get how far the entity is rendered from where it was simulated.
+    pub fn offset(&self, entity: bones::Entity) -> Vec3 {
+        self.offsets.get(&entity).copied().unwrap_or_default()
+    }
+
+    /// Shrink the offsets as the main session advances from `frame` to the next one.
+    ///
+    /// The offsets are dropped if the session didn't continue from the frame it was on before,
+    /// such as when it was restarted, because they don't apply to it anymore.
+    pub fn advance(&mut self, frame: u64) {
+        if frame != self.frame {
+            self.offsets.clear();
+        }
+        self.frame = frame + 1;
+        self.offsets.retain(|_, offset| {
+            *offset *= SMOOTHING_DECAY;
+            offset.length() >= MIN_SMOOTHING_OFFSET
+        });
+    }
+
+    /// Start smoothing out a rollback that moved the entities from their `predicted` translations
+    /// to their `corrected` ones.
+    ///
+    /// Entities that would end up further than `max_distance` from where they are simulated snap
+    /// to it instead.
+    pub fn add_correction(
+        &mut self,
+        predicted: &[(bones::Entity, Vec3)],
+        corrected: &[(bones::Entity, Vec3)],
+        max_distance: f32,
+    ) {
+        let corrected = corrected.iter().copied().collect::<HashMap<_, _>>();
+        for (ent, predicted) in predicted {
+            let Some(corrected) = corrected.get(ent) else {
+                continue;
+            };
+            let offset = self.offset(*ent) + *predicted - *corrected;
+            if offset.length() > max_distance || offset.length() < MIN_SMOOTHING_OFFSET {
+                self.offsets.remove(ent);
+            } else {
+                self.offsets.insert(*ent, offset);
+            }
+        }
+    }
+}
+
+/// Get the translations of the session's entities that rollback corrections are smoothed out for.
+///
+/// Cameras, and entities with the [`NoInterpolation`][jumpy_core::camera::NoInterpolation]
+/// component, are left out, the same as for the [`RenderInterpolation`].
+fn smoothed_translations(session: &mut GameSession) -> Vec<(bones::Entity, Vec3)> {
+    session
+        .world
+        .run_initialized_system(
+            |entities: bones::Res<bones::Entities>,
+             cameras: bones::Comp<bones::Camera>,
+             no_interpolation: bones::Comp<jumpy_core::camera::NoInterpolation>,
+             transforms: bones::Comp<bones::Transform>| {
+                Ok(entities
+                    .iter_with(&transforms)
+                    .filter(|(ent, _)| !cameras.contains(*ent) && !no_interpolation.contains(*ent))
+                    .map(|(ent, transform)| (ent, transform.translation))
+                    .collect::<Vec<_>>())
+            },
+        )
+        .unwrap()
+}
+
+/// Run criteria that runs the session stage once for every simulation frame that should have
+/// passed since the last update, taking the [`TimeScale`] into account.
+///
+/// The length of a frame comes from the simulation rate of the main session. The time left over
+/// after the last frame is used as the [`RenderInterpolation::alpha`].
+pub fn session_fixed_timestep(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    sessions: Option<Res<Sessions>>,
+    mut interpolation: ResMut<RenderInterpolation>,
+    mut accumulator: Local<f64>,
+    mut looping: Local<bool>,
+) -> ShouldRun {
+    let fps = sessions
+        .as_ref()
+        .and_then(|sessions| sessions.main())
+        .map(|session| session.info.fps)
+        .unwrap_or(jumpy_core::FPS);
+    let step = 1.0 / fps as f64;
+
+    // Only accumulate time on the first check of each frame
+    if !*looping {
+        *accumulator += time.delta_seconds_f64() * time_scale.get() as f64;
+    }
+
+    if *accumulator >= step {
+        *accumulator -= step;
+        *looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        *looping = false;
+        interpolation.alpha = (*accumulator / step).clamp(0.0, 1.0) as f32;
+        ShouldRun::No
+    }
+}
+
+/// Play out the [`FinalKillSlowMotion`] in real time, freezing the main session once it is over if
+/// the match has ended.
+fn update_final_kill_slow_motion(
+    time: Res<Time>,
+    mut slow_motion: ResMut<FinalKillSlowMotion>,
+    mut time_scale: ResMut<TimeScale>,
+    mut events: EventReader<SessionEvent>,
+    sessions: Option<ResMut<Sessions>>,
+) {
+    let restarted = events.iter().any(|event| {
+        matches!(
+            event,
+            SessionEvent::Restarted(SessionId::MAIN) | SessionEvent::Stopped(SessionId::MAIN)
+        )
+    });
+    if !slow_motion.is_playing() {
+        return;
+    }
+
+    // A new match doesn't start in slow motion
+    if restarted {
+        if let Some(previous) = slow_motion.stop() {
+            *time_scale = previous;
+        }
+        return;
+    }
+
+    if let Some(previous) = slow_motion.tick(time.delta()) {
+        *time_scale = previous;
+        if let Some(mut sessions) = sessions {
+            if sessions
+                .main()
+                .map_or(false, |x| x.match_result().is_some())
+            {
+                sessions.paused.insert(SessionId::MAIN);
+            }
+        }
+    }
+}
+
+/// Run criteria that checks that the game session isn't paused, or that a step was requested.
+fn session_should_advance(run_state: Res<SessionRunState>) -> bool {
+    // Note that the fixed timestep keeps running while we are paused, so we don't build up a backlog
+    // of frames to catch up on once we resume.
+    !run_state.paused || run_state.step_requested
+}
+
+/// Pause the game session when entering the pause menu.
+fn pause_session(mut session_manager: SessionManager) {
+    session_manager.pause();
+}
+
+/// Resume the game session when leaving the pause menu.
+fn resume_session(mut session_manager: SessionManager) {
+    session_manager.resume();
+}
+
+/// Pace the [`MusicLayers`] to the intensity of the match in the main session.
+fn update_music_intensity(sessions: Option<Res<Sessions>>, mut layers: ResMut<MusicLayers>) {
+    layers.intensity = sessions
+        .as_ref()
+        .and_then(|x| x.main())
+        .map_or(0.0, |session| session.match_intensity());
+}
+
+/// The announcements of the main session that have already been queued up in the
+/// [`AnnouncerVoice`], with the frame that each was made on.
+///
+/// Frames that are re-simulated after a rollback make their announcements again, which are skipped
+/// if they were already queued up. Frames further back than a rollback can reach are never
+/// re-simulated, so their announcements are forgotten.
+#[derive(Default)]
+struct QueuedAnnouncements {
+    announcements: HashSet<(u64, jumpy_core::announcer::Announcement)>,
+    /// The frame that the main session was on when its announcements were last queued up.
+    frame: u64,
+}
+
+impl QueuedAnnouncements {
+    /// Update the frame that the main session is on, forgetting the announcements of the last match
+    /// if the session was restarted, and the ones older than `max_rollback_frames`.
+    fn update_frame(&mut self, frame: u64, max_rollback_frames: usize) {
+        if frame < self.frame {
+            self.announcements.clear();
+        }
+        self.frame = frame;
+        let oldest = frame.saturating_sub(max_rollback_frames as u64 + 1);
+        self.announcements
+            .retain(|(announced, _)| *announced >= oldest);
+    }
+
+    /// Remember that the announcement made on `frame` was queued up, returning whether it wasn't
+    /// already.
+    fn insert(&mut self, frame: u64, announcement: jumpy_core::announcer::Announcement) -> bool {
+        self.announcements.insert((frame, announcement))
+    }
+}
+
+/// Pick the voice lines for the announcements made in the main session, and queue them up in the
+/// [`AnnouncerVoice`].
+fn play_announcements(
+    game: Option<Res<GameMeta>>,
+    sessions: Option<Res<Sessions>>,
+    rollback_settings: Res<RollbackSettings>,
+    mut voice: ResMut<AnnouncerVoice>,
+    mut queued: Local<QueuedAnnouncements>,
+) {
+    let Some(sessions) = sessions else {
+        return;
+    };
+    let mut announcements = Vec::new();
+    for (id, session) in sessions.iter() {
+        let announcer = session.world.resource::<jumpy_core::announcer::Announcer>();
+        let mut announcer = announcer.borrow_mut();
+        if id == SessionId::MAIN {
+            announcements.extend(announcer.drain());
+        } else {
+            announcer.queue.clear();
+        }
+    }
+    let (Some(game), Some(session)) = (game, sessions.main()) else {
+        return;
+    };
+
+    queued.update_frame(session.frame(), rollback_settings.max_rollback_frames);
+    for (frame, announcement) in announcements {
+        if !queued.insert(frame, announcement) {
+            continue;
+        }
+
+        // A new match cuts off whatever was still waiting to be announced from the last one
+        if announcement == jumpy_core::announcer::Announcement::MatchStart {
+            voice.clear();
+        }
+
+        // Pick the line from the seed of the match, so every client hears the same one
+        let lines = game.announcer.lines(announcement);
+        if lines.is_empty() {
+            continue;
+        }
+        let hash = (session.info.seed ^ frame).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let line = &lines[(hash >> 32) as usize % lines.len()];
+        voice.push(announcement, line.inner.clone_weak());
+    }
+}
+
+/// Play sounds from the game session.
+fn play_sounds(
+    audio: Res<AudioChannel<EffectsChannel>>,
+    spatial_settings: Res<SpatialAudioSettings>,
+    volume_settings: Res<VolumeSettings>,
+    mut looping_sounds: ResMut<LoopingSounds>,
+    mut sound_voices: ResMut<SoundVoices>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    run_state: Res<SessionRunState>,
+    time_scale: Res<TimeScale>,
+    sessions: Option<Res<Sessions>>,
+) {
+    sound_voices.retain_playing(&audio_instances);
+    let Some(sessions) = sessions else {
+        return;
+    };
+
+    // Only the main session makes any noise
+    for (_id, session) in sessions.iter().filter(|(id, _)| *id != SessionId::MAIN) {
+        let audio_events = session.world.resource::<jumpy_core::audio::AudioEvents>();
+        audio_events.borrow_mut().queue.clear();
+    }
+    let Some(session) = sessions.main() else {
+        return;
+    };
+
+    // Get the sound queue and the camera position out of the world
+    let (queue, listener) = session
+        .world
+        .run_initialized_system(
+            move |mut audio_events: bones::ResMut<jumpy_core::audio::AudioEvents>,
+                  entities: bones::Res<bones::Entities>,
+                  cameras: bones::Comp<bones::Camera>,
+                  transforms: bones::Comp<bones::Transform>| {
+                let listener = entities
+                    .iter_with((&cameras, &transforms))
+                    .next()
+                    .map(|(_ent, (_camera, transform))| transform.translation.truncate());
+
+                Ok((audio_events.queue.drain(..).collect::<Vec<_>>(), listener))
+            },
+        )
+        .unwrap();
+
+    // Pitch shift the sounds to match the simulation speed
+    let playback_rate = time_scale.get() as f64;
+
     // Play all the sounds in the queue
     for event in queue {
         match event {
-            bones::AudioEvent::PlaySound {
+            jumpy_core::audio::AudioEvent::PlaySound {
                 sound_source,
                 volume,
+                pitch,
+                priority,
+                position,
             } => {
-                audio
-                    .play(sound_source.get_bevy_handle_untyped().typed())
-                    .with_volume(volume.into());
+                let handle = sound_source.get_bevy_handle_untyped().typed();
+                let playback_rate = playback_rate * pitch as f64;
+                let volume = volume * volume_settings.effects_volume() * run_state.effects_volume();
+
+                // Spatialize sounds that have a position in the world
+                let (volume, panning) = match position.zip(listener) {
+                    Some((position, listener)) => {
+                        let offset = position - listener;
+                        let volume = volume * spatial_settings.attenuation(offset.length());
+
+                        // Skip sounds that are too far away to hear
+                        if volume <= 0.0 {
+                            continue;
+                        }
+
+                        (volume, Some(spatial_settings.panning(offset.x)))
+                    }
+                    _ => (volume, None),
+                };
+
+                // Cut off a less important sound if too many are playing already
+                match sound_voices.make_room(priority) {
+                    VoiceSlot::Free => (),
+                    VoiceSlot::Steal(instance) => sound_voices.stop(instance, &mut audio_instances),
+                    VoiceSlot::Full => continue,
+                }
+
+                let mut command = audio.play(handle);
+                command
+                    .with_volume(volume.into())
+                    .with_playback_rate(playback_rate);
+                if let Some(panning) = panning {
+                    command.with_panning(panning.into());
+                }
+                sound_voices.push(command.handle(), priority);
+            }
+            jumpy_core::audio::AudioEvent::PlayLoop {
+                sound_source,
+                volume,
+                handle_id,
+            } => {
+                let handle = sound_source.get_bevy_handle_untyped().typed();
+                let instance = audio
+                    .play(handle)
+                    .with_volume(
+                        (volume * volume_settings.effects_volume() * run_state.effects_volume())
+                            .into(),
+                    )
+                    .with_playback_rate(playback_rate)
+                    .looped()
+                    .handle();
+
+                if let Some(replaced) =
+                    looping_sounds.insert(handle_id, LoopingSound { instance, volume })
+                {
+                    looping_sounds.stop(replaced, &mut audio_instances);
+                }
+            }
+            jumpy_core::audio::AudioEvent::StopLoop { handle_id } => {
+                if let Some(sound) = looping_sounds.remove(handle_id) {
+                    looping_sounds.stop(sound, &mut audio_instances);
+                }
+            }
+        }
+    }
+}
+
+/// Update the volume and playback rate of looping sounds when the volume settings or time scale
+/// change, or the session is paused.
+///
+/// This runs every frame rather than with the session's fixed timestep, so that the settings are
+/// still applied while the session is frozen.
+fn update_looping_sounds(
+    volume_settings: Res<VolumeSettings>,
+    run_state: Res<SessionRunState>,
+    time_scale: Res<TimeScale>,
+    looping_sounds: Res<LoopingSounds>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if !volume_settings.is_changed() && !run_state.is_changed() && !time_scale.is_changed() {
+        return;
+    }
+
+    for sound in looping_sounds.iter() {
+        if let Some(instance) = audio_instances.get_mut(&sound.instance) {
+            let volume =
+                sound.volume * volume_settings.effects_volume() * run_state.effects_volume();
+            instance.set_volume(volume.into(), AudioTween::default());
+            instance.set_playback_rate(time_scale.get() as f64, AudioTween::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{asset::AssetPlugin, ecs::system::System};
+    use jumpy_core::input::{EditorInput, PlayerInputs};
+
+    use super::*;
+
+    /// Create an app with the resources needed by the [`SessionManager`].
+    fn session_manager_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<AudioInstance>()
+            .add_event::<SessionEvent>()
+            .init_resource::<AudioChannel<MusicChannel>>()
+            .init_resource::<CurrentMusic>()
+            .init_resource::<ShuffledPlaylist>()
+            .init_resource::<MusicSettings>()
+            .init_resource::<MusicLayers>()
+            .init_resource::<LoopingSounds>()
+            .init_resource::<SessionRunState>()
+            .init_resource::<CoreMetaArc>();
+        app
+    }
+
+    /// Run a system that uses the session manager once, applying it's commands.
+    fn run_session_manager<Marker>(app: &mut App, system: impl IntoSystem<(), (), Marker>) {
+        let mut system = IntoSystem::into_system(system);
+        system.initialize(&mut app.world);
+        system.run((), &mut app.world);
+        system.apply_buffers(&mut app.world);
+    }
+
+    #[test]
+    fn session_events_are_sent_in_order() {
+        let mut app = session_manager_app();
+
+        run_session_manager(&mut app, |mut session_manager: SessionManager| {
+            session_manager.start(GameSessionInfo {
+                meta: default(),
+                map_meta: default(),
+                player_info: default(),
+                player_colors: default(),
+                player_teams: default(),
+                friendly_fire: default(),
+                mode: default(),
+                seed: 42,
+                fps: jumpy_core::FPS,
+            });
+        });
+        run_session_manager(&mut app, |mut session_manager: SessionManager| {
+            session_manager.restart();
+        });
+        run_session_manager(&mut app, |mut session_manager: SessionManager| {
+            session_manager.stop();
+        });
+
+        let events = app.world.resource::<Events<SessionEvent>>();
+        let events = events
+            .get_reader()
+            .iter(events)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                SessionEvent::Started(SessionId::MAIN),
+                SessionEvent::Restarted(SessionId::MAIN),
+                SessionEvent::Stopped(SessionId::MAIN),
+            ]
+        );
+        assert!(app.world.get_resource::<Sessions>().is_none());
+    }
+
+    #[test]
+    fn session_stops_when_all_players_leave() {
+        let mut app = session_manager_app();
+        let mut player_info: [Option<bones::Handle<PlayerMeta>>; MAX_PLAYERS] = default();
+        player_info[0] = Some(default());
+
+        run_session_manager(&mut app, move |mut session_manager: SessionManager| {
+            session_manager.start(GameSessionInfo {
+                meta: default(),
+                map_meta: default(),
+                player_info: player_info.clone(),
+                player_colors: default(),
+                player_teams: default(),
+                friendly_fire: default(),
+                mode: default(),
+                seed: 42,
+                fps: jumpy_core::FPS,
+            });
+        });
+        run_session_manager(&mut app, |mut session_manager: SessionManager| {
+            session_manager.add_player(1, default());
+        });
+        run_session_manager(&mut app, |mut session_manager: SessionManager| {
+            session_manager.remove_player(0);
+        });
+        assert!(app.world.get_resource::<Sessions>().is_some());
+        assert!(app.world.get_resource::<NextState<EngineState>>().is_none());
+
+        run_session_manager(&mut app, |mut session_manager: SessionManager| {
+            session_manager.remove_player(1);
+        });
+        assert!(app.world.get_resource::<Sessions>().is_none());
+        assert_eq!(
+            app.world.resource::<NextState<EngineState>>().0,
+            EngineState::MainMenu
+        );
+
+        let events = app.world.resource::<Events<SessionEvent>>();
+        let events = events
+            .get_reader()
+            .iter(events)
+            .filter(|event| {
+                matches!(
+                    event,
+                    SessionEvent::PlayerJoined { .. } | SessionEvent::PlayerLeft { .. }
+                )
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                SessionEvent::PlayerJoined {
+                    session: SessionId::MAIN,
+                    player_idx: 0
+                },
+                SessionEvent::PlayerJoined {
+                    session: SessionId::MAIN,
+                    player_idx: 1
+                },
+                SessionEvent::PlayerLeft {
+                    session: SessionId::MAIN,
+                    player_idx: 0
+                },
+                SessionEvent::PlayerLeft {
+                    session: SessionId::MAIN,
+                    player_idx: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn editor_input_goes_to_local_player() {
+        let mut inputs = PlayerInputs::default();
+        let local_player = LocalPlayer {
+            idx: 2,
+            networked: true,
+        };
+
+        route_editor_input(
+            &mut inputs,
+            &local_player,
+            Some(EditorInput::CreateLayer { id: "layer".into() }),
+        );
+
+        for (i, input) in inputs.players.iter().enumerate() {
+            assert_eq!(input.editor_input.is_some(), i == 2);
+        }
+
+        // A local player outside of the session gets no editor input
+        let mut inputs = PlayerInputs::default();
+        let local_player = LocalPlayer {
+            idx: inputs.players.len(),
+            ..local_player
+        };
+        route_editor_input(
+            &mut inputs,
+            &local_player,
+            Some(EditorInput::CreateLayer { id: "layer".into() }),
+        );
+        assert!(inputs.players.iter().all(|x| x.editor_input.is_none()));
+    }
+
+    #[test]
+    fn spectator_camera_flies_inside_the_map() {
+        let bounds = Rect::from_corners(Vec2::ZERO, vec2(1000.0, 500.0));
+        let mut mode = SpectatorMode::Follow;
+        let fly = |mode: &mut SpectatorMode, input: SpectatorInput| {
+            mode.fly(
+                input,
+                vec2(100.0, 100.0),
+                200.0,
+                &[1, 3],
+                bounds,
+                100.0..=400.0,
+            )
+        };
+
+        // Doing nothing keeps following all of the players
+        fly(&mut mode, default());
+        assert_eq!(mode, SpectatorMode::Follow);
+
+        // Panning flies off from the camera, without leaving the map
+        let pan_left = SpectatorInput {
+            pan: Vec2::NEG_X,
+            ..default()
+        };
+        for _ in 0..100 {
+            fly(&mut mode, pan_left);
+        }
+        assert_eq!(
+            mode,
+            SpectatorMode::FreeFly {
+                position: vec2(0.0, 100.0),
+                height: 200.0,
+                following: None,
+            }
+        );
+
+        let zoom_out = SpectatorInput {
+            zoom: 1.0,
+            ..default()
+        };
+        for _ in 0..100 {
+            fly(&mut mode, zoom_out);
+        }
+        assert!(matches!(mode, SpectatorMode::FreeFly { height, .. } if height == 400.0));
+
+        // Following cycles through the living players, and panning lets go of them
+        let toggle_follow = SpectatorInput {
+            toggle_follow: true,
+            ..default()
+        };
+        let next_player = SpectatorInput {
+            next_player: true,
+            ..default()
+        };
+        let following = |mode: SpectatorMode| match mode {
+            SpectatorMode::FreeFly { following, .. } => following,
+            _ => None,
+        };
+        fly(&mut mode, toggle_follow);
+        assert_eq!(following(mode), Some(1));
+        fly(&mut mode, next_player);
+        assert_eq!(following(mode), Some(3));
+        fly(&mut mode, next_player);
+        assert_eq!(following(mode), Some(1));
+        fly(&mut mode, pan_left);
+        assert_eq!(following(mode), None);
+    }
+
+    #[test]
+    fn final_kill_slow_motion_restores_time_scale() {
+        let mut slow_motion = FinalKillSlowMotion::default();
+        let mut time_scale = TimeScale::new(2.0);
+
+        // Networked sessions always run in real time
+        slow_motion.start(&mut time_scale, true);
+        assert!(!slow_motion.is_playing());
+
+        slow_motion.start(&mut time_scale, false);
+        assert_eq!(time_scale.get(), TimeScale::MIN);
+        assert_eq!(slow_motion.tick(Duration::from_secs(1)), None);
+        assert_eq!(
+            slow_motion.tick(Duration::from_secs(1)),
+            Some(TimeScale::new(2.0))
+        );
+        assert!(!slow_motion.is_playing());
+
+        let mut slow_motion = FinalKillSlowMotion {
+            enabled: false,
+            ..default()
+        };
+        slow_motion.start(&mut time_scale, false);
+        assert!(!slow_motion.is_playing());
+    }
+
+    #[test]
+    fn default_players_clamped_to_metadata() {
+        let mut inputs = PlayerInputs::default();
+        let players = vec![bones::Handle::<PlayerMeta>::default()];
+
+        activate_default_players(&mut inputs, &players, 2, None);
+
+        for (i, input) in inputs.players.iter().enumerate() {
+            assert_eq!(input.active, i == 0);
+        }
+    }
+
+    #[test]
+    fn more_than_two_default_players() {
+        let mut inputs = PlayerInputs::default();
+        let players = vec![bones::Handle::<PlayerMeta>::default(); MAX_PLAYERS];
+
+        activate_default_players(&mut inputs, &players, 3, None);
+
+        for (i, input) in inputs.players.iter().enumerate() {
+            assert_eq!(input.active, i < 3);
+            assert!(input.bot.is_none());
+        }
+    }
+
+    #[test]
+    fn default_players_filled_with_bots() {
+        let mut inputs = PlayerInputs::default();
+        let players = vec![bones::Handle::<PlayerMeta>::default(); MAX_PLAYERS];
+        let difficulty = jumpy_core::bot::BotDifficulty(0.8);
+
+        activate_default_players(&mut inputs, &players, 3, Some(difficulty));
+
+        for (i, input) in inputs.players.iter().enumerate() {
+            assert_eq!(input.active, i < 3);
+            assert_eq!(input.bot, (i > 0 && i < 3).then_some(difficulty));
+        }
+    }
+
+    #[test]
+    fn presses_in_the_pause_menu_are_only_held() {
+        let mut control = jumpy_core::input::PlayerControl {
+            move_direction: Vec2::X,
+            just_moved: true,
+            jump_pressed: true,
+            jump_just_pressed: true,
+            emote: Some(1),
+            ..default()
+        };
+
+        release_presses(&mut control);
+
+        assert!(control.jump_pressed && !control.jump_just_pressed);
+        assert_eq!(control.move_direction, Vec2::X);
+        assert!(!control.just_moved);
+        assert_eq!(control.emote, None);
+    }
+
+    #[test]
+    fn input_is_only_collected_for_stepped_frames_while_paused() {
+        let mut sessions = Sessions::default();
+        sessions.insert(
+            SessionId::MAIN,
+            Session(jumpy_core::testing::test_session()),
+        );
+        let mut world = World::default();
+        world.insert_resource(sessions);
+        world.insert_resource(ScriptedInput::default().press_jump(0, 0));
+        world.insert_resource(RollbackSettings {
+            max_rollback_frames: 8,
+            ..default()
+        });
+        world.insert_resource(SessionRunState {
+            paused: true,
+            step_requested: false,
+        });
+        world.insert_resource(CurrentState(InGameState::Playing));
+        world.init_resource::<Rollback>();
+        world.init_resource::<NetSettings>();
+        world.init_resource::<InputDelay>();
+        world.init_resource::<CurrentEditorInput>();
+        world.init_resource::<LocalPlayer>();
+        world.init_resource::<InputSettings>();
+        world.init_resource::<SpectatorMode>();
+        world.init_resource::<InputRecorder>();
+        world.init_resource::<InputPlayback>();
+        world.init_resource::<EmoteWheels>();
+        let mut stage = SystemStage::single_threaded().with_system(update_input);
+
+        stage.run(&mut world);
+        assert!(!world.resource::<Rollback>().controls(0)[0].jump_pressed);
+
+        world.resource_mut::<SessionRunState>().step_requested = true;
+        stage.run(&mut world);
+        assert!(world.resource::<Rollback>().controls(0)[0].jump_pressed);
+    }
+
+    #[test]
+    fn networked_client_only_controls_local_player() {
+        let local_player = LocalPlayer {
+            idx: 2,
+            networked: true,
+        };
+        for i in 0..MAX_PLAYERS {
+            assert_eq!(local_player.controls_player(i), i == 2);
+        }
+
+        let local_player = LocalPlayer {
+            networked: false,
+            ..local_player
+        };
+        for i in 0..MAX_PLAYERS {
+            assert!(local_player.controls_player(i));
+        }
+    }
+
+    #[test]
+    fn last_session_is_never_removed() {
+        let mut sessions = Sessions::default();
+        sessions.insert(
+            SessionId::MAIN,
+            Session(jumpy_core::testing::test_session()),
+        );
+        sessions.insert(
+            SessionId::KILL_CAM,
+            Session(jumpy_core::testing::test_session()),
+        );
+        sessions.set_rendered(SessionId::KILL_CAM);
+
+        assert!(sessions.remove(SessionId::MAIN).is_some());
+        assert!(sessions.remove(SessionId::KILL_CAM).is_none());
+        assert_eq!(sessions.rendered_id(), SessionId::KILL_CAM);
+    }
+
+    #[test]
+    fn announcements_are_only_queued_once() {
+        use jumpy_core::announcer::Announcement;
+
+        let mut queued = QueuedAnnouncements::default();
+        queued.update_frame(10, 8);
+        assert!(queued.insert(0, Announcement::MatchStart));
+        assert!(queued.insert(8, Announcement::LastStock { player_idx: 1 }));
+
+        // Re-simulated frames make the same announcements again, and maybe some new ones
+        queued.update_frame(11, 8);
+        assert!(!queued.insert(8, Announcement::LastStock { player_idx: 1 }));
+        assert!(queued.insert(8, Announcement::LastStock { player_idx: 0 }));
+        assert!(queued.insert(9, Announcement::LastStock { player_idx: 1 }));
+
+        // Announcements too old to be re-simulated are forgotten
+        assert_eq!(queued.announcements.len(), 3);
+        queued.update_frame(17, 8);
+        assert_eq!(queued.announcements.len(), 3);
+        queued.update_frame(18, 8);
+        assert_eq!(queued.announcements.len(), 1);
+
+        // A restarted match is announced again
+        queued.update_frame(1, 8);
+        assert!(queued.insert(0, Announcement::MatchStart));
+    }
+
+    #[test]
+    fn rollback_corrections_are_smoothed_out() {
+        let mut entities = bones::Entities::default();
+        let (nudged, teleported) = (entities.create(), entities.create());
+        let predicted = [
+            (nudged, vec3(10.0, 0.0, 0.0)),
+            (teleported, vec3(500.0, 0.0, 0.0)),
+        ];
+        let corrected = [(nudged, Vec3::ZERO), (teleported, Vec3::ZERO)];
+
+        let mut smoothing = RollbackSmoothing::default();
+        smoothing.advance(0);
+        smoothing.add_correction(&predicted, &corrected, 48.0);
+
+        // The entity is still shown where it was predicted to be, unless it was corrected too far
+        assert_eq!(smoothing.offset(nudged), vec3(10.0, 0.0, 0.0));
+        assert_eq!(smoothing.offset(teleported), Vec3::ZERO);
+
+        // And it eases into its corrected position over the next frames
+        smoothing.advance(1);
+        let offset = smoothing.offset(nudged).x;
+        assert!(offset > 0.0 && offset < 10.0);
+        for frame in 2..30 {
+            smoothing.advance(frame);
+        }
+        assert_eq!(smoothing.offset(nudged), Vec3::ZERO);
+
+        // The offsets don't carry over to a restarted session
+        smoothing.add_correction(&predicted, &corrected, 48.0);
+        smoothing.advance(0);
+        assert_eq!(smoothing.offset(nudged), Vec3::ZERO);
+    }
+
+    /// Create a world for [`update_game`] to advance, with a main session that plays a networked
+    /// match with the other clients on the `transport`.
+    fn networked_game_world(transport: &crate::netplay::LoopbackTransport) -> World {
+        let mut session = jumpy_core::testing::test_session();
+        // The bevy world with the player metadata is kept in the test session's scratch world
+        let mut world = session.scratch_world.replace(default()).unwrap();
+        let netplay = Netplay::new(&session, Box::new(transport.clone()), default());
+        let mut sessions = Sessions::default();
+        sessions.insert(SessionId::MAIN, Session(session));
+
+        world.insert_resource(sessions);
+        world.insert_resource(netplay);
+        world.insert_resource(RollbackSettings {
+            max_rollback_frames: NETPLAY_ROLLBACK_FRAMES,
+            ..default()
+        });
+        world.init_resource::<Rollback>();
+        world.init_resource::<RollbackSmoothing>();
+        world.init_resource::<RenderInterpolation>();
+        world.init_resource::<SessionRunState>();
+        world.init_resource::<Events<SessionEvent>>();
+        world
+    }
+
+    #[test]
+    fn networked_sessions_stay_in_sync() {
+        use jumpy_core::input::PlayerControl;
+
+        let transports = crate::netplay::LoopbackTransport::connect(2);
+        let mut worlds = transports
+            .iter()
+            .map(networked_game_world)
+            .collect::<Vec<_>>();
+        for frame in 0..30 {
+            for world in &mut worlds {
+                // The second player turns around part way through, which the first client only
+                // finds out about after predicting otherwise
+                world.resource_scope(|world, mut netplay: Mut<Netplay>| {
+                    let direction = if netplay.player_idx == 1 && frame >= 12 {
+                        Vec2::NEG_X
+                    } else {
+                        Vec2::X
+                    };
+                    let control = PlayerControl {
+                        move_direction: direction,
+                        ..default()
+                    };
+                    netplay.add_local_input(&mut world.resource_mut::<Rollback>(), frame, control);
+                    netplay.send_inputs();
+                });
+                update_game(world);
             }
         }
+
+        let (host, client) = (&worlds[0], &worlds[1]);
+        for world in [host, client] {
+            let session = world.resource::<Sessions>().main().unwrap();
+            assert_eq!(session.frame(), 30);
+            assert_eq!(world.resource::<Netplay>().sync.desync_frame, None);
+        }
+        assert!(
+            host.resource::<Rollback>()
+                .stats()
+                .depths
+                .iter()
+                .sum::<u64>()
+                > 0
+        );
+        assert_eq!(
+            host.resource::<Sessions>().main().unwrap().state_hashes(),
+            client.resource::<Sessions>().main().unwrap().state_hashes()
+        );
     }
 }
@@ -9,8 +9,10 @@ pub mod widgets;
 
 pub mod debug_tools;
 pub mod editor;
+pub mod hud;
 pub mod main_menu;
 pub mod pause_menu;
+pub mod scoreboard;
 
 pub struct JumpyUiPlugin;
 
@@ -21,7 +23,9 @@ impl Plugin for JumpyUiPlugin {
             .add_plugin(main_menu::MainMenuPlugin)
             .add_plugin(editor::EditorPlugin)
             .add_plugin(debug_tools::DebugToolsPlugin)
+            .add_plugin(hud::HudPlugin)
             .add_plugin(pause_menu::PausePlugin)
+            .add_plugin(scoreboard::ScoreboardPlugin)
             .init_resource::<WidgetAdjacencies>()
             .init_resource::<DisableMenuInput>()
             .add_system_to_stage(
@@ -174,7 +178,7 @@ pub struct DisableMenuInput(pub bool);
 
 fn handle_menu_input(
     disable_menu_input: Res<DisableMenuInput>,
-    mut windows: ResMut<Windows>,
+    settings: Option<ResMut<Settings>>,
     input: Query<&ActionState<MenuAction>>,
     keyboard: Res<Input<KeyCode>>,
     mut egui_inputs: ResMut<bevy_egui::EguiRenderInputContainer>,
@@ -183,13 +187,10 @@ fn handle_menu_input(
 ) {
     let input = input.single();
 
-    // Handle fullscreen toggling
+    // Handle fullscreen toggling, which is applied to the window along with the other settings
     if input.just_pressed(MenuAction::ToggleFullscreen) {
-        if let Some(window) = windows.get_primary_mut() {
-            window.set_mode(match window.mode() {
-                WindowMode::BorderlessFullscreen => WindowMode::Windowed,
-                _ => WindowMode::BorderlessFullscreen,
-            });
+        if let Some(mut settings) = settings {
+            settings.display.fullscreen = !settings.display.fullscreen;
         }
     }
 
@@ -5,20 +5,21 @@ use bevy::{
 use bevy_egui::*;
 use bevy_fluent::Localization;
 
-use crate::prelude::*;
+use crate::{netplay::NetSettings, prelude::*};
 
 pub struct DebugToolsPlugin;
 
 impl Plugin for DebugToolsPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(FrameTimeDiagnosticsPlugin)
-            // .init_resource::<ShowNetworkVisualizer>()
+            .init_resource::<ShowNetplayDiagnostics>()
             .init_resource::<BonesSnapshot>()
             .init_resource::<CoreDebugSettings>()
             .init_resource::<ShowFameTimeDiagnostics>()
             .add_system(sync_core_debug_settings)
             .add_system(debug_tools_window)
-            .add_system(frame_diagnostic_window);
+            .add_system(frame_diagnostic_window)
+            .add_system(netplay_diagnostic_window);
     }
 }
 
@@ -27,35 +28,37 @@ impl Plugin for DebugToolsPlugin {
 pub struct CoreDebugSettings(pub jumpy_core::debug::DebugSettings);
 
 /// System to sync the core debug settings with any active bones sessions.
-fn sync_core_debug_settings(session: Option<ResMut<Session>>, settings: Res<CoreDebugSettings>) {
+fn sync_core_debug_settings(sessions: Option<ResMut<Sessions>>, settings: Res<CoreDebugSettings>) {
     if settings.is_changed() {
-        if let Some(mut session) = session {
-            session.world.insert_resource(settings.0);
+        if let Some(mut sessions) = sessions {
+            for (_id, session) in sessions.iter_mut() {
+                session.world.insert_resource(settings.0);
+            }
         }
     }
 }
 
-// #[derive(Deref, DerefMut, Default)]
-// pub struct ShowNetworkVisualizer(pub bool);
-
-// fn network_visualizer_window(
-//     show: Res<ShowNetworkVisualizer>,
-//     mut egui_context: ResMut<EguiContext>,
-//     mut visualizer: ResMut<RenetServerVisualizer<200>>,
-//     server: Res<RenetServer>,
-// ) {
-//     if **show {
-//         visualizer.update(&server);
-//         visualizer.show_window(egui_context.ctx_mut());
-//     }
-// }
+#[derive(Resource, Default, Deref, DerefMut)]
+struct ShowNetplayDiagnostics(pub bool);
 
 #[derive(Resource, Default, Deref, DerefMut)]
 struct ShowFameTimeDiagnostics(pub bool);
 
 /// Resource containing the bones snapshot.
 #[derive(Default, Resource)]
-struct BonesSnapshot(Option<bones::World>);
+struct BonesSnapshot(Option<jumpy_core::session::SessionSnapshot>);
+
+/// The resources used by the debug tools to record and play back the main session's inputs.
+#[derive(SystemParam)]
+struct InputRecordingParams<'w, 's> {
+    recorder: ResMut<'w, InputRecorder>,
+    playback: ResMut<'w, InputPlayback>,
+    net_settings: Res<'w, NetSettings>,
+    last_recording: Local<'s, Option<InputRecording>>,
+    core: Res<'w, CoreMetaArc>,
+    map_assets: Res<'w, Assets<MapMeta>>,
+    commands: Commands<'w, 's>,
+}
 
 /// System that renders the debug tools window which can be toggled by pressing F12
 fn debug_tools_window(
@@ -63,12 +66,26 @@ fn debug_tools_window(
     mut visible: Local<bool>,
     mut egui_context: ResMut<EguiContext>,
     mut show_frame_diagnostics: ResMut<ShowFameTimeDiagnostics>,
+    mut show_netplay_diagnostics: ResMut<ShowNetplayDiagnostics>,
     localization: Res<Localization>,
     input: Res<Input<KeyCode>>,
     mut show_inspector: ResMut<WorldInspectorEnabled>,
     mut bones_world_snapshot: ResMut<BonesSnapshot>,
-    session: Option<ResMut<Session>>,
+    mut session_manager: SessionManager,
+    recording: InputRecordingParams,
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut time_scale: ResMut<TimeScale>,
+    mut determinism_audit: ResMut<DeterminismAudit>,
 ) {
+    let InputRecordingParams {
+        mut recorder,
+        mut playback,
+        net_settings,
+        mut last_recording,
+        core,
+        map_assets,
+        mut commands,
+    } = recording;
     let ctx = egui_context.ctx_mut();
 
     // Toggle debug window visibility
@@ -92,10 +109,10 @@ fn debug_tools_window(
         **show_frame_diagnostics = !**show_frame_diagnostics;
     }
 
-    // // Shortcut to toggle network visualizers
-    // if input.just_pressed(KeyCode::F7) {
-    //     **show_network_visualizer = !**show_network_visualizer;
-    // }
+    // Shortcut to toggle netplay diagnostics
+    if input.just_pressed(KeyCode::F7) {
+        **show_netplay_diagnostics = !**show_netplay_diagnostics;
+    }
 
     // Display debug tool window
     egui::Window::new(localization.get("debug-tools"))
@@ -130,10 +147,10 @@ fn debug_tools_window(
             ui.heading(localization.get("snapshot"));
             ui.horizontal(|ui| {
                 ui.scope(|ui| {
-                    ui.set_enabled(session.is_some());
+                    ui.set_enabled(session_manager.session().is_some());
 
                     if ui.button(localization.get("take-snapshot")).clicked() {
-                        if let Some(session) = &session {
+                        if let Some(session) = session_manager.session() {
                             bones_world_snapshot.0 = Some(session.snapshot());
                         }
                     }
@@ -142,9 +159,9 @@ fn debug_tools_window(
                         ui.set_enabled(bones_world_snapshot.0.is_some());
 
                         if ui.button(localization.get("restore-snapshot")).clicked() {
-                            if let Some(mut session) = session {
-                                if let Some(snapshot) = &mut bones_world_snapshot.0 {
-                                    session.restore(&mut snapshot.clone())
+                            if let Some(session) = session_manager.session_mut() {
+                                if let Some(snapshot) = &bones_world_snapshot.0 {
+                                    session.restore(snapshot)
                                 }
                             }
                         }
@@ -152,11 +169,169 @@ fn debug_tools_window(
                 });
             });
 
-            // Show network visualizer
-            // ui.checkbox(
-            //     &mut show_network_visualizer,
-            //     format!("{} ( F7 )", localization.get("show-network-visualizer")),
-            // );
+            // Frame step buttons
+            ui.add_space(2.0);
+            ui.heading(localization.get("frame-step"));
+            ui.horizontal(|ui| {
+                ui.set_enabled(session_manager.session().is_some());
+
+                if session_manager.run_state.paused {
+                    if ui.button(localization.get("resume-session")).clicked() {
+                        session_manager.resume();
+                    }
+                } else if ui.button(localization.get("pause-session")).clicked() {
+                    session_manager.pause();
+                }
+
+                ui.scope(|ui| {
+                    ui.set_enabled(session_manager.run_state.paused);
+
+                    if ui.button(localization.get("step-frame")).clicked() {
+                        session_manager.step_once();
+                    }
+                });
+            });
+
+            // Input recording buttons
+            ui.add_space(2.0);
+            ui.heading(localization.get("input-recording"));
+            ui.horizontal(|ui| {
+                ui.set_enabled(session_manager.session().is_some());
+
+                if recorder.is_recording() {
+                    if ui.button(localization.get("stop-recording")).clicked() {
+                        if let Some(recording) = recorder.stop() {
+                            #[cfg(not(target_arch = "wasm32"))]
+                            {
+                                let path = InputRecording::default_path();
+                                match recording.save(&path) {
+                                    Ok(()) => info!(?path, "Saved input recording"),
+                                    Err(e) => error!("Error saving input recording: {e}"),
+                                }
+
+                                // Also save a replay that can be shared with other players
+                                if let Some(session) = session_manager.session() {
+                                    let path = Replay::default_path();
+                                    match session.save_replay(&path, recording.clone()) {
+                                        Ok(()) => info!(?path, "Saved replay"),
+                                        Err(e) => error!("Error saving replay: {e}"),
+                                    }
+                                }
+                            }
+                            *last_recording = Some(recording);
+                        }
+                    }
+                } else if ui.button(localization.get("start-recording")).clicked() {
+                    // Restart the session so that the recording starts from the first frame.
+                    if let Some(session) = session_manager.session() {
+                        playback.stop();
+                        recorder.start(session.seed(), net_settings.input_delay_frames);
+                        session_manager.restart();
+                    }
+                }
+
+                ui.scope(|ui| {
+                    ui.set_enabled(!recorder.is_recording());
+
+                    if ui.button(localization.get("replay-recording")).clicked() {
+                        // Load the recording from the last run if we haven't made one yet.
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            if last_recording.is_none() {
+                                let path = InputRecording::default_path();
+                                match InputRecording::load(&path) {
+                                    Ok(recording) => *last_recording = Some(recording),
+                                    Err(e) => error!("Error loading input recording: {e}"),
+                                }
+                            }
+                        }
+
+                        if let Some(recording) = &*last_recording {
+                            // Restart the session with the recorded seed, so the replay plays out
+                            // the same way.
+                            if let Some(session) = session_manager.session_mut() {
+                                session.info.seed = recording.seed;
+                            }
+                            session_manager.restart();
+                            playback.start(recording.clone());
+                            replay_player.reset();
+                        }
+                    }
+                });
+            });
+
+            // Watching a saved replay starts a new session, so it works from the menus too
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button(localization.get("play-saved-replay")).clicked() {
+                let path = Replay::default_path();
+                match Replay::load(&path).and_then(|replay| {
+                    replay.playback(&core.0, &map_assets, ENGINE_CONFIG.simulation_rate)
+                }) {
+                    Ok((info, replay_playback)) => {
+                        info!(?path, "Playing replay");
+                        recorder.stop();
+                        session_manager.start(info);
+                        *playback = replay_playback;
+                        replay_player.reset();
+                        commands.insert_resource(NextState(EngineState::InGame));
+                        commands.insert_resource(NextState(InGameState::Playing));
+                    }
+                    Err(e) => error!("Error playing replay: {e}"),
+                }
+            }
+
+            // Replay scrubbing and speed, while a recording is played back
+            if playback.is_playing() {
+                ui.add_space(2.0);
+                ui.heading(localization.get("replay-playback"));
+                if let Some(session) = session_manager.session() {
+                    let mut frame = session.frame();
+                    let last_frame = playback.last_frame().unwrap_or_default();
+                    let slider = egui::Slider::new(&mut frame, 0..=last_frame)
+                        .text(localization.get("replay-frame"));
+                    if ui.add(slider).changed() {
+                        replay_player.seek(frame);
+                    }
+                }
+
+                let mut speed = time_scale.get();
+                let slider = egui::Slider::new(&mut speed, TimeScale::MIN..=TimeScale::MAX)
+                    .text(localization.get("replay-speed"));
+                if ui.add(slider).changed() {
+                    time_scale.set(speed);
+                }
+            }
+
+            // Determinism audit
+            ui.add_space(2.0);
+            ui.heading(localization.get("determinism-audit"));
+            ui.scope(|ui| {
+                ui.set_enabled(session_manager.session().is_some());
+
+                let was_enabled = determinism_audit.enabled;
+                ui.checkbox(
+                    &mut determinism_audit.enabled,
+                    localization.get("enable-determinism-audit"),
+                );
+                if determinism_audit.enabled && !was_enabled {
+                    determinism_audit.divergence = None;
+                }
+            });
+            if let Some(divergence) = determinism_audit.divergence {
+                ui.label(format!(
+                    "{} {}: {}",
+                    localization.get("determinism-audit-diverged"),
+                    divergence.frame,
+                    divergence.component
+                ));
+            }
+
+            // Show netplay diagnostics
+            ui.add_space(2.0);
+            ui.checkbox(
+                &mut show_netplay_diagnostics,
+                format!("{} ( F7 )", localization.get("show-netplay-diagnostics")),
+            );
         });
 }
 
@@ -240,3 +415,88 @@ fn frame_diagnostic_window(
             });
     }
 }
+
+/// System that renders the netplay diagnostics window, showing the input delay and how much the
+/// game is having to roll back, which helps to diagnose laggy networked matches.
+///
+/// This only reads the statistics kept by the [`Rollback`], and doesn't affect the simulation.
+fn netplay_diagnostic_window(
+    mut egui_context: ResMut<EguiContext>,
+    mut show: ResMut<ShowNetplayDiagnostics>,
+    rollback_settings: Res<RollbackSettings>,
+    net_settings: Res<NetSettings>,
+    rollback: Option<Res<Rollback>>,
+    localization: Res<Localization>,
+) {
+    if !**show {
+        return;
+    }
+    let ctx = egui_context.ctx_mut();
+    let stats = rollback
+        .as_ref()
+        .map(|x| x.stats().clone())
+        .unwrap_or_default();
+
+    egui::Window::new(&localization.get("netplay-diagnostics"))
+        .id(egui::Id::new("netplay_diagnostics"))
+        .default_width(300.0)
+        .open(&mut show)
+        .show(ctx, |ui| {
+            if !rollback_settings.is_enabled() {
+                ui.label(localization.get("rollback-disabled"));
+            }
+
+            ui.monospace(localization.get(&format!(
+                "input-delay-frames?frames={}&ms={:.0}",
+                net_settings.input_delay_frames,
+                stats.frames_to_ms(net_settings.input_delay_frames)
+            )));
+            ui.monospace(localization.get(&format!(
+                "rollback-frames-last-second?frames={}",
+                stats.rollback_frames_last_second()
+            )));
+
+            // Ping of each remote player, estimated from how late their inputs arrive
+            ui.add_space(2.0);
+            ui.heading(localization.get("remote-players"));
+            for (player_idx, lateness) in stats.input_lateness.iter().enumerate() {
+                let Some(lateness) = lateness else {
+                    continue;
+                };
+                ui.monospace(localization.get(&format!(
+                    "remote-player-lateness?player={}&frames={lateness}&ms={:.0}",
+                    player_idx + 1,
+                    stats.frames_to_ms(*lateness)
+                )));
+            }
+
+            // Rolling graph of the rollback depth of each frame, scaled to the deepest rollback
+            // that is allowed
+            ui.add_space(2.0);
+            ui.heading(localization.get("rollback-depth"));
+            let max_depth = (rollback_settings.max_rollback_frames as u64)
+                .max(stats.depths.iter().copied().max().unwrap_or_default())
+                .max(1);
+            let (response, painter) =
+                ui.allocate_painter(egui::vec2(ui.available_width(), 80.0), egui::Sense::hover());
+            let rect = response.rect;
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(128));
+            let history_frames = stats.history_frames();
+            let bar_width = rect.width() / history_frames as f32;
+            let offset = history_frames.saturating_sub(stats.depths.len());
+            for (i, depth) in stats.depths.iter().enumerate().filter(|(_, x)| **x > 0) {
+                let x = rect.left() + (offset + i) as f32 * bar_width;
+                let height = rect.height() * *depth as f32 / max_depth as f32;
+                painter.rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(x, rect.bottom() - height),
+                        egui::pos2(x + bar_width, rect.bottom()),
+                    ),
+                    0.0,
+                    egui::Color32::LIGHT_RED,
+                );
+            }
+            response
+                .on_hover_text(localization.get(&format!("rollback-depth-max?frames={max_depth}")));
+        });
+}
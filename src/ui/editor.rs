@@ -75,9 +75,9 @@ impl EditorTool {
     }
 }
 
-pub fn cleanup_editor(session: Option<ResMut<Session>>) {
+pub fn cleanup_editor(sessions: Option<ResMut<Sessions>>) {
     // Update camera viewport to fit into central editor area.
-    if let Some(session) = session {
+    if let Some(session) = sessions.as_ref().and_then(|x| x.main()) {
         let cameras = session.world.components.get::<bones::Camera>();
         let mut cameras = cameras.borrow_mut();
         let camera = cameras.iter_mut().next().unwrap();
@@ -98,7 +98,8 @@ pub fn editor_ui_system(world: &mut World) {
     // Force set the camera position
     {
         let world = world.cell();
-        let session = world.get_resource::<Session>();
+        let session = world.get_resource::<Sessions>();
+        let session = session.as_ref().and_then(|x| x.main());
         let editor_state = world.resource_mut::<EditorState>();
         let camera_info = editor_state.camera;
         if let Some(session) = session {
@@ -139,7 +140,8 @@ pub fn editor_ui_system(world: &mut World) {
     // Get the up-to-date map meta export from the world
     let map_meta = {
         world
-            .get_resource::<Session>()
+            .get_resource::<Sessions>()
+            .and_then(|x| x.main())
             .map(|sess| sess.export_map())
     };
     world.insert_resource(EditorMapExport(map_meta));
@@ -265,7 +267,7 @@ impl<'w, 's> WidgetSystem for EditorTopBar<'w, 's> {
                 }
 
                 ui.scope(|ui| {
-                    ui.set_enabled(params.session_manager.session.is_some());
+                    ui.set_enabled(params.session_manager.session().is_some());
                     if ui.button(&params.localization.get("play")).clicked() {
                         params
                             .commands
@@ -286,6 +288,12 @@ impl<'w, 's> WidgetSystem for EditorTopBar<'w, 's> {
                             meta: params.core_meta.0.clone(),
                             map_meta: params.map_export.0.as_ref().unwrap().clone(),
                             player_info: default(),
+                            player_colors: default(),
+                            player_teams: default(),
+                            friendly_fire: default(),
+                            mode: default(),
+                            seed: rand::random(),
+                            fps: ENGINE_CONFIG.simulation_rate,
                         });
                         params
                             .commands
@@ -702,7 +710,7 @@ impl<'w, 's> WidgetSystem for EditorCentralPanel<'w, 's> {
             .collect::<Vec<_>>();
         element_categories.sort_by(|a, b| a.0.cmp(&b.0));
 
-        if let Some(session) = params.session_manager.session {
+        if let Some(session) = params.session_manager.session() {
             let core_meta = session.world.resource::<CoreMetaArc>();
             let core_meta = core_meta.borrow();
 
@@ -1012,6 +1020,12 @@ fn map_open_dialog(ui: &mut egui::Ui, params: &mut EditorCentralPanel) {
                                 meta: params.core_meta.0.clone(),
                                 map_meta: (*map_meta).clone(),
                                 player_info: default(),
+                                player_colors: default(),
+                                player_teams: default(),
+                                friendly_fire: default(),
+                                mode: default(),
+                                seed: rand::random(),
+                                fps: ENGINE_CONFIG.simulation_rate,
                             });
                             *params.show_map_open = false;
                             // TODO: center camera.
@@ -0,0 +1,181 @@
+//! The in-game heads-up display.
+
+use bevy_egui::*;
+use bevy_fluent::Localization;
+use jumpy_core::announcer::Announcement;
+
+use crate::{localization::LocalizationExt, metadata::GameMeta, prelude::*, EngineState};
+
+use super::widgets::EguiUiExt;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            hud.run_in_state(EngineState::InGame)
+                .run_if_resource_exists::<Sessions>(),
+        );
+    }
+}
+
+/// Render the ammo of the weapon that each player in the main session is holding, the combos that
+/// they are taking, a warning for the players that are about to be knocked out of the map, and the
+/// emote wheels that are open, with a banner over it all while the kill cam is playing, and a
+/// caption for the announcer's line.
+fn hud(
+    mut egui_context: ResMut<EguiContext>,
+    game: Res<GameMeta>,
+    core_meta: Res<CoreMetaArc>,
+    localization: Res<Localization>,
+    sessions: Res<Sessions>,
+    emote_wheels: Res<EmoteWheels>,
+    kill_cam: Res<KillCam>,
+    announcer_voice: Res<AnnouncerVoice>,
+) {
+    let Some(session) = sessions.main() else {
+        return;
+    };
+    let hud_theme = &game.ui_theme.hud;
+    let player_ammo = session.player_ammo();
+    let players_out_of_bounds = session.players_out_of_bounds();
+    let player_combos = session.player_combos();
+    let respawn_delays = session.player_respawn_delays();
+    let fps = session.info.fps;
+
+    egui::Area::new("hud")
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0))
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for (i, (ammo, out_of_bounds)) in
+                    player_ammo.iter().zip(&players_out_of_bounds).enumerate()
+                {
+                    ui.allocate_ui(egui::vec2(hud_theme.player_hud_width, 0.0), |ui| {
+                        ui.vertical(|ui| {
+                            let player = i + 1;
+                            if let Some(ammo) = ammo {
+                                let ammo = if ammo.is_reloading() {
+                                    localization.get(&format!("hud-reloading?player={player}"))
+                                } else {
+                                    localization.get(&format!(
+                                        "hud-ammo?player={player}&count={}&max={}",
+                                        ammo.count, ammo.max
+                                    ))
+                                };
+                                ui.themed_label(&hud_theme.font, &ammo);
+                            }
+                            // A single hit isn't a combo yet
+                            if player_combos[i] > 1 {
+                                let combo = localization.get(&format!(
+                                    "hud-combo?player={player}&hits={}",
+                                    player_combos[i]
+                                ));
+                                ui.themed_label(&hud_theme.font, &combo);
+                            }
+                            if let Some(out_of_bounds) = out_of_bounds {
+                                let arrow = out_of_bounds_arrow(out_of_bounds.direction);
+                                let warning = localization.get(&format!(
+                                    "hud-out-of-bounds?player={player}&arrow={arrow}"
+                                ));
+                                ui.themed_label(&hud_theme.font, &warning);
+                            }
+                            if let Some(frames) = respawn_delays[i] {
+                                let seconds = (frames as f32 / fps).ceil();
+                                let respawning = localization.get(&format!(
+                                    "hud-respawning?player={player}&seconds={seconds}"
+                                ));
+                                ui.themed_label(&hud_theme.font, &respawning);
+                            }
+                            let wheel = &emote_wheels.players[i];
+                            if wheel.open {
+                                let names = core_meta
+                                    .emotes
+                                    .wheel
+                                    .iter()
+                                    .map(|x| localization.get(&format!("emote-{}", x.name)))
+                                    .collect::<Vec<_>>();
+                                emote_wheel(ui, &hud_theme.font, &names, wheel.selection);
+                            }
+                        });
+                    });
+                }
+            });
+        });
+
+    if kill_cam.is_playing() {
+        egui::Area::new("kill-cam")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+            .interactable(false)
+            .show(egui_context.ctx_mut(), |ui| {
+                ui.themed_label(&hud_theme.font, &localization.get("hud-kill-cam"));
+            });
+    }
+
+    if let Some(announcement) = announcer_voice.playing() {
+        let caption = match announcement {
+            Announcement::MatchStart => localization.get("announcer-match-start"),
+            Announcement::MultiKill { player_idx, kills } => localization.get(&format!(
+                "announcer-multi-kill?player={}&kills={kills}",
+                player_idx + 1
+            )),
+            Announcement::LastStock { player_idx } => {
+                localization.get(&format!("announcer-last-stock?player={}", player_idx + 1))
+            }
+            Announcement::Victory {
+                winner: Some(winner),
+            } => localization.get(&format!("announcer-victory?player={}", winner + 1)),
+            Announcement::Victory { winner: None } => localization.get("announcer-draw"),
+        };
+        egui::Area::new("announcer")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .interactable(false)
+            .show(egui_context.ctx_mut(), |ui| {
+                ui.themed_label(&hud_theme.font, &caption);
+            });
+    }
+}
+
+/// Render an emote wheel with the emote `names` laid out clockwise from the top, highlighting the
+/// selected one.
+fn emote_wheel(ui: &mut egui::Ui, font: &FontMeta, names: &[String], selection: Option<usize>) {
+    let size = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let painter = ui.painter();
+    let radius = size / 2.0;
+    let color = font.color.into_egui();
+    painter.circle_stroke(rect.center(), radius - 1.0, (1.0, color));
+
+    for (i, name) in names.iter().enumerate() {
+        let angle = i as f32 / names.len() as f32 * std::f32::consts::TAU;
+        // Egui's y axis points down
+        let position = rect.center() + egui::vec2(angle.sin(), -angle.cos()) * radius * 0.6;
+        let text = if selection == Some(i) {
+            format!("[{name}]")
+        } else {
+            name.clone()
+        };
+        painter.text(
+            position,
+            egui::Align2::CENTER_CENTER,
+            text,
+            font.font_id(),
+            color,
+        );
+    }
+}
+
+/// Get an arrow pointing in the direction that a player left the blast zone in.
+fn out_of_bounds_arrow(direction: Vec2) -> &'static str {
+    if direction.x.abs() > direction.y.abs() {
+        if direction.x > 0.0 {
+            "→"
+        } else {
+            "←"
+        }
+    } else if direction.y > 0.0 {
+        "↑"
+    } else {
+        "↓"
+    }
+}
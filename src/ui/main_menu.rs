@@ -10,7 +10,6 @@ use bevy_fluent::Localization;
 use crate::{
     localization::LocalizationExt,
     metadata::{GameMeta, Settings},
-    platform::Storage,
     prelude::*,
     ui::input::MenuAction,
 };
@@ -152,8 +151,9 @@ impl Default for MenuPage {
 impl SettingsTab {
     const TABS: &'static [(Self, &'static str)] = &[
         (Self::Controls, "controls"),
-        (Self::Networking, "networking"), // For now, hide the sound tab because we don't have it working yet.
-                                          // (Self::Sound, "sound")
+        (Self::Networking, "networking"),
+        (Self::Language, "language"), // For now, hide the sound tab because we don't have it working yet.
+                                      // (Self::Sound, "sound")
     ];
 }
 
@@ -222,7 +222,7 @@ struct HomeMenu<'w, 's> {
     game: Res<'w, GameMeta>,
     localization: Res<'w, Localization>,
     app_exit: EventWriter<'w, 's, AppExit>,
-    storage: ResMut<'w, Storage>,
+    settings: Res<'w, Settings>,
 }
 
 impl<'w, 's> WidgetSystem for HomeMenu<'w, 's> {
@@ -335,12 +335,7 @@ impl<'w, 's> WidgetSystem for HomeMenu<'w, 's> {
                     .clicked()
                     {
                         *params.menu_page = MenuPage::Settings;
-                        **params.modified_settings = Some(
-                            params
-                                .storage
-                                .get(Settings::STORAGE_KEY)
-                                .unwrap_or_else(|| params.game.default_settings.clone()),
-                        );
+                        **params.modified_settings = Some(params.settings.clone());
                     }
 
                     // Quit button
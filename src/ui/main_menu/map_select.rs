@@ -118,17 +118,45 @@ impl<'w, 's> WidgetSystem for MapSelectMenu<'w, 's> {
                                         let mut player_info = <[Option<bones::Handle<PlayerMeta>>;
                                             MAX_PLAYERS]>::default(
                                         );
+                                        let mut player_colors =
+                                            <[Option<ColorMeta>; MAX_PLAYERS]>::default();
+                                        let mut player_teams =
+                                            <[Option<u8>; MAX_PLAYERS]>::default();
                                         (0..MAX_PLAYERS).for_each(|i| {
                                             let slot = &params.player_select_state.slots[i];
                                             if slot.active {
                                                 player_info[i] = Some(slot.selected_player.clone());
+                                                player_teams[i] = slot.team;
+                                                player_colors[i] = params
+                                                    .core
+                                                    .player_colors
+                                                    .get(slot.color_idx)
+                                                    .copied();
                                             }
                                         });
-                                        params.session_manager.start(GameSessionInfo {
+                                        let transport = crate::netplay::connect_from_config();
+                                        let info = GameSessionInfo {
                                             meta: params.core.0.clone(),
                                             map_meta: map_meta.clone(),
                                             player_info,
-                                        });
+                                            player_colors,
+                                            player_teams,
+                                            friendly_fire: default(),
+                                            mode: default(),
+                                            // There's no lobby to share a random seed in yet, so
+                                            // every client plays networked matches with the same one
+                                            seed: if transport.is_some() {
+                                                0
+                                            } else {
+                                                rand::random()
+                                            },
+                                            fps: ENGINE_CONFIG.simulation_rate,
+                                        };
+                                        if let Some(transport) = transport {
+                                            params.session_manager.start_networked(info, transport);
+                                        } else {
+                                            params.session_manager.start(info);
+                                        }
                                         params
                                             .commands
                                             .insert_resource(NextState(EngineState::InGame));
@@ -14,7 +14,7 @@ pub struct MatchmakingMenu<'w, 's> {
     commands: Commands<'w, 's>,
     menu_page: ResMut<'w, MenuPage>,
     game: Res<'w, GameMeta>,
-    storage: ResMut<'w, Storage>,
+    settings: Res<'w, Settings>,
     localization: Res<'w, Localization>,
     state: Local<'s, State>,
     menu_input: Query<'w, 's, &'static mut ActionState<MenuAction>>,
@@ -146,7 +146,7 @@ impl<'w, 's> WidgetSystem for MatchmakingMenu<'w, 's> {
                 ui.horizontal(|ui| {
                     ui.themed_label(
                         bigger_text_style,
-                        &format!("{}: ", params.localization.get("player-count")),
+                        &params.localization.get("player-count"),
                     );
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -192,10 +192,7 @@ impl<'w, 's> WidgetSystem for MatchmakingMenu<'w, 's> {
                         .show(ui)
                         .clicked()
                         {
-                            let matchmaking_server =
-                                Settings::get_stored_or_default(&params.game, &mut params.storage)
-                                    .matchmaking_server
-                                    .clone();
+                            let matchmaking_server = params.settings.matchmaking_server.clone();
 
                             let io_pool = IoTaskPool::get();
                             let (status_sender, status_receiver) = async_channel::unbounded();
@@ -16,6 +16,10 @@ pub struct PlayerSlot {
     pub active: bool,
     pub confirmed: bool,
     pub selected_player: bones::Handle<PlayerMeta>,
+    /// The index of the selected color accent in the core metadata's player colors.
+    pub color_idx: usize,
+    /// The team that the player picked, by it's index in the core metadata's team colors, if any.
+    pub team: Option<u8>,
 }
 
 #[derive(SystemParam)]
@@ -234,15 +238,22 @@ fn get_player_actions(idx: usize, map: &InputMap<PlayerAction>) -> PlayerActionM
 
     let mut jump_actions = vec![get_user_action(map_idx, PlayerAction::Jump, map)];
     let mut grab_actions = vec![get_user_action(map_idx, PlayerAction::Grab, map)];
+    let mut shoot_actions = vec![get_user_action(map_idx, PlayerAction::Shoot, map)];
 
     if idx <= 1 {
         jump_actions.push(get_user_action(GAMEPAD_ACTION_IDX, PlayerAction::Jump, map));
         grab_actions.push(get_user_action(GAMEPAD_ACTION_IDX, PlayerAction::Grab, map));
+        shoot_actions.push(get_user_action(
+            GAMEPAD_ACTION_IDX,
+            PlayerAction::Shoot,
+            map,
+        ));
     }
 
     PlayerActionMap(HashMap::from_iter(vec![
         (PlayerAction::Jump, jump_actions),
         (PlayerAction::Grab, grab_actions),
+        (PlayerAction::Shoot, shoot_actions),
     ]))
 }
 
@@ -348,6 +359,8 @@ impl<'w, 's> WidgetSystem for PlayerSelectPanel<'w, 's> {
                 slot.confirmed = true;
             } else {
                 slot.active = true;
+                // Give each player a different color to start with
+                slot.color_idx = player_id % params.core.player_colors.len().max(1);
             }
             // } else {
             //     slot.confirmed = true;
@@ -365,6 +378,7 @@ impl<'w, 's> WidgetSystem for PlayerSelectPanel<'w, 's> {
                 slot.confirmed = false;
             } else {
                 slot.active = false;
+                slot.team = None;
             }
             // } else {
             //     slot.confirmed = false;
@@ -375,29 +389,48 @@ impl<'w, 's> WidgetSystem for PlayerSelectPanel<'w, 's> {
             //         TargetClient::All,
             //     );
             // }
+        } else if player_actions.just_pressed(PlayerAction::Shoot) && slot.active && !slot.confirmed
+        {
+            // Cycle through the teams, and back to playing alone
+            let team_count = params.core.team_colors.len();
+            slot.team = match slot.team {
+                None if team_count > 0 => Some(0),
+                Some(team) if (team as usize + 1) < team_count => Some(team + 1),
+                _ => None,
+            };
         } else if player_actions.just_pressed(PlayerAction::Move) && !slot.confirmed {
             let direction = player_actions
                 .clamped_axis_pair(PlayerAction::Move)
                 .unwrap();
 
-            let current_player_handle_idx = params
-                .core
-                .players
-                .iter()
-                .enumerate()
-                .find(|(_, handle)| handle.path == player_handle.path)
-                .map(|(i, _)| i)
-                .unwrap_or(0);
-
-            if direction.x() > 0.0 {
-                *player_handle = params
+            // Up and down change the player's color, left and right change the character
+            let color_count = params.core.player_colors.len();
+            if direction.y().abs() > direction.x().abs() {
+                if color_count > 0 {
+                    slot.color_idx = if direction.y() > 0.0 {
+                        (slot.color_idx + 1) % color_count
+                    } else {
+                        (slot.color_idx + color_count - 1) % color_count
+                    };
+                }
+            } else {
+                let current_player_handle_idx = params
                     .core
                     .players
-                    .get(current_player_handle_idx + 1)
-                    .cloned()
-                    .unwrap_or_else(|| params.core.players[0].clone());
-            } else if direction.x() <= 0.0 {
-                if current_player_handle_idx > 0 {
+                    .iter()
+                    .enumerate()
+                    .find(|(_, handle)| handle.path == player_handle.path)
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+
+                if direction.x() > 0.0 {
+                    *player_handle = params
+                        .core
+                        .players
+                        .get(current_player_handle_idx + 1)
+                        .cloned()
+                        .unwrap_or_else(|| params.core.players[0].clone());
+                } else if current_player_handle_idx > 0 {
                     *player_handle = params
                         .core
                         .players
@@ -470,8 +503,30 @@ impl<'w, 's> WidgetSystem for PlayerSelectPanel<'w, 's> {
                                     .localization
                                     .get(&format!("press-button-to-remove?button={}", player_action_map.get_text(PlayerAction::Grab))),
                             );
+
+                            if !params.core.player_colors.is_empty() && slot.team.is_none() {
+                                ui.themed_label(
+                                    normal_font,
+                                    &params.localization.get("up-down-to-change-color"),
+                                );
+                            }
+
+                            if !params.core.team_colors.is_empty() {
+                                ui.themed_label(
+                                    normal_font,
+                                    &params
+                                        .localization
+                                        .get(&format!("press-button-to-change-team?button={}", player_action_map.get_text(PlayerAction::Shoot))),
+                                );
+                            }
                         }
 
+                        let team_label = match slot.team {
+                            Some(team) => params.localization.get(&format!("player-select-team?team={}", team + 1)),
+                            None => params.localization.get("player-select-no-team"),
+                        };
+                        ui.themed_label(normal_font, &team_label);
+
                         ui.vertical_centered(|ui| {
                             ui.set_height(heading_font.size * 1.5);
 
@@ -502,7 +557,18 @@ impl<'w, 's> WidgetSystem for PlayerSelectPanel<'w, 's> {
                                 },
                             );
 
-                            player_image(ui, player_meta, &params.atlas_meta_assets, &params.player_atlas_egui_textures);
+                            // Teammates are tinted with their team's color in the match
+                            let color = match slot.team {
+                                Some(team) => params.core.team_colors.get(team as usize),
+                                None => params.core.player_colors.get(slot.color_idx),
+                            };
+                            let tint = color
+                                .map(|color| {
+                                    let [r, g, b, a] = color.0.map(|x| (x * 255.0) as u8);
+                                    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+                                })
+                                .unwrap_or(egui::Color32::WHITE);
+                            player_image(ui, player_meta, tint, &params.atlas_meta_assets, &params.player_atlas_egui_textures);
                         });
                     });
                 } else {
@@ -525,6 +591,7 @@ pub struct PlayerAtlasEguiTextures(pub HashMap<bones::AssetPath, egui::TextureId
 fn player_image(
     ui: &mut egui::Ui,
     player_meta: &PlayerMeta,
+    tint: egui::Color32,
     atlas_assets: &Assets<TextureAtlas>,
     egui_textures: &PlayerAtlasEguiTextures,
 ) {
@@ -575,7 +642,7 @@ fn player_image(
             ..default()
         };
 
-        mesh.add_rect_with_uv(rect, uv, egui::Color32::WHITE);
+        mesh.add_rect_with_uv(rect, uv, tint);
         mesh.translate(egui::vec2(0.0, y_offset));
         ui.painter().add(mesh);
 
@@ -621,7 +688,7 @@ fn player_image(
             egui::vec2(sprite_size.x, sprite_size.y),
         );
 
-        mesh.add_rect_with_uv(rect, uv, egui::Color32::WHITE);
+        mesh.add_rect_with_uv(rect, uv, tint);
         ui.painter().add(mesh);
     }
 
@@ -663,7 +730,7 @@ fn player_image(
             egui::vec2(sprite_size.x, sprite_size.y),
         );
 
-        mesh.add_rect_with_uv(rect, uv, egui::Color32::WHITE);
+        mesh.add_rect_with_uv(rect, uv, tint);
         ui.painter().add(mesh);
     }
 }
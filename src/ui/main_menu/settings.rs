@@ -1,9 +1,11 @@
+use bevy_fluent::{BundleAsset, Locale};
 use bevy_inspector_egui::egui::style::Margin;
 use leafwing_input_manager::{axislike::SingleAxis, user_input::InputKind, Actionlike};
 
 use super::*;
 
 mod controls;
+mod language;
 mod networking;
 mod sound;
 
@@ -14,6 +16,7 @@ pub enum SettingsTab {
     #[allow(unused)] // TODO: Just for now until we get sound settings setup
     Sound,
     Networking,
+    Language,
 }
 
 impl Default for SettingsTab {
@@ -33,10 +36,20 @@ pub struct SettingsMenu<'w, 's> {
     modified_settings: ResMut<'w, ModifiedSettings>,
     currently_binding_input_idx: Local<'s, Option<usize>>,
     localization: Res<'w, Localization>,
+    locale: ResMut<'w, Locale>,
+    bundles: Res<'w, Assets<BundleAsset>>,
     adjacencies: ResMut<'w, WidgetAdjacencies>,
-    storage: ResMut<'w, Storage>,
+    settings: ResMut<'w, Settings>,
     control_inputs: controls::ControlInputBindingEvents<'w, 's>,
     menu_input: Query<'w, 's, &'static mut ActionState<MenuAction>>,
+    player_input_maps: Query<
+        'w,
+        's,
+        (
+            &'static PlayerInputCollector,
+            &'static mut InputMap<PlayerAction>,
+        ),
+    >,
     #[system_param(ignore)]
     _phantom: PhantomData<(&'w (), &'s ())>,
 }
@@ -162,13 +175,22 @@ impl<'w, 's> WidgetSystem for SettingsMenu<'w, 's> {
 
                                 // Save new settings if settings button clicked
                                 if save_button.clicked() {
-                                    // Update in-memory settings
-                                    params.storage.set(
-                                        Settings::STORAGE_KEY,
-                                        params.modified_settings.0.as_ref().unwrap(),
-                                    );
-                                    // Persist to storage
-                                    params.storage.save();
+                                    let settings = params.modified_settings.0.as_ref().unwrap();
+
+                                    // Update the settings, which are persisted to storage when
+                                    // they change
+                                    *params.settings = settings.clone();
+
+                                    // Switch to the new language
+                                    *params.locale =
+                                        settings_locale(settings, &params.game.translations);
+
+                                    // Apply the new controls to the player input collectors
+                                    for (player_idx, mut input_map) in &mut params.player_input_maps
+                                    {
+                                        *input_map =
+                                            settings.player_controls.get_input_map(player_idx.0);
+                                    }
 
                                     // Go to main menu
                                     *params.menu_page = MenuPage::Home;
@@ -206,6 +228,13 @@ impl<'w, 's> WidgetSystem for SettingsMenu<'w, 's> {
                                     &tabs,
                                     &bottom_buttons,
                                 ),
+                                SettingsTab::Language => language::language_settings_ui(
+                                    &mut params,
+                                    ui,
+                                    bottom_buttons[1].clicked(),
+                                    &tabs,
+                                    &bottom_buttons,
+                                ),
                                 SettingsTab::Sound => sound::sound_settings_ui(ui, &params.game),
                             }
                         });
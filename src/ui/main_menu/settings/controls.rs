@@ -105,11 +105,38 @@ pub fn controls_settings_ui(
                 &mut controls.gamepad.slide,
             ],
         ),
+        (
+            &params.localization.get("block"),
+            [
+                &mut controls.keyboard1.block,
+                &mut controls.keyboard2.block,
+                &mut controls.gamepad.block,
+            ],
+        ),
+        (
+            &params.localization.get("taunt"),
+            [
+                &mut controls.keyboard1.taunt,
+                &mut controls.keyboard2.taunt,
+                &mut controls.gamepad.taunt,
+            ],
+        ),
+        (
+            &params.localization.get("emote"),
+            [
+                &mut controls.keyboard1.emote,
+                &mut controls.keyboard2.emote,
+                &mut controls.gamepad.emote,
+            ],
+        ),
     ];
 
     // Collect input button responses for building adjacency graph
     let mut input_buttons = Vec::new();
 
+    // The row, column, and input of a binding that was just made, if any
+    let mut new_binding = None;
+
     // Create input table
     egui_extras::TableBuilder::new(ui)
         .cell_layout(egui::Layout::centered_and_justified(
@@ -138,7 +165,7 @@ pub fn controls_settings_ui(
             let mut input_idx = 0;
 
             // Loop through the input rows
-            for (title, inputs) in &mut input_rows {
+            for (row_idx, (title, inputs)) in input_rows.iter_mut().enumerate() {
                 body.row(row_height, |mut row| {
                     // Add row label
                     row.col(|ui| {
@@ -214,9 +241,10 @@ pub fn controls_settings_ui(
                                                     // Reset the focus on the input button
                                                     button.request_focus();
 
-                                                    // Set the input for this button to the pressed
-                                                    // input
-                                                    **input = input_kind;
+                                                    // Bind the pressed input once we are done
+                                                    // rendering the table.
+                                                    new_binding =
+                                                        Some((row_idx, button_idx, input_kind));
 
                                                 // If the user cancelled the input binding
                                                 } else if get_input.is_err() {
@@ -301,6 +329,12 @@ pub fn controls_settings_ui(
             }
         }
     }
+
+    // Apply the new binding, swapping it with any other binding to the same input for that player
+    if let Some((row_idx, button_idx, input)) = new_binding {
+        let controls = &mut params.modified_settings.0.as_mut().unwrap().player_controls;
+        controls.rebind(button_idx, row_idx, input);
+    }
 }
 
 /// Format an InputKind as a user-facing string
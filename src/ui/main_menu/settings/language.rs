@@ -0,0 +1,60 @@
+use super::*;
+
+/// Render the list of languages that the game can be shown in
+pub fn language_settings_ui(
+    params: &mut SettingsMenu,
+    ui: &mut egui::Ui,
+    should_reset: bool,
+    settings_tabs: &[egui::Response],
+    bottom_buttons: &[egui::Response],
+) {
+    let settings = params.modified_settings.0.as_mut().unwrap();
+    if should_reset {
+        settings.locale = params.game.default_settings.locale.clone();
+    }
+
+    let bigger_font = &params.game.ui_theme.font_styles.bigger;
+    ui.add_space(bigger_font.size);
+
+    // The language that is in use, when the settings follow the system's language
+    let current = settings
+        .locale
+        .clone()
+        .unwrap_or_else(|| params.locale.requested.clone());
+
+    let mut buttons = Vec::new();
+    for bundle in params
+        .game
+        .translations
+        .locales
+        .iter()
+        .filter_map(|x| params.bundles.get(&x.inner))
+    {
+        let locale = &bundle.locales[0];
+        let mut name = egui::RichText::new(language_name(bundle));
+        if *locale == current {
+            name = name.underline();
+        }
+
+        let button =
+            BorderedButton::themed(&params.game.ui_theme.button_styles.normal, name).show(ui);
+        if button.clicked() {
+            settings.locale = Some(locale.clone());
+        }
+        buttons.push(button);
+    }
+
+    // Go down the list from the tabs to the bottom buttons
+    let (Some(first), Some(last)) = (buttons.first(), buttons.last()) else {
+        return;
+    };
+    for tab in settings_tabs {
+        params.adjacencies.widget(first).below(tab);
+    }
+    for pair in buttons.windows(2) {
+        params.adjacencies.widget(&pair[1]).below(&pair[0]);
+    }
+    for button in bottom_buttons {
+        params.adjacencies.widget(button).below(last);
+    }
+}
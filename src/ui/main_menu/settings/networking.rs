@@ -16,10 +16,7 @@ pub fn networking_settings_ui(
 
     ui.horizontal(|ui| {
         ui.add_space(bigger_font.size * 2.0);
-        ui.themed_label(
-            bigger_font,
-            &format!("{}:", params.localization.get("matchmaking-server")),
-        );
+        ui.themed_label(bigger_font, &params.localization.get("matchmaking-server"));
 
         if should_reset {
             settings.matchmaking_server = params.game.default_settings.matchmaking_server.clone();
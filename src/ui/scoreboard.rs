@@ -0,0 +1,172 @@
+//! The scoreboard shown at the end of a match.
+
+use bevy_egui::*;
+use bevy_fluent::Localization;
+
+use crate::{
+    localization::LocalizationExt, metadata::GameMeta, prelude::*, widgets::EguiResponseExt,
+    EngineState,
+};
+
+use super::widgets::{bordered_button::BorderedButton, bordered_frame::BorderedFrame, EguiUiExt};
+
+pub struct ScoreboardPlugin;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            scoreboard
+                .run_in_state(EngineState::InGame)
+                .run_in_state(InGameState::Playing)
+                .run_if_resource_exists::<Sessions>(),
+        );
+    }
+}
+
+/// Render the stats of every player once the match in the main session is over, with buttons to
+/// play a rematch or go back to the main menu.
+fn scoreboard(
+    mut commands: Commands,
+    mut egui_context: ResMut<EguiContext>,
+    game: Res<GameMeta>,
+    localization: Res<Localization>,
+    mut session_manager: SessionManager,
+) {
+    let Some(session) = session_manager.session() else {
+        return;
+    };
+    let Some(result) = session.match_result() else {
+        return;
+    };
+    // Wait for the final kill's slow motion to finish, and the session to freeze
+    let frozen = session_manager
+        .sessions
+        .as_ref()
+        .map_or(false, |x| x.is_paused(SessionId::MAIN));
+    if !frozen {
+        return;
+    }
+    let sudden_death = session.is_sudden_death();
+    let stats = session.player_stats();
+    let standings = session
+        .world
+        .resource::<jumpy_core::stats::SessionStats>()
+        .borrow()
+        .standings(result, (0..MAX_PLAYERS).filter(|&i| stats[i].is_some()));
+    let ui_theme = &game.ui_theme;
+
+    egui::CentralPanel::default()
+        .frame(egui::Frame::none())
+        .show(egui_context.ctx_mut(), |ui| {
+            let screen_rect = ui.max_rect();
+
+            let width = game.main_menu.menu_width * 1.5;
+            let x_margin = (screen_rect.width() - width) / 2.0;
+            let outer_margin =
+                egui::style::Margin::symmetric(x_margin, screen_rect.height() * 0.15);
+
+            BorderedFrame::new(&ui_theme.panel.border)
+                .margin(outer_margin)
+                .padding(ui_theme.panel.padding.into())
+                .show(ui, |ui| {
+                    ui.set_min_width(ui.available_width());
+
+                    let heading_font = ui_theme
+                        .font_styles
+                        .heading
+                        .colored(ui_theme.panel.font_color);
+                    let bigger_font = ui_theme
+                        .font_styles
+                        .bigger
+                        .colored(ui_theme.panel.font_color);
+                    let normal_font = ui_theme
+                        .font_styles
+                        .normal
+                        .colored(ui_theme.panel.font_color);
+
+                    ui.vertical_centered(|ui| {
+                        let outcome = match result.winner {
+                            Some(winner) => match session.info.player_teams[winner] {
+                                Some(team) => localization
+                                    .get(&format!("scoreboard-team-winner?team={}", team + 1)),
+                                None => localization
+                                    .get(&format!("scoreboard-winner?player={}", winner + 1)),
+                            },
+                            None => localization.get("scoreboard-draw"),
+                        };
+                        ui.themed_label(&heading_font, &outcome);
+                        if sudden_death {
+                            ui.themed_label(
+                                &bigger_font,
+                                &localization.get("scoreboard-sudden-death"),
+                            );
+                        }
+
+                        ui.add_space(10.0);
+
+                        egui::Grid::new("scoreboard")
+                            .num_columns(5)
+                            .spacing(egui::vec2(20.0, 4.0))
+                            .show(ui, |ui| {
+                                for header in [
+                                    "scoreboard-place",
+                                    "scoreboard-player",
+                                    "scoreboard-kills",
+                                    "scoreboard-deaths",
+                                    "scoreboard-damage",
+                                ] {
+                                    ui.themed_label(&bigger_font, &localization.get(header));
+                                }
+                                ui.end_row();
+
+                                for &(player_idx, place) in &standings {
+                                    let Some(stats) = stats[player_idx] else {
+                                        continue;
+                                    };
+                                    let player = localization
+                                        .get(&format!("hud-player?player={}", player_idx + 1));
+                                    ui.themed_label(&normal_font, &place.to_string());
+                                    ui.themed_label(&normal_font, &player);
+                                    ui.themed_label(&normal_font, &stats.kills.to_string());
+                                    ui.themed_label(&normal_font, &stats.deaths.to_string());
+                                    ui.themed_label(
+                                        &normal_font,
+                                        &format!("{:.0}", stats.damage_dealt),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+
+                        ui.add_space(10.0);
+
+                        let width = ui.available_width();
+
+                        let rematch_button = BorderedButton::themed(
+                            &ui_theme.button_styles.normal,
+                            &localization.get("rematch"),
+                        )
+                        .min_size(egui::vec2(width, 0.0))
+                        .show(ui)
+                        .focus_by_default(ui);
+
+                        if rematch_button.clicked() {
+                            // Restarting the session also resets everybody's stats
+                            session_manager.restart();
+                        }
+
+                        if BorderedButton::themed(
+                            &ui_theme.button_styles.normal,
+                            &localization.get("main-menu"),
+                        )
+                        .min_size(egui::vec2(width, 0.0))
+                        .show(ui)
+                        .clicked()
+                        {
+                            session_manager.stop();
+                            commands.insert_resource(NextState(EngineState::MainMenu));
+                            ui.ctx().clear_focus();
+                        }
+                    });
+                });
+        });
+}